@@ -0,0 +1,130 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Benchmarks iterating over the attributes of OTAP logs.
+//!
+//! Compares the zero-allocation `AttributeIter` over the `LogAttrs` record batch with the
+//! allocating alternative of converting the batch back to OTLP and walking the decoded
+//! `KeyValue`s (one `String` allocation per key and string value).
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use otap_df_otap::pdata::OtlpProtoBytes;
+use otap_df_otap::pdata::attributes::{AttributeIter, AttributeValue};
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::proto::opentelemetry::common::v1::any_value::Value;
+use otel_arrow_rust::proto::opentelemetry::common::v1::{AnyValue, InstrumentationScope, KeyValue};
+use otel_arrow_rust::proto::opentelemetry::logs::v1::{
+    LogRecord, LogsData, ResourceLogs, ScopeLogs, SeverityNumber,
+};
+use otel_arrow_rust::proto::opentelemetry::resource::v1::Resource;
+use prost::Message;
+
+use mimalloc_rust::GlobalMiMalloc;
+
+#[global_allocator]
+static GLOBAL: GlobalMiMalloc = GlobalMiMalloc;
+
+fn create_logs_records(num_logs: usize, num_attrs: usize) -> OtapArrowRecords {
+    let logs_data = LogsData::new(vec![
+        ResourceLogs::build(Resource::default())
+            .scope_logs(vec![
+                ScopeLogs::build(InstrumentationScope::build("library").finish())
+                    .log_records(
+                        (0..num_logs)
+                            .map(|i| {
+                                LogRecord::build(2_000_000_000u64, SeverityNumber::Info, "event")
+                                    .attributes(
+                                        (0..num_attrs)
+                                            .map(|j| {
+                                                let value = if j % 2 == 0 {
+                                                    AnyValue::new_string(format!("value{}", i % 10))
+                                                } else {
+                                                    AnyValue::new_int((i * j) as i64)
+                                                };
+                                                KeyValue::new(format!("attr{j}"), value)
+                                            })
+                                            .collect::<Vec<_>>(),
+                                    )
+                                    .finish()
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .finish(),
+            ])
+            .finish(),
+    ]);
+
+    let mut bytes = vec![];
+    logs_data
+        .encode(&mut bytes)
+        .expect("can encode proto bytes");
+    OtlpProtoBytes::ExportLogsRequest(bytes)
+        .try_into()
+        .expect("can convert to OTAP")
+}
+
+fn bench_iterate_log_attrs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate_log_attrs");
+
+    for (num_logs, num_attrs) in [(100, 8), (1000, 8), (1000, 32)] {
+        let records = create_logs_records(num_logs, num_attrs);
+        let test_id = format!("logs={num_logs},attrs={num_attrs}");
+
+        let _ = group.bench_with_input(
+            BenchmarkId::new("attribute_iter", &test_id),
+            &records,
+            |b, records| {
+                let batch = records
+                    .get(ArrowPayloadType::LogAttrs)
+                    .expect("log attrs batch");
+                b.iter(|| {
+                    let mut total = 0usize;
+                    for attr in AttributeIter::try_new(batch).expect("valid attrs batch") {
+                        total += attr.key.len();
+                        if let AttributeValue::Str(s) = attr.value {
+                            total += s.len();
+                        }
+                    }
+                    black_box(total)
+                })
+            },
+        );
+
+        let _ = group.bench_with_input(
+            BenchmarkId::new("otap->otlp->prost", &test_id),
+            &records,
+            |b, records| {
+                b.iter(|| {
+                    let otlp: OtlpProtoBytes = records.clone().try_into().expect("no error");
+                    let logs_data = LogsData::decode(otlp.as_bytes()).expect("can decode");
+                    let mut total = 0usize;
+                    for log in logs_data
+                        .resource_logs
+                        .iter()
+                        .flat_map(|rl| rl.scope_logs.iter())
+                        .flat_map(|sl| sl.log_records.iter())
+                    {
+                        for attr in &log.attributes {
+                            total += attr.key.len();
+                            if let Some(Value::StringValue(s)) =
+                                attr.value.as_ref().and_then(|v| v.value.as_ref())
+                            {
+                                total += s.len();
+                            }
+                        }
+                    }
+                    black_box(total)
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_iterate_log_attrs);
+criterion_main!(benches);
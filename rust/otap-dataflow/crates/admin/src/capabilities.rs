@@ -0,0 +1,113 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Capabilities endpoint.
+//!
+//! - GET `/capabilities` - the node plugins compiled into the running binary, and the state of
+//!   the feature gates
+
+use crate::AppState;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use otap_df_engine::feature_gate::FeatureGateState;
+use serde::Serialize;
+
+/// All the routes for capabilities.
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new().route("/capabilities", get(show_capabilities))
+}
+
+/// The node plugins compiled into the running binary, identified by their URNs, and the state of
+/// the feature gates.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Capabilities {
+    /// Available receiver plugins.
+    pub receivers: Vec<&'static str>,
+    /// Available processor plugins.
+    pub processors: Vec<&'static str>,
+    /// Available exporter plugins.
+    pub exporters: Vec<&'static str>,
+    /// Feature gates guarding the experimental components.
+    pub feature_gates: Vec<FeatureGateState>,
+}
+
+async fn show_capabilities(State(state): State<AppState>) -> Json<Capabilities> {
+    Json(state.capabilities.as_ref().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PipelineReloader, ReloadError};
+    use otap_df_config::pipeline::{PipelineConfig, PipelineConfigDiff};
+    use otap_df_config::{PipelineGroupId, PipelineId};
+    use otap_df_engine::feature_gate::FeatureGateStage;
+    use otap_df_state::store::ObservedStateStore;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    struct NoReloader;
+
+    impl PipelineReloader for NoReloader {
+        fn reload(
+            &self,
+            _pipeline_group_id: &PipelineGroupId,
+            _pipeline_id: &PipelineId,
+            _config: PipelineConfig,
+        ) -> Result<PipelineConfigDiff, ReloadError> {
+            Err(ReloadError::Rejected("read-only".into()))
+        }
+
+        fn config(
+            &self,
+            _pipeline_group_id: &PipelineGroupId,
+            _pipeline_id: &PipelineId,
+        ) -> Option<PipelineConfig> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_show_capabilities() {
+        let config =
+            PipelineConfig::from_json("group".into(), "pipeline".into(), r#"{"nodes": {}}"#)
+                .expect("valid config");
+        let store = ObservedStateStore::new(config.pipeline_settings());
+        let capabilities = Capabilities {
+            receivers: vec!["urn:test:receiver"],
+            processors: vec![],
+            exporters: vec!["urn:test:a:exporter", "urn:test:b:exporter"],
+            feature_gates: vec![FeatureGateState {
+                id: "test.gate",
+                stage: FeatureGateStage::Alpha,
+                enabled: false,
+                description: "Test gate",
+            }],
+        };
+        let state = AppState {
+            observed_state_store: store.handle(),
+            metrics_registry: MetricsRegistryHandle::new(),
+            ctrl_msg_senders: vec![],
+            capabilities: Arc::new(capabilities),
+            reloader: Arc::new(NoReloader),
+        };
+
+        let Json(response) = show_capabilities(State(state)).await;
+        assert_eq!(
+            serde_json::to_value(&response).expect("serializable"),
+            json!({
+                "receivers": ["urn:test:receiver"],
+                "processors": [],
+                "exporters": ["urn:test:a:exporter", "urn:test:b:exporter"],
+                "feature_gates": [{
+                    "id": "test.gate",
+                    "stage": "alpha",
+                    "enabled": false,
+                    "description": "Test gate"
+                }]
+            })
+        );
+    }
+}
@@ -0,0 +1,312 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Debug pages (zPages-style) for live introspection of the running pipelines.
+//! Only exposed when `debug_pages` is enabled in the admin settings.
+//!
+//! - GET `/debug/pipelines` - snapshot of the running pipelines in JSON
+//! - GET `/debug` - the same snapshot rendered as a simple HTML page
+//!
+//! The snapshot contains, for each deployed pipeline:
+//! - the topology (nodes, plugins, configurations with their secrets masked and out-port
+//!   connections),
+//! - the per-node counters (e.g. items received/sent, errors) summed across cores,
+//! - the per-node gauges, which include the occupancy of the node queues,
+//! - the health reported by the nodes,
+//! - the most recent error events observed on each core.
+//!
+//! Reading these pages never resets the metrics.
+
+use crate::AppState;
+use axum::extract::State;
+use axum::response::{Html, IntoResponse};
+use axum::routing::get;
+use axum::{Json, Router};
+use otap_df_config::node::NodeKind;
+use otap_df_engine::topology::PipelineTopology;
+use otap_df_state::PipelineKey;
+use otap_df_state::event::ObservedEvent;
+use otap_df_state::health::{NodeHealth, NodeHealthStatus};
+use otap_df_state::phase::PipelineAggPhase;
+use otap_df_telemetry::attributes::AttributeSetHandler;
+use otap_df_telemetry::descriptor::Instrument;
+use otap_df_telemetry::registry::MetricsRegistryHandle;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+
+/// Maximum number of error events reported per pipeline.
+const MAX_RECENT_ERRORS: usize = 20;
+
+/// All the routes for the debug pages.
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/debug", get(show_html))
+        .route("/debug/pipelines", get(show_json))
+}
+
+/// Snapshot of all the running pipelines.
+#[derive(Serialize)]
+struct DebugSnapshot {
+    timestamp: String,
+    pipelines: Vec<PipelineSnapshot>,
+}
+
+#[derive(Serialize)]
+struct PipelineSnapshot {
+    /// `group_id:pipeline_id`
+    pipeline: String,
+    phase: PipelineAggPhase,
+    nodes: Vec<NodeSnapshot>,
+    recent_errors: Vec<ObservedEvent>,
+}
+
+#[derive(Serialize)]
+struct NodeSnapshot {
+    id: String,
+    kind: NodeKind,
+    plugin_urn: String,
+    /// Out port name -> downstream node ids.
+    out_ports: BTreeMap<String, Vec<String>>,
+    /// Node configuration, with its secrets masked.
+    config: serde_json::Value,
+    counters: BTreeMap<String, u64>,
+    gauges: BTreeMap<String, u64>,
+    health: Vec<NodeHealthStatus>,
+}
+
+/// Metrics of a node, summed across cores.
+#[derive(Default)]
+struct NodeMetrics {
+    counters: BTreeMap<String, u64>,
+    gauges: BTreeMap<String, u64>,
+}
+
+async fn show_json(State(state): State<AppState>) -> impl IntoResponse {
+    Json(snapshot(&state))
+}
+
+async fn show_html(State(state): State<AppState>) -> impl IntoResponse {
+    Html(render_html(&snapshot(&state)))
+}
+
+fn snapshot(state: &AppState) -> DebugSnapshot {
+    let mut metrics = collect_node_metrics(&state.metrics_registry);
+    let health = state.observed_state_store.health_report();
+    let mut statuses: Vec<(PipelineKey, _)> =
+        state.observed_state_store.snapshot().into_iter().collect();
+    statuses.sort_by_key(|(key, _)| key.as_string());
+
+    let pipelines = statuses
+        .into_iter()
+        .map(|(key, status)| {
+            let pipeline = key.as_string();
+            let nodes = state
+                .reloader
+                .config(key.pipeline_group_id(), key.pipeline_id())
+                .map(|config| {
+                    let topology = PipelineTopology::from_config(&config);
+                    topology
+                        .nodes
+                        .iter()
+                        .map(|node| {
+                            let node_metrics = metrics
+                                .remove(&(key.pipeline_id().to_string(), node.id.to_string()))
+                                .unwrap_or_default();
+                            NodeSnapshot {
+                                id: node.id.to_string(),
+                                kind: node.kind,
+                                plugin_urn: node.plugin_urn.to_string(),
+                                out_ports: topology
+                                    .out_edges(&node.id)
+                                    .map(|edge| {
+                                        let destinations =
+                                            edge.destinations.iter().map(|d| d.to_string());
+                                        (edge.port.to_string(), destinations.collect())
+                                    })
+                                    .collect(),
+                                config: node.config.clone(),
+                                counters: node_metrics.counters,
+                                gauges: node_metrics.gauges,
+                                health: health
+                                    .nodes
+                                    .iter()
+                                    .filter(|h| {
+                                        h.pipeline == pipeline && h.node_id.as_str() == node.id
+                                    })
+                                    .cloned()
+                                    .collect(),
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let mut recent_errors: Vec<ObservedEvent> = status
+                .per_core()
+                .values()
+                .flat_map(|core| core.recent_events().filter(|e| e.is_error()).cloned())
+                .collect();
+            recent_errors.sort_by_key(|e| std::cmp::Reverse(e.time()));
+            recent_errors.truncate(MAX_RECENT_ERRORS);
+
+            PipelineSnapshot {
+                pipeline,
+                phase: status.phase().clone(),
+                nodes,
+                recent_errors,
+            }
+        })
+        .collect();
+
+    DebugSnapshot {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        pipelines,
+    }
+}
+
+/// Collects the current metrics (without resetting them), keyed by (pipeline id, node id).
+fn collect_node_metrics(
+    registry: &MetricsRegistryHandle,
+) -> HashMap<(String, String), NodeMetrics> {
+    let mut nodes: HashMap<(String, String), NodeMetrics> = HashMap::new();
+    registry.visit_current_metrics(|descriptor, attributes, metrics_iter| {
+        let (pipeline_id, node_id) = node_key(attributes);
+        let (Some(pipeline_id), Some(node_id)) = (pipeline_id, node_id) else {
+            return;
+        };
+        let node = nodes.entry((pipeline_id, node_id)).or_default();
+        for (field, value) in metrics_iter {
+            let values = match field.instrument {
+                Instrument::Counter => &mut node.counters,
+                Instrument::UpDownCounter | Instrument::Gauge => &mut node.gauges,
+                Instrument::Histogram => continue,
+            };
+            let entry = values
+                .entry(format!("{}.{}", descriptor.name, field.name))
+                .or_insert(0);
+            *entry = entry.saturating_add(value);
+        }
+    });
+    nodes
+}
+
+fn node_key(attributes: &dyn AttributeSetHandler) -> (Option<String>, Option<String>) {
+    let mut pipeline_id = None;
+    let mut node_id = None;
+    for (key, value) in attributes.iter_attributes() {
+        match key {
+            "pipeline.id" => pipeline_id = Some(value.to_string_value()),
+            "node.id" => node_id = Some(value.to_string_value()),
+            _ => {}
+        }
+    }
+    (pipeline_id, node_id)
+}
+
+fn render_html(snapshot: &DebugSnapshot) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html><html><head><title>Pipelines</title><style>\
+         body{{font-family:sans-serif}}table{{border-collapse:collapse;margin-bottom:1em}}\
+         td,th{{border:1px solid #ccc;padding:2px 6px;text-align:left;vertical-align:top}}\
+         </style></head><body><h1>Pipelines</h1><p>{}</p>",
+        escape(&snapshot.timestamp)
+    );
+    for pipeline in &snapshot.pipelines {
+        let _ = write!(
+            out,
+            "<h2>{} <small>{}</small></h2>\
+             <table><tr><th>Node</th><th>Kind</th><th>Plugin</th><th>Out ports</th>\
+             <th>Counters</th><th>Gauges</th><th>Health</th></tr>",
+            escape(&pipeline.pipeline),
+            escape(&format!("{:?}", pipeline.phase))
+        );
+        for node in &pipeline.nodes {
+            let out_ports = node
+                .out_ports
+                .iter()
+                .map(|(port, destinations)| format!("{port} -> {}", destinations.join(", ")));
+            let health = node.health.iter().map(|h| match &h.health {
+                NodeHealth::Healthy => "healthy".to_owned(),
+                NodeHealth::Degraded(reason) => format!("degraded (core {}): {reason}", h.core_id),
+                NodeHealth::Unhealthy(reason) => {
+                    format!("unhealthy (core {}): {reason}", h.core_id)
+                }
+            });
+            let _ = write!(
+                out,
+                "<tr><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                 <td>{}</td></tr>",
+                escape(&node.id),
+                node.kind,
+                escape(&node.plugin_urn),
+                lines(out_ports),
+                lines(node.counters.iter().map(|(k, v)| format!("{k} = {v}"))),
+                lines(node.gauges.iter().map(|(k, v)| format!("{k} = {v}"))),
+                lines(health),
+            );
+        }
+        out.push_str("</table>");
+        if !pipeline.recent_errors.is_empty() {
+            out.push_str("<h3>Recent errors</h3><ul>");
+            for error in &pipeline.recent_errors {
+                let _ = write!(
+                    out,
+                    "<li><code>{}</code></li>",
+                    escape(&format!("{error:?}"))
+                );
+            }
+            out.push_str("</ul>");
+        }
+    }
+    out.push_str("</body></html>");
+    out
+}
+
+/// Escapes each line and joins them with line breaks.
+fn lines<I: Iterator<Item = String>>(items: I) -> String {
+    items
+        .map(|item| escape(&item))
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_html_escapes_values() {
+        let snapshot = DebugSnapshot {
+            timestamp: "now".into(),
+            pipelines: vec![PipelineSnapshot {
+                pipeline: "group:pipeline".into(),
+                phase: PipelineAggPhase::Unknown,
+                nodes: vec![NodeSnapshot {
+                    id: "<exporter>".into(),
+                    kind: NodeKind::Exporter,
+                    plugin_urn: "urn:otel:noop:exporter".into(),
+                    out_ports: BTreeMap::new(),
+                    config: serde_json::Value::Null,
+                    counters: BTreeMap::from([("exporter.sent".to_owned(), 42)]),
+                    gauges: BTreeMap::new(),
+                    health: Vec::new(),
+                }],
+                recent_errors: Vec::new(),
+            }],
+        };
+        let html = render_html(&snapshot);
+        assert!(html.contains("&lt;exporter&gt;"));
+        assert!(!html.contains("<exporter>"));
+        assert!(html.contains("exporter.sent = 42"));
+    }
+}
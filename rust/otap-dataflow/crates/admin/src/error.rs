@@ -34,4 +34,13 @@ pub enum Error {
         /// Human-readable details of the server failure.
         details: String,
     },
+
+    /// The gRPC control-plane server encountered a fatal error while serving.
+    #[error("Admin gRPC server error on '{addr}': {details}")]
+    GrpcServerError {
+        /// The address the server was bound to.
+        addr: String,
+        /// Human-readable details of the server failure.
+        details: String,
+    },
 }
@@ -0,0 +1,352 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! gRPC control plane, used by orchestration tooling to manage the pipelines of an agent
+//! programmatically (see `proto/otap_df/admin/v1/control_service.proto`).
+//!
+//! - `StopPipeline` - gracefully stop a pipeline on all its cores
+//! - `GetConfig` - dump the configuration currently applied to a pipeline (JSON)
+//! - `GetMetrics` - snapshot of the current metrics, optionally resetting them
+//! - `StartPipeline`, `PausePipeline`, `ResumePipeline`, `FlushPipeline` - not supported by the
+//!   engine yet, these return `UNIMPLEMENTED` for deployed pipelines
+//!
+//! All the requests targeting a pipeline return `NOT_FOUND` if the pipeline is not deployed.
+
+use crate::AppState;
+use crate::error::Error;
+use crate::proto::otap_df::admin::v1::control_service_server::{
+    ControlService, ControlServiceServer,
+};
+use crate::proto::otap_df::admin::v1::{
+    ControlResponse, GetConfigResponse, GetMetricsRequest, GetMetricsResponse, Metric, MetricSet,
+    PipelineRequest, StopPipelineRequest,
+};
+use otap_df_config::pipeline::PipelineConfig;
+use otap_df_telemetry::attributes::AttributeSetHandler;
+use otap_df_telemetry::descriptor::{Instrument, MetricsDescriptor};
+use otap_df_telemetry::registry::MetricsIterator;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio_util::sync::CancellationToken;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+/// Time given to a pipeline to drain when the stop request doesn't specify a timeout.
+const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Implementation of the control-plane service on top of the admin state.
+pub(crate) struct ControlPlane {
+    state: AppState,
+}
+
+impl ControlPlane {
+    pub(crate) fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Returns the configuration of the targeted pipeline, or `NOT_FOUND` if it is not deployed.
+    fn pipeline_config(
+        &self,
+        pipeline_group_id: &str,
+        pipeline_id: &str,
+    ) -> Result<PipelineConfig, Status> {
+        self.state
+            .reloader
+            .config(
+                &pipeline_group_id.to_owned().into(),
+                &pipeline_id.to_owned().into(),
+            )
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "pipeline `{pipeline_group_id}:{pipeline_id}` is not deployed"
+                ))
+            })
+    }
+
+    /// Checks that the targeted pipeline exists before refusing an unsupported operation.
+    fn unsupported(&self, request: &PipelineRequest, operation: &str) -> Status {
+        match self.pipeline_config(&request.pipeline_group_id, &request.pipeline_id) {
+            Ok(_) => {
+                Status::unimplemented(format!("{operation} is not supported by the engine yet"))
+            }
+            Err(status) => status,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl ControlService for ControlPlane {
+    async fn start_pipeline(
+        &self,
+        request: Request<PipelineRequest>,
+    ) -> Result<Response<ControlResponse>, Status> {
+        // ToDo Restarting a stopped pipeline requires the controller to respawn its threads.
+        Err(self.unsupported(request.get_ref(), "starting a pipeline"))
+    }
+
+    async fn stop_pipeline(
+        &self,
+        request: Request<StopPipelineRequest>,
+    ) -> Result<Response<ControlResponse>, Status> {
+        let request = request.into_inner();
+        let _ = self.pipeline_config(&request.pipeline_group_id, &request.pipeline_id)?;
+        let timeout = match request.timeout_ms {
+            0 => DEFAULT_STOP_TIMEOUT,
+            ms => Duration::from_millis(ms),
+        };
+        let reason = if request.reason.is_empty() {
+            "admin requested shutdown".to_owned()
+        } else {
+            request.reason
+        };
+
+        let deadline = Instant::now() + timeout;
+        let errors: Vec<String> = self
+            .state
+            .ctrl_msg_senders
+            .iter()
+            .filter_map(|sender| sender.try_send_shutdown(deadline, reason.clone()).err())
+            .map(|e| e.to_string())
+            .collect();
+
+        if errors.is_empty() {
+            Ok(Response::new(ControlResponse {
+                accepted: true,
+                message: String::new(),
+            }))
+        } else {
+            Err(Status::internal(errors.join(", ")))
+        }
+    }
+
+    async fn pause_pipeline(
+        &self,
+        request: Request<PipelineRequest>,
+    ) -> Result<Response<ControlResponse>, Status> {
+        // ToDo Pausing requires the receivers to support a pause/resume control message.
+        Err(self.unsupported(request.get_ref(), "pausing a pipeline"))
+    }
+
+    async fn resume_pipeline(
+        &self,
+        request: Request<PipelineRequest>,
+    ) -> Result<Response<ControlResponse>, Status> {
+        Err(self.unsupported(request.get_ref(), "resuming a pipeline"))
+    }
+
+    async fn flush_pipeline(
+        &self,
+        request: Request<PipelineRequest>,
+    ) -> Result<Response<ControlResponse>, Status> {
+        // ToDo Flushing requires a dedicated node control message (processors only flush their
+        // buffered data when they shut down).
+        Err(self.unsupported(request.get_ref(), "flushing a pipeline"))
+    }
+
+    async fn get_config(
+        &self,
+        request: Request<PipelineRequest>,
+    ) -> Result<Response<GetConfigResponse>, Status> {
+        let request = request.get_ref();
+        let config = self.pipeline_config(&request.pipeline_group_id, &request.pipeline_id)?;
+        let config_json = serde_json::to_string(&config)
+            .map_err(|e| Status::internal(format!("failed to serialize the configuration: {e}")))?;
+        Ok(Response::new(GetConfigResponse { config_json }))
+    }
+
+    async fn get_metrics(
+        &self,
+        request: Request<GetMetricsRequest>,
+    ) -> Result<Response<GetMetricsResponse>, Status> {
+        let mut metric_sets = Vec::new();
+        let visitor = |descriptor: &'static MetricsDescriptor,
+                       attributes: &dyn AttributeSetHandler,
+                       metrics_iter: MetricsIterator<'_>| {
+            metric_sets.push(MetricSet {
+                name: descriptor.name.to_owned(),
+                attributes: attributes
+                    .iter_attributes()
+                    .map(|(key, value)| (key.to_owned(), value.to_string_value()))
+                    .collect(),
+                metrics: metrics_iter
+                    .map(|(field, value)| Metric {
+                        name: field.name.to_owned(),
+                        unit: field.unit.to_owned(),
+                        instrument: instrument_name(field.instrument).to_owned(),
+                        value,
+                    })
+                    .collect(),
+            });
+        };
+        if request.get_ref().reset {
+            self.state.metrics_registry.visit_metrics_and_reset(visitor);
+        } else {
+            self.state.metrics_registry.visit_current_metrics(visitor);
+        }
+
+        let time_unix_nano = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX));
+        Ok(Response::new(GetMetricsResponse {
+            time_unix_nano,
+            metric_sets,
+        }))
+    }
+}
+
+fn instrument_name(instrument: Instrument) -> &'static str {
+    match instrument {
+        Instrument::Counter => "counter",
+        Instrument::UpDownCounter => "up_down_counter",
+        Instrument::Gauge => "gauge",
+        Instrument::Histogram => "histogram",
+    }
+}
+
+/// Runs the gRPC control-plane server until shutdown is requested.
+pub(crate) async fn serve(
+    bind_address: String,
+    state: AppState,
+    cancel: CancellationToken,
+) -> Result<(), Error> {
+    let addr = bind_address
+        .parse::<SocketAddr>()
+        .map_err(|e| Error::InvalidBindAddress {
+            bind_address: bind_address.clone(),
+            details: format!("{e}"),
+        })?;
+
+    Server::builder()
+        .add_service(ControlServiceServer::new(ControlPlane::new(state)))
+        .serve_with_shutdown(addr, async move {
+            cancel.cancelled().await;
+        })
+        .await
+        .map_err(|e| Error::GrpcServerError {
+            addr: addr.to_string(),
+            details: format!("{e}"),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Capabilities, PipelineReloader, ReloadError};
+    use otap_df_config::pipeline::PipelineConfigDiff;
+    use otap_df_config::{PipelineGroupId, PipelineId};
+    use otap_df_engine::control::PipelineAdminSender;
+    use otap_df_state::store::ObservedStateStore;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    struct TestReloader(PipelineConfig);
+
+    impl PipelineReloader for TestReloader {
+        fn reload(
+            &self,
+            _pipeline_group_id: &PipelineGroupId,
+            _pipeline_id: &PipelineId,
+            _config: PipelineConfig,
+        ) -> Result<PipelineConfigDiff, ReloadError> {
+            Err(ReloadError::Rejected("read-only".into()))
+        }
+
+        fn config(
+            &self,
+            pipeline_group_id: &PipelineGroupId,
+            pipeline_id: &PipelineId,
+        ) -> Option<PipelineConfig> {
+            (pipeline_group_id == "group" && pipeline_id == "pipeline").then(|| self.0.clone())
+        }
+    }
+
+    #[derive(Default)]
+    struct TestSender {
+        shutdowns: AtomicUsize,
+        reason: Mutex<String>,
+    }
+
+    impl PipelineAdminSender for TestSender {
+        fn try_send_shutdown(
+            &self,
+            _deadline: Instant,
+            reason: String,
+        ) -> Result<(), otap_df_engine::error::Error> {
+            let _ = self.shutdowns.fetch_add(1, Ordering::Relaxed);
+            *self
+                .reason
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = reason;
+            Ok(())
+        }
+    }
+
+    fn pipeline(pipeline_id: &str) -> PipelineRequest {
+        PipelineRequest {
+            pipeline_group_id: "group".into(),
+            pipeline_id: pipeline_id.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_control_plane() {
+        let config =
+            PipelineConfig::from_json("group".into(), "pipeline".into(), r#"{"nodes": {}}"#)
+                .expect("valid config");
+        let store = ObservedStateStore::new(config.pipeline_settings());
+        let sender = Arc::new(TestSender::default());
+        let control = ControlPlane::new(AppState {
+            observed_state_store: store.handle(),
+            metrics_registry: MetricsRegistryHandle::new(),
+            ctrl_msg_senders: vec![
+                sender.clone() as Arc<dyn PipelineAdminSender>,
+                sender.clone(),
+            ],
+            capabilities: Arc::new(Capabilities::default()),
+            reloader: Arc::new(TestReloader(config)),
+        });
+
+        let response = control
+            .get_config(Request::new(pipeline("pipeline")))
+            .await
+            .expect("pipeline is deployed");
+        assert!(response.get_ref().config_json.contains("nodes"));
+        let status = control
+            .get_config(Request::new(pipeline("unknown")))
+            .await
+            .expect_err("pipeline is not deployed");
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let response = control
+            .stop_pipeline(Request::new(StopPipelineRequest {
+                pipeline_group_id: "group".into(),
+                pipeline_id: "pipeline".into(),
+                timeout_ms: 0,
+                reason: "rollout".into(),
+            }))
+            .await
+            .expect("stop accepted");
+        assert!(response.get_ref().accepted);
+        assert_eq!(sender.shutdowns.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            *sender
+                .reason
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            "rollout"
+        );
+
+        let status = control
+            .flush_pipeline(Request::new(pipeline("pipeline")))
+            .await
+            .expect_err("flush is not supported");
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+
+        let response = control
+            .get_metrics(Request::new(GetMetricsRequest { reset: false }))
+            .await
+            .expect("metrics snapshot");
+        assert!(response.get_ref().metric_sets.is_empty());
+    }
+}
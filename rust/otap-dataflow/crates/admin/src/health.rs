@@ -0,0 +1,52 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Process-level health probes.
+//!
+//! - GET `/healthz` - liveness of all the deployed pipelines
+//!   - 200 OK if every pipeline satisfies its liveness policy
+//!   - 500 Internal Server Error otherwise
+//! - GET `/readyz` - readiness of the process to receive traffic
+//!   - 200 OK if at least one pipeline is deployed, every pipeline satisfies its readiness policy
+//!     and no node reports itself as unhealthy
+//!   - 503 Service Unavailable otherwise
+//!
+//! Both endpoints return the aggregated `HealthReport` as JSON, listing the pipelines and the
+//! nodes that are not healthy.
+
+use crate::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+
+/// All the routes for the process health probes.
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+/// Used by the kubelet livenessProbe to decide whether to restart the container.
+async fn healthz(State(state): State<AppState>) -> impl IntoResponse {
+    let report = state.observed_state_store.health_report();
+    let code = if report.live {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    (code, Json(report))
+}
+
+/// Used by the kubelet readinessProbe (and by Services) to decide whether the Pod should receive
+/// traffic.
+async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    let report = state.observed_state_store.health_report();
+    let code = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(report))
+}
@@ -1,11 +1,17 @@
 // Copyright The OpenTelemetry Authors
 // SPDX-License-Identifier: Apache-2.0
 
-//! HTTP server for exposing admin endpoints.
+//! HTTP server for exposing admin endpoints, and optional gRPC control-plane server.
 
+mod capabilities;
+mod debug;
 pub mod error;
+mod grpc;
+mod health;
 mod pipeline;
 mod pipeline_group;
+mod prometheus;
+pub mod proto;
 mod telemetry;
 
 use axum::Router;
@@ -15,7 +21,9 @@ use tokio::net::TcpListener;
 use tokio_util::sync::CancellationToken;
 use tower::ServiceBuilder;
 
+pub use crate::capabilities::Capabilities;
 use crate::error::Error;
+pub use crate::pipeline::{PipelineReloader, ReloadError};
 use otap_df_config::engine::HttpAdminSettings;
 use otap_df_engine::control::PipelineAdminSender;
 use otap_df_state::store::ObservedStateHandle;
@@ -32,28 +40,46 @@ struct AppState {
 
     /// The control message senders for controlling pipelines.
     ctrl_msg_senders: Vec<Arc<dyn PipelineAdminSender>>,
+
+    /// The node plugins compiled into the running binary.
+    capabilities: Arc<Capabilities>,
+
+    /// Applies configuration changes to the running pipelines.
+    reloader: Arc<dyn PipelineReloader>,
 }
 
-/// Run the admin HTTP server until shutdown is requested.
+/// Run the admin HTTP server, and the gRPC control-plane server if a gRPC bind address is
+/// configured, until shutdown is requested.
 pub async fn run(
     config: HttpAdminSettings,
     observed_store: ObservedStateHandle,
     ctrl_msg_senders: Vec<Arc<dyn PipelineAdminSender>>,
     metrics_registry: MetricsRegistryHandle,
+    capabilities: Capabilities,
+    reloader: Arc<dyn PipelineReloader>,
     cancel: CancellationToken,
 ) -> Result<(), Error> {
     let app_state = AppState {
         observed_state_store: observed_store,
         metrics_registry,
         ctrl_msg_senders,
+        capabilities: Arc::new(capabilities),
+        reloader,
     };
 
-    let app = Router::new()
+    let mut app = Router::new()
+        .merge(health::routes())
         .merge(telemetry::routes())
+        .merge(prometheus::routes())
         .merge(pipeline_group::routes())
         .merge(pipeline::routes())
+        .merge(capabilities::routes());
+    if config.debug_pages {
+        app = app.merge(debug::routes());
+    }
+    let app = app
         .layer(ServiceBuilder::new())
-        .with_state(app_state);
+        .with_state(app_state.clone());
 
     // Parse the configured bind address.
     let addr =
@@ -74,13 +100,25 @@ pub async fn run(
         })?;
 
     // Start serving requests, with graceful shutdown on signal.
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            cancel.cancelled().await;
-        })
-        .await
-        .map_err(|e| Error::ServerError {
-            addr: addr.to_string(),
-            details: format!("{e}"),
-        })
+    let http_cancel = cancel.clone();
+    let http_server = async move {
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async move {
+                http_cancel.cancelled().await;
+            })
+            .await
+            .map_err(|e| Error::ServerError {
+                addr: addr.to_string(),
+                details: format!("{e}"),
+            })
+    };
+
+    match config.grpc_bind_address {
+        Some(grpc_bind_address) => {
+            let grpc_server = grpc::serve(grpc_bind_address, app_state, cancel);
+            let _ = tokio::try_join!(http_server, grpc_server)?;
+            Ok(())
+        }
+        None => http_server.await,
+    }
 }
@@ -8,6 +8,16 @@
 //!   Get the configuration of the specified pipeline.
 //! - GET `/pipeline-groups/{pipeline_group_id}/pipelines/{pipeline_id}/status`
 //!   Get the status of the specified pipeline.
+//! - GET `/pipeline-groups/{pipeline_group_id}/pipelines/{pipeline_id}/topology`
+//!   Get the graph of the specified pipeline: its nodes, plugin URNs, configurations with their
+//!   secrets masked, and edges
+//!   - 404 Not Found if the pipeline does not exist
+//! - PUT `/pipeline-groups/{pipeline_group_id}/pipelines/{pipeline_id}/config`
+//!   Replace the configuration of the specified pipeline (JSON, or YAML with a YAML content type)
+//!   - 202 Accepted if the new configuration is being applied
+//!   - 200 OK if the new configuration is identical to the running one
+//!   - 400 Bad Request if the configuration is invalid or was rejected
+//!   - 404 Not Found if the pipeline does not exist
 //! - POST `/pipeline-groups/{pipeline_group_id}/pipelines/{pipeline_id}/shutdown`
 //!   Shutdown a specific pipeline
 //!   - 202 Accepted if the stop request was accepted and is being processed (async operation)
@@ -18,12 +28,53 @@
 
 use crate::AppState;
 use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode, header};
 use axum::response::IntoResponse;
-use axum::routing::get;
+use axum::routing::{get, put};
 use axum::{Json, Router};
+use otap_df_config::pipeline::{PipelineConfig, PipelineConfigDiff};
+use otap_df_config::{PipelineGroupId, PipelineId};
+use otap_df_engine::topology::PipelineTopology;
 use otap_df_state::PipelineKey;
 use otap_df_state::pipeline_status::PipelineStatus;
+use serde::Serialize;
+
+/// Applies a new configuration to a running pipeline.
+pub trait PipelineReloader: Send + Sync {
+    /// Replaces the configuration of the given pipeline, returning the changes being applied.
+    fn reload(
+        &self,
+        pipeline_group_id: &PipelineGroupId,
+        pipeline_id: &PipelineId,
+        config: PipelineConfig,
+    ) -> Result<PipelineConfigDiff, ReloadError>;
+
+    /// Returns the configuration currently applied to the given pipeline, if it is deployed.
+    fn config(
+        &self,
+        pipeline_group_id: &PipelineGroupId,
+        pipeline_id: &PipelineId,
+    ) -> Option<PipelineConfig>;
+}
+
+/// Reasons why a configuration reload was not applied.
+#[derive(Debug)]
+pub enum ReloadError {
+    /// No pipeline with this ID is deployed.
+    UnknownPipeline,
+    /// The configuration was refused, e.g. because it references unknown plugins.
+    Rejected(String),
+}
+
+/// Response body of a configuration reload.
+#[derive(Serialize)]
+struct ReloadResponse {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changes: Option<PipelineConfigDiff>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
 
 /// All the routes for pipelines.
 pub(crate) fn routes() -> Router<AppState> {
@@ -33,6 +84,16 @@ pub(crate) fn routes() -> Router<AppState> {
             "/pipeline-groups/{pipeline_group_id}/pipelines/{pipeline_id}/status",
             get(show_status),
         )
+        // Returns the graph of a specific pipeline.
+        .route(
+            "/pipeline-groups/{pipeline_group_id}/pipelines/{pipeline_id}/topology",
+            get(show_topology),
+        )
+        // Replaces the configuration of a specific pipeline.
+        .route(
+            "/pipeline-groups/{pipeline_group_id}/pipelines/{pipeline_id}/config",
+            put(reload_config),
+        )
         // liveness and readiness probes.
         .route(
             "/pipeline-groups/{pipeline_group_id}/pipelines/{pipeline_id}/livez",
@@ -53,6 +114,69 @@ pub async fn show_status(
     Ok(Json(pipeline_status))
 }
 
+async fn show_topology(
+    Path((pipeline_group_id, pipeline_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<Json<PipelineTopology>, StatusCode> {
+    state
+        .reloader
+        .config(&pipeline_group_id.into(), &pipeline_id.into())
+        .map(|config| Json(PipelineTopology::from_config(&config)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn reload_config(
+    Path((pipeline_group_id, pipeline_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> impl IntoResponse {
+    let reply = |code: StatusCode, status, changes, error| {
+        (
+            code,
+            Json(ReloadResponse {
+                status,
+                changes,
+                error,
+            }),
+        )
+    };
+    let pipeline_group_id: PipelineGroupId = pipeline_group_id.into();
+    let pipeline_id: PipelineId = pipeline_id.into();
+    let is_yaml = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("yaml"));
+    let parsed = if is_yaml {
+        PipelineConfig::from_yaml(pipeline_group_id.clone(), pipeline_id.clone(), &body)
+    } else {
+        PipelineConfig::from_json(pipeline_group_id.clone(), pipeline_id.clone(), &body)
+    };
+    let config = match parsed {
+        Ok(config) => config,
+        Err(e) => {
+            return reply(
+                StatusCode::BAD_REQUEST,
+                "invalid",
+                None,
+                Some(e.to_string()),
+            );
+        }
+    };
+
+    match state
+        .reloader
+        .reload(&pipeline_group_id, &pipeline_id, config)
+    {
+        Ok(changes) if changes.is_empty() => reply(StatusCode::OK, "unchanged", None, None),
+        Ok(changes) => reply(StatusCode::ACCEPTED, "accepted", Some(changes), None),
+        Err(ReloadError::UnknownPipeline) => reply(StatusCode::NOT_FOUND, "not_found", None, None),
+        Err(ReloadError::Rejected(error)) => {
+            reply(StatusCode::BAD_REQUEST, "rejected", None, Some(error))
+        }
+    }
+}
+
 /// Used by the kubelet livenessProbe to decide whether to restart the container.
 ///
 /// Typical use cases:
@@ -0,0 +1,372 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Prometheus scrape endpoint for the internal telemetry.
+//!
+//! - GET `/metrics` - current metrics in the Prometheus text exposition format (0.0.4)
+//!
+//! Unlike `/telemetry/metrics?format=prometheus`, this endpoint is meant to be scraped
+//! periodically by existing monitoring stacks:
+//! - reading the metrics never resets them, so counters are cumulative,
+//! - the samples of a metric family are grouped under a single `HELP`/`TYPE` header,
+//! - metric names are namespaced by their metric set, e.g. the `consumed` counter of the
+//!   `batch.processor.metrics` set is exposed as `otap_batch_processor_consumed_total`,
+//! - the metric set attributes become labels (e.g. `node.id` -> `node_id`),
+//! - histograms are exposed as cumulative `_bucket` series with their `_sum` and `_count`,
+//! - no timestamps are attached, the scrape time is used by Prometheus.
+
+use crate::AppState;
+use crate::telemetry::{
+    escape_prom_help, escape_prom_label_value, sanitize_prom_label_key, sanitize_prom_metric_name,
+};
+use axum::Router;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use otap_df_telemetry::attributes::AttributeSetHandler;
+use otap_df_telemetry::descriptor::{Instrument, MetricsDescriptor, MetricsField};
+use otap_df_telemetry::registry::{MetricValue, MetricsIterator, MetricsRegistryHandle};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// Prefix of all the exposed metric names.
+const NAMESPACE: &str = "otap";
+
+/// All the routes for the Prometheus scrape endpoint.
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new().route("/metrics", get(scrape))
+}
+
+async fn scrape(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        Exposition::collect(&state.metrics_registry).render(),
+    )
+}
+
+/// Samples of a metric family, keyed by their rendered labels.
+struct Family {
+    help: &'static str,
+    prom_type: &'static str,
+    samples: BTreeMap<String, Sample>,
+}
+
+/// Value of a sample, summed over the metric sets registered with identical attributes.
+enum Sample {
+    Value(u64),
+    Histogram {
+        count: u64,
+        sum: u64,
+        /// Upper bound (`None` for +Inf) and count of each bucket, not cumulative.
+        buckets: Vec<(Option<u64>, u64)>,
+    },
+}
+
+impl Sample {
+    fn add(&mut self, value: MetricValue<'_>) {
+        match (self, value) {
+            (Self::Value(sample), MetricValue::Value(v)) => *sample = sample.saturating_add(v),
+            (
+                Self::Histogram {
+                    count,
+                    sum,
+                    buckets,
+                },
+                MetricValue::Histogram(h),
+            ) => {
+                *count = count.saturating_add(h.count());
+                *sum = sum.saturating_add(h.sum());
+                for ((_, total), (_, n)) in buckets.iter_mut().zip(h.buckets()) {
+                    *total = total.saturating_add(n);
+                }
+            }
+            // a metric name always has the same instrument
+            _ => {}
+        }
+    }
+}
+
+impl From<MetricValue<'_>> for Sample {
+    fn from(value: MetricValue<'_>) -> Self {
+        match value {
+            MetricValue::Value(v) => Self::Value(v),
+            MetricValue::Histogram(h) => Self::Histogram {
+                count: h.count(),
+                sum: h.sum(),
+                buckets: h.buckets().collect(),
+            },
+        }
+    }
+}
+
+/// Metric families sorted by name.
+#[derive(Default)]
+struct Exposition {
+    families: BTreeMap<String, Family>,
+}
+
+impl Exposition {
+    /// Collects the current metrics without resetting them.
+    fn collect(registry: &MetricsRegistryHandle) -> Self {
+        let mut exposition = Self::default();
+        registry.visit_current_metrics(|descriptor, attributes, metrics_iter| {
+            exposition.add(descriptor, attributes, metrics_iter);
+        });
+        exposition
+    }
+
+    fn add(
+        &mut self,
+        descriptor: &'static MetricsDescriptor,
+        attributes: &dyn AttributeSetHandler,
+        metrics_iter: MetricsIterator<'_>,
+    ) {
+        let labels = attributes
+            .iter_attributes()
+            .map(|(key, value)| {
+                format!(
+                    "{}=\"{}\"",
+                    sanitize_prom_label_key(key),
+                    escape_prom_label_value(&value.to_string_value())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        for (field, value) in metrics_iter.with_histograms() {
+            let family = self
+                .families
+                .entry(metric_name(descriptor.name, field))
+                .or_insert_with(|| Family {
+                    help: field.brief,
+                    prom_type: match field.instrument {
+                        Instrument::Counter => "counter",
+                        Instrument::UpDownCounter | Instrument::Gauge => "gauge",
+                        Instrument::Histogram => "histogram",
+                    },
+                    samples: BTreeMap::new(),
+                });
+            // Metric sets registered with identical attributes are exposed as a single series.
+            match family.samples.get_mut(&labels) {
+                Some(sample) => sample.add(value),
+                None => {
+                    let _ = family.samples.insert(labels.clone(), value.into());
+                }
+            }
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, family) in &self.families {
+            if !family.help.is_empty() {
+                let _ = writeln!(out, "# HELP {name} {}", escape_prom_help(family.help));
+            }
+            let _ = writeln!(out, "# TYPE {name} {}", family.prom_type);
+            for (labels, sample) in &family.samples {
+                match sample {
+                    Sample::Value(value) => write_sample(&mut out, name, "", labels, *value),
+                    Sample::Histogram {
+                        count,
+                        sum,
+                        buckets,
+                    } => {
+                        let mut cumulative = 0u64;
+                        for (bound, n) in buckets {
+                            cumulative = cumulative.saturating_add(*n);
+                            let le = bound.map_or_else(|| "+Inf".to_owned(), |b| b.to_string());
+                            let labels = if labels.is_empty() {
+                                format!("le=\"{le}\"")
+                            } else {
+                                format!("{labels},le=\"{le}\"")
+                            };
+                            write_sample(&mut out, name, "_bucket", &labels, cumulative);
+                        }
+                        write_sample(&mut out, name, "_sum", labels, *sum);
+                        write_sample(&mut out, name, "_count", labels, *count);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn write_sample(out: &mut String, name: &str, suffix: &str, labels: &str, value: u64) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{name}{suffix} {value}");
+    } else {
+        let _ = writeln!(out, "{name}{suffix}{{{labels}}} {value}");
+    }
+}
+
+/// Builds the Prometheus name of a metric: `otap_<set>_<field>[_<unit>][_total]`.
+fn metric_name(set_name: &str, field: &MetricsField) -> String {
+    let set_name = set_name.strip_suffix(".metrics").unwrap_or(set_name);
+    let mut name = sanitize_prom_metric_name(&format!("{NAMESPACE}.{set_name}.{}", field.name));
+    if let Some(unit) = unit_suffix(field.unit).filter(|unit| !name.ends_with(unit)) {
+        name.push('_');
+        name.push_str(unit);
+    }
+    if field.instrument == Instrument::Counter && !name.ends_with("_total") {
+        name.push_str("_total");
+    }
+    name
+}
+
+/// Returns the Prometheus base unit suffix of a UCUM unit. Dimensionless units and annotations
+/// (e.g. `{msg}`) have no suffix.
+fn unit_suffix(unit: &str) -> Option<&'static str> {
+    match unit {
+        "By" => Some("bytes"),
+        "s" => Some("seconds"),
+        "ms" => Some("milliseconds"),
+        "us" => Some("microseconds"),
+        "ns" => Some("nanoseconds"),
+        "%" => Some("percent"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otap_df_telemetry::attributes::AttributeValue;
+    use otap_df_telemetry::descriptor::{AttributeField, AttributeValueType, AttributesDescriptor};
+    use otap_df_telemetry::instrument::Histogram;
+    use otap_df_telemetry::metrics::{MetricSet, MetricSetHandler};
+
+    static NODE_ATTRIBUTES: AttributesDescriptor = AttributesDescriptor {
+        name: "node_attrs",
+        fields: &[AttributeField {
+            key: "node.id",
+            r#type: AttributeValueType::String,
+            brief: "Node identifier",
+        }],
+    };
+
+    static EXPORTER_METRICS: MetricsDescriptor = MetricsDescriptor {
+        name: "test.exporter.metrics",
+        metrics: &[
+            MetricsField {
+                name: "sent",
+                unit: "{msg}",
+                brief: "Messages sent",
+                instrument: Instrument::Counter,
+            },
+            MetricsField {
+                name: "queue.size",
+                unit: "By",
+                brief: "Size of the send queue",
+                instrument: Instrument::Gauge,
+            },
+        ],
+    };
+
+    #[derive(Debug)]
+    struct NodeAttributes(Vec<AttributeValue>);
+
+    impl AttributeSetHandler for NodeAttributes {
+        fn descriptor(&self) -> &'static AttributesDescriptor {
+            &NODE_ATTRIBUTES
+        }
+        fn attribute_values(&self) -> &[AttributeValue] {
+            &self.0
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct ExporterMetrics;
+
+    impl MetricSetHandler for ExporterMetrics {
+        fn descriptor(&self) -> &'static MetricsDescriptor {
+            &EXPORTER_METRICS
+        }
+        fn snapshot_values(&self) -> Vec<u64> {
+            vec![3, 1024]
+        }
+        fn clear_values(&mut self) {}
+        fn needs_flush(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_exposition() {
+        let registry = MetricsRegistryHandle::new();
+        let _exporter: MetricSet<ExporterMetrics> =
+            registry.register(NodeAttributes(vec![AttributeValue::String("otlp".into())]));
+        let _other: MetricSet<ExporterMetrics> =
+            registry.register(NodeAttributes(vec![AttributeValue::String("arrow".into())]));
+
+        let text = Exposition::collect(&registry).render();
+        assert_eq!(
+            text,
+            "# HELP otap_test_exporter_queue_size_bytes Size of the send queue\n\
+             # TYPE otap_test_exporter_queue_size_bytes gauge\n\
+             otap_test_exporter_queue_size_bytes{node_id=\"arrow\"} 1024\n\
+             otap_test_exporter_queue_size_bytes{node_id=\"otlp\"} 1024\n\
+             # HELP otap_test_exporter_sent_total Messages sent\n\
+             # TYPE otap_test_exporter_sent_total counter\n\
+             otap_test_exporter_sent_total{node_id=\"arrow\"} 3\n\
+             otap_test_exporter_sent_total{node_id=\"otlp\"} 3\n"
+        );
+
+        // Scraping doesn't reset the counters.
+        assert_eq!(Exposition::collect(&registry).render(), text);
+    }
+
+    static LATENCY_METRICS: MetricsDescriptor = MetricsDescriptor {
+        name: "test.latency.metrics",
+        metrics: &[MetricsField {
+            name: "duration",
+            unit: "us",
+            brief: "Processing duration",
+            instrument: Instrument::Histogram,
+        }],
+    };
+
+    #[derive(Debug, Default)]
+    struct LatencyMetrics;
+
+    impl MetricSetHandler for LatencyMetrics {
+        fn descriptor(&self) -> &'static MetricsDescriptor {
+            &LATENCY_METRICS
+        }
+        fn snapshot_values(&self) -> Vec<u64> {
+            let mut histogram = Histogram::<u64>::default();
+            histogram.record(1);
+            histogram.record(4);
+            let mut values = Vec::new();
+            histogram.snapshot_into(&mut values);
+            values
+        }
+        fn clear_values(&mut self) {}
+        fn needs_flush(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_histogram_exposition() {
+        let registry = MetricsRegistryHandle::new();
+        let _latency: MetricSet<LatencyMetrics> =
+            registry.register(NodeAttributes(vec![AttributeValue::String("otlp".into())]));
+
+        let text = Exposition::collect(&registry).render();
+        let name = "otap_test_latency_duration_microseconds";
+        assert!(text.contains(&format!("# TYPE {name} histogram\n")));
+        assert!(text.contains(&format!("{name}_bucket{{node_id=\"otlp\",le=\"1\"}} 1\n")));
+        assert!(text.contains(&format!("{name}_bucket{{node_id=\"otlp\",le=\"2\"}} 1\n")));
+        assert!(text.contains(&format!("{name}_bucket{{node_id=\"otlp\",le=\"5\"}} 2\n")));
+        assert!(text.contains(&format!(
+            "{name}_bucket{{node_id=\"otlp\",le=\"+Inf\"}} 2\n"
+        )));
+        assert!(text.contains(&format!("{name}_sum{{node_id=\"otlp\"}} 5\n")));
+        assert!(text.contains(&format!("{name}_count{{node_id=\"otlp\"}} 2\n")));
+    }
+}
@@ -0,0 +1,21 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generated code for the gRPC control-plane service (see `xtask compile-proto`).
+
+// Disallow some rustc and clippy lints for the generated code
+// (applied to all modules in this file).
+
+#![allow(unused_results)]
+#![allow(missing_docs)]
+#![allow(unused_qualifications)]
+#![allow(clippy::must_use_candidate)]
+
+#[path = ""]
+pub mod otap_df {
+    #[path = ""]
+    pub mod admin {
+        #[path = "otap_df.admin.v1.rs"]
+        pub mod v1;
+    }
+}
@@ -6,6 +6,8 @@
 //! - /telemetry/live-schema - current semantic conventions registry
 //! - /telemetry/metrics - current aggregated metrics in JSON, line protocol, or Prometheus text format
 //! - /telemetry/metrics/aggregate - aggregated metrics grouped by metric set name and optional attributes
+//!
+//! See also the `/metrics` endpoint, exposing the metrics to Prometheus scrapers.
 
 use crate::AppState;
 use axum::extract::{Query, State};
@@ -838,7 +840,7 @@ fn escape_lp_field_key(s: &str) -> String {
     escape_lp_tag_key(s)
 }
 
-fn sanitize_prom_metric_name(s: &str) -> String {
+pub(crate) fn sanitize_prom_metric_name(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for (i, ch) in s.chars().enumerate() {
         let ok = matches!(ch, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | ':');
@@ -860,7 +862,7 @@ fn sanitize_prom_metric_name(s: &str) -> String {
     }
 }
 
-fn sanitize_prom_label_key(s: &str) -> String {
+pub(crate) fn sanitize_prom_label_key(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for (i, ch) in s.chars().enumerate() {
         let ok = matches!(ch, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | ':');
@@ -882,7 +884,7 @@ fn sanitize_prom_label_key(s: &str) -> String {
     }
 }
 
-fn escape_prom_label_value(s: &str) -> String {
+pub(crate) fn escape_prom_label_value(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for ch in s.chars() {
         match ch {
@@ -904,7 +906,7 @@ fn escape_prom_label_value(s: &str) -> String {
     out
 }
 
-fn escape_prom_help(s: &str) -> String {
+pub(crate) fn escape_prom_help(s: &str) -> String {
     // Similar escaping to label value per Prometheus recommendations
     escape_prom_label_value(s)
 }
@@ -177,6 +177,19 @@ impl<T> Receiver<T> {
         let state = self.channel.state.borrow();
         state.buffer.is_empty()
     }
+
+    /// Returns the number of values waiting in the channel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.channel.state.borrow().buffer.len()
+    }
+
+    /// Returns the maximum number of values the channel can hold.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        let state = self.channel.state.borrow();
+        state.capacity
+    }
 }
 
 struct SendFuture<T> {
@@ -257,6 +270,7 @@ mod tests {
 
             let result = tx.send(2);
             assert!(result.is_ok());
+            assert_eq!((rx.len(), rx.capacity()), (2, 2));
             assert_eq!(rx.try_recv().unwrap(), 1);
             assert_eq!(rx.try_recv().unwrap(), 2);
 
@@ -52,12 +52,24 @@ pub struct HttpAdminSettings {
     /// The address to bind the HTTP server to (e.g., "127.0.0.1:8080").
     #[serde(default = "default_bind_address")]
     pub bind_address: String,
+
+    /// Whether to expose the `/debug` pages, used to introspect the running pipelines (topology,
+    /// per-node counters, recent errors). Disabled by default.
+    #[serde(default)]
+    pub debug_pages: bool,
+
+    /// The address to bind the gRPC control-plane server to (e.g., "127.0.0.1:8081"). The gRPC
+    /// server is not started when unset.
+    #[serde(default)]
+    pub grpc_bind_address: Option<String>,
 }
 
 impl Default for HttpAdminSettings {
     fn default() -> Self {
         Self {
             bind_address: default_bind_address(),
+            debug_pages: false,
+            grpc_bind_address: None,
         }
     }
 }
@@ -75,14 +87,41 @@ fn default_bind_address() -> String {
 }
 
 impl EngineConfig {
-    /// Creates a new `EngineConfig` with the given JSON string.
+    /// Creates a new `EngineConfig` with the given JSON string, expanding the environment
+    /// variables referenced by its string values.
     pub fn from_json(json: &str) -> Result<Self, Error> {
-        let config: EngineConfig =
-            serde_json::from_str(json).map_err(|e| Error::DeserializationError {
-                context: Default::default(),
-                format: "JSON".to_string(),
-                details: e.to_string(),
-            })?;
+        let deserialization_error = |e: serde_json::Error| Error::DeserializationError {
+            context: Default::default(),
+            format: "JSON".to_string(),
+            details: e.to_string(),
+        };
+        let mut value: serde_json::Value =
+            serde_json::from_str(json).map_err(deserialization_error)?;
+        crate::env::expand_json(&mut value).map_err(|details| Error::EnvVarSubstitutionError {
+            context: Default::default(),
+            details,
+        })?;
+        let config: EngineConfig = serde_json::from_value(value).map_err(deserialization_error)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Creates a new `EngineConfig` with the given YAML string, applying its merge keys and
+    /// expanding the environment variables referenced by its string values.
+    pub fn from_yaml(yaml: &str) -> Result<Self, Error> {
+        let deserialization_error = |e: serde_yaml::Error| Error::DeserializationError {
+            context: Default::default(),
+            format: "YAML".to_string(),
+            details: e.to_string(),
+        };
+        let mut value: serde_yaml::Value =
+            serde_yaml::from_str(yaml).map_err(deserialization_error)?;
+        value.apply_merge().map_err(deserialization_error)?;
+        crate::env::expand_yaml(&mut value).map_err(|details| Error::EnvVarSubstitutionError {
+            context: Default::default(),
+            details,
+        })?;
+        let config: EngineConfig = serde_yaml::from_value(value).map_err(deserialization_error)?;
         config.validate()?;
         Ok(config)
     }
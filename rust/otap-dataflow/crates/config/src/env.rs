@@ -0,0 +1,159 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Environment variable substitution in configuration values.
+//!
+//! The string values of a configuration may reference environment variables, which are expanded
+//! before the configuration is deserialized, so that the same file can be deployed across
+//! environments:
+//!
+//! - `${NAME}` expands to the value of `NAME`, and is an error if `NAME` is not set;
+//! - `${NAME:-default}` expands to `default` if `NAME` is not set or empty;
+//! - `$$` expands to a literal `$`, e.g. `$${NAME}` is kept as `${NAME}`.
+//!
+//! Only the values are expanded, not the keys, and an expansion is always a string: an
+//! environment variable cannot change the structure of the configuration.
+//!
+//! The secret references (`${secret:provider:key}`) are kept as is, to be resolved when the
+//! nodes are created, see [`crate::secret`]. In the values holding one, the `$` stay escaped.
+
+use crate::secret::{SECRET_REFERENCE, SECRET_SCHEME};
+
+/// Expands the environment variable references of `value`.
+pub fn expand_env_vars(value: &str) -> Result<String, String> {
+    expand_with(value, |name| std::env::var(name).ok())
+}
+
+/// Expands the references of `value`, resolving the variables with `lookup`.
+pub fn expand_with<F>(value: &str, lookup: F) -> Result<String, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let dollar = if value.contains(SECRET_REFERENCE) {
+        "$$"
+    } else {
+        "$"
+    };
+    substitute(value, dollar, |reference, expanded| {
+        if reference.starts_with(SECRET_SCHEME) {
+            expanded.push_str("${");
+            expanded.push_str(reference);
+            expanded.push('}');
+        } else {
+            expanded.push_str(&resolve(reference, &lookup)?.replace('$', dollar));
+        }
+        Ok(())
+    })
+}
+
+/// Replaces the `${...}` references of `value` with the output of `reference`, and the `$$`
+/// escapes and the other `$` with `dollar`.
+pub(crate) fn substitute<F>(value: &str, dollar: &str, mut reference: F) -> Result<String, String>
+where
+    F: FnMut(&str, &mut String) -> Result<(), String>,
+{
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(tail) = rest.strip_prefix("$$") {
+            expanded.push_str(dollar);
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("${") {
+            let end = tail
+                .find('}')
+                .ok_or_else(|| format!("unterminated reference in `{value}`"))?;
+            reference(&tail[..end], &mut expanded)?;
+            rest = &tail[end + 1..];
+        } else {
+            expanded.push_str(dollar);
+            rest = &rest[1..];
+        }
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Resolves the content of a `${...}` reference.
+fn resolve<F>(reference: &str, lookup: &F) -> Result<String, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let (name, default) = match reference.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (reference, None),
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("invalid environment variable name `{name}`"));
+    }
+    match (lookup(name), default) {
+        (Some(value), Some(default)) if value.is_empty() => Ok(default.to_owned()),
+        (Some(value), _) => Ok(value),
+        (None, Some(default)) => Ok(default.to_owned()),
+        (None, None) => Err(format!("environment variable `{name}` is not set")),
+    }
+}
+
+/// Expands the environment variable references of the string values of a JSON document.
+pub(crate) fn expand_json(value: &mut serde_json::Value) -> Result<(), String> {
+    match value {
+        serde_json::Value::String(s) => *s = expand_env_vars(s)?,
+        serde_json::Value::Array(values) => values.iter_mut().try_for_each(expand_json)?,
+        serde_json::Value::Object(fields) => fields.values_mut().try_for_each(expand_json)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Expands the environment variable references of the string values of a YAML document.
+pub(crate) fn expand_yaml(value: &mut serde_yaml::Value) -> Result<(), String> {
+    match value {
+        serde_yaml::Value::String(s) => *s = expand_env_vars(s)?,
+        serde_yaml::Value::Sequence(values) => values.iter_mut().try_for_each(expand_yaml)?,
+        serde_yaml::Value::Mapping(fields) => fields.values_mut().try_for_each(expand_yaml)?,
+        serde_yaml::Value::Tagged(tagged) => expand_yaml(&mut tagged.value)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "ACCOUNT" => Some("geneva-prod".to_owned()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_env_vars() {
+        let expand = |value| expand_with(value, lookup);
+
+        assert_eq!(expand("${ACCOUNT}").unwrap(), "geneva-prod");
+        assert_eq!(expand("acct-${ACCOUNT}-1").unwrap(), "acct-geneva-prod-1");
+        assert_eq!(expand("${MISSING:-dev}").unwrap(), "dev");
+        assert_eq!(expand("${EMPTY:-dev}").unwrap(), "dev");
+        assert_eq!(expand("${EMPTY}").unwrap(), "");
+        assert_eq!(expand("${MISSING:-}").unwrap(), "");
+        assert_eq!(
+            expand("$${ACCOUNT} costs $5").unwrap(),
+            "${ACCOUNT} costs $5"
+        );
+
+        assert!(expand("${MISSING}").unwrap_err().contains("MISSING"));
+        assert!(expand("${ACCOUNT").is_err());
+        assert!(expand("${}").is_err());
+        assert!(expand("${A-B}").is_err());
+
+        // secret references are kept, with the `$` escaped
+        assert_eq!(
+            expand("${ACCOUNT}:${secret:file:/run/pass} $5 $$").unwrap(),
+            "geneva-prod:${secret:file:/run/pass} $$5 $$"
+        );
+    }
+}
@@ -56,6 +56,28 @@ pub enum Error {
         details: String,
     },
 
+    /// An environment variable referenced by a configuration value could not be expanded.
+    #[error("Environment variable substitution error: {details}\nContext: {context}")]
+    #[diagnostic(code(data_plane::env_var_substitution_error), url(docsrs))]
+    EnvVarSubstitutionError {
+        /// The context in which the error occurred.
+        context: Context,
+        /// A description of the error that occurred.
+        details: String,
+    },
+
+    /// A secret referenced by the configuration of a node could not be resolved.
+    #[error("Secret resolution error in node `{node_id}`: {details}\nContext: {context}")]
+    #[diagnostic(code(data_plane::secret_resolution_error), url(docsrs))]
+    SecretResolutionError {
+        /// The context in which the error occurred.
+        context: Context,
+        /// The node whose configuration references the secret.
+        node_id: NodeId,
+        /// A description of the error that occurred.
+        details: String,
+    },
+
     /// A cycle was detected in the pipeline configuration.
     #[error("Cycle detected involving nodes: {nodes:?}\nContext: {context}")]
     #[diagnostic(code(data_plane::cycle_detected), url(docsrs))]
@@ -105,6 +127,32 @@ pub enum Error {
         details: Box<HyperEdgeSpecDetails>,
     },
 
+    /// A node referenced by a pipeline edit does not exist in the pipeline.
+    #[error("Unknown node id `{node_id}`\nContext: {context}")]
+    #[diagnostic(code(data_plane::unknown_node), url(docsrs))]
+    UnknownNode {
+        /// The context in which the error occurred.
+        context: Context,
+        /// The id of the missing node.
+        node_id: NodeId,
+    },
+
+    /// An edge connects nodes whose kinds cannot be connected, e.g. an edge leading to a receiver.
+    #[error(
+        "The node `{source_node}` cannot be connected to `{target_node}`: {details}\nContext: {context}"
+    )]
+    #[diagnostic(code(data_plane::incompatible_edge), url(docsrs))]
+    IncompatibleEdge {
+        /// The context in which the error occurred.
+        context: Context,
+        /// The source node of the edge.
+        source_node: NodeId,
+        /// The target node of the edge.
+        target_node: NodeId,
+        /// Why the nodes cannot be connected.
+        details: String,
+    },
+
     /// An invalid user configuration occurred.
     #[error("An invalid user configuration occurred: {error}")]
     InvalidUserConfig {
@@ -0,0 +1,223 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Composition of a pipeline configuration from several files.
+//!
+//! A pipeline configuration can be split into layers, typically a base shared by many
+//! deployments (e.g. the exporters) and an overlay per environment. The layers are merged in
+//! order, each one on top of the previous ones:
+//!
+//! - mappings are merged key by key, recursively, so that an overlay can change the endpoint of
+//!   an exporter of the base without repeating the rest of its configuration;
+//! - any other value, lists included, replaces the value of the previous layers;
+//! - a `null` value removes the key from the previous layers, e.g. `nodes: {debug: null}` drops
+//!   the `debug` node of the base.
+//!
+//! A file can list the files it is layered on in a top-level `include` field, with paths
+//! relative to the file:
+//!
+//! ```yaml
+//! include: [../base/pipeline.yaml]
+//! nodes:
+//!   exporter:
+//!     config:
+//!       endpoint: https://eu.example.com
+//! ```
+//!
+//! The included files are merged in order, then the including file on top of them. Includes can
+//! be nested but not circular. The YAML anchors, merge keys and `x-` fields are resolved within
+//! each file, and the environment variables once all the layers are merged.
+//!
+//! A watched configuration file is reloaded when it changes, not when one of the files it includes
+//! does.
+
+use crate::error::{Context, Error};
+use serde_yaml::{Mapping, Value};
+use std::path::{Path, PathBuf};
+
+/// Name of the top-level field listing the files a configuration file is layered on.
+pub const INCLUDE_FIELD: &str = "include";
+
+/// Merges `overlay` on top of `base`.
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                if value.is_null() {
+                    let _ = base.remove(&key);
+                } else if let Some(base_value) = base.get_mut(&key) {
+                    merge(base_value, value);
+                } else {
+                    let _ = base.insert(key, value);
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Loads a configuration file (`.json`, `.yaml` or `.yml`) merged on top of the files it
+/// includes.
+pub fn load<C>(path: &Path, context: &C) -> Result<Value, Error>
+where
+    C: Fn() -> Context,
+{
+    load_layer(path, context, &mut Vec::new())
+}
+
+fn load_layer<C>(path: &Path, context: &C, includers: &mut Vec<PathBuf>) -> Result<Value, Error>
+where
+    C: Fn() -> Context,
+{
+    let read_error = |details: String| Error::FileReadError {
+        context: context(),
+        details: format!("{}: {details}", path.display()),
+    };
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    let format = match extension.as_deref() {
+        Some("json") => "JSON",
+        Some("yaml") | Some("yml") => "YAML",
+        _ => {
+            return Err(Error::FileReadError {
+                context: context(),
+                details: format!(
+                    "Unsupported file extension: {}. Supported extensions are: .json, .yaml, .yml",
+                    extension.unwrap_or_else(|| "<none>".to_string())
+                ),
+            });
+        }
+    };
+    let contents = std::fs::read_to_string(path).map_err(|e| read_error(e.to_string()))?;
+    let canonical = path.canonicalize().map_err(|e| read_error(e.to_string()))?;
+    if includers.contains(&canonical) {
+        return Err(read_error("circular include".to_owned()));
+    }
+
+    let deserialization_error = |details: String| Error::DeserializationError {
+        context: context(),
+        format: format.to_owned(),
+        details: format!("{}: {details}", path.display()),
+    };
+    let mut value: Value = if format == "JSON" {
+        serde_json::from_str(&contents).map_err(|e| deserialization_error(e.to_string()))?
+    } else {
+        let mut value: Value =
+            serde_yaml::from_str(&contents).map_err(|e| deserialization_error(e.to_string()))?;
+        value
+            .apply_merge()
+            .map_err(|e| deserialization_error(e.to_string()))?;
+        if let Value::Mapping(fields) = &mut value {
+            fields.retain(|key, _| !key.as_str().is_some_and(|key| key.starts_with("x-")));
+        }
+        value
+    };
+    let includes: Vec<PathBuf> = match &mut value {
+        Value::Mapping(fields) => match fields.remove(INCLUDE_FIELD) {
+            Some(includes) => serde_yaml::from_value(includes).map_err(|e| {
+                deserialization_error(format!("invalid `{INCLUDE_FIELD}` field: {e}"))
+            })?,
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+
+    let mut merged = Value::Mapping(Mapping::new());
+    includers.push(canonical);
+    for include in includes {
+        let include = path.parent().unwrap_or(Path::new(".")).join(include);
+        merge(&mut merged, load_layer(&include, context, includers)?);
+    }
+    let _ = includers.pop();
+    merge(&mut merged, value);
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut base = yaml(
+            r#"
+            settings: {default_pdata_channel_size: 100}
+            nodes:
+              exporter:
+                kind: exporter
+                config: {endpoint: "http://base:4317", headers: [a, b], timeout: 10s}
+              debug: {kind: exporter}
+            "#,
+        );
+        merge(
+            &mut base,
+            yaml(
+                r#"
+                nodes:
+                  exporter:
+                    config: {endpoint: "http://eu:4317", headers: [c]}
+                  debug: null
+                  missing: null
+                "#,
+            ),
+        );
+        assert_eq!(
+            base,
+            yaml(
+                r#"
+                settings: {default_pdata_channel_size: 100}
+                nodes:
+                  exporter:
+                    kind: exporter
+                    config: {endpoint: "http://eu:4317", headers: [c], timeout: 10s}
+                "#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_load_with_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base");
+        std::fs::create_dir(&base).unwrap();
+        std::fs::write(
+            base.join("exporters.json"),
+            r#"{"nodes": {"geneva": {"kind": "exporter", "config": {"account": "dev"}}}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            base.join("pipeline.yaml"),
+            "include: [exporters.json]\nnodes:\n  otlp: {kind: receiver}\n",
+        )
+        .unwrap();
+        let overlay = dir.path().join("prod.yaml");
+        std::fs::write(
+            &overlay,
+            "include: [base/pipeline.yaml]\nnodes:\n  geneva: {config: {account: prod}}\n",
+        )
+        .unwrap();
+
+        let value = load(&overlay, &Context::default).unwrap();
+        assert_eq!(
+            value,
+            yaml(
+                "nodes: {geneva: {kind: exporter, config: {account: prod}}, otlp: {kind: receiver}}"
+            )
+        );
+
+        let cycle = dir.path().join("cycle.yaml");
+        std::fs::write(&cycle, "include: [cycle.yaml]\n").unwrap();
+        match load(&cycle, &Context::default) {
+            Err(Error::FileReadError { details, .. }) => {
+                assert!(details.contains("circular include"))
+            }
+            other => panic!("Expected FileReadError, got {other:?}"),
+        }
+    }
+}
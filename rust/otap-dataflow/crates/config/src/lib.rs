@@ -11,16 +11,24 @@
 //!
 //! A data plane is a collection of pipeline groups, where each group can have multiple pipelines.
 //! A pipeline is a collection of nodes interconnected in a directed acyclic graph (DAG).
+//!
+//! The string values of the configurations may reference environment variables, see [`env`], and
+//! those of the node configurations secrets, see [`secret`]. A pipeline configuration can be
+//! composed from several files, see [`layer`].
 
 use std::borrow::Cow;
 pub mod engine;
+pub mod env;
 pub mod error;
 pub mod experimental;
 pub mod health;
+pub mod layer;
 pub mod node;
 pub mod observed_state;
 pub mod pipeline;
 pub mod pipeline_group;
+pub mod schema;
+pub mod secret;
 pub mod urn;
 
 /// The id of a pipeline group.
@@ -15,6 +15,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
 /// User configuration for a node in the pipeline.
 /// Each node contains its own settings (i.e. user config) and defines how it connects to downstream
@@ -22,7 +23,7 @@ use std::collections::{HashMap, HashSet};
 /// Each out_port is a named output (e.g. "success", "error") that defines a hyper-edge:
 /// - The hyper-edge configuration determines which downstream nodes are connected,
 ///   and how messages are routed (broadcast, round-robin, ...).
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct NodeUserConfig {
     /// The kind of this node, which determines its role in the pipeline.
@@ -52,6 +53,11 @@ pub struct NodeUserConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub default_out_port: Option<PortName>,
 
+    /// Optional deadline for the Ack or Nack of the pdata sent to an exporter. Ignored for the
+    /// other node kinds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ack_deadline: Option<AckDeadlineConfig>,
+
     /// Node-specific configuration.
     ///
     /// This configuration is interpreted by the node itself and is not interpreted and validated by
@@ -65,7 +71,7 @@ pub struct NodeUserConfig {
 
 /// Describes a hyper-edge from a node output port to one or more destination nodes,
 /// and defines the dispatching strategy for this port.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct HyperEdgeConfig {
     /// List of downstream node IDs this port connects to.
@@ -78,8 +84,28 @@ pub struct HyperEdgeConfig {
     pub dispatch_strategy: DispatchStrategy,
 }
 
+/// Deadline for the Ack or Nack of the pdata sent to an exporter.
+///
+/// The engine tracks the pdata in flight in the exporter. A pdata neither acknowledged nor
+/// refused within the timeout, e.g. because its upload is stuck, is counted as timed out and no
+/// longer accounted as in flight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AckDeadlineConfig {
+    /// Time given to the exporter to acknowledge or refuse a pdata, e.g. `30s`.
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub timeout: Duration,
+
+    /// Whether to Nack the pdata timing out, so that their subscribers (e.g. a retry processor or
+    /// a receiver) can act on the failure. The Ack or Nack sent later by the exporter for these
+    /// pdata is dropped.
+    #[serde(default)]
+    pub nack: bool,
+}
+
 /// Dispatching strategies for hyper-edges.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum DispatchStrategy {
     /// Broadcast the data to all targeted nodes.
@@ -131,6 +157,7 @@ impl NodeUserConfig {
             description: None,
             out_ports: HashMap::new(),
             default_out_port: None,
+            ack_deadline: None,
             config: Value::Null,
         }
     }
@@ -143,6 +170,7 @@ impl NodeUserConfig {
             description: None,
             out_ports: HashMap::new(),
             default_out_port: None,
+            ack_deadline: None,
             config: Value::Null,
         }
     }
@@ -155,6 +183,7 @@ impl NodeUserConfig {
             description: None,
             out_ports: HashMap::new(),
             default_out_port: None,
+            ack_deadline: None,
             config: Value::Null,
         }
     }
@@ -168,6 +197,7 @@ impl NodeUserConfig {
             description: None,
             out_ports: HashMap::new(),
             default_out_port: None,
+            ack_deadline: None,
             config: user_config,
         }
     }
@@ -215,4 +245,25 @@ mod tests {
         assert!(matches!(cfg.kind, NodeKind::Receiver));
         assert!(cfg.out_ports.is_empty());
     }
+
+    #[test]
+    fn node_user_config_ack_deadline() {
+        let json = r#"{
+            "kind": "exporter",
+            "plugin_urn": "urn:example:exporter",
+            "ack_deadline": { "timeout": "30s", "nack": true }
+        }"#;
+        let cfg: NodeUserConfig = serde_json::from_str(json).unwrap();
+        let deadline = cfg.ack_deadline.unwrap();
+        assert_eq!(deadline.timeout, Duration::from_secs(30));
+        assert!(deadline.nack);
+
+        let json = r#"{
+            "kind": "exporter",
+            "plugin_urn": "urn:example:exporter",
+            "ack_deadline": { "timeout": "1m" }
+        }"#;
+        let cfg: NodeUserConfig = serde_json::from_str(json).unwrap();
+        assert!(!cfg.ack_deadline.unwrap().nack);
+    }
 }
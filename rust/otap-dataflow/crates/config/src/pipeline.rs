@@ -11,7 +11,7 @@ use crate::{Description, NodeId, PipelineGroupId, PipelineId, PortName, Urn};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -52,7 +52,7 @@ fn default_pipeline_type() -> PipelineType {
 
 /// The type of pipeline, which can be either OTLP (OpenTelemetry Protocol) or
 /// OTAP (OpenTelemetry with Apache Arrow Protocol).
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum PipelineType {
     /// OpenTelemetry Protocol (OTLP) pipeline.
@@ -62,7 +62,7 @@ pub enum PipelineType {
     Otap,
 }
 /// A configuration for a pipeline.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct PipelineSettings {
     /// The default size of the node control message channels.
@@ -86,6 +86,43 @@ pub struct PipelineSettings {
     /// Health policy.
     #[serde(default)]
     pub health_policy: HealthPolicy,
+
+    /// Optional budget of the memory held by the pdata in flight in each instance of the
+    /// pipeline. Unlimited when not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_budget: Option<MemoryBudgetConfig>,
+
+    /// Feature gates to enable (`true`) or disable (`false`), by identifier, e.g.
+    /// `exporter.geneva.traces: true`. The gates are process-wide and only the ones of the main
+    /// pipeline are applied, at startup.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub feature_gates: BTreeMap<String, bool>,
+}
+
+/// Budget of the memory held by the pdata in flight in a pipeline.
+///
+/// Every pdata sent by a receiver is charged with the in-memory size of its payload until the
+/// pdata is dropped, and is charged again with its new size each time a processor sends it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryBudgetConfig {
+    /// Maximum number of bytes held by the pdata in flight.
+    pub limit_bytes: usize,
+
+    /// What the receivers do when the budget is exceeded.
+    #[serde(default)]
+    pub on_exceeded: MemoryBudgetPolicy,
+}
+
+/// Behavior of the receivers when the memory budget of their pipeline is exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryBudgetPolicy {
+    /// Wait for the pdata in flight to release enough memory, slowing down the receivers.
+    #[default]
+    Backpressure,
+    /// Refuse the pdata, so that receivers report the failure to their clients.
+    Reject,
 }
 
 fn default_node_ctrl_msg_channel_size() -> usize {
@@ -106,42 +143,108 @@ impl Default for PipelineSettings {
             default_pdata_channel_size: default_pdata_channel_size(),
             observed_state: ObservedStateSettings::default(),
             health_policy: HealthPolicy::default(),
+            memory_budget: None,
+            feature_gates: BTreeMap::new(),
         }
     }
 }
 
+fn yaml_error(
+    pipeline_group_id: &PipelineGroupId,
+    pipeline_id: &PipelineId,
+    e: serde_yaml::Error,
+) -> Error {
+    Error::DeserializationError {
+        context: Context::new(pipeline_group_id.clone(), pipeline_id.clone()),
+        format: "YAML".to_string(),
+        details: e.to_string(),
+    }
+}
+
 impl PipelineConfig {
     /// Create a new [`PipelineConfig`] from a JSON string.
+    ///
+    /// The environment variables referenced by the string values are expanded, see
+    /// [`crate::env`], and the secrets referenced by the node configurations are checked.
     pub fn from_json(
         pipeline_group_id: PipelineGroupId,
         pipeline_id: PipelineId,
         json_str: &str,
     ) -> Result<Self, Error> {
-        let cfg: PipelineConfig =
-            serde_json::from_str(json_str).map_err(|e| Error::DeserializationError {
-                context: Context::new(pipeline_group_id.clone(), pipeline_id.clone()),
-                format: "JSON".to_string(),
-                details: e.to_string(),
-            })?;
+        let deserialization_error = |e: serde_json::Error| Error::DeserializationError {
+            context: Context::new(pipeline_group_id.clone(), pipeline_id.clone()),
+            format: "JSON".to_string(),
+            details: e.to_string(),
+        };
+        let mut value: serde_json::Value =
+            serde_json::from_str(json_str).map_err(deserialization_error)?;
+        crate::env::expand_json(&mut value).map_err(|details| Error::EnvVarSubstitutionError {
+            context: Context::new(pipeline_group_id.clone(), pipeline_id.clone()),
+            details,
+        })?;
+        let cfg: PipelineConfig = serde_json::from_value(value).map_err(deserialization_error)?;
 
         cfg.validate(&pipeline_group_id, &pipeline_id)?;
+        cfg.check_secrets(&pipeline_group_id, &pipeline_id)?;
         Ok(cfg)
     }
 
     /// Create a new [`PipelineConfig`] from a YAML string.
+    ///
+    /// Anchors, aliases and merge keys (`<<: *anchor`) can be used to share settings between
+    /// nodes. The anchored settings can be declared in top-level fields prefixed with `x-`, which
+    /// are ignored otherwise. The environment variables referenced by the string values are
+    /// expanded, see [`crate::env`], and the secrets referenced by the node configurations are
+    /// checked.
     pub fn from_yaml(
         pipeline_group_id: PipelineGroupId,
         pipeline_id: PipelineId,
         yaml_str: &str,
     ) -> Result<Self, Error> {
-        let spec: PipelineConfig =
-            serde_yaml::from_str(yaml_str).map_err(|e| Error::DeserializationError {
-                context: Context::new(pipeline_group_id.clone(), pipeline_id.clone()),
-                format: "YAML".to_string(),
-                details: e.to_string(),
-            })?;
+        let mut value: serde_yaml::Value = serde_yaml::from_str(yaml_str)
+            .map_err(|e| yaml_error(&pipeline_group_id, &pipeline_id, e))?;
+        value
+            .apply_merge()
+            .map_err(|e| yaml_error(&pipeline_group_id, &pipeline_id, e))?;
+        if let serde_yaml::Value::Mapping(fields) = &mut value {
+            fields.retain(|key, _| !key.as_str().is_some_and(|key| key.starts_with("x-")));
+        }
+        Self::from_yaml_value(pipeline_group_id, pipeline_id, value)
+    }
+
+    /// Load a [`PipelineConfig`] from layered files, see [`crate::layer`]: each file, merged on
+    /// top of the files it includes, is merged on top of the previous ones.
+    ///
+    /// The environment variables referenced by the string values of the merged configuration are
+    /// expanded, see [`crate::env`], and the secrets referenced by the node configurations are
+    /// checked.
+    pub fn from_files<P: AsRef<Path>>(
+        pipeline_group_id: PipelineGroupId,
+        pipeline_id: PipelineId,
+        paths: &[P],
+    ) -> Result<Self, Error> {
+        let context = || Context::new(pipeline_group_id.clone(), pipeline_id.clone());
+        let mut value = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+        for path in paths {
+            crate::layer::merge(&mut value, crate::layer::load(path.as_ref(), &context)?);
+        }
+        Self::from_yaml_value(pipeline_group_id, pipeline_id, value)
+    }
+
+    fn from_yaml_value(
+        pipeline_group_id: PipelineGroupId,
+        pipeline_id: PipelineId,
+        mut value: serde_yaml::Value,
+    ) -> Result<Self, Error> {
+        crate::env::expand_yaml(&mut value).map_err(|details| Error::EnvVarSubstitutionError {
+            context: Context::new(pipeline_group_id.clone(), pipeline_id.clone()),
+            details,
+        })?;
+        let spec: PipelineConfig = serde_yaml::from_value(value)
+            .map_err(|e| yaml_error(&pipeline_group_id, &pipeline_id, e))?;
 
         spec.validate(&pipeline_group_id, &pipeline_id)?;
+        spec.check_secrets(&pipeline_group_id, &pipeline_id)?;
         Ok(spec)
     }
 
@@ -176,31 +279,14 @@ impl PipelineConfig {
     /// Supports:
     /// - JSON files: `.json`
     /// - YAML files: `.yaml`, `.yml`
+    ///
+    /// The file can include other files it is layered on, see [`crate::layer`].
     pub fn from_file<P: AsRef<Path>>(
         pipeline_group_id: PipelineGroupId,
         pipeline_id: PipelineId,
         path: P,
     ) -> Result<Self, Error> {
-        let path = path.as_ref();
-        let extension = path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.to_lowercase());
-
-        match extension.as_deref() {
-            Some("json") => Self::from_json_file(pipeline_group_id, pipeline_id, path),
-            Some("yaml") | Some("yml") => {
-                Self::from_yaml_file(pipeline_group_id, pipeline_id, path)
-            }
-            _ => {
-                let context = Context::new(pipeline_group_id, pipeline_id);
-                let details = format!(
-                    "Unsupported file extension: {}. Supported extensions are: .json, .yaml, .yml",
-                    extension.unwrap_or_else(|| "<none>".to_string())
-                );
-                Err(Error::FileReadError { context, details })
-            }
-        }
+        Self::from_files(pipeline_group_id, pipeline_id, &[path])
     }
 
     /// Returns the general settings for this pipeline.
@@ -219,12 +305,177 @@ impl PipelineConfig {
         self.nodes.into_iter()
     }
 
+    /// Returns a copy of this configuration whose node-specific configurations are replaced with
+    /// the output of `f`.
+    #[must_use]
+    pub fn map_node_configs<F>(&self, mut f: F) -> PipelineConfig
+    where
+        F: FnMut(&NodeId, &NodeUserConfig) -> Value,
+    {
+        let mut config = self.clone();
+        for (node_id, node) in config.nodes.iter_mut() {
+            let node_config = f(node_id, node);
+            Arc::make_mut(node).config = node_config;
+        }
+        config
+    }
+
+    /// Computes the changes needed to go from this configuration to `new`.
+    #[must_use]
+    pub fn diff(&self, new: &PipelineConfig) -> PipelineConfigDiff {
+        let mut diff = PipelineConfigDiff {
+            settings_changed: self.r#type != new.r#type || self.settings != new.settings,
+            ..Default::default()
+        };
+        for (node_id, node) in &self.nodes {
+            match new.nodes.get(node_id) {
+                None => diff.removed.push(node_id.clone()),
+                Some(new_node) if new_node != node => diff.changed.push(node_id.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.added = new
+            .nodes
+            .keys()
+            .filter(|node_id| !self.nodes.contains_key(*node_id))
+            .cloned()
+            .collect();
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+
+    /// Returns a copy of this configuration with the given edits applied in order.
+    ///
+    /// The resulting configuration is validated as a whole, so intermediate states (e.g. a node
+    /// connected before being added) are allowed.
+    pub fn with_edits<I>(
+        &self,
+        pipeline_group_id: &PipelineGroupId,
+        pipeline_id: &PipelineId,
+        edits: I,
+    ) -> Result<PipelineConfig, Error>
+    where
+        I: IntoIterator<Item = PipelineEdit>,
+    {
+        let context = || Context::new(pipeline_group_id.clone(), pipeline_id.clone());
+        let mut config = self.clone();
+        for edit in edits {
+            match edit {
+                PipelineEdit::AddNode { node_id, node } => {
+                    if config.nodes.contains_key(&node_id) {
+                        return Err(Error::DuplicateNode {
+                            context: context(),
+                            node_id,
+                        });
+                    }
+                    let _ = config.nodes.insert(node_id, Arc::new(node));
+                }
+                PipelineEdit::RemoveNode { node_id } => {
+                    if config.nodes.remove(&node_id).is_none() {
+                        return Err(Error::UnknownNode {
+                            context: context(),
+                            node_id,
+                        });
+                    }
+                    // Drop the edges leading to the removed node
+                    for node in config.nodes.values_mut() {
+                        if node
+                            .out_ports
+                            .values()
+                            .any(|edge| edge.destinations.contains(&node_id))
+                        {
+                            let node = Arc::make_mut(node);
+                            for edge in node.out_ports.values_mut() {
+                                let _ = edge.destinations.remove(&node_id);
+                            }
+                            node.out_ports
+                                .retain(|_, edge| !edge.destinations.is_empty());
+                            if node
+                                .default_out_port
+                                .as_ref()
+                                .is_some_and(|port| !node.out_ports.contains_key(port))
+                            {
+                                node.default_out_port = None;
+                            }
+                        }
+                    }
+                }
+                PipelineEdit::Connect {
+                    source,
+                    port,
+                    destinations,
+                    dispatch_strategy,
+                } => {
+                    let Some(node) = config.nodes.get_mut(&source) else {
+                        return Err(Error::UnknownNode {
+                            context: context(),
+                            node_id: source,
+                        });
+                    };
+                    let _ = Arc::make_mut(node).out_ports.insert(
+                        port,
+                        HyperEdgeConfig {
+                            destinations,
+                            dispatch_strategy,
+                        },
+                    );
+                }
+                PipelineEdit::Disconnect { source, port } => {
+                    let Some(node) = config.nodes.get_mut(&source) else {
+                        return Err(Error::UnknownNode {
+                            context: context(),
+                            node_id: source,
+                        });
+                    };
+                    let node = Arc::make_mut(node);
+                    let _ = node.out_ports.remove(&port);
+                    if node.default_out_port.as_ref() == Some(&port) {
+                        node.default_out_port = None;
+                    }
+                }
+            }
+        }
+        config.validate(pipeline_group_id, pipeline_id)?;
+        Ok(config)
+    }
+
+    /// Checks that the secrets referenced by the node configurations can be resolved, see
+    /// [`crate::secret`]. The references are kept in the configuration.
+    pub fn check_secrets(
+        &self,
+        pipeline_group_id: &PipelineGroupId,
+        pipeline_id: &PipelineId,
+    ) -> Result<(), Error> {
+        let errors: Vec<Error> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| crate::secret::has_secrets(&node.config))
+            .filter_map(|(node_id, node)| {
+                crate::secret::resolve_secrets(&node.config)
+                    .err()
+                    .map(|details| Error::SecretResolutionError {
+                        context: Context::new(pipeline_group_id.clone(), pipeline_id.clone()),
+                        node_id: node_id.clone(),
+                        details,
+                    })
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidConfiguration { errors })
+        }
+    }
+
     /// Validate the pipeline specification.
     ///
     /// This method checks for:
     /// - Duplicate node IDs
     /// - Duplicate out-ports (same source node + port name)
     /// - Invalid hyper-edges (missing source or target nodes)
+    /// - Edges leaving an exporter or leading to a receiver
     /// - Cycles in the DAG
     pub fn validate(
         &self,
@@ -244,6 +495,21 @@ impl PipelineConfig {
                     }
                 }
 
+                // Data flows from receivers to exporters
+                for target in &edge.destinations {
+                    let details = match (node.kind, self.nodes.get(target).map(|t| t.kind)) {
+                        (NodeKind::Exporter, _) => "an exporter has no out port",
+                        (_, Some(NodeKind::Receiver)) => "a receiver has no input",
+                        _ => continue,
+                    };
+                    errors.push(Error::IncompatibleEdge {
+                        context: Context::new(pipeline_group_id.clone(), pipeline_id.clone()),
+                        source_node: node_id.clone(),
+                        target_node: target.clone(),
+                        details: details.to_owned(),
+                    });
+                }
+
                 if !missing_targets.is_empty() {
                     errors.push(Error::InvalidHyperEdgeSpec {
                         context: Context::new(pipeline_group_id.clone(), pipeline_id.clone()),
@@ -334,6 +600,79 @@ impl PipelineConfig {
     }
 }
 
+/// A change to the topology of a pipeline, see [`PipelineConfig::with_edits`].
+#[derive(Debug, Clone)]
+pub enum PipelineEdit {
+    /// Adds a node, without any connection.
+    AddNode {
+        /// The id of the new node.
+        node_id: NodeId,
+        /// The configuration of the new node.
+        node: NodeUserConfig,
+    },
+    /// Removes a node along with the edges leading to it.
+    RemoveNode {
+        /// The id of the node to remove.
+        node_id: NodeId,
+    },
+    /// Connects an out port of a node to destination nodes, replacing any existing connection of
+    /// this port.
+    Connect {
+        /// The node owning the out port.
+        source: NodeId,
+        /// The out port to connect.
+        port: PortName,
+        /// The downstream nodes.
+        destinations: HashSet<NodeId>,
+        /// How messages are dispatched to the destinations.
+        dispatch_strategy: DispatchStrategy,
+    },
+    /// Removes the connection of an out port of a node.
+    Disconnect {
+        /// The node owning the out port.
+        source: NodeId,
+        /// The out port to disconnect.
+        port: PortName,
+    },
+}
+
+/// Changes between two versions of a pipeline configuration, see [`PipelineConfig::diff`].
+///
+/// Node IDs are sorted. A node is considered changed when any part of its definition differs:
+/// plugin, user config or out ports.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PipelineConfigDiff {
+    /// Nodes only present in the new configuration.
+    pub added: Vec<NodeId>,
+    /// Nodes only present in the old configuration.
+    pub removed: Vec<NodeId>,
+    /// Nodes present in both configurations with a different definition.
+    pub changed: Vec<NodeId>,
+    /// Whether the pipeline type or settings changed.
+    pub settings_changed: bool,
+}
+
+impl PipelineConfigDiff {
+    /// Returns `true` if both configurations are equivalent.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && !self.settings_changed
+    }
+}
+
+impl std::fmt::Display for PipelineConfigDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "added: {:?}, removed: {:?}, changed: {:?}, settings changed: {}",
+            self.added, self.removed, self.changed, self.settings_changed
+        )
+    }
+}
+
 /// A builder for constructing a [`PipelineConfig`].
 pub struct PipelineConfigBuilder {
     description: Option<Description>,
@@ -390,6 +729,7 @@ impl PipelineConfigBuilder {
                     description: None,
                     out_ports: HashMap::new(),
                     default_out_port: None,
+                    ack_deadline: None,
                     config: config.unwrap_or(Value::Null),
                 },
             );
@@ -617,8 +957,10 @@ impl Default for PipelineConfigBuilder {
 mod tests {
     use crate::error::Error;
     use crate::node::DispatchStrategy;
-    use crate::pipeline::{PipelineConfigBuilder, PipelineType};
+    use crate::node::{NodeKind, NodeUserConfig};
+    use crate::pipeline::{PipelineConfigBuilder, PipelineConfigDiff, PipelineEdit, PipelineType};
     use serde_json::json;
+    use std::collections::HashMap;
 
     #[test]
     fn test_duplicate_node_errors() {
@@ -765,6 +1107,122 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_diff() {
+        let old = PipelineConfigBuilder::new()
+            .add_receiver("recv", "urn:test:receiver", Some(json!({"port": 4317})))
+            .add_processor("batch", "urn:test:processor", Some(json!({"size": 10})))
+            .add_exporter("debug", "urn:test:exporter", None)
+            .round_robin("recv", "out", ["batch"])
+            .round_robin("batch", "out", ["debug"])
+            .build(PipelineType::Otap, "pgroup", "pipeline")
+            .expect("valid pipeline");
+        assert!(old.diff(&old.clone()).is_empty());
+
+        let new = PipelineConfigBuilder::new()
+            .add_receiver("recv", "urn:test:receiver", Some(json!({"port": 4317})))
+            .add_processor("batch", "urn:test:processor", Some(json!({"size": 20})))
+            .add_exporter("otlp", "urn:test:exporter", None)
+            .round_robin("recv", "out", ["batch"])
+            .round_robin("batch", "out", ["otlp"])
+            .build(PipelineType::Otap, "pgroup", "pipeline")
+            .expect("valid pipeline");
+        assert_eq!(
+            old.diff(&new),
+            PipelineConfigDiff {
+                added: vec!["otlp".into()],
+                removed: vec!["debug".into()],
+                // "batch" has a new config and is rewired to "otlp"
+                changed: vec!["batch".into()],
+                settings_changed: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_with_edits() {
+        let config = PipelineConfigBuilder::new()
+            .add_receiver("recv", "urn:test:receiver", None)
+            .add_exporter("otlp", "urn:test:exporter", None)
+            .broadcast("recv", "out", ["otlp"])
+            .build(PipelineType::Otap, "pgroup", "pipeline")
+            .expect("valid pipeline");
+        let (group, pipeline) = ("pgroup".into(), "pipeline".into());
+
+        // Temporarily attach a debug exporter
+        let debug = NodeUserConfig {
+            kind: NodeKind::Exporter,
+            plugin_urn: "urn:test:exporter".into(),
+            description: None,
+            out_ports: HashMap::new(),
+            default_out_port: None,
+            ack_deadline: None,
+            config: json!(null),
+        };
+        let edited = config
+            .with_edits(
+                &group,
+                &pipeline,
+                [
+                    PipelineEdit::AddNode {
+                        node_id: "debug".into(),
+                        node: debug,
+                    },
+                    PipelineEdit::Connect {
+                        source: "recv".into(),
+                        port: "out".into(),
+                        destinations: ["otlp".into(), "debug".into()].into_iter().collect(),
+                        dispatch_strategy: DispatchStrategy::Broadcast,
+                    },
+                ],
+            )
+            .expect("valid edits");
+        assert_eq!(edited.nodes["recv"].out_ports["out"].destinations.len(), 2);
+
+        // Removing it drops the edge leading to it
+        let restored = edited
+            .with_edits(
+                &group,
+                &pipeline,
+                [PipelineEdit::RemoveNode {
+                    node_id: "debug".into(),
+                }],
+            )
+            .expect("valid edits");
+        assert!(config.diff(&restored).is_empty());
+
+        // Edges must flow from receivers to exporters
+        let result = config.with_edits(
+            &group,
+            &pipeline,
+            [PipelineEdit::Connect {
+                source: "otlp".into(),
+                port: "out".into(),
+                destinations: ["recv".into()].into_iter().collect(),
+                dispatch_strategy: DispatchStrategy::Broadcast,
+            }],
+        );
+        match result {
+            Err(Error::InvalidConfiguration { errors }) => {
+                assert!(
+                    errors
+                        .iter()
+                        .all(|e| matches!(e, Error::IncompatibleEdge { .. }))
+                );
+            }
+            other => panic!("expected an incompatible edge error, got {other:?}"),
+        }
+
+        let result = config.with_edits(
+            &group,
+            &pipeline,
+            [PipelineEdit::RemoveNode {
+                node_id: "unknown".into(),
+            }],
+        );
+        assert!(matches!(result, Err(Error::UnknownNode { .. })));
+    }
+
     #[test]
     fn test_valid_complex_pipeline_spec() {
         let dag = PipelineConfigBuilder::new()
@@ -964,6 +1422,102 @@ mod tests {
         assert!(config.nodes.contains_key("exporter1"));
     }
 
+    #[test]
+    fn test_memory_budget_settings() {
+        let yaml = r#"
+settings:
+  memory_budget:
+    limit_bytes: 268435456
+    on_exceeded: reject
+nodes:
+  receiver1:
+    kind: receiver
+    plugin_urn: "urn:test:receiver"
+    config: null
+    out_ports: {}
+"#;
+        let config =
+            super::PipelineConfig::from_yaml("test_group".into(), "test_pipeline".into(), yaml)
+                .unwrap();
+        let budget = config.pipeline_settings().memory_budget.as_ref().unwrap();
+        assert_eq!(budget.limit_bytes, 256 << 20);
+        assert_eq!(budget.on_exceeded, super::MemoryBudgetPolicy::Reject);
+        assert!(super::PipelineSettings::default().memory_budget.is_none());
+    }
+
+    #[test]
+    fn test_yaml_anchors_and_merge_keys() {
+        let yaml = r#"
+x-exporter: &exporter
+  kind: exporter
+  plugin_urn: "urn:test:exporter"
+  out_ports: {}
+x-geneva: &geneva
+  endpoint: "https://geneva.example.com"
+  account: "otel"
+nodes:
+  receiver1:
+    kind: receiver
+    plugin_urn: "urn:test:receiver"
+    out_ports:
+      out:
+        destinations: [logs, metrics]
+        dispatch_strategy: broadcast
+  logs:
+    <<: *exporter
+    config:
+      <<: *geneva
+      namespace: "logs"
+  metrics: *exporter
+"#;
+        let config =
+            super::PipelineConfig::from_yaml("test_group".into(), "test_pipeline".into(), yaml)
+                .unwrap();
+        assert_eq!(config.nodes.len(), 3);
+        assert_eq!(config.nodes["metrics"].kind, NodeKind::Exporter);
+        assert_eq!(
+            config.nodes["logs"].config,
+            json!({
+                "endpoint": "https://geneva.example.com",
+                "account": "otel",
+                "namespace": "logs",
+            })
+        );
+    }
+
+    #[test]
+    fn test_env_var_substitution() {
+        let yaml = r#"
+nodes:
+  exporter1:
+    kind: exporter
+    plugin_urn: "urn:test:exporter"
+    config:
+      account: "${OTAP_DF_TEST_UNSET_ACCOUNT:-geneva-dev}"
+      pattern: "^$${literal}$"
+    out_ports: {}
+"#;
+        let config =
+            super::PipelineConfig::from_yaml("test_group".into(), "test_pipeline".into(), yaml)
+                .unwrap();
+        assert_eq!(
+            config.nodes["exporter1"].config,
+            json!({"account": "geneva-dev", "pattern": "^${literal}$"})
+        );
+
+        let json = r#"{"nodes": {"receiver1": {"kind": "receiver",
+            "plugin_urn": "urn:test:receiver", "config": {"cert_password": "${OTAP_DF_TEST_UNSET}"},
+            "out_ports": {}}}}"#;
+        let result =
+            super::PipelineConfig::from_json("test_group".into(), "test_pipeline".into(), json);
+        match result {
+            Err(Error::EnvVarSubstitutionError { details, .. }) => {
+                assert!(details.contains("OTAP_DF_TEST_UNSET"));
+            }
+            other => panic!("Expected EnvVarSubstitutionError, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_from_json_file_nonexistent_file() {
         let result = super::PipelineConfig::from_json_file(
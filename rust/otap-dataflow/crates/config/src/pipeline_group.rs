@@ -102,6 +102,12 @@ pub enum CoreAllocation {
         /// End core ID (inclusive).
         end: usize,
     },
+    /// Use an explicit set of CPU core IDs, e.g. the cores isolated for the collector on a
+    /// dedicated host. Every listed core must be available.
+    CoreSet {
+        /// Core IDs to use. Duplicates are ignored.
+        cores: Vec<usize>,
+    },
 }
 
 impl Default for CoreAllocation {
@@ -0,0 +1,279 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! JSON Schemas and validation of the node configurations.
+//!
+//! Every node plugin describes its configuration with a [`NodeConfigSchema`], registered with
+//! the engine next to its factory. The engine validates the configuration of every node against
+//! its schema before creating the nodes of a pipeline, and reports all the invalid nodes at once,
+//! with the path of the offending field:
+//!
+//! ```text
+//! node `exporter`: auth.mode: unknown variant `basik`, expected `basic` or `bearer`
+//! ```
+
+use crate::Urn;
+use crate::error::Error;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+
+/// Schema of the configuration of a node plugin.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeConfigSchema {
+    /// URN of the node plugin.
+    pub plugin_urn: &'static str,
+    /// Returns the JSON Schema of the configuration.
+    pub schema: fn() -> Value,
+    /// Checks a user configuration.
+    pub validate: fn(&Value) -> Result<(), UserConfigError>,
+}
+
+impl NodeConfigSchema {
+    /// Creates the schema of a node plugin configured with a `T`.
+    #[must_use]
+    pub const fn new<T: JsonSchema + DeserializeOwned>(plugin_urn: &'static str) -> Self {
+        Self {
+            plugin_urn,
+            schema: json_schema::<T>,
+            validate: validate_user_config::<T>,
+        }
+    }
+
+    /// Returns a copy of a user configuration with the defaults of its missing fields filled
+    /// in, see [`fill_defaults`].
+    #[must_use]
+    pub fn materialize(&self, config: &Value) -> Value {
+        let mut config = config.clone();
+        fill_defaults(&(self.schema)(), &mut config);
+        config
+    }
+
+    /// Checks the user configuration of the node `node_id`.
+    pub fn validate_node(&self, node_id: &str, config: &Value) -> Result<(), Error> {
+        (self.validate)(config).map_err(|e| Error::InvalidUserConfig {
+            error: format!("node `{node_id}`: {e}"),
+        })
+    }
+}
+
+/// An invalid field of a user configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserConfigError {
+    /// Path of the field, e.g. `auth.type` or `targets[1].endpoint`, empty for the root.
+    pub path: String,
+    /// Why the field is invalid.
+    pub message: String,
+}
+
+impl Display for UserConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for UserConfigError {}
+
+/// Deserializes a user configuration, reporting the path of the first invalid field.
+pub fn parse_user_config<T: DeserializeOwned>(config: &Value) -> Result<T, UserConfigError> {
+    serde_path_to_error::deserialize(config).map_err(|e| {
+        let path = e.path().to_string();
+        UserConfigError {
+            path: if path == "." { String::new() } else { path },
+            message: e.into_inner().to_string(),
+        }
+    })
+}
+
+fn validate_user_config<T: DeserializeOwned>(config: &Value) -> Result<(), UserConfigError> {
+    parse_user_config::<T>(config).map(|_| ())
+}
+
+/// Returns the JSON Schema of `T`.
+#[must_use]
+pub fn json_schema<T: JsonSchema>() -> Value {
+    schemars::schema_for!(T).to_value()
+}
+
+/// Fills in the missing fields of `config` that have a default in `schema`, recursively.
+///
+/// The defaults are the ones the schema documents, i.e. the defaults of the fields whose type
+/// is serializable. The variants of the enums are not descended into.
+pub fn fill_defaults(schema: &Value, config: &mut Value) {
+    fill(schema, schema, config);
+}
+
+fn fill(root: &Value, schema: &Value, config: &mut Value) {
+    let schema = resolve_ref(root, schema);
+    match config {
+        Value::Object(fields) => {
+            if let Some(Value::Object(properties)) = schema.get("properties") {
+                for (name, property) in properties {
+                    if !fields.contains_key(name) {
+                        match property.get("default") {
+                            Some(default) => {
+                                let _ = fields.insert(name.clone(), default.clone());
+                            }
+                            None => continue,
+                        }
+                    }
+                    if let Some(value) = fields.get_mut(name) {
+                        fill(root, property, value);
+                    }
+                }
+            }
+            // `Option<T>` is described as `anyOf: [T, null]`, and flattened fields as `allOf`
+            if let Some(Value::Array(schemas)) = schema.get("allOf") {
+                for schema in schemas {
+                    fill(root, schema, config);
+                }
+            }
+            if let Some(Value::Array(schemas)) = schema.get("anyOf") {
+                let mut objects = schemas
+                    .iter()
+                    .filter(|schema| resolve_ref(root, schema).get("properties").is_some());
+                if let (Some(schema), None) = (objects.next(), objects.next()) {
+                    fill(root, schema, config);
+                }
+            }
+        }
+        Value::Array(values) => {
+            if let Some(items) = schema.get("items") {
+                for value in values {
+                    fill(root, items, value);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Follows a `#/$defs/<name>` reference of a schema generated by [`json_schema`].
+fn resolve_ref<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+    schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix("#/$defs/"))
+        .and_then(|name| root.get("$defs")?.get(name))
+        .unwrap_or(schema)
+}
+
+/// Returns the schema registered for a node plugin, if any.
+#[must_use]
+pub fn find_schema<'a>(
+    schemas: &'a [NodeConfigSchema],
+    plugin_urn: &Urn,
+) -> Option<&'a NodeConfigSchema> {
+    schemas
+        .iter()
+        .find(|schema| schema.plugin_urn == plugin_urn.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Config {
+        endpoint: String,
+        #[serde(default)]
+        targets: Vec<Target>,
+        #[serde(default)]
+        retry: Retry,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Retry {
+        #[serde(default = "default_max_attempts")]
+        max_attempts: u32,
+        #[serde(default)]
+        jitter: bool,
+    }
+
+    impl Default for Retry {
+        fn default() -> Self {
+            Self {
+                max_attempts: default_max_attempts(),
+                jitter: false,
+            }
+        }
+    }
+
+    fn default_max_attempts() -> u32 {
+        3
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Target {
+        auth: Auth,
+    }
+
+    #[derive(Debug, Deserialize, JsonSchema)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    enum Auth {
+        Basic,
+        Bearer,
+    }
+
+    const SCHEMA: NodeConfigSchema = NodeConfigSchema::new::<Config>("urn:test:exporter");
+
+    #[test]
+    fn test_user_config_validation() {
+        assert!((SCHEMA.validate)(&json!({"endpoint": "http://localhost"})).is_ok());
+
+        let error = (SCHEMA.validate)(&json!({
+            "endpoint": "http://localhost",
+            "targets": [{"auth": {"type": "basic"}}, {"auth": {"type": "basik"}}],
+        }))
+        .unwrap_err();
+        assert_eq!(error.path, "targets[1].auth");
+        assert!(error.message.contains("unknown variant `basik`"));
+
+        let error = (SCHEMA.validate)(&json!({"endpoint": "x", "endpiont": "y"})).unwrap_err();
+        assert!(error.message.contains("unknown field `endpiont`"));
+
+        let error = (SCHEMA.validate)(&json!({})).unwrap_err();
+        assert_eq!(error.to_string(), "missing field `endpoint`");
+
+        let error = SCHEMA
+            .validate_node("exp", &json!({"endpoint": 1}))
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("node `exp`: endpoint: invalid type")
+        );
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = (SCHEMA.schema)();
+        assert_eq!(schema["required"], json!(["endpoint"]));
+        assert_eq!(schema["additionalProperties"], json!(false));
+        assert!(schema["properties"]["targets"].is_object());
+    }
+
+    #[test]
+    fn test_materialize() {
+        assert_eq!(
+            SCHEMA.materialize(&json!({"endpoint": "x", "retry": {"jitter": true}})),
+            json!({
+                "endpoint": "x",
+                "retry": {"max_attempts": 3, "jitter": true},
+            })
+        );
+    }
+}
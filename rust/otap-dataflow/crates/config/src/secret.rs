@@ -0,0 +1,279 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Secrets referenced by the node configurations.
+//!
+//! The string values of a node configuration may reference secrets as
+//! `${secret:<provider>:<key>}`, e.g. `${secret:file:/run/secrets/cert_password}`. The references
+//! are kept in the configuration, so the secrets never appear in it, and are resolved:
+//!
+//! - when the configuration is loaded, to report the unresolvable secrets early;
+//! - each time the engine creates a node, e.g. when a pipeline is rebuilt on a configuration
+//!   reload, so that rotated secrets are picked up.
+//!
+//! The built-in providers are:
+//!
+//! - `env`: the value of the environment variable named by the key;
+//! - `file`: the content of the file at the path given by the key, without its trailing newline
+//!   (e.g. a Kubernetes or Docker secret mount);
+//! - `azure_key_vault`: with the `azure-key-vault` feature, the secret `<vault>/<name>` (or
+//!   `<vault>/<name>/<version>`) of an Azure Key Vault, read with the managed identity of the host.
+//!
+//! Other providers can be registered with [`register_secret_provider`]. In a value holding a
+//! secret reference, a literal `$` is written `$$`.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// Scheme of the secret references.
+pub(crate) const SECRET_SCHEME: &str = "secret:";
+
+/// Start of a secret reference in a configuration value.
+pub(crate) const SECRET_REFERENCE: &str = "${secret:";
+
+/// A source of secrets.
+pub trait SecretProvider: Send + Sync {
+    /// Returns the secret identified by `key`.
+    fn get_secret(&self, key: &str) -> Result<String, String>;
+}
+
+/// Secret providers, by name.
+static PROVIDERS: LazyLock<RwLock<HashMap<String, Arc<dyn SecretProvider>>>> =
+    LazyLock::new(|| {
+        let mut providers: HashMap<String, Arc<dyn SecretProvider>> = HashMap::new();
+        let _ = providers.insert("env".into(), Arc::new(EnvSecretProvider));
+        let _ = providers.insert("file".into(), Arc::new(FileSecretProvider));
+        #[cfg(feature = "azure-key-vault")]
+        let _ = providers.insert(
+            "azure_key_vault".into(),
+            Arc::new(azure::AzureKeyVaultSecretProvider::default()),
+        );
+        RwLock::new(providers)
+    });
+
+/// Registers a secret provider, replacing the provider previously registered under `name`.
+pub fn register_secret_provider(name: impl Into<String>, provider: Arc<dyn SecretProvider>) {
+    let _ = PROVIDERS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(name.into(), provider);
+}
+
+/// Returns the secret referenced by `provider:key`.
+pub fn get_secret(reference: &str) -> Result<String, String> {
+    let (name, key) = reference
+        .split_once(':')
+        .ok_or_else(|| format!("invalid secret reference `{reference}`, expected provider:key"))?;
+    let provider = PROVIDERS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(name)
+        .cloned()
+        .ok_or_else(|| format!("unknown secret provider `{name}`"))?;
+    provider
+        .get_secret(key)
+        .map_err(|e| format!("cannot resolve secret `{reference}`: {e}"))
+}
+
+/// Returns `true` if a string value of `config` references a secret.
+#[must_use]
+pub fn has_secrets(config: &Value) -> bool {
+    match config {
+        Value::String(s) => s.contains(SECRET_REFERENCE),
+        Value::Array(values) => values.iter().any(has_secrets),
+        Value::Object(fields) => fields.values().any(has_secrets),
+        _ => false,
+    }
+}
+
+/// Returns a copy of `config` whose secret references are replaced with the secrets.
+pub fn resolve_secrets(config: &Value) -> Result<Value, String> {
+    Ok(match config {
+        Value::String(s) if s.contains(SECRET_REFERENCE) => {
+            Value::String(crate::env::substitute(s, "$", |reference, resolved| {
+                let reference = reference
+                    .strip_prefix(SECRET_SCHEME)
+                    .ok_or_else(|| format!("unexpected reference `${{{reference}}}` in `{s}`"))?;
+                resolved.push_str(&get_secret(reference)?);
+                Ok(())
+            })?)
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(resolve_secrets)
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), resolve_secrets(value)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+/// Reads the secrets from the environment variables.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvSecretProvider;
+
+impl SecretProvider for EnvSecretProvider {
+    fn get_secret(&self, key: &str) -> Result<String, String> {
+        std::env::var(key).map_err(|e| format!("environment variable `{key}`: {e}"))
+    }
+}
+
+/// Reads the secrets from files.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSecretProvider;
+
+impl SecretProvider for FileSecretProvider {
+    fn get_secret(&self, key: &str) -> Result<String, String> {
+        let mut secret = std::fs::read_to_string(key).map_err(|e| format!("file `{key}`: {e}"))?;
+        if secret.ends_with('\n') {
+            let _ = secret.pop();
+            if secret.ends_with('\r') {
+                let _ = secret.pop();
+            }
+        }
+        Ok(secret)
+    }
+}
+
+#[cfg(feature = "azure-key-vault")]
+mod azure {
+    use super::SecretProvider;
+    use serde::Deserialize;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    /// Endpoint of the Azure Instance Metadata Service issuing the managed identity tokens.
+    const IMDS_TOKEN_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+    const KEY_VAULT_RESOURCE: &str = "https://vault.azure.net";
+    const KEY_VAULT_API_VERSION: &str = "7.4";
+    /// Margin before the expiry of a token from which it is renewed.
+    const TOKEN_RENEWAL_MARGIN: Duration = Duration::from_secs(300);
+
+    #[derive(Deserialize)]
+    struct TokenResponse {
+        access_token: String,
+        expires_in: String,
+    }
+
+    #[derive(Deserialize)]
+    struct SecretBundle {
+        value: String,
+    }
+
+    /// Reads the secrets from Azure Key Vault, authenticated with the managed identity of the
+    /// host. A user-assigned identity is selected with the `AZURE_CLIENT_ID` environment
+    /// variable.
+    #[derive(Default)]
+    pub(super) struct AzureKeyVaultSecretProvider {
+        token: Mutex<Option<(String, Instant)>>,
+    }
+
+    impl AzureKeyVaultSecretProvider {
+        fn access_token(&self) -> Result<String, String> {
+            let mut token = self
+                .token
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let now = Instant::now();
+            if let Some((access_token, _)) = token.as_ref().filter(|(_, renew_at)| now < *renew_at)
+            {
+                return Ok(access_token.clone());
+            }
+            let mut request = ureq::get(IMDS_TOKEN_ENDPOINT)
+                .set("Metadata", "true")
+                .query("api-version", "2018-02-01")
+                .query("resource", KEY_VAULT_RESOURCE);
+            if let Ok(client_id) = std::env::var("AZURE_CLIENT_ID") {
+                request = request.query("client_id", &client_id);
+            }
+            let response: TokenResponse = request
+                .call()
+                .map_err(|e| format!("managed identity token request failed: {e}"))?
+                .into_json()
+                .map_err(|e| format!("invalid managed identity token: {e}"))?;
+            let expires_in = response
+                .expires_in
+                .parse()
+                .map(Duration::from_secs)
+                .unwrap_or_default();
+            let renew_at = Instant::now() + expires_in.saturating_sub(TOKEN_RENEWAL_MARGIN);
+            *token = Some((response.access_token.clone(), renew_at));
+            Ok(response.access_token)
+        }
+    }
+
+    impl SecretProvider for AzureKeyVaultSecretProvider {
+        fn get_secret(&self, key: &str) -> Result<String, String> {
+            let (vault, secret) = key
+                .split_once('/')
+                .ok_or_else(|| "expected <vault>/<name>[/<version>]".to_owned())?;
+            let url = format!("https://{vault}.vault.azure.net/secrets/{secret}");
+            let bundle: SecretBundle = ureq::get(&url)
+                .set("Authorization", &format!("Bearer {}", self.access_token()?))
+                .query("api-version", KEY_VAULT_API_VERSION)
+                .call()
+                .map_err(|e| format!("Key Vault request failed: {e}"))?
+                .into_json()
+                .map_err(|e| format!("invalid Key Vault response: {e}"))?;
+            Ok(bundle.value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct StaticProvider;
+
+    impl SecretProvider for StaticProvider {
+        fn get_secret(&self, key: &str) -> Result<String, String> {
+            match key {
+                "cert_password" => Ok("s3cr3t".to_owned()),
+                _ => Err("not found".to_owned()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve_secrets() {
+        register_secret_provider("static", Arc::new(StaticProvider));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("client_secret");
+        std::fs::write(&path, "from-file\n").unwrap();
+
+        let config = json!({
+            "tls": {"password": "${secret:static:cert_password}"},
+            "auth": [format!("id:${{secret:file:{}}}", path.display())],
+            "price": "$$5 ${secret:static:cert_password}",
+            "port": 4317,
+        });
+        assert!(has_secrets(&config));
+        assert_eq!(
+            resolve_secrets(&config).unwrap(),
+            json!({
+                "tls": {"password": "s3cr3t"},
+                "auth": ["id:from-file"],
+                "price": "$5 s3cr3t",
+                "port": 4317,
+            })
+        );
+
+        let plain = json!({"price": "$$5", "pattern": "${literal}"});
+        assert!(!has_secrets(&plain));
+        assert_eq!(resolve_secrets(&plain).unwrap(), plain);
+
+        for reference in ["static:missing", "vault:cert_password", "static"] {
+            let config = json!({"password": format!("${{secret:{reference}}}")});
+            assert!(resolve_secrets(&config).is_err(), "{reference}");
+        }
+    }
+}
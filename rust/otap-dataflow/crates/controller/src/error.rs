@@ -16,6 +16,17 @@ pub enum Error {
         errors: Vec<otap_df_config::error::Error>,
     },
 
+    /// A configuration reload could not be applied.
+    #[error("Failed to reload the pipeline configuration: {message}")]
+    ConfigReloadError {
+        /// Error message.
+        message: String,
+    },
+
+    /// The pipelines cannot be edited before the controller is started.
+    #[error("The controller is not running")]
+    ControllerNotRunning,
+
     /// An error originating from the admin module.
     #[error("Admin module error: {0}")]
     AdminError(#[from] otap_df_admin::error::Error),
@@ -63,6 +74,17 @@ pub enum Error {
         available: Vec<usize>,
     },
 
+    /// Invalid requested set of CPU core IDs.
+    #[error("Invalid core ID set {cores:?}: {message}. Available core IDs: {available:?}")]
+    InvalidCoreSet {
+        /// The requested core IDs.
+        cores: Vec<usize>,
+        /// Error message.
+        message: String,
+        /// The available CPU core IDs detected on this system.
+        available: Vec<usize>,
+    },
+
     /// Core affinity error.
     #[error("Failed to set core affinity for thread {thread_id} to core {core_id}: {message}")]
     CoreAffinityError {
@@ -12,12 +12,15 @@
 //! - TODO: Status and health checks for pipelines
 //! - TODO: Graceful shutdown of pipelines
 //! - TODO: Auto-restart threads in case of panic
-//! - TODO: Live pipeline updates
 //! - TODO: Better resource control
 //! - TODO: Monitoring
 //! - TODO: Support pipeline groups
 
 use crate::error::Error;
+use crate::reload::{
+    PipelineEditor, PipelineSlot, Reloader, SharedPipelineSlot, SlotAdminSender, lock_slot,
+    watch_config_file,
+};
 use crate::thread_task::spawn_thread_local_task;
 use core_affinity::CoreId;
 use otap_df_config::engine::HttpAdminSettings;
@@ -33,16 +36,26 @@ use otap_df_engine::control::{
     PipelineCtrlMsgReceiver, PipelineCtrlMsgSender, pipeline_ctrl_msg_channel,
 };
 use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::feature_gate;
+use otap_df_engine::in_flight::InFlightData;
+use otap_df_engine::latency::LatencyTrackedData;
+use otap_df_engine::memory::MemoryAccountedData;
+use otap_df_engine::shutdown::ShutdownReport;
 use otap_df_state::DeployedPipelineKey;
 use otap_df_state::event::{ErrorSummary, ObservedEvent};
 use otap_df_state::reporter::ObservedEventReporter;
 use otap_df_state::store::ObservedStateStore;
 use otap_df_telemetry::MetricsSystem;
 use otap_df_telemetry::reporter::MetricsReporter;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
+use std::time::Duration;
 
 /// Error types and helpers for the controller module.
 pub mod error;
+/// Hot-reload of the pipeline configuration.
+pub mod reload;
 /// Utilities to spawn async tasks on dedicated threads with graceful shutdown.
 pub mod thread_task;
 
@@ -55,15 +68,116 @@ pub mod thread_task;
 pub struct Controller<PData: 'static + Clone + Send + Sync + std::fmt::Debug> {
     /// The pipeline factory used to build runtime pipelines.
     pipeline_factory: &'static PipelineFactory<PData>,
+    /// Configuration file to watch for changes, and how often to check it.
+    config_watch: Option<(PathBuf, Duration)>,
+    /// Applies configuration changes once the pipelines are running.
+    reloader: Arc<OnceLock<Arc<Reloader<PData>>>>,
+    /// Pipelines of the same pipeline group run alongside the main pipeline, with their own quota
+    /// if they do not share the cores of the main pipeline.
+    additional_pipelines: Vec<(PipelineId, PipelineConfig, Option<Quota>)>,
 }
 
+/// Maximum duration given to a pipeline to drain before being rebuilt with a new configuration.
+const RELOAD_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
 impl<PData: 'static + Clone + Send + Sync + std::fmt::Debug> Controller<PData> {
     /// Creates a new controller with the given pipeline factory.
     pub fn new(pipeline_factory: &'static PipelineFactory<PData>) -> Self {
-        Self { pipeline_factory }
+        Self {
+            pipeline_factory,
+            config_watch: None,
+            reloader: Arc::new(OnceLock::new()),
+            additional_pipelines: Vec::new(),
+        }
+    }
+
+    /// Returns a handle to add or remove nodes of the running pipelines.
+    #[must_use]
+    pub fn editor(&self) -> PipelineEditor<PData> {
+        PipelineEditor {
+            reloader: self.reloader.clone(),
+        }
+    }
+
+    /// Watches the given pipeline configuration file, checking it every `poll_interval`, and
+    /// applies its changes to the running pipelines without restarting the process.
+    #[must_use]
+    pub fn with_config_watch(mut self, path: PathBuf, poll_interval: Duration) -> Self {
+        self.config_watch = Some((path, poll_interval));
+        self
+    }
+
+    /// Runs another pipeline of the pipeline group alongside the main pipeline, on the same cores.
+    ///
+    /// Additional pipelines are typically fed by the main pipeline through pipeline bridges. They
+    /// are not affected by the configuration watch nor by the reloads of the main pipeline.
+    #[must_use]
+    pub fn with_additional_pipeline(
+        mut self,
+        pipeline_id: PipelineId,
+        pipeline: PipelineConfig,
+    ) -> Self {
+        self.additional_pipelines
+            .push((pipeline_id, pipeline, None));
+        self
+    }
+
+    /// Runs another pipeline of the pipeline group on its own cores, one instance pinned to each
+    /// core selected by the given quota.
+    ///
+    /// Combined with pipeline bridges, this runs sharded pipeline instances behind a shared
+    /// receiver, e.g. an ingest pipeline with the OTLP receiver pinned to core 0 feeding a
+    /// processing pipeline pinned to cores 1 to 7, so the receiver does not compete with the
+    /// processors for the same cores.
+    #[must_use]
+    pub fn with_sharded_pipeline(
+        mut self,
+        pipeline_id: PipelineId,
+        pipeline: PipelineConfig,
+        quota: Quota,
+    ) -> Self {
+        self.additional_pipelines
+            .push((pipeline_id, pipeline, Some(quota)));
+        self
+    }
+
+    /// Returns the node plugins registered in the pipeline factory, sorted by URN.
+    #[must_use]
+    pub fn capabilities(&self) -> otap_df_admin::Capabilities {
+        let sorted_urns = |mut urns: Vec<&'static str>| {
+            urns.sort_unstable();
+            urns
+        };
+        otap_df_admin::Capabilities {
+            receivers: sorted_urns(
+                self.pipeline_factory
+                    .get_receiver_factory_map()
+                    .keys()
+                    .copied()
+                    .collect(),
+            ),
+            processors: sorted_urns(
+                self.pipeline_factory
+                    .get_processor_factory_map()
+                    .keys()
+                    .copied()
+                    .collect(),
+            ),
+            exporters: sorted_urns(
+                self.pipeline_factory
+                    .get_exporter_factory_map()
+                    .keys()
+                    .copied()
+                    .collect(),
+            ),
+            feature_gates: feature_gate::states(),
+        }
     }
 
     /// Starts the controller with the given pipeline configuration and quota.
+    ///
+    /// The feature gates set in the settings of `pipeline` are applied before any pipeline is
+    /// built, and stay as they are for the lifetime of the process.
     pub fn run_forever(
         &self,
         pipeline_group_id: PipelineGroupId,
@@ -71,15 +185,23 @@ impl<PData: 'static + Clone + Send + Sync + std::fmt::Debug> Controller<PData> {
         pipeline: PipelineConfig,
         quota: Quota,
         admin_settings: HttpAdminSettings,
-    ) -> Result<(), Error> {
+    ) -> Result<(), Error>
+    where
+        PData: InFlightData + MemoryAccountedData + LatencyTrackedData,
+    {
+        feature_gate::apply(&pipeline.pipeline_settings().feature_gates)
+            .map_err(|errors| Error::InvalidConfiguration { errors })?;
+
         // Initialize metrics system and observed event store.
         // ToDo A hierarchical metrics system will be implemented to better support hardware with multiple NUMA nodes.
         let metrics_system = MetricsSystem::default();
         let metrics_reporter = metrics_system.reporter();
-        let controller_ctx = ControllerContext::new(metrics_system.registry());
         let obs_state_store = ObservedStateStore::new(pipeline.pipeline_settings());
+        let controller_ctx = ControllerContext::new(metrics_system.registry())
+            .with_health_registry(obs_state_store.health_registry());
         let obs_evt_reporter = obs_state_store.reporter(); // Only the reporting API
         let obs_state_handle = obs_state_store.handle(); // Only the querying API
+        feature_gate::report(&controller_ctx, &mut metrics_reporter.clone());
 
         // Start the metrics aggregation
         let metrics_registry = metrics_system.registry();
@@ -94,102 +216,168 @@ impl<PData: 'static + Clone + Send + Sync + std::fmt::Debug> Controller<PData> {
                 obs_state_store.run(cancellation_token)
             })?;
 
-        // Start one thread per requested core
+        // Start one thread per requested core and pipeline
         // Get available CPU cores for pinning
-        let requested_cores = Self::select_cores_for_quota(
-            core_affinity::get_core_ids().ok_or_else(|| Error::CoreDetectionUnavailable)?,
-            quota,
-        )?;
-        let mut threads = Vec::with_capacity(requested_cores.len());
-        let mut ctrl_msg_senders = Vec::with_capacity(requested_cores.len());
+        let available_core_ids =
+            core_affinity::get_core_ids().ok_or_else(|| Error::CoreDetectionUnavailable)?;
+        let requested_cores = Self::select_cores_for_quota(available_core_ids.clone(), quota)?;
+        let mut pipelines: Vec<(PipelineId, PipelineConfig, Vec<CoreId>)> =
+            Vec::with_capacity(1 + self.additional_pipelines.len());
+        pipelines.push((pipeline_id.clone(), pipeline.clone(), requested_cores));
+        for (additional_id, additional_pipeline, additional_quota) in &self.additional_pipelines {
+            let cores = match additional_quota {
+                Some(additional_quota) => Self::select_cores_for_quota(
+                    available_core_ids.clone(),
+                    additional_quota.clone(),
+                )?,
+                None => pipelines[0].2.clone(),
+            };
+            pipelines.push((additional_id.clone(), additional_pipeline.clone(), cores));
+        }
+        let thread_count = pipelines.iter().map(|(_, _, cores)| cores.len()).sum();
+        let mut threads = Vec::with_capacity(thread_count);
+        let mut slots = Vec::with_capacity(thread_count);
 
         // ToDo [LQ] Support multiple pipeline groups in the future.
 
-        for (thread_id, core_id) in requested_cores.into_iter().enumerate() {
-            let pipeline_key = DeployedPipelineKey {
-                pipeline_group_id: pipeline_group_id.clone(),
-                pipeline_id: pipeline_id.clone(),
-                core_id: core_id.id,
-            };
-            let (pipeline_ctrl_msg_tx, pipeline_ctrl_msg_rx) = pipeline_ctrl_msg_channel(
-                pipeline
-                    .pipeline_settings()
-                    .default_pipeline_ctrl_msg_channel_size,
-            );
-            ctrl_msg_senders.push(pipeline_ctrl_msg_tx.clone());
-
-            let pipeline_config = pipeline.clone();
-            let pipeline_factory = self.pipeline_factory;
-            let pipeline_handle = controller_ctx.pipeline_context_with(
-                pipeline_group_id.clone(),
-                pipeline_id.clone(),
-                core_id.id,
-                thread_id,
-            );
-            let metrics_reporter = metrics_reporter.clone();
-
-            let thread_name = format!("pipeline-core-{}", core_id.id);
-            let obs_evt_reporter = obs_evt_reporter.clone();
-            let handle = thread::Builder::new()
-                .name(thread_name.clone())
-                .spawn(move || {
-                    Self::run_pipeline_thread(
-                        pipeline_key,
-                        core_id,
-                        pipeline_config,
-                        pipeline_factory,
-                        pipeline_handle,
-                        obs_evt_reporter,
-                        metrics_reporter,
-                        pipeline_ctrl_msg_tx,
-                        pipeline_ctrl_msg_rx,
-                    )
-                })
-                .map_err(|e| Error::ThreadSpawnError {
-                    thread_name: thread_name.clone(),
-                    source: e,
-                })?;
-
-            threads.push((thread_name, thread_id, core_id.id, handle));
+        let mut thread_id = 0;
+        for (current_pipeline_id, current_pipeline, cores) in pipelines {
+            for core_id in cores {
+                let pipeline_key = DeployedPipelineKey {
+                    pipeline_group_id: pipeline_group_id.clone(),
+                    pipeline_id: current_pipeline_id.clone(),
+                    core_id: core_id.id,
+                };
+                let (pipeline_ctrl_msg_tx, pipeline_ctrl_msg_rx) = pipeline_ctrl_msg_channel(
+                    current_pipeline
+                        .pipeline_settings()
+                        .default_pipeline_ctrl_msg_channel_size,
+                );
+                let slot: SharedPipelineSlot<PData> = Arc::new(Mutex::new(PipelineSlot {
+                    ctrl_msg_tx: pipeline_ctrl_msg_tx.clone(),
+                    pending_config: None,
+                }));
+                slots.push((pipeline_key.clone(), slot.clone()));
+
+                let pipeline_config = current_pipeline.clone();
+                let pipeline_factory = self.pipeline_factory;
+                let pipeline_handle = controller_ctx.pipeline_context_with(
+                    pipeline_group_id.clone(),
+                    current_pipeline_id.clone(),
+                    core_id.id,
+                    thread_id,
+                );
+                let metrics_reporter = metrics_reporter.clone();
+
+                let thread_name = if current_pipeline_id == pipeline_id {
+                    format!("pipeline-core-{}", core_id.id)
+                } else {
+                    format!("pipeline-{current_pipeline_id}-core-{}", core_id.id)
+                };
+                let obs_evt_reporter = obs_evt_reporter.clone();
+                let handle = thread::Builder::new()
+                    .name(thread_name.clone())
+                    .spawn(move || {
+                        Self::run_pipeline_thread(
+                            pipeline_key,
+                            core_id,
+                            pipeline_config,
+                            pipeline_factory,
+                            pipeline_handle,
+                            obs_evt_reporter,
+                            metrics_reporter,
+                            slot,
+                            pipeline_ctrl_msg_tx,
+                            pipeline_ctrl_msg_rx,
+                        )
+                    })
+                    .map_err(|e| Error::ThreadSpawnError {
+                        thread_name: thread_name.clone(),
+                        source: e,
+                    })?;
+
+                threads.push((
+                    thread_name,
+                    thread_id,
+                    current_pipeline_id.clone(),
+                    core_id.id,
+                    handle,
+                ));
+                thread_id += 1;
+            }
         }
 
         // Drop the original metrics sender so only pipeline threads hold references
         drop(metrics_reporter);
 
+        let capabilities = self.capabilities();
+
+        // Convert the pipeline slots to trait objects for the admin crate
+        let admin_senders: Vec<Arc<dyn otap_df_engine::control::PipelineAdminSender>> = slots
+            .iter()
+            .map(|(_, slot)| {
+                Arc::new(SlotAdminSender(slot.clone()))
+                    as Arc<dyn otap_df_engine::control::PipelineAdminSender>
+            })
+            .collect();
+        // Only the main pipeline is reloaded
+        let main_slots = slots
+            .into_iter()
+            .filter(|(key, _)| key.pipeline_id == pipeline_id)
+            .collect();
+        let reloader = Arc::new(Reloader::new(
+            pipeline_group_id.clone(),
+            pipeline_id.clone(),
+            self.pipeline_factory,
+            pipeline,
+            main_slots,
+            obs_evt_reporter.clone(),
+            RELOAD_DRAIN_TIMEOUT,
+        ));
+        let _ = self.reloader.set(reloader.clone());
+
+        // Start watching the configuration file for changes
+        let config_watcher_handle = match self.config_watch.clone() {
+            Some((path, poll_interval)) => {
+                let reloader = reloader.clone();
+                Some(spawn_thread_local_task(
+                    "config-watcher",
+                    move |cancellation_token| {
+                        watch_config_file(path, poll_interval, reloader, cancellation_token)
+                    },
+                )?)
+            }
+            None => None,
+        };
+
         // Start the admin HTTP server
         let admin_server_handle =
             spawn_thread_local_task("http-admin", move |cancellation_token| {
-                // Convert the concrete senders to trait objects for the admin crate
-                let admin_senders: Vec<
-                    std::sync::Arc<dyn otap_df_engine::control::PipelineAdminSender>,
-                > = ctrl_msg_senders
-                    .into_iter()
-                    .map(|sender| {
-                        std::sync::Arc::new(sender)
-                            as std::sync::Arc<dyn otap_df_engine::control::PipelineAdminSender>
-                    })
-                    .collect();
-
                 otap_df_admin::run(
                     admin_settings,
                     obs_state_handle,
                     admin_senders,
                     metrics_registry,
+                    capabilities,
+                    reloader,
                     cancellation_token,
                 )
             })?;
 
         // Wait for all pipeline threads to finish and collect their results
         let mut results: Vec<Result<(), Error>> = Vec::with_capacity(threads.len());
-        for (thread_name, thread_id, core_id, handle) in threads {
+        for (thread_name, thread_id, thread_pipeline_id, core_id, handle) in threads {
             let pipeline_key = DeployedPipelineKey {
                 pipeline_group_id: pipeline_group_id.clone(),
-                pipeline_id: pipeline_id.clone(),
+                pipeline_id: thread_pipeline_id,
                 core_id,
             };
             match handle.join() {
-                Ok(Ok(_)) => {
-                    obs_evt_reporter.report(ObservedEvent::drained(pipeline_key, None));
+                Ok(Ok(report)) => {
+                    obs_evt_reporter.report(ObservedEvent::drained(
+                        pipeline_key,
+                        Some(format!("Pipeline shutdown: {report}.")),
+                    ));
                 }
                 Ok(Err(e)) => {
                     let err_summary: ErrorSummary = error_summary_from_gen(&e);
@@ -230,6 +418,9 @@ impl<PData: 'static + Clone + Send + Sync + std::fmt::Debug> Controller<PData> {
         thread::park();
 
         // All pipelines have finished; shut down the admin HTTP server and metric aggregator gracefully.
+        if let Some(handle) = config_watcher_handle {
+            handle.shutdown_and_join()?;
+        }
         admin_server_handle.shutdown_and_join()?;
         metrics_agg_handle.shutdown_and_join()?;
         obs_state_join_handle.shutdown_and_join()?;
@@ -286,10 +477,34 @@ impl<PData: 'static + Clone + Send + Sync + std::fmt::Debug> Controller<PData> {
 
                 Ok(selected)
             }
+            CoreAllocation::CoreSet { cores } => {
+                let invalid = |message: &str| Error::InvalidCoreSet {
+                    cores: cores.clone(),
+                    message: message.to_owned(),
+                    available: available_core_ids.iter().map(|c| c.id).collect(),
+                };
+                if cores.is_empty() {
+                    return Err(invalid("The set of cores is empty"));
+                }
+                if let Some(missing) = cores
+                    .iter()
+                    .find(|id| !available_core_ids.iter().any(|c| c.id == **id))
+                {
+                    return Err(invalid(&format!("Core {missing} is not available")));
+                }
+                Ok(available_core_ids
+                    .iter()
+                    .filter(|c| cores.contains(&c.id))
+                    .copied()
+                    .collect())
+            }
         }
     }
 
     /// Runs a single pipeline in the current thread.
+    ///
+    /// When the pipeline is shut down for a configuration reload, it is rebuilt from the pending
+    /// configuration of its slot, or from the previous configuration if that fails.
     fn run_pipeline_thread(
         pipeline_key: DeployedPipelineKey,
         core_id: CoreId,
@@ -298,9 +513,13 @@ impl<PData: 'static + Clone + Send + Sync + std::fmt::Debug> Controller<PData> {
         pipeline_handle: PipelineContext,
         obs_evt_reporter: ObservedEventReporter,
         metrics_reporter: MetricsReporter,
+        slot: SharedPipelineSlot<PData>,
         pipeline_ctrl_msg_tx: PipelineCtrlMsgSender<PData>,
         pipeline_ctrl_msg_rx: PipelineCtrlMsgReceiver<PData>,
-    ) -> Result<Vec<()>, Error> {
+    ) -> Result<ShutdownReport, Error>
+    where
+        PData: InFlightData + MemoryAccountedData + LatencyTrackedData,
+    {
         // Pin thread to specific core
         if !core_affinity::set_for_current(core_id) {
             // Continue execution even if pinning fails.
@@ -314,23 +533,84 @@ impl<PData: 'static + Clone + Send + Sync + std::fmt::Debug> Controller<PData> {
         ));
 
         // Build the runtime pipeline from the configuration
-        let runtime_pipeline = pipeline_factory
-            .build(pipeline_handle, pipeline_config.clone())
-            .map_err(|e| Error::PipelineRuntimeError {
-                source: Box::new(e),
-            })?;
+        let build = |config: &PipelineConfig| {
+            pipeline_factory
+                .build(pipeline_handle.clone(), config.clone())
+                .map_err(|e| Error::PipelineRuntimeError {
+                    source: Box::new(e),
+                })
+        };
+        let mut runtime_pipeline = build(&pipeline_config)?;
+        let mut current_config = pipeline_config;
 
         obs_evt_reporter.report(ObservedEvent::ready(
             pipeline_key.clone(),
             Some("Pipeline initialization successful.".to_owned()),
         ));
 
-        // Start the pipeline (this will use the current thread's Tokio runtime)
-        runtime_pipeline
-            .run_forever(metrics_reporter, pipeline_ctrl_msg_tx, pipeline_ctrl_msg_rx)
-            .map_err(|e| Error::PipelineRuntimeError {
-                source: Box::new(e),
-            })
+        let mut ctrl_msg_tx = pipeline_ctrl_msg_tx;
+        let mut ctrl_msg_rx = pipeline_ctrl_msg_rx;
+        loop {
+            // Start the pipeline (this will use the current thread's Tokio runtime)
+            let report = runtime_pipeline
+                .run_forever(metrics_reporter.clone(), ctrl_msg_tx, ctrl_msg_rx)
+                .map_err(|e| Error::PipelineRuntimeError {
+                    source: Box::new(e),
+                })?;
+
+            // The pipeline has drained. Unless this was a configuration reload, we are done.
+            let mut guard = lock_slot(&slot);
+            let Some(new_config) = guard.pending_config.take() else {
+                return Ok(report);
+            };
+            let (tx, rx) = pipeline_ctrl_msg_channel(
+                new_config
+                    .pipeline_settings()
+                    .default_pipeline_ctrl_msg_channel_size,
+            );
+            guard.ctrl_msg_tx = tx.clone();
+            drop(guard);
+            ctrl_msg_tx = tx;
+            ctrl_msg_rx = rx;
+
+            runtime_pipeline = match build(&new_config) {
+                Ok(runtime_pipeline) => {
+                    obs_evt_reporter.report(ObservedEvent::update_applied(
+                        pipeline_key.clone(),
+                        Some("Pipeline configuration reloaded.".to_owned()),
+                    ));
+                    current_config = new_config;
+                    runtime_pipeline
+                }
+                Err(e) => {
+                    obs_evt_reporter.report(ObservedEvent::update_failed(
+                        pipeline_key.clone(),
+                        Some("The new pipeline configuration could not be built.".to_owned()),
+                        error_summary_from_gen(&e),
+                    ));
+                    match build(&current_config) {
+                        Ok(runtime_pipeline) => {
+                            obs_evt_reporter.report(ObservedEvent::rollback_complete(
+                                pipeline_key.clone(),
+                                Some("Previous pipeline configuration restored.".to_owned()),
+                            ));
+                            runtime_pipeline
+                        }
+                        Err(e) => {
+                            obs_evt_reporter.report(ObservedEvent::rollback_failed(
+                                pipeline_key.clone(),
+                                Some(
+                                    "The previous pipeline configuration could not be restored."
+                                        .to_owned(),
+                                ),
+                                error_summary_from_gen(&e),
+                            ));
+                            return Err(e);
+                        }
+                    }
+                }
+            };
+        }
     }
 }
 
@@ -510,6 +790,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn select_with_core_set() {
+        let quota = Quota {
+            core_allocation: CoreAllocation::CoreSet {
+                cores: vec![6, 1, 3, 1],
+            },
+        };
+        let result = Controller::<()>::select_cores_for_quota(available_core_ids(), quota).unwrap();
+        assert_eq!(to_ids(&result), vec![1, 3, 6]);
+    }
+
+    #[test]
+    fn select_with_invalid_core_set_errors() {
+        for cores in [vec![], vec![2, 8]] {
+            let quota = Quota {
+                core_allocation: CoreAllocation::CoreSet {
+                    cores: cores.clone(),
+                },
+            };
+            let err =
+                Controller::<()>::select_cores_for_quota(available_core_ids(), quota).unwrap_err();
+            match err {
+                Error::InvalidCoreSet {
+                    cores: requested,
+                    available,
+                    ..
+                } => {
+                    assert_eq!(requested, cores);
+                    assert_eq!(available, to_ids(&available_core_ids()));
+                }
+                other => panic!("unexpected error: {other:?}"),
+            }
+        }
+    }
+
     #[test]
     fn select_with_zero_count_uses_all_cores() {
         let quota = Quota {
@@ -520,4 +835,36 @@ mod tests {
         let result = Controller::<()>::select_cores_for_quota(available_core_ids, quota).unwrap();
         assert_eq!(to_ids(&result), to_ids(&expected_core_ids));
     }
+
+    static UNSORTED_EXPORTERS: [otap_df_engine::ExporterFactory<()>; 3] = [
+        otap_df_engine::ExporterFactory {
+            name: "urn:test:c:exporter",
+            create: |_, _, _, _| unreachable!(),
+        },
+        otap_df_engine::ExporterFactory {
+            name: "urn:test:a:exporter",
+            create: |_, _, _, _| unreachable!(),
+        },
+        otap_df_engine::ExporterFactory {
+            name: "urn:test:b:exporter",
+            create: |_, _, _, _| unreachable!(),
+        },
+    ];
+
+    static TEST_FACTORY: PipelineFactory<()> = PipelineFactory::new(&[], &[], &UNSORTED_EXPORTERS);
+
+    #[test]
+    fn capabilities_list_sorted_urns() {
+        let capabilities = Controller::new(&TEST_FACTORY).capabilities();
+        assert!(capabilities.receivers.is_empty());
+        assert!(capabilities.processors.is_empty());
+        assert_eq!(
+            capabilities.exporters,
+            vec![
+                "urn:test:a:exporter",
+                "urn:test:b:exporter",
+                "urn:test:c:exporter"
+            ]
+        );
+    }
 }
@@ -0,0 +1,512 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hot-reload of the pipeline configuration.
+//!
+//! A new configuration is applied without restarting the process: the pipeline running on each
+//! core is drained with the regular shutdown sequence (receivers stop first, then the in-flight
+//! data flows through the processors and exporters, all bounded by a deadline), and the pipeline
+//! thread rebuilds it from the new configuration. If the new configuration cannot be built (e.g.
+//! a node rejects its user config), the previous configuration is restored.
+//!
+//! Reloads are triggered by the admin API, by [`watch_config_file`], which polls the pipeline
+//! configuration file for changes, or programmatically through a [`PipelineEditor`] adding or
+//! removing nodes and edges.
+
+use crate::error::Error;
+use otap_df_admin::{PipelineReloader, ReloadError};
+use otap_df_config::node::{DispatchStrategy, NodeKind, NodeUserConfig};
+use otap_df_config::pipeline::{PipelineConfig, PipelineConfigDiff, PipelineEdit};
+use otap_df_config::{NodeId, PipelineGroupId, PipelineId, PortName};
+use otap_df_engine::PipelineFactory;
+use otap_df_engine::control::{PipelineAdminSender, PipelineCtrlMsgSender};
+use otap_df_state::DeployedPipelineKey;
+use otap_df_state::event::ObservedEvent;
+use otap_df_state::reporter::ObservedEventReporter;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio_util::sync::CancellationToken;
+
+/// The state shared between a pipeline thread and the [`Reloader`].
+///
+/// The lock is held while a reload is requested and while the pipeline thread switches to a new
+/// configuration, so that a shutdown request always reaches the pipeline meant to be replaced.
+pub(crate) struct PipelineSlot<PData> {
+    /// Control channel of the pipeline currently running (or about to run) on the thread.
+    pub(crate) ctrl_msg_tx: PipelineCtrlMsgSender<PData>,
+    /// Configuration to run once the current pipeline has drained.
+    pub(crate) pending_config: Option<PipelineConfig>,
+}
+
+/// Shared handle on a [`PipelineSlot`].
+pub(crate) type SharedPipelineSlot<PData> = Arc<Mutex<PipelineSlot<PData>>>;
+
+/// Locks a pipeline slot, recovering it if a pipeline thread panicked while holding the lock.
+pub(crate) fn lock_slot<PData>(
+    slot: &SharedPipelineSlot<PData>,
+) -> MutexGuard<'_, PipelineSlot<PData>> {
+    slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Admin sender targeting whichever pipeline currently runs on a core, so that admin requests
+/// keep working after a reload replaced the pipeline control channel.
+pub(crate) struct SlotAdminSender<PData>(pub(crate) SharedPipelineSlot<PData>);
+
+impl<PData: 'static + Send + Sync> PipelineAdminSender for SlotAdminSender<PData> {
+    fn try_send_shutdown(
+        &self,
+        deadline: Instant,
+        reason: String,
+    ) -> Result<(), otap_df_engine::error::Error> {
+        lock_slot(&self.0)
+            .ctrl_msg_tx
+            .try_send_shutdown(deadline, reason)
+    }
+}
+
+/// Applies configuration changes to the pipeline deployed on every core.
+pub struct Reloader<PData: 'static> {
+    pipeline_group_id: PipelineGroupId,
+    pipeline_id: PipelineId,
+    pipeline_factory: &'static PipelineFactory<PData>,
+    /// The last configuration accepted.
+    current: Mutex<PipelineConfig>,
+    slots: Vec<(DeployedPipelineKey, SharedPipelineSlot<PData>)>,
+    obs_evt_reporter: ObservedEventReporter,
+    drain_timeout: Duration,
+}
+
+impl<PData: 'static + Send + Sync> Reloader<PData> {
+    pub(crate) fn new(
+        pipeline_group_id: PipelineGroupId,
+        pipeline_id: PipelineId,
+        pipeline_factory: &'static PipelineFactory<PData>,
+        config: PipelineConfig,
+        slots: Vec<(DeployedPipelineKey, SharedPipelineSlot<PData>)>,
+        obs_evt_reporter: ObservedEventReporter,
+        drain_timeout: Duration,
+    ) -> Self {
+        Self {
+            pipeline_group_id,
+            pipeline_id,
+            pipeline_factory,
+            current: Mutex::new(config),
+            slots,
+            obs_evt_reporter,
+            drain_timeout,
+        }
+    }
+
+    /// Replaces the running configuration, returning the changes being applied.
+    ///
+    /// Nothing happens if the new configuration is identical to the running one. Otherwise the
+    /// pipeline on each core is drained and rebuilt from the new configuration. The feature gates
+    /// being applied at startup, a configuration changing them is rejected.
+    pub fn reload(&self, config: PipelineConfig) -> Result<PipelineConfigDiff, Error> {
+        config
+            .validate(&self.pipeline_group_id, &self.pipeline_id)
+            .map_err(|e| Error::InvalidConfiguration { errors: vec![e] })?;
+        self.check_plugins(&config)?;
+        let mut current = self.lock_current();
+        if config.pipeline_settings().feature_gates != current.pipeline_settings().feature_gates {
+            return Err(Error::ConfigReloadError {
+                message: "the feature gates cannot be changed without a restart".to_owned(),
+            });
+        }
+        self.apply(&mut current, config)
+    }
+
+    /// Applies topology changes to the running configuration, returning the changes being
+    /// applied.
+    ///
+    /// The edits are applied as a whole: the resulting pipeline must be acyclic, its edges must
+    /// flow from receivers to exporters, and all its plugins must be available.
+    pub fn edit<I>(&self, edits: I) -> Result<PipelineConfigDiff, Error>
+    where
+        I: IntoIterator<Item = PipelineEdit>,
+    {
+        let mut current = self.lock_current();
+        let config = current
+            .with_edits(&self.pipeline_group_id, &self.pipeline_id, edits)
+            .map_err(|e| Error::InvalidConfiguration { errors: vec![e] })?;
+        self.check_plugins(&config)?;
+        self.apply(&mut current, config)
+    }
+
+    /// Returns the last configuration accepted.
+    #[must_use]
+    pub fn config(&self) -> PipelineConfig {
+        self.lock_current().clone()
+    }
+
+    fn lock_current(&self) -> MutexGuard<'_, PipelineConfig> {
+        self.current
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Drains the pipeline on each core to rebuild it from `config`.
+    fn apply(
+        &self,
+        current: &mut PipelineConfig,
+        config: PipelineConfig,
+    ) -> Result<PipelineConfigDiff, Error> {
+        let diff = current.diff(&config);
+        if diff.is_empty() {
+            return Ok(diff);
+        }
+
+        let mut errors = Vec::new();
+        for (key, slot) in &self.slots {
+            let mut slot = lock_slot(slot);
+            slot.pending_config = Some(config.clone());
+            let deadline = Instant::now() + self.drain_timeout;
+            match slot
+                .ctrl_msg_tx
+                .try_send_shutdown(deadline, "configuration reload".to_owned())
+            {
+                Ok(()) => self.obs_evt_reporter.report(ObservedEvent::update_admitted(
+                    key.clone(),
+                    Some(format!("Configuration reload: {diff}")),
+                )),
+                Err(e) => {
+                    slot.pending_config = None;
+                    errors.push(format!("core {}: {e}", key.core_id));
+                }
+            }
+        }
+        *current = config;
+
+        if errors.is_empty() {
+            Ok(diff)
+        } else {
+            Err(Error::ConfigReloadError {
+                message: errors.join(", "),
+            })
+        }
+    }
+
+    /// Rejects configurations referencing plugins not compiled into this binary.
+    fn check_plugins(&self, config: &PipelineConfig) -> Result<(), Error> {
+        let mut unknown: Vec<String> = config
+            .node_iter()
+            .filter(|(_, node)| {
+                let urn = node.plugin_urn.as_ref();
+                match node.kind {
+                    NodeKind::Receiver => !self
+                        .pipeline_factory
+                        .get_receiver_factory_map()
+                        .contains_key(urn),
+                    NodeKind::Processor => !self
+                        .pipeline_factory
+                        .get_processor_factory_map()
+                        .contains_key(urn),
+                    NodeKind::Exporter => !self
+                        .pipeline_factory
+                        .get_exporter_factory_map()
+                        .contains_key(urn),
+                    NodeKind::ProcessorChain => false,
+                }
+            })
+            .map(|(node_id, node)| {
+                format!("node `{node_id}`: unknown plugin `{}`", node.plugin_urn)
+            })
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        unknown.sort();
+        Err(Error::ConfigReloadError {
+            message: unknown.join(", "),
+        })
+    }
+}
+
+impl<PData: 'static + Send + Sync> PipelineReloader for Reloader<PData> {
+    fn reload(
+        &self,
+        pipeline_group_id: &PipelineGroupId,
+        pipeline_id: &PipelineId,
+        config: PipelineConfig,
+    ) -> Result<PipelineConfigDiff, ReloadError> {
+        if *pipeline_group_id != self.pipeline_group_id || *pipeline_id != self.pipeline_id {
+            return Err(ReloadError::UnknownPipeline);
+        }
+        Reloader::reload(self, config).map_err(|e| ReloadError::Rejected(e.to_string()))
+    }
+
+    fn config(
+        &self,
+        pipeline_group_id: &PipelineGroupId,
+        pipeline_id: &PipelineId,
+    ) -> Option<PipelineConfig> {
+        (*pipeline_group_id == self.pipeline_group_id && *pipeline_id == self.pipeline_id)
+            .then(|| Reloader::config(self))
+    }
+}
+
+/// Handle used to change the topology of the pipelines run by a [`Controller`](crate::Controller)
+/// at runtime, e.g. to temporarily attach a debug exporter.
+///
+/// The handle can be obtained before the controller is started and used from any thread. Edits
+/// are refused until the pipelines are running.
+pub struct PipelineEditor<PData: 'static> {
+    pub(crate) reloader: Arc<OnceLock<Arc<Reloader<PData>>>>,
+}
+
+impl<PData: 'static> Clone for PipelineEditor<PData> {
+    fn clone(&self) -> Self {
+        Self {
+            reloader: self.reloader.clone(),
+        }
+    }
+}
+
+impl<PData: 'static + Send + Sync> PipelineEditor<PData> {
+    /// Applies the given edits as a whole, see [`Reloader::edit`].
+    pub fn apply<I>(&self, edits: I) -> Result<PipelineConfigDiff, Error>
+    where
+        I: IntoIterator<Item = PipelineEdit>,
+    {
+        self.reloader()?.edit(edits)
+    }
+
+    /// Adds an unconnected node.
+    pub fn add_node<N: Into<NodeId>>(
+        &self,
+        node_id: N,
+        node: NodeUserConfig,
+    ) -> Result<PipelineConfigDiff, Error> {
+        self.apply([PipelineEdit::AddNode {
+            node_id: node_id.into(),
+            node,
+        }])
+    }
+
+    /// Removes a node along with the edges leading to it.
+    pub fn remove_node<N: Into<NodeId>>(&self, node_id: N) -> Result<PipelineConfigDiff, Error> {
+        self.apply([PipelineEdit::RemoveNode {
+            node_id: node_id.into(),
+        }])
+    }
+
+    /// Connects an out port of a node to the given destinations, replacing any existing
+    /// connection of this port.
+    pub fn connect<S, P, T, I>(
+        &self,
+        source: S,
+        port: P,
+        destinations: I,
+        dispatch_strategy: DispatchStrategy,
+    ) -> Result<PipelineConfigDiff, Error>
+    where
+        S: Into<NodeId>,
+        P: Into<PortName>,
+        T: Into<NodeId>,
+        I: IntoIterator<Item = T>,
+    {
+        self.apply([PipelineEdit::Connect {
+            source: source.into(),
+            port: port.into(),
+            destinations: destinations.into_iter().map(Into::into).collect(),
+            dispatch_strategy,
+        }])
+    }
+
+    /// Removes the connection of an out port of a node.
+    pub fn disconnect<S: Into<NodeId>, P: Into<PortName>>(
+        &self,
+        source: S,
+        port: P,
+    ) -> Result<PipelineConfigDiff, Error> {
+        self.apply([PipelineEdit::Disconnect {
+            source: source.into(),
+            port: port.into(),
+        }])
+    }
+
+    /// Returns the configuration of the running pipelines.
+    pub fn config(&self) -> Result<PipelineConfig, Error> {
+        Ok(self.reloader()?.config())
+    }
+
+    fn reloader(&self) -> Result<&Arc<Reloader<PData>>, Error> {
+        self.reloader.get().ok_or(Error::ControllerNotRunning)
+    }
+}
+
+/// Polls the pipeline configuration file and reloads the pipeline when it is modified, until
+/// `cancel` is triggered.
+///
+/// Invalid configurations are reported and ignored; the pipeline keeps running with its current
+/// configuration.
+#[allow(clippy::print_stderr)]
+pub(crate) async fn watch_config_file<PData: 'static + Send + Sync>(
+    path: PathBuf,
+    poll_interval: Duration,
+    reloader: Arc<Reloader<PData>>,
+    cancel: CancellationToken,
+) -> Result<(), Error> {
+    let modified = |path: &PathBuf| -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    };
+    let mut last_modified = modified(&path);
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => return Ok(()),
+            _ = tokio::time::sleep(poll_interval) => {}
+        }
+        let current = modified(&path);
+        if current.is_none() || current == last_modified {
+            continue;
+        }
+        last_modified = current;
+
+        let result = PipelineConfig::from_file(
+            reloader.pipeline_group_id.clone(),
+            reloader.pipeline_id.clone(),
+            &path,
+        )
+        .map_err(|e| Error::InvalidConfiguration { errors: vec![e] })
+        .and_then(|config| reloader.reload(config));
+        // ToDo Replace these eprintln once we have selected a logging solution
+        match result {
+            Ok(diff) if diff.is_empty() => {}
+            Ok(diff) => eprintln!("Reloading pipeline configuration ({diff})"),
+            Err(e) => eprintln!(
+                "Ignoring pipeline configuration change in {}: {e}",
+                path.display()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otap_df_engine::control::{PipelineControlMsg, pipeline_ctrl_msg_channel};
+    use otap_df_state::store::ObservedStateStore;
+
+    static EMPTY_FACTORY: PipelineFactory<()> = PipelineFactory::new(&[], &[], &[]);
+
+    fn config(json: &str) -> PipelineConfig {
+        PipelineConfig::from_json("group".into(), "pipeline".into(), json).expect("valid config")
+    }
+
+    #[tokio::test]
+    async fn reload_drains_pipeline_with_pending_config() {
+        let initial = config(r#"{"nodes": {}}"#);
+        let store = ObservedStateStore::new(initial.pipeline_settings());
+        let (tx, mut rx) = pipeline_ctrl_msg_channel::<()>(4);
+        let slot = Arc::new(Mutex::new(PipelineSlot {
+            ctrl_msg_tx: tx,
+            pending_config: None,
+        }));
+        let key = DeployedPipelineKey {
+            pipeline_group_id: "group".into(),
+            pipeline_id: "pipeline".into(),
+            core_id: 0,
+        };
+        let reloader = Reloader::new(
+            "group".into(),
+            "pipeline".into(),
+            &EMPTY_FACTORY,
+            initial.clone(),
+            vec![(key, slot.clone())],
+            store.reporter(),
+            Duration::from_secs(1),
+        );
+
+        // An identical configuration is a no-op
+        assert!(reloader.reload(initial).expect("reload").is_empty());
+        assert!(lock_slot(&slot).pending_config.is_none());
+
+        // The feature gates are applied at startup only
+        let gated = config(r#"{"settings": {"feature_gates": {"test.gate": true}}, "nodes": {}}"#);
+        assert!(matches!(
+            reloader.reload(gated),
+            Err(Error::ConfigReloadError { .. })
+        ));
+        assert!(lock_slot(&slot).pending_config.is_none());
+
+        let updated = config(r#"{"settings": {"default_pdata_channel_size": 10}, "nodes": {}}"#);
+        let diff = reloader.reload(updated).expect("reload");
+        assert!(diff.settings_changed);
+        assert!(lock_slot(&slot).pending_config.is_some());
+        match rx.recv().await {
+            Ok(PipelineControlMsg::Shutdown { reason, .. }) => {
+                assert_eq!(reason, "configuration reload");
+            }
+            other => panic!("expected a shutdown request, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn editor_refuses_invalid_edits() {
+        let editor = PipelineEditor::<()> {
+            reloader: Arc::new(OnceLock::new()),
+        };
+        assert!(matches!(
+            editor.remove_node("recv"),
+            Err(Error::ControllerNotRunning)
+        ));
+
+        let initial = config(r#"{"nodes": {}}"#);
+        let store = ObservedStateStore::new(initial.pipeline_settings());
+        let reloader = Reloader::<()>::new(
+            "group".into(),
+            "pipeline".into(),
+            &EMPTY_FACTORY,
+            initial,
+            Vec::new(),
+            store.reporter(),
+            Duration::from_secs(1),
+        );
+        assert!(editor.reloader.set(Arc::new(reloader)).is_ok());
+
+        assert!(matches!(
+            editor.remove_node("recv"),
+            Err(Error::InvalidConfiguration { .. })
+        ));
+        let node = NodeUserConfig {
+            kind: NodeKind::Exporter,
+            plugin_urn: "urn:test:unknown".into(),
+            description: None,
+            out_ports: Default::default(),
+            default_out_port: None,
+            ack_deadline: None,
+            config: Default::default(),
+        };
+        assert!(matches!(
+            editor.add_node("debug", node),
+            Err(Error::ConfigReloadError { .. })
+        ));
+        assert_eq!(editor.config().expect("config").node_iter().count(), 0);
+    }
+
+    #[test]
+    fn reload_rejects_unknown_plugins() {
+        let initial = config(r#"{"nodes": {}}"#);
+        let store = ObservedStateStore::new(initial.pipeline_settings());
+        let reloader = Reloader::<()>::new(
+            "group".into(),
+            "pipeline".into(),
+            &EMPTY_FACTORY,
+            initial,
+            Vec::new(),
+            store.reporter(),
+            Duration::from_secs(1),
+        );
+
+        let updated = config(
+            r#"{"nodes": {"recv": {"kind": "receiver", "plugin_urn": "urn:test:unknown"}}}"#,
+        );
+        match reloader.reload(updated) {
+            Err(Error::ConfigReloadError { message }) => {
+                assert!(message.contains("urn:test:unknown"));
+            }
+            other => panic!("expected the reload to be rejected, got {other:?}"),
+        }
+    }
+}
@@ -66,3 +66,19 @@ pub struct NodeAttributeSet {
     #[attribute]
     pub node_type: Cow<'static, str>,
 }
+
+/// Feature gate attributes.
+#[attribute_set(name = "feature_gate.attrs")]
+#[derive(Debug, Clone, Default, Hash)]
+pub struct FeatureGateAttributeSet {
+    /// Resource attributes.
+    #[compose]
+    pub resource_attrs: ResourceAttributeSet,
+
+    /// Feature gate identifier.
+    #[attribute]
+    pub feature_gate_id: Cow<'static, str>,
+    /// Feature gate stage (e.g., "alpha", "beta", "stable").
+    #[attribute]
+    pub stage: Cow<'static, str>,
+}
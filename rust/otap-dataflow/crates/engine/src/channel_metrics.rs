@@ -0,0 +1,113 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Occupancy of the pdata channels connecting the nodes of a pipeline.
+//!
+//! The engine attaches a [`ChannelMetrics`] set, registered with the attributes of the node, to
+//! the message channel of every processor and exporter, i.e. one per edge of the pipeline:
+//!
+//! - the channel samples its queue depth each time it delivers a pdata, the depth peaking right
+//!   before a pdata is taken out of the channel;
+//! - the metrics are reported when the node receives a `CollectTelemetry` message, before the
+//!   node handles it. The high-water mark covers the interval since the previous report.
+//!
+//! A queue depth close to the capacity reveals a saturated edge, whose upstream node will soon
+//! be backpressured. The nodes which never collect telemetry do not report these metrics.
+
+use otap_df_telemetry::instrument::Gauge;
+use otap_df_telemetry::metrics::MetricSet;
+use otap_df_telemetry::reporter::MetricsReporter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Occupancy metrics of the pdata input channel of a node.
+#[metric_set(name = "channel.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct ChannelMetrics {
+    /// Number of pdata waiting in the channel.
+    #[metric(unit = "{msg}")]
+    pub queue_depth: Gauge<u64>,
+
+    /// Maximum number of pdata the channel can hold.
+    #[metric(unit = "{msg}")]
+    pub capacity: Gauge<u64>,
+
+    /// Maximum number of pdata waiting in the channel since the previous report.
+    #[metric(unit = "{msg}")]
+    pub high_water_mark: Gauge<u64>,
+}
+
+/// Samples the occupancy of a channel and reports it.
+pub(crate) struct ChannelMonitor {
+    metrics: MetricSet<ChannelMetrics>,
+}
+
+impl ChannelMonitor {
+    /// Creates a monitor reporting the given metrics.
+    pub(crate) fn new(metrics: MetricSet<ChannelMetrics>) -> Self {
+        Self { metrics }
+    }
+
+    /// Records the depth of the channel.
+    pub(crate) fn observe(&mut self, depth: usize) {
+        let depth = depth as u64;
+        if depth > self.metrics.high_water_mark.get() {
+            self.metrics.high_water_mark.set(depth);
+        }
+    }
+
+    /// Reports the current depth and capacity of the channel, and its high-water mark.
+    pub(crate) fn report(
+        &mut self,
+        depth: usize,
+        capacity: Option<usize>,
+        metrics_reporter: &MetricsReporter,
+    ) {
+        self.observe(depth);
+        self.metrics.queue_depth.set(depth as u64);
+        self.metrics
+            .capacity
+            .set(capacity.map_or(0, |capacity| capacity as u64));
+        let _ = metrics_reporter.clone().report(&mut self.metrics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::NodeControlMsg;
+    use crate::message::{Message, MessageChannel, Receiver};
+    use otap_df_channel::mpsc;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otap_df_telemetry::testing::EmptyAttributes;
+
+    #[tokio::test]
+    async fn test_channel_metrics() {
+        let metrics = MetricsRegistryHandle::new().register::<ChannelMetrics>(EmptyAttributes());
+        let (control_tx, control_rx) = mpsc::Channel::<NodeControlMsg<u32>>::new(4);
+        let (pdata_tx, pdata_rx) = mpsc::Channel::new(8);
+        let mut channel = MessageChannel::new(
+            Receiver::new_local_mpsc_receiver(control_rx),
+            Receiver::new_local_mpsc_receiver(pdata_rx),
+        )
+        .with_channel_metrics(metrics);
+
+        for i in 0..3 {
+            pdata_tx.send(i).expect("channel has capacity");
+        }
+        for _ in 0..2 {
+            assert!(channel.recv().await.is_ok_and(|msg| msg.is_data()));
+        }
+
+        let (snapshots, metrics_reporter) = MetricsReporter::create_new_and_receiver(1);
+        control_tx
+            .send(NodeControlMsg::CollectTelemetry { metrics_reporter })
+            .expect("channel has capacity");
+        assert!(matches!(
+            channel.recv().await,
+            Ok(Message::Control(NodeControlMsg::CollectTelemetry { .. }))
+        ));
+        let snapshot = snapshots.try_recv().expect("reported");
+        // queue depth, capacity, high-water mark
+        assert_eq!(snapshot.get_metrics(), &[1, 8, 3]);
+    }
+}
@@ -3,9 +3,14 @@
 
 //! Context providing general information on the current controller and the current pipeline.
 
-use crate::attributes::{EngineAttributeSet, NodeAttributeSet, PipelineAttributeSet};
+use crate::attributes::{
+    EngineAttributeSet, NodeAttributeSet, PipelineAttributeSet, ResourceAttributeSet,
+};
 use otap_df_config::node::NodeKind;
 use otap_df_config::{NodeId, PipelineGroupId, PipelineId};
+use otap_df_state::DeployedPipelineKey;
+use otap_df_state::health::{HealthRegistry, NodeHealthReporter};
+use otap_df_telemetry::attributes::AttributeSetHandler;
 use otap_df_telemetry::metrics::{MetricSet, MetricSetHandler};
 use otap_df_telemetry::registry::MetricsRegistryHandle;
 use std::fmt::Debug;
@@ -86,6 +91,7 @@ pub struct ControllerContext {
     host_id: Cow<'static, str>,
     container_id: Cow<'static, str>,
     numa_node_id: usize,
+    health_registry: HealthRegistry,
 }
 
 /// A lightweight/cloneable pipeline context.
@@ -109,9 +115,17 @@ impl ControllerContext {
             host_id: HOST_ID.clone(),
             container_id: CONTAINER_ID.clone(),
             numa_node_id: 0, // ToDo(LQ): Set NUMA node ID if available
+            health_registry: HealthRegistry::default(),
         }
     }
 
+    /// Sets the registry collecting the health statuses reported by the nodes.
+    #[must_use]
+    pub fn with_health_registry(mut self, health_registry: HealthRegistry) -> Self {
+        self.health_registry = health_registry;
+        self
+    }
+
     /// Returns a new pipeline context with the given identifiers and the current controller context
     /// as the parent context.
     #[must_use]
@@ -130,6 +144,25 @@ impl ControllerContext {
             thread_id,
         )
     }
+
+    /// Returns the resource attributes of the process.
+    pub(crate) fn resource_attrs(&self) -> ResourceAttributeSet {
+        ResourceAttributeSet {
+            process_instance_id: self.process_instance_id.clone(),
+            host_id: self.host_id.clone(),
+            container_id: self.container_id.clone(),
+        }
+    }
+
+    /// Registers a new multivariate metrics instance not attached to a pipeline, e.g. the state
+    /// of a feature gate.
+    #[must_use]
+    pub(crate) fn register_metrics<T: MetricSetHandler + Default + Debug + Send + Sync>(
+        &self,
+        attrs: impl AttributeSetHandler + Send + Sync + 'static,
+    ) -> MetricSet<T> {
+        self.metrics_registry_handle.register::<T>(attrs)
+    }
 }
 
 impl PipelineContext {
@@ -157,21 +190,12 @@ impl PipelineContext {
     pub fn register_metrics<T: MetricSetHandler + Default + Debug + Send + Sync>(
         &self,
     ) -> MetricSet<T> {
-        use crate::attributes::ResourceAttributeSet;
-
         self.controller_context
             .metrics_registry_handle
             .register::<T>(NodeAttributeSet {
                 pipeline_attrs: PipelineAttributeSet {
                     engine_attrs: EngineAttributeSet {
-                        resource_attrs: ResourceAttributeSet {
-                            process_instance_id: self
-                                .controller_context
-                                .process_instance_id
-                                .clone(),
-                            host_id: self.controller_context.host_id.clone(),
-                            container_id: self.controller_context.container_id.clone(),
-                        },
+                        resource_attrs: self.controller_context.resource_attrs(),
                         core_id: self.core_id,
                         numa_node_id: self.controller_context.numa_node_id,
                     },
@@ -188,6 +212,32 @@ impl PipelineContext {
         self.controller_context.metrics_registry_handle.clone()
     }
 
+    /// Returns the identifier of the pipeline group of this pipeline.
+    #[must_use]
+    pub fn pipeline_group_id(&self) -> PipelineGroupId {
+        self.pipeline_group_id.clone()
+    }
+
+    /// Returns the identifier of this pipeline.
+    #[must_use]
+    pub fn pipeline_id(&self) -> PipelineId {
+        self.pipeline_id.clone()
+    }
+
+    /// Registers the current node in the health registry and returns the reporter used by the
+    /// node to update its health (see [`otap_df_state::health`]).
+    #[must_use]
+    pub fn node_health_reporter(&self) -> NodeHealthReporter {
+        let key = DeployedPipelineKey {
+            pipeline_group_id: self.pipeline_group_id.clone(),
+            pipeline_id: self.pipeline_id.clone(),
+            core_id: self.core_id,
+        };
+        self.controller_context
+            .health_registry
+            .reporter(&key, self.node_id.clone())
+    }
+
     /// Returns a new pipeline context with the given node identifiers.
     #[must_use]
     pub fn with_node_context(&self, node_id: NodeId, node_kind: NodeKind) -> Self {
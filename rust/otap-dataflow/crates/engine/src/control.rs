@@ -83,7 +83,57 @@ impl<PData> AckMsg<PData> {
     }
 }
 
+/// Class of the failure reported by a NACK.
+///
+/// The class lets the nodes receiving a NACK decide how to handle the
+/// refused pdata, e.g. a receiver choosing between the redelivery of a
+/// message and its move to a dead letter queue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NackClass {
+    /// The failure was not classified.
+    #[default]
+    Unknown,
+    /// A destination or a node is unavailable, e.g. an unreachable
+    /// endpoint or a closed channel.
+    Unavailable,
+    /// A destination or a node is throttling, e.g. a rate limit or a
+    /// full queue.
+    Throttled,
+    /// The deadline of the pdata expired before it was delivered.
+    DeadlineExceeded,
+    /// The pdata is malformed or was refused as invalid.
+    InvalidData,
+    /// The credentials were missing or refused.
+    Unauthorized,
+    /// A node failed to process the pdata.
+    Internal,
+}
+
+impl NackClass {
+    /// Returns the name of the class, e.g. for logs and message headers.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Unavailable => "unavailable",
+            Self::Throttled => "throttled",
+            Self::DeadlineExceeded => "deadline_exceeded",
+            Self::InvalidData => "invalid_data",
+            Self::Unauthorized => "unauthorized",
+            Self::Internal => "internal",
+        }
+    }
+}
+
 /// The NACK message.
+///
+/// A NACK describes the failure with a reason, a class, a retryable
+/// flag and the node that refused the pdata. These are set by the node
+/// creating the NACK and travel unchanged with it upstream: a node
+/// forwarding a NACK it received, e.g. a retry processor giving up,
+/// may extend the reason but keeps the other fields, so the receivers
+/// decide on the original failure. The engine sets the refusing node
+/// when the NACK is first routed.
 #[derive(Debug, Clone)]
 pub struct NackMsg<PData> {
     /// Human-readable reason for the NACK.
@@ -94,6 +144,17 @@ pub struct NackMsg<PData> {
 
     /// Refused pdata being returned.
     pub refused: Box<PData>,
+
+    /// True when retrying the refused pdata cannot succeed, e.g. the
+    /// destination rejected it as malformed. Retry components pass
+    /// permanent NACKs upstream without retrying.
+    pub permanent: bool,
+
+    /// Class of the failure.
+    pub class: NackClass,
+
+    /// Node that refused the pdata, set when the NACK is first routed.
+    pub node: Option<NodeId>,
 }
 
 impl<PData> NackMsg<PData> {
@@ -103,8 +164,34 @@ impl<PData> NackMsg<PData> {
             reason: reason.into(),
             calldata: smallvec![],
             refused: Box::new(refused),
+            permanent: false,
+            class: NackClass::Unknown,
+            node: None,
         }
     }
+
+    /// Creates a new NACK for a failure that should not be retried.
+    pub fn new_permanent<T: Into<String>>(reason: T, refused: PData) -> Self {
+        Self {
+            permanent: true,
+            ..Self::new(reason, refused)
+        }
+    }
+
+    /// Sets the class of the failure.
+    #[must_use]
+    pub fn with_class(self, class: NackClass) -> Self {
+        Self { class, ..self }
+    }
+
+    /// Returns true when retrying the refused pdata may succeed, i.e. the NACK is not permanent.
+    ///
+    /// Only the node creating the NACK decides whether it is permanent, e.g. following its
+    /// configured failure policy, the class describing the failure without overriding it.
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        !self.permanent
+    }
 }
 
 /// Control messages sent by the pipeline engine to nodes to manage their behavior,
@@ -477,3 +564,33 @@ where
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nack_is_retryable() {
+        assert!(NackMsg::new("unavailable", ()).is_retryable());
+        assert!(
+            NackMsg::new("throttled", ())
+                .with_class(NackClass::Throttled)
+                .is_retryable()
+        );
+        assert!(!NackMsg::new_permanent("rejected", ()).is_retryable());
+        assert!(
+            !NackMsg::new_permanent("rejected", ())
+                .with_class(NackClass::Unavailable)
+                .is_retryable()
+        );
+        // the class does not override the node that created the Nack
+        for class in [NackClass::InvalidData, NackClass::Unauthorized] {
+            assert!(NackMsg::new("refused", ()).with_class(class).is_retryable());
+            assert!(
+                !NackMsg::new_permanent("refused", ())
+                    .with_class(class)
+                    .is_retryable()
+            );
+        }
+    }
+}
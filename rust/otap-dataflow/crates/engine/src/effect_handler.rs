@@ -5,6 +5,10 @@
 
 use crate::control::{AckMsg, NackMsg, PipelineControlMsg, PipelineCtrlMsgSender};
 use crate::error::Error;
+use crate::error::TypedError;
+use crate::in_flight::InFlightTracker;
+use crate::latency::LatencyRecorder;
+use crate::memory::MemoryAccountant;
 use crate::node::NodeId;
 use otap_df_channel::error::SendError;
 use otap_df_telemetry::error::Error as TelemetryError;
@@ -25,6 +29,12 @@ pub(crate) struct EffectHandlerCore<PData> {
     #[allow(dead_code)]
     // Will be used in the future. ToDo report metrics from channel and messages.
     pub(crate) metrics_reporter: MetricsReporter,
+    /// Pdata in flight in the exporter owning this effect handler, completed by its Acks and Nacks.
+    pub(crate) in_flight: Option<InFlightTracker<PData>>,
+    /// Memory budget of the pipeline charged with the pdata sent by the node, if configured.
+    pub(crate) memory: Option<MemoryAccountant<PData>>,
+    /// Recorder of the processing latency of the node.
+    pub(crate) latency: Option<LatencyRecorder<PData>>,
 }
 
 impl<PData> EffectHandlerCore<PData> {
@@ -34,9 +44,52 @@ impl<PData> EffectHandlerCore<PData> {
             node_id,
             pipeline_ctrl_msg_sender: None,
             metrics_reporter,
+            in_flight: None,
+            memory: None,
+            latency: None,
         }
     }
 
+    /// Charges a pdata sent by a receiver against the memory budget of the pipeline, waiting for
+    /// memory or refusing the pdata depending on the budget policy (see [`crate::memory`]).
+    pub(crate) async fn admit(&self, data: PData) -> Result<PData, TypedError<PData>> {
+        let data = self.stamp(data);
+        match &self.memory {
+            Some(memory) => memory.admit(data).await,
+            None => Ok(data),
+        }
+    }
+
+    /// Charges a pdata sent by a receiver against the memory budget of the pipeline without
+    /// waiting.
+    pub(crate) fn try_admit(&self, data: PData) -> Result<PData, TypedError<PData>> {
+        let data = self.stamp(data);
+        match &self.memory {
+            Some(memory) => memory.try_admit(data),
+            None => Ok(data),
+        }
+    }
+
+    /// Charges a pdata sent by a processor with the current size of its payload, and records
+    /// its processing latency (see [`crate::latency`]).
+    pub(crate) fn recharge(&self, mut data: PData) -> PData {
+        if let Some(memory) = &self.memory {
+            memory.recharge(&mut data);
+        }
+        if let Some(latency) = &self.latency {
+            latency.forward(&mut data);
+        }
+        data
+    }
+
+    /// Stamps a pdata sent by a receiver with the instant it is enqueued.
+    fn stamp(&self, mut data: PData) -> PData {
+        if let Some(latency) = &self.latency {
+            latency.stamp(&mut data);
+        }
+        data
+    }
+
     /// Sets the pipeline control message sender for this effect handler.
     pub fn set_pipeline_ctrl_msg_sender(
         &mut self,
@@ -230,12 +283,16 @@ impl<PData> EffectHandlerCore<PData> {
     /// delivery with the recipient's calldata.
     pub async fn route_ack<Transfer>(
         &self,
-        ack_in: AckMsg<PData>,
+        mut ack_in: AckMsg<PData>,
         transfer: Transfer,
     ) -> Result<(), Error>
     where
         Transfer: FnOnce(AckMsg<PData>) -> Option<(usize, AckMsg<PData>)>,
     {
+        if !self.complete_in_flight(&ack_in.accepted) {
+            return Ok(());
+        }
+        self.complete_latency(&mut ack_in.accepted);
         if let Some((node_id, ack)) = transfer(ack_in) {
             self.send_pipeline_ctrl_msg(PipelineControlMsg::DeliverAck { node_id, ack })
                 .await
@@ -255,12 +312,20 @@ impl<PData> EffectHandlerCore<PData> {
     /// delivery with the recipient's calldata.
     pub async fn route_nack<Transfer>(
         &self,
-        nack_in: NackMsg<PData>,
+        mut nack_in: NackMsg<PData>,
         transfer: Transfer,
     ) -> Result<(), Error>
     where
         Transfer: FnOnce(NackMsg<PData>) -> Option<(usize, NackMsg<PData>)>,
     {
+        if !self.complete_in_flight(&nack_in.refused) {
+            return Ok(());
+        }
+        self.complete_latency(&mut nack_in.refused);
+        // The first node routing a NACK is the one refusing the pdata.
+        if nack_in.node.is_none() {
+            nack_in.node = Some(self.node_id());
+        }
         if let Some((node_id, nack)) = transfer(nack_in) {
             self.send_pipeline_ctrl_msg(PipelineControlMsg::DeliverNack { node_id, nack })
                 .await
@@ -273,6 +338,21 @@ impl<PData> EffectHandlerCore<PData> {
         }
     }
 
+    /// Completes a pdata in flight in the exporter owning this effect handler. Returns `false`
+    /// when its Ack or Nack must be dropped, the engine having Nacked it at its deadline.
+    fn complete_in_flight(&self, pdata: &PData) -> bool {
+        self.in_flight
+            .as_ref()
+            .is_none_or(|in_flight| in_flight.complete(pdata))
+    }
+
+    /// Records the processing latency of a pdata Acked or Nacked by the node.
+    fn complete_latency(&self, pdata: &mut PData) {
+        if let Some(latency) = &self.latency {
+            latency.complete(pdata);
+        }
+    }
+
     /// Delay a message.
     pub async fn delay_data(&self, when: Instant, data: Box<PData>) -> Result<(), PData> {
         self.send_pipeline_ctrl_msg(PipelineControlMsg::DelayData {
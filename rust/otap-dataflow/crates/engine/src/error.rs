@@ -139,6 +139,19 @@ pub enum TypedError<T> {
         error: SendError<T>,
     },
 
+    /// The memory budget of the pipeline is exceeded (see [`crate::memory`]).
+    #[error(
+        "The pipeline memory budget of {limit} bytes is exceeded ({requested} bytes requested)"
+    )]
+    MemoryBudgetExceeded {
+        /// Number of bytes requested for the data.
+        requested: usize,
+        /// Maximum number of bytes held by the data in flight.
+        limit: usize,
+        /// The refused data.
+        data: T,
+    },
+
     /// A type-less error in TypedError<T> context.
     #[error("{0}")]
     Error(Error),
@@ -160,6 +173,9 @@ impl<T: Sized> From<TypedError<T>> for Error {
                     error: error.to_string(),
                 }
             }
+            TypedError::MemoryBudgetExceeded {
+                requested, limit, ..
+            } => Error::MemoryBudgetExceeded { requested, limit },
             TypedError::Error(e) => e,
         }
     }
@@ -183,6 +199,17 @@ pub enum Error {
         error: String,
     },
 
+    /// The memory budget of the pipeline is exceeded.
+    #[error(
+        "The pipeline memory budget of {limit} bytes is exceeded ({requested} bytes requested)"
+    )]
+    MemoryBudgetExceeded {
+        /// Number of bytes requested for the data.
+        requested: usize,
+        /// Maximum number of bytes held by the data in flight.
+        limit: usize,
+    },
+
     /// A wrapper for the pipeline control message send errors.
     #[error("A control channel error occurred: {error}")]
     PipelineControlMsgError {
@@ -385,6 +412,7 @@ impl Error {
             Error::ConfigError(_) => "ConfigError",
             Error::ChannelRecvError(_) => "ChannelRecvError",
             Error::ChannelSendError { .. } => "ChannelSendError",
+            Error::MemoryBudgetExceeded { .. } => "MemoryBudgetExceeded",
             Error::PipelineControlMsgError { .. } => "PipelineControlMsgError",
             Error::NodeControlMsgSendError { .. } => "NodeControlMsgSendError",
             Error::InvalidHyperEdge { .. } => "InvalidHyperEdge",
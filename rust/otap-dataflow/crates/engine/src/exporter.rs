@@ -7,9 +7,12 @@
 //! For more details on the `!Send` implementation of an exporter, see [`local::Exporter`].
 //! See [`shared::Exporter`] for the Send implementation.
 
+use crate::channel_metrics::ChannelMetrics;
 use crate::config::ExporterConfig;
 use crate::control::{Controllable, NodeControlMsg, PipelineCtrlMsgSender};
 use crate::error::{Error, ExporterErrorKind};
+use crate::in_flight::InFlightTracker;
+use crate::latency::LatencyRecorder;
 use crate::local::exporter as local;
 use crate::local::message::{LocalReceiver, LocalSender};
 use crate::message;
@@ -17,10 +20,12 @@ use crate::message::{Receiver, Sender};
 use crate::node::{Node, NodeId, NodeWithPDataReceiver};
 use crate::shared::exporter as shared;
 use crate::shared::message::{SharedReceiver, SharedSender};
+use crate::shutdown::ShutdownTracker;
 use crate::terminal_state::TerminalState;
 use otap_df_channel::error::SendError;
 use otap_df_channel::mpsc;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_telemetry::metrics::MetricSet;
 use otap_df_telemetry::reporter::MetricsReporter;
 use std::sync::Arc;
 
@@ -43,6 +48,14 @@ pub enum ExporterWrapper<PData> {
         control_receiver: Option<LocalReceiver<NodeControlMsg<PData>>>,
         /// Receiver for PData messages.
         pdata_receiver: Option<Receiver<PData>>,
+        /// Tracker of the pipeline shutdown.
+        shutdown_tracker: ShutdownTracker,
+        /// Tracker of the Ack deadline of the pdata sent to the exporter, if configured.
+        in_flight: Option<InFlightTracker<PData>>,
+        /// Recorder of the processing latency of the exporter.
+        latency: Option<LatencyRecorder<PData>>,
+        /// Occupancy metrics of the pdata input channel of the exporter.
+        channel_metrics: Option<MetricSet<ChannelMetrics>>,
     },
     /// An exporter with a `Send` implementation.
     Shared {
@@ -58,6 +71,14 @@ pub enum ExporterWrapper<PData> {
         control_receiver: Option<SharedReceiver<NodeControlMsg<PData>>>,
         /// Receiver for PData messages.
         pdata_receiver: Option<SharedReceiver<PData>>,
+        /// Tracker of the pipeline shutdown.
+        shutdown_tracker: ShutdownTracker,
+        /// Tracker of the Ack deadline of the pdata sent to the exporter, if configured.
+        in_flight: Option<InFlightTracker<PData>>,
+        /// Recorder of the processing latency of the exporter.
+        latency: Option<LatencyRecorder<PData>>,
+        /// Occupancy metrics of the pdata input channel of the exporter.
+        channel_metrics: Option<MetricSet<ChannelMetrics>>,
     },
 }
 
@@ -96,6 +117,10 @@ impl<PData> ExporterWrapper<PData> {
             control_sender: LocalSender::MpscSender(control_sender),
             control_receiver: Some(LocalReceiver::MpscReceiver(control_receiver)),
             pdata_receiver: None, // This will be set later
+            shutdown_tracker: ShutdownTracker::default(),
+            in_flight: None,
+            latency: None,
+            channel_metrics: None,
         }
     }
 
@@ -120,6 +145,68 @@ impl<PData> ExporterWrapper<PData> {
             control_sender: SharedSender::MpscSender(control_sender),
             control_receiver: Some(SharedReceiver::MpscReceiver(control_receiver)),
             pdata_receiver: None, // This will be set later
+            shutdown_tracker: ShutdownTracker::default(),
+            in_flight: None,
+            latency: None,
+            channel_metrics: None,
+        }
+    }
+
+    /// Sets the tracker of the pipeline shutdown this exporter takes part in.
+    pub(crate) fn set_shutdown_tracker(&mut self, tracker: ShutdownTracker) {
+        match self {
+            ExporterWrapper::Local {
+                shutdown_tracker, ..
+            }
+            | ExporterWrapper::Shared {
+                shutdown_tracker, ..
+            } => *shutdown_tracker = tracker,
+        }
+    }
+
+    /// Sets the tracker of the Ack deadline of the pdata sent to this exporter.
+    pub(crate) fn set_in_flight_tracker(&mut self, tracker: InFlightTracker<PData>) {
+        match self {
+            ExporterWrapper::Local { in_flight, .. }
+            | ExporterWrapper::Shared { in_flight, .. } => *in_flight = Some(tracker),
+        }
+    }
+
+    /// Returns the tracker of the Ack deadline of the pdata sent to this exporter, if configured.
+    pub(crate) fn in_flight_tracker(&self) -> Option<InFlightTracker<PData>> {
+        match self {
+            ExporterWrapper::Local { in_flight, .. }
+            | ExporterWrapper::Shared { in_flight, .. } => in_flight.clone(),
+        }
+    }
+
+    /// Sets the recorder of the processing latency of this exporter.
+    pub(crate) fn set_latency_recorder(&mut self, recorder: LatencyRecorder<PData>) {
+        match self {
+            ExporterWrapper::Local { latency, .. } | ExporterWrapper::Shared { latency, .. } => {
+                *latency = Some(recorder)
+            }
+        }
+    }
+
+    /// Sets the occupancy metrics of the pdata input channel of this exporter.
+    pub(crate) fn set_channel_metrics(&mut self, metrics: MetricSet<ChannelMetrics>) {
+        match self {
+            ExporterWrapper::Local {
+                channel_metrics, ..
+            }
+            | ExporterWrapper::Shared {
+                channel_metrics, ..
+            } => *channel_metrics = Some(metrics),
+        }
+    }
+
+    /// Returns the recorder of the processing latency of this exporter, if configured.
+    pub(crate) fn latency_recorder(&self) -> Option<LatencyRecorder<PData>> {
+        match self {
+            ExporterWrapper::Local { latency, .. } | ExporterWrapper::Shared { latency, .. } => {
+                latency.clone()
+            }
         }
     }
 
@@ -128,6 +215,38 @@ impl<PData> ExporterWrapper<PData> {
         self,
         pipeline_ctrl_msg_tx: PipelineCtrlMsgSender<PData>,
         metrics_reporter: MetricsReporter,
+    ) -> Result<TerminalState, Error> {
+        let in_flight = self.in_flight_tracker();
+        self.run(
+            pipeline_ctrl_msg_tx,
+            metrics_reporter,
+            in_flight.clone(),
+            in_flight,
+        )
+        .await
+    }
+
+    /// Starts the exporter as a part of another exporter. The pdata it receives were stamped by
+    /// the other exporter, whose Ack deadline tracker is completed by the Acks and Nacks of this
+    /// exporter (see [`crate::in_flight`]).
+    pub(crate) async fn start_nested(
+        self,
+        pipeline_ctrl_msg_tx: PipelineCtrlMsgSender<PData>,
+        metrics_reporter: MetricsReporter,
+        in_flight: Option<InFlightTracker<PData>>,
+    ) -> Result<TerminalState, Error> {
+        self.run(pipeline_ctrl_msg_tx, metrics_reporter, None, in_flight)
+            .await
+    }
+
+    /// Runs the exporter, stamping the pdata it receives with the `stamping` tracker and
+    /// completing the `completing` tracker with its Acks and Nacks.
+    async fn run(
+        self,
+        pipeline_ctrl_msg_tx: PipelineCtrlMsgSender<PData>,
+        metrics_reporter: MetricsReporter,
+        stamping: Option<InFlightTracker<PData>>,
+        completing: Option<InFlightTracker<PData>>,
     ) -> Result<TerminalState, Error> {
         match (self, metrics_reporter) {
             (
@@ -136,10 +255,14 @@ impl<PData> ExporterWrapper<PData> {
                     exporter,
                     control_receiver,
                     pdata_receiver,
+                    shutdown_tracker,
+                    latency,
+                    channel_metrics,
                     ..
                 },
                 metrics_reporter,
             ) => {
+                let tracked_node_id = node_id.clone();
                 let mut effect_handler = local::EffectHandler::new(node_id, metrics_reporter);
                 let control_rx = control_receiver.ok_or_else(|| Error::ExporterError {
                     exporter: effect_handler.exporter_id(),
@@ -156,8 +279,17 @@ impl<PData> ExporterWrapper<PData> {
                 effect_handler
                     .core
                     .set_pipeline_ctrl_msg_sender(pipeline_ctrl_msg_tx);
-                let message_channel =
-                    message::MessageChannel::new(Receiver::Local(control_rx), pdata_rx);
+                effect_handler.core.in_flight = completing;
+                effect_handler.core.latency = latency;
+                let mut message_channel =
+                    message::MessageChannel::new(Receiver::Local(control_rx), pdata_rx)
+                        .with_shutdown_tracker(tracked_node_id, shutdown_tracker);
+                if let Some(tracker) = stamping {
+                    message_channel = message_channel.with_in_flight_tracker(tracker);
+                }
+                if let Some(metrics) = channel_metrics {
+                    message_channel = message_channel.with_channel_metrics(metrics);
+                }
                 exporter.start(message_channel, effect_handler).await
             }
             (
@@ -166,10 +298,14 @@ impl<PData> ExporterWrapper<PData> {
                     exporter,
                     control_receiver,
                     pdata_receiver,
+                    shutdown_tracker,
+                    latency,
+                    channel_metrics,
                     ..
                 },
                 metrics_reporter,
             ) => {
+                let tracked_node_id = node_id.clone();
                 let mut effect_handler = shared::EffectHandler::new(node_id, metrics_reporter);
                 let control_rx = control_receiver.ok_or_else(|| Error::ExporterError {
                     exporter: effect_handler.exporter_id(),
@@ -186,7 +322,16 @@ impl<PData> ExporterWrapper<PData> {
                 effect_handler
                     .core
                     .set_pipeline_ctrl_msg_sender(pipeline_ctrl_msg_tx);
-                let message_channel = shared::MessageChannel::new(control_rx, pdata_rx);
+                effect_handler.core.in_flight = completing;
+                effect_handler.core.latency = latency;
+                let mut message_channel = shared::MessageChannel::new(control_rx, pdata_rx)
+                    .with_shutdown_tracker(tracked_node_id, shutdown_tracker);
+                if let Some(tracker) = stamping {
+                    message_channel = message_channel.with_in_flight_tracker(tracker);
+                }
+                if let Some(metrics) = channel_metrics {
+                    message_channel = message_channel.with_channel_metrics(metrics);
+                }
                 exporter.start(message_channel, effect_handler).await
             }
         }
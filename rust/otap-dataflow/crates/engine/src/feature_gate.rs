@@ -0,0 +1,245 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Feature gates of the experimental components.
+//!
+//! An experimental node plugin or behavior is guarded by a [`FeatureGate`], registered in
+//! [`FEATURE_GATES`], so that it can ship in the binary while being disabled by default, and be
+//! enabled per deployment in the pipeline settings:
+//!
+//! ```yaml
+//! settings:
+//!   feature_gates:
+//!     exporter.geneva.traces: true
+//! ```
+//!
+//! The gates are process-wide: the controller applies the settings of the main pipeline once, at
+//! startup, and reports the state of every gate in the `feature_gate.metrics` metric set. A
+//! pipeline using a node plugin guarded by a disabled gate is rejected when it is built. Code
+//! paths guarded by a gate check [`FeatureGate::is_enabled`].
+
+use crate::attributes::FeatureGateAttributeSet;
+use crate::context::ControllerContext;
+use linkme::distributed_slice;
+use otap_df_config::error::Error;
+use otap_df_telemetry::instrument::Gauge;
+use otap_df_telemetry::reporter::MetricsReporter;
+use otap_df_telemetry_macros::metric_set;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Feature gates of the process, registered next to the components they guard.
+#[allow(unsafe_code)]
+#[distributed_slice]
+pub static FEATURE_GATES: [FeatureGate] = [..];
+
+/// Maturity of a feature gate, which defines whether it is enabled by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureGateStage {
+    /// Experimental, disabled by default.
+    Alpha,
+    /// Stabilizing, enabled by default but can still be disabled.
+    Beta,
+    /// Stable, always enabled. The gate is kept so that the configurations setting it stay valid.
+    Stable,
+}
+
+impl Display for FeatureGateStage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeatureGateStage::Alpha => write!(f, "alpha"),
+            FeatureGateStage::Beta => write!(f, "beta"),
+            FeatureGateStage::Stable => write!(f, "stable"),
+        }
+    }
+}
+
+/// A named switch guarding an experimental component.
+#[derive(Debug)]
+pub struct FeatureGate {
+    /// Unique identifier of the gate, e.g. `exporter.geneva.traces`.
+    pub id: &'static str,
+    /// What the gate enables.
+    pub description: &'static str,
+    /// Maturity of the gate.
+    pub stage: FeatureGateStage,
+    /// URN of the node plugin guarded by the gate, if any.
+    pub plugin_urn: Option<&'static str>,
+    enabled: AtomicBool,
+}
+
+impl FeatureGate {
+    /// Creates a gate in its default state for its stage.
+    #[must_use]
+    pub const fn new(id: &'static str, description: &'static str, stage: FeatureGateStage) -> Self {
+        Self {
+            id,
+            description,
+            stage,
+            plugin_urn: None,
+            enabled: AtomicBool::new(!matches!(stage, FeatureGateStage::Alpha)),
+        }
+    }
+
+    /// Makes the gate guard the node plugin `plugin_urn`.
+    #[must_use]
+    pub const fn for_plugin(mut self, plugin_urn: &'static str) -> Self {
+        self.plugin_urn = Some(plugin_urn);
+        self
+    }
+
+    /// Returns `true` if the gate is enabled.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+}
+
+/// State of a feature gate, as reported by [`states`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeatureGateState {
+    /// Identifier of the gate.
+    pub id: &'static str,
+    /// Maturity of the gate.
+    pub stage: FeatureGateStage,
+    /// Whether the gate is enabled.
+    pub enabled: bool,
+    /// What the gate enables.
+    pub description: &'static str,
+}
+
+/// Feature gate metrics, one set per gate.
+#[metric_set(name = "feature_gate.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct FeatureGateMetrics {
+    /// 1 if the gate is enabled, 0 otherwise.
+    #[metric(unit = "{gate}")]
+    pub enabled: Gauge<u64>,
+}
+
+/// Returns the gate registered with the identifier `id`, if any.
+#[must_use]
+pub fn find_gate(id: &str) -> Option<&'static FeatureGate> {
+    FEATURE_GATES.iter().find(|gate| gate.id == id)
+}
+
+/// Returns the gate guarding the node plugin `plugin_urn` if it is disabled.
+#[must_use]
+pub fn disabled_gate_for_plugin(plugin_urn: &str) -> Option<&'static FeatureGate> {
+    FEATURE_GATES
+        .iter()
+        .find(|gate| gate.plugin_urn == Some(plugin_urn) && !gate.is_enabled())
+}
+
+/// Enables or disables the gates listed in `settings`, by identifier. The gates not listed keep
+/// their state.
+///
+/// Nothing is changed if a gate is unknown or if a stable gate would be disabled, and all these
+/// errors are returned at once.
+pub fn apply(settings: &BTreeMap<String, bool>) -> Result<(), Vec<Error>> {
+    let mut gates = Vec::with_capacity(settings.len());
+    let mut errors = Vec::new();
+    for (id, &enabled) in settings {
+        match find_gate(id) {
+            None => errors.push(Error::InvalidUserConfig {
+                error: format!("unknown feature gate `{id}`"),
+            }),
+            Some(gate) if gate.stage == FeatureGateStage::Stable && !enabled => {
+                errors.push(Error::InvalidUserConfig {
+                    error: format!("feature gate `{id}` is stable and cannot be disabled"),
+                })
+            }
+            Some(gate) => gates.push((gate, enabled)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    for (gate, enabled) in gates {
+        gate.enabled.store(enabled, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// Returns the state of every gate, sorted by identifier.
+#[must_use]
+pub fn states() -> Vec<FeatureGateState> {
+    let mut states: Vec<_> = FEATURE_GATES
+        .iter()
+        .map(|gate| FeatureGateState {
+            id: gate.id,
+            stage: gate.stage,
+            enabled: gate.is_enabled(),
+            description: gate.description,
+        })
+        .collect();
+    states.sort_by_key(|state| state.id);
+    states
+}
+
+/// Reports the state of every gate in the `feature_gate.metrics` metric set.
+pub fn report(controller_ctx: &ControllerContext, metrics_reporter: &mut MetricsReporter) {
+    for state in states() {
+        let mut metrics =
+            controller_ctx.register_metrics::<FeatureGateMetrics>(FeatureGateAttributeSet {
+                resource_attrs: controller_ctx.resource_attrs(),
+                feature_gate_id: state.id.into(),
+                stage: state.stage.to_string().into(),
+            });
+        metrics.enabled.set(u64::from(state.enabled));
+        let _ = metrics_reporter.report(&mut metrics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(unsafe_code)]
+    #[distributed_slice(FEATURE_GATES)]
+    static TEST_ALPHA_GATE: FeatureGate =
+        FeatureGate::new("test.alpha", "Test alpha gate", FeatureGateStage::Alpha)
+            .for_plugin("urn:test:alpha:exporter");
+
+    #[allow(unsafe_code)]
+    #[distributed_slice(FEATURE_GATES)]
+    static TEST_STABLE_GATE: FeatureGate =
+        FeatureGate::new("test.stable", "Test stable gate", FeatureGateStage::Stable);
+
+    #[test]
+    fn test_apply() {
+        assert!(!TEST_ALPHA_GATE.is_enabled());
+        assert!(TEST_STABLE_GATE.is_enabled());
+        assert_eq!(
+            disabled_gate_for_plugin("urn:test:alpha:exporter").map(|gate| gate.id),
+            Some("test.alpha")
+        );
+
+        let settings = BTreeMap::from([
+            ("test.alpha".to_owned(), true),
+            ("test.stable".to_owned(), false),
+            ("test.unknown".to_owned(), true),
+        ]);
+        let errors = apply(&settings).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].to_string().contains("`test.stable` is stable"));
+        assert!(
+            errors[1]
+                .to_string()
+                .contains("unknown feature gate `test.unknown`")
+        );
+        assert!(!TEST_ALPHA_GATE.is_enabled());
+
+        apply(&BTreeMap::from([("test.alpha".to_owned(), true)])).unwrap();
+        assert!(TEST_ALPHA_GATE.is_enabled());
+        assert!(disabled_gate_for_plugin("urn:test:alpha:exporter").is_none());
+        assert!(
+            states()
+                .iter()
+                .any(|state| state.id == "test.alpha" && state.enabled)
+        );
+    }
+}
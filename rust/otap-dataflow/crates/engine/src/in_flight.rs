@@ -0,0 +1,317 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Ack deadline tracking of the pdata in flight in the exporters.
+//!
+//! An exporter configured with an `ack_deadline` is given an [`InFlightTracker`]:
+//!
+//! 1. Its message channel stamps every pdata delivered to the exporter with an id, recorded with
+//!    the deadline of the pdata.
+//! 2. Its effect handler completes the id when the exporter routes the Ack or Nack of the pdata.
+//! 3. The pipeline control manager sweeps the trackers every [`SWEEP_INTERVAL`]. A pdata still in
+//!    flight after its deadline, e.g. because its upload is stuck, is counted as timed out and
+//!    forgotten, so it no longer holds the in-flight accounting. When configured, it is also
+//!    Nacked upstream with [`NackClass::DeadlineExceeded`], and the Ack or Nack the exporter may
+//!    still send for it is dropped, its subscribers already having the outcome. The deadline
+//!    Nack is retryable, and returns the payload to the subscribers asking for it, e.g. a retry
+//!    processor sending it again.
+//!
+//! The engine does not know the structure of the pdata, the id is kept by the pdata itself (see
+//! [`InFlightData`]). A nested exporter (see `start_nested_exporter`) completes the ids stamped
+//! by the exporter running it.
+
+use crate::control::{NackClass, NackMsg};
+use crate::node::NodeId;
+use otap_df_config::node::AckDeadlineConfig;
+use otap_df_telemetry::instrument::{Counter, Gauge};
+use otap_df_telemetry::metrics::MetricSet;
+use otap_df_telemetry::reporter::MetricsReporter;
+use otap_df_telemetry_macros::metric_set;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Interval at which the pipeline control manager checks the deadlines of the pdata in flight.
+pub const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pdata whose Ack deadline can be tracked while in flight in an exporter.
+pub trait InFlightData: Sized {
+    /// Stamps the pdata with its in-flight id, replacing any previous id.
+    fn set_in_flight_id(&mut self, id: u64);
+
+    /// Returns the in-flight id stamped on the pdata, if any.
+    fn in_flight_id(&self) -> Option<u64>;
+
+    /// Returns the pdata to Nack at the deadline, or `None` if there is nobody to notify. It is
+    /// taken when the pdata is delivered to the exporter, and holds a copy of the payload if the
+    /// subscriber asked for the data returned, so that it can retry it, or only the context
+    /// otherwise.
+    fn deadline_nack_data(&self) -> Option<Self>;
+
+    /// Context transfer function of the Nacks sent at the deadline, see `route_nack`.
+    fn next_nack(nack: NackMsg<Self>) -> Option<(usize, NackMsg<Self>)>;
+}
+
+/// Ack deadline metrics of an exporter.
+#[metric_set(name = "exporter.in_flight.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct InFlightMetrics {
+    /// Number of pdata waiting for their Ack or Nack.
+    #[metric(unit = "{msg}")]
+    pub in_flight: Gauge<u64>,
+
+    /// Number of pdata neither acknowledged nor refused before their deadline.
+    #[metric(unit = "{msg}")]
+    pub ack_timeouts: Counter<u64>,
+
+    /// Number of pdata Nacked at their deadline.
+    #[metric(unit = "{msg}")]
+    pub deadline_nacks: Counter<u64>,
+}
+
+/// Pdata in flight in an exporter.
+///
+/// Cloning the tracker is cheap, all the clones refer to the same state.
+pub struct InFlightTracker<PData> {
+    node_id: NodeId,
+    inner: Arc<Mutex<InFlightState<PData>>>,
+    set_id: fn(&mut PData, u64),
+    get_id: fn(&PData) -> Option<u64>,
+    nack_data: fn(&PData) -> Option<PData>,
+    next_nack: fn(NackMsg<PData>) -> Option<(usize, NackMsg<PData>)>,
+}
+
+struct InFlightState<PData> {
+    timeout: Duration,
+    nack: bool,
+    next_id: u64,
+    /// Deadline of the pdata in flight, with the pdata to Nack at the deadline, by id. Ids are
+    /// allocated in time order and share the same timeout, so the map is ordered by deadline.
+    pending: BTreeMap<u64, (Instant, Option<PData>)>,
+    /// Ids Nacked at their deadline, whose Ack or Nack is dropped.
+    nacked: HashSet<u64>,
+    metrics: MetricSet<InFlightMetrics>,
+}
+
+impl<PData> Clone for InFlightTracker<PData> {
+    fn clone(&self) -> Self {
+        Self {
+            node_id: self.node_id.clone(),
+            inner: self.inner.clone(),
+            set_id: self.set_id,
+            get_id: self.get_id,
+            nack_data: self.nack_data,
+            next_nack: self.next_nack,
+        }
+    }
+}
+
+impl<PData: InFlightData> InFlightTracker<PData> {
+    /// Creates the tracker of the given exporter.
+    #[must_use]
+    pub fn new(
+        node_id: NodeId,
+        config: &AckDeadlineConfig,
+        metrics: MetricSet<InFlightMetrics>,
+    ) -> Self {
+        Self {
+            node_id,
+            inner: Arc::new(Mutex::new(InFlightState {
+                timeout: config.timeout,
+                nack: config.nack,
+                next_id: 0,
+                pending: BTreeMap::new(),
+                nacked: HashSet::new(),
+                metrics,
+            })),
+            set_id: PData::set_in_flight_id,
+            get_id: PData::in_flight_id,
+            nack_data: PData::deadline_nack_data,
+            next_nack: PData::next_nack,
+        }
+    }
+}
+
+impl<PData> InFlightTracker<PData> {
+    /// Stamps a pdata delivered to the exporter and starts its deadline.
+    pub(crate) fn track(&self, pdata: &mut PData) {
+        let mut state = self.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        (self.set_id)(pdata, id);
+        let nack_data = if state.nack {
+            (self.nack_data)(pdata)
+        } else {
+            None
+        };
+        let deadline = Instant::now() + state.timeout;
+        let _ = state.pending.insert(id, (deadline, nack_data));
+    }
+
+    /// Completes the pdata whose Ack or Nack is routed by the exporter. Returns `false` when the
+    /// Ack or Nack must be dropped, the pdata having been Nacked at its deadline.
+    pub(crate) fn complete(&self, pdata: &PData) -> bool {
+        let Some(id) = (self.get_id)(pdata) else {
+            return true;
+        };
+        let mut state = self.lock();
+        if state.pending.remove(&id).is_some() {
+            return true;
+        }
+        !state.nacked.remove(&id)
+    }
+
+    /// Forgets the pdata whose deadline passed at `now`, and returns the Nacks to deliver for
+    /// them with the id of their recipient.
+    pub(crate) fn expire(&self, now: Instant) -> Vec<(usize, NackMsg<PData>)> {
+        let mut state = self.lock();
+        let mut nacks = Vec::new();
+        while let Some(entry) = state.pending.first_entry() {
+            if entry.get().0 > now {
+                break;
+            }
+            let (id, (_, nack_data)) = entry.remove_entry();
+            state.metrics.ack_timeouts.inc();
+            let Some(data) = nack_data else {
+                continue;
+            };
+            let mut nack = NackMsg::new(format!("no Ack or Nack within {:?}", state.timeout), data)
+                .with_class(NackClass::DeadlineExceeded);
+            nack.node = Some(self.node_id.clone());
+            if let Some(routed) = (self.next_nack)(nack) {
+                let _ = state.nacked.insert(id);
+                state.metrics.deadline_nacks.inc();
+                nacks.push(routed);
+            }
+        }
+        nacks
+    }
+
+    /// Returns the number of pdata in flight.
+    #[must_use]
+    pub fn in_flight(&self) -> usize {
+        self.lock().pending.len()
+    }
+
+    /// Reports the metrics of the tracker.
+    pub(crate) fn report(&self, metrics_reporter: &mut MetricsReporter) {
+        let mut state = self.lock();
+        let in_flight = state.pending.len() as u64;
+        state.metrics.in_flight.set(in_flight);
+        let _ = metrics_reporter.report(&mut state.metrics);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, InFlightState<PData>> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otap_df_telemetry::testing::EmptyAttributes;
+
+    /// A pdata routing its Nacks to the node of its subscriber, if any.
+    #[derive(Debug, Clone, Default)]
+    struct Data {
+        id: Option<u64>,
+        subscriber: Option<usize>,
+    }
+
+    impl InFlightData for Data {
+        fn set_in_flight_id(&mut self, id: u64) {
+            self.id = Some(id);
+        }
+
+        fn in_flight_id(&self) -> Option<u64> {
+            self.id
+        }
+
+        fn deadline_nack_data(&self) -> Option<Self> {
+            self.subscriber.map(|_| self.clone())
+        }
+
+        fn next_nack(nack: NackMsg<Self>) -> Option<(usize, NackMsg<Self>)> {
+            nack.refused.subscriber.map(|node_id| (node_id, nack))
+        }
+    }
+
+    fn tracker(timeout: Duration, nack: bool) -> InFlightTracker<Data> {
+        let metrics = MetricsRegistryHandle::new().register::<InFlightMetrics>(EmptyAttributes());
+        InFlightTracker::new(
+            NodeId {
+                index: 3,
+                name: "exporter".into(),
+            },
+            &AckDeadlineConfig { timeout, nack },
+            metrics,
+        )
+    }
+
+    #[test]
+    fn test_complete() {
+        let tracker = tracker(Duration::from_secs(30), false);
+        let mut first = Data::default();
+        let mut second = Data::default();
+        tracker.track(&mut first);
+        tracker.track(&mut second);
+        assert_ne!(first.id, second.id);
+        assert_eq!(tracker.in_flight(), 2);
+
+        // completed out of order, once
+        assert!(tracker.complete(&second));
+        assert!(tracker.complete(&second));
+        assert!(tracker.complete(&Data::default()));
+        assert_eq!(tracker.in_flight(), 1);
+        assert!(tracker.expire(Instant::now()).is_empty());
+        assert!(tracker.complete(&first));
+        assert_eq!(tracker.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_expire() {
+        let tracker = tracker(Duration::from_secs(30), false);
+        let mut stuck = Data {
+            subscriber: Some(1),
+            ..Default::default()
+        };
+        tracker.track(&mut stuck);
+        let later = Instant::now() + Duration::from_secs(31);
+
+        // counted as timed out without Nack, a late Ack is still routed
+        assert!(tracker.expire(later).is_empty());
+        assert_eq!(tracker.in_flight(), 0);
+        assert_eq!(tracker.lock().metrics.ack_timeouts.get(), 1);
+        assert!(tracker.complete(&stuck));
+    }
+
+    #[test]
+    fn test_expire_with_nack() {
+        let tracker = tracker(Duration::from_secs(30), true);
+        let mut stuck = Data {
+            subscriber: Some(1),
+            ..Default::default()
+        };
+        let mut unsubscribed = Data::default();
+        tracker.track(&mut stuck);
+        tracker.track(&mut unsubscribed);
+
+        let nacks = tracker.expire(Instant::now() + Duration::from_secs(31));
+        assert_eq!(nacks.len(), 1);
+        let (node_id, nack) = &nacks[0];
+        assert_eq!(*node_id, 1);
+        assert_eq!(nack.class, NackClass::DeadlineExceeded);
+        assert_eq!(nack.node.as_ref().map(|node| node.index), Some(3));
+        assert_eq!(tracker.in_flight(), 0);
+        assert_eq!(tracker.lock().metrics.ack_timeouts.get(), 2);
+        assert_eq!(tracker.lock().metrics.deadline_nacks.get(), 1);
+
+        // the late outcome of the Nacked pdata is dropped once
+        assert!(!tracker.complete(&stuck));
+        assert!(tracker.complete(&stuck));
+        assert!(tracker.complete(&unsubscribed));
+    }
+}
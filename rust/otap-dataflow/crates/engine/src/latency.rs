@@ -0,0 +1,196 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Processing latency of the processors and exporters of a pipeline.
+//!
+//! Every node is given a [`LatencyRecorder`] by the engine, and the pdata carry the instant they
+//! were enqueued in the input channel of their current node (see [`LatencyTrackedData`]):
+//!
+//! 1. The effect handler of a receiver stamps every pdata it sends.
+//! 2. The effect handler of a processor records the time elapsed since the previous stamp of
+//!    every pdata it sends, then stamps it again for the next node.
+//! 3. The effect handler of the node Acking or Nacking a pdata, usually an exporter, records the
+//!    time elapsed since its stamp and clears it, so that the nodes the Ack or Nack is routed to
+//!    do not record it again.
+//!
+//! The latency of a node therefore covers the wait in its input channel, its processing, and the
+//! wait for capacity in its output channel. A node holding the pdata until they are Acked
+//! downstream, e.g. a batch processor Acking its inputs once their batch is exported, includes
+//! the downstream latency in its own.
+
+use otap_df_telemetry::instrument::Histogram;
+use otap_df_telemetry::metrics::MetricSet;
+use otap_df_telemetry::reporter::MetricsReporter;
+use otap_df_telemetry_macros::metric_set;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Pdata carrying the instant they were enqueued in the input channel of their current node.
+pub trait LatencyTrackedData {
+    /// Returns the instant the pdata was enqueued, if stamped.
+    fn enqueued_at(&self) -> Option<Instant>;
+
+    /// Stamps the pdata with the instant it was enqueued, or clears the stamp.
+    fn set_enqueued_at(&mut self, at: Option<Instant>);
+}
+
+/// Processing latency metrics of a node.
+#[metric_set(name = "node.latency.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct NodeLatencyMetrics {
+    /// Time elapsed between the enqueuing of a pdata in the input channel of the node and its
+    /// forwarding, Ack or Nack.
+    #[metric(unit = "us")]
+    pub processing_duration: Histogram<u64>,
+}
+
+/// Stamps the pdata sent by a node and records its processing latency.
+pub struct LatencyRecorder<PData> {
+    /// Latency histogram of the node, `None` for the receivers which only stamp their pdata.
+    metrics: Option<Arc<Mutex<MetricSet<NodeLatencyMetrics>>>>,
+    enqueued_at: fn(&PData) -> Option<Instant>,
+    set_enqueued_at: fn(&mut PData, Option<Instant>),
+}
+
+impl<PData> Clone for LatencyRecorder<PData> {
+    fn clone(&self) -> Self {
+        Self {
+            metrics: self.metrics.clone(),
+            enqueued_at: self.enqueued_at,
+            set_enqueued_at: self.set_enqueued_at,
+        }
+    }
+}
+
+impl<PData: LatencyTrackedData> LatencyRecorder<PData> {
+    /// Creates a recorder of the processing latency of a node.
+    #[must_use]
+    pub fn new(metrics: MetricSet<NodeLatencyMetrics>) -> Self {
+        Self {
+            metrics: Some(Arc::new(Mutex::new(metrics))),
+            enqueued_at: PData::enqueued_at,
+            set_enqueued_at: PData::set_enqueued_at,
+        }
+    }
+
+    /// Creates a recorder only stamping the pdata sent by a receiver.
+    #[must_use]
+    pub fn stamping() -> Self {
+        Self {
+            metrics: None,
+            enqueued_at: PData::enqueued_at,
+            set_enqueued_at: PData::set_enqueued_at,
+        }
+    }
+}
+
+impl<PData> LatencyRecorder<PData> {
+    /// Stamps a pdata sent by a receiver.
+    pub(crate) fn stamp(&self, pdata: &mut PData) {
+        (self.set_enqueued_at)(pdata, Some(Instant::now()));
+    }
+
+    /// Records the latency of a pdata sent by a processor, and stamps it for the next node.
+    pub(crate) fn forward(&self, pdata: &mut PData) {
+        let now = Instant::now();
+        if let Some(enqueued_at) = (self.enqueued_at)(pdata) {
+            self.record(now, enqueued_at);
+        }
+        (self.set_enqueued_at)(pdata, Some(now));
+    }
+
+    /// Records the latency of a pdata Acked or Nacked by the node, and clears its stamp.
+    pub(crate) fn complete(&self, pdata: &mut PData) {
+        if let Some(enqueued_at) = (self.enqueued_at)(pdata) {
+            self.record(Instant::now(), enqueued_at);
+            (self.set_enqueued_at)(pdata, None);
+        }
+    }
+
+    /// Reports the latency histogram of the node.
+    pub(crate) fn report(&self, metrics_reporter: &mut MetricsReporter) {
+        if let Some(metrics) = &self.metrics {
+            let mut metrics = metrics
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let _ = metrics_reporter.report(&mut metrics);
+        }
+    }
+
+    fn record(&self, now: Instant, enqueued_at: Instant) {
+        if let Some(metrics) = &self.metrics {
+            let micros = now.saturating_duration_since(enqueued_at).as_micros();
+            metrics
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .processing_duration
+                .record(u64::try_from(micros).unwrap_or(u64::MAX));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otap_df_telemetry::testing::EmptyAttributes;
+    use std::time::Duration;
+
+    #[derive(Debug, Default)]
+    struct Data {
+        enqueued_at: Option<Instant>,
+    }
+
+    impl LatencyTrackedData for Data {
+        fn enqueued_at(&self) -> Option<Instant> {
+            self.enqueued_at
+        }
+
+        fn set_enqueued_at(&mut self, at: Option<Instant>) {
+            self.enqueued_at = at;
+        }
+    }
+
+    fn recorder() -> LatencyRecorder<Data> {
+        let metrics =
+            MetricsRegistryHandle::new().register::<NodeLatencyMetrics>(EmptyAttributes());
+        LatencyRecorder::new(metrics)
+    }
+
+    fn recorded(recorder: &LatencyRecorder<Data>) -> (u64, u64) {
+        let metrics = recorder.metrics.as_ref().unwrap().lock().unwrap();
+        (
+            metrics.processing_duration.count(),
+            metrics.processing_duration.sum(),
+        )
+    }
+
+    #[test]
+    fn test_latency_recording() {
+        let receiver = LatencyRecorder::<Data>::stamping();
+        let processor = recorder();
+        let exporter = recorder();
+
+        // unstamped pdata, e.g. created by a processor, are only stamped
+        let mut pdata = Data::default();
+        processor.forward(&mut pdata);
+        assert!(pdata.enqueued_at.is_some());
+        assert_eq!(recorded(&processor), (0, 0));
+
+        let mut pdata = Data::default();
+        receiver.stamp(&mut pdata);
+        pdata.enqueued_at = pdata.enqueued_at.map(|at| at - Duration::from_millis(5));
+        processor.forward(&mut pdata);
+        let (count, sum) = recorded(&processor);
+        assert_eq!(count, 1);
+        assert!(sum >= 5_000);
+
+        exporter.complete(&mut pdata);
+        assert_eq!(recorded(&exporter).0, 1);
+        assert!(pdata.enqueued_at.is_none());
+
+        // the nodes the Ack is routed to do not record it again
+        processor.complete(&mut pdata);
+        assert_eq!(recorded(&processor).0, 1);
+    }
+}
@@ -2,13 +2,43 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Async Pipeline Engine
+//!
+//! # Backpressure
+//!
+//! All the channels connecting the nodes of a pipeline are bounded. A slow exporter fills its
+//! input channel, which in turn blocks the processors feeding it, and so on up to the receivers.
+//! The engine never buffers data beyond the configured channel capacities; the contract for the
+//! nodes is the following:
+//!
+//! - `send_message` and `send_message_to` wait for channel capacity and never drop data. A node
+//!   awaiting a send stops pulling from its own input, propagating the pressure upstream.
+//! - Nodes that must stay responsive while a channel is full use the `try_send_*` variants, which
+//!   hand the message back with [`otap_df_channel::error::SendError::Full`], and stop reading from
+//!   their source until the message could be sent (e.g. the Kafka receiver pauses its partitions).
+//! - Receivers serving push-based protocols await the send inside the request handler, so that
+//!   the transport flow control (e.g. HTTP/2 windows for gRPC) slows the clients down.
+//! - With a `memory_budget` in the pipeline settings, receivers also wait for, or are refused,
+//!   memory when the pdata in flight exceed the budget (see [`memory`]).
+//!
+//! The processing latency of every processor and exporter is recorded in a histogram, and the
+//! occupancy of their input channels in gauges, so that the bottleneck of a backpressured
+//! pipeline can be identified (see [`latency`] and [`channel_metrics`]).
+//!
+//! The secrets referenced by the node configurations are resolved each time a node is created,
+//! so a pipeline rebuilt on a configuration reload picks up the rotated secrets. Beforehand, the
+//! node configurations are validated against the schemas registered in [`NODE_CONFIG_SCHEMAS`],
+//! and the nodes whose plugin is guarded by a disabled feature gate are rejected (see
+//! [`feature_gate`]).
 
 use crate::{
     config::{ExporterConfig, ProcessorConfig, ReceiverConfig},
     control::{AckMsg, CallData, NackMsg},
     error::Error,
     exporter::ExporterWrapper,
+    in_flight::{InFlightData, InFlightTracker},
+    latency::{LatencyRecorder, LatencyTrackedData},
     local::message::{LocalReceiver, LocalSender},
+    memory::{MemoryAccountant, MemoryAccountedData, MemoryBudget},
     message::{Receiver, Sender},
     node::{Node, NodeDefs, NodeId, NodeName, NodeType},
     processor::ProcessorWrapper,
@@ -23,6 +53,7 @@ use otap_df_config::{
     PortName,
     node::{DispatchStrategy, NodeUserConfig},
     pipeline::PipelineConfig,
+    schema::NodeConfigSchema,
 };
 use std::fmt::Debug;
 use std::num::NonZeroUsize;
@@ -36,17 +67,29 @@ pub mod processor;
 pub mod receiver;
 
 mod attributes;
+pub mod channel_metrics;
 pub mod config;
 pub mod context;
 pub mod control;
 mod effect_handler;
+pub mod feature_gate;
+pub mod in_flight;
+pub mod latency;
 pub mod local;
+pub mod memory;
 pub mod node;
 pub mod pipeline_ctrl;
 pub mod runtime_pipeline;
 pub mod shared;
+pub mod shutdown;
 pub mod terminal_state;
 pub mod testing;
+pub mod topology;
+
+/// Schemas of the configurations of the node plugins, registered next to their factories.
+#[allow(unsafe_code)]
+#[distributed_slice]
+pub static NODE_CONFIG_SCHEMAS: [NodeConfigSchema] = [..];
 
 /// Trait for factory types that expose a name.
 ///
@@ -290,7 +333,10 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
         self: &PipelineFactory<PData>,
         pipeline_ctx: PipelineContext,
         config: PipelineConfig,
-    ) -> Result<RuntimePipeline<PData>, Error> {
+    ) -> Result<RuntimePipeline<PData>, Error>
+    where
+        PData: InFlightData + MemoryAccountedData + LatencyTrackedData,
+    {
         let mut receivers = Vec::new();
         let mut processors = Vec::new();
         let mut exporters = Vec::new();
@@ -299,6 +345,30 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
         let mut exporter_names = HashMap::new();
         let mut nodes = NodeDefs::default();
 
+        // Validate the configuration of every node first, to report all the invalid nodes at once.
+        let errors: Vec<_> = config
+            .node_iter()
+            .filter_map(|(name, node_config)| {
+                if let Some(gate) = feature_gate::disabled_gate_for_plugin(&node_config.plugin_urn)
+                {
+                    return Some(otap_df_config::error::Error::InvalidUserConfig {
+                        error: format!(
+                            "node `{name}`: plugin `{}` requires the feature gate `{}`",
+                            node_config.plugin_urn, gate.id
+                        ),
+                    });
+                }
+                otap_df_config::schema::find_schema(&NODE_CONFIG_SCHEMAS, &node_config.plugin_urn)?
+                    .validate_node(name, &node_config.config)
+                    .err()
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(Error::ConfigError(Box::new(
+                otap_df_config::error::Error::InvalidConfiguration { errors },
+            )));
+        }
+
         // Create runtime nodes based on the pipeline configuration.
         // ToDo(LQ): Collect all errors instead of failing fast to provide better feedback.
         for (name, node_config) in config.node_iter() {
@@ -340,7 +410,24 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
 
         let edges = collect_hyper_edges_runtime(&receivers, &processors);
 
-        let mut pipeline = RuntimePipeline::new(config, receivers, processors, exporters, nodes);
+        // Charge the pdata sent by the receivers and processors against the memory budget.
+        let memory_budget = config
+            .pipeline_settings()
+            .memory_budget
+            .as_ref()
+            .map(|memory_budget| MemoryBudget::new(memory_budget, pipeline_ctx.register_metrics()));
+        if let Some(memory_budget) = &memory_budget {
+            let accountant = MemoryAccountant::new(memory_budget.clone());
+            for receiver in &mut receivers {
+                receiver.set_memory_accountant(accountant.clone());
+            }
+            for processor in &mut processors {
+                processor.set_memory_accountant(accountant.clone());
+            }
+        }
+
+        let mut pipeline = RuntimePipeline::new(config, receivers, processors, exporters, nodes)
+            .with_memory_budget(memory_budget);
 
         // First pass: collect all channel assignments to avoid multiple mutable borrows
         struct ChannelAssignment<PData> {
@@ -494,7 +581,10 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
         receivers: &mut Vec<ReceiverWrapper<PData>>,
         name: NodeName,
         node_config: Arc<NodeUserConfig>,
-    ) -> Result<(), Error> {
+    ) -> Result<(), Error>
+    where
+        PData: LatencyTrackedData,
+    {
         // Validate plugin URN structure during registration
         otap_df_config::urn::validate_plugin_urn(
             node_config.plugin_urn.as_ref(),
@@ -510,6 +600,7 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
             })?;
         let runtime_config = ReceiverConfig::new(name.clone());
         let create = factory.create;
+        let node_config = resolve_secrets(&name, node_config)?;
 
         let node_id = nodes.next(
             name.clone(),
@@ -520,10 +611,10 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
             return Err(Error::ReceiverAlreadyExists { receiver: node_id });
         }
 
-        receivers.push(
-            create(pipeline_ctx, node_id, node_config, &runtime_config)
-                .map_err(|e| Error::ConfigError(Box::new(e)))?,
-        );
+        let mut receiver = create(pipeline_ctx, node_id, node_config, &runtime_config)
+            .map_err(|e| Error::ConfigError(Box::new(e)))?;
+        receiver.set_latency_recorder(LatencyRecorder::stamping());
+        receivers.push(receiver);
         Ok(())
     }
 
@@ -536,7 +627,10 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
         processors: &mut Vec<ProcessorWrapper<PData>>,
         name: NodeName,
         node_config: Arc<NodeUserConfig>,
-    ) -> Result<(), Error> {
+    ) -> Result<(), Error>
+    where
+        PData: LatencyTrackedData,
+    {
         // Validate plugin URN structure during registration
         otap_df_config::urn::validate_plugin_urn(
             node_config.plugin_urn.as_ref(),
@@ -552,6 +646,7 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
             })?;
         let processor_config = ProcessorConfig::new(name.clone());
         let create = factory.create;
+        let node_config = resolve_secrets(&name, node_config)?;
 
         let node_id = nodes.next(
             name.clone(),
@@ -561,15 +656,18 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
         if names.insert(name.clone(), node_id.clone()).is_some() {
             return Err(Error::ProcessorAlreadyExists { processor: node_id });
         }
-        processors.push(
-            create(
-                pipeline_ctx,
-                node_id,
-                node_config.clone(),
-                &processor_config,
-            )
-            .map_err(|e| Error::ConfigError(Box::new(e)))?,
-        );
+        let latency = LatencyRecorder::new(pipeline_ctx.register_metrics());
+        let channel_metrics = pipeline_ctx.register_metrics();
+        let mut processor = create(
+            pipeline_ctx,
+            node_id,
+            node_config.clone(),
+            &processor_config,
+        )
+        .map_err(|e| Error::ConfigError(Box::new(e)))?;
+        processor.set_latency_recorder(latency);
+        processor.set_channel_metrics(channel_metrics);
+        processors.push(processor);
 
         Ok(())
     }
@@ -583,7 +681,10 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
         exporters: &mut Vec<ExporterWrapper<PData>>,
         name: NodeName,
         node_config: Arc<NodeUserConfig>,
-    ) -> Result<(), Error> {
+    ) -> Result<(), Error>
+    where
+        PData: InFlightData + LatencyTrackedData,
+    {
         // Validate plugin URN structure during registration
         otap_df_config::urn::validate_plugin_urn(
             node_config.plugin_urn.as_ref(),
@@ -599,6 +700,7 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
             })?;
         let exporter_config = ExporterConfig::new(name.clone());
         let create = factory.create;
+        let node_config = resolve_secrets(&name, node_config)?;
 
         let node_id = nodes.next(
             name.clone(),
@@ -609,10 +711,23 @@ impl<PData: 'static + Clone + Debug> PipelineFactory<PData> {
         if names.insert(name.clone(), node_id.clone()).is_some() {
             return Err(Error::ExporterAlreadyExists { exporter: node_id });
         }
-        exporters.push(
-            create(pipeline_ctx, node_id, node_config, &exporter_config)
-                .map_err(|e| Error::ConfigError(Box::new(e)))?,
-        );
+        let in_flight = node_config.ack_deadline.as_ref().map(|ack_deadline| {
+            InFlightTracker::new(
+                node_id.clone(),
+                ack_deadline,
+                pipeline_ctx.register_metrics(),
+            )
+        });
+        let latency = LatencyRecorder::new(pipeline_ctx.register_metrics());
+        let channel_metrics = pipeline_ctx.register_metrics();
+        let mut exporter = create(pipeline_ctx, node_id, node_config, &exporter_config)
+            .map_err(|e| Error::ConfigError(Box::new(e)))?;
+        if let Some(tracker) = in_flight {
+            exporter.set_in_flight_tracker(tracker);
+        }
+        exporter.set_latency_recorder(latency);
+        exporter.set_channel_metrics(channel_metrics);
+        exporters.push(exporter);
         Ok(())
     }
 }
@@ -632,6 +747,31 @@ struct HyperEdgeRuntime {
     destinations: Vec<NodeName>,
 }
 
+/// Returns the configuration of a node with its secret references resolved, see
+/// [`otap_df_config::secret`]. The pipeline configuration keeps the references.
+fn resolve_secrets(
+    name: &NodeName,
+    node_config: Arc<NodeUserConfig>,
+) -> Result<Arc<NodeUserConfig>, Error> {
+    if !otap_df_config::secret::has_secrets(&node_config.config) {
+        return Ok(node_config);
+    }
+    let config =
+        otap_df_config::secret::resolve_secrets(&node_config.config).map_err(|details| {
+            Error::ConfigError(Box::new(
+                otap_df_config::error::Error::SecretResolutionError {
+                    context: Default::default(),
+                    node_id: name.clone(),
+                    details,
+                },
+            ))
+        })?;
+    Ok(Arc::new(NodeUserConfig {
+        config,
+        ..(*node_config).clone()
+    }))
+}
+
 /// Returns a vector of all hyper-edges in the runtime graph.
 ///
 /// Each item represents a hyper-edge with source node id, port, dispatch strategy, and destination
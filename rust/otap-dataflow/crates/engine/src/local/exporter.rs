@@ -35,7 +35,8 @@
 
 use crate::control::{AckMsg, NackMsg};
 use crate::effect_handler::{EffectHandlerCore, TelemetryTimerCancelHandle, TimerCancelHandle};
-use crate::error::Error;
+use crate::error::{Error, ExporterErrorKind};
+use crate::exporter::ExporterWrapper;
 use crate::message::MessageChannel;
 use crate::node::NodeId;
 use crate::terminal_state::TerminalState;
@@ -157,6 +158,36 @@ impl<PData> EffectHandler<PData> {
         self.core.route_nack(nack, cxf).await
     }
 
+    /// Runs `exporter` as a part of this exporter, e.g. one of the backends of a load balancing
+    /// exporter, until it stops.
+    ///
+    /// The nested exporter shares the pipeline control channel, the metrics reporter and the Ack
+    /// deadline tracker of this exporter, so its Acks and Nacks are delivered to the subscribers
+    /// of the pdata it receives. Its control messages, including the shutdown, must be forwarded
+    /// by this exporter.
+    pub async fn start_nested_exporter(
+        &self,
+        exporter: ExporterWrapper<PData>,
+    ) -> Result<TerminalState, Error> {
+        let pipeline_ctrl_msg_tx =
+            self.core
+                .pipeline_ctrl_msg_sender
+                .clone()
+                .ok_or_else(|| Error::ExporterError {
+                    exporter: self.exporter_id(),
+                    kind: ExporterErrorKind::Configuration,
+                    error: "Pipeline control sender not initialized".to_owned(),
+                    source_detail: String::new(),
+                })?;
+        exporter
+            .start_nested(
+                pipeline_ctrl_msg_tx,
+                self.core.metrics_reporter.clone(),
+                self.core.in_flight.clone(),
+            )
+            .await
+    }
+
     /// Reports metrics collected by the exporter.
     #[allow(dead_code)] // Will be used in the future. ToDo report metrics from channel and messages.
     pub(crate) fn report_metrics<M: MetricSetHandler + 'static>(
@@ -75,4 +75,22 @@ impl<T> LocalReceiver<T> {
             LocalReceiver::MpmcReceiver(receiver) => receiver.is_empty(),
         }
     }
+
+    /// Returns the number of messages waiting in the channel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            LocalReceiver::MpscReceiver(receiver) => receiver.len(),
+            LocalReceiver::MpmcReceiver(receiver) => receiver.len(),
+        }
+    }
+
+    /// Returns the maximum number of messages the channel can hold.
+    #[must_use]
+    pub fn capacity(&self) -> Option<usize> {
+        match self {
+            LocalReceiver::MpscReceiver(receiver) => Some(receiver.capacity()),
+            LocalReceiver::MpmcReceiver(receiver) => Some(receiver.capacity()),
+        }
+    }
 }
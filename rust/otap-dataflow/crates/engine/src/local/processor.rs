@@ -152,7 +152,7 @@ impl<PData> EffectHandler<PData> {
     pub async fn send_message(&self, data: PData) -> Result<(), TypedError<PData>> {
         match &self.default_sender {
             Some(sender) => sender
-                .send(data)
+                .send(self.core.recharge(data))
                 .await
                 .map_err(TypedError::ChannelSendError),
             None => Err(TypedError::Error(Error::ProcessorError {
@@ -180,7 +180,7 @@ impl<PData> EffectHandler<PData> {
         let port_name: PortName = port.into();
         match self.msg_senders.get(&port_name) {
             Some(sender) => sender
-                .send(data)
+                .send(self.core.recharge(data))
                 .await
                 .map_err(TypedError::ChannelSendError),
             None => Err(TypedError::Error(Error::ProcessorError {
@@ -195,6 +195,34 @@ impl<PData> EffectHandler<PData> {
         }
     }
 
+    /// Attempts to send a message to a specific named out port without awaiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::ChannelSendError`] holding the message back if the channel is full or
+    /// closed, or [`Error::ProcessorError`] if the port does not exist.
+    #[inline]
+    pub fn try_send_message_to<P>(&self, port: P, data: PData) -> Result<(), TypedError<PData>>
+    where
+        P: Into<PortName>,
+    {
+        let port_name: PortName = port.into();
+        match self.msg_senders.get(&port_name) {
+            Some(sender) => sender
+                .try_send(self.core.recharge(data))
+                .map_err(TypedError::ChannelSendError),
+            None => Err(TypedError::Error(Error::ProcessorError {
+                processor: self.processor_id(),
+                kind: ProcessorErrorKind::Configuration,
+                error: format!(
+                    "Unknown out port '{port_name}' for node {}",
+                    self.processor_id()
+                ),
+                source_detail: String::new(),
+            })),
+        }
+    }
+
     /// Print an info message to stdout.
     ///
     /// This method provides a standardized way for processors to output
@@ -261,6 +289,7 @@ mod tests {
     use super::*;
     use crate::local::message::LocalSender;
     use crate::testing::test_node;
+    use otap_df_channel::error::SendError;
     use otap_df_channel::mpsc;
     use std::borrow::Cow;
     use std::collections::{HashMap, HashSet};
@@ -292,6 +321,26 @@ mod tests {
         assert_eq!(b_rx.recv().await.unwrap(), 42);
     }
 
+    #[tokio::test]
+    async fn effect_handler_try_send_message_to_full_port() {
+        let (tx, rx) = channel::<u64>(1);
+
+        let mut senders = HashMap::new();
+        let _ = senders.insert("a".into(), LocalSender::MpscSender(tx));
+
+        let (_metrics_rx, metrics_reporter) = MetricsReporter::create_new_and_receiver(1);
+        let eh = EffectHandler::new(test_node("proc"), senders, None, metrics_reporter);
+        eh.try_send_message_to("a", 1).unwrap();
+
+        // The channel is full: the message is handed back
+        match eh.try_send_message_to("a", 2) {
+            Err(TypedError::ChannelSendError(SendError::Full(2))) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(eh.try_send_message_to("unknown", 3).is_err());
+        assert_eq!(rx.recv().await.unwrap(), 1);
+    }
+
     #[tokio::test]
     async fn effect_handler_send_message_single_port_fallback() {
         let (tx, rx) = channel::<u64>(10);
@@ -123,7 +123,7 @@ impl<PData> ControlChannel<PData> {
 /// A `!Send` implementation of the EffectHandler.
 #[derive(Clone)]
 pub struct EffectHandler<PData> {
-    core: EffectHandlerCore<PData>,
+    pub(crate) core: EffectHandlerCore<PData>,
 
     /// A sender used to forward messages from the receiver.
     /// Supports multiple named output ports.
@@ -186,10 +186,41 @@ impl<PData> EffectHandler<PData> {
     #[inline]
     pub async fn send_message(&self, data: PData) -> Result<(), TypedError<PData>> {
         match &self.default_sender {
-            Some(sender) => sender
-                .send(data)
-                .await
-                .map_err(TypedError::ChannelSendError),
+            Some(sender) => {
+                let data = self.core.admit(data).await?;
+                sender
+                    .send(data)
+                    .await
+                    .map_err(TypedError::ChannelSendError)
+            }
+            None => Err(TypedError::Error(Error::ReceiverError {
+                receiver: self.receiver_id(),
+                kind: ReceiverErrorKind::Configuration,
+                error:
+                    "Ambiguous default out port: multiple ports connected and no default configured"
+                        .to_string(),
+                source_detail: String::new(),
+            })),
+        }
+    }
+
+    /// Attempts to send a message to the default out port without waiting for channel capacity.
+    ///
+    /// Receivers reading from a source they can pause (e.g. a Kafka consumer) should use this
+    /// method and stop reading from the source while the channel is full, see the crate-level
+    /// documentation on backpressure.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypedError::ChannelSendError`] holding the message back if the channel is full
+    /// or closed, or a [`TypedError::Error`] if the default port is not configured.
+    #[inline]
+    pub fn try_send_message(&self, data: PData) -> Result<(), TypedError<PData>> {
+        match &self.default_sender {
+            Some(sender) => {
+                let data = self.core.try_admit(data)?;
+                sender.try_send(data).map_err(TypedError::ChannelSendError)
+            }
             None => Err(TypedError::Error(Error::ReceiverError {
                 receiver: self.receiver_id(),
                 kind: ReceiverErrorKind::Configuration,
@@ -214,10 +245,13 @@ impl<PData> EffectHandler<PData> {
     {
         let port_name: PortName = port.into();
         match self.msg_senders.get(&port_name) {
-            Some(sender) => sender
-                .send(data)
-                .await
-                .map_err(TypedError::ChannelSendError),
+            Some(sender) => {
+                let data = self.core.admit(data).await?;
+                sender
+                    .send(data)
+                    .await
+                    .map_err(TypedError::ChannelSendError)
+            }
             None => Err(TypedError::Error(Error::ReceiverError {
                 receiver: self.receiver_id(),
                 kind: ReceiverErrorKind::Configuration,
@@ -298,6 +332,7 @@ mod tests {
     use crate::control::pipeline_ctrl_msg_channel;
     use crate::local::message::LocalSender;
     use crate::testing::test_node;
+    use otap_df_channel::error::SendError;
     use otap_df_channel::mpsc;
     use std::borrow::Cow;
     use std::collections::{HashMap, HashSet};
@@ -403,6 +438,30 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn effect_handler_try_send_message_full_channel() {
+        let (tx, rx) = channel::<u64>(1);
+        let mut senders = HashMap::new();
+        let _ = senders.insert("only".into(), LocalSender::MpscSender(tx));
+
+        let (ctrl_tx, _ctrl_rx) = pipeline_ctrl_msg_channel(4);
+        let (_metrics_rx, metrics_reporter) = MetricsReporter::create_new_and_receiver(1);
+        let eh = EffectHandler::new(test_node("recv"), senders, None, ctrl_tx, metrics_reporter);
+
+        eh.try_send_message(1).unwrap();
+
+        // The channel is full: the message is handed back instead of being buffered
+        match eh.try_send_message(2) {
+            Err(TypedError::ChannelSendError(SendError::Full(2))) => {}
+            other => panic!("expected a full channel, got {other:?}"),
+        }
+
+        // Once the downstream node consumed a message, sending succeeds again
+        assert_eq!(rx.recv().await.unwrap(), 1);
+        eh.try_send_message(2).unwrap();
+        assert_eq!(rx.recv().await.unwrap(), 2);
+    }
+
     #[tokio::test]
     async fn effect_handler_connected_ports_lists_all() {
         let (a_tx, _a_rx) = channel::<u64>(1);
@@ -0,0 +1,516 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Memory budget of the pdata in flight in a pipeline.
+//!
+//! A pipeline configured with a `memory_budget` gives a [`MemoryAccountant`] to its receivers and
+//! processors:
+//!
+//! 1. The effect handler of a receiver reserves the in-memory size of every pdata it sends. When
+//!    the budget is exceeded, it waits for memory to be released with the `backpressure` policy,
+//!    and returns the pdata with [`TypedError::MemoryBudgetExceeded`] with the `reject` policy.
+//! 2. The effect handler of a processor resizes the reservation of every pdata it sends to the
+//!    size of its payload, e.g. after a transformation. A pdata sharing its reservation with other
+//!    pdata, e.g. a part of a request split by the processor, is given its own reservation, split
+//!    from the shared one, so that every part is charged. Processors never wait, the memory being
+//!    already allocated.
+//! 3. The reservation is kept by the pdata itself (see [`MemoryAccountedData`]) and shared by its
+//!    clones. It is released when the last of them is dropped, e.g. once exported.
+//!
+//! A pdata larger than the whole budget is admitted when nothing else is in flight, so that it
+//! does not wait forever. The pdata held by a processor, e.g. a batch processor, stay charged,
+//! so the budget must leave room for them.
+
+use crate::error::TypedError;
+use otap_df_channel::error::SendError;
+use otap_df_config::pipeline::{MemoryBudgetConfig, MemoryBudgetPolicy};
+use otap_df_telemetry::instrument::{Counter, Gauge};
+use otap_df_telemetry::metrics::MetricSet;
+use otap_df_telemetry::reporter::MetricsReporter;
+use otap_df_telemetry_macros::metric_set;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Pdata whose memory can be charged against the budget of a pipeline.
+pub trait MemoryAccountedData: Sized {
+    /// Returns the in-memory size of the pdata in bytes.
+    fn memory_size(&self) -> usize;
+
+    /// Returns the reservation held by the pdata, if any.
+    fn memory_reservation(&self) -> Option<&MemoryReservation>;
+
+    /// Attaches a reservation to the pdata, replacing any previous reservation.
+    fn set_memory_reservation(&mut self, reservation: MemoryReservation);
+}
+
+/// Memory budget metrics of a pipeline.
+#[metric_set(name = "pipeline.memory.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct MemoryBudgetMetrics {
+    /// Number of bytes held by the pdata in flight.
+    #[metric(unit = "By")]
+    pub used: Gauge<u64>,
+
+    /// Maximum number of bytes held by the pdata in flight.
+    #[metric(unit = "By")]
+    pub limit: Gauge<u64>,
+
+    /// Number of pdata whose receiver waited for memory to be released.
+    #[metric(unit = "{msg}")]
+    pub waits: Counter<u64>,
+
+    /// Number of pdata refused because the budget was exceeded.
+    #[metric(unit = "{msg}")]
+    pub rejected: Counter<u64>,
+}
+
+/// Memory budget of a pipeline.
+///
+/// Cloning the budget is cheap, all the clones refer to the same state.
+#[derive(Clone)]
+pub struct MemoryBudget {
+    inner: Arc<BudgetState>,
+}
+
+struct BudgetState {
+    limit: usize,
+    policy: MemoryBudgetPolicy,
+    used: AtomicUsize,
+    /// Wakes up the receivers waiting for memory each time memory is released.
+    released: Notify,
+    metrics: Mutex<MetricSet<MemoryBudgetMetrics>>,
+}
+
+impl MemoryBudget {
+    /// Creates the budget of a pipeline.
+    #[must_use]
+    pub fn new(config: &MemoryBudgetConfig, metrics: MetricSet<MemoryBudgetMetrics>) -> Self {
+        Self {
+            inner: Arc::new(BudgetState {
+                limit: config.limit_bytes,
+                policy: config.on_exceeded,
+                used: AtomicUsize::new(0),
+                released: Notify::new(),
+                metrics: Mutex::new(metrics),
+            }),
+        }
+    }
+
+    /// Returns the number of bytes held by the pdata in flight.
+    #[must_use]
+    pub fn used(&self) -> usize {
+        self.inner.used.load(Ordering::Acquire)
+    }
+
+    /// Returns the maximum number of bytes held by the pdata in flight.
+    #[must_use]
+    pub fn limit(&self) -> usize {
+        self.inner.limit
+    }
+
+    /// Reserves the given number of bytes if they fit in the budget, or if nothing else is
+    /// reserved.
+    #[must_use]
+    pub fn try_reserve(&self, bytes: usize) -> Option<MemoryReservation> {
+        let limit = self.inner.limit;
+        self.inner
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                (used == 0 || used.saturating_add(bytes) <= limit).then_some(used + bytes)
+            })
+            .ok()
+            .map(|_| self.reservation(bytes))
+    }
+
+    /// Reserves the given number of bytes, waiting for memory to be released with the
+    /// `backpressure` policy. Returns `None` if the budget is exceeded with the `reject` policy.
+    pub async fn reserve(&self, bytes: usize) -> Option<MemoryReservation> {
+        if let Some(reservation) = self.try_reserve(bytes) {
+            return Some(reservation);
+        }
+        if self.inner.policy == MemoryBudgetPolicy::Reject {
+            self.lock_metrics().rejected.inc();
+            return None;
+        }
+        self.lock_metrics().waits.inc();
+        loop {
+            // Registered before trying again, so that a release in between is not missed.
+            let released = self.inner.released.notified();
+            tokio::pin!(released);
+            let _ = released.as_mut().enable();
+            if let Some(reservation) = self.try_reserve(bytes) {
+                return Some(reservation);
+            }
+            released.await;
+        }
+    }
+
+    /// Reserves the given number of bytes even if they do not fit in the budget, e.g. for memory
+    /// already allocated.
+    #[must_use]
+    pub fn force_reserve(&self, bytes: usize) -> MemoryReservation {
+        let _ = self.inner.used.fetch_add(bytes, Ordering::AcqRel);
+        self.reservation(bytes)
+    }
+
+    /// Reports the metrics of the budget.
+    pub(crate) fn report(&self, metrics_reporter: &mut MetricsReporter) {
+        let used = self.used() as u64;
+        let mut metrics = self.lock_metrics();
+        metrics.used.set(used);
+        metrics.limit.set(self.inner.limit as u64);
+        let _ = metrics_reporter.report(&mut metrics);
+    }
+
+    fn reservation(&self, bytes: usize) -> MemoryReservation {
+        MemoryReservation {
+            inner: Arc::new(Reservation {
+                budget: self.clone(),
+                bytes: AtomicUsize::new(bytes),
+            }),
+        }
+    }
+
+    fn release(&self, bytes: usize) {
+        if bytes > 0 {
+            let _ = self.inner.used.fetch_sub(bytes, Ordering::AcqRel);
+            self.inner.released.notify_waiters();
+        }
+    }
+
+    fn lock_metrics(&self) -> std::sync::MutexGuard<'_, MetricSet<MemoryBudgetMetrics>> {
+        self.inner
+            .metrics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Memory reserved in a budget, released when the reservation and all its clones are dropped.
+#[derive(Clone)]
+pub struct MemoryReservation {
+    inner: Arc<Reservation>,
+}
+
+struct Reservation {
+    budget: MemoryBudget,
+    bytes: AtomicUsize,
+}
+
+impl MemoryReservation {
+    /// Returns the number of reserved bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.inner.bytes.load(Ordering::Acquire)
+    }
+
+    /// Changes the number of reserved bytes, even if they no longer fit in the budget.
+    pub fn resize(&self, bytes: usize) {
+        let previous = self.inner.bytes.swap(bytes, Ordering::AcqRel);
+        if bytes > previous {
+            let _ = self
+                .inner
+                .budget
+                .inner
+                .used
+                .fetch_add(bytes - previous, Ordering::AcqRel);
+        } else {
+            self.inner.budget.release(previous - bytes);
+        }
+    }
+
+    /// Moves `bytes` of this reservation to a new reservation. The bytes missing when fewer are
+    /// reserved are reserved even if they no longer fit in the budget.
+    #[must_use]
+    pub fn split(&self, bytes: usize) -> MemoryReservation {
+        let (Ok(previous) | Err(previous)) =
+            self.inner
+                .bytes
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |reserved| {
+                    Some(reserved.saturating_sub(bytes))
+                });
+        let missing = bytes.saturating_sub(previous);
+        let _ = self
+            .inner
+            .budget
+            .inner
+            .used
+            .fetch_add(missing, Ordering::AcqRel);
+        self.inner.budget.reservation(bytes)
+    }
+
+    /// Returns true if the reservation is held by several pdata.
+    fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.inner) > 1
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.budget.release(*self.bytes.get_mut());
+    }
+}
+
+impl fmt::Debug for MemoryReservation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryReservation")
+            .field("bytes", &self.size())
+            .finish()
+    }
+}
+
+/// Charges the pdata sent by a node against the memory budget of its pipeline.
+pub struct MemoryAccountant<PData> {
+    budget: MemoryBudget,
+    size: fn(&PData) -> usize,
+    reservation: fn(&PData) -> Option<&MemoryReservation>,
+    set_reservation: fn(&mut PData, MemoryReservation),
+}
+
+impl<PData> Clone for MemoryAccountant<PData> {
+    fn clone(&self) -> Self {
+        Self {
+            budget: self.budget.clone(),
+            size: self.size,
+            reservation: self.reservation,
+            set_reservation: self.set_reservation,
+        }
+    }
+}
+
+impl<PData: MemoryAccountedData> MemoryAccountant<PData> {
+    /// Creates an accountant charging the given budget.
+    #[must_use]
+    pub fn new(budget: MemoryBudget) -> Self {
+        Self {
+            budget,
+            size: PData::memory_size,
+            reservation: PData::memory_reservation,
+            set_reservation: PData::set_memory_reservation,
+        }
+    }
+}
+
+impl<PData> MemoryAccountant<PData> {
+    /// Charges a pdata sent by a receiver, waiting for memory with the `backpressure` policy.
+    pub(crate) async fn admit(&self, mut pdata: PData) -> Result<PData, TypedError<PData>> {
+        let size = (self.size)(&pdata);
+        if let Some(reservation) = (self.reservation)(&pdata) {
+            reservation.resize(size);
+            return Ok(pdata);
+        }
+        match self.budget.reserve(size).await {
+            Some(reservation) => {
+                (self.set_reservation)(&mut pdata, reservation);
+                Ok(pdata)
+            }
+            None => Err(self.exceeded(size, pdata)),
+        }
+    }
+
+    /// Charges a pdata sent by a receiver without waiting. With the `backpressure` policy, the
+    /// pdata is returned as if the channel was full, so that the receiver pauses its source.
+    pub(crate) fn try_admit(&self, mut pdata: PData) -> Result<PData, TypedError<PData>> {
+        let size = (self.size)(&pdata);
+        if let Some(reservation) = (self.reservation)(&pdata) {
+            reservation.resize(size);
+            return Ok(pdata);
+        }
+        match self.budget.try_reserve(size) {
+            Some(reservation) => {
+                (self.set_reservation)(&mut pdata, reservation);
+                Ok(pdata)
+            }
+            None if self.budget.inner.policy == MemoryBudgetPolicy::Backpressure => {
+                Err(TypedError::ChannelSendError(SendError::Full(pdata)))
+            }
+            None => {
+                self.budget.lock_metrics().rejected.inc();
+                Err(self.exceeded(size, pdata))
+            }
+        }
+    }
+
+    /// Charges a pdata sent by a processor with the current size of its payload.
+    pub(crate) fn recharge(&self, pdata: &mut PData) {
+        let size = (self.size)(pdata);
+        let reservation = match (self.reservation)(pdata) {
+            // e.g. a part of a split request, charged apart from the other parts
+            Some(reservation) if reservation.is_shared() => reservation.split(size),
+            Some(reservation) => {
+                reservation.resize(size);
+                return;
+            }
+            None => self.budget.force_reserve(size),
+        };
+        (self.set_reservation)(pdata, reservation);
+    }
+
+    fn exceeded(&self, requested: usize, data: PData) -> TypedError<PData> {
+        TypedError::MemoryBudgetExceeded {
+            requested,
+            limit: self.budget.limit(),
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otap_df_telemetry::testing::EmptyAttributes;
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, Default)]
+    struct Data {
+        size: usize,
+        reservation: Option<MemoryReservation>,
+    }
+
+    impl MemoryAccountedData for Data {
+        fn memory_size(&self) -> usize {
+            self.size
+        }
+
+        fn memory_reservation(&self) -> Option<&MemoryReservation> {
+            self.reservation.as_ref()
+        }
+
+        fn set_memory_reservation(&mut self, reservation: MemoryReservation) {
+            self.reservation = Some(reservation);
+        }
+    }
+
+    fn data(size: usize) -> Data {
+        Data {
+            size,
+            reservation: None,
+        }
+    }
+
+    fn accountant(limit_bytes: usize, on_exceeded: MemoryBudgetPolicy) -> MemoryAccountant<Data> {
+        let metrics =
+            MetricsRegistryHandle::new().register::<MemoryBudgetMetrics>(EmptyAttributes());
+        let config = MemoryBudgetConfig {
+            limit_bytes,
+            on_exceeded,
+        };
+        MemoryAccountant::new(MemoryBudget::new(&config, metrics))
+    }
+
+    #[tokio::test]
+    async fn test_reject() {
+        let accountant = accountant(100, MemoryBudgetPolicy::Reject);
+        let first = accountant.admit(data(60)).await.unwrap();
+        let clone = first.clone();
+        assert_eq!(accountant.budget.used(), 60);
+
+        match accountant.admit(data(50)).await {
+            Err(TypedError::MemoryBudgetExceeded {
+                requested, limit, ..
+            }) => assert_eq!((requested, limit), (50, 100)),
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(matches!(
+            accountant.try_admit(data(50)),
+            Err(TypedError::MemoryBudgetExceeded { .. })
+        ));
+        assert_eq!(accountant.budget.lock_metrics().rejected.get(), 2);
+
+        // released once the last clone is dropped
+        drop(first);
+        assert_eq!(accountant.budget.used(), 60);
+        drop(clone);
+        assert_eq!(accountant.budget.used(), 0);
+        let oversized = accountant.admit(data(150)).await.unwrap();
+        assert_eq!(accountant.budget.used(), 150);
+        drop(oversized);
+    }
+
+    #[tokio::test]
+    async fn test_backpressure() {
+        let accountant = accountant(100, MemoryBudgetPolicy::Backpressure);
+        let first = accountant.admit(data(60)).await.unwrap();
+        assert!(matches!(
+            accountant.try_admit(data(50)),
+            Err(TypedError::ChannelSendError(SendError::Full(_)))
+        ));
+
+        let waiting = accountant.clone();
+        let second = tokio::spawn(async move { waiting.admit(data(50)).await.map(|_| ()) });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!second.is_finished());
+        drop(first);
+        tokio::time::timeout(Duration::from_secs(1), second)
+            .await
+            .expect("admitted once memory is released")
+            .unwrap()
+            .unwrap();
+        assert_eq!(accountant.budget.used(), 0);
+        assert_eq!(accountant.budget.lock_metrics().waits.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_recharge() {
+        let accountant = accountant(100, MemoryBudgetPolicy::Reject);
+        let mut pdata = accountant.admit(data(60)).await.unwrap();
+        pdata.size = 150;
+        accountant.recharge(&mut pdata);
+        assert_eq!(accountant.budget.used(), 150);
+        pdata.size = 10;
+        accountant.recharge(&mut pdata);
+        assert_eq!(accountant.budget.used(), 10);
+
+        // pdata created by a processor are charged even over the budget
+        let mut created = data(200);
+        accountant.recharge(&mut created);
+        assert_eq!(accountant.budget.used(), 210);
+        drop(pdata);
+        drop(created);
+        assert_eq!(accountant.budget.used(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_recharge_split() {
+        let accountant = accountant(1000, MemoryBudgetPolicy::Reject);
+        let request = accountant.admit(data(100)).await.unwrap();
+
+        // a processor splits the request in parts sharing its reservation
+        let parts: Vec<Data> = [60, 40]
+            .into_iter()
+            .map(|size| Data {
+                size,
+                ..request.clone()
+            })
+            .collect();
+        drop(request);
+        let mut parts: Vec<Data> = parts
+            .into_iter()
+            .map(|mut part| {
+                accountant.recharge(&mut part);
+                part
+            })
+            .collect();
+        assert_eq!(accountant.budget.used(), 100);
+        assert_eq!(
+            parts
+                .iter()
+                .filter_map(|part| part.reservation.as_ref())
+                .map(MemoryReservation::size)
+                .collect::<Vec<_>>(),
+            [60, 40]
+        );
+
+        // every part is charged with its own size, and released on its own
+        parts[1].size = 90;
+        accountant.recharge(&mut parts[1]);
+        assert_eq!(accountant.budget.used(), 150);
+        drop(parts.remove(0));
+        assert_eq!(accountant.budget.used(), 90);
+        drop(parts);
+        assert_eq!(accountant.budget.used(), 0);
+    }
+}
@@ -3,11 +3,16 @@
 
 //! Message definitions for the pipeline engine.
 
+use crate::channel_metrics::{ChannelMetrics, ChannelMonitor};
 use crate::control::{AckMsg, NackMsg, NodeControlMsg};
+use crate::in_flight::InFlightTracker;
 use crate::local::message::{LocalReceiver, LocalSender};
+use crate::node::NodeId;
 use crate::shared::message::{SharedReceiver, SharedSender};
+use crate::shutdown::ShutdownTracker;
 use otap_df_channel::error::{RecvError, SendError};
 use otap_df_channel::mpsc;
+use otap_df_telemetry::metrics::MetricSet;
 use std::ops::Add;
 use std::pin::Pin;
 use std::time::{Duration, Instant};
@@ -165,6 +170,24 @@ impl<T> Receiver<T> {
             Receiver::Shared(receiver) => receiver.is_empty(),
         }
     }
+
+    /// Returns the number of messages waiting in the channel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            Receiver::Local(receiver) => receiver.len(),
+            Receiver::Shared(receiver) => receiver.len(),
+        }
+    }
+
+    /// Returns the maximum number of messages the channel can hold, `None` if unbounded.
+    #[must_use]
+    pub fn capacity(&self) -> Option<usize> {
+        match self {
+            Receiver::Local(receiver) => receiver.capacity(),
+            Receiver::Shared(receiver) => receiver.capacity(),
+        }
+    }
 }
 
 /// A channel for receiving control and pdata messages.
@@ -172,9 +195,9 @@ impl<T> Receiver<T> {
 /// Control messages are prioritized until the first `Shutdown` is received.
 /// After that, only pdata messages are considered, up to the deadline.
 ///
-/// Note: This approach is used to implement a graceful shutdown. The engine will first close all
-/// data sources in the pipeline, and then send a shutdown message with a deadline to all nodes in
-/// the pipeline.
+/// Note: This approach is used to implement a graceful shutdown. The engine first shuts down the
+/// receivers, then each downstream node is shut down once all its upstream nodes are gone (see
+/// [`crate::shutdown`]).
 pub struct MessageChannel<PData> {
     control_rx: Option<Receiver<NodeControlMsg<PData>>>,
     pdata_rx: Option<Receiver<PData>>,
@@ -183,6 +206,12 @@ pub struct MessageChannel<PData> {
     shutting_down_deadline: Option<Instant>,
     /// Holds the ControlMsg::Shutdown until after we’ve drained pdata.
     pending_shutdown: Option<NodeControlMsg<PData>>,
+    /// Pipeline shutdown state, and the node owning this channel.
+    shutdown_tracker: Option<(NodeId, ShutdownTracker)>,
+    /// Pdata in flight in the exporter owning this channel, stamped when delivered.
+    in_flight: Option<InFlightTracker<PData>>,
+    /// Occupancy metrics of the pdata channel, if attached.
+    channel_monitor: Option<ChannelMonitor>,
 }
 
 impl<PData> MessageChannel<PData> {
@@ -194,9 +223,38 @@ impl<PData> MessageChannel<PData> {
             pdata_rx: Some(pdata_rx),
             shutting_down_deadline: None,
             pending_shutdown: None,
+            shutdown_tracker: None,
+            in_flight: None,
+            channel_monitor: None,
         }
     }
 
+    /// Attaches the pipeline shutdown tracker to this channel.
+    ///
+    /// When the pdata channel is closed, the `Shutdown` returned to the node carries the global
+    /// shutdown deadline, and pdata left unprocessed at the deadline is reported as dropped.
+    #[must_use]
+    pub fn with_shutdown_tracker(mut self, node_id: NodeId, tracker: ShutdownTracker) -> Self {
+        self.shutdown_tracker = Some((node_id, tracker));
+        self
+    }
+
+    /// Attaches the Ack deadline tracker of the exporter owning this channel (see
+    /// [`crate::in_flight`]).
+    #[must_use]
+    pub(crate) fn with_in_flight_tracker(mut self, tracker: InFlightTracker<PData>) -> Self {
+        self.in_flight = Some(tracker);
+        self
+    }
+
+    /// Attaches the occupancy metrics of the pdata channel, reported with the `CollectTelemetry`
+    /// messages of the node (see [`crate::channel_metrics`]).
+    #[must_use]
+    pub(crate) fn with_channel_metrics(mut self, metrics: MetricSet<ChannelMetrics>) -> Self {
+        self.channel_monitor = Some(ChannelMonitor::new(metrics));
+        self
+    }
+
     /// Asynchronously receives the next message to process.
     ///
     /// Order of precedence:
@@ -259,7 +317,7 @@ impl<PData> MessageChannel<PData> {
 
                     // 2) Any pdata?
                     pdata = self.pdata_rx.as_mut().expect("pdata_rx must exist").recv() => match pdata {
-                        Ok(pdata) => return Ok(Message::PData(pdata)),
+                        Ok(pdata) => return Ok(self.deliver(pdata)),
                         Err(_) => {
                             // pdata channel closed → emit Shutdown
                             let shutdown = self.pending_shutdown
@@ -292,7 +350,7 @@ impl<PData> MessageChannel<PData> {
                         self.pending_shutdown = Some(NodeControlMsg::Shutdown { deadline, reason });
                         continue; // re-enter the loop into draining mode
                     }
-                    Ok(msg) => return Ok(Message::Control(msg)),
+                    Ok(msg) => return Ok(self.control(msg)),
                     Err(e)  => return Err(e),
                 },
 
@@ -300,13 +358,14 @@ impl<PData> MessageChannel<PData> {
                 pdata = self.pdata_rx.as_mut().expect("pdata_rx must exist").recv() => {
                     match pdata {
                         Ok(pdata) => {
-                            return Ok(Message::PData(pdata));
+                            return Ok(self.deliver(pdata));
                         }
                         Err(RecvError::Closed) => {
-                            // pdata channel closed -> emit Shutdown
+                            // pdata channel closed -> emit Shutdown with the pipeline deadline
+                            let deadline = self.drain_deadline();
                             self.shutdown();
                             return Ok(Message::Control(NodeControlMsg::Shutdown {
-                                deadline: Instant::now().add(Duration::from_secs(1)),
+                                deadline,
                                 reason: "pdata channel closed".to_owned(),
                             }));
                         }
@@ -319,9 +378,48 @@ impl<PData> MessageChannel<PData> {
         }
     }
 
+    fn deliver(&mut self, mut pdata: PData) -> Message<PData> {
+        if let Some(monitor) = &mut self.channel_monitor {
+            // the pdata was the last one taken out of the channel
+            let depth = self.pdata_rx.as_ref().map_or(0, Receiver::len) + 1;
+            monitor.observe(depth);
+        }
+        if let Some(in_flight) = &self.in_flight {
+            in_flight.track(&mut pdata);
+        }
+        Message::PData(pdata)
+    }
+
+    fn control(&mut self, msg: NodeControlMsg<PData>) -> Message<PData> {
+        if let (NodeControlMsg::CollectTelemetry { metrics_reporter }, Some(monitor)) =
+            (&msg, &mut self.channel_monitor)
+        {
+            let (depth, capacity) = self
+                .pdata_rx
+                .as_ref()
+                .map_or((0, None), |pdata_rx| (pdata_rx.len(), pdata_rx.capacity()));
+            monitor.report(depth, capacity, metrics_reporter);
+        }
+        Message::Control(msg)
+    }
+
     fn shutdown(&mut self) {
         self.shutting_down_deadline = None;
         drop(self.control_rx.take().expect("control_rx must exist"));
-        drop(self.pdata_rx.take().expect("pdata_rx must exist"));
+        let mut pdata_rx = self.pdata_rx.take().expect("pdata_rx must exist");
+        if let Some((node_id, tracker)) = &self.shutdown_tracker {
+            let mut dropped = 0;
+            while pdata_rx.try_recv().is_ok() {
+                dropped += 1;
+            }
+            tracker.record_dropped(node_id, dropped);
+        }
+    }
+
+    fn drain_deadline(&self) -> Instant {
+        match &self.shutdown_tracker {
+            Some((_, tracker)) => tracker.drain_deadline(),
+            None => Instant::now().add(Duration::from_secs(1)),
+        }
     }
 }
@@ -7,12 +7,20 @@
 //! timers for nodes in the pipeline. It handles scheduling, cancellation, and expiration
 //! of recurring timers, using a priority queue for efficient timer management.
 //!
+//! The manager also checks the Ack deadlines of the pdata in flight in the exporters (see
+//! [`crate::in_flight`]), and reports the usage of the memory budget of the pipeline (see
+//! [`crate::memory`]) and the processing latency of its nodes (see [`crate::latency`]).
+//!
 //! Note 1: This manager is designed for single-threaded async execution.
 //! Note 2: Other pipeline control messages can be added in the future, but currently only timers
 //! are supported.
 
 use crate::control::{ControlSenders, NodeControlMsg, PipelineControlMsg, PipelineCtrlMsgReceiver};
 use crate::error::Error;
+use crate::in_flight::{InFlightTracker, SWEEP_INTERVAL};
+use crate::latency::LatencyRecorder;
+use crate::memory::MemoryBudget;
+use crate::shutdown::ShutdownTracker;
 use otap_df_telemetry::reporter::MetricsReporter;
 use std::cmp::Reverse;
 use std::collections::{BinaryHeap, HashMap};
@@ -169,6 +177,17 @@ pub struct PipelineCtrlMsgManager<PData> {
     delayed_data: BinaryHeap<Delayed<PData>>,
     /// Global metrics reporter.
     metrics_reporter: MetricsReporter,
+    /// Shared state of the pipeline shutdown.
+    shutdown_tracker: ShutdownTracker,
+    /// Ack deadline trackers of the exporters, swept every [`SWEEP_INTERVAL`].
+    in_flight_trackers: Vec<InFlightTracker<PData>>,
+    /// Memory budget of the pipeline, reported every [`SWEEP_INTERVAL`].
+    memory_budget: Option<MemoryBudget>,
+    /// Latency recorders of the processors and exporters, reported every [`SWEEP_INTERVAL`].
+    latency_recorders: Vec<LatencyRecorder<PData>>,
+    /// Next sweep of the Ack deadline trackers and report of the memory budget and latencies, if
+    /// any.
+    next_in_flight_sweep: Option<Instant>,
 }
 
 impl<PData> PipelineCtrlMsgManager<PData> {
@@ -186,7 +205,49 @@ impl<PData> PipelineCtrlMsgManager<PData> {
             telemetry_timers: TimerSet::new(),
             delayed_data: BinaryHeap::new(),
             metrics_reporter,
+            shutdown_tracker: ShutdownTracker::default(),
+            in_flight_trackers: Vec::new(),
+            memory_budget: None,
+            latency_recorders: Vec::new(),
+            next_in_flight_sweep: None,
+        }
+    }
+
+    /// Sets the tracker recording the global deadline of the pipeline shutdown, so that every
+    /// node of the pipeline drains its input within the same deadline.
+    #[must_use]
+    pub fn with_shutdown_tracker(mut self, shutdown_tracker: ShutdownTracker) -> Self {
+        self.shutdown_tracker = shutdown_tracker;
+        self
+    }
+
+    /// Sets the Ack deadline trackers of the exporters, whose pdata in flight past their deadline
+    /// are timed out, and Nacked when configured.
+    #[must_use]
+    pub fn with_in_flight_trackers(mut self, trackers: Vec<InFlightTracker<PData>>) -> Self {
+        self.next_in_flight_sweep = (!trackers.is_empty()).then(|| Instant::now() + SWEEP_INTERVAL);
+        self.in_flight_trackers = trackers;
+        self
+    }
+
+    /// Sets the memory budget of the pipeline, whose usage is reported periodically.
+    #[must_use]
+    pub fn with_memory_budget(mut self, memory_budget: Option<MemoryBudget>) -> Self {
+        if memory_budget.is_some() && self.next_in_flight_sweep.is_none() {
+            self.next_in_flight_sweep = Some(Instant::now() + SWEEP_INTERVAL);
         }
+        self.memory_budget = memory_budget;
+        self
+    }
+
+    /// Sets the latency recorders of the nodes, whose histograms are reported periodically.
+    #[must_use]
+    pub fn with_latency_recorders(mut self, recorders: Vec<LatencyRecorder<PData>>) -> Self {
+        if !recorders.is_empty() && self.next_in_flight_sweep.is_none() {
+            self.next_in_flight_sweep = Some(Instant::now() + SWEEP_INTERVAL);
+        }
+        self.latency_recorders = recorders;
+        self
     }
 
     /// Runs the manager event loop.
@@ -195,14 +256,19 @@ impl<PData> PipelineCtrlMsgManager<PData> {
     /// timers).
     /// - On StartTimer: schedules a timer for the node.
     /// - On CancelTimer: marks the timer as canceled.
+    /// - On Shutdown: records the global deadline and shuts down the receivers.
     /// - On timer expiration: checks for cancellation and outdatedness before firing.
+    /// - Every [`SWEEP_INTERVAL`]: times out the pdata in flight past their Ack deadline.
     pub async fn run(mut self) -> Result<(), Error> {
         loop {
             // Get the next expirations, if any.
             let next_expiry = self.tick_timers.next_expiry();
             let next_tel_expiry = self.telemetry_timers.next_expiry();
             let next_delay_expiry = self.delayed_data.peek().map(|d| d.when);
-            let next_earliest = opt_min(opt_min(next_expiry, next_tel_expiry), next_delay_expiry);
+            let next_earliest = opt_min(
+                opt_min(next_expiry, next_tel_expiry),
+                opt_min(next_delay_expiry, self.next_in_flight_sweep),
+            );
             tokio::select! {
                 biased;
                 // Handle incoming control messages from nodes.
@@ -210,6 +276,9 @@ impl<PData> PipelineCtrlMsgManager<PData> {
                     let Some(msg) = msg.ok() else { break; };
                     match msg {
                         PipelineControlMsg::Shutdown { deadline, reason } => {
+                            // Receivers stop first, downstream nodes follow as their input
+                            // channels close, all bounded by the same deadline.
+                            self.shutdown_tracker.begin(deadline);
                             // ToDo don't ignore the returned errors
                             _ = self.control_senders.shutdown_receivers(deadline, reason).await;
                             break;
@@ -271,6 +340,23 @@ impl<PData> PipelineCtrlMsgManager<PData> {
                         to_send.push((delayed.node_id, NodeControlMsg::DelayedData { when: delayed.when, data: delayed.data }));
                     }
 
+                    // Time out the pdata in flight past their deadline.
+                    if self.next_in_flight_sweep.is_some_and(|when| when <= now) {
+                        for tracker in &self.in_flight_trackers {
+                            for (node_id, nack) in tracker.expire(now) {
+                                to_send.push((node_id, NodeControlMsg::Nack(nack)));
+                            }
+                            tracker.report(&mut self.metrics_reporter);
+                        }
+                        if let Some(memory_budget) = &self.memory_budget {
+                            memory_budget.report(&mut self.metrics_reporter);
+                        }
+                        for recorder in &self.latency_recorders {
+                            recorder.report(&mut self.metrics_reporter);
+                        }
+                        self.next_in_flight_sweep = Some(now + SWEEP_INTERVAL);
+                    }
+
                     // Deliver all accumulated control messages (best-effort)
                     for (node_id, msg) in to_send {
                         self.send(node_id, msg).await;
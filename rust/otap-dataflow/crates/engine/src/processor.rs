@@ -7,19 +7,24 @@
 //! For more details on the `!Send` implementation of a processor, see [`local::Processor`].
 //! See [`shared::Processor`] for the Send implementation.
 
+use crate::channel_metrics::ChannelMetrics;
 use crate::config::ProcessorConfig;
 use crate::control::{Controllable, NodeControlMsg, PipelineCtrlMsgSender};
 use crate::error::{Error, ProcessorErrorKind};
+use crate::latency::LatencyRecorder;
 use crate::local::message::{LocalReceiver, LocalSender};
 use crate::local::processor as local;
+use crate::memory::MemoryAccountant;
 use crate::message::{Message, MessageChannel, Receiver, Sender};
 use crate::node::{Node, NodeId, NodeWithPDataReceiver, NodeWithPDataSender};
 use crate::shared::message::{SharedReceiver, SharedSender};
 use crate::shared::processor as shared;
+use crate::shutdown::ShutdownTracker;
 use otap_df_channel::error::SendError;
 use otap_df_channel::mpsc;
 use otap_df_config::PortName;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_telemetry::metrics::MetricSet;
 use otap_df_telemetry::reporter::MetricsReporter;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -49,6 +54,14 @@ pub enum ProcessorWrapper<PData> {
         pdata_senders: HashMap<PortName, LocalSender<PData>>,
         /// A receiver for pdata messages.
         pdata_receiver: Option<Receiver<PData>>,
+        /// Tracker of the pipeline shutdown.
+        shutdown_tracker: ShutdownTracker,
+        /// Memory budget of the pipeline, if configured.
+        memory: Option<MemoryAccountant<PData>>,
+        /// Recorder of the processing latency of the processor.
+        latency: Option<LatencyRecorder<PData>>,
+        /// Occupancy metrics of the pdata input channel of the processor.
+        channel_metrics: Option<MetricSet<ChannelMetrics>>,
     },
     /// A processor with a `Send` implementation.
     Shared {
@@ -68,6 +81,14 @@ pub enum ProcessorWrapper<PData> {
         pdata_senders: HashMap<PortName, SharedSender<PData>>,
         /// A receiver for pdata messages.
         pdata_receiver: Option<SharedReceiver<PData>>,
+        /// Tracker of the pipeline shutdown.
+        shutdown_tracker: ShutdownTracker,
+        /// Memory budget of the pipeline, if configured.
+        memory: Option<MemoryAccountant<PData>>,
+        /// Recorder of the processing latency of the processor.
+        latency: Option<LatencyRecorder<PData>>,
+        /// Occupancy metrics of the pdata input channel of the processor.
+        channel_metrics: Option<MetricSet<ChannelMetrics>>,
     },
 }
 
@@ -121,6 +142,10 @@ impl<PData> ProcessorWrapper<PData> {
             control_receiver: LocalReceiver::MpscReceiver(control_receiver),
             pdata_senders: HashMap::new(),
             pdata_receiver: None,
+            shutdown_tracker: ShutdownTracker::default(),
+            memory: None,
+            latency: None,
+            channel_metrics: None,
         }
     }
 
@@ -147,6 +172,61 @@ impl<PData> ProcessorWrapper<PData> {
             control_receiver: SharedReceiver::MpscReceiver(control_receiver),
             pdata_senders: HashMap::new(),
             pdata_receiver: None,
+            shutdown_tracker: ShutdownTracker::default(),
+            memory: None,
+            latency: None,
+            channel_metrics: None,
+        }
+    }
+
+    /// Sets the tracker of the pipeline shutdown this processor takes part in.
+    pub(crate) fn set_shutdown_tracker(&mut self, tracker: ShutdownTracker) {
+        match self {
+            ProcessorWrapper::Local {
+                shutdown_tracker, ..
+            }
+            | ProcessorWrapper::Shared {
+                shutdown_tracker, ..
+            } => *shutdown_tracker = tracker,
+        }
+    }
+
+    /// Sets the memory budget of the pipeline charged with the pdata sent by this processor.
+    pub(crate) fn set_memory_accountant(&mut self, accountant: MemoryAccountant<PData>) {
+        match self {
+            ProcessorWrapper::Local { memory, .. } | ProcessorWrapper::Shared { memory, .. } => {
+                *memory = Some(accountant)
+            }
+        }
+    }
+
+    /// Sets the recorder of the processing latency of this processor.
+    pub(crate) fn set_latency_recorder(&mut self, recorder: LatencyRecorder<PData>) {
+        match self {
+            ProcessorWrapper::Local { latency, .. } | ProcessorWrapper::Shared { latency, .. } => {
+                *latency = Some(recorder)
+            }
+        }
+    }
+
+    /// Sets the occupancy metrics of the pdata input channel of this processor.
+    pub(crate) fn set_channel_metrics(&mut self, metrics: MetricSet<ChannelMetrics>) {
+        match self {
+            ProcessorWrapper::Local {
+                channel_metrics, ..
+            }
+            | ProcessorWrapper::Shared {
+                channel_metrics, ..
+            } => *channel_metrics = Some(metrics),
+        }
+    }
+
+    /// Returns the recorder of the processing latency of this processor, if configured.
+    pub(crate) fn latency_recorder(&self) -> Option<LatencyRecorder<PData>> {
+        match self {
+            ProcessorWrapper::Local { latency, .. } | ProcessorWrapper::Shared { latency, .. } => {
+                latency.clone()
+            }
         }
     }
 
@@ -164,9 +244,13 @@ impl<PData> ProcessorWrapper<PData> {
                 pdata_senders,
                 pdata_receiver,
                 user_config,
+                shutdown_tracker,
+                memory,
+                latency,
+                channel_metrics,
                 ..
             } => {
-                let message_channel = MessageChannel::new(
+                let mut message_channel = MessageChannel::new(
                     Receiver::Local(control_receiver),
                     pdata_receiver.ok_or_else(|| Error::ProcessorError {
                         processor: node_id.clone(),
@@ -174,14 +258,20 @@ impl<PData> ProcessorWrapper<PData> {
                         error: "The pdata receiver must be defined at this stage".to_owned(),
                         source_detail: String::new(),
                     })?,
-                );
+                )
+                .with_shutdown_tracker(node_id.clone(), shutdown_tracker);
+                if let Some(metrics) = channel_metrics {
+                    message_channel = message_channel.with_channel_metrics(metrics);
+                }
                 let default_port = user_config.default_out_port.clone();
-                let effect_handler = local::EffectHandler::new(
+                let mut effect_handler = local::EffectHandler::new(
                     node_id,
                     pdata_senders,
                     default_port,
                     metrics_reporter,
                 );
+                effect_handler.core.memory = memory;
+                effect_handler.core.latency = latency;
                 Ok(ProcessorWrapperRuntime::Local {
                     processor,
                     effect_handler,
@@ -195,9 +285,13 @@ impl<PData> ProcessorWrapper<PData> {
                 pdata_senders,
                 pdata_receiver,
                 user_config,
+                shutdown_tracker,
+                memory,
+                latency,
+                channel_metrics,
                 ..
             } => {
-                let message_channel = MessageChannel::new(
+                let mut message_channel = MessageChannel::new(
                     Receiver::Shared(control_receiver),
                     Receiver::Shared(pdata_receiver.ok_or_else(|| Error::ProcessorError {
                         processor: node_id.clone(),
@@ -205,14 +299,20 @@ impl<PData> ProcessorWrapper<PData> {
                         error: "The pdata receiver must be defined at this stage".to_owned(),
                         source_detail: String::new(),
                     })?),
-                );
+                )
+                .with_shutdown_tracker(node_id.clone(), shutdown_tracker);
+                if let Some(metrics) = channel_metrics {
+                    message_channel = message_channel.with_channel_metrics(metrics);
+                }
                 let default_port = user_config.default_out_port.clone();
-                let effect_handler = shared::EffectHandler::new(
+                let mut effect_handler = shared::EffectHandler::new(
                     node_id,
                     pdata_senders,
                     default_port,
                     metrics_reporter,
                 );
+                effect_handler.core.memory = memory;
+                effect_handler.core.latency = latency;
                 Ok(ProcessorWrapperRuntime::Shared {
                     processor,
                     effect_handler,
@@ -10,8 +10,10 @@
 use crate::config::ReceiverConfig;
 use crate::control::{Controllable, NodeControlMsg, PipelineCtrlMsgSender};
 use crate::error::{Error, ProcessorErrorKind, ReceiverErrorKind};
+use crate::latency::LatencyRecorder;
 use crate::local::message::{LocalReceiver, LocalSender};
 use crate::local::receiver as local;
+use crate::memory::MemoryAccountant;
 use crate::message::{Receiver, Sender};
 use crate::node::{Node, NodeId, NodeWithPDataSender};
 use crate::shared::message::{SharedReceiver, SharedSender};
@@ -48,6 +50,10 @@ pub enum ReceiverWrapper<PData> {
         pdata_senders: HashMap<PortName, LocalSender<PData>>,
         /// A receiver for pdata messages.
         pdata_receiver: Option<LocalReceiver<PData>>,
+        /// Memory budget of the pipeline, if configured.
+        memory: Option<MemoryAccountant<PData>>,
+        /// Stamper of the pdata sent by the receiver, for the latency of the next nodes.
+        latency: Option<LatencyRecorder<PData>>,
     },
     /// A receiver with a `Send` implementation.
     Shared {
@@ -67,6 +73,10 @@ pub enum ReceiverWrapper<PData> {
         pdata_senders: HashMap<PortName, SharedSender<PData>>,
         /// A receiver for pdata messages.
         pdata_receiver: Option<SharedReceiver<PData>>,
+        /// Memory budget of the pipeline, if configured.
+        memory: Option<MemoryAccountant<PData>>,
+        /// Stamper of the pdata sent by the receiver, for the latency of the next nodes.
+        latency: Option<LatencyRecorder<PData>>,
     },
 }
 
@@ -106,6 +116,8 @@ impl<PData> ReceiverWrapper<PData> {
             control_receiver: LocalReceiver::MpscReceiver(control_receiver),
             pdata_senders: HashMap::new(),
             pdata_receiver: None,
+            memory: None,
+            latency: None,
         }
     }
 
@@ -131,6 +143,26 @@ impl<PData> ReceiverWrapper<PData> {
             control_receiver: SharedReceiver::MpscReceiver(control_receiver),
             pdata_senders: HashMap::new(),
             pdata_receiver: None,
+            memory: None,
+            latency: None,
+        }
+    }
+
+    /// Sets the memory budget of the pipeline charged with the pdata sent by this receiver.
+    pub(crate) fn set_memory_accountant(&mut self, accountant: MemoryAccountant<PData>) {
+        match self {
+            ReceiverWrapper::Local { memory, .. } | ReceiverWrapper::Shared { memory, .. } => {
+                *memory = Some(accountant)
+            }
+        }
+    }
+
+    /// Sets the stamper of the pdata sent by this receiver (see [`crate::latency`]).
+    pub(crate) fn set_latency_recorder(&mut self, recorder: LatencyRecorder<PData>) {
+        match self {
+            ReceiverWrapper::Local { latency, .. } | ReceiverWrapper::Shared { latency, .. } => {
+                *latency = Some(recorder)
+            }
         }
     }
 
@@ -148,6 +180,8 @@ impl<PData> ReceiverWrapper<PData> {
                     control_receiver,
                     pdata_senders,
                     user_config,
+                    memory,
+                    latency,
                     ..
                 },
                 metrics_reporter,
@@ -164,13 +198,15 @@ impl<PData> ReceiverWrapper<PData> {
                 };
                 let default_port = user_config.default_out_port.clone();
                 let ctrl_msg_chan = local::ControlChannel::new(Receiver::Local(control_receiver));
-                let effect_handler = local::EffectHandler::new(
+                let mut effect_handler = local::EffectHandler::new(
                     node_id,
                     msg_senders,
                     default_port,
                     pipeline_ctrl_msg_tx,
                     metrics_reporter,
                 );
+                effect_handler.core.memory = memory;
+                effect_handler.core.latency = latency;
                 receiver.start(ctrl_msg_chan, effect_handler).await
             }
             (
@@ -180,6 +216,8 @@ impl<PData> ReceiverWrapper<PData> {
                     control_receiver,
                     pdata_senders,
                     user_config,
+                    memory,
+                    latency,
                     ..
                 },
                 metrics_reporter,
@@ -196,13 +234,15 @@ impl<PData> ReceiverWrapper<PData> {
                 };
                 let default_port = user_config.default_out_port.clone();
                 let ctrl_msg_chan = shared::ControlChannel::new(control_receiver);
-                let effect_handler = shared::EffectHandler::new(
+                let mut effect_handler = shared::EffectHandler::new(
                     node_id,
                     msg_senders,
                     default_port,
                     pipeline_ctrl_msg_tx,
                     metrics_reporter,
                 );
+                effect_handler.core.memory = memory;
+                effect_handler.core.latency = latency;
                 receiver.start(ctrl_msg_chan, effect_handler).await
             }
         }
@@ -7,14 +7,18 @@ use crate::control::{
     ControlSenders, Controllable, NodeControlMsg, PipelineCtrlMsgReceiver, PipelineCtrlMsgSender,
 };
 use crate::error::{Error, TypedError};
+use crate::memory::MemoryBudget;
 use crate::node::{Node, NodeDefs, NodeId, NodeType, NodeWithPDataReceiver, NodeWithPDataSender};
 use crate::pipeline_ctrl::PipelineCtrlMsgManager;
+use crate::shutdown::{ABORT_GRACE_PERIOD, ShutdownReport, ShutdownTracker};
 use crate::terminal_state::TerminalState;
+use crate::topology::PipelineTopology;
 use crate::{exporter::ExporterWrapper, processor::ProcessorWrapper, receiver::ReceiverWrapper};
 use otap_df_config::pipeline::PipelineConfig;
 use otap_df_telemetry::reporter::MetricsReporter;
 
 use std::fmt::Debug;
+use std::time::Instant;
 use tokio::runtime::Builder;
 use tokio::task::LocalSet;
 
@@ -35,6 +39,9 @@ pub struct RuntimePipeline<PData: Debug> {
     /// A precomputed map of all node IDs to their Node trait objects (? @@@) for efficient access
     /// Indexed by NodeIndex
     nodes: NodeDefs<PData, PipeNode>,
+
+    /// Memory budget of the pdata in flight, if configured.
+    memory_budget: Option<MemoryBudget>,
 }
 
 fn report_terminal_metrics(metrics_reporter: &MetricsReporter, terminal_state: TerminalState) {
@@ -43,6 +50,12 @@ fn report_terminal_metrics(metrics_reporter: &MetricsReporter, terminal_state: T
     }
 }
 
+async fn sleep_until_opt(when: Option<Instant>) {
+    if let Some(when) = when {
+        tokio::time::sleep_until(when.into()).await;
+    }
+}
+
 /// PipeNode contains runtime-specific info.
 pub(crate) struct PipeNode {
     index: usize, // NodeIndex into the appropriate vector w/ offset precomputed
@@ -72,9 +85,17 @@ impl<PData: 'static + Debug + Clone> RuntimePipeline<PData> {
             processors,
             exporters,
             nodes,
+            memory_budget: None,
         }
     }
 
+    /// Sets the memory budget of the pdata in flight, whose usage is reported by the pipeline.
+    #[must_use]
+    pub(crate) fn with_memory_budget(mut self, memory_budget: Option<MemoryBudget>) -> Self {
+        self.memory_budget = memory_budget;
+        self
+    }
+
     /// Returns the number of nodes in the pipeline.
     #[must_use]
     pub fn node_count(&self) -> usize {
@@ -87,14 +108,24 @@ impl<PData: 'static + Debug + Clone> RuntimePipeline<PData> {
         &self.config
     }
 
+    /// Returns the graph of the pipeline, with the secrets of the node configurations masked.
+    #[must_use]
+    pub fn topology(&self) -> PipelineTopology {
+        PipelineTopology::from_config(&self.config)
+    }
+
     /// Runs the pipeline forever, starting all nodes and handling their tasks.
     /// Returns an error if any node fails to start or if any task encounters an error.
+    ///
+    /// Once all the nodes have terminated, returns a report of the shutdown sequence (see
+    /// [`crate::shutdown`]). Nodes still running [`ABORT_GRACE_PERIOD`] after the shutdown
+    /// deadline are aborted.
     pub fn run_forever(
         self,
         metrics_reporter: MetricsReporter,
         pipeline_ctrl_msg_tx: PipelineCtrlMsgSender<PData>,
         pipeline_ctrl_msg_rx: PipelineCtrlMsgReceiver<PData>,
-    ) -> Result<Vec<()>, Error> {
+    ) -> Result<ShutdownReport, Error> {
         use futures::stream::{FuturesUnordered, StreamExt};
 
         let rt = Builder::new_current_thread()
@@ -105,10 +136,28 @@ impl<PData: 'static + Debug + Clone> RuntimePipeline<PData> {
         // ToDo create an optimized version of FuturesUnordered that can be used for !Send, !Sync tasks
         let mut futures = FuturesUnordered::new();
         let mut control_senders = ControlSenders::default();
+        let shutdown_tracker = ShutdownTracker::default();
+        let mut abort_handles = Vec::with_capacity(self.node_count());
+        let in_flight_trackers: Vec<_> = self
+            .exporters
+            .iter()
+            .filter_map(ExporterWrapper::in_flight_tracker)
+            .collect();
+        let latency_recorders: Vec<_> = self
+            .processors
+            .iter()
+            .filter_map(ProcessorWrapper::latency_recorder)
+            .chain(
+                self.exporters
+                    .iter()
+                    .filter_map(ExporterWrapper::latency_recorder),
+            )
+            .collect();
 
         // Create a task for each node type and pass the pipeline ctrl msg channel to each node, so
         // they can communicate with the runtime pipeline.
-        for exporter in self.exporters {
+        for mut exporter in self.exporters {
+            exporter.set_shutdown_tracker(shutdown_tracker.clone());
             control_senders.register(
                 exporter.node_id(),
                 NodeType::Exporter,
@@ -117,16 +166,20 @@ impl<PData: 'static + Debug + Clone> RuntimePipeline<PData> {
             let pipeline_ctrl_msg_tx = pipeline_ctrl_msg_tx.clone();
             let effect_metrics_reporter = metrics_reporter.clone();
             let final_metrics_reporter = metrics_reporter.clone();
-            futures.push(local_tasks.spawn_local(async move {
+            let node_id = exporter.node_id();
+            let task = local_tasks.spawn_local(async move {
                 exporter
                     .start(pipeline_ctrl_msg_tx, effect_metrics_reporter)
                     .await
                     .map(|terminal_state| {
                         report_terminal_metrics(&final_metrics_reporter, terminal_state);
                     })
-            }));
+            });
+            abort_handles.push((node_id, task.abort_handle()));
+            futures.push(task);
         }
-        for processor in self.processors {
+        for mut processor in self.processors {
+            processor.set_shutdown_tracker(shutdown_tracker.clone());
             control_senders.register(
                 processor.node_id(),
                 NodeType::Processor,
@@ -134,11 +187,14 @@ impl<PData: 'static + Debug + Clone> RuntimePipeline<PData> {
             );
             let pipeline_ctrl_msg_tx = pipeline_ctrl_msg_tx.clone();
             let metrics_reporter = metrics_reporter.clone();
-            futures.push(local_tasks.spawn_local(async move {
+            let node_id = processor.node_id();
+            let task = local_tasks.spawn_local(async move {
                 processor
                     .start(pipeline_ctrl_msg_tx, metrics_reporter)
                     .await
-            }));
+            });
+            abort_handles.push((node_id, task.abort_handle()));
+            futures.push(task);
         }
         for receiver in self.receivers {
             control_senders.register(
@@ -149,43 +205,75 @@ impl<PData: 'static + Debug + Clone> RuntimePipeline<PData> {
             let pipeline_ctrl_msg_tx = pipeline_ctrl_msg_tx.clone();
             let effect_metrics_reporter = metrics_reporter.clone();
             let final_metrics_reporter = metrics_reporter.clone();
-            futures.push(local_tasks.spawn_local(async move {
+            let node_id = receiver.node_id();
+            let task = local_tasks.spawn_local(async move {
                 receiver
                     .start(pipeline_ctrl_msg_tx, effect_metrics_reporter)
                     .await
                     .map(|terminal_state| {
                         report_terminal_metrics(&final_metrics_reporter, terminal_state);
                     })
-            }));
+            });
+            abort_handles.push((node_id, task.abort_handle()));
+            futures.push(task);
         }
 
         // Create a task to process pipeline control messages, i.e. messages sent from nodes to
         // the pipeline engine.
+        let manager_shutdown_tracker = shutdown_tracker.clone();
+        let memory_budget = self.memory_budget;
         futures.push(local_tasks.spawn_local(async move {
             let manager = PipelineCtrlMsgManager::new(
                 pipeline_ctrl_msg_rx,
                 control_senders,
                 metrics_reporter,
-            );
+            )
+            .with_shutdown_tracker(manager_shutdown_tracker)
+            .with_in_flight_trackers(in_flight_trackers)
+            .with_memory_budget(memory_budget)
+            .with_latency_recorders(latency_recorders);
             manager.run().await
         }));
 
         rt.block_on(async {
             local_tasks
                 .run_until(async {
-                    let mut task_results = Vec::new();
+                    let mut aborted = false;
 
                     // Process each future as they complete and handle errors
-                    while let Some(result) = futures.next().await {
+                    loop {
+                        let abort_at = shutdown_tracker
+                            .deadline()
+                            .filter(|_| !aborted)
+                            .map(|deadline| deadline + ABORT_GRACE_PERIOD);
+                        let result = tokio::select! {
+                            result = futures.next() => match result {
+                                Some(result) => result,
+                                None => break,
+                            },
+                            _ = sleep_until_opt(abort_at), if abort_at.is_some() => {
+                                // Nodes outliving the shutdown deadline are forcibly stopped.
+                                for (node_id, handle) in &abort_handles {
+                                    if !handle.is_finished() {
+                                        shutdown_tracker.record_aborted(node_id);
+                                        handle.abort();
+                                    }
+                                }
+                                aborted = true;
+                                continue;
+                            }
+                        };
                         match result {
-                            Ok(Ok(res)) => {
-                                // Task completed successfully, collect its result
-                                task_results.push(res);
+                            Ok(Ok(())) => {
+                                // Task completed successfully
                             }
                             Ok(Err(e)) => {
                                 // A task returned an error
                                 return Err(e);
                             }
+                            Err(e) if aborted && e.is_cancelled() => {
+                                // Task aborted at the end of the shutdown
+                            }
                             Err(e) => {
                                 // JoinError (panic or cancellation)
                                 return Err(Error::JoinTaskError {
@@ -196,7 +284,7 @@ impl<PData: 'static + Debug + Clone> RuntimePipeline<PData> {
                             }
                         }
                     }
-                    Ok(task_results)
+                    Ok(shutdown_tracker.report())
                 })
                 .await
         })
@@ -92,4 +92,22 @@ impl<T> SharedReceiver<T> {
             SharedReceiver::MpmcReceiver(receiver) => receiver.is_empty(),
         }
     }
+
+    /// Returns the number of messages waiting in the channel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            SharedReceiver::MpscReceiver(receiver) => receiver.len(),
+            SharedReceiver::MpmcReceiver(receiver) => receiver.len(),
+        }
+    }
+
+    /// Returns the maximum number of messages the channel can hold, `None` if unbounded.
+    #[must_use]
+    pub fn capacity(&self) -> Option<usize> {
+        match self {
+            SharedReceiver::MpscReceiver(receiver) => Some(receiver.max_capacity()),
+            SharedReceiver::MpmcReceiver(receiver) => receiver.capacity(),
+        }
+    }
 }
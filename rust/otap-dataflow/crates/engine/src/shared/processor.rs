@@ -147,7 +147,7 @@ impl<PData> EffectHandler<PData> {
     pub async fn send_message(&self, data: PData) -> Result<(), TypedError<PData>> {
         match &self.default_sender {
             Some(sender) => sender
-                .send(data)
+                .send(self.core.recharge(data))
                 .await
                 .map_err(TypedError::ChannelSendError),
             None => Err(TypedError::Error(Error::ProcessorError {
@@ -170,7 +170,7 @@ impl<PData> EffectHandler<PData> {
         let port_name: PortName = port.into();
         match self.msg_senders.get(&port_name) {
             Some(sender) => sender
-                .send(data)
+                .send(self.core.recharge(data))
                 .await
                 .map_err(TypedError::ChannelSendError),
             None => Err(TypedError::Error(Error::ProcessorError {
@@ -91,7 +91,7 @@ impl<PData> ControlChannel<PData> {
 /// A `Send` implementation of the EffectHandlerTrait.
 #[derive(Clone)]
 pub struct EffectHandler<PData> {
-    core: EffectHandlerCore<PData>,
+    pub(crate) core: EffectHandlerCore<PData>,
 
     /// A sender used to forward messages from the receiver.
     /// Supports multiple named output ports.
@@ -153,10 +153,41 @@ impl<PData> EffectHandler<PData> {
     #[inline]
     pub async fn send_message(&self, data: PData) -> Result<(), TypedError<PData>> {
         match &self.default_sender {
-            Some(sender) => sender
-                .send(data)
-                .await
-                .map_err(TypedError::ChannelSendError),
+            Some(sender) => {
+                let data = self.core.admit(data).await?;
+                sender
+                    .send(data)
+                    .await
+                    .map_err(TypedError::ChannelSendError)
+            }
+            None => Err(TypedError::Error(Error::ReceiverError {
+                receiver: self.receiver_id(),
+                kind: ReceiverErrorKind::Configuration,
+                error:
+                    "Ambiguous default out port: multiple ports connected and no default configured"
+                        .to_string(),
+                source_detail: String::new(),
+            })),
+        }
+    }
+
+    /// Attempts to send a message to the default out port without waiting for channel capacity.
+    ///
+    /// Receivers reading from a source they can pause (e.g. a Kafka consumer) should use this
+    /// method and stop reading from the source while the channel is full, see the crate-level
+    /// documentation on backpressure.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypedError::ChannelSendError`] holding the message back if the channel is full
+    /// or closed, or a [`TypedError::Error`] if the default port is not configured.
+    #[inline]
+    pub fn try_send_message(&self, data: PData) -> Result<(), TypedError<PData>> {
+        match &self.default_sender {
+            Some(sender) => {
+                let data = self.core.try_admit(data)?;
+                sender.try_send(data).map_err(TypedError::ChannelSendError)
+            }
             None => Err(TypedError::Error(Error::ReceiverError {
                 receiver: self.receiver_id(),
                 kind: ReceiverErrorKind::Configuration,
@@ -176,10 +207,13 @@ impl<PData> EffectHandler<PData> {
     {
         let port_name: PortName = port.into();
         match self.msg_senders.get(&port_name) {
-            Some(sender) => sender
-                .send(data)
-                .await
-                .map_err(TypedError::ChannelSendError),
+            Some(sender) => {
+                let data = self.core.admit(data).await?;
+                sender
+                    .send(data)
+                    .await
+                    .map_err(TypedError::ChannelSendError)
+            }
             None => Err(TypedError::Error(Error::ReceiverError {
                 receiver: self.receiver_id(),
                 kind: ReceiverErrorKind::Configuration,
@@ -0,0 +1,236 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Ordered shutdown of a pipeline.
+//!
+//! A pipeline shuts down from its sources to its sinks, all nodes sharing a single global deadline:
+//!
+//! 1. The pipeline control manager sends a `Shutdown` to the receivers, which stop accepting new
+//!    data and drop their pdata senders.
+//! 2. Once all the senders feeding a processor are gone, its message channel delivers the
+//!    remaining pdata followed by a `Shutdown` carrying the global deadline, giving the processor
+//!    a chance to flush its buffered state downstream before exiting.
+//! 3. Exporters are shut down the same way once all their upstream processors have exited, and
+//!    drain their input queue until the deadline.
+//!
+//! Pdata still queued in a node's input channel when the deadline expires is dropped and accounted
+//! for in the [`ShutdownTracker`]. Nodes still running [`ABORT_GRACE_PERIOD`] after the deadline
+//! are aborted. The resulting [`ShutdownReport`] is returned by the runtime pipeline once all nodes have
+//! terminated.
+
+use crate::node::NodeId;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Delay after the shutdown deadline before the nodes still running are aborted.
+pub const ABORT_GRACE_PERIOD: Duration = Duration::from_millis(500);
+
+/// Deadline used when a node channel is closed outside an ordered shutdown (e.g. an upstream node
+/// terminated on its own).
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Shared state of an ongoing pipeline shutdown.
+///
+/// Cloning the tracker is cheap, all the clones refer to the same state.
+#[derive(Clone, Default)]
+pub struct ShutdownTracker {
+    inner: Arc<Mutex<ShutdownState>>,
+}
+
+#[derive(Default)]
+struct ShutdownState {
+    deadline: Option<Instant>,
+    dropped: BTreeMap<String, u64>,
+    aborted: Vec<String>,
+}
+
+impl ShutdownTracker {
+    /// Records the global deadline of the shutdown. The earliest deadline wins if the shutdown is
+    /// requested several times.
+    pub fn begin(&self, deadline: Instant) {
+        let mut state = self.lock();
+        state.deadline = Some(state.deadline.map_or(deadline, |dl| dl.min(deadline)));
+    }
+
+    /// Returns the global deadline, if a shutdown has been requested.
+    #[must_use]
+    pub fn deadline(&self) -> Option<Instant> {
+        self.lock().deadline
+    }
+
+    /// Returns the deadline a node should use to drain its input once its upstream nodes are gone.
+    #[must_use]
+    pub fn drain_deadline(&self) -> Instant {
+        self.deadline()
+            .unwrap_or_else(|| Instant::now() + DEFAULT_DRAIN_TIMEOUT)
+    }
+
+    /// Records pdata messages dropped by a node at the end of the shutdown.
+    pub fn record_dropped(&self, node_id: &NodeId, count: u64) {
+        if count == 0 {
+            return;
+        }
+        *self
+            .lock()
+            .dropped
+            .entry(node_id.name.to_string())
+            .or_default() += count;
+    }
+
+    /// Records a node aborted because it did not terminate before the deadline.
+    pub fn record_aborted(&self, node_id: &NodeId) {
+        self.lock().aborted.push(node_id.name.to_string());
+    }
+
+    /// Returns a summary of the shutdown.
+    #[must_use]
+    pub fn report(&self) -> ShutdownReport {
+        let state = self.lock();
+        ShutdownReport {
+            dropped: state.dropped.clone(),
+            aborted: state.aborted.clone(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ShutdownState> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Summary of a pipeline shutdown.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Number of pdata messages dropped per node name.
+    pub dropped: BTreeMap<String, u64>,
+    /// Names of the nodes aborted because they outlived the deadline.
+    pub aborted: Vec<String>,
+}
+
+impl ShutdownReport {
+    /// Returns the total number of pdata messages dropped by the pipeline.
+    #[must_use]
+    pub fn total_dropped(&self) -> u64 {
+        self.dropped.values().sum()
+    }
+
+    /// Returns `true` if all the nodes drained their input before the deadline.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.dropped.is_empty() && self.aborted.is_empty()
+    }
+}
+
+impl Display for ShutdownReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_clean() {
+            return write!(f, "all nodes drained before the deadline");
+        }
+        write!(f, "{} messages dropped", self.total_dropped())?;
+        if !self.dropped.is_empty() {
+            let per_node: Vec<String> = self
+                .dropped
+                .iter()
+                .map(|(node, count)| format!("{node}: {count}"))
+                .collect();
+            write!(f, " ({})", per_node.join(", "))?;
+        }
+        if !self.aborted.is_empty() {
+            write!(f, "; nodes aborted: {}", self.aborted.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::NodeControlMsg;
+    use crate::message::{Message, MessageChannel, Receiver};
+    use otap_df_channel::mpsc;
+
+    fn node(index: usize, name: &'static str) -> NodeId {
+        NodeId {
+            index,
+            name: name.into(),
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let tracker = ShutdownTracker::default();
+        assert!(tracker.deadline().is_none());
+        assert!(tracker.report().is_clean());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        tracker.begin(deadline);
+        tracker.begin(deadline + Duration::from_secs(5));
+        assert_eq!(tracker.deadline(), Some(deadline));
+        assert_eq!(tracker.clone().drain_deadline(), deadline);
+
+        tracker.record_dropped(&node(0, "batch"), 0);
+        tracker.record_dropped(&node(1, "exporter"), 3);
+        tracker.record_dropped(&node(1, "exporter"), 2);
+        tracker.record_aborted(&node(1, "exporter"));
+
+        let report = tracker.report();
+        assert_eq!(report.total_dropped(), 5);
+        assert!(!report.dropped.contains_key("batch"));
+        assert_eq!(
+            report.to_string(),
+            "5 messages dropped (exporter: 5); nodes aborted: exporter"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_message_channel_shutdown() {
+        let tracker = ShutdownTracker::default();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        tracker.begin(deadline);
+
+        // Upstream nodes gone: the remaining pdata is delivered, then a Shutdown carrying the
+        // global deadline.
+        let (_control_tx, control_rx) = mpsc::Channel::<NodeControlMsg<u32>>::new(4);
+        let (pdata_tx, pdata_rx) = mpsc::Channel::new(4);
+        let mut channel = MessageChannel::new(
+            Receiver::new_local_mpsc_receiver(control_rx),
+            Receiver::new_local_mpsc_receiver(pdata_rx),
+        )
+        .with_shutdown_tracker(node(0, "batch"), tracker.clone());
+        pdata_tx.send(1).expect("channel has capacity");
+        drop(pdata_tx);
+        assert!(matches!(channel.recv().await, Ok(Message::PData(1))));
+        match channel.recv().await {
+            Ok(Message::Control(NodeControlMsg::Shutdown { deadline: dl, .. })) => {
+                assert_eq!(dl, deadline);
+            }
+            _ => panic!("expected a shutdown message"),
+        }
+
+        // Deadline expired: the pdata still queued is dropped and reported.
+        let (control_tx, control_rx) = mpsc::Channel::new(4);
+        let (pdata_tx, pdata_rx) = mpsc::Channel::new(4);
+        let mut channel = MessageChannel::new(
+            Receiver::new_local_mpsc_receiver(control_rx),
+            Receiver::new_local_mpsc_receiver(pdata_rx),
+        )
+        .with_shutdown_tracker(node(1, "exporter"), tracker.clone());
+        for i in 0..3 {
+            pdata_tx.send(i).expect("channel has capacity");
+        }
+        control_tx
+            .send(NodeControlMsg::Shutdown {
+                deadline: Instant::now(),
+                reason: "test".to_owned(),
+            })
+            .expect("channel has capacity");
+        assert!(channel.recv().await.is_ok_and(|msg| msg.is_shutdown()));
+
+        let report = tracker.report();
+        assert_eq!(report.dropped.get("exporter"), Some(&3));
+        assert_eq!(report.total_dropped(), 3);
+    }
+}
@@ -104,6 +104,20 @@ impl<PData> TestContext<PData> {
             .await
     }
 
+    /// Sends a collect telemetry control message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message could not be sent.
+    pub async fn send_collect_telemetry(
+        &self,
+        metrics_reporter: MetricsReporter,
+    ) -> Result<(), SendError<NodeControlMsg<PData>>> {
+        self.control_tx
+            .send(NodeControlMsg::CollectTelemetry { metrics_reporter })
+            .await
+    }
+
     /// Sends a shutdown control message.
     ///
     /// # Errors
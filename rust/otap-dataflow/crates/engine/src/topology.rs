@@ -0,0 +1,259 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Serializable view of the graph of a pipeline.
+//!
+//! A [`PipelineTopology`] lists the nodes of a pipeline, with their plugin URN and effective
+//! configuration, and the hyper-edges connecting their out ports to the downstream nodes. It is
+//! returned by [`crate::runtime_pipeline::RuntimePipeline::topology`] for a built pipeline, or
+//! derived from a configuration with [`PipelineTopology::from_config`], and is served by the
+//! admin endpoints.
+//!
+//! The values of the configuration fields whose name suggests a secret (e.g. `password`,
+//! `api_key`, `token`) are masked, so that the topology can be exposed as is.
+//!
+//! [`materialize_config`] returns the effective configuration of a pipeline, with the same
+//! masking and the defaults of the node configurations filled in.
+
+use otap_df_config::node::{DispatchStrategy, NodeKind};
+use otap_df_config::pipeline::PipelineConfig;
+use otap_df_config::schema::find_schema;
+use otap_df_config::{Description, NodeId, PortName, Urn};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Replacement of the masked configuration values.
+pub const MASKED_VALUE: &str = "***";
+
+/// Fragments of the configuration field names holding secrets, matched case-insensitively.
+const SECRET_KEY_FRAGMENTS: [&str; 8] = [
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "authorization",
+    "credential",
+];
+
+/// Graph of a pipeline, with its nodes sorted by id and its edges by source and port.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PipelineTopology {
+    /// Nodes of the pipeline.
+    pub nodes: Vec<NodeTopology>,
+    /// Hyper-edges connecting the out ports of the nodes to the downstream nodes.
+    pub edges: Vec<EdgeTopology>,
+}
+
+/// A node of a pipeline.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeTopology {
+    /// Id of the node.
+    pub id: NodeId,
+    /// Kind of the node.
+    pub kind: NodeKind,
+    /// URN of the plugin implementing the node.
+    pub plugin_urn: Urn,
+    /// Description of the node, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Description>,
+    /// Node-specific configuration, with its secrets masked.
+    pub config: Value,
+}
+
+/// A hyper-edge from an out port of a node to one or more downstream nodes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EdgeTopology {
+    /// Id of the source node.
+    pub source: NodeId,
+    /// Out port of the source node.
+    pub port: PortName,
+    /// Ids of the downstream nodes, sorted.
+    pub destinations: Vec<NodeId>,
+    /// Strategy dispatching the pdata between the downstream nodes.
+    pub dispatch_strategy: DispatchStrategy,
+}
+
+impl PipelineTopology {
+    /// Derives the topology of a pipeline from its configuration.
+    #[must_use]
+    pub fn from_config(config: &PipelineConfig) -> Self {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        for (id, node) in config.node_iter() {
+            nodes.push(NodeTopology {
+                id: id.clone(),
+                kind: node.kind,
+                plugin_urn: node.plugin_urn.clone(),
+                description: node.description.clone(),
+                config: mask_secrets(&node.config),
+            });
+            for (port, edge) in &node.out_ports {
+                let mut destinations: Vec<NodeId> = edge.destinations.iter().cloned().collect();
+                destinations.sort();
+                edges.push(EdgeTopology {
+                    source: id.clone(),
+                    port: port.clone(),
+                    destinations,
+                    dispatch_strategy: edge.dispatch_strategy.clone(),
+                });
+            }
+        }
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        edges.sort_by(|a, b| (&a.source, &a.port).cmp(&(&b.source, &b.port)));
+        Self { nodes, edges }
+    }
+
+    /// Returns the node with the given id, if any.
+    #[must_use]
+    pub fn node(&self, id: &str) -> Option<&NodeTopology> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// Returns the edges leaving the out ports of the given node.
+    pub fn out_edges<'a>(&'a self, source: &'a str) -> impl Iterator<Item = &'a EdgeTopology> {
+        self.edges.iter().filter(move |edge| edge.source == source)
+    }
+
+    /// Returns the ids of the nodes connected to the given out port of a node, sorted.
+    #[must_use]
+    pub fn destinations(&self, source: &str, port: &str) -> Vec<&str> {
+        self.out_edges(source)
+            .filter(|edge| edge.port == port)
+            .flat_map(|edge| edge.destinations.iter().map(|d| &**d))
+            .collect()
+    }
+
+    /// Returns the ids of the nodes sending pdata to the given node, sorted and deduplicated.
+    #[must_use]
+    pub fn upstream(&self, destination: &str) -> Vec<&str> {
+        let mut upstream: Vec<&str> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.destinations.iter().any(|d| d == destination))
+            .map(|edge| &*edge.source)
+            .collect();
+        upstream.dedup();
+        upstream
+    }
+
+    /// Returns `true` if an out port of `source` is connected to `destination`.
+    #[must_use]
+    pub fn is_connected(&self, source: &str, destination: &str) -> bool {
+        self.out_edges(source)
+            .any(|edge| edge.destinations.iter().any(|d| d == destination))
+    }
+}
+
+/// Returns the effective configuration of a pipeline: the node configurations have the defaults
+/// documented by the schema of their plugin filled in (see [`crate::NODE_CONFIG_SCHEMAS`]), and
+/// their secret values masked.
+///
+/// The environment variables are expanded when the configuration is loaded, and the secret
+/// references (`${secret:...}`) are kept as is.
+#[must_use]
+pub fn materialize_config(config: &PipelineConfig) -> PipelineConfig {
+    config.map_node_configs(|_, node| {
+        match find_schema(&crate::NODE_CONFIG_SCHEMAS, &node.plugin_urn) {
+            Some(schema) => mask_secrets(&schema.materialize(&node.config)),
+            None => mask_secrets(&node.config),
+        }
+    })
+}
+
+/// Returns a copy of a node configuration whose secret values are replaced with
+/// [`MASKED_VALUE`].
+#[must_use]
+pub fn mask_secrets(config: &Value) -> Value {
+    match config {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| {
+                    let value = if is_secret_key(key) && !value.is_null() {
+                        Value::String(MASKED_VALUE.to_owned())
+                    } else {
+                        mask_secrets(value)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(mask_secrets).collect()),
+        value => value.clone(),
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase().replace('-', "_");
+    SECRET_KEY_FRAGMENTS
+        .iter()
+        .any(|fragment| key.contains(fragment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otap_df_config::pipeline::{PipelineConfigBuilder, PipelineType};
+    use serde_json::json;
+
+    fn config() -> PipelineConfig {
+        PipelineConfigBuilder::new()
+            .add_receiver("otlp", "urn:otel:otlp:receiver", None)
+            .add_processor("batch", "urn:otel:batch:processor", None)
+            .add_exporter(
+                "primary",
+                "urn:otel:otlp:exporter",
+                Some(json!({
+                    "endpoint": "http://collector:4317",
+                    "auth": {"username": "otel", "password": "hunter2", "api-key": null},
+                    "tls": [{"ca_file": "/etc/ca.pem", "client_secret": {"inline": "abc"}}],
+                })),
+            )
+            .add_exporter("debug", "urn:otel:debug:exporter", None)
+            .round_robin("otlp", "out", ["batch"])
+            .broadcast("batch", "out", ["primary", "debug"])
+            .build(PipelineType::Otap, "group", "pipeline")
+            .expect("valid pipeline")
+    }
+
+    #[test]
+    fn test_topology_from_config() {
+        let topology = PipelineTopology::from_config(&config());
+
+        let ids: Vec<&str> = topology.nodes.iter().map(|n| &*n.id).collect();
+        assert_eq!(ids, ["batch", "debug", "otlp", "primary"]);
+        assert_eq!(topology.node("batch").unwrap().kind, NodeKind::Processor);
+        assert_eq!(topology.destinations("batch", "out"), ["debug", "primary"]);
+        assert_eq!(topology.upstream("primary"), ["batch"]);
+        assert!(topology.is_connected("otlp", "batch"));
+        assert!(!topology.is_connected("otlp", "primary"));
+        assert_eq!(
+            topology
+                .out_edges("batch")
+                .next()
+                .unwrap()
+                .dispatch_strategy,
+            DispatchStrategy::Broadcast
+        );
+
+        let exporter = serde_json::to_value(topology.node("primary").unwrap()).unwrap();
+        assert_eq!(exporter["plugin_urn"], "urn:otel:otlp:exporter");
+        assert_eq!(
+            exporter["config"],
+            json!({
+                "endpoint": "http://collector:4317",
+                "auth": {"username": "otel", "password": MASKED_VALUE, "api-key": null},
+                "tls": [{"ca_file": "/etc/ca.pem", "client_secret": MASKED_VALUE}],
+            })
+        );
+
+        let materialized = materialize_config(&config());
+        let (_, primary) = materialized
+            .node_iter()
+            .find(|(id, _)| *id == "primary")
+            .unwrap();
+        assert_eq!(primary.config, exporter["config"]);
+    }
+}
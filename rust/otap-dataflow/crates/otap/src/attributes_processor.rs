@@ -10,9 +10,17 @@
 //! Supported actions (current subset):
 //! - `rename`: Renames an attribute key (non-standard deviation from the Go collector).
 //! - `delete`: Removes an attribute by key.
+//! - `insert`: Adds an attribute where it does not exist yet.
+//! - `update`: Updates an existing attribute.
+//! - `upsert`: Inserts or updates an attribute.
+//! - `hash`: Replaces the value with its hex encoded SHA-256 hash.
+//! - `lowercase` / `uppercase`: Converts a string value (non-standard).
 //!
-//! Unsupported actions are ignored if present in the config:
-//! `insert`, `upsert`, `update` (value update), `hash`, `extract`, `convert`.
+//! `insert`, `update` and `upsert` take either a literal `value` (string, number or boolean) or
+//! the key of another attribute of the same parent to copy the value from in `from_attribute`.
+//! Renames and deletes are applied first, then the other actions in the order of the config.
+//!
+//! Unsupported actions are ignored if present in the config: `extract`, `convert`.
 //! We may add support for them later.
 //!
 //! Example configuration (YAML):
@@ -25,18 +33,29 @@
 //!     destination_key: "rpc.method"       # Renames http.method to rpc.method
 //!   - key: "db.statement"
 //!     action: "delete"       # Removes db.statement attribute
+//!   - key: "deployment.environment"
+//!     action: "insert"
+//!     value: "production"
+//!   - key: "user.id"
+//!     action: "hash"
 //!   # apply_to: ["signal", "resource"]  # Optional; defaults to ["signal"]
 //! ```
 //!
 //! Implementation uses otel_arrow_rust::otap::transform::transform_attributes for
-//! efficient batch processing of Arrow record batches.
+//! efficient batch processing of Arrow record batches. The other actions are applied with
+//! Arrow compute kernels (see the `value_actions` module).
 
-use crate::{OTAP_PROCESSOR_FACTORIES, pdata::OtapPdata};
+use crate::{
+    OTAP_PROCESSOR_FACTORIES,
+    pdata::{OtapPdata, pdata_error},
+};
 use async_trait::async_trait;
 use linkme::distributed_slice;
 use otap_df_config::error::Error as ConfigError;
 use otap_df_config::experimental::SignalType;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::config::ProcessorConfig;
 use otap_df_engine::context::PipelineContext;
 use otap_df_engine::error::Error as EngineError;
@@ -52,18 +71,21 @@ use otel_arrow_rust::otap::{
     },
 };
 use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::sync::Arc;
 
 mod metrics;
+pub(crate) mod value_actions;
 use self::metrics::AttributesProcessorMetrics;
+use self::value_actions::{ActionStats, Literal, ValueAction, ValueSource};
 
 /// URN for the AttributesProcessor
 pub const ATTRIBUTES_PROCESSOR_URN: &str = "urn:otap:processor:attributes_processor";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 /// Actions that can be performed on attributes.
 #[serde(tag = "action", rename_all = "lowercase")]
 pub enum Action {
@@ -81,17 +103,72 @@ pub enum Action {
         key: String,
     },
 
+    /// Insert an attribute where it does not exist yet.
+    Insert {
+        /// The attribute key to insert.
+        key: String,
+        /// The value to insert.
+        #[serde(default)]
+        value: Option<Value>,
+        /// The key of the attribute to copy the value from.
+        #[serde(default)]
+        from_attribute: Option<String>,
+    },
+
+    /// Update an existing attribute.
+    Update {
+        /// The attribute key to update.
+        key: String,
+        /// The new value.
+        #[serde(default)]
+        value: Option<Value>,
+        /// The key of the attribute to copy the value from.
+        #[serde(default)]
+        from_attribute: Option<String>,
+    },
+
+    /// Insert an attribute, or update it if it already exists.
+    Upsert {
+        /// The attribute key to upsert.
+        key: String,
+        /// The value to set.
+        #[serde(default)]
+        value: Option<Value>,
+        /// The key of the attribute to copy the value from.
+        #[serde(default)]
+        from_attribute: Option<String>,
+    },
+
+    /// Replace the value of an attribute with its hex encoded SHA-256 hash.
+    Hash {
+        /// The attribute key to hash.
+        key: String,
+    },
+
+    /// Convert the string value of an attribute to lowercase (non-standard).
+    Lowercase {
+        /// The attribute key to convert.
+        key: String,
+    },
+
+    /// Convert the string value of an attribute to uppercase (non-standard).
+    Uppercase {
+        /// The attribute key to convert.
+        key: String,
+    },
+
     /// Other actions are accepted for forward-compatibility but ignored.
     /// These variants allow deserialization of Go-style configs without effect.
     #[serde(other)]
     Unsupported,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 /// Configuration for the AttributesProcessor.
 ///
 /// Accepts configuration in the same format as the OpenTelemetry Collector's attributes processor.
-/// Supported actions: rename (deviation), delete. Others are ignored.
+/// Supported actions: rename (deviation), delete, insert, update, upsert, hash, lowercase and
+/// uppercase (deviations). Others are ignored.
 ///
 /// You can control which attribute domains are transformed via `apply_to`.
 /// Valid values: "signal" (default), "resource", "scope".
@@ -114,6 +191,8 @@ pub struct Config {
 pub struct AttributesProcessor {
     // Pre-computed transform to avoid rebuilding per message
     transform: AttributesTransform,
+    // Value actions, applied in order after the transform
+    value_actions: Vec<ValueAction>,
     // Pre-computed flags for domain lookup
     has_resource_domain: bool,
     has_scope_domain: bool,
@@ -130,7 +209,7 @@ impl AttributesProcessor {
     #[must_use = "AttributesProcessor creation may fail and return a ConfigError"]
     pub fn from_config(config: &Value) -> Result<Self, otap_df_config::error::Error> {
         let cfg: Config =
-            serde_json::from_value(config.clone()).map_err(|e| ConfigError::InvalidUserConfig {
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
                 error: format!("Failed to parse AttributesProcessor configuration: {e}"),
             })?;
         Self::new(cfg)
@@ -140,9 +219,37 @@ impl AttributesProcessor {
     fn new(config: Config) -> Result<Self, otap_df_config::error::Error> {
         let mut renames = BTreeMap::new();
         let mut deletes = BTreeSet::new();
+        let mut value_actions = Vec::new();
 
         for action in config.actions {
             match action {
+                Action::Insert {
+                    key,
+                    value,
+                    from_attribute,
+                } => {
+                    let source = value_source(&key, value, from_attribute)?;
+                    value_actions.push(ValueAction::Insert { key, source });
+                }
+                Action::Update {
+                    key,
+                    value,
+                    from_attribute,
+                } => {
+                    let source = value_source(&key, value, from_attribute)?;
+                    value_actions.push(ValueAction::Update { key, source });
+                }
+                Action::Upsert {
+                    key,
+                    value,
+                    from_attribute,
+                } => {
+                    let source = value_source(&key, value, from_attribute)?;
+                    value_actions.push(ValueAction::Upsert { key, source });
+                }
+                Action::Hash { key } => value_actions.push(ValueAction::Hash { key }),
+                Action::Lowercase { key } => value_actions.push(ValueAction::Lowercase { key }),
+                Action::Uppercase { key } => value_actions.push(ValueAction::Uppercase { key }),
                 Action::Delete { key } => {
                     let _ = deletes.insert(key);
                 }
@@ -192,6 +299,7 @@ impl AttributesProcessor {
 
         Ok(Self {
             transform,
+            value_actions,
             has_resource_domain,
             has_scope_domain,
             has_signal_domain,
@@ -200,8 +308,10 @@ impl AttributesProcessor {
     }
 
     #[inline]
-    const fn is_noop(&self) -> bool {
-        self.transform.rename.is_none() && self.transform.delete.is_none()
+    fn is_noop(&self) -> bool {
+        self.transform.rename.is_none()
+            && self.transform.delete.is_none()
+            && self.value_actions.is_empty()
     }
 
     #[inline]
@@ -253,17 +363,18 @@ impl AttributesProcessor {
         &self,
         records: &mut OtapArrowRecords,
         signal: SignalType,
-    ) -> Result<(u64, u64), EngineError> {
+    ) -> Result<(u64, u64, ActionStats), EngineError> {
         let mut deleted_total: u64 = 0;
         let mut renamed_total: u64 = 0;
+        let mut value_totals = ActionStats::default();
+        let payloads = self.attrs_payloads(signal);
 
         // Only apply if we have transforms to apply
-        if !self.is_noop() {
-            let payloads = self.attrs_payloads(signal);
+        if self.transform.rename.is_some() || self.transform.delete.is_some() {
             for &payload_ty in payloads {
                 if let Some(rb) = records.get(payload_ty) {
                     let (rb, stats) = transform_attributes_with_stats(rb, &self.transform)
-                        .map_err(|e| engine_err(&format!("transform_attributes failed: {e}")))?;
+                        .map_err(|e| pdata_error(&format!("transform_attributes failed: {e}")))?;
                     deleted_total += stats.deleted_entries;
                     renamed_total += stats.renamed_entries;
                     records.set(payload_ty, rb);
@@ -271,7 +382,23 @@ impl AttributesProcessor {
             }
         }
 
-        Ok((deleted_total, renamed_total))
+        if !self.value_actions.is_empty() {
+            // value actions match attributes to their parents by id
+            records
+                .decode_transport_optimized_ids()
+                .map_err(|e| pdata_error(&format!("failed to decode ids: {e}")))?;
+            for action in &self.value_actions {
+                for &payload_ty in payloads {
+                    let stats = action
+                        .apply(records, payload_ty)
+                        .map_err(|e| pdata_error(&format!("attribute action failed: {e}")))?;
+                    value_totals.inserted += stats.inserted;
+                    value_totals.updated += stats.updated;
+                }
+            }
+        }
+
+        Ok((deleted_total, renamed_total, value_totals))
     }
 }
 
@@ -332,7 +459,7 @@ impl local::Processor<OtapPdata> for AttributesProcessor {
                 }
                 // Apply transform across selected domains and collect exact stats
                 match self.apply_transform_with_stats(&mut records, signal) {
-                    Ok((deleted_total, renamed_total)) => {
+                    Ok((deleted_total, renamed_total, value_totals)) => {
                         if let Some(m) = self.metrics.as_mut() {
                             if deleted_total > 0 {
                                 m.deleted_entries.add(deleted_total);
@@ -340,6 +467,12 @@ impl local::Processor<OtapPdata> for AttributesProcessor {
                             if renamed_total > 0 {
                                 m.renamed_entries.add(renamed_total);
                             }
+                            if value_totals.inserted > 0 {
+                                m.inserted_entries.add(value_totals.inserted);
+                            }
+                            if value_totals.updated > 0 {
+                                m.updated_entries.add(value_totals.updated);
+                            }
                         }
                     }
                     Err(e) => {
@@ -403,9 +536,26 @@ fn parse_apply_to(apply_to: Option<&Vec<String>>) -> HashSet<ApplyDomain> {
     set
 }
 
-fn engine_err(msg: &str) -> EngineError {
-    EngineError::PdataConversionError {
-        error: msg.to_string(),
+/// Resolves the `value` / `from_attribute` pair of an insert, update or upsert action.
+fn value_source(
+    key: &str,
+    value: Option<Value>,
+    from_attribute: Option<String>,
+) -> Result<ValueSource, ConfigError> {
+    match (value, from_attribute) {
+        (Some(value), None) => Literal::from_json(&value)
+            .map(ValueSource::Literal)
+            .ok_or_else(|| ConfigError::InvalidUserConfig {
+                error: format!(
+                    "Invalid value for attribute `{key}`: expected a string, number or boolean"
+                ),
+            }),
+        (None, Some(from_attribute)) => Ok(ValueSource::FromAttribute(from_attribute)),
+        _ => Err(ConfigError::InvalidUserConfig {
+            error: format!(
+                "Action on attribute `{key}` requires exactly one of `value` or `from_attribute`"
+            ),
+        }),
     }
 }
 
@@ -443,6 +593,12 @@ pub static ATTRIBUTES_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPd
         },
     };
 
+/// Schema of the configuration of the attributes processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static ATTRIBUTES_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(ATTRIBUTES_PROCESSOR_URN);
+
 // Pre-computed arrays for all domain combinations
 mod payload_sets {
     use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType as A;
@@ -985,6 +1141,106 @@ mod tests {
             })
             .validate(|_| async move {});
     }
+
+    #[test]
+    fn test_value_actions_applied_in_order() {
+        let input = build_logs_with_attrs(
+            vec![],
+            vec![],
+            vec![
+                KeyValue::new("user", AnyValue::new_string("alice")),
+                KeyValue::new("status", AnyValue::new_int(200)),
+            ],
+        );
+
+        let cfg = json!({
+            "actions": [
+                {"action": "insert", "key": "env", "value": "prod"},
+                {"action": "insert", "key": "user.hash", "from_attribute": "user"},
+                {"action": "hash", "key": "user.hash"},
+                {"action": "uppercase", "key": "user"},
+                {"action": "update", "key": "status", "value": 1.5},
+                {"action": "upsert", "key": "missing", "from_attribute": "nope"}
+            ],
+            "apply_to": ["signal", "resource"]
+        });
+
+        let metrics_registry_handle = MetricsRegistryHandle::new();
+        let controller_ctx = ControllerContext::new(metrics_registry_handle);
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+
+        let node = test_node("attributes-processor-test");
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let mut node_config = NodeUserConfig::new_processor_config(ATTRIBUTES_PROCESSOR_URN);
+        node_config.config = cfg;
+        let proc =
+            create_attributes_processor(pipeline_ctx, node, Arc::new(node_config), rt.config())
+                .expect("create processor");
+        let phase = rt.set_processor(proc);
+
+        phase
+            .run_test(|mut ctx| async move {
+                let mut bytes = Vec::new();
+                input.encode(&mut bytes).expect("encode");
+                let pdata_in =
+                    OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(bytes).into());
+                ctx.process(Message::PData(pdata_in))
+                    .await
+                    .expect("process");
+
+                let out = ctx.drain_pdata().await;
+                let first = out.into_iter().next().expect("one output").payload();
+                let otlp_bytes: OtlpProtoBytes = first.try_into().expect("convert to otlp");
+                let bytes = match otlp_bytes {
+                    OtlpProtoBytes::ExportLogsRequest(b) => b,
+                    _ => panic!("unexpected otlp variant"),
+                };
+                let decoded = ExportLogsServiceRequest::decode(bytes.as_slice()).expect("decode");
+
+                let res_attrs = &decoded.resource_logs[0]
+                    .resource
+                    .as_ref()
+                    .unwrap()
+                    .attributes;
+                assert_eq!(
+                    res_attrs,
+                    &vec![KeyValue::new("env", AnyValue::new_string("prod"))]
+                );
+
+                let mut log_attrs = decoded.resource_logs[0].scope_logs[0].log_records[0]
+                    .attributes
+                    .clone();
+                log_attrs.sort_by(|a, b| a.key.cmp(&b.key));
+                let sha256_of_alice =
+                    "2bd806c97f0e00af1a1fc3328fa763a9269723c8db8fac4f93af71db186d6e90";
+                assert_eq!(
+                    log_attrs,
+                    vec![
+                        KeyValue::new("env", AnyValue::new_string("prod")),
+                        KeyValue::new("status", AnyValue::new_double(1.5)),
+                        KeyValue::new("user", AnyValue::new_string("ALICE")),
+                        KeyValue::new("user.hash", AnyValue::new_string(sha256_of_alice)),
+                    ]
+                );
+            })
+            .validate(|_| async move {});
+    }
+
+    #[test]
+    fn test_value_action_config_errors() {
+        for action in [
+            json!({"action": "insert", "key": "k"}),
+            json!({"action": "update", "key": "k", "value": "v", "from_attribute": "f"}),
+            json!({"action": "upsert", "key": "k", "value": ["not", "a", "scalar"]}),
+        ] {
+            let cfg = json!({ "actions": [action] });
+            assert!(matches!(
+                AttributesProcessor::from_config(&cfg),
+                Err(ConfigError::InvalidUserConfig { .. })
+            ));
+        }
+    }
 }
 
 #[cfg(test)]
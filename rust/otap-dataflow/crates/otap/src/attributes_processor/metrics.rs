@@ -30,6 +30,14 @@ pub struct AttributesProcessorMetrics {
     #[metric(unit = "{attr}")]
     pub deleted_entries: Counter<u64>,
 
+    /// Total number of attribute entries inserted by insert/upsert actions.
+    #[metric(unit = "{attr}")]
+    pub inserted_entries: Counter<u64>,
+
+    /// Total number of attribute values changed by update/upsert/hash/case actions.
+    #[metric(unit = "{attr}")]
+    pub updated_entries: Counter<u64>,
+
     /// Number of times transforms were applied to signal-level payloads.
     #[metric(unit = "{apply}")]
     pub domains_signal: Counter<u64>,
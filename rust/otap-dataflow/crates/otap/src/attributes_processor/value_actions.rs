@@ -0,0 +1,1116 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Value actions of the attributes processor: `insert`, `update`, `upsert`, `hash`,
+//! `lowercase` and `uppercase`.
+//!
+//! Actions operate on a copy of the attribute record batch in which dictionary encoded columns
+//! are unpacked. The rows an action applies to are selected with a comparison kernel on the key
+//! column, values are replaced with the `zip` and `nullif` kernels, copied from another attribute
+//! of the same parent with `take`, and new attributes are appended with `concat_batches`.
+//!
+//! Parent ids must be decoded (see `OtapArrowRecords::decode_transport_optimized_ids`) before the
+//! actions are applied.
+
+use arrow::array::{
+    Array, ArrayRef, AsArray, BooleanArray, Datum, Float64Array, Int64Array, RecordBatch, Scalar,
+    StringArray, UInt8Array, UInt32Array, new_null_array,
+};
+use arrow::compute::kernels::{cmp, zip::zip};
+use arrow::compute::{
+    cast, concat_batches, filter_record_batch, not, nullif, prep_null_mask_filter, take,
+    take_record_batch,
+};
+use arrow::datatypes::{DataType, Field, Schema, UInt32Type};
+use arrow::error::ArrowError;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::otlp::attributes::AttributeValueType;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::schema::consts;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Value columns of an attribute record batch.
+const VALUE_COLUMNS: [&str; 6] = [
+    consts::ATTRIBUTE_STR,
+    consts::ATTRIBUTE_INT,
+    consts::ATTRIBUTE_DOUBLE,
+    consts::ATTRIBUTE_BOOL,
+    consts::ATTRIBUTE_BYTES,
+    consts::ATTRIBUTE_SER,
+];
+
+/// Literal attribute value of an `insert`, `update` or `upsert` action.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Literal {
+    Str(String),
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+impl Literal {
+    /// Converts a JSON scalar to a literal.
+    pub(crate) fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) => Some(Literal::Str(s.clone())),
+            Value::Bool(b) => Some(Literal::Bool(*b)),
+            Value::Number(n) => n
+                .as_i64()
+                .map(Literal::Int)
+                .or_else(|| n.as_f64().map(Literal::Double)),
+            _ => None,
+        }
+    }
+
+    const fn value_type(&self) -> AttributeValueType {
+        match self {
+            Literal::Str(_) => AttributeValueType::Str,
+            Literal::Int(_) => AttributeValueType::Int,
+            Literal::Double(_) => AttributeValueType::Double,
+            Literal::Bool(_) => AttributeValueType::Bool,
+        }
+    }
+
+    const fn column(&self) -> &'static str {
+        match self {
+            Literal::Str(_) => consts::ATTRIBUTE_STR,
+            Literal::Int(_) => consts::ATTRIBUTE_INT,
+            Literal::Double(_) => consts::ATTRIBUTE_DOUBLE,
+            Literal::Bool(_) => consts::ATTRIBUTE_BOOL,
+        }
+    }
+
+    /// The literal repeated `len` times.
+    fn array(&self, len: usize) -> ArrayRef {
+        match self {
+            Literal::Str(v) => Arc::new(StringArray::from(vec![v.as_str(); len])),
+            Literal::Int(v) => Arc::new(Int64Array::from(vec![*v; len])),
+            Literal::Double(v) => Arc::new(Float64Array::from(vec![*v; len])),
+            Literal::Bool(v) => Arc::new(BooleanArray::from(vec![*v; len])),
+        }
+    }
+}
+
+/// Where the value set by an `insert`, `update` or `upsert` action comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ValueSource {
+    /// A literal value.
+    Literal(Literal),
+    /// The value of another attribute of the same parent.
+    FromAttribute(String),
+}
+
+/// An action changing attribute values.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ValueAction {
+    /// Set the attribute on the parents that don't have it yet.
+    Insert { key: String, source: ValueSource },
+    /// Set the attribute on the parents that already have it.
+    Update { key: String, source: ValueSource },
+    /// Set the attribute on all parents.
+    Upsert { key: String, source: ValueSource },
+    /// Replace the value with the hex encoded SHA-256 hash of its value.
+    Hash { key: String },
+    /// Convert a string value to lowercase.
+    Lowercase { key: String },
+    /// Convert a string value to uppercase.
+    Uppercase { key: String },
+}
+
+/// Number of attribute entries changed by an action.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ActionStats {
+    pub(crate) inserted: u64,
+    pub(crate) updated: u64,
+}
+
+impl ValueAction {
+    const fn key(&self) -> &String {
+        match self {
+            ValueAction::Insert { key, .. }
+            | ValueAction::Update { key, .. }
+            | ValueAction::Upsert { key, .. }
+            | ValueAction::Hash { key }
+            | ValueAction::Lowercase { key }
+            | ValueAction::Uppercase { key } => key,
+        }
+    }
+
+    /// Applies the action to the attributes of the given payload type.
+    pub(crate) fn apply(
+        &self,
+        records: &mut OtapArrowRecords,
+        attrs_type: ArrowPayloadType,
+    ) -> Result<ActionStats, ArrowError> {
+        // exemplars keep the attributes they were recorded with, nothing is added to them
+        let inserts = matches!(
+            self,
+            ValueAction::Insert { .. } | ValueAction::Upsert { .. }
+        ) && !matches!(
+            attrs_type,
+            ArrowPayloadType::NumberDpExemplarAttrs
+                | ArrowPayloadType::HistogramDpExemplarAttrs
+                | ArrowPayloadType::ExpHistogramDpExemplarAttrs
+        );
+
+        let mut batch = match records.get(attrs_type) {
+            Some(batch) => unpack_dictionaries(batch)?,
+            None if inserts => empty_attrs_batch(parent_id_type(attrs_type)),
+            None => return Ok(ActionStats::default()),
+        };
+        let key_rows = key_mask(&batch, self.key())?;
+        let mut stats = ActionStats::default();
+
+        match self {
+            ValueAction::Update { source, .. } | ValueAction::Upsert { source, .. } => {
+                (batch, stats.updated) = update(&batch, &key_rows, source)?;
+            }
+            ValueAction::Hash { .. } => (batch, stats.updated) = hash(&batch, &key_rows)?,
+            ValueAction::Lowercase { .. } => {
+                (batch, stats.updated) = map_strings(&batch, &key_rows, str::to_lowercase)?;
+            }
+            ValueAction::Uppercase { .. } => {
+                (batch, stats.updated) = map_strings(&batch, &key_rows, str::to_uppercase)?;
+            }
+            ValueAction::Insert { .. } => {}
+        }
+        if inserts {
+            if let ValueAction::Insert { key, source } | ValueAction::Upsert { key, source } = self
+            {
+                let parents = parent_ids(records, attrs_type)?;
+                (batch, stats.inserted) = insert(&batch, &key_rows, key, source, &parents)?;
+            }
+        }
+
+        if stats != ActionStats::default() {
+            records.set(attrs_type, batch);
+        }
+        Ok(stats)
+    }
+}
+
+/// Sets the integer attribute `key` of every row of the payload holding the parents of
+/// `attrs_type` to the corresponding entry of `values`, replacing any previous value.
+///
+/// `values` has one entry per parent row. Resource and scope attributes, whose parents are not
+/// rows, are not supported.
+pub(crate) fn set_int_attribute(
+    records: &mut OtapArrowRecords,
+    attrs_type: ArrowPayloadType,
+    key: &str,
+    values: &[i64],
+) -> Result<(), ArrowError> {
+    if !matches!(parent_payload(records, attrs_type), Some((_, None))) {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "cannot set per-row attributes in {attrs_type:?}"
+        )));
+    }
+    let parents = parent_ids(records, attrs_type)?;
+    if parents.len() != values.len() {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "expected {} values, got {}",
+            parents.len(),
+            values.len()
+        )));
+    }
+
+    let batch = match records.get(attrs_type) {
+        Some(batch) => unpack_dictionaries(batch)?,
+        None => empty_attrs_batch(parent_id_type(attrs_type)),
+    };
+    let batch = filter_record_batch(&batch, &not(&key_mask(&batch, key)?)?)?;
+    let batch = match batch.column_by_name(consts::ATTRIBUTE_INT) {
+        Some(_) => batch,
+        None => with_column(
+            &batch,
+            consts::ATTRIBUTE_INT,
+            new_null_array(&DataType::Int64, batch.num_rows()),
+        )?,
+    };
+
+    let len = values.len();
+    let schema = batch.schema();
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match field.name().as_str() {
+            consts::PARENT_ID => cast(&UInt32Array::from(parents.clone()), field.data_type()),
+            consts::ATTRIBUTE_KEY => Ok(Arc::new(StringArray::from(vec![key; len])) as _),
+            consts::ATTRIBUTE_TYPE => {
+                Ok(Arc::new(UInt8Array::from(vec![AttributeValueType::Int as u8; len])) as _)
+            }
+            consts::ATTRIBUTE_INT => cast(&Int64Array::from(values.to_vec()), field.data_type()),
+            _ => Ok(new_null_array(field.data_type(), len)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let new_rows = RecordBatch::try_new(schema.clone(), columns)?;
+    records.set(attrs_type, concat_batches(&schema, [&batch, &new_rows])?);
+    Ok(())
+}
+
+/// Appends string attributes given as `(parent id, key, value)` to the attributes of
+/// `attrs_type`, without checking whether their parents already have them.
+pub(crate) fn append_str_attributes(
+    records: &mut OtapArrowRecords,
+    attrs_type: ArrowPayloadType,
+    rows: &[(u32, String, String)],
+) -> Result<(), ArrowError> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let batch = match records.get(attrs_type) {
+        Some(batch) => unpack_dictionaries(batch)?,
+        None => empty_attrs_batch(parent_id_type(attrs_type)),
+    };
+    let batch = match batch.column_by_name(consts::ATTRIBUTE_STR) {
+        Some(_) => batch,
+        None => with_column(
+            &batch,
+            consts::ATTRIBUTE_STR,
+            new_null_array(&DataType::Utf8, batch.num_rows()),
+        )?,
+    };
+
+    let len = rows.len();
+    let schema = batch.schema();
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match field.name().as_str() {
+            consts::PARENT_ID => cast(
+                &rows.iter().map(|row| row.0).collect::<UInt32Array>(),
+                field.data_type(),
+            ),
+            consts::ATTRIBUTE_KEY => Ok(Arc::new(
+                rows.iter()
+                    .map(|row| Some(row.1.as_str()))
+                    .collect::<StringArray>(),
+            ) as _),
+            consts::ATTRIBUTE_TYPE => {
+                Ok(Arc::new(UInt8Array::from(vec![AttributeValueType::Str as u8; len])) as _)
+            }
+            consts::ATTRIBUTE_STR => cast(
+                &rows
+                    .iter()
+                    .map(|row| Some(row.2.as_str()))
+                    .collect::<StringArray>(),
+                field.data_type(),
+            ),
+            _ => Ok(new_null_array(field.data_type(), len)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let new_rows = RecordBatch::try_new(schema.clone(), columns)?;
+    records.set(attrs_type, concat_batches(&schema, [&batch, &new_rows])?);
+    Ok(())
+}
+
+/// Overwrites the values of the attribute on the rows it is set on.
+fn update(
+    batch: &RecordBatch,
+    rows: &BooleanArray,
+    source: &ValueSource,
+) -> Result<(RecordBatch, u64), ArrowError> {
+    match source {
+        ValueSource::Literal(literal) => {
+            let count = rows.true_count();
+            if count == 0 {
+                return Ok((batch.clone(), 0));
+            }
+            let values = Scalar::new(literal.array(1));
+            let batch = overwrite(batch, rows, literal.value_type(), literal.column(), &values)?;
+            Ok((batch, count as u64))
+        }
+        ValueSource::FromAttribute(from) => {
+            let sources = first_row_by_parent(batch, &key_mask(batch, from)?)?;
+            let parent_ids = parent_id_column(batch)?;
+            let mut count = 0;
+            let indices: UInt32Array = (0..batch.num_rows())
+                .map(|row| {
+                    let source = (rows.value(row) && parent_ids.is_valid(row))
+                        .then(|| sources.get(&parent_ids.value(row)))
+                        .flatten();
+                    match source {
+                        Some(&source) => {
+                            count += 1;
+                            source
+                        }
+                        None => row as u32,
+                    }
+                })
+                .collect();
+            if count == 0 {
+                return Ok((batch.clone(), 0));
+            }
+
+            // copy type and values of the source rows, keep parent ids and keys
+            let schema = batch.schema();
+            let columns = schema
+                .fields()
+                .iter()
+                .zip(batch.columns())
+                .map(|(field, column)| match field.name().as_str() {
+                    consts::PARENT_ID | consts::ATTRIBUTE_KEY => Ok(column.clone()),
+                    _ => take(column, &indices, None),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((RecordBatch::try_new(schema, columns)?, count))
+        }
+    }
+}
+
+/// Adds the attribute to the parents that don't have it yet.
+fn insert(
+    batch: &RecordBatch,
+    key_rows: &BooleanArray,
+    key: &str,
+    source: &ValueSource,
+    parents: &[u32],
+) -> Result<(RecordBatch, u64), ArrowError> {
+    let parent_ids = parent_id_column(batch)?;
+    let with_key: HashSet<u32> = (0..batch.num_rows())
+        .filter(|&row| key_rows.value(row) && parent_ids.is_valid(row))
+        .map(|row| parent_ids.value(row))
+        .collect();
+    let missing: Vec<u32> = parents
+        .iter()
+        .copied()
+        .filter(|parent| !with_key.contains(parent))
+        .collect();
+    if missing.is_empty() {
+        return Ok((batch.clone(), 0));
+    }
+
+    let (batch, new_rows) = match source {
+        ValueSource::Literal(literal) => {
+            let batch = match batch.column_by_name(literal.column()) {
+                Some(_) => batch.clone(),
+                None => with_column(
+                    batch,
+                    literal.column(),
+                    new_null_array(literal.array(0).data_type(), batch.num_rows()),
+                )?,
+            };
+            let len = missing.len();
+            let schema = batch.schema();
+            let columns = schema
+                .fields()
+                .iter()
+                .map(|field| match field.name().as_str() {
+                    consts::PARENT_ID => {
+                        cast(&UInt32Array::from(missing.clone()), field.data_type())
+                    }
+                    consts::ATTRIBUTE_KEY => Ok(Arc::new(StringArray::from(vec![key; len])) as _),
+                    consts::ATTRIBUTE_TYPE => {
+                        Ok(Arc::new(UInt8Array::from(vec![literal.value_type() as u8; len])) as _)
+                    }
+                    name if name == literal.column() => Ok(literal.array(len)),
+                    _ => Ok(new_null_array(field.data_type(), len)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let new_rows = RecordBatch::try_new(schema, columns)?;
+            (batch, new_rows)
+        }
+        ValueSource::FromAttribute(from) => {
+            let sources = first_row_by_parent(batch, &key_mask(batch, from)?)?;
+            let indices: UInt32Array = missing
+                .iter()
+                .filter_map(|parent| sources.get(parent).copied())
+                .collect();
+            if indices.is_empty() {
+                return Ok((batch.clone(), 0));
+            }
+            let new_rows = take_record_batch(batch, &indices)?;
+            let keys = Arc::new(StringArray::from(vec![key; new_rows.num_rows()]));
+            let new_rows = with_column(&new_rows, consts::ATTRIBUTE_KEY, keys)?;
+            (batch.clone(), new_rows)
+        }
+    };
+
+    let inserted = new_rows.num_rows() as u64;
+    Ok((
+        concat_batches(&batch.schema(), [&batch, &new_rows])?,
+        inserted,
+    ))
+}
+
+/// Replaces the values of the selected rows with the hash of their value.
+pub(crate) fn hash(
+    batch: &RecordBatch,
+    rows: &BooleanArray,
+) -> Result<(RecordBatch, u64), ArrowError> {
+    if rows.true_count() == 0 {
+        return Ok((batch.clone(), 0));
+    }
+    let values = Values::new(batch)?;
+    let hashes: StringArray = (0..batch.num_rows())
+        .map(|row| {
+            rows.value(row)
+                .then(|| values.bytes(row))
+                .flatten()
+                .map(|bytes| hex(&Sha256::digest(&bytes)))
+        })
+        .collect();
+    let hashed = BooleanArray::from(
+        (0..hashes.len())
+            .map(|row| hashes.is_valid(row))
+            .collect::<Vec<_>>(),
+    );
+    let count = hashed.true_count();
+    if count == 0 {
+        return Ok((batch.clone(), 0));
+    }
+    let batch = overwrite(
+        batch,
+        &hashed,
+        AttributeValueType::Str,
+        consts::ATTRIBUTE_STR,
+        &hashes,
+    )?;
+    Ok((batch, count as u64))
+}
+
+/// Replaces the string values of the selected rows with `f(value)`.
+pub(crate) fn map_strings(
+    batch: &RecordBatch,
+    rows: &BooleanArray,
+    f: impl Fn(&str) -> String,
+) -> Result<(RecordBatch, u64), ArrowError> {
+    let Some(strings) = batch.column_by_name(consts::ATTRIBUTE_STR) else {
+        return Ok((batch.clone(), 0));
+    };
+    let strings = strings.as_string::<i32>();
+    let mut count = 0;
+    let mapped: StringArray = strings
+        .iter()
+        .enumerate()
+        .map(|(row, value)| match value {
+            Some(value) if rows.value(row) => {
+                count += 1;
+                Some(f(value))
+            }
+            value => value.map(str::to_string),
+        })
+        .collect();
+    if count == 0 {
+        return Ok((batch.clone(), 0));
+    }
+    let batch = with_column(batch, consts::ATTRIBUTE_STR, Arc::new(mapped))?;
+    Ok((batch, count))
+}
+
+/// Sets the type of the selected rows to `value_type`, their value in `column` to `values`, and
+/// clears their other value columns.
+fn overwrite(
+    batch: &RecordBatch,
+    rows: &BooleanArray,
+    value_type: AttributeValueType,
+    column: &str,
+    values: &dyn Datum,
+) -> Result<RecordBatch, ArrowError> {
+    let types = batch
+        .column_by_name(consts::ATTRIBUTE_TYPE)
+        .ok_or_else(|| ArrowError::SchemaError("missing attribute type column".into()))?;
+    let types = zip(
+        rows,
+        &Scalar::new(UInt8Array::from(vec![value_type as u8])),
+        types,
+    )?;
+    let mut batch = with_column(batch, consts::ATTRIBUTE_TYPE, types)?;
+
+    for name in VALUE_COLUMNS {
+        let current = batch.column_by_name(name).cloned();
+        if name == column {
+            let current = match current {
+                Some(current) => current,
+                None => new_null_array(values.get().0.data_type(), batch.num_rows()),
+            };
+            batch = with_column(&batch, name, zip(rows, values, &current)?)?;
+        } else if let Some(current) = current {
+            batch = with_column(&batch, name, nullif(&current, rows)?)?;
+        }
+    }
+    Ok(batch)
+}
+
+/// Typed access to the values of an attribute record batch.
+struct Values<'a> {
+    types: &'a UInt8Array,
+    str: Option<&'a StringArray>,
+    int: Option<&'a Int64Array>,
+    double: Option<&'a Float64Array>,
+    bool: Option<&'a BooleanArray>,
+    bytes: Option<&'a arrow::array::BinaryArray>,
+    ser: Option<&'a arrow::array::BinaryArray>,
+}
+
+impl<'a> Values<'a> {
+    fn new(batch: &'a RecordBatch) -> Result<Self, ArrowError> {
+        let column = |name| batch.column_by_name(name);
+        Ok(Self {
+            types: column(consts::ATTRIBUTE_TYPE)
+                .and_then(|c| c.as_primitive_opt())
+                .ok_or_else(|| ArrowError::SchemaError("invalid attribute type column".into()))?,
+            str: column(consts::ATTRIBUTE_STR).and_then(|c| c.as_string_opt()),
+            int: column(consts::ATTRIBUTE_INT).and_then(|c| c.as_primitive_opt()),
+            double: column(consts::ATTRIBUTE_DOUBLE).and_then(|c| c.as_primitive_opt()),
+            bool: column(consts::ATTRIBUTE_BOOL).and_then(|c| c.as_boolean_opt()),
+            bytes: column(consts::ATTRIBUTE_BYTES).and_then(|c| c.as_binary_opt()),
+            ser: column(consts::ATTRIBUTE_SER).and_then(|c| c.as_binary_opt()),
+        })
+    }
+
+    /// Byte representation of the value of a row, as hashed by the `hash` action.
+    fn bytes(&self, row: usize) -> Option<Vec<u8>> {
+        fn valid<A: Array>(array: Option<&A>, row: usize) -> Option<&A> {
+            array.filter(|a| a.is_valid(row))
+        }
+        let value_type = self.types.value(row);
+        if value_type == AttributeValueType::Str as u8 {
+            valid(self.str, row).map(|a| a.value(row).as_bytes().to_vec())
+        } else if value_type == AttributeValueType::Int as u8 {
+            valid(self.int, row).map(|a| a.value(row).to_be_bytes().to_vec())
+        } else if value_type == AttributeValueType::Double as u8 {
+            valid(self.double, row).map(|a| a.value(row).to_bits().to_be_bytes().to_vec())
+        } else if value_type == AttributeValueType::Bool as u8 {
+            valid(self.bool, row).map(|a| vec![a.value(row) as u8])
+        } else if value_type == AttributeValueType::Bytes as u8 {
+            valid(self.bytes, row).map(|a| a.value(row).to_vec())
+        } else if value_type == AttributeValueType::Map as u8
+            || value_type == AttributeValueType::Slice as u8
+        {
+            valid(self.ser, row).map(|a| a.value(row).to_vec())
+        } else {
+            None
+        }
+    }
+}
+
+/// Lowercase hexadecimal encoding of `bytes`.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Rows whose key is `key`.
+fn key_mask(batch: &RecordBatch, key: &str) -> Result<BooleanArray, ArrowError> {
+    match batch.column_by_name(consts::ATTRIBUTE_KEY) {
+        Some(keys) => Ok(prep_null_mask_filter(&cmp::eq(
+            keys,
+            &Scalar::new(StringArray::from(vec![key])),
+        )?)),
+        None => Ok(BooleanArray::from(vec![false; batch.num_rows()])),
+    }
+}
+
+fn parent_id_column(batch: &RecordBatch) -> Result<UInt32Array, ArrowError> {
+    let parent_ids = batch
+        .column_by_name(consts::PARENT_ID)
+        .ok_or_else(|| ArrowError::SchemaError("missing parent_id column".into()))?;
+    Ok(cast(parent_ids, &DataType::UInt32)?
+        .as_primitive::<UInt32Type>()
+        .clone())
+}
+
+/// First selected row of each parent.
+fn first_row_by_parent(
+    batch: &RecordBatch,
+    rows: &BooleanArray,
+) -> Result<HashMap<u32, u32>, ArrowError> {
+    let parent_ids = parent_id_column(batch)?;
+    let mut first_rows = HashMap::new();
+    for row in 0..batch.num_rows() {
+        if rows.value(row) && parent_ids.is_valid(row) {
+            let _ = first_rows
+                .entry(parent_ids.value(row))
+                .or_insert(row as u32);
+        }
+    }
+    Ok(first_rows)
+}
+
+/// Payload type holding the parents of the given attributes payload type, and the struct column
+/// holding their id (`None` for the `id` column of the payload).
+fn parent_payload(
+    records: &OtapArrowRecords,
+    attrs_type: ArrowPayloadType,
+) -> Option<(ArrowPayloadType, Option<&'static str>)> {
+    use ArrowPayloadType as A;
+    let root = match records {
+        OtapArrowRecords::Logs(_) => A::Logs,
+        OtapArrowRecords::Metrics(_) => A::UnivariateMetrics,
+        OtapArrowRecords::Traces(_) => A::Spans,
+    };
+    let parent = match attrs_type {
+        A::ResourceAttrs => return Some((root, Some(consts::RESOURCE))),
+        A::ScopeAttrs => return Some((root, Some(consts::SCOPE))),
+        A::LogAttrs | A::SpanAttrs | A::MetricAttrs => root,
+        A::SpanEventAttrs => A::SpanEvents,
+        A::SpanLinkAttrs => A::SpanLinks,
+        A::NumberDpAttrs => A::NumberDataPoints,
+        A::SummaryDpAttrs => A::SummaryDataPoints,
+        A::HistogramDpAttrs => A::HistogramDataPoints,
+        A::ExpHistogramDpAttrs => A::ExpHistogramDataPoints,
+        A::NumberDpExemplarAttrs => A::NumberDpExemplars,
+        A::HistogramDpExemplarAttrs => A::HistogramDpExemplars,
+        A::ExpHistogramDpExemplarAttrs => A::ExpHistogramDpExemplars,
+        _ => return None,
+    };
+    Some((parent, None))
+}
+
+/// Type of the `parent_id` column of the given attributes payload type.
+const fn parent_id_type(attrs_type: ArrowPayloadType) -> DataType {
+    match attrs_type {
+        ArrowPayloadType::ResourceAttrs
+        | ArrowPayloadType::ScopeAttrs
+        | ArrowPayloadType::LogAttrs
+        | ArrowPayloadType::SpanAttrs
+        | ArrowPayloadType::MetricAttrs => DataType::UInt16,
+        _ => DataType::UInt32,
+    }
+}
+
+/// Ids of all the parents the attributes of `attrs_type` can belong to.
+///
+/// Rows are only given an id when they have attributes, so ids are first assigned to the rows of
+/// the parent payload that don't have one.
+pub(crate) fn parent_ids(
+    records: &mut OtapArrowRecords,
+    attrs_type: ArrowPayloadType,
+) -> Result<Vec<u32>, ArrowError> {
+    let Some((payload_type, struct_column)) = parent_payload(records, attrs_type) else {
+        return Ok(Vec::new());
+    };
+    let Some(batch) = records.get(payload_type) else {
+        return Ok(Vec::new());
+    };
+
+    if let Some(struct_column) = struct_column {
+        let Some(ids) = batch
+            .column_by_name(struct_column)
+            .and_then(|c| c.as_struct_opt())
+            .and_then(|s| s.column_by_name(consts::ID))
+        else {
+            return Ok(Vec::new());
+        };
+        let ids = cast(ids, &DataType::UInt32)?;
+        let mut seen = HashSet::new();
+        return Ok(ids
+            .as_primitive::<UInt32Type>()
+            .iter()
+            .flatten()
+            .filter(|id| seen.insert(*id))
+            .collect());
+    }
+
+    let (id_type, ids): (DataType, Vec<Option<u32>>) = match batch.column_by_name(consts::ID) {
+        Some(ids) => (
+            ids.data_type().clone(),
+            cast(ids, &DataType::UInt32)?
+                .as_primitive::<UInt32Type>()
+                .iter()
+                .collect(),
+        ),
+        None => (parent_id_type(attrs_type), vec![None; batch.num_rows()]),
+    };
+    if ids.iter().all(Option::is_some) {
+        return Ok(ids.into_iter().flatten().collect());
+    }
+
+    let max_id = match id_type {
+        DataType::UInt16 => u64::from(u16::MAX),
+        _ => u64::from(u32::MAX),
+    };
+    let mut next_id = ids
+        .iter()
+        .flatten()
+        .max()
+        .map_or(0, |id| u64::from(*id) + 1);
+    let mut assigned = Vec::with_capacity(ids.len());
+    for id in ids {
+        match id {
+            Some(id) => assigned.push(id),
+            None if next_id <= max_id => {
+                assigned.push(next_id as u32);
+                next_id += 1;
+            }
+            None => {
+                return Err(ArrowError::ComputeError(format!(
+                    "no {id_type} id left to assign to {payload_type:?} rows"
+                )));
+            }
+        }
+    }
+    let id_column = cast(&UInt32Array::from(assigned.clone()), &id_type)?;
+    let batch = with_column(batch, consts::ID, id_column)?;
+    records.set(payload_type, batch);
+    Ok(assigned)
+}
+
+/// Casts the dictionary encoded columns of `batch` to their value type.
+pub(crate) fn unpack_dictionaries(batch: &RecordBatch) -> Result<RecordBatch, ArrowError> {
+    let mut unpacked = batch.clone();
+    for (field, column) in batch.schema().fields().iter().zip(batch.columns()) {
+        if let DataType::Dictionary(_, values) = field.data_type() {
+            unpacked = with_column(&unpacked, field.name(), cast(column, values)?)?;
+        }
+    }
+    Ok(unpacked)
+}
+
+/// Empty attribute record batch with the required columns.
+fn empty_attrs_batch(parent_id_type: DataType) -> RecordBatch {
+    let schema = Schema::new(vec![
+        plain_encoded(Field::new(consts::PARENT_ID, parent_id_type, false)),
+        Field::new(consts::ATTRIBUTE_KEY, DataType::Utf8, false),
+        Field::new(consts::ATTRIBUTE_TYPE, DataType::UInt8, false),
+    ]);
+    RecordBatch::new_empty(Arc::new(schema))
+}
+
+fn plain_encoded(field: Field) -> Field {
+    field.with_metadata(
+        [(
+            consts::metadata::COLUMN_ENCODING.to_string(),
+            consts::metadata::encodings::PLAIN.to_string(),
+        )]
+        .into(),
+    )
+}
+
+/// Replaces the column `name` of `batch`, or appends it if the batch doesn't have it.
+fn with_column(
+    batch: &RecordBatch,
+    name: &str,
+    column: ArrayRef,
+) -> Result<RecordBatch, ArrowError> {
+    let schema = batch.schema();
+    let mut fields = schema.fields().to_vec();
+    let mut columns = batch.columns().to_vec();
+    match schema.index_of(name) {
+        Ok(index) => {
+            let field = fields[index]
+                .as_ref()
+                .clone()
+                .with_data_type(column.data_type().clone())
+                .with_nullable(fields[index].is_nullable() || column.null_count() > 0);
+            fields[index] = Arc::new(field);
+            columns[index] = column;
+        }
+        Err(_) => {
+            let field = Field::new(name, column.data_type().clone(), true);
+            let field = match name {
+                consts::ID | consts::PARENT_ID => plain_encoded(field),
+                _ => field,
+            };
+            fields.push(Arc::new(field));
+            columns.push(column);
+        }
+    }
+    RecordBatch::try_new(
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())),
+        columns,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::attributes::{AttributeIter, AttributeValue};
+    use arrow::array::{StructArray, UInt16Array};
+    use otel_arrow_rust::otap::Logs;
+
+    /// Logs batch with 3 log records of resource 0; log 2 has no attributes (and no id).
+    fn logs() -> OtapArrowRecords {
+        let resource = StructArray::from(vec![(
+            Arc::new(plain_encoded(Field::new(
+                consts::ID,
+                DataType::UInt16,
+                true,
+            ))),
+            Arc::new(UInt16Array::from(vec![0, 0, 0])) as ArrayRef,
+        )]);
+        let root = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                plain_encoded(Field::new(consts::ID, DataType::UInt16, true)),
+                Field::new(consts::RESOURCE, resource.data_type().clone(), true),
+            ])),
+            vec![
+                Arc::new(UInt16Array::from(vec![Some(0), Some(1), None])),
+                Arc::new(resource),
+            ],
+        )
+        .unwrap();
+
+        let dict = DataType::Dictionary(Box::new(DataType::UInt8), Box::new(DataType::Utf8));
+        let attrs = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                plain_encoded(Field::new(consts::PARENT_ID, DataType::UInt16, false)),
+                Field::new(consts::ATTRIBUTE_KEY, dict.clone(), false),
+                Field::new(consts::ATTRIBUTE_TYPE, DataType::UInt8, false),
+                Field::new(consts::ATTRIBUTE_STR, dict.clone(), true),
+                Field::new(consts::ATTRIBUTE_INT, DataType::Int64, true),
+            ])),
+            vec![
+                Arc::new(UInt16Array::from(vec![0, 0, 1])),
+                cast(&StringArray::from(vec!["user", "status", "user"]), &dict).unwrap(),
+                Arc::new(UInt8Array::from(vec![1, 2, 1])),
+                cast(
+                    &StringArray::from(vec![Some("Alice"), None, Some("Bob")]),
+                    &dict,
+                )
+                .unwrap(),
+                Arc::new(Int64Array::from(vec![None, Some(200), None])),
+            ],
+        )
+        .unwrap();
+
+        let mut records = OtapArrowRecords::Logs(Logs::default());
+        records.set(ArrowPayloadType::Logs, root);
+        records.set(ArrowPayloadType::LogAttrs, attrs);
+        records
+    }
+
+    /// Attributes of the given payload, sorted by parent id and key.
+    fn attributes(records: &OtapArrowRecords, attrs_type: ArrowPayloadType) -> Vec<String> {
+        let batch = records.get(attrs_type).unwrap();
+        let mut attrs: Vec<_> = AttributeIter::try_new(batch)
+            .unwrap()
+            .map(|attr| {
+                let value = match attr.value {
+                    AttributeValue::Str(s) => s.to_string(),
+                    AttributeValue::Int(i) => i.to_string(),
+                    AttributeValue::Double(d) => d.to_string(),
+                    AttributeValue::Bool(b) => b.to_string(),
+                    other => format!("{other:?}"),
+                };
+                format!("{}:{}={}", attr.parent_id, attr.key, value)
+            })
+            .collect();
+        attrs.sort();
+        attrs
+    }
+
+    fn apply(action: ValueAction) -> (OtapArrowRecords, ActionStats) {
+        let mut records = logs();
+        let stats = action
+            .apply(&mut records, ArrowPayloadType::LogAttrs)
+            .unwrap();
+        (records, stats)
+    }
+
+    fn literal(value: Literal) -> ValueSource {
+        ValueSource::Literal(value)
+    }
+
+    #[test]
+    fn test_update_literal_changes_type() {
+        let (records, stats) = apply(ValueAction::Update {
+            key: "status".into(),
+            source: literal(Literal::Str("ok".into())),
+        });
+        assert_eq!(
+            stats,
+            ActionStats {
+                inserted: 0,
+                updated: 1
+            }
+        );
+        assert_eq!(
+            attributes(&records, ArrowPayloadType::LogAttrs),
+            ["0:status=ok", "0:user=Alice", "1:user=Bob"]
+        );
+    }
+
+    #[test]
+    fn test_insert_assigns_ids_to_rows_without_attributes() {
+        let (records, stats) = apply(ValueAction::Insert {
+            key: "env".into(),
+            source: literal(Literal::Bool(true)),
+        });
+        assert_eq!(
+            stats,
+            ActionStats {
+                inserted: 3,
+                updated: 0
+            }
+        );
+        assert_eq!(
+            attributes(&records, ArrowPayloadType::LogAttrs),
+            [
+                "0:env=true",
+                "0:status=200",
+                "0:user=Alice",
+                "1:env=true",
+                "1:user=Bob",
+                "2:env=true"
+            ]
+        );
+        let ids = records
+            .get(ArrowPayloadType::Logs)
+            .unwrap()
+            .column_by_name(consts::ID)
+            .unwrap();
+        assert_eq!(ids.null_count(), 0);
+    }
+
+    #[test]
+    fn test_upsert_and_copy_from_attribute() {
+        let (records, stats) = apply(ValueAction::Upsert {
+            key: "status".into(),
+            source: literal(Literal::Int(500)),
+        });
+        assert_eq!(
+            stats,
+            ActionStats {
+                inserted: 2,
+                updated: 1
+            }
+        );
+        assert_eq!(
+            attributes(&records, ArrowPayloadType::LogAttrs),
+            [
+                "0:status=500",
+                "0:user=Alice",
+                "1:status=500",
+                "1:user=Bob",
+                "2:status=500"
+            ]
+        );
+
+        let (records, stats) = apply(ValueAction::Insert {
+            key: "user.copy".into(),
+            source: ValueSource::FromAttribute("user".into()),
+        });
+        assert_eq!(
+            stats,
+            ActionStats {
+                inserted: 2,
+                updated: 0
+            }
+        );
+        assert_eq!(
+            attributes(&records, ArrowPayloadType::LogAttrs),
+            [
+                "0:status=200",
+                "0:user.copy=Alice",
+                "0:user=Alice",
+                "1:user.copy=Bob",
+                "1:user=Bob"
+            ]
+        );
+
+        // update from an attribute the parent doesn't have is a no-op
+        let (records, stats) = apply(ValueAction::Update {
+            key: "user".into(),
+            source: ValueSource::FromAttribute("status".into()),
+        });
+        assert_eq!(
+            stats,
+            ActionStats {
+                inserted: 0,
+                updated: 1
+            }
+        );
+        assert_eq!(
+            attributes(&records, ArrowPayloadType::LogAttrs),
+            ["0:status=200", "0:user=200", "1:user=Bob"]
+        );
+    }
+
+    #[test]
+    fn test_hash_and_case_conversion() {
+        let (records, stats) = apply(ValueAction::Hash { key: "user".into() });
+        assert_eq!(stats.updated, 2);
+        let attrs = attributes(&records, ArrowPayloadType::LogAttrs);
+        assert_eq!(
+            attrs[1],
+            format!("0:user={}", hex(&Sha256::digest("Alice")))
+        );
+
+        let (records, _) = apply(ValueAction::Hash {
+            key: "status".into(),
+        });
+        assert_eq!(
+            attributes(&records, ArrowPayloadType::LogAttrs)[0],
+            format!("0:status={}", hex(&Sha256::digest(200i64.to_be_bytes())))
+        );
+
+        let (records, stats) = apply(ValueAction::Uppercase { key: "user".into() });
+        assert_eq!(stats.updated, 2);
+        assert_eq!(
+            attributes(&records, ArrowPayloadType::LogAttrs),
+            ["0:status=200", "0:user=ALICE", "1:user=BOB"]
+        );
+
+        // values of other types are left untouched
+        let (_, stats) = apply(ValueAction::Lowercase {
+            key: "status".into(),
+        });
+        assert_eq!(stats, ActionStats::default());
+    }
+
+    #[test]
+    fn test_set_int_attribute_per_row() {
+        let mut records = logs();
+        let rows = records.get(ArrowPayloadType::Logs).unwrap().num_rows();
+        let values: Vec<i64> = (1..=rows as i64).collect();
+        set_int_attribute(&mut records, ArrowPayloadType::LogAttrs, "n", &values).unwrap();
+        set_int_attribute(&mut records, ArrowPayloadType::LogAttrs, "n", &values).unwrap();
+
+        let attrs = records.get(ArrowPayloadType::LogAttrs).unwrap();
+        let n_rows = key_mask(attrs, "n").unwrap();
+        assert_eq!(n_rows.true_count(), rows, "previous values are replaced");
+        let ints = cast(
+            attrs.column_by_name(consts::ATTRIBUTE_INT).unwrap(),
+            &DataType::Int64,
+        )
+        .unwrap();
+        let mut set: Vec<i64> = (0..attrs.num_rows())
+            .filter(|row| n_rows.value(*row))
+            .map(|row| {
+                ints.as_primitive::<arrow::datatypes::Int64Type>()
+                    .value(row)
+            })
+            .collect();
+        set.sort();
+        assert_eq!(set, values);
+
+        assert!(
+            set_int_attribute(&mut records, ArrowPayloadType::ResourceAttrs, "n", &values).is_err()
+        );
+    }
+
+    #[test]
+    fn test_append_str_attributes() {
+        let mut records = logs();
+        append_str_attributes(
+            &mut records,
+            ArrowPayloadType::ResourceAttrs,
+            &[(0, "k8s.pod.name".into(), "web-0".into())],
+        )
+        .unwrap();
+        assert_eq!(
+            attributes(&records, ArrowPayloadType::ResourceAttrs),
+            ["0:k8s.pod.name=web-0"]
+        );
+    }
+
+    #[test]
+    fn test_insert_creates_missing_attrs_payload() {
+        let mut records = logs();
+        let stats = ValueAction::Insert {
+            key: "service.name".into(),
+            source: literal(Literal::Str("api".into())),
+        }
+        .apply(&mut records, ArrowPayloadType::ResourceAttrs)
+        .unwrap();
+        assert_eq!(stats.inserted, 1);
+        assert_eq!(
+            attributes(&records, ArrowPayloadType::ResourceAttrs),
+            ["0:service.name=api"]
+        );
+    }
+}
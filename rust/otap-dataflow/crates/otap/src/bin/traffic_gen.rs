@@ -0,0 +1,512 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Synthetic traffic generator used for capacity testing of OTAP pipelines.
+//!
+//! The generator produces configurable log, trace, and metric traffic (rate, batch size,
+//! attribute count, attribute cardinality, and attribute value size) and sends it to a running
+//! pipeline, either as OTAP Arrow streams (OTAP receiver) or as OTLP gRPC requests (OTLP
+//! receiver).
+//!
+//! This binary is only built when the `traffic-gen` feature is enabled:
+//!
+//! ```text
+//! cargo run --release -p otap-df-otap --features traffic-gen --bin otap-traffic-gen -- \
+//!     --endpoint http://127.0.0.1:4317 --protocol otap --batches-per-second 200 --batch-size 512
+//! ```
+
+#![allow(clippy::print_stdout)]
+
+use async_stream::stream;
+use async_trait::async_trait;
+use clap::{Parser, ValueEnum};
+use otap_df_config::experimental::SignalType;
+use otap_df_otap::fake_data_generator::fake_data::{current_time, gen_span_id, gen_trace_id};
+use otap_df_otap::otap_grpc::otlp::client::{
+    LogsServiceClient, MetricsServiceClient, TraceServiceClient,
+};
+use otap_df_otap::pdata::OtlpProtoBytes;
+use otel_arrow_rust::Producer;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::{
+    BatchArrowRecords, BatchStatus, arrow_logs_service_client::ArrowLogsServiceClient,
+    arrow_traces_service_client::ArrowTracesServiceClient,
+};
+use otel_arrow_rust::proto::opentelemetry::{
+    common::v1::{AnyValue, InstrumentationScope, KeyValue},
+    logs::v1::{LogRecord, LogsData, ResourceLogs, ScopeLogs, SeverityNumber},
+    metrics::v1::{
+        AggregationTemporality, Metric, MetricsData, NumberDataPoint, ResourceMetrics,
+        ScopeMetrics, Sum,
+    },
+    resource::v1::Resource,
+    trace::v1::{ResourceSpans, ScopeSpans, Span, TracesData, span::SpanKind},
+};
+use prost::Message;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+use tonic::transport::Channel;
+use tonic::{IntoStreamingRequest, Response, Status, Streaming};
+
+/// Name reported in the instrumentation scope of every generated signal.
+const SCOPE_NAME: &str = "otap-traffic-gen";
+
+/// Number of distinct metric names generated in a metrics batch.
+const METRIC_NAME_COUNT: usize = 16;
+
+#[derive(Parser, Debug, Clone)]
+#[command(
+    author,
+    version,
+    about = "Generates synthetic OTAP/OTLP traffic for capacity testing",
+    long_about = None
+)]
+struct Args {
+    /// gRPC endpoint of the pipeline receiver (e.g. "http://127.0.0.1:4317")
+    #[arg(long, default_value = "http://127.0.0.1:4317")]
+    endpoint: String,
+
+    /// Wire protocol used to send the generated traffic
+    #[arg(long, value_enum, default_value_t = Protocol::Otap)]
+    protocol: Protocol,
+
+    /// Number of batches sent per second (all signals combined)
+    #[arg(long, default_value_t = 10)]
+    batches_per_second: u32,
+
+    /// Number of log records, spans, or metric data points per batch
+    #[arg(long, default_value_t = 100)]
+    batch_size: usize,
+
+    /// How long to generate traffic for, in seconds
+    #[arg(long, default_value_t = 60)]
+    duration_secs: u64,
+
+    /// Relative weight of log batches
+    #[arg(long, default_value_t = 1)]
+    log_weight: u32,
+
+    /// Relative weight of trace batches
+    #[arg(long, default_value_t = 1)]
+    trace_weight: u32,
+
+    /// Relative weight of metric batches (OTLP protocol only)
+    #[arg(long, default_value_t = 0)]
+    metric_weight: u32,
+
+    /// Number of attributes attached to each log record, span, or data point
+    #[arg(long, default_value_t = 8)]
+    attributes_per_item: usize,
+
+    /// Number of distinct values each attribute can take
+    #[arg(long, default_value_t = 100)]
+    attribute_cardinality: usize,
+
+    /// Length in bytes of each generated attribute value
+    #[arg(long, default_value_t = 16)]
+    attribute_value_len: usize,
+
+    /// Number of resource attributes attached to each batch
+    #[arg(long, default_value_t = 4)]
+    resource_attributes: usize,
+}
+
+/// Wire protocol used to send the generated traffic.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    /// OTAP Arrow streams (logs and traces)
+    Otap,
+    /// OTLP unary gRPC requests (logs, traces, and metrics)
+    Otlp,
+}
+
+impl Args {
+    fn validate(&self) -> Result<(), String> {
+        if self.batches_per_second == 0 {
+            return Err("--batches-per-second must be greater than 0".into());
+        }
+        if self.batch_size == 0 {
+            return Err("--batch-size must be greater than 0".into());
+        }
+        if self.attribute_cardinality == 0 {
+            return Err("--attribute-cardinality must be greater than 0".into());
+        }
+        let total_weight = self
+            .log_weight
+            .checked_add(self.trace_weight)
+            .and_then(|weight| weight.checked_add(self.metric_weight))
+            .ok_or("the sum of the signal weights must fit in a u32")?;
+        if total_weight == 0 {
+            return Err("at least one of the signal weights must be greater than 0".into());
+        }
+        if self.protocol == Protocol::Otap && self.metric_weight > 0 {
+            // OTLP -> OTAP conversion is not yet implemented for metrics.
+            return Err(
+                "metrics are not supported with --protocol otap, use --protocol otlp".into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the order in which signals are emitted, honoring the configured weights.
+    fn signal_schedule(&self) -> Vec<SignalType> {
+        let mut schedule = Vec::new();
+        for (signal, weight) in [
+            (SignalType::Logs, self.log_weight),
+            (SignalType::Traces, self.trace_weight),
+            (SignalType::Metrics, self.metric_weight),
+        ] {
+            schedule.extend(std::iter::repeat_n(signal, weight as usize));
+        }
+        schedule
+    }
+}
+
+/// Builds synthetic OTLP payloads according to the command line configuration.
+struct Generator {
+    batch_size: usize,
+    attributes_per_item: usize,
+    attribute_cardinality: usize,
+    attribute_value_len: usize,
+    resource_attributes: usize,
+}
+
+impl Generator {
+    fn new(args: &Args) -> Self {
+        Self {
+            batch_size: args.batch_size,
+            attributes_per_item: args.attributes_per_item,
+            attribute_cardinality: args.attribute_cardinality,
+            attribute_value_len: args.attribute_value_len,
+            resource_attributes: args.resource_attributes,
+        }
+    }
+
+    /// Generates a batch of the given signal type encoded as an OTLP export request.
+    fn generate(&self, signal: SignalType) -> OtlpProtoBytes {
+        match signal {
+            SignalType::Logs => OtlpProtoBytes::ExportLogsRequest(self.logs().encode_to_vec()),
+            SignalType::Traces => {
+                OtlpProtoBytes::ExportTracesRequest(self.traces().encode_to_vec())
+            }
+            SignalType::Metrics => {
+                OtlpProtoBytes::ExportMetricsRequest(self.metrics().encode_to_vec())
+            }
+        }
+    }
+
+    fn attributes(&self, prefix: &str, count: usize) -> Vec<KeyValue> {
+        (0..count)
+            .map(|i| {
+                let id = rand::random_range(0..self.attribute_cardinality);
+                let value = format!("{id:0>width$}", width = self.attribute_value_len);
+                KeyValue::new(format!("{prefix}.{i}"), AnyValue::new_string(value))
+            })
+            .collect()
+    }
+
+    fn resource(&self) -> Resource {
+        Resource::build(self.attributes("resource.attr", self.resource_attributes)).finish()
+    }
+
+    fn scope() -> InstrumentationScope {
+        InstrumentationScope::build(SCOPE_NAME)
+            .version(env!("CARGO_PKG_VERSION"))
+            .finish()
+    }
+
+    fn logs(&self) -> LogsData {
+        let records = (0..self.batch_size)
+            .map(|_| {
+                let now = current_time();
+                LogRecord::build(now, SeverityNumber::Info, "synthetic.event")
+                    .attributes(self.attributes("log.attr", self.attributes_per_item))
+                    .body(AnyValue::new_string("synthetic log record"))
+                    .observed_time_unix_nano(now)
+                    .finish()
+            })
+            .collect::<Vec<_>>();
+
+        LogsData::new(vec![
+            ResourceLogs::build(self.resource())
+                .scope_logs(vec![
+                    ScopeLogs::build(Self::scope())
+                        .log_records(records)
+                        .finish(),
+                ])
+                .finish(),
+        ])
+    }
+
+    fn traces(&self) -> TracesData {
+        let spans = (0..self.batch_size)
+            .map(|_| {
+                let start = current_time();
+                Span::build(gen_trace_id(), gen_span_id(), "synthetic.span", start)
+                    .attributes(self.attributes("span.attr", self.attributes_per_item))
+                    .kind(SpanKind::Server)
+                    .end_time_unix_nano(start + 1_000_000)
+                    .finish()
+            })
+            .collect::<Vec<_>>();
+
+        TracesData::new(vec![
+            ResourceSpans::build(self.resource())
+                .scope_spans(vec![ScopeSpans::build(Self::scope()).spans(spans).finish()])
+                .finish(),
+        ])
+    }
+
+    fn metrics(&self) -> MetricsData {
+        let metrics = (0..self.batch_size)
+            .map(|i| {
+                let datapoints = vec![
+                    NumberDataPoint::build_double(current_time(), 1.0)
+                        .attributes(self.attributes("metric.attr", self.attributes_per_item))
+                        .finish(),
+                ];
+                Metric::build_sum(
+                    format!("synthetic.metric.{}", i % METRIC_NAME_COUNT),
+                    Sum::new(AggregationTemporality::Cumulative, true, datapoints),
+                )
+                .unit("{item}")
+                .finish()
+            })
+            .collect::<Vec<_>>();
+
+        MetricsData::new(vec![
+            ResourceMetrics::build(self.resource())
+                .scope_metrics(vec![
+                    ScopeMetrics::build(Self::scope()).metrics(metrics).finish(),
+                ])
+                .finish(),
+        ])
+    }
+}
+
+/// Sends generated batches to the pipeline using the selected protocol.
+enum Sink {
+    Otap {
+        logs: mpsc::Sender<OtapArrowRecords>,
+        traces: mpsc::Sender<OtapArrowRecords>,
+        handles: Vec<JoinHandle<Result<u64, Status>>>,
+    },
+    Otlp {
+        logs: LogsServiceClient<Channel>,
+        traces: TraceServiceClient<Channel>,
+        metrics: MetricsServiceClient<Channel>,
+    },
+}
+
+impl Sink {
+    async fn connect(args: &Args) -> Result<Self, Box<dyn std::error::Error>> {
+        let channel = Channel::from_shared(args.endpoint.clone())?
+            .connect()
+            .await?;
+        Ok(match args.protocol {
+            Protocol::Otap => {
+                let (logs, logs_rx) = mpsc::channel(64);
+                let (traces, traces_rx) = mpsc::channel(64);
+                let handles = vec![
+                    tokio::task::spawn_local(stream_arrow_batches(
+                        ArrowLogsServiceClient::new(channel.clone()),
+                        logs_rx,
+                    )),
+                    tokio::task::spawn_local(stream_arrow_batches(
+                        ArrowTracesServiceClient::new(channel),
+                        traces_rx,
+                    )),
+                ];
+                Sink::Otap {
+                    logs,
+                    traces,
+                    handles,
+                }
+            }
+            Protocol::Otlp => Sink::Otlp {
+                logs: LogsServiceClient::new(channel.clone()),
+                traces: TraceServiceClient::new(channel.clone()),
+                metrics: MetricsServiceClient::new(channel),
+            },
+        })
+    }
+
+    async fn send(&mut self, batch: OtlpProtoBytes) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Sink::Otap { logs, traces, .. } => {
+                let sender = match batch {
+                    OtlpProtoBytes::ExportLogsRequest(_) => logs,
+                    OtlpProtoBytes::ExportTracesRequest(_) => traces,
+                    OtlpProtoBytes::ExportMetricsRequest(_) => {
+                        return Err("metrics are not supported over OTAP".into());
+                    }
+                };
+                let records = OtapArrowRecords::try_from(batch).map_err(|e| e.to_string())?;
+                sender
+                    .send(records)
+                    .await
+                    .map_err(|_| "OTAP stream closed by the server")?;
+            }
+            Sink::Otlp {
+                logs,
+                traces,
+                metrics,
+            } => match batch {
+                OtlpProtoBytes::ExportLogsRequest(bytes) => _ = logs.export(bytes).await?,
+                OtlpProtoBytes::ExportTracesRequest(bytes) => _ = traces.export(bytes).await?,
+                OtlpProtoBytes::ExportMetricsRequest(bytes) => _ = metrics.export(bytes).await?,
+            },
+        }
+        Ok(())
+    }
+
+    /// Closes the OTAP streams and waits until the server acknowledged every batch.
+    async fn close(self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Sink::Otap {
+            logs,
+            traces,
+            handles,
+        } = self
+        {
+            drop(logs);
+            drop(traces);
+            for handle in handles {
+                let acked = handle.await??;
+                log::debug!("OTAP stream closed after {acked} acknowledged batches");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+trait ArrowStreamClient {
+    async fn open_stream(
+        &mut self,
+        req_stream: impl IntoStreamingRequest<Message = BatchArrowRecords> + Send + 'static,
+    ) -> Result<Response<Streaming<BatchStatus>>, Status>;
+}
+
+#[async_trait(?Send)]
+impl ArrowStreamClient for ArrowLogsServiceClient<Channel> {
+    async fn open_stream(
+        &mut self,
+        req_stream: impl IntoStreamingRequest<Message = BatchArrowRecords> + Send + 'static,
+    ) -> Result<Response<Streaming<BatchStatus>>, Status> {
+        self.arrow_logs(req_stream).await
+    }
+}
+
+#[async_trait(?Send)]
+impl ArrowStreamClient for ArrowTracesServiceClient<Channel> {
+    async fn open_stream(
+        &mut self,
+        req_stream: impl IntoStreamingRequest<Message = BatchArrowRecords> + Send + 'static,
+    ) -> Result<Response<Streaming<BatchStatus>>, Status> {
+        self.arrow_traces(req_stream).await
+    }
+}
+
+/// Streams every batch received on `rx` to the server and returns the number of acknowledged
+/// batches once the channel is closed.
+async fn stream_arrow_batches<C: ArrowStreamClient>(
+    mut client: C,
+    mut rx: mpsc::Receiver<OtapArrowRecords>,
+) -> Result<u64, Status> {
+    let req_stream = stream! {
+        let mut producer = Producer::new();
+        while let Some(mut records) = rx.recv().await {
+            match producer.produce_bar(&mut records) {
+                Ok(bar) => yield bar,
+                Err(e) => log::error!("failed to encode OTAP batch: {e}"),
+            }
+        }
+    };
+
+    let mut res_stream = client.open_stream(req_stream).await?.into_inner();
+    let mut acked = 0;
+    while res_stream.message().await?.is_some() {
+        acked += 1;
+    }
+    Ok(acked)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.validate()?;
+
+    let local = tokio::task::LocalSet::new();
+    local.run_until(run(args)).await
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let generator = Generator::new(&args);
+    let schedule = args.signal_schedule();
+    let mut sink = Sink::connect(&args).await?;
+
+    let mut ticker = tokio::time::interval(Duration::from_secs(1) / args.batches_per_second);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Burst);
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(args.duration_secs);
+    let mut sent_batches: u64 = 0;
+    let mut failed_batches: u64 = 0;
+
+    for signal in schedule.iter().cycle() {
+        if ticker.tick().await >= deadline {
+            break;
+        }
+        match sink.send(generator.generate(*signal)).await {
+            Ok(()) => sent_batches += 1,
+            Err(e) => {
+                failed_batches += 1;
+                log::warn!("failed to send {signal:?} batch: {e}");
+            }
+        }
+    }
+    sink.close().await?;
+
+    let elapsed = start.elapsed().as_secs_f64();
+    let items = sent_batches * args.batch_size as u64;
+    println!(
+        "sent {sent_batches} batches ({items} items) in {elapsed:.2}s, {failed_batches} failed, {:.0} items/s",
+        items as f64 / elapsed
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_weights() {
+        let args = |weights: &[&str]| {
+            let mut argv = vec!["traffic_gen", "--protocol", "otlp"];
+            argv.extend_from_slice(weights);
+            Args::parse_from(argv)
+        };
+        assert!(args(&["--log-weight", "1"]).validate().is_ok());
+        assert!(
+            args(&[
+                "--log-weight",
+                "0",
+                "--trace-weight",
+                "0",
+                "--metric-weight",
+                "0"
+            ])
+            .validate()
+            .is_err()
+        );
+        let max = u32::MAX.to_string();
+        assert!(
+            args(&["--log-weight", &max, "--trace-weight", "1"])
+                .validate()
+                .is_err()
+        );
+    }
+}
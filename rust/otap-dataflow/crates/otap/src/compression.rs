@@ -5,11 +5,12 @@
 //! Defines a compression enum to abstract from tonic and allows the exporter and receiver to get the respective tonic equivalent
 //!
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tonic::codec::CompressionEncoding;
 
 /// Enum to represent various compression methods
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum CompressionMethod {
     /// Fastest compression
@@ -0,0 +1,571 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exporter printing the OTAP record batches it receives in a human-readable form.
+//!
+//! Unlike the debug processor, which converts the data to OTLP, this exporter renders the Arrow
+//! record batches themselves, which shows the data exactly as other OTAP exporters see it. With
+//! the `basic` verbosity only the number of rows of every payload is printed. The `normal`
+//! verbosity adds a table with the first `sample_rows` rows of the root payload (logs, spans or
+//! metrics), where struct columns are flattened (e.g. `body.str`) and the attribute batches are
+//! decoded into `key=value` lists next to the row they belong to. The `detailed` verbosity prints
+//! the same table for every non-attribute payload (span events, data points, exemplars, ...).
+//! Values longer than `max_value_width` characters are truncated.
+
+use crate::OTAP_EXPORTER_FACTORIES;
+use crate::metrics::ExporterPDataMetrics;
+use crate::pdata::OtapPdata;
+use crate::pdata::attributes::{AttributeIter, AttributeValue};
+use arrow::array::{Array, ArrayRef, AsArray, RecordBatch, StructArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, UInt32Type};
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use async_trait::async_trait;
+use data_encoding::HEXLOWER;
+use linkme::distributed_slice;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ExporterConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, NackClass, NackMsg, NodeControlMsg};
+use otap_df_engine::error::{Error, ExporterErrorKind, format_error_sources};
+use otap_df_engine::exporter::ExporterWrapper;
+use otap_df_engine::local::exporter::{EffectHandler, Exporter};
+use otap_df_engine::message::{Message, MessageChannel};
+use otap_df_engine::node::NodeId;
+use otap_df_engine::terminal_state::TerminalState;
+use otap_df_engine::{ConsumerEffectHandlerExtension, ExporterFactory};
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::schema::consts;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// The URN for the debug exporter
+pub const DEBUG_EXPORTER_URN: &str = "urn:otel:debug:exporter";
+
+/// How much of every batch is printed.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verbosity {
+    /// Number of rows of every payload.
+    Basic,
+    /// Adds a sample of the rows of the root payload.
+    #[default]
+    Normal,
+    /// Adds a sample of the rows of every non-attribute payload.
+    Detailed,
+}
+
+/// Configuration for the debug exporter
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// How much of every batch is printed.
+    #[serde(default)]
+    pub verbosity: Verbosity,
+
+    /// Number of rows printed per payload.
+    #[serde(default = "default_sample_rows")]
+    pub sample_rows: usize,
+
+    /// Values longer than this number of characters are truncated.
+    #[serde(default = "default_max_value_width")]
+    pub max_value_width: usize,
+
+    /// File the output is appended to, stdout when unset.
+    #[serde(default)]
+    pub output: Option<PathBuf>,
+}
+
+const fn default_sample_rows() -> usize {
+    10
+}
+
+const fn default_max_value_width() -> usize {
+    64
+}
+
+/// Exporter printing OTAP record batches
+pub struct DebugExporter {
+    config: Config,
+    pdata_metrics: MetricSet<ExporterPDataMetrics>,
+}
+
+/// Declares the debug exporter as a local exporter factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_EXPORTER_FACTORIES)]
+pub static DEBUG_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
+    name: DEBUG_EXPORTER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             exporter_config: &ExporterConfig| {
+        Ok(ExporterWrapper::local(
+            DebugExporter::from_config(pipeline, &node_config.config)?,
+            node,
+            node_config,
+            exporter_config,
+        ))
+    },
+};
+
+/// Schema of the configuration of the debug exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static DEBUG_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(DEBUG_EXPORTER_URN);
+
+impl DebugExporter {
+    /// create a new instance of the `[DebugExporter]` from json config value
+    pub fn from_config(
+        pipeline_ctx: PipelineContext,
+        config: &serde_json::Value,
+    ) -> Result<Self, otap_df_config::error::Error> {
+        let pdata_metrics = pipeline_ctx.register_metrics::<ExporterPDataMetrics>();
+
+        let config: Config = parse_user_config(config).map_err(|e| {
+            otap_df_config::error::Error::InvalidUserConfig {
+                error: e.to_string(),
+            }
+        })?;
+
+        Ok(Self {
+            config,
+            pdata_metrics,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Exporter<OtapPdata> for DebugExporter {
+    async fn start(
+        mut self: Box<Self>,
+        mut msg_chan: MessageChannel<OtapPdata>,
+        effect_handler: EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        let mut writer: Box<dyn AsyncWrite + Unpin> = match &self.config.output {
+            Some(path) => Box::new(
+                tokio::fs::File::options()
+                    .append(true)
+                    .create(true)
+                    .open(path)
+                    .await
+                    .map_err(|e| {
+                        let source_detail = format_error_sources(&e);
+                        Error::ExporterError {
+                            exporter: effect_handler.exporter_id(),
+                            kind: ExporterErrorKind::Configuration,
+                            error: format!("cannot open {}: {e}", path.display()),
+                            source_detail,
+                        }
+                    })?,
+            ),
+            None => Box::new(tokio::io::stdout()),
+        };
+
+        let timer_cancel_handle = effect_handler
+            .start_periodic_telemetry(Duration::from_secs(1))
+            .await?;
+
+        loop {
+            match msg_chan.recv().await? {
+                Message::Control(NodeControlMsg::Shutdown { deadline, .. }) => {
+                    _ = timer_cancel_handle.cancel().await;
+                    return Ok(TerminalState::new(deadline, [self.pdata_metrics]));
+                }
+                Message::Control(NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                }) => {
+                    _ = metrics_reporter.report(&mut self.pdata_metrics);
+                }
+                Message::PData(pdata) => {
+                    let signal_type = pdata.signal_type();
+                    self.pdata_metrics.inc_consumed(signal_type);
+
+                    let (context, mut payload) = pdata.into_parts();
+                    let data = context.take_payload_for_export(&mut payload);
+
+                    let rendered = OtapArrowRecords::try_from(data)
+                        .map_err(|e| e.to_string())
+                        .map(|mut records| render(&mut records, &self.config));
+                    let written = match rendered {
+                        Ok(text) => match writer.write_all(text.as_bytes()).await {
+                            Ok(()) => writer.flush().await,
+                            Err(e) => Err(e),
+                        }
+                        .map_err(|e| e.to_string()),
+                        Err(e) => Err(e),
+                    };
+
+                    let pdata = OtapPdata::new(context, payload);
+                    match written {
+                        Ok(()) => {
+                            self.pdata_metrics.inc_exported(signal_type);
+                            effect_handler.notify_ack(AckMsg::new(pdata)).await?;
+                        }
+                        Err(e) => {
+                            self.pdata_metrics.inc_failed(signal_type);
+                            effect_handler
+                                .notify_nack(
+                                    NackMsg::new(&format!("debug output failed: {e}"), pdata)
+                                        .with_class(NackClass::Unavailable),
+                                )
+                                .await?;
+                        }
+                    }
+                }
+                _ => {
+                    // ignore unhandled messages
+                }
+            }
+        }
+    }
+}
+
+/// Renders a batch according to the configuration.
+fn render(records: &mut OtapArrowRecords, config: &Config) -> String {
+    let (signal, root_types): (&str, &[ArrowPayloadType]) = match records {
+        OtapArrowRecords::Logs(_) => ("logs", &[ArrowPayloadType::Logs]),
+        OtapArrowRecords::Traces(_) => ("traces", &[ArrowPayloadType::Spans]),
+        OtapArrowRecords::Metrics(_) => (
+            "metrics",
+            &[
+                ArrowPayloadType::UnivariateMetrics,
+                ArrowPayloadType::MultivariateMetrics,
+            ],
+        ),
+    };
+
+    let mut out = String::new();
+    let counts = records
+        .allowed_payload_types()
+        .iter()
+        .filter_map(|payload_type| {
+            records
+                .get(*payload_type)
+                .map(|batch| format!("{}: {}", payload_name(*payload_type), batch.num_rows()))
+        })
+        .collect::<Vec<_>>();
+    let _ = writeln!(out, "OTAP {signal} batch ({})", counts.join(", "));
+    if config.verbosity == Verbosity::Basic {
+        return out;
+    }
+
+    // the attributes are matched with their parent rows by id, which requires plain ids
+    if let Err(e) = records.decode_transport_optimized_ids() {
+        let _ = writeln!(out, "cannot decode ids: {e}");
+        return out;
+    }
+
+    for payload_type in records.allowed_payload_types() {
+        let shown = if config.verbosity == Verbosity::Detailed {
+            attrs_payload_type(*payload_type).is_some()
+        } else {
+            root_types.contains(payload_type)
+        };
+        if let Some(batch) = records.get(*payload_type).filter(|_| shown) {
+            render_payload(&mut out, records, *payload_type, batch, config);
+        }
+    }
+    out
+}
+
+/// Renders a sample of the rows of a payload, with their attributes.
+fn render_payload(
+    out: &mut String,
+    records: &OtapArrowRecords,
+    payload_type: ArrowPayloadType,
+    batch: &RecordBatch,
+    config: &Config,
+) {
+    let num_rows = batch.num_rows().min(config.sample_rows);
+    let _ = writeln!(
+        out,
+        "{} (showing {num_rows} of {} rows)",
+        payload_name(payload_type),
+        batch.num_rows()
+    );
+
+    let mut headers = Vec::new();
+    let mut columns: Vec<Vec<String>> = Vec::new();
+    for (field, array) in batch.schema().fields().iter().zip(batch.columns()) {
+        flatten_column(field.name(), array, num_rows, &mut headers, &mut columns);
+    }
+
+    // the attributes of the rows, and of their resource and scope for the root payloads
+    let mut attributes = vec![(consts::ID, attrs_payload_type(payload_type))];
+    if batch.column_by_name(consts::RESOURCE).is_some() {
+        attributes.push((consts::RESOURCE, Some(ArrowPayloadType::ResourceAttrs)));
+        attributes.push((consts::SCOPE, Some(ArrowPayloadType::ScopeAttrs)));
+    }
+    for (column, attrs_type) in attributes {
+        let Some(attrs) = attrs_type.and_then(|attrs_type| records.get(attrs_type)) else {
+            continue;
+        };
+        let ids = match column {
+            consts::ID => batch.column_by_name(consts::ID).cloned(),
+            _ => batch
+                .column_by_name(column)
+                .and_then(|c| c.as_struct_opt())
+                .and_then(|s| s.column_by_name(consts::ID))
+                .cloned(),
+        };
+        let Some(ids) = ids.and_then(|ids| cast(&ids, &DataType::UInt32).ok()) else {
+            continue;
+        };
+        let by_parent = attributes_by_parent(attrs);
+        let ids = ids.as_primitive::<UInt32Type>();
+        headers.push(match column {
+            consts::ID => "attributes".to_string(),
+            _ => format!("{column}.attributes"),
+        });
+        columns.push(
+            (0..num_rows)
+                .map(|row| {
+                    ids.is_valid(row)
+                        .then(|| ids.value(row))
+                        .and_then(|id| by_parent.get(&id))
+                        .map(|attrs| attrs.join(", "))
+                        .unwrap_or_default()
+                })
+                .collect(),
+        );
+    }
+
+    for column in &mut columns {
+        for value in column.iter_mut() {
+            truncate(value, config.max_value_width);
+        }
+    }
+    render_table(out, &headers, &columns, num_rows);
+}
+
+/// Adds the formatted values of a column, or of the fields of a struct column, to the table.
+fn flatten_column(
+    name: &str,
+    array: &ArrayRef,
+    num_rows: usize,
+    headers: &mut Vec<String>,
+    columns: &mut Vec<Vec<String>>,
+) {
+    if let Some(array) = array.as_any().downcast_ref::<StructArray>() {
+        for (field, child) in array.fields().iter().zip(array.columns()) {
+            flatten_column(
+                &format!("{name}.{}", field.name()),
+                child,
+                num_rows,
+                headers,
+                columns,
+            );
+        }
+        return;
+    }
+
+    let options = FormatOptions::default();
+    let values = match ArrayFormatter::try_new(array.as_ref(), &options) {
+        Ok(formatter) => (0..num_rows)
+            .map(|row| formatter.value(row).to_string())
+            .collect(),
+        Err(_) => vec![format!("<{}>", array.data_type()); num_rows],
+    };
+    headers.push(name.to_string());
+    columns.push(values);
+}
+
+/// Groups the `key=value` pairs of an attribute batch by parent id.
+fn attributes_by_parent(batch: &RecordBatch) -> HashMap<u32, Vec<String>> {
+    let mut by_parent: HashMap<u32, Vec<String>> = HashMap::new();
+    let Ok(attrs) = AttributeIter::try_new(batch) else {
+        return by_parent;
+    };
+    for attr in attrs {
+        let value = match attr.value {
+            AttributeValue::Empty => String::new(),
+            AttributeValue::Str(s) => s.to_string(),
+            AttributeValue::Int(i) => i.to_string(),
+            AttributeValue::Double(d) => d.to_string(),
+            AttributeValue::Bool(b) => b.to_string(),
+            AttributeValue::Bytes(b) => HEXLOWER.encode(b),
+            AttributeValue::Serialized(b) => format!("<{} bytes>", b.len()),
+        };
+        by_parent
+            .entry(attr.parent_id)
+            .or_default()
+            .push(format!("{}={value}", attr.key));
+    }
+    by_parent
+}
+
+/// Truncates a value to at most `max_width` characters, marking the truncation with `…`.
+fn truncate(value: &mut String, max_width: usize) {
+    if let Some((idx, _)) = value.char_indices().nth(max_width) {
+        let keep = value[..idx]
+            .char_indices()
+            .last()
+            .map_or(0, |(last, _)| last);
+        value.truncate(keep);
+        value.push('…');
+    }
+    // keep every row on a single line
+    if value.contains('\n') {
+        *value = value.replace('\n', "\\n");
+    }
+}
+
+fn render_table(out: &mut String, headers: &[String], columns: &[Vec<String>], num_rows: usize) {
+    let widths = headers
+        .iter()
+        .zip(columns)
+        .map(|(header, values)| {
+            values
+                .iter()
+                .chain(std::iter::once(header))
+                .map(|v| v.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let separator = widths
+        .iter()
+        .map(|w| "-".repeat(w + 2))
+        .collect::<Vec<_>>()
+        .join("+");
+    let separator = format!("+{separator}+");
+
+    out.push_str(&separator);
+    out.push('\n');
+    write_row(out, headers.iter(), &widths);
+    out.push_str(&separator);
+    out.push('\n');
+    for row in 0..num_rows {
+        write_row(out, columns.iter().map(|c| &c[row]), &widths);
+    }
+    out.push_str(&separator);
+    out.push('\n');
+}
+
+fn write_row<'a>(out: &mut String, cells: impl Iterator<Item = &'a String>, widths: &[usize]) {
+    out.push('|');
+    for (cell, width) in cells.zip(widths) {
+        let _ = write!(out, " {cell:<width$} |");
+    }
+    out.push('\n');
+}
+
+fn payload_name(payload_type: ArrowPayloadType) -> String {
+    payload_type.as_str_name().to_lowercase()
+}
+
+/// Returns the payload holding the attributes of the rows of the given payload, `None` for the
+/// attribute payloads themselves.
+fn attrs_payload_type(payload_type: ArrowPayloadType) -> Option<ArrowPayloadType> {
+    match payload_type {
+        ArrowPayloadType::Logs => Some(ArrowPayloadType::LogAttrs),
+        ArrowPayloadType::Spans => Some(ArrowPayloadType::SpanAttrs),
+        ArrowPayloadType::SpanEvents => Some(ArrowPayloadType::SpanEventAttrs),
+        ArrowPayloadType::SpanLinks => Some(ArrowPayloadType::SpanLinkAttrs),
+        ArrowPayloadType::UnivariateMetrics | ArrowPayloadType::MultivariateMetrics => {
+            Some(ArrowPayloadType::MetricAttrs)
+        }
+        ArrowPayloadType::NumberDataPoints => Some(ArrowPayloadType::NumberDpAttrs),
+        ArrowPayloadType::SummaryDataPoints => Some(ArrowPayloadType::SummaryDpAttrs),
+        ArrowPayloadType::HistogramDataPoints => Some(ArrowPayloadType::HistogramDpAttrs),
+        ArrowPayloadType::ExpHistogramDataPoints => Some(ArrowPayloadType::ExpHistogramDpAttrs),
+        ArrowPayloadType::NumberDpExemplars => Some(ArrowPayloadType::NumberDpExemplarAttrs),
+        ArrowPayloadType::HistogramDpExemplars => Some(ArrowPayloadType::HistogramDpExemplarAttrs),
+        ArrowPayloadType::ExpHistogramDpExemplars => {
+            Some(ArrowPayloadType::ExpHistogramDpExemplarAttrs)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::{OtapPayload, OtlpProtoBytes};
+    use crate::testing::{
+        create_test_logs, test_exporter_no_subscription, test_exporter_with_subscription,
+    };
+    use otap_df_engine::Interests;
+    use prost::Message as _;
+    use serde_json::json;
+
+    fn test_records() -> OtapArrowRecords {
+        let payload: OtapPayload =
+            OtlpProtoBytes::ExportLogsRequest(create_test_logs().encode_to_vec()).into();
+        payload.try_into().unwrap()
+    }
+
+    fn config(verbosity: Verbosity) -> Config {
+        Config {
+            verbosity,
+            sample_rows: default_sample_rows(),
+            max_value_width: default_max_value_width(),
+            output: None,
+        }
+    }
+
+    #[test]
+    fn test_render_basic() {
+        let out = render(&mut test_records(), &config(Verbosity::Basic));
+        assert_eq!(out.lines().count(), 1);
+        assert!(out.starts_with("OTAP logs batch (logs: 1"), "{out}");
+        assert!(out.contains("log_attrs: 1"), "{out}");
+    }
+
+    #[test]
+    fn test_render_normal() {
+        let out = render(&mut test_records(), &config(Verbosity::Normal));
+        assert!(out.contains("logs (showing 1 of 1 rows)"), "{out}");
+        assert!(out.contains("body.str"), "{out}");
+        assert!(out.contains("| event "), "{out}");
+        assert!(out.contains("key=val"), "{out}");
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut value = "héllo world".to_string();
+        truncate(&mut value, 5);
+        assert_eq!(value, "héll…");
+
+        let mut value = "short".to_string();
+        truncate(&mut value, 5);
+        assert_eq!(value, "short");
+
+        let mut value = "two\nlines".to_string();
+        truncate(&mut value, 64);
+        assert_eq!(value, "two\\nlines");
+    }
+
+    #[test]
+    fn test_debug_exporter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("debug.txt");
+        let config = json!({ "output": path, "verbosity": "detailed" });
+        test_exporter_no_subscription(&DEBUG_EXPORTER, config.clone());
+        test_exporter_with_subscription(&DEBUG_EXPORTER, config, Interests::ACKS, Interests::ACKS);
+
+        let out = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(out.matches("OTAP logs batch").count(), 2);
+    }
+
+    #[test]
+    fn test_debug_exporter_conformance() {
+        let dir = tempfile::tempdir().unwrap();
+        crate::testing::conformance::run_exporter_conformance(
+            &DEBUG_EXPORTER,
+            json!({ "output": dir.path().join("debug.txt") }),
+            Some(Interests::ACKS),
+        );
+    }
+}
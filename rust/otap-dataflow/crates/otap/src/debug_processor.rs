@@ -21,6 +21,8 @@ use linkme::distributed_slice;
 use otap_df_config::PortName;
 use otap_df_config::error::Error as ConfigError;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::config::ProcessorConfig;
 use otap_df_engine::context::PipelineContext;
 use otap_df_engine::control::{CallData, NodeControlMsg};
@@ -92,6 +94,12 @@ pub static DEBUG_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata>
         },
     };
 
+/// Schema of the configuration of the debug processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static DEBUG_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(DEBUG_PROCESSOR_URN);
+
 impl DebugProcessor {
     /// Creates a new Debug processor
     #[must_use]
@@ -110,7 +118,7 @@ impl DebugProcessor {
     pub fn from_config(pipeline_ctx: PipelineContext, config: &Value) -> Result<Self, ConfigError> {
         let metrics = pipeline_ctx.register_metrics::<DebugPdataMetrics>();
         let config: Config =
-            serde_json::from_value(config.clone()).map_err(|e| ConfigError::InvalidUserConfig {
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
                 error: e.to_string(),
             })?;
         let sampler = Sampler::new(config.sampling());
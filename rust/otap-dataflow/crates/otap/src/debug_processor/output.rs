@@ -21,11 +21,12 @@ use otel_arrow_rust::proto::opentelemetry::{
     metrics::v1::{MetricsData, ResourceMetrics, ScopeMetrics},
     trace::v1::{ResourceSpans, ScopeSpans, TracesData},
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
 use tokio::fs::File;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum OutputMode {
     Console,
@@ -3,6 +3,7 @@
 
 //! Implementation of the predicate struct used to filter metric, log, span signals
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -13,20 +14,20 @@ use otel_arrow_rust::proto::opentelemetry::{
     trace::v1::Span,
 };
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
 pub struct Predicate {
     field: SignalField,
     value: MatchValue,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SignalField {
     Attribute,
 }
 
 // ToDo: Add bytes
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
 #[serde(untagged)]
 pub enum MatchValue {
     String(String),
@@ -37,7 +38,7 @@ pub enum MatchValue {
     KeyValue(Vec<KeyValue>),
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
 pub struct KeyValue {
     key: String,
     value: MatchValue,
@@ -4,10 +4,11 @@
 //! Implementation of the Sampler for the DebugProcessor
 
 use otap_df_engine::error::Error;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use tokio::time::{Duration, Instant};
 
-#[derive(Debug, Clone, Copy, Deserialize)]
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SamplingConfig {
     NoSampling,
@@ -6,6 +6,8 @@ use crate::pdata::OtapPdata;
 use async_trait::async_trait;
 use linkme::distributed_slice;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::config::ExporterConfig;
 use otap_df_engine::context::PipelineContext;
 use otap_df_engine::control::{NackMsg, NodeControlMsg};
@@ -16,6 +18,7 @@ use otap_df_engine::message::{Message, MessageChannel};
 use otap_df_engine::node::NodeId;
 use otap_df_engine::terminal_state::TerminalState;
 use otap_df_engine::{ConsumerEffectHandlerExtension, ExporterFactory};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -28,7 +31,7 @@ struct ErrorExporter {
 }
 
 /// Configuration for the error exporter.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct ErrorExporterConfig {
     /// The error message.
     pub message: String,
@@ -41,6 +44,12 @@ static ERROR_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
     create: ErrorExporter::create_exporter,
 };
 
+/// Schema of the configuration of the error exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static ERROR_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<ErrorExporterConfig>(ERROR_EXPORTER_URN);
+
 impl ErrorExporter {
     fn create_exporter(
         _pipeline: PipelineContext,
@@ -48,10 +57,11 @@ impl ErrorExporter {
         node_config: Arc<NodeUserConfig>,
         exporter_config: &ExporterConfig,
     ) -> Result<ExporterWrapper<OtapPdata>, otap_df_config::error::Error> {
-        let config: ErrorExporterConfig = serde_json::from_value(node_config.config.clone())
-            .map_err(|e| otap_df_config::error::Error::InvalidUserConfig {
+        let config: ErrorExporterConfig = parse_user_config(&node_config.config).map_err(|e| {
+            otap_df_config::error::Error::InvalidUserConfig {
                 error: format!("Failed to parse error-exporter configuration: {e}"),
-            })?;
+            }
+        })?;
 
         let exporter = ErrorExporter::from_config(config);
 
@@ -129,4 +139,13 @@ mod tests {
             Interests::empty(),
         );
     }
+
+    #[test]
+    fn test_error_exporter_conformance() {
+        crate::testing::conformance::run_exporter_conformance(
+            &ERROR_EXPORTER,
+            json!({"message": "Test error"}),
+            Some(Interests::NACKS),
+        );
+    }
 }
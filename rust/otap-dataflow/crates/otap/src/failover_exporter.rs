@@ -0,0 +1,623 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exporter sending pdata to a primary exporter, and to a secondary exporter while the primary
+//! is unhealthy.
+//!
+//! The exporter subscribes to the Acks and Nacks of the pdata it sends to keep track of the
+//! health of the primary. The primary is unhealthy when its Nack rate over the last `window`
+//! outcomes exceeds `max_nack_rate`, or when `breaker_threshold` consecutive Nacks open its
+//! breaker. The pdata is then sent to the secondary, except for one message every
+//! `probe_interval` sent to the primary as a probe: the exporter switches back to the primary
+//! once a probe is Acked.
+//!
+//! Nacks are returned upstream, so a retry processor in front of the exporter resends the
+//! refused pdata, to the secondary once the primary is unhealthy.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! primary:
+//!   plugin_urn: "urn:otel:otlp:exporter"
+//!   config:
+//!     grpc_endpoint: http://collector-a:4317
+//! secondary:
+//!   plugin_urn: "urn:otel:otlp:exporter"
+//!   config:
+//!     grpc_endpoint: http://collector-b:4317
+//! health:
+//!   window: 20
+//!   max_nack_rate: 0.5
+//!   breaker_threshold: 5
+//! probe_interval: 30s
+//! ```
+
+use crate::nested_exporter::NestedExporter;
+use crate::pdata::OtapPdata;
+use crate::{OTAP_EXPORTER_FACTORIES, get_otap_exporter_factory_map};
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ExporterConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{CallData, NackClass, NackMsg, NodeControlMsg};
+use otap_df_engine::error::Error;
+use otap_df_engine::exporter::ExporterWrapper;
+use otap_df_engine::local::exporter::{EffectHandler, Exporter};
+use otap_df_engine::message::{Message, MessageChannel};
+use otap_df_engine::node::NodeId;
+use otap_df_engine::terminal_state::TerminalState;
+use otap_df_engine::{
+    ConsumerEffectHandlerExtension, ExporterFactory, Interests, ProducerEffectHandlerExtension,
+};
+use otap_df_telemetry::instrument::{Counter, Gauge};
+use otap_df_telemetry::metrics::MetricSet;
+use otap_df_telemetry_macros::metric_set;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The URN for the failover exporter
+pub const FAILOVER_EXPORTER_URN: &str = "urn:otap:exporter:failover";
+
+/// Configuration of the primary or secondary exporter
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    /// URN of the exporter plugin.
+    pub plugin_urn: String,
+    /// Configuration of the exporter plugin.
+    #[serde(default)]
+    pub config: Value,
+}
+
+/// Thresholds deciding whether the primary is healthy
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HealthConfig {
+    /// Number of most recent outcomes of the primary the Nack rate is computed on.
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// Nack rate over a full window above which the primary is unhealthy.
+    #[serde(default = "default_max_nack_rate")]
+    pub max_nack_rate: f64,
+    /// Number of consecutive Nacks opening the breaker of the primary.
+    #[serde(default = "default_breaker_threshold")]
+    pub breaker_threshold: usize,
+}
+
+const fn default_window() -> usize {
+    20
+}
+
+const fn default_max_nack_rate() -> f64 {
+    0.5
+}
+
+const fn default_breaker_threshold() -> usize {
+    5
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            window: default_window(),
+            max_nack_rate: default_max_nack_rate(),
+            breaker_threshold: default_breaker_threshold(),
+        }
+    }
+}
+
+const fn default_probe_interval() -> Duration {
+    Duration::from_secs(30)
+}
+
+/// Configuration for the failover exporter
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Exporter used while it is healthy.
+    pub primary: TargetConfig,
+    /// Exporter used while the primary is unhealthy.
+    pub secondary: TargetConfig,
+    /// Thresholds deciding whether the primary is healthy.
+    #[serde(default)]
+    pub health: HealthConfig,
+    /// Interval between two probes of the primary while the secondary is active.
+    #[serde(with = "humantime_serde", default = "default_probe_interval")]
+    #[schemars(with = "String")]
+    pub probe_interval: Duration,
+}
+
+/// Failover exporter metrics.
+#[metric_set(name = "failover.exporter.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct FailoverExporterMetrics {
+    /// Exporter receiving the pdata: 0 for the primary, 1 for the secondary.
+    #[metric(unit = "{target}")]
+    pub active_target: Gauge<u64>,
+
+    /// Number of switches from the primary to the secondary.
+    #[metric(unit = "{switch}")]
+    pub failovers: Counter<u64>,
+
+    /// Number of switches back from the secondary to the primary.
+    #[metric(unit = "{switch}")]
+    pub failbacks: Counter<u64>,
+
+    /// Number of messages sent to the primary as a probe.
+    #[metric(unit = "{msg}")]
+    pub probes: Counter<u64>,
+
+    /// Number of messages Nacked by the primary.
+    #[metric(unit = "{msg}")]
+    pub primary_nacks: Counter<u64>,
+
+    /// Number of messages Nacked by the secondary.
+    #[metric(unit = "{msg}")]
+    pub secondary_nacks: Counter<u64>,
+}
+
+/// One of the two wrapped exporters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target {
+    Primary,
+    Secondary,
+}
+
+impl Target {
+    const fn index(self) -> usize {
+        match self {
+            Target::Primary => 0,
+            Target::Secondary => 1,
+        }
+    }
+}
+
+/// Target of a message and whether it is a probe, carried in the calldata.
+fn calldata(target: Target, probe: bool) -> CallData {
+    smallvec::smallvec![(target.index() as u64).into(), u64::from(probe).into()]
+}
+
+fn from_calldata(calldata: &CallData) -> Option<(Target, bool)> {
+    if calldata.len() != 2 {
+        return None;
+    }
+    let target = match u64::from(calldata[0]) {
+        0 => Target::Primary,
+        1 => Target::Secondary,
+        _ => return None,
+    };
+    Some((target, u64::from(calldata[1]) != 0))
+}
+
+/// Recent outcomes of the primary.
+struct Health {
+    config: HealthConfig,
+    /// Most recent outcomes, `true` for a Nack.
+    outcomes: VecDeque<bool>,
+    consecutive_nacks: usize,
+}
+
+impl Health {
+    fn new(config: HealthConfig) -> Self {
+        Self {
+            outcomes: VecDeque::with_capacity(config.window),
+            consecutive_nacks: 0,
+            config,
+        }
+    }
+
+    fn record(&mut self, nacked: bool) {
+        if self.outcomes.len() == self.config.window {
+            let _ = self.outcomes.pop_front();
+        }
+        self.outcomes.push_back(nacked);
+        self.consecutive_nacks = if nacked {
+            self.consecutive_nacks + 1
+        } else {
+            0
+        };
+    }
+
+    fn is_degraded(&self) -> bool {
+        if self.consecutive_nacks >= self.config.breaker_threshold {
+            return true;
+        }
+        if self.outcomes.len() < self.config.window {
+            return false;
+        }
+        let nacks = self.outcomes.iter().filter(|nacked| **nacked).count();
+        nacks as f64 / self.outcomes.len() as f64 > self.config.max_nack_rate
+    }
+
+    fn reset(&mut self) {
+        self.outcomes.clear();
+        self.consecutive_nacks = 0;
+    }
+}
+
+/// Exporter switching between a primary and a secondary exporter
+pub struct FailoverExporter {
+    /// The primary and secondary exporters.
+    targets: [NestedExporter; 2],
+    active: Target,
+    health: Health,
+    probe_interval: Duration,
+    /// When the secondary became active or the primary was last probed.
+    last_probe: Instant,
+    metrics: MetricSet<FailoverExporterMetrics>,
+}
+
+/// Declare the failover exporter as a local exporter factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_EXPORTER_FACTORIES)]
+pub static FAILOVER_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
+    name: FAILOVER_EXPORTER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             exporter_config: &ExporterConfig| {
+        Ok(ExporterWrapper::local(
+            FailoverExporter::from_config(
+                pipeline,
+                node.clone(),
+                &node_config.config,
+                exporter_config,
+            )?,
+            node,
+            node_config,
+            exporter_config,
+        ))
+    },
+};
+
+/// Schema of the configuration of the failover exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static FAILOVER_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(FAILOVER_EXPORTER_URN);
+
+impl FailoverExporter {
+    /// create a new instance of the `[FailoverExporter]` and of its primary and secondary
+    /// exporters from json config value
+    pub fn from_config(
+        pipeline_ctx: PipelineContext,
+        node: NodeId,
+        config: &Value,
+        exporter_config: &ExporterConfig,
+    ) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: e.to_string(),
+            })?;
+        validate(&config)?;
+
+        let target = |name: &str, target: TargetConfig| {
+            let factory = get_otap_exporter_factory_map()
+                .get(target.plugin_urn.as_str())
+                .ok_or_else(|| ConfigError::InvalidUserConfig {
+                    error: format!("unknown {name} exporter `{}`", target.plugin_urn),
+                })?;
+            NestedExporter::new(
+                name.to_string(),
+                factory,
+                target.config,
+                &pipeline_ctx,
+                &node,
+                exporter_config,
+            )
+        };
+        let targets = [
+            target("primary", config.primary)?,
+            target("secondary", config.secondary)?,
+        ];
+
+        Ok(Self {
+            targets,
+            active: Target::Primary,
+            health: Health::new(config.health),
+            probe_interval: config.probe_interval,
+            last_probe: Instant::now(),
+            metrics: pipeline_ctx.register_metrics::<FailoverExporterMetrics>(),
+        })
+    }
+
+    /// Sends `pdata` to the active exporter, or to the primary when it is time to probe it.
+    async fn dispatch(
+        &mut self,
+        mut pdata: OtapPdata,
+        effect_handler: &EffectHandler<OtapPdata>,
+    ) -> Result<(), Error> {
+        let probe =
+            self.active == Target::Secondary && self.last_probe.elapsed() >= self.probe_interval;
+        let target = if probe {
+            self.last_probe = Instant::now();
+            self.metrics.probes.inc();
+            Target::Primary
+        } else {
+            self.active
+        };
+
+        effect_handler.subscribe_to(
+            Interests::ACKS_OR_NACKS,
+            calldata(target, probe),
+            &mut pdata,
+        );
+        let exporter = &self.targets[target.index()];
+        match exporter.send(pdata).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                // delivered back to this exporter, counting as a Nack of the target
+                let reason = format!("{} exporter stopped", exporter.name);
+                effect_handler
+                    .notify_nack(
+                        NackMsg::new(&reason, e.inner()).with_class(NackClass::Unavailable),
+                    )
+                    .await
+            }
+        }
+    }
+
+    /// Updates the health of the primary with the outcome of a message sent to `target`, and
+    /// switches exporter if needed.
+    fn record(&mut self, target: Target, probe: bool, nacked: bool) {
+        match target {
+            Target::Secondary => {
+                if nacked {
+                    self.metrics.secondary_nacks.inc();
+                }
+            }
+            Target::Primary => {
+                if nacked {
+                    self.metrics.primary_nacks.inc();
+                }
+                if probe {
+                    if !nacked && self.active == Target::Secondary {
+                        log::info!("Primary exporter recovered, switching back to it");
+                        self.active = Target::Primary;
+                        self.health.reset();
+                        self.metrics.failbacks.inc();
+                    }
+                    return;
+                }
+                if self.active != Target::Primary {
+                    // outcome of a message sent before the failover
+                    return;
+                }
+                self.health.record(nacked);
+                if self.health.is_degraded() {
+                    log::warn!("Primary exporter unhealthy, switching to the secondary");
+                    self.active = Target::Secondary;
+                    self.last_probe = Instant::now();
+                    self.metrics.failovers.inc();
+                }
+            }
+        }
+    }
+}
+
+/// Checks the parts of the configuration the wrapped exporters don't check.
+fn validate(config: &Config) -> Result<(), ConfigError> {
+    let invalid = |error: &str| {
+        Err(ConfigError::InvalidUserConfig {
+            error: error.to_string(),
+        })
+    };
+    if config.health.window == 0 {
+        return invalid("health.window must be positive");
+    }
+    if !(config.health.max_nack_rate > 0.0 && config.health.max_nack_rate <= 1.0) {
+        return invalid("health.max_nack_rate must be in (0, 1]");
+    }
+    if config.health.breaker_threshold == 0 {
+        return invalid("health.breaker_threshold must be positive");
+    }
+    if config.probe_interval.is_zero() {
+        return invalid("probe_interval must be positive");
+    }
+    Ok(())
+}
+
+#[async_trait(?Send)]
+impl Exporter<OtapPdata> for FailoverExporter {
+    async fn start(
+        mut self: Box<Self>,
+        mut msg_chan: MessageChannel<OtapPdata>,
+        effect_handler: EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        for target in &mut self.targets {
+            target.start(&effect_handler);
+        }
+        let timer_cancel_handle = effect_handler
+            .start_periodic_telemetry(Duration::from_secs(1))
+            .await?;
+
+        loop {
+            match msg_chan.recv().await? {
+                Message::Control(NodeControlMsg::Shutdown { deadline, reason }) => {
+                    _ = timer_cancel_handle.cancel().await;
+                    for target in &self.targets {
+                        target
+                            .send_control(NodeControlMsg::Shutdown {
+                                deadline,
+                                reason: reason.clone(),
+                            })
+                            .await;
+                    }
+                    for target in &mut self.targets {
+                        target.join().await;
+                    }
+                    return Ok(TerminalState::new(deadline, [self.metrics]));
+                }
+                Message::Control(NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                }) => {
+                    self.metrics.active_target.set(self.active.index() as u64);
+                    _ = metrics_reporter.report(&mut self.metrics);
+                    // the wrapped exporters share the telemetry timer of this exporter
+                    for target in &self.targets {
+                        target
+                            .send_control(NodeControlMsg::CollectTelemetry {
+                                metrics_reporter: metrics_reporter.clone(),
+                            })
+                            .await;
+                    }
+                }
+                Message::Control(NodeControlMsg::TimerTick {}) => {
+                    // the wrapped exporters share the node, and thus the timers, of this exporter
+                    for target in &self.targets {
+                        target.send_control(NodeControlMsg::TimerTick {}).await;
+                    }
+                }
+                Message::Control(NodeControlMsg::Ack(ack)) => {
+                    if let Some((target, probe)) = from_calldata(&ack.calldata) {
+                        self.record(target, probe, false);
+                    }
+                    effect_handler.notify_ack(ack).await?;
+                }
+                Message::Control(NodeControlMsg::Nack(nack)) => {
+                    if let Some((target, probe)) = from_calldata(&nack.calldata) {
+                        self.record(target, probe, true);
+                    }
+                    effect_handler.notify_nack(nack).await?;
+                }
+                Message::PData(pdata) => self.dispatch(pdata, &effect_handler).await?,
+                _ => {
+                    // ignore unhandled messages
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::test_node;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use serde_json::json;
+
+    fn exporter(config: Value) -> Result<FailoverExporter, ConfigError> {
+        let controller_ctx = ControllerContext::new(MetricsRegistryHandle::new());
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        FailoverExporter::from_config(
+            pipeline_ctx,
+            test_node("failover"),
+            &config,
+            &ExporterConfig::new("failover"),
+        )
+    }
+
+    fn config(health: Value) -> Value {
+        json!({
+            "primary": { "plugin_urn": "urn:otel:noop:exporter" },
+            "secondary": { "plugin_urn": "urn:otel:noop:exporter" },
+            "health": health,
+            "probe_interval": "10s",
+        })
+    }
+
+    #[test]
+    fn test_config() {
+        let failover = exporter(config(json!({}))).expect("valid config");
+        assert_eq!(failover.probe_interval, Duration::from_secs(10));
+        assert_eq!(failover.health.config.window, 20);
+        assert_eq!(failover.targets[1].name, "secondary");
+
+        assert!(exporter(config(json!({ "window": 0 }))).is_err());
+        assert!(exporter(config(json!({ "max_nack_rate": 1.5 }))).is_err());
+        assert!(exporter(config(json!({ "breaker_threshold": 0 }))).is_err());
+        assert!(
+            exporter(json!({
+                "primary": { "plugin_urn": "urn:otel:unknown:exporter" },
+                "secondary": { "plugin_urn": "urn:otel:noop:exporter" },
+            }))
+            .is_err()
+        );
+        assert!(
+            exporter(json!({ "primary": { "plugin_urn": "urn:otel:noop:exporter" } })).is_err()
+        );
+    }
+
+    #[test]
+    fn test_health() {
+        let mut health = Health::new(HealthConfig {
+            window: 4,
+            max_nack_rate: 0.5,
+            breaker_threshold: 3,
+        });
+        // the rate is only computed on a full window
+        health.record(true);
+        health.record(false);
+        health.record(true);
+        assert!(!health.is_degraded());
+        health.record(true);
+        assert!(health.is_degraded());
+
+        // consecutive Nacks open the breaker
+        health.reset();
+        for _ in 0..2 {
+            health.record(true);
+            assert!(!health.is_degraded());
+        }
+        health.record(true);
+        assert!(health.is_degraded());
+    }
+
+    #[test]
+    fn test_failover_and_failback() {
+        let mut failover =
+            exporter(config(json!({ "breaker_threshold": 2 }))).expect("valid config");
+
+        failover.record(Target::Primary, false, true);
+        assert_eq!(failover.active, Target::Primary);
+        failover.record(Target::Primary, false, true);
+        assert_eq!(failover.active, Target::Secondary);
+        assert_eq!(failover.metrics.failovers.get(), 1);
+
+        // late outcomes of the primary and outcomes of the secondary don't switch back
+        failover.record(Target::Primary, false, false);
+        failover.record(Target::Secondary, false, true);
+        assert_eq!(failover.active, Target::Secondary);
+
+        // a failed probe keeps the secondary, a successful one switches back
+        failover.record(Target::Primary, true, true);
+        assert_eq!(failover.active, Target::Secondary);
+        failover.record(Target::Primary, true, false);
+        assert_eq!(failover.active, Target::Primary);
+        assert_eq!(failover.metrics.failbacks.get(), 1);
+        assert_eq!(failover.metrics.primary_nacks.get(), 3);
+        assert_eq!(failover.metrics.secondary_nacks.get(), 1);
+    }
+
+    #[test]
+    fn test_calldata() {
+        for (target, probe) in [(Target::Primary, true), (Target::Secondary, false)] {
+            assert_eq!(
+                from_calldata(&calldata(target, probe)),
+                Some((target, probe))
+            );
+        }
+        assert_eq!(from_calldata(&CallData::default()), None);
+    }
+
+    #[test]
+    fn test_failover_exporter_conformance() {
+        // The failover exporter relays the Acks/Nacks of its wrapped exporters once they are
+        // delivered back to its own subscription, which the test runtime does not route, so the
+        // Ack/Nack check does not apply.
+        crate::testing::conformance::run_exporter_conformance(
+            &FAILOVER_EXPORTER,
+            config(json!({})),
+            None,
+        );
+    }
+}
@@ -15,6 +15,8 @@ use async_trait::async_trait;
 use linkme::distributed_slice;
 use metrics::FakeSignalReceiverMetrics;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::config::ReceiverConfig;
 use otap_df_engine::context::PipelineContext;
 use otap_df_engine::error::{Error, ReceiverErrorKind, format_error_sources};
@@ -72,6 +74,12 @@ pub static OTAP_FAKE_DATA_GENERATOR: ReceiverFactory<OtapPdata> = ReceiverFactor
     },
 };
 
+/// Schema of the configuration of the fake data generator
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static OTAP_FAKE_DATA_GENERATOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(OTAP_FAKE_DATA_GENERATOR_URN);
+
 impl FakeGeneratorReceiver {
     /// creates a new FakeSignalReceiver
     #[must_use]
@@ -87,7 +95,7 @@ impl FakeGeneratorReceiver {
     ) -> Result<Self, otap_df_config::error::Error> {
         Ok(FakeGeneratorReceiver::new(
             pipeline_ctx,
-            serde_json::from_value(config.clone()).map_err(|e| {
+            parse_user_config(config).map_err(|e| {
                 otap_df_config::error::Error::InvalidUserConfig {
                     error: e.to_string(),
                 }
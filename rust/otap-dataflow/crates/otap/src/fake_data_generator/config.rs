@@ -7,6 +7,7 @@
 use otel_arrow_rust::proto::opentelemetry::{
     logs::v1::LogsData, metrics::v1::MetricsData, trace::v1::TracesData,
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use weaver_common::result::WResult;
@@ -27,17 +28,18 @@ pub enum OTLPSignal {
     Logs(LogsData),
 }
 /// Configuration should take a scenario to play out
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     // Configuration of the traffic to generate
     traffic_config: TrafficConfig,
     #[serde(default = "default_registry_path")]
+    #[schemars(with = "String")]
     registry_path: VirtualDirectoryPath,
 }
 
 /// Configuration to describe the traffic being sent
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct TrafficConfig {
     #[serde(default = "default_signals_per_second")]
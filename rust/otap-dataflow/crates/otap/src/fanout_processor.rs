@@ -0,0 +1,555 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fan-out processor for OTAP pipelines.
+//!
+//! This processor sends a copy of each message to several out ports, each fronted by its own
+//! bounded queue, so that a slow secondary destination cannot stall the primary one.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! destinations:
+//!   - port: primary
+//!     queue_size: 100
+//!     drop_policy: block
+//!   - port: debug
+//!     queue_size: 10
+//!     drop_policy: drop_oldest
+//! drain_interval: 100ms
+//! ```
+//!
+//! Copies are queued per destination and sent without waiting as long as the destination
+//! channel has room; what is left in a queue is retried on each message and every
+//! `drain_interval`. When a queue is full, its `drop_policy` applies:
+//! - `drop_newest` (default): the new copy is dropped;
+//! - `drop_oldest`: the oldest queued copy is dropped to make room for the new one;
+//! - `block`: processing waits until the destination accepts the oldest queued copy.
+//!
+//! The first destination receives the message with its context, so acknowledgements and
+//! retries upstream follow the primary destination; the other destinations receive copies
+//! with a fresh context. Dropped copies are nacked. Queues are flushed on shutdown.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::pdata::OtapPdata;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_channel::error::SendError;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::ConsumerEffectHandlerExtension;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{NackClass, NackMsg, NodeControlMsg};
+use otap_df_engine::error::{Error as EngineError, TypedError};
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod metrics;
+use self::metrics::FanoutProcessorMetrics;
+
+/// URN for the FanoutProcessor
+pub const FANOUT_PROCESSOR_URN: &str = "urn:otap:processor:fanout";
+
+const DEFAULT_QUEUE_SIZE: usize = 100;
+const DEFAULT_DRAIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What to do with a new copy when the queue of its destination is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DropPolicy {
+    /// Drop the new copy.
+    #[default]
+    DropNewest,
+    /// Drop the oldest queued copy.
+    DropOldest,
+    /// Wait until the destination accepts the oldest queued copy.
+    Block,
+}
+
+/// A destination of the configuration.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DestinationConfig {
+    /// Out port of the destination.
+    pub port: String,
+
+    /// Maximum number of messages queued for the destination.
+    #[serde(default = "default_queue_size")]
+    pub queue_size: usize,
+
+    /// Policy applied when the queue is full.
+    #[serde(default)]
+    pub drop_policy: DropPolicy,
+}
+
+const fn default_queue_size() -> usize {
+    DEFAULT_QUEUE_SIZE
+}
+
+/// Configuration for the FanoutProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Destinations, the first one being the primary destination.
+    pub destinations: Vec<DestinationConfig>,
+
+    /// Interval at which queued messages are retried.
+    #[serde(with = "humantime_serde", default = "default_drain_interval")]
+    #[schemars(with = "String")]
+    pub drain_interval: Duration,
+}
+
+const fn default_drain_interval() -> Duration {
+    DEFAULT_DRAIN_INTERVAL
+}
+
+/// A destination and its queue.
+struct Destination {
+    port: String,
+    queue_size: usize,
+    drop_policy: DropPolicy,
+    queue: VecDeque<OtapPdata>,
+}
+
+/// Processor duplicating messages to several out ports through independent queues.
+pub struct FanoutProcessor {
+    destinations: Vec<Destination>,
+    drain_interval: Duration,
+    timer_started: bool,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<FanoutProcessorMetrics>>,
+}
+
+impl FanoutProcessor {
+    /// Creates a new FanoutProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse FanoutProcessor configuration: {e}"),
+            })?;
+        Self::new(config)
+    }
+
+    fn new(config: Config) -> Result<Self, ConfigError> {
+        if config.destinations.is_empty() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "destinations must not be empty".to_string(),
+            });
+        }
+        if config.drain_interval.is_zero() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "drain_interval must be greater than zero".to_string(),
+            });
+        }
+        let mut ports = HashSet::new();
+        for destination in &config.destinations {
+            if destination.port.is_empty() || !ports.insert(destination.port.as_str()) {
+                return Err(ConfigError::InvalidUserConfig {
+                    error: format!("invalid or duplicate port `{}`", destination.port),
+                });
+            }
+            if destination.queue_size == 0 {
+                return Err(ConfigError::InvalidUserConfig {
+                    error: format!("queue_size of port `{}` must be positive", destination.port),
+                });
+            }
+        }
+        let destinations = config
+            .destinations
+            .into_iter()
+            .map(|destination| Destination {
+                port: destination.port,
+                queue_size: destination.queue_size,
+                drop_policy: destination.drop_policy,
+                queue: VecDeque::with_capacity(destination.queue_size),
+            })
+            .collect();
+        Ok(Self {
+            destinations,
+            drain_interval: config.drain_interval,
+            timer_started: false,
+            metrics_set: None,
+        })
+    }
+
+    /// Queue a copy for the destination with the given index, applying its drop policy.
+    async fn enqueue(
+        &mut self,
+        index: usize,
+        pdata: OtapPdata,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        let destination = &mut self.destinations[index];
+        if destination.queue.len() < destination.queue_size {
+            destination.queue.push_back(pdata);
+            return Ok(());
+        }
+        match destination.drop_policy {
+            DropPolicy::DropNewest => self.drop_copy(pdata, effect_handler).await,
+            DropPolicy::DropOldest => {
+                let oldest = destination.queue.pop_front();
+                destination.queue.push_back(pdata);
+                match oldest {
+                    Some(oldest) => self.drop_copy(oldest, effect_handler).await,
+                    None => Ok(()),
+                }
+            }
+            DropPolicy::Block => {
+                if let Some(oldest) = destination.queue.pop_front() {
+                    let port = destination.port.clone();
+                    if let Some(m) = self.metrics_set.as_mut() {
+                        m.blocked.inc();
+                    }
+                    effect_handler.send_message_to(port, oldest).await?;
+                    if let Some(m) = self.metrics_set.as_mut() {
+                        m.msgs_sent.inc();
+                    }
+                }
+                self.destinations[index].queue.push_back(pdata);
+                Ok(())
+            }
+        }
+    }
+
+    async fn drop_copy(
+        &mut self,
+        pdata: OtapPdata,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        if let Some(m) = self.metrics_set.as_mut() {
+            m.msgs_dropped.inc();
+        }
+        effect_handler
+            .notify_nack(
+                NackMsg::new("fan-out destination queue is full", pdata)
+                    .with_class(NackClass::Throttled),
+            )
+            .await
+    }
+
+    /// Send queued copies, without waiting on destinations that are full.
+    fn drain(
+        &mut self,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        for destination in &mut self.destinations {
+            while let Some(pdata) = destination.queue.pop_front() {
+                match effect_handler.try_send_message_to(destination.port.clone(), pdata) {
+                    Ok(()) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.msgs_sent.inc();
+                        }
+                    }
+                    Err(TypedError::ChannelSendError(SendError::Full(pdata))) => {
+                        destination.queue.push_front(pdata);
+                        break;
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Send all queued copies, waiting on destinations that are full.
+    async fn flush(
+        &mut self,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        for destination in &mut self.destinations {
+            while let Some(pdata) = destination.queue.pop_front() {
+                effect_handler
+                    .send_message_to(destination.port.clone(), pdata)
+                    .await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_sent.inc();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for FanoutProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                NodeControlMsg::TimerTick { .. } => self.drain(effect_handler),
+                NodeControlMsg::Shutdown { .. } => self.flush(effect_handler).await,
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                if !self.timer_started {
+                    // Processors have no start hook, so the drain timer starts on first data
+                    let _timer_cancel = effect_handler
+                        .start_periodic_timer(self.drain_interval)
+                        .await?;
+                    self.timer_started = true;
+                }
+
+                // Secondary destinations get copies with a fresh context
+                for index in 1..self.destinations.len() {
+                    let (_, payload) = pdata.clone().into_parts();
+                    let copy = OtapPdata::new_todo_context(payload);
+                    self.enqueue(index, copy, effect_handler).await?;
+                }
+                self.enqueue(0, pdata, effect_handler).await?;
+                self.drain(effect_handler)
+            }
+        }
+    }
+}
+
+/// Factory function to create a FanoutProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_fanout_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = FanoutProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<FanoutProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register FanoutProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static FANOUT_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: FANOUT_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_fanout_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the fanout processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static FANOUT_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(FANOUT_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_channel::mpsc;
+    use otap_df_engine::local::message::LocalSender;
+    use otap_df_engine::local::processor::{EffectHandler as LocalEffectHandler, Processor as _};
+    use otap_df_engine::testing::{setup_test_runtime, test_node};
+    use otap_df_telemetry::reporter::MetricsReporter;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::logs::v1::ExportLogsServiceRequest,
+        common::v1::AnyValue,
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+    };
+    use prost::Message as _;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    /// A logs message holding `count` log records.
+    fn logs(count: usize) -> OtapPdata {
+        let req = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                scope_logs: vec![ScopeLogs {
+                    log_records: (0..count)
+                        .map(|_| LogRecord {
+                            time_unix_nano: 1,
+                            body: Some(AnyValue::new_string("log")),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(bytes).into())
+    }
+
+    /// An effect handler connected to a `primary` port with room for 8 messages and to a
+    /// `secondary` port with room for 1 message.
+    fn effect_handler() -> (
+        LocalEffectHandler<OtapPdata>,
+        mpsc::Receiver<OtapPdata>,
+        mpsc::Receiver<OtapPdata>,
+    ) {
+        let (primary_tx, primary_rx) = mpsc::Channel::new(8);
+        let (secondary_tx, secondary_rx) = mpsc::Channel::new(1);
+        let mut senders = HashMap::new();
+        let _ = senders.insert("primary".into(), LocalSender::MpscSender(primary_tx));
+        let _ = senders.insert("secondary".into(), LocalSender::MpscSender(secondary_tx));
+        let (_, reporter) = MetricsReporter::create_new_and_receiver(1);
+        let eh = LocalEffectHandler::new(test_node("fanout"), senders, None, reporter);
+        (eh, primary_rx, secondary_rx)
+    }
+
+    fn processor(config: Value) -> FanoutProcessor {
+        let mut proc = FanoutProcessor::from_config(&config).expect("valid config");
+        // the bare effect handler has no pipeline control channel to start the timer on,
+        // TimerTick messages are sent by the tests instead
+        proc.timer_started = true;
+        proc
+    }
+
+    fn received(rx: &mpsc::Receiver<OtapPdata>) -> Vec<usize> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|pdata| pdata.num_items())
+            .collect()
+    }
+
+    #[test]
+    fn test_slow_destination_drops_newest() {
+        let (rt, local) = setup_test_runtime();
+        rt.block_on(local.run_until(async move {
+            let config = json!({
+                "destinations": [
+                    {"port": "primary"},
+                    {"port": "secondary", "queue_size": 1},
+                ],
+            });
+            let mut proc = processor(config);
+            let (mut eh, primary_rx, secondary_rx) = effect_handler();
+
+            for count in 1..=4 {
+                proc.process(Message::PData(logs(count)), &mut eh)
+                    .await
+                    .expect("process");
+            }
+            // The primary destination is not held back by the secondary one
+            assert_eq!(received(&primary_rx), vec![1, 2, 3, 4]);
+            // One message in the channel, one queued, the other two dropped
+            assert_eq!(received(&secondary_rx), vec![1]);
+
+            proc.process(Message::Control(NodeControlMsg::TimerTick {}), &mut eh)
+                .await
+                .expect("drain");
+            assert_eq!(received(&secondary_rx), vec![2]);
+        }));
+    }
+
+    #[test]
+    fn test_slow_destination_drops_oldest() {
+        let (rt, local) = setup_test_runtime();
+        rt.block_on(local.run_until(async move {
+            let config = json!({
+                "destinations": [
+                    {"port": "primary"},
+                    {"port": "secondary", "queue_size": 1, "drop_policy": "drop_oldest"},
+                ],
+            });
+            let mut proc = processor(config);
+            let (mut eh, primary_rx, secondary_rx) = effect_handler();
+
+            for count in 1..=4 {
+                proc.process(Message::PData(logs(count)), &mut eh)
+                    .await
+                    .expect("process");
+            }
+            assert_eq!(received(&primary_rx), vec![1, 2, 3, 4]);
+            assert_eq!(received(&secondary_rx), vec![1]);
+
+            proc.process(Message::Control(NodeControlMsg::TimerTick {}), &mut eh)
+                .await
+                .expect("drain");
+            assert_eq!(received(&secondary_rx), vec![4]);
+        }));
+    }
+
+    #[test]
+    fn test_blocking_destination_keeps_all_messages() {
+        let (rt, local) = setup_test_runtime();
+        rt.block_on(local.run_until(async move {
+            let config = json!({
+                "destinations": [
+                    {"port": "secondary", "queue_size": 1, "drop_policy": "block"},
+                    {"port": "primary"},
+                ],
+            });
+            let mut proc = processor(config);
+            let (mut eh, primary_rx, secondary_rx) = effect_handler();
+
+            let consumer = tokio::task::spawn_local(async move {
+                let mut counts = Vec::new();
+                while let Ok(pdata) = secondary_rx.recv().await {
+                    counts.push(pdata.num_items());
+                }
+                counts
+            });
+            for count in 1..=4 {
+                proc.process(Message::PData(logs(count)), &mut eh)
+                    .await
+                    .expect("process");
+            }
+            proc.process(
+                Message::Control(NodeControlMsg::Shutdown {
+                    deadline: std::time::Instant::now() + Duration::from_secs(1),
+                    reason: "test".into(),
+                }),
+                &mut eh,
+            )
+            .await
+            .expect("flush");
+            drop(eh);
+            drop(proc);
+
+            assert_eq!(consumer.await.expect("consumer"), vec![1, 2, 3, 4]);
+            assert_eq!(received(&primary_rx), vec![1, 2, 3, 4]);
+        }));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({ "destinations": [] }),
+            json!({ "destinations": [{"port": "a"}, {"port": "a"}] }),
+            json!({ "destinations": [{"port": "a", "queue_size": 0}] }),
+            json!({ "destinations": [{"port": "a", "drop_policy": "drop_random"}] }),
+            json!({ "destinations": [{"port": "a"}], "drain_interval": "0s" }),
+        ] {
+            let err = FanoutProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
@@ -0,0 +1,28 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the FanoutProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the FanoutProcessor node.
+#[metric_set(name = "fanout.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct FanoutProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// Copies of PData messages sent to a destination.
+    #[metric(unit = "{msg}")]
+    pub msgs_sent: Counter<u64>,
+
+    /// Copies of PData messages dropped because the queue of their destination was full.
+    #[metric(unit = "{msg}")]
+    pub msgs_dropped: Counter<u64>,
+
+    /// Number of times processing waited on a full destination with the `block` policy.
+    #[metric(unit = "{event}")]
+    pub blocked: Counter<u64>,
+}
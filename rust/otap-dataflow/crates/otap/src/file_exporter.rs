@@ -0,0 +1,638 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exporter writing OTLP or OTAP requests to local files.
+//!
+//! Every pdata is written as one record, either a line of OTLP/JSON (`json`), a protobuf
+//! `Export*ServiceRequest` preceded by its length as a 4-byte big-endian integer (`proto`), or a
+//! protobuf OTAP `BatchArrowRecords` framed the same way (`otap`). Every `otap` record holds
+//! self-contained Arrow IPC streams, so that it can be decoded on its own, e.g. by the replay
+//! receiver.
+//! The file is rotated when it exceeds `max_size` or gets older than `max_age`: the active file
+//! is closed and renamed with a timestamp suffix (`otlp.jsonl` becomes
+//! `otlp-20250101T120000.000.jsonl`), and only the `max_backups` most recent rotated files are
+//! kept. Records are flushed before the pdata is Acked. The writes go through the
+//! [`IoBackend`] set by `io_backend`, `std` by default.
+
+use crate::OTAP_EXPORTER_FACTORIES;
+use crate::io_backend::{BackedFile, IoBackend};
+use crate::metrics::ExporterPDataMetrics;
+use crate::otlp_json;
+use crate::pdata::{OtapPayload, OtapPdata, OtlpProtoBytes};
+use async_trait::async_trait;
+use chrono::Utc;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use linkme::distributed_slice;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ExporterConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, NackClass, NackMsg, NodeControlMsg};
+use otap_df_engine::error::{Error, ExporterErrorKind, format_error_sources};
+use otap_df_engine::exporter::ExporterWrapper;
+use otap_df_engine::local::exporter::{EffectHandler, Exporter};
+use otap_df_engine::message::{Message, MessageChannel};
+use otap_df_engine::node::NodeId;
+use otap_df_engine::terminal_state::TerminalState;
+use otap_df_engine::{ConsumerEffectHandlerExtension, ExporterFactory};
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::Producer;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use prost::Message as _;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The URN for the file exporter
+pub const FILE_EXPORTER_URN: &str = "urn:otel:file:exporter";
+
+/// Encoding of the records written to the file.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileFormat {
+    /// One OTLP/JSON request per line.
+    #[default]
+    Json,
+    /// Length-prefixed OTLP protobuf requests.
+    Proto,
+    /// Length-prefixed OTAP `BatchArrowRecords`.
+    Otap,
+}
+
+/// Compression of the written files.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileCompression {
+    /// No compression.
+    #[default]
+    None,
+    /// Gzip compression; each file is a complete gzip stream.
+    Gzip,
+}
+
+/// When the active file is rotated.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Rotation {
+    /// Rotate once the file holds this many bytes (before compression).
+    #[serde(default)]
+    pub max_size: Option<u64>,
+
+    /// Rotate once the file has been open this long.
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    pub max_age: Option<Duration>,
+
+    /// Number of rotated files to keep, all are kept when unset.
+    #[serde(default)]
+    pub max_backups: Option<usize>,
+}
+
+/// Configuration for the File Exporter
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Path of the active file. Its parent directory must exist.
+    pub path: PathBuf,
+
+    /// Encoding of the records.
+    #[serde(default)]
+    pub format: FileFormat,
+
+    /// Compression of the files.
+    #[serde(default)]
+    pub compression: FileCompression,
+
+    /// Rotation policy, the file grows unbounded when unset.
+    #[serde(default)]
+    pub rotation: Option<Rotation>,
+
+    /// Backend of the file writes.
+    #[serde(default)]
+    pub io_backend: IoBackend,
+}
+
+/// Exporter that writes OTLP data to files
+pub struct FileExporter {
+    config: Config,
+    pdata_metrics: MetricSet<ExporterPDataMetrics>,
+}
+
+/// Declare the File Exporter as a local exporter factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_EXPORTER_FACTORIES)]
+pub static FILE_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
+    name: FILE_EXPORTER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             exporter_config: &ExporterConfig| {
+        Ok(ExporterWrapper::local(
+            FileExporter::from_config(pipeline, &node_config.config)?,
+            node,
+            node_config,
+            exporter_config,
+        ))
+    },
+};
+
+/// Schema of the configuration of the file exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static FILE_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(FILE_EXPORTER_URN);
+
+impl FileExporter {
+    /// create a new instance of the `[FileExporter]` from json config value
+    pub fn from_config(
+        pipeline_ctx: PipelineContext,
+        config: &serde_json::Value,
+    ) -> Result<Self, otap_df_config::error::Error> {
+        let pdata_metrics = pipeline_ctx.register_metrics::<ExporterPDataMetrics>();
+
+        let config: Config = parse_user_config(config).map_err(|e| {
+            otap_df_config::error::Error::InvalidUserConfig {
+                error: e.to_string(),
+            }
+        })?;
+
+        Ok(Self {
+            config,
+            pdata_metrics,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Exporter<OtapPdata> for FileExporter {
+    async fn start(
+        mut self: Box<Self>,
+        mut msg_chan: MessageChannel<OtapPdata>,
+        effect_handler: EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        let exporter_id = effect_handler.exporter_id();
+        let path = self.config.path.clone();
+        let io_error = |kind: ExporterErrorKind, e: io::Error| {
+            let source_detail = format_error_sources(&e);
+            Error::ExporterError {
+                exporter: exporter_id.clone(),
+                kind,
+                error: format!("file exporter error on {}: {e}", path.display()),
+                source_detail,
+            }
+        };
+
+        let mut file = RotatingFile::open(
+            self.config.path.clone(),
+            self.config.compression,
+            self.config.rotation.clone(),
+            self.config.io_backend,
+        )
+        .map_err(|e| io_error(ExporterErrorKind::Configuration, e))?;
+
+        let timer_cancel_handle = effect_handler
+            .start_periodic_telemetry(Duration::from_secs(1))
+            .await?;
+
+        loop {
+            match msg_chan.recv().await? {
+                Message::Control(NodeControlMsg::Shutdown { deadline, .. }) => {
+                    file.close()
+                        .map_err(|e| io_error(ExporterErrorKind::Shutdown, e))?;
+                    _ = timer_cancel_handle.cancel().await;
+                    return Ok(TerminalState::new(deadline, [self.pdata_metrics]));
+                }
+                Message::Control(NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                }) => {
+                    _ = metrics_reporter.report(&mut self.pdata_metrics);
+                }
+                Message::PData(pdata) => {
+                    let signal_type = pdata.signal_type();
+                    self.pdata_metrics.inc_consumed(signal_type);
+
+                    let (context, mut payload) = pdata.into_parts();
+                    let data = context.take_payload_for_export(&mut payload);
+                    let pdata_for_notify = |payload| OtapPdata::new(context, payload);
+
+                    let record = match encode_record(data, self.config.format) {
+                        Ok(record) => record,
+                        Err(e) => {
+                            self.pdata_metrics.inc_failed(signal_type);
+                            let nack = NackMsg::new_permanent(
+                                &format!("cannot encode payload: {e}"),
+                                pdata_for_notify(payload),
+                            )
+                            .with_class(NackClass::InvalidData);
+                            effect_handler.notify_nack(nack).await?;
+                            continue;
+                        }
+                    };
+
+                    match file.write_record(&record) {
+                        Ok(()) => {
+                            self.pdata_metrics.inc_exported(signal_type);
+                            effect_handler
+                                .notify_ack(AckMsg::new(pdata_for_notify(payload)))
+                                .await?;
+                        }
+                        Err(e) => {
+                            self.pdata_metrics.inc_failed(signal_type);
+                            effect_handler
+                                .notify_nack(
+                                    NackMsg::new(
+                                        &format!("cannot write to file: {e}"),
+                                        pdata_for_notify(payload),
+                                    )
+                                    .with_class(NackClass::Unavailable),
+                                )
+                                .await?;
+                        }
+                    }
+                }
+                _ => {
+                    // ignore unhandled messages
+                }
+            }
+        }
+    }
+}
+
+/// Encodes a payload as one record of the file.
+fn encode_record(payload: OtapPayload, format: FileFormat) -> Result<Vec<u8>, String> {
+    match format {
+        FileFormat::Json => {
+            let bytes: OtlpProtoBytes = payload.try_into().map_err(|e| format!("{e}"))?;
+            let json = match &bytes {
+                OtlpProtoBytes::ExportLogsRequest(b) => otlp_json::logs_request_to_json(b),
+                OtlpProtoBytes::ExportMetricsRequest(b) => otlp_json::metrics_request_to_json(b),
+                OtlpProtoBytes::ExportTracesRequest(b) => otlp_json::traces_request_to_json(b),
+            }
+            .map_err(|e| e.to_string())?;
+            let mut record = json.into_bytes();
+            record.push(b'\n');
+            Ok(record)
+        }
+        FileFormat::Proto => {
+            let bytes: OtlpProtoBytes = payload.try_into().map_err(|e| format!("{e}"))?;
+            length_prefixed(bytes.as_bytes())
+        }
+        FileFormat::Otap => {
+            let mut records: OtapArrowRecords = payload.try_into().map_err(|e| format!("{e}"))?;
+            // a new producer per record makes every record an independent Arrow IPC stream
+            let batch = Producer::new()
+                .produce_bar(&mut records)
+                .map_err(|e| format!("{e}"))?;
+            length_prefixed(&batch.encode_to_vec())
+        }
+    }
+}
+
+fn length_prefixed(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let len = u32::try_from(bytes.len()).map_err(|_| "request too large".to_string())?;
+    let mut record = Vec::with_capacity(bytes.len() + 4);
+    record.extend_from_slice(&len.to_be_bytes());
+    record.extend_from_slice(bytes);
+    Ok(record)
+}
+
+enum Writer {
+    Plain(BufWriter<BackedFile>),
+    Gzip(GzEncoder<BufWriter<BackedFile>>),
+}
+
+impl Writer {
+    fn write_record(&mut self, record: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Plain(w) => {
+                w.write_all(record)?;
+                w.flush()
+            }
+            Self::Gzip(w) => {
+                w.write_all(record)?;
+                w.flush()
+            }
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        let mut inner = match self {
+            Self::Plain(w) => w,
+            Self::Gzip(w) => w.finish()?,
+        };
+        inner.flush()?;
+        inner.get_mut().sync_all()
+    }
+}
+
+/// The active file, rotated according to the [`Rotation`] policy.
+struct RotatingFile {
+    path: PathBuf,
+    compression: FileCompression,
+    rotation: Option<Rotation>,
+    io_backend: IoBackend,
+    writer: Option<Writer>,
+    size: u64,
+    opened_at: Instant,
+}
+
+impl RotatingFile {
+    fn open(
+        path: PathBuf,
+        compression: FileCompression,
+        rotation: Option<Rotation>,
+        io_backend: IoBackend,
+    ) -> io::Result<Self> {
+        let mut file = Self {
+            path,
+            compression,
+            rotation,
+            io_backend,
+            writer: None,
+            size: 0,
+            opened_at: Instant::now(),
+        };
+        file.open_writer()?;
+        Ok(file)
+    }
+
+    fn open_writer(&mut self) -> io::Result<()> {
+        // an existing gzip file cannot be appended to, it is rotated out of the way first
+        if self.compression == FileCompression::Gzip && self.path.exists() {
+            self.rename_to_backup()?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = file.metadata()?.len();
+        self.opened_at = Instant::now();
+        let writer = BufWriter::new(BackedFile::new(file, self.io_backend));
+        self.writer = Some(match self.compression {
+            FileCompression::None => Writer::Plain(writer),
+            FileCompression::Gzip => Writer::Gzip(GzEncoder::new(writer, Compression::default())),
+        });
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &[u8]) -> io::Result<()> {
+        if self.should_rotate(record.len() as u64) {
+            self.rotate()?;
+        }
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => return Err(io::Error::other("file is closed")),
+        };
+        writer.write_record(record)?;
+        self.size += record.len() as u64;
+        Ok(())
+    }
+
+    fn should_rotate(&self, next_record: u64) -> bool {
+        let Some(rotation) = &self.rotation else {
+            return false;
+        };
+        // never rotate an empty file, even if a single record exceeds the size limit
+        self.size > 0
+            && (rotation
+                .max_size
+                .is_some_and(|max| self.size + next_record > max)
+                || rotation
+                    .max_age
+                    .is_some_and(|max| self.opened_at.elapsed() >= max))
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.close()?;
+        self.rename_to_backup()?;
+        self.remove_old_backups()?;
+        self.open_writer()
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        match self.writer.take() {
+            Some(writer) => writer.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Splits the file name into the part before its first extension and the extensions, so
+    /// that `otlp.jsonl.gz` is rotated to `otlp-<timestamp>.jsonl.gz`.
+    fn name_parts(&self) -> (String, String) {
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match name.find('.') {
+            Some(pos) if pos > 0 => (name[..pos].to_string(), name[pos..].to_string()),
+            _ => (name, String::new()),
+        }
+    }
+
+    fn rename_to_backup(&self) -> io::Result<()> {
+        let (stem, extensions) = self.name_parts();
+        let timestamp = Utc::now().format("%Y%m%dT%H%M%S%.3f");
+        let mut backup = self
+            .path
+            .with_file_name(format!("{stem}-{timestamp}{extensions}"));
+        // several rotations within the same millisecond
+        let mut n = 1;
+        while backup.exists() {
+            backup = self
+                .path
+                .with_file_name(format!("{stem}-{timestamp}-{n}{extensions}"));
+            n += 1;
+        }
+        fs::rename(&self.path, backup)
+    }
+
+    fn remove_old_backups(&self) -> io::Result<()> {
+        let Some(max_backups) = self.rotation.as_ref().and_then(|r| r.max_backups) else {
+            return Ok(());
+        };
+        let mut backups = self.backups()?;
+        // timestamps sort lexicographically, oldest first
+        backups.sort();
+        let excess = backups.len().saturating_sub(max_backups);
+        for backup in backups.into_iter().take(excess) {
+            fs::remove_file(backup)?;
+        }
+        Ok(())
+    }
+
+    fn backups(&self) -> io::Result<Vec<PathBuf>> {
+        let (stem, extensions) = self.name_parts();
+        let prefix = format!("{stem}-");
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if name.starts_with(&prefix) && name.ends_with(&extensions) {
+                backups.push(path);
+            }
+        }
+        Ok(backups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::opentelemetry::collector::logs::v1::ExportLogsServiceRequest;
+    use crate::proto::opentelemetry::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+    use crate::testing::{test_exporter_no_subscription, test_exporter_with_subscription};
+    use flate2::read::GzDecoder;
+    use otap_df_engine::Interests;
+    use otel_arrow_rust::proto::opentelemetry::arrow::v1::BatchArrowRecords;
+    use serde_json::json;
+    use std::fs::File;
+    use std::io::Read;
+
+    fn logs_request() -> OtlpProtoBytes {
+        let request = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                scope_logs: vec![ScopeLogs {
+                    log_records: vec![LogRecord {
+                        time_unix_nano: 1,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        OtlpProtoBytes::ExportLogsRequest(request.encode_to_vec())
+    }
+
+    #[test]
+    fn test_encode_record() {
+        let bytes = logs_request();
+        let json = encode_record(bytes.clone().into(), FileFormat::Json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "{\"resourceLogs\":[{\"scopeLogs\":[{\"logRecords\":[{\"timeUnixNano\":\"1\"}]}]}]}\n"
+        );
+
+        let proto = encode_record(bytes.clone().into(), FileFormat::Proto).unwrap();
+        let len = u32::from_be_bytes(proto[..4].try_into().unwrap()) as usize;
+        assert_eq!(len, bytes.as_bytes().len());
+        assert_eq!(&proto[4..], bytes.as_bytes());
+
+        let otap = encode_record(bytes.into(), FileFormat::Otap).unwrap();
+        let len = u32::from_be_bytes(otap[..4].try_into().unwrap()) as usize;
+        let batch = BatchArrowRecords::decode(&otap[4..]).unwrap();
+        assert_eq!(len, otap.len() - 4);
+        assert!(!batch.arrow_payloads.is_empty());
+    }
+
+    #[test]
+    fn test_size_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("otlp.jsonl");
+        let mut file = RotatingFile::open(
+            path.clone(),
+            FileCompression::None,
+            Some(Rotation {
+                max_size: Some(10),
+                max_age: None,
+                max_backups: Some(2),
+            }),
+            IoBackend::Std,
+        )
+        .unwrap();
+        for _ in 0..5 {
+            file.write_record(b"0123456789").unwrap();
+        }
+        file.close().unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"0123456789");
+        let backups = file.backups().unwrap();
+        assert_eq!(backups.len(), 2);
+        for backup in backups {
+            let name = backup.file_name().unwrap().to_string_lossy().into_owned();
+            assert!(
+                name.starts_with("otlp-") && name.ends_with(".jsonl"),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_gzip_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("otlp.jsonl.gz");
+        let mut file =
+            RotatingFile::open(path.clone(), FileCompression::Gzip, None, IoBackend::Std).unwrap();
+        file.write_record(b"hello\n").unwrap();
+        file.write_record(b"world\n").unwrap();
+        file.close().unwrap();
+
+        let mut content = String::new();
+        let _ = GzDecoder::new(File::open(&path).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "hello\nworld\n");
+
+        // reopening moves the previous gzip stream aside
+        let mut file =
+            RotatingFile::open(path, FileCompression::Gzip, None, IoBackend::Std).unwrap();
+        file.close().unwrap();
+        assert_eq!(file.backups().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_file_exporter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("otlp.bin");
+        let config = json!({ "path": path, "format": "proto" });
+        test_exporter_no_subscription(&FILE_EXPORTER, config.clone());
+        test_exporter_with_subscription(
+            &FILE_EXPORTER,
+            config.clone(),
+            Interests::ACKS,
+            Interests::ACKS,
+        );
+        test_exporter_with_subscription(
+            &FILE_EXPORTER,
+            config,
+            Interests::ACKS | Interests::RETURN_DATA,
+            Interests::ACKS,
+        );
+
+        // three length-prefixed records were appended
+        let content = fs::read(&path).unwrap();
+        let mut records = 0;
+        let mut pos = 0;
+        while pos < content.len() {
+            let len = u32::from_be_bytes(content[pos..pos + 4].try_into().unwrap()) as usize;
+            pos += 4 + len;
+            records += 1;
+        }
+        assert_eq!(pos, content.len());
+        assert_eq!(records, 3);
+    }
+
+    #[test]
+    fn test_file_exporter_conformance() {
+        let dir = tempfile::tempdir().unwrap();
+        crate::testing::conformance::run_exporter_conformance(
+            &FILE_EXPORTER,
+            json!({ "path": dir.path().join("otlp.jsonl") }),
+            Some(Interests::ACKS),
+        );
+    }
+}
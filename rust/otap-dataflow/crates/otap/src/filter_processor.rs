@@ -0,0 +1,446 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Filter processor for OTAP pipelines.
+//!
+//! This processor drops the log records, spans and metrics that do not match a boolean
+//! expression. Expressions are evaluated directly on the Arrow columns of the OTAP batch and the
+//! non-matching rows are removed with Arrow's filter kernel, so the data stays columnar and is
+//! never converted to OTLP (OTLP bytes received by the processor are converted to OTAP).
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! logs: 'severity_number >= 13 and not (body matches "^GET /health")'
+//! traces: 'resource.attributes["service.name"] != "loadgen" or attributes["error"] == true'
+//! metrics: 'name != "process.runtime.heap"'
+//! ```
+//! Each expression selects the rows to keep. Signals without an expression pass through.
+//!
+//! Expressions can refer to:
+//! - columns of the log record, span or metric, e.g. `severity_number`, `severity_text`, `name`
+//!   or `kind`. Fields of struct columns are accessed with `.`, e.g. `scope.name` or
+//!   `resource.schema_url`, and `body` is the string value of the log body.
+//! - attributes of the row, its resource or its scope: `attributes["key"]`,
+//!   `resource.attributes["key"]`, `scope.attributes["key"]`.
+//!
+//! They support comparisons with strings, numbers and booleans (`==`, `!=`, `<`, `<=`, `>`,
+//! `>=`), regular expressions (`body matches "timeout|refused"`), `exists(...)`, and `and`, `or`,
+//! `not` and parentheses. A missing value, or a value of another type than the literal it is
+//! compared with, is different from the literal: `!=` holds and the other comparisons do not.
+//!
+//! Rows of the child payloads (attributes, span events and links, data points, exemplars, ...)
+//! belonging to dropped rows are dropped as well. Metric expressions select whole metrics, they
+//! are not evaluated on individual data points. Batches in which no row matches are acknowledged
+//! and not forwarded.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::pdata::{OtapPayload, OtapPdata, pdata_error};
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::ConsumerEffectHandlerExtension;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::AckMsg;
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+pub(crate) mod eval;
+pub(crate) mod expr;
+mod metrics;
+use self::expr::Expr;
+use self::metrics::FilterProcessorMetrics;
+
+/// URN for the FilterProcessor
+pub const FILTER_PROCESSOR_URN: &str = "urn:otap:processor:filter_processor";
+
+/// Configuration for the FilterProcessor.
+///
+/// Each field is an expression selecting the rows of the corresponding signal to keep.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Expression selecting the log records to keep.
+    #[serde(default)]
+    pub logs: Option<String>,
+
+    /// Expression selecting the metrics to keep.
+    #[serde(default)]
+    pub metrics: Option<String>,
+
+    /// Expression selecting the spans to keep.
+    #[serde(default)]
+    pub traces: Option<String>,
+}
+
+/// Processor that drops the rows of OTAP batches not matching a filter expression.
+pub struct FilterProcessor {
+    // Parsed expressions per signal, None when the signal passes through
+    logs: Option<Expr>,
+    metrics: Option<Expr>,
+    traces: Option<Expr>,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<FilterProcessorMetrics>>,
+}
+
+impl FilterProcessor {
+    /// Creates a new FilterProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let cfg: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse FilterProcessor configuration: {e}"),
+            })?;
+        Self::new(cfg)
+    }
+
+    /// Creates a new FilterProcessor with the given parsed configuration.
+    fn new(config: Config) -> Result<Self, ConfigError> {
+        let parse = |signal: &str, expression: Option<String>| {
+            expression
+                .map(|expression| {
+                    expr::parse(&expression).map_err(|e| ConfigError::InvalidUserConfig {
+                        error: format!("invalid {signal} filter expression `{expression}`: {e}"),
+                    })
+                })
+                .transpose()
+        };
+        Ok(Self {
+            logs: parse("logs", config.logs)?,
+            metrics: parse("metrics", config.metrics)?,
+            traces: parse("traces", config.traces)?,
+            metrics_set: None,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for FilterProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                otap_df_engine::control::NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+
+                let expression = match pdata.signal_type() {
+                    SignalType::Logs => self.logs.as_ref(),
+                    SignalType::Metrics => self.metrics.as_ref(),
+                    SignalType::Traces => self.traces.as_ref(),
+                };
+                let Some(expression) = expression else {
+                    // Fast path: nothing to filter for this signal
+                    effect_handler.send_message(pdata).await?;
+                    if let Some(m) = self.metrics_set.as_mut() {
+                        m.msgs_forwarded.inc();
+                    }
+                    return Ok(());
+                };
+
+                let (context, payload) = pdata.into_parts();
+                let mut records: OtapArrowRecords = payload.try_into()?;
+
+                let (num_rows, kept) = match eval::filter(expression, &mut records) {
+                    Ok(counts) => counts,
+                    Err(e) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.filter_failed.inc();
+                        }
+                        return Err(pdata_error(&format!("filter evaluation failed: {e}")));
+                    }
+                };
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.rows_dropped.add((num_rows - kept) as u64);
+                }
+
+                if num_rows > 0 && kept == 0 {
+                    // Nothing left to forward: the batch is fully processed
+                    if let Some(m) = self.metrics_set.as_mut() {
+                        m.msgs_dropped.inc();
+                    }
+                    let mut payload: OtapPayload = records.into();
+                    if !context.may_return_payload() {
+                        let _ = payload.take_payload();
+                    }
+                    return effect_handler
+                        .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+                        .await;
+                }
+
+                effect_handler
+                    .send_message(OtapPdata::new(context, records.into()))
+                    .await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_forwarded.inc();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Factory function to create a FilterProcessor.
+///
+/// See the module documentation for the configuration and the expression language.
+pub fn create_filter_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = FilterProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<FilterProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register FilterProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static FILTER_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: FILTER_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_filter_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the filter processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static FILTER_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(FILTER_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::{node::test_node, processor::TestRuntime};
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::{logs::v1::ExportLogsServiceRequest, trace::v1::ExportTraceServiceRequest},
+        common::v1::{AnyValue, InstrumentationScope, KeyValue},
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+        resource::v1::Resource,
+        trace::v1::{ResourceSpans, ScopeSpans, Span, span::Event},
+    };
+    use prost::Message as _;
+    use serde_json::json;
+
+    fn log(severity: i32, body: &str, attributes: Vec<KeyValue>) -> LogRecord {
+        LogRecord {
+            time_unix_nano: 1,
+            severity_number: severity,
+            body: Some(AnyValue::new_string(body)),
+            attributes,
+            ..Default::default()
+        }
+    }
+
+    fn logs_request() -> OtlpProtoBytes {
+        let req = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue::new("service.name", AnyValue::new_string("api"))],
+                    ..Default::default()
+                }),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(InstrumentationScope::default()),
+                    log_records: vec![
+                        log(
+                            9,
+                            "GET /health",
+                            vec![KeyValue::new("http.route", AnyValue::new_string("/health"))],
+                        ),
+                        log(
+                            17,
+                            "payment failed",
+                            vec![KeyValue::new("error", AnyValue::new_string("declined"))],
+                        ),
+                        log(
+                            13,
+                            "GET /orders slow",
+                            vec![KeyValue::new("http.route", AnyValue::new_string("/orders"))],
+                        ),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtlpProtoBytes::ExportLogsRequest(bytes)
+    }
+
+    fn traces_request() -> OtlpProtoBytes {
+        let span = |id: u8, name: &str| Span {
+            trace_id: vec![1; 16],
+            span_id: vec![id; 8],
+            name: name.to_string(),
+            start_time_unix_nano: 1,
+            end_time_unix_nano: 2,
+            events: vec![Event {
+                time_unix_nano: 1,
+                name: format!("{name}-event"),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let req = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource::default()),
+                scope_spans: vec![ScopeSpans {
+                    scope: Some(InstrumentationScope::default()),
+                    spans: vec![span(1, "GET /health"), span(2, "POST /pay")],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtlpProtoBytes::ExportTracesRequest(bytes)
+    }
+
+    /// Runs the processor configured with `config` on `input`, and checks its output.
+    fn run(config: Value, input: OtlpProtoBytes, check: impl FnOnce(Vec<OtapPdata>) + 'static) {
+        let pipeline_ctx = ControllerContext::new(MetricsRegistryHandle::new())
+            .pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let mut node_config = NodeUserConfig::new_processor_config(FILTER_PROCESSOR_URN);
+        node_config.config = config;
+        let proc = create_filter_processor(
+            pipeline_ctx,
+            test_node("filter-processor-test"),
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                ctx.process(Message::PData(OtapPdata::new_default(input.into())))
+                    .await
+                    .expect("process");
+                check(ctx.drain_pdata().await);
+            })
+            .validate(|_| async move {});
+    }
+
+    fn to_otlp(pdata: OtapPdata) -> OtlpProtoBytes {
+        pdata.payload().try_into().expect("convert to otlp")
+    }
+
+    #[test]
+    fn test_filter_logs() {
+        let config = json!({
+            "logs": r#"severity_number >= 13 and not exists(attributes["error"])"#,
+        });
+        run(config, logs_request(), |out| {
+            assert_eq!(out.len(), 1);
+            let OtlpProtoBytes::ExportLogsRequest(bytes) = to_otlp(out.into_iter().next().unwrap())
+            else {
+                panic!("unexpected otlp variant");
+            };
+            let decoded = ExportLogsServiceRequest::decode(bytes.as_slice()).expect("decode");
+            let resource_logs = &decoded.resource_logs[0];
+            let resource = resource_logs.resource.as_ref().unwrap();
+            assert_eq!(resource.attributes[0].key, "service.name");
+
+            let records = &resource_logs.scope_logs[0].log_records;
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].severity_number, 13);
+            assert_eq!(records[0].attributes.len(), 1);
+            assert_eq!(records[0].attributes[0].key, "http.route");
+            assert_eq!(
+                records[0].attributes[0].value,
+                Some(AnyValue::new_string("/orders"))
+            );
+        });
+    }
+
+    #[test]
+    fn test_filter_traces_drops_events_of_dropped_spans() {
+        let config = json!({ "traces": r#"not (name matches "health")"# });
+        run(config, traces_request(), |out| {
+            assert_eq!(out.len(), 1);
+            let OtlpProtoBytes::ExportTracesRequest(bytes) =
+                to_otlp(out.into_iter().next().unwrap())
+            else {
+                panic!("unexpected otlp variant");
+            };
+            let decoded = ExportTraceServiceRequest::decode(bytes.as_slice()).expect("decode");
+            let spans = &decoded.resource_spans[0].scope_spans[0].spans;
+            assert_eq!(spans.len(), 1);
+            assert_eq!(spans[0].name, "POST /pay");
+            assert_eq!(spans[0].events.len(), 1);
+            assert_eq!(spans[0].events[0].name, "POST /pay-event");
+        });
+    }
+
+    #[test]
+    fn test_batch_without_matching_rows_is_not_forwarded() {
+        let config = json!({ "logs": r#"resource.attributes["service.name"] == "web""# });
+        run(config, logs_request(), |out| assert!(out.is_empty()));
+    }
+
+    #[test]
+    fn test_signal_without_expression_passes_through() {
+        let config = json!({ "logs": "severity_number > 100" });
+        run(config, traces_request(), |out| {
+            assert_eq!(out.len(), 1);
+            assert_eq!(out[0].num_items(), 2);
+        });
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({ "logs": "severity_number >" }),
+            json!({ "traces": r#"name matches "(""# }),
+            json!({ "spans": "name == \"a\"" }),
+        ] {
+            let err = FilterProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+        assert!(FilterProcessor::from_config(&json!({})).is_ok());
+    }
+}
@@ -0,0 +1,619 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Evaluation of filter expressions over OTAP record batches.
+//!
+//! An expression is evaluated to a [`BooleanArray`] with one entry per row of the root payload
+//! (logs, spans or metrics). Column comparisons use Arrow's comparison kernels, attribute
+//! comparisons look up the matching parent ids in the attribute payloads. The root payload is
+//! then filtered with the mask, and the rows of the child payloads that no longer have a parent
+//! are removed.
+
+use super::expr::{CmpOp, Domain, Expr, Field, Literal};
+use crate::pdata::attributes::{AttributeValue, row_attribute_values};
+use crate::pdata::decode_ids;
+use arrow::array::{
+    Array, ArrayRef, AsArray, BooleanArray, Float64Array, Int64Array, RecordBatch, Scalar,
+    StringArray,
+};
+use arrow::compute::kernels::{boolean, cmp};
+use arrow::compute::{cast, filter_record_batch, is_not_null};
+use arrow::datatypes::{DataType, Int64Type, UInt32Type};
+use arrow::error::ArrowError;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::schema::consts;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Evaluate `expr` against `records` and drop the rows that do not match it.
+///
+/// Returns the number of root rows before and after filtering. When no row matches, the records
+/// are left untouched.
+pub(crate) fn filter(
+    expr: &Expr,
+    records: &mut OtapArrowRecords,
+) -> Result<(usize, usize), ArrowError> {
+    let root_type = root_payload_type(records);
+    let num_rows = records.get(root_type).map_or(0, RecordBatch::num_rows);
+    if num_rows == 0 {
+        return Ok((0, 0));
+    }
+
+    decode_ids(records)?;
+
+    let mask = matching_rows(expr, records)?;
+    let kept = mask.true_count();
+    if kept > 0 && kept < num_rows {
+        filter_rows(records, root_type, &mask)?;
+    }
+    Ok((num_rows, kept))
+}
+
+/// Evaluate `expr` to a mask over the root rows (logs, spans or metrics) of `records`.
+///
+/// The ids of `records` must have been decoded with `decode_transport_optimized_ids`.
+pub(crate) fn matching_rows(
+    expr: &Expr,
+    records: &OtapArrowRecords,
+) -> Result<BooleanArray, ArrowError> {
+    let root_type = root_payload_type(records);
+    match records.get(root_type) {
+        Some(root) => evaluate(expr, records, root_type, root),
+        None => Ok(BooleanArray::from(Vec::<bool>::new())),
+    }
+}
+
+/// Payload type of the record batch holding the logs, spans or metrics of `records`.
+pub(crate) fn root_payload_type(records: &OtapArrowRecords) -> ArrowPayloadType {
+    match records {
+        OtapArrowRecords::Logs(_) => ArrowPayloadType::Logs,
+        OtapArrowRecords::Metrics(_) => ArrowPayloadType::UnivariateMetrics,
+        OtapArrowRecords::Traces(_) => ArrowPayloadType::Spans,
+    }
+}
+
+/// Payload types whose `parent_id` refers to the `id` of the given payload type.
+///
+/// Resource and scope attributes are not included: their parent is the resource/scope id of the
+/// root rows, and entries no longer referenced by any row are simply ignored by consumers.
+const fn child_payload_types(payload_type: ArrowPayloadType) -> &'static [ArrowPayloadType] {
+    use ArrowPayloadType as A;
+    match payload_type {
+        A::Logs => &[A::LogAttrs],
+        A::Spans => &[A::SpanAttrs, A::SpanEvents, A::SpanLinks],
+        A::SpanEvents => &[A::SpanEventAttrs],
+        A::SpanLinks => &[A::SpanLinkAttrs],
+        A::UnivariateMetrics | A::MultivariateMetrics => &[
+            A::MetricAttrs,
+            A::NumberDataPoints,
+            A::SummaryDataPoints,
+            A::HistogramDataPoints,
+            A::ExpHistogramDataPoints,
+        ],
+        A::NumberDataPoints => &[A::NumberDpAttrs, A::NumberDpExemplars],
+        A::NumberDpExemplars => &[A::NumberDpExemplarAttrs],
+        A::SummaryDataPoints => &[A::SummaryDpAttrs],
+        A::HistogramDataPoints => &[A::HistogramDpAttrs, A::HistogramDpExemplars],
+        A::HistogramDpExemplars => &[A::HistogramDpExemplarAttrs],
+        A::ExpHistogramDataPoints => &[A::ExpHistogramDpAttrs, A::ExpHistogramDpExemplars],
+        A::ExpHistogramDpExemplars => &[A::ExpHistogramDpExemplarAttrs],
+        _ => &[],
+    }
+}
+
+/// Attribute payload type of the rows of the given root payload type.
+const fn attrs_payload_type(root_type: ArrowPayloadType, domain: Domain) -> ArrowPayloadType {
+    match (domain, root_type) {
+        (Domain::Resource, _) => ArrowPayloadType::ResourceAttrs,
+        (Domain::Scope, _) => ArrowPayloadType::ScopeAttrs,
+        (Domain::Signal, ArrowPayloadType::Spans) => ArrowPayloadType::SpanAttrs,
+        (Domain::Signal, ArrowPayloadType::Logs) => ArrowPayloadType::LogAttrs,
+        (Domain::Signal, _) => ArrowPayloadType::MetricAttrs,
+    }
+}
+
+/// Evaluate `expr` to a mask over the rows of `root`. The mask never contains nulls.
+fn evaluate(
+    expr: &Expr,
+    records: &OtapArrowRecords,
+    root_type: ArrowPayloadType,
+    root: &RecordBatch,
+) -> Result<BooleanArray, ArrowError> {
+    match expr {
+        Expr::And(lhs, rhs) => boolean::and(
+            &evaluate(lhs, records, root_type, root)?,
+            &evaluate(rhs, records, root_type, root)?,
+        ),
+        Expr::Or(lhs, rhs) => boolean::or(
+            &evaluate(lhs, records, root_type, root)?,
+            &evaluate(rhs, records, root_type, root)?,
+        ),
+        Expr::Not(expr) => boolean::not(&evaluate(expr, records, root_type, root)?),
+        Expr::Compare { field, op, value } => match field {
+            Field::Column(path) => compare_column(root, path, *op, value),
+            Field::Attribute { domain, key } => {
+                // `!=` is the negation of `==`, so that it holds for missing attributes
+                let (op, negate) = match op {
+                    CmpOp::Ne => (CmpOp::Eq, true),
+                    op => (*op, false),
+                };
+                attribute_mask(records, root_type, root, *domain, key, negate, |v| {
+                    compare_attribute(v, value).is_some_and(|ordering| op.test(ordering))
+                })
+            }
+        },
+        Expr::Matches { field, regex } => match field {
+            Field::Column(path) => {
+                let num_rows = root.num_rows();
+                match column(root, path)? {
+                    Some(array) if array.data_type() == &DataType::Utf8 => Ok(array
+                        .as_string::<i32>()
+                        .iter()
+                        .map(|v| Some(v.is_some_and(|s| regex.is_match(s))))
+                        .collect()),
+                    _ => Ok(BooleanArray::from(vec![false; num_rows])),
+                }
+            }
+            Field::Attribute { domain, key } => attribute_mask(
+                records,
+                root_type,
+                root,
+                *domain,
+                key,
+                false,
+                |v| matches!(v, AttributeValue::Str(s) if regex.is_match(s)),
+            ),
+        },
+        Expr::Exists(field) => match field {
+            Field::Column(path) => match column(root, path)? {
+                Some(array) => is_not_null(&array),
+                None => Ok(BooleanArray::from(vec![false; root.num_rows()])),
+            },
+            Field::Attribute { domain, key } => {
+                attribute_mask(records, root_type, root, *domain, key, false, |_| true)
+            }
+        },
+    }
+}
+
+/// Get a (possibly nested) column of `batch`, with dictionaries unpacked to their values.
+fn column(batch: &RecordBatch, path: &[String]) -> Result<Option<ArrayRef>, ArrowError> {
+    let Some((first, rest)) = path.split_first() else {
+        return Ok(None);
+    };
+    let mut array = batch.column_by_name(first).cloned();
+    for name in rest {
+        array = array
+            .as_ref()
+            .and_then(|a| a.as_struct_opt())
+            .and_then(|s| s.column_by_name(name))
+            .cloned();
+    }
+    match array {
+        Some(array) => match array.data_type() {
+            DataType::Dictionary(_, values) => cast(&array, values).map(Some),
+            _ => Ok(Some(array)),
+        },
+        None => Ok(None),
+    }
+}
+
+fn is_integer_like(data_type: &DataType) -> bool {
+    data_type.is_integer() || matches!(data_type, DataType::Timestamp(..) | DataType::Duration(_))
+}
+
+/// Compare a column with a literal. Null values, missing columns and columns of another type
+/// than the literal do not compare equal to it.
+fn compare_column(
+    batch: &RecordBatch,
+    path: &[String],
+    op: CmpOp,
+    value: &Literal,
+) -> Result<BooleanArray, ArrowError> {
+    let missing = op == CmpOp::Ne;
+    let Some(array) = column(batch, path)? else {
+        return Ok(BooleanArray::from(vec![missing; batch.num_rows()]));
+    };
+
+    let data_type = array.data_type().clone();
+    let (lhs, rhs): (ArrayRef, ArrayRef) = match value {
+        Literal::Str(s) if data_type == DataType::Utf8 => {
+            (array, Arc::new(StringArray::from(vec![s.as_str()])))
+        }
+        Literal::Bool(b) if data_type == DataType::Boolean => {
+            (array, Arc::new(BooleanArray::from(vec![*b])))
+        }
+        Literal::Int(v) if is_integer_like(&data_type) => (
+            cast(&array, &DataType::Int64)?,
+            Arc::new(Int64Array::from(vec![*v])),
+        ),
+        Literal::Int(_) | Literal::Double(_) if data_type.is_floating() => (
+            cast(&array, &DataType::Float64)?,
+            Arc::new(Float64Array::from(vec![literal_f64(value)])),
+        ),
+        Literal::Double(v) if is_integer_like(&data_type) => (
+            cast(&cast(&array, &DataType::Int64)?, &DataType::Float64)?,
+            Arc::new(Float64Array::from(vec![*v])),
+        ),
+        _ => return Ok(BooleanArray::from(vec![missing; batch.num_rows()])),
+    };
+
+    let rhs = Scalar::new(rhs);
+    let mask = match op {
+        CmpOp::Eq => cmp::eq(&lhs, &rhs)?,
+        CmpOp::Ne => cmp::neq(&lhs, &rhs)?,
+        CmpOp::Lt => cmp::lt(&lhs, &rhs)?,
+        CmpOp::Le => cmp::lt_eq(&lhs, &rhs)?,
+        CmpOp::Gt => cmp::gt(&lhs, &rhs)?,
+        CmpOp::Ge => cmp::gt_eq(&lhs, &rhs)?,
+    };
+    if mask.null_count() == 0 {
+        return Ok(mask);
+    }
+    Ok(mask.iter().map(|v| Some(v.unwrap_or(missing))).collect())
+}
+
+fn literal_f64(value: &Literal) -> f64 {
+    match value {
+        Literal::Int(v) => *v as f64,
+        Literal::Double(v) => *v,
+        _ => f64::NAN,
+    }
+}
+
+/// Order an attribute value relative to a literal, if they are comparable.
+fn compare_attribute(value: &AttributeValue<'_>, literal: &Literal) -> Option<Ordering> {
+    match (value, literal) {
+        (AttributeValue::Str(v), Literal::Str(l)) => Some(v.cmp(&l.as_str())),
+        (AttributeValue::Int(v), Literal::Int(l)) => Some(v.cmp(l)),
+        (AttributeValue::Int(v), Literal::Double(l)) => (*v as f64).partial_cmp(l),
+        (AttributeValue::Double(v), Literal::Int(_) | Literal::Double(_)) => {
+            v.partial_cmp(&literal_f64(literal))
+        }
+        (AttributeValue::Bool(v), Literal::Bool(l)) => Some(v.cmp(l)),
+        _ => None,
+    }
+}
+
+/// Mask of the root rows having a `key` attribute in `domain` whose value satisfies `predicate`,
+/// inverted if `negate` is set.
+fn attribute_mask(
+    records: &OtapArrowRecords,
+    root_type: ArrowPayloadType,
+    root: &RecordBatch,
+    domain: Domain,
+    key: &str,
+    negate: bool,
+    predicate: impl Fn(&AttributeValue<'_>) -> bool,
+) -> Result<BooleanArray, ArrowError> {
+    Ok(attribute_values(records, root_type, root, domain, key)?
+        .iter()
+        .map(|value| Some(value.as_ref().is_some_and(&predicate) != negate))
+        .collect())
+}
+
+/// Value of the `key` attribute in `domain` of each root row.
+fn attribute_values<'a>(
+    records: &'a OtapArrowRecords,
+    root_type: ArrowPayloadType,
+    root: &RecordBatch,
+    domain: Domain,
+    key: &str,
+) -> Result<Vec<Option<AttributeValue<'a>>>, ArrowError> {
+    let struct_column = match domain {
+        Domain::Signal => None,
+        Domain::Resource => Some(consts::RESOURCE),
+        Domain::Scope => Some(consts::SCOPE),
+    };
+    let attrs = records.get(attrs_payload_type(root_type, domain));
+    row_attribute_values(root, struct_column, attrs, key)
+        .map_err(|e| ArrowError::InvalidArgumentError(e.to_string()))
+}
+
+/// Value of `field` for each root row, `None` when the row doesn't have this field. Values are
+/// rendered as strings.
+///
+/// The ids of `records` must have been decoded with `decode_transport_optimized_ids`.
+pub(crate) fn field_values(
+    field: &Field,
+    records: &OtapArrowRecords,
+) -> Result<Vec<Option<String>>, ArrowError> {
+    let root_type = root_payload_type(records);
+    let Some(root) = records.get(root_type) else {
+        return Ok(Vec::new());
+    };
+    let (domain, key) = match field {
+        Field::Column(path) => {
+            let Some(array) = column(root, path)? else {
+                return Ok(vec![None; root.num_rows()]);
+            };
+            let array = cast(&array, &DataType::Utf8)?;
+            return Ok(array
+                .as_string::<i32>()
+                .iter()
+                .map(|v| v.map(str::to_string))
+                .collect());
+        }
+        Field::Attribute { domain, key } => (*domain, key),
+    };
+
+    Ok(attribute_values(records, root_type, root, domain, key)?
+        .into_iter()
+        .map(|value| match value? {
+            AttributeValue::Str(v) => Some(v.to_string()),
+            AttributeValue::Int(v) => Some(v.to_string()),
+            AttributeValue::Double(v) => Some(v.to_string()),
+            AttributeValue::Bool(v) => Some(v.to_string()),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Value of the integer-like column at `path` (e.g. `kind`, `duration_time_unix_nano`) for each
+/// root row, `None` for null values or when the column is missing.
+pub(crate) fn int_column_values(
+    records: &OtapArrowRecords,
+    path: &[String],
+) -> Result<Vec<Option<i64>>, ArrowError> {
+    let Some(root) = records.get(root_payload_type(records)) else {
+        return Ok(Vec::new());
+    };
+    let Some(array) = column(root, path)? else {
+        return Ok(vec![None; root.num_rows()]);
+    };
+    let array = cast(&array, &DataType::Int64)?;
+    Ok(array.as_primitive::<Int64Type>().iter().collect())
+}
+
+/// Ids of the rows of `batch`.
+fn row_ids(batch: &RecordBatch) -> Result<HashSet<u32>, ArrowError> {
+    let Some(ids) = batch.column_by_name(consts::ID) else {
+        return Ok(HashSet::new());
+    };
+    let ids = cast(ids, &DataType::UInt32)?;
+    Ok(ids.as_primitive::<UInt32Type>().iter().flatten().collect())
+}
+
+/// Keep the rows of `payload_type` selected by `mask`, and the rows of its child payloads that
+/// belong to them.
+pub(crate) fn filter_rows(
+    records: &mut OtapArrowRecords,
+    payload_type: ArrowPayloadType,
+    mask: &BooleanArray,
+) -> Result<(), ArrowError> {
+    let Some(batch) = records.get(payload_type) else {
+        return Ok(());
+    };
+    let batch = filter_record_batch(batch, mask)?;
+    let kept_ids = row_ids(&batch)?;
+    records.set(payload_type, batch);
+
+    for &child_type in child_payload_types(payload_type) {
+        let Some(parent_ids) = records
+            .get(child_type)
+            .and_then(|child| child.column_by_name(consts::PARENT_ID))
+        else {
+            continue;
+        };
+        let parent_ids = cast(parent_ids, &DataType::UInt32)?;
+        let mask: BooleanArray = parent_ids
+            .as_primitive::<UInt32Type>()
+            .iter()
+            .map(|id| Some(id.is_some_and(|id| kept_ids.contains(&id))))
+            .collect();
+        filter_rows(records, child_type, &mask)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter_processor::expr::parse;
+    use arrow::array::{StructArray, UInt8Array, UInt16Array};
+    use arrow::datatypes::{Field as ArrowField, Schema};
+    use otel_arrow_rust::otap::Logs;
+
+    /// Field of an id column holding plain (not delta encoded) ids.
+    fn id_field(name: &str) -> ArrowField {
+        ArrowField::new(name, DataType::UInt16, true).with_metadata(
+            [(
+                consts::metadata::COLUMN_ENCODING.to_string(),
+                consts::metadata::encodings::PLAIN.to_string(),
+            )]
+            .into(),
+        )
+    }
+
+    fn logs() -> OtapArrowRecords {
+        let resource = StructArray::from(vec![(
+            Arc::new(id_field(consts::ID)),
+            Arc::new(UInt16Array::from(vec![0, 0, 1, 1])) as ArrayRef,
+        )]);
+        let body = StructArray::from(vec![(
+            Arc::new(ArrowField::new(consts::ATTRIBUTE_STR, DataType::Utf8, true)),
+            Arc::new(StringArray::from(vec![
+                Some("GET /health"),
+                Some("payment failed"),
+                None,
+                Some("GET /orders"),
+            ])) as ArrayRef,
+        )]);
+        let root = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                id_field(consts::ID),
+                ArrowField::new(consts::RESOURCE, resource.data_type().clone(), true),
+                ArrowField::new(consts::SEVERITY_NUMBER, DataType::Int32, true),
+                ArrowField::new(consts::BODY, body.data_type().clone(), true),
+            ])),
+            vec![
+                Arc::new(UInt16Array::from(vec![0, 1, 2, 3])),
+                Arc::new(resource),
+                Arc::new(arrow::array::Int32Array::from(vec![
+                    Some(9),
+                    Some(17),
+                    Some(13),
+                    None,
+                ])),
+                Arc::new(body),
+            ],
+        )
+        .unwrap();
+
+        let attrs = |parent_ids: Vec<u16>, keys: Vec<&str>, values: Vec<&str>| {
+            RecordBatch::try_new(
+                Arc::new(Schema::new(vec![
+                    id_field(consts::PARENT_ID),
+                    ArrowField::new(consts::ATTRIBUTE_KEY, DataType::Utf8, false),
+                    ArrowField::new(consts::ATTRIBUTE_TYPE, DataType::UInt8, false),
+                    ArrowField::new(consts::ATTRIBUTE_STR, DataType::Utf8, true),
+                ])),
+                vec![
+                    Arc::new(UInt16Array::from(parent_ids)),
+                    Arc::new(StringArray::from(keys.clone())),
+                    Arc::new(UInt8Array::from(vec![1; keys.len()])),
+                    Arc::new(StringArray::from(values)),
+                ],
+            )
+            .unwrap()
+        };
+
+        let mut records = OtapArrowRecords::Logs(Logs::default());
+        records.set(ArrowPayloadType::Logs, root);
+        records.set(
+            ArrowPayloadType::LogAttrs,
+            attrs(
+                vec![0, 1, 1, 3],
+                vec!["http.route", "error", "http.route", "http.route"],
+                vec!["/health", "card declined", "/pay", "/orders"],
+            ),
+        );
+        records.set(
+            ArrowPayloadType::ResourceAttrs,
+            attrs(
+                vec![0, 1],
+                vec!["service.name", "service.name"],
+                vec!["frontend", "checkout"],
+            ),
+        );
+        records
+    }
+
+    fn mask(expr: &str) -> Vec<bool> {
+        let records = logs();
+        let root = records.get(ArrowPayloadType::Logs).unwrap();
+        let expr = parse(expr).unwrap();
+        evaluate(&expr, &records, ArrowPayloadType::Logs, root)
+            .unwrap()
+            .iter()
+            .map(|v| v.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_column_comparisons() {
+        assert_eq!(mask("severity_number >= 13"), [false, true, true, false]);
+        assert_eq!(mask("severity_number != 9"), [false, true, true, true]);
+        assert_eq!(mask("severity_number < 13.5"), [true, false, true, false]);
+        assert_eq!(
+            mask(r#"body == "GET /orders""#),
+            [false, false, false, true]
+        );
+        assert_eq!(mask(r#"body != "GET /orders""#), [true, true, true, false]);
+        assert_eq!(mask("resource.id == 1"), [false, false, true, true]);
+        // type mismatch and missing columns never compare equal
+        assert_eq!(mask(r#"severity_number == "9""#), [false; 4]);
+        assert_eq!(mask(r#"severity_text != "INFO""#), [true; 4]);
+    }
+
+    #[test]
+    fn test_regex_and_exists() {
+        assert_eq!(mask(r#"body matches "^GET ""#), [true, false, false, true]);
+        assert_eq!(mask("exists(body)"), [true, true, false, true]);
+        assert_eq!(mask("exists(severity_text)"), [false; 4]);
+        assert_eq!(
+            mask(r#"attributes["http.route"] matches "^/(pay|orders)""#),
+            [false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_attribute_comparisons() {
+        assert_eq!(
+            mask(r#"exists(attributes["error"])"#),
+            [false, true, false, false]
+        );
+        assert_eq!(
+            mask(r#"attributes["http.route"] == "/health""#),
+            [true, false, false, false]
+        );
+        // rows without the attribute are different from any value
+        assert_eq!(
+            mask(r#"attributes["http.route"] != "/health""#),
+            [false, true, true, true]
+        );
+        assert_eq!(
+            mask(r#"resource.attributes["service.name"] == "checkout""#),
+            [false, false, true, true]
+        );
+        assert_eq!(
+            mask(r#"scope.attributes["service.name"] == "checkout""#),
+            [false; 4]
+        );
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        assert_eq!(
+            mask(r#"severity_number >= 13 and not (body matches "^GET")"#),
+            [false, true, true, false]
+        );
+        assert_eq!(
+            mask(r#"severity_number >= 17 or resource.attributes["service.name"] == "checkout""#),
+            [false, true, true, true]
+        );
+    }
+
+    #[test]
+    fn test_filter_drops_child_rows() {
+        let mut records = logs();
+        let expr = parse(r#"not (body matches "health")"#).unwrap();
+        assert_eq!(filter(&expr, &mut records).unwrap(), (4, 3));
+
+        let root = records.get(ArrowPayloadType::Logs).unwrap();
+        let ids = root.column_by_name(consts::ID).unwrap();
+        let ids: Vec<_> = ids
+            .as_primitive::<arrow::datatypes::UInt16Type>()
+            .values()
+            .to_vec();
+        assert_eq!(ids, [1, 2, 3]);
+
+        let attrs = records.get(ArrowPayloadType::LogAttrs).unwrap();
+        let parent_ids = cast(
+            attrs.column_by_name(consts::PARENT_ID).unwrap(),
+            &DataType::UInt32,
+        )
+        .unwrap();
+        let parent_ids: Vec<_> = parent_ids.as_primitive::<UInt32Type>().values().to_vec();
+        assert_eq!(parent_ids, [1, 1, 3]);
+
+        // resource attributes are left as is
+        assert_eq!(
+            records
+                .get(ArrowPayloadType::ResourceAttrs)
+                .unwrap()
+                .num_rows(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_filter_keeps_records_when_nothing_matches() {
+        let mut records = logs();
+        let expr = parse("severity_number > 100").unwrap();
+        assert_eq!(filter(&expr, &mut records).unwrap(), (4, 0));
+        assert_eq!(records.get(ArrowPayloadType::Logs).unwrap().num_rows(), 4);
+    }
+}
@@ -0,0 +1,541 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Parser for the filter expression language.
+//!
+//! Grammar (keywords are case sensitive, `&&`, `||` and `!` are accepted as aliases of `and`,
+//! `or` and `not`):
+//!
+//! ```text
+//! expr       := or
+//! or         := and ("or" and)*
+//! and        := unary ("and" unary)*
+//! unary      := "not" unary | primary
+//! primary    := "(" expr ")" | "exists" "(" field ")" | field "matches" STRING | field OP literal
+//! field      := attributes "[" STRING "]" | IDENT ("." IDENT)*
+//! attributes := "attributes" | "resource.attributes" | "scope.attributes"
+//! OP         := "==" | "!=" | "<" | "<=" | ">" | ">="
+//! literal    := STRING | NUMBER | "true" | "false"
+//! ```
+
+use regex::Regex;
+use std::fmt;
+
+/// A parsed filter expression.
+#[derive(Debug, Clone)]
+pub(crate) enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare {
+        field: Field,
+        op: CmpOp,
+        value: Literal,
+    },
+    Matches {
+        field: Field,
+        regex: Regex,
+    },
+    Exists(Field),
+}
+
+/// The value an expression refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Field {
+    /// A column of the root record batch, with nested struct fields separated by `.`
+    /// (e.g. `severity_number`, `scope.name`).
+    Column(Vec<String>),
+    /// An attribute of the root record, or of its resource or scope.
+    Attribute { domain: Domain, key: String },
+}
+
+/// Which attributes an attribute field refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Domain {
+    Signal,
+    Resource,
+    Scope,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CmpOp {
+    /// Whether two values ordered as `ordering` satisfy this operator.
+    pub(crate) fn test(self, ordering: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::*;
+        match self {
+            CmpOp::Eq => ordering == Equal,
+            CmpOp::Ne => ordering != Equal,
+            CmpOp::Lt => ordering == Less,
+            CmpOp::Le => ordering != Greater,
+            CmpOp::Gt => ordering == Greater,
+            CmpOp::Ge => ordering != Less,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Literal {
+    Str(String),
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+/// Error returned when an expression cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ParseError {
+    /// Byte offset in the expression at which the error was detected.
+    pub(crate) position: usize,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse a filter expression.
+pub(crate) fn parse(input: &str) -> Result<Expr, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: input.len(),
+    };
+    let expr = parser.parse_or()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some((_, pos)) => Err(ParseError {
+            position: pos,
+            message: "unexpected trailing input".to_string(),
+        }),
+    }
+}
+
+/// Parse a single field, e.g. `severity_text` or `resource.attributes["service.name"]`.
+pub(crate) fn parse_field(input: &str) -> Result<Field, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: input.len(),
+    };
+    let field = parser.parse_field()?;
+    match parser.peek() {
+        None => Ok(field),
+        Some((_, pos)) => Err(ParseError {
+            position: pos,
+            message: "unexpected trailing input".to_string(),
+        }),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    Double(f64),
+    Op(CmpOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(pos, c)) = chars.peek() {
+        let err = |message: &str| ParseError {
+            position: pos,
+            message: message.to_string(),
+        };
+        if c.is_whitespace() {
+            let _ = chars.next();
+            continue;
+        }
+        let token = match c {
+            '(' | ')' | '[' | ']' => {
+                let _ = chars.next();
+                match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    _ => Token::RBracket,
+                }
+            }
+            '=' | '!' | '<' | '>' | '&' | '|' => {
+                let _ = chars.next();
+                let next = chars.peek().map(|&(_, c)| c);
+                let (token, two_chars) = match (c, next) {
+                    ('=', Some('=')) => (Token::Op(CmpOp::Eq), true),
+                    ('!', Some('=')) => (Token::Op(CmpOp::Ne), true),
+                    ('<', Some('=')) => (Token::Op(CmpOp::Le), true),
+                    ('>', Some('=')) => (Token::Op(CmpOp::Ge), true),
+                    ('&', Some('&')) => (Token::And, true),
+                    ('|', Some('|')) => (Token::Or, true),
+                    ('<', _) => (Token::Op(CmpOp::Lt), false),
+                    ('>', _) => (Token::Op(CmpOp::Gt), false),
+                    ('!', _) => (Token::Not, false),
+                    _ => return Err(err(&format!("unexpected character `{c}`"))),
+                };
+                if two_chars {
+                    let _ = chars.next();
+                }
+                token
+            }
+            '"' => {
+                let _ = chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(err("unterminated string")),
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, 'n')) => value.push('\n'),
+                            Some((_, 't')) => value.push('\t'),
+                            Some((_, c @ ('"' | '\\'))) => value.push(c),
+                            // keep unknown escapes as-is so regular expressions such as `\d`
+                            // can be written without doubling the backslash
+                            Some((_, c)) => {
+                                value.push('\\');
+                                value.push(c);
+                            }
+                            None => return Err(err("unterminated string")),
+                        },
+                        Some((_, c)) => value.push(c),
+                    }
+                }
+                Token::Str(value)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut text = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_ascii_digit() || c == '.' || (c == '-' && text.is_empty()) {
+                        text.push(c);
+                        let _ = chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if let Ok(value) = text.parse::<i64>() {
+                    Token::Int(value)
+                } else if let Ok(value) = text.parse::<f64>() {
+                    Token::Double(value)
+                } else {
+                    return Err(err(&format!("invalid number `{text}`")));
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut text = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+                        text.push(c);
+                        let _ = chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                match text.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Ident(text),
+                }
+            }
+            _ => return Err(err(&format!("unexpected character `{c}`"))),
+        };
+        tokens.push((token, pos));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Length of the input, reported as the position of errors at the end of the input.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(&Token, usize)> {
+        self.tokens.get(self.pos).map(|(t, pos)| (t, *pos))
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            position: self.peek().map_or(self.end, |(_, pos)| pos),
+            message: message.into(),
+        }
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), ParseError> {
+        match self.peek() {
+            Some((token, _)) if *token == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(self.error(format!("expected {what}"))),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_and()?;
+        while matches!(self.peek(), Some((Token::Or, _))) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_unary()?;
+        while matches!(self.peek(), Some((Token::And, _))) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if matches!(self.peek(), Some((Token::Not, _))) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some((Token::LParen, _)) => {
+                self.pos += 1;
+                let expr = self.parse_or()?;
+                self.expect(Token::RParen, "`)`")?;
+                Ok(expr)
+            }
+            Some((Token::Ident(name), _)) if name == "exists" => {
+                self.pos += 1;
+                self.expect(Token::LParen, "`(` after `exists`")?;
+                let field = self.parse_field()?;
+                self.expect(Token::RParen, "`)`")?;
+                Ok(Expr::Exists(field))
+            }
+            _ => {
+                let field = self.parse_field()?;
+                match self.next() {
+                    Some((Token::Op(op), _)) => {
+                        let value = self.parse_literal()?;
+                        Ok(Expr::Compare { field, op, value })
+                    }
+                    Some((Token::Ident(kw), _)) if kw == "matches" => match self.next() {
+                        Some((Token::Str(pattern), pos)) => {
+                            let regex = Regex::new(&pattern).map_err(|e| ParseError {
+                                position: pos,
+                                message: format!("invalid regular expression: {e}"),
+                            })?;
+                            Ok(Expr::Matches { field, regex })
+                        }
+                        _ => {
+                            self.pos -= 1;
+                            Err(self.error("expected a string after `matches`"))
+                        }
+                    },
+                    _ => {
+                        self.pos -= 1;
+                        Err(self.error("expected a comparison operator or `matches`"))
+                    }
+                }
+            }
+        }
+    }
+
+    fn parse_field(&mut self) -> Result<Field, ParseError> {
+        let name = match self.peek() {
+            Some((Token::Ident(name), _)) => name.clone(),
+            _ => return Err(self.error("expected a field")),
+        };
+        self.pos += 1;
+
+        let domain = match name.as_str() {
+            "attributes" => Some(Domain::Signal),
+            "resource.attributes" => Some(Domain::Resource),
+            "scope.attributes" => Some(Domain::Scope),
+            _ => None,
+        };
+        if let Some(domain) = domain {
+            self.expect(Token::LBracket, "`[` after attributes")?;
+            let key = match self.next() {
+                Some((Token::Str(key), _)) => key,
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("expected an attribute key string"));
+                }
+            };
+            self.expect(Token::RBracket, "`]`")?;
+            return Ok(Field::Attribute { domain, key });
+        }
+
+        let path: Vec<String> = name.split('.').map(str::to_string).collect();
+        if path.iter().any(String::is_empty) {
+            self.pos -= 1;
+            return Err(self.error(format!("invalid field `{name}`")));
+        }
+        // `body` refers to the string value of the log body
+        if path == ["body"] {
+            return Ok(Field::Column(vec!["body".to_string(), "str".to_string()]));
+        }
+        Ok(Field::Column(path))
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal, ParseError> {
+        let literal = match self.peek() {
+            Some((Token::Str(s), _)) => Literal::Str(s.clone()),
+            Some((Token::Int(v), _)) => Literal::Int(*v),
+            Some((Token::Double(v), _)) => Literal::Double(*v),
+            Some((Token::Ident(kw), _)) if kw == "true" => Literal::Bool(true),
+            Some((Token::Ident(kw), _)) if kw == "false" => Literal::Bool(false),
+            _ => return Err(self.error("expected a string, number or boolean")),
+        };
+        self.pos += 1;
+        Ok(literal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(path: &[&str]) -> Field {
+        Field::Column(path.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_parse_comparisons() {
+        let Expr::Compare { field, op, value } = parse("severity_number >= 9").unwrap() else {
+            panic!("expected comparison");
+        };
+        assert_eq!(field, column(&["severity_number"]));
+        assert_eq!(op, CmpOp::Ge);
+        assert_eq!(value, Literal::Int(9));
+
+        let Expr::Compare { field, op, value } =
+            parse(r#"resource.attributes["service.name"] != "checkout""#).unwrap()
+        else {
+            panic!("expected comparison");
+        };
+        assert_eq!(
+            field,
+            Field::Attribute {
+                domain: Domain::Resource,
+                key: "service.name".into()
+            }
+        );
+        assert_eq!(op, CmpOp::Ne);
+        assert_eq!(value, Literal::Str("checkout".into()));
+
+        let Expr::Compare { field, value, .. } = parse("scope.name == -1.5").unwrap() else {
+            panic!("expected comparison");
+        };
+        assert_eq!(field, column(&["scope", "name"]));
+        assert_eq!(value, Literal::Double(-1.5));
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        // `and` binds tighter than `or`, `not` tighter than `and`
+        let expr = parse(r#"not body matches "a" and exists(attributes["k"]) or true_col == true"#)
+            .unwrap();
+        let Expr::Or(lhs, rhs) = expr else {
+            panic!("expected or");
+        };
+        let Expr::And(not, exists) = *lhs else {
+            panic!("expected and");
+        };
+        let Expr::Not(matches) = *not else {
+            panic!("expected not");
+        };
+        let Expr::Matches { field, .. } = *matches else {
+            panic!("expected matches");
+        };
+        assert_eq!(field, column(&["body", "str"]));
+        assert!(matches!(*exists, Expr::Exists(Field::Attribute { .. })));
+        assert!(matches!(
+            *rhs,
+            Expr::Compare {
+                value: Literal::Bool(true),
+                ..
+            }
+        ));
+
+        let expr = parse(r#"!(a == 1 || b == 2) && c == 3"#).unwrap();
+        let Expr::And(lhs, _) = expr else {
+            panic!("expected and");
+        };
+        assert!(matches!(*lhs, Expr::Not(_)));
+    }
+
+    #[test]
+    fn test_parse_string_escapes() {
+        let Expr::Matches { regex, .. } = parse(r#"body matches "^\d+ \"ok\"$""#).unwrap() else {
+            panic!("expected matches");
+        };
+        assert!(regex.is_match(r#"200 "ok""#));
+        assert!(!regex.is_match("ok"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (input, position) in [
+            ("severity_number >=", 18),
+            ("severity_number 9", 16),
+            ("(a == 1", 7),
+            (r#"attributes[k] == 1"#, 11),
+            (r#"body matches "(""#, 13),
+            ("a == 1 b == 2", 7),
+            (r#""unterminated"#, 0),
+            ("a ~ 1", 2),
+        ] {
+            let err = parse(input).expect_err(input);
+            assert_eq!(err.position, position, "{input}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_parse_single_field() {
+        assert_eq!(
+            parse_field("severity_text").unwrap(),
+            column(&["severity_text"])
+        );
+        assert_eq!(
+            parse_field(r#"resource.attributes["service.name"]"#).unwrap(),
+            Field::Attribute {
+                domain: Domain::Resource,
+                key: "service.name".to_string(),
+            }
+        );
+        assert!(parse_field("severity_text == 1").is_err());
+        assert!(parse_field("").is_err());
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the FilterProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the FilterProcessor node.
+#[metric_set(name = "filter.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct FilterProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// PData messages dropped because none of their rows matched.
+    #[metric(unit = "{msg}")]
+    pub msgs_dropped: Counter<u64>,
+
+    /// Rows (log records, spans or metrics) that did not match and were dropped.
+    #[metric(unit = "{row}")]
+    pub rows_dropped: Counter<u64>,
+
+    /// Number of failed filter evaluations.
+    #[metric(unit = "{op}")]
+    pub filter_failed: Counter<u64>,
+}
@@ -0,0 +1,557 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Receiver bridging the internal telemetry of the engine into a pipeline.
+//!
+//! The receiver periodically reads the metric sets aggregated in the metrics registry (the same
+//! ones exposed by the admin endpoints), converts them into OTLP metrics and sends them down the
+//! pipeline. Connecting it to an OTLP exporter (or any other exporter) lets the agent monitor
+//! itself with the same backends it serves, e.g.:
+//!
+//! ```yaml
+//! nodes:
+//!   self_telemetry:
+//!     kind: receiver
+//!     plugin_urn: "urn:otel:internal_telemetry:receiver"
+//!     out_ports:
+//!       out_port:
+//!         destinations: [otlp_exporter]
+//!         dispatch_strategy: round_robin
+//!     config:
+//!       interval: 30s
+//!       resource_attributes:
+//!         deployment.environment: prod
+//! ```
+//!
+//! Each field of a metric set becomes an OTLP metric named `<metric set>.<field>` (the `.metrics`
+//! suffix of the metric set names is dropped), with the metric set attributes (e.g. `node.id`,
+//! `core.id`) as data point attributes. Counters are reported as cumulative monotonic sums,
+//! up-down counters as cumulative non-monotonic sums, gauges as gauges, and histograms as
+//! cumulative explicit-bucket histograms.
+//!
+//! Note: the metrics are read without being reset, counters only restart from zero when they are
+//! reset through the admin API.
+
+use crate::OTAP_RECEIVER_FACTORIES;
+use crate::pdata::{OtapPdata, OtlpProtoBytes};
+use crate::proto::opentelemetry::collector::metrics::v1::ExportMetricsServiceRequest;
+use crate::proto::opentelemetry::common::v1::{
+    AnyValue, InstrumentationScope, KeyValue, any_value,
+};
+use crate::proto::opentelemetry::metrics::v1::{
+    AggregationTemporality, Gauge, Histogram, HistogramDataPoint, Metric, NumberDataPoint,
+    ResourceMetrics, ScopeMetrics, Sum, metric, number_data_point,
+};
+use crate::proto::opentelemetry::resource::v1::Resource;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::ReceiverFactory;
+use otap_df_engine::config::ReceiverConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::NodeControlMsg;
+use otap_df_engine::error::Error;
+use otap_df_engine::local::receiver as local;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::receiver::ReceiverWrapper;
+use otap_df_engine::terminal_state::TerminalState;
+use otap_df_telemetry::attributes::AttributeValue;
+use otap_df_telemetry::descriptor::{Instrument, MetricsDescriptor, MetricsField};
+use otap_df_telemetry::instrument::{Counter, HISTOGRAM_BOUNDS};
+use otap_df_telemetry::metrics::MetricSet;
+use otap_df_telemetry::registry::{MetricValue, MetricsRegistryHandle};
+use otap_df_telemetry_macros::metric_set;
+use prost::Message as _;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::{MissedTickBehavior, interval};
+
+/// The URN for the internal telemetry receiver
+pub const INTERNAL_TELEMETRY_RECEIVER_URN: &str = "urn:otel:internal_telemetry:receiver";
+
+/// Name of the instrumentation scope of the exported metrics.
+const SCOPE_NAME: &str = "otap-df-internal-telemetry";
+
+/// Configuration for the internal telemetry receiver
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Interval between two exports of the internal metrics.
+    #[serde(with = "humantime_serde", default = "default_interval")]
+    #[schemars(with = "String")]
+    pub interval: Duration,
+
+    /// Value of the `service.name` resource attribute.
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+
+    /// Additional resource attributes.
+    #[serde(default)]
+    pub resource_attributes: BTreeMap<String, String>,
+}
+
+const fn default_interval() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_service_name() -> String {
+    "otap-dataflow".to_owned()
+}
+
+/// Internal telemetry receiver metrics.
+#[metric_set(name = "internal_telemetry.receiver.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct InternalTelemetryReceiverMetrics {
+    /// Number of OTLP metrics requests sent down the pipeline.
+    #[metric(unit = "{msg}")]
+    pub exports: Counter<u64>,
+
+    /// Number of data points sent down the pipeline.
+    #[metric(unit = "{datapoint}")]
+    pub data_points: Counter<u64>,
+}
+
+/// A Receiver converting the internal metrics of the engine into OTLP metrics.
+pub struct InternalTelemetryReceiver {
+    config: Config,
+    registry: MetricsRegistryHandle,
+    metrics: MetricSet<InternalTelemetryReceiverMetrics>,
+}
+
+/// Declares the internal telemetry receiver as a local receiver factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_RECEIVER_FACTORIES)]
+pub static INTERNAL_TELEMETRY_RECEIVER: ReceiverFactory<OtapPdata> = ReceiverFactory {
+    name: INTERNAL_TELEMETRY_RECEIVER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             receiver_config: &ReceiverConfig| {
+        Ok(ReceiverWrapper::local(
+            InternalTelemetryReceiver::from_config(pipeline, &node_config.config)?,
+            node,
+            node_config,
+            receiver_config,
+        ))
+    },
+};
+
+/// Schema of the configuration of the internal telemetry receiver
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static INTERNAL_TELEMETRY_RECEIVER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(INTERNAL_TELEMETRY_RECEIVER_URN);
+
+impl InternalTelemetryReceiver {
+    /// Creates a new internal telemetry receiver from a configuration object
+    pub fn from_config(
+        pipeline_ctx: PipelineContext,
+        config: &Value,
+    ) -> Result<Self, otap_df_config::error::Error> {
+        let config: Config = parse_user_config(config).map_err(|e| {
+            otap_df_config::error::Error::InvalidUserConfig {
+                error: e.to_string(),
+            }
+        })?;
+        if config.interval.is_zero() {
+            return Err(otap_df_config::error::Error::InvalidUserConfig {
+                error: "interval must be greater than zero".to_owned(),
+            });
+        }
+        let metrics = pipeline_ctx.register_metrics::<InternalTelemetryReceiverMetrics>();
+        Ok(Self {
+            config,
+            registry: pipeline_ctx.metrics_registry(),
+            metrics,
+        })
+    }
+
+    fn resource(&self) -> Resource {
+        let mut attributes = vec![string_attribute("service.name", &self.config.service_name)];
+        attributes.extend(
+            self.config
+                .resource_attributes
+                .iter()
+                .map(|(key, value)| string_attribute(key, value)),
+        );
+        Resource {
+            attributes,
+            ..Default::default()
+        }
+    }
+
+    /// Reads the current metrics of the registry.
+    fn collect(&self) -> Vec<CollectedMetricSet> {
+        let mut sets = Vec::new();
+        self.registry
+            .visit_current_metrics(|descriptor, attributes, metrics_iter| {
+                sets.push(CollectedMetricSet {
+                    descriptor,
+                    attributes: attributes
+                        .iter_attributes()
+                        .map(|(key, value)| attribute(key, value))
+                        .collect(),
+                    values: metrics_iter
+                        .with_histograms()
+                        .map(|(field, value)| (field, value.into()))
+                        .collect(),
+                });
+            });
+        sets
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Receiver<OtapPdata> for InternalTelemetryReceiver {
+    async fn start(
+        mut self: Box<Self>,
+        mut ctrl_msg_recv: local::ControlChannel<OtapPdata>,
+        effect_handler: local::EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        let resource = self.resource();
+        let start_time_unix_nano = unix_nanos(SystemTime::now());
+        let mut ticker = interval(self.config.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes immediately, nothing has been measured yet.
+        let _ = ticker.tick().await;
+
+        let telemetry_cancel_handle = effect_handler
+            .start_periodic_telemetry(Duration::from_secs(1))
+            .await?;
+
+        loop {
+            tokio::select! {
+                biased;
+
+                ctrl_msg = ctrl_msg_recv.recv() => match ctrl_msg {
+                    Ok(NodeControlMsg::Shutdown { deadline, .. }) => {
+                        let snapshot = self.metrics.snapshot();
+                        _ = telemetry_cancel_handle.cancel().await;
+                        return Ok(TerminalState::new(deadline, [snapshot]));
+                    }
+                    Ok(NodeControlMsg::CollectTelemetry { mut metrics_reporter }) => {
+                        _ = metrics_reporter.report(&mut self.metrics);
+                    }
+                    Err(e) => return Err(Error::ChannelRecvError(e)),
+                    _ => {
+                        // unknown control message do nothing
+                    }
+                },
+
+                _ = ticker.tick() => {
+                    let sets = self.collect();
+                    let now = unix_nanos(SystemTime::now());
+                    let Some((request, data_points)) =
+                        build_request(&sets, resource.clone(), start_time_unix_nano, now)
+                    else {
+                        continue;
+                    };
+                    effect_handler
+                        .send_message(OtapPdata::new_todo_context(
+                            OtlpProtoBytes::ExportMetricsRequest(request.encode_to_vec()).into(),
+                        ))
+                        .await?;
+                    self.metrics.exports.inc();
+                    self.metrics.data_points.add(data_points);
+                }
+            }
+        }
+    }
+}
+
+/// The values of a metric set read from the registry.
+struct CollectedMetricSet {
+    descriptor: &'static MetricsDescriptor,
+    attributes: Vec<KeyValue>,
+    values: Vec<(&'static MetricsField, CollectedValue)>,
+}
+
+/// The value of a metric read from the registry.
+enum CollectedValue {
+    Value(u64),
+    Histogram {
+        count: u64,
+        sum: u64,
+        bucket_counts: Vec<u64>,
+    },
+}
+
+impl From<MetricValue<'_>> for CollectedValue {
+    fn from(value: MetricValue<'_>) -> Self {
+        match value {
+            MetricValue::Value(v) => Self::Value(v),
+            MetricValue::Histogram(h) => Self::Histogram {
+                count: h.count(),
+                sum: h.sum(),
+                bucket_counts: h.buckets().map(|(_, count)| count).collect(),
+            },
+        }
+    }
+}
+
+/// Data points of a metric, grouped across the metric sets.
+#[derive(Default)]
+struct DataPoints {
+    numbers: Vec<NumberDataPoint>,
+    histograms: Vec<HistogramDataPoint>,
+}
+
+/// Converts the collected metric sets into an OTLP request, returning it with its number of data
+/// points. Returns `None` if there is nothing to export.
+fn build_request(
+    sets: &[CollectedMetricSet],
+    resource: Resource,
+    start_time_unix_nano: u64,
+    time_unix_nano: u64,
+) -> Option<(ExportMetricsServiceRequest, u64)> {
+    // Data points of the same metric (e.g. one per node and core) are grouped in a single metric.
+    let mut metrics: BTreeMap<String, (&'static MetricsField, DataPoints)> = BTreeMap::new();
+    let mut data_points = 0;
+    for set in sets {
+        let set_name = set
+            .descriptor
+            .name
+            .strip_suffix(".metrics")
+            .unwrap_or(set.descriptor.name);
+        for (field, value) in &set.values {
+            let points = &mut metrics
+                .entry(format!("{set_name}.{}", field.name))
+                .or_insert_with(|| (*field, DataPoints::default()))
+                .1;
+            match value {
+                CollectedValue::Value(value) => points.numbers.push(NumberDataPoint {
+                    attributes: set.attributes.clone(),
+                    start_time_unix_nano: match field.instrument {
+                        Instrument::Counter | Instrument::UpDownCounter => start_time_unix_nano,
+                        Instrument::Gauge | Instrument::Histogram => 0,
+                    },
+                    time_unix_nano,
+                    value: Some(number_data_point::Value::AsInt(
+                        i64::try_from(*value).unwrap_or(i64::MAX),
+                    )),
+                    ..Default::default()
+                }),
+                CollectedValue::Histogram {
+                    count,
+                    sum,
+                    bucket_counts,
+                } => points.histograms.push(HistogramDataPoint {
+                    attributes: set.attributes.clone(),
+                    start_time_unix_nano,
+                    time_unix_nano,
+                    count: *count,
+                    sum: Some(*sum as f64),
+                    bucket_counts: bucket_counts.clone(),
+                    explicit_bounds: HISTOGRAM_BOUNDS.iter().map(|&b| b as f64).collect(),
+                    ..Default::default()
+                }),
+            }
+            data_points += 1;
+        }
+    }
+    if metrics.is_empty() {
+        return None;
+    }
+
+    let metrics = metrics
+        .into_iter()
+        .map(|(name, (field, data_points))| {
+            let DataPoints {
+                numbers: data_points,
+                histograms,
+            } = data_points;
+            let data = match field.instrument {
+                Instrument::Counter | Instrument::UpDownCounter => metric::Data::Sum(Sum {
+                    data_points,
+                    aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                    is_monotonic: field.instrument == Instrument::Counter,
+                }),
+                Instrument::Gauge => metric::Data::Gauge(Gauge { data_points }),
+                Instrument::Histogram => metric::Data::Histogram(Histogram {
+                    data_points: histograms,
+                    aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                }),
+            };
+            Metric {
+                name,
+                description: field.brief.to_owned(),
+                unit: field.unit.to_owned(),
+                data: Some(data),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    let request = ExportMetricsServiceRequest {
+        resource_metrics: vec![ResourceMetrics {
+            resource: Some(resource),
+            scope_metrics: vec![ScopeMetrics {
+                scope: Some(InstrumentationScope {
+                    name: SCOPE_NAME.to_owned(),
+                    version: env!("CARGO_PKG_VERSION").to_owned(),
+                    ..Default::default()
+                }),
+                metrics,
+                schema_url: String::new(),
+            }],
+            schema_url: String::new(),
+        }],
+    };
+    Some((request, data_points))
+}
+
+fn attribute(key: &str, value: &AttributeValue) -> KeyValue {
+    let value = match value {
+        AttributeValue::String(s) => any_value::Value::StringValue(s.clone()),
+        AttributeValue::Int(i) => any_value::Value::IntValue(*i),
+        AttributeValue::UInt(u) => {
+            any_value::Value::IntValue(i64::try_from(*u).unwrap_or(i64::MAX))
+        }
+        AttributeValue::Double(d) => any_value::Value::DoubleValue(*d),
+        AttributeValue::Boolean(b) => any_value::Value::BoolValue(*b),
+    };
+    KeyValue {
+        key: key.to_owned(),
+        value: Some(AnyValue { value: Some(value) }),
+    }
+}
+
+fn string_attribute(key: &str, value: &str) -> KeyValue {
+    attribute(key, &AttributeValue::String(value.to_owned()))
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static EXPORTER_METRICS: MetricsDescriptor = MetricsDescriptor {
+        name: "otlp.exporter.metrics",
+        metrics: &[
+            MetricsField {
+                name: "sent",
+                unit: "{msg}",
+                brief: "Messages sent",
+                instrument: Instrument::Counter,
+            },
+            MetricsField {
+                name: "queue.size",
+                unit: "{msg}",
+                brief: "Messages queued",
+                instrument: Instrument::Gauge,
+            },
+        ],
+    };
+
+    fn exporter_set(node_id: &str, sent: u64, queued: u64) -> CollectedMetricSet {
+        CollectedMetricSet {
+            descriptor: &EXPORTER_METRICS,
+            attributes: vec![string_attribute("node.id", node_id)],
+            values: vec![
+                (&EXPORTER_METRICS.metrics[0], CollectedValue::Value(sent)),
+                (&EXPORTER_METRICS.metrics[1], CollectedValue::Value(queued)),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_build_request() {
+        assert!(build_request(&[], Resource::default(), 1, 2).is_none());
+
+        let sets = [exporter_set("otlp", 10, 2), exporter_set("arrow", 5, 0)];
+        let (request, data_points) =
+            build_request(&sets, Resource::default(), 1, 2).expect("metrics to export");
+        assert_eq!(data_points, 4);
+
+        let metrics = &request.resource_metrics[0].scope_metrics[0].metrics;
+        let names: Vec<&str> = metrics.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["otlp.exporter.queue.size", "otlp.exporter.sent"]);
+
+        match &metrics[1].data {
+            Some(metric::Data::Sum(sum)) => {
+                assert!(sum.is_monotonic);
+                assert_eq!(
+                    sum.aggregation_temporality,
+                    AggregationTemporality::Cumulative as i32
+                );
+                assert_eq!(sum.data_points.len(), 2);
+                assert_eq!(sum.data_points[0].start_time_unix_nano, 1);
+                assert_eq!(
+                    sum.data_points[0].value,
+                    Some(number_data_point::Value::AsInt(10))
+                );
+                assert_eq!(sum.data_points[1].attributes[0].key, "node.id");
+            }
+            other => panic!("expected a sum, got {other:?}"),
+        }
+        assert!(matches!(metrics[0].data, Some(metric::Data::Gauge(_))));
+
+        // The request round-trips through the OTLP encoding.
+        let decoded = ExportMetricsServiceRequest::decode(request.encode_to_vec().as_slice())
+            .expect("valid OTLP request");
+        assert_eq!(decoded, request);
+    }
+
+    static LATENCY_METRICS: MetricsDescriptor = MetricsDescriptor {
+        name: "node.latency.metrics",
+        metrics: &[MetricsField {
+            name: "processing.duration",
+            unit: "us",
+            brief: "Processing duration",
+            instrument: Instrument::Histogram,
+        }],
+    };
+
+    #[test]
+    fn test_build_histogram() {
+        let mut bucket_counts = vec![0; HISTOGRAM_BOUNDS.len() + 1];
+        bucket_counts[2] = 3;
+        let sets = [CollectedMetricSet {
+            descriptor: &LATENCY_METRICS,
+            attributes: vec![string_attribute("node.id", "batch")],
+            values: vec![(
+                &LATENCY_METRICS.metrics[0],
+                CollectedValue::Histogram {
+                    count: 3,
+                    sum: 12,
+                    bucket_counts,
+                },
+            )],
+        }];
+        let (request, data_points) =
+            build_request(&sets, Resource::default(), 1, 2).expect("metrics to export");
+        assert_eq!(data_points, 1);
+
+        let metric = &request.resource_metrics[0].scope_metrics[0].metrics[0];
+        assert_eq!(metric.name, "node.latency.processing.duration");
+        let Some(metric::Data::Histogram(histogram)) = &metric.data else {
+            panic!("expected a histogram, got {:?}", metric.data);
+        };
+        let point = &histogram.data_points[0];
+        assert_eq!((point.count, point.sum), (3, Some(12.0)));
+        assert_eq!(point.explicit_bounds.len() + 1, point.bucket_counts.len());
+        assert_eq!(point.bucket_counts[2], 3);
+    }
+
+    #[test]
+    fn test_config() {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "interval": "30s",
+            "resource_attributes": {"deployment.environment": "prod"}
+        }))
+        .expect("valid config");
+        assert_eq!(config.interval, Duration::from_secs(30));
+        assert_eq!(config.service_name, "otap-dataflow");
+        assert!(serde_json::from_value::<Config>(serde_json::json!({"unknown": 1})).is_err());
+    }
+}
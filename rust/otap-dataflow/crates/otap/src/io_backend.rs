@@ -0,0 +1,324 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! File IO backends of the nodes writing to local files.
+//!
+//! The file exporter and the persistent queue processor write through a [`BackedFile`], selected
+//! by their `io_backend` setting:
+//!
+//! - `std` (default): blocking writes and fsyncs through the standard library;
+//! - `io_uring`: on Linux with the `io-uring` feature, a write and the fsync following it are
+//!   submitted to an io_uring as linked entries and completed with a single system call.
+//!
+//! Writes complete before returning with both backends, so the nodes keep their durability
+//! guarantees. When io_uring is unavailable (feature disabled, other OS, or a kernel or seccomp
+//! policy refusing to set up the ring), the file falls back to the `std` backend with a warning.
+//!
+//! The network IO of the receivers is not covered: their sockets are driven by the Tokio reactor,
+//! and moving them to io_uring requires a completion-based runtime.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{self, Write};
+
+/// Backend used for the file writes of a node.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IoBackend {
+    /// Standard library file IO.
+    #[default]
+    Std,
+    /// io_uring submissions, falling back to `std` when unavailable.
+    IoUring,
+}
+
+/// A file written through an [`IoBackend`].
+///
+/// Writes are unbuffered and go to the current file position (the end of the file when opened
+/// in append mode).
+#[derive(Debug)]
+pub struct BackedFile {
+    file: File,
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    ring: Option<uring::Ring>,
+}
+
+impl BackedFile {
+    /// Wraps `file`, falling back to the `std` backend if `backend` is unavailable.
+    #[must_use]
+    pub fn new(file: File, backend: IoBackend) -> Self {
+        match backend {
+            IoBackend::Std => Self::std(file),
+            IoBackend::IoUring => Self::io_uring(file),
+        }
+    }
+
+    fn std(file: File) -> Self {
+        Self {
+            file,
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            ring: None,
+        }
+    }
+
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    fn io_uring(file: File) -> Self {
+        match uring::Ring::new(&file) {
+            Ok(ring) => Self {
+                file,
+                ring: Some(ring),
+            },
+            Err(e) => {
+                log::warn!("io_uring unavailable, falling back to std file IO: {e}");
+                Self::std(file)
+            }
+        }
+    }
+
+    #[cfg(not(all(feature = "io-uring", target_os = "linux")))]
+    fn io_uring(file: File) -> Self {
+        log::warn!(
+            "io_uring requires the `io-uring` feature on Linux, falling back to std file IO"
+        );
+        Self::std(file)
+    }
+
+    /// Backend actually in use.
+    #[must_use]
+    pub fn backend(&self) -> IoBackend {
+        #[cfg(all(feature = "io-uring", target_os = "linux"))]
+        if self.ring.is_some() {
+            return IoBackend::IoUring;
+        }
+        IoBackend::Std
+    }
+
+    /// Writes all of `buf`, then flushes the file data to the disk if `sync` is set.
+    pub fn write_all_synced(&mut self, buf: &[u8], sync: bool) -> io::Result<()> {
+        #[cfg(all(feature = "io-uring", target_os = "linux"))]
+        if let Some(ring) = self.ring.as_mut() {
+            return ring.write_all(buf, sync);
+        }
+        self.file.write_all(buf)?;
+        if sync {
+            self.file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the file data to the disk.
+    pub fn sync_data(&mut self) -> io::Result<()> {
+        #[cfg(all(feature = "io-uring", target_os = "linux"))]
+        if let Some(ring) = self.ring.as_mut() {
+            return ring.sync(true);
+        }
+        self.file.sync_data()
+    }
+
+    /// Flushes the file data and metadata to the disk.
+    pub fn sync_all(&mut self) -> io::Result<()> {
+        #[cfg(all(feature = "io-uring", target_os = "linux"))]
+        if let Some(ring) = self.ring.as_mut() {
+            return ring.sync(false);
+        }
+        self.file.sync_all()
+    }
+
+    /// Underlying file.
+    #[must_use]
+    pub fn get_ref(&self) -> &File {
+        &self.file
+    }
+}
+
+impl Write for BackedFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_all_synced(buf, false)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+mod uring {
+    use io_uring::{IoUring, opcode, squeue, types};
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::{AsRawFd, RawFd};
+
+    /// Largest write submitted at once, the length of an entry being a `u32`.
+    const MAX_WRITE: usize = 1 << 30;
+    /// Offset of a write at the current file position.
+    const CURRENT_POSITION: u64 = u64::MAX;
+
+    /// A ring dedicated to the writes of one file, used synchronously.
+    pub(super) struct Ring {
+        ring: IoUring,
+        fd: RawFd,
+    }
+
+    impl std::fmt::Debug for Ring {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Ring")
+                .field("fd", &self.fd)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl Ring {
+        /// Sets up a ring for `file`, which must outlive it.
+        pub(super) fn new(file: &File) -> io::Result<Self> {
+            let ring = IoUring::new(2)?;
+            if !ring.params().is_feature_rw_cur_pos() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "kernel does not support writes at the file position",
+                ));
+            }
+            Ok(Self {
+                ring,
+                fd: file.as_raw_fd(),
+            })
+        }
+
+        pub(super) fn write_all(&mut self, mut buf: &[u8], sync: bool) -> io::Result<()> {
+            let mut synced = !sync;
+            while !buf.is_empty() {
+                let len = buf.len().min(MAX_WRITE);
+                let write = opcode::Write::new(types::Fd(self.fd), buf.as_ptr(), len as u32)
+                    .offset(CURRENT_POSITION)
+                    .build()
+                    .user_data(0);
+                // the fsync is linked to the last write, it is cancelled if the write comes short
+                let link = sync && len == buf.len();
+                let results = if link {
+                    let fsync = self.fsync(true);
+                    self.submit(&[write.flags(squeue::Flags::IO_LINK), fsync])?
+                } else {
+                    self.submit(&[write])?
+                };
+                let written = completion(results[0])?;
+                if written == 0 {
+                    return Err(io::ErrorKind::WriteZero.into());
+                }
+                buf = &buf[written..];
+                if link && buf.is_empty() {
+                    let _ = completion(results[1])?;
+                    synced = true;
+                }
+            }
+            if !synced {
+                self.sync(true)?;
+            }
+            Ok(())
+        }
+
+        pub(super) fn sync(&mut self, data_only: bool) -> io::Result<()> {
+            let fsync = self.fsync(data_only);
+            let results = self.submit(&[fsync])?;
+            completion(results[0]).map(|_| ())
+        }
+
+        fn fsync(&self, data_only: bool) -> squeue::Entry {
+            let flags = if data_only {
+                types::FsyncFlags::DATASYNC
+            } else {
+                types::FsyncFlags::empty()
+            };
+            opcode::Fsync::new(types::Fd(self.fd))
+                .flags(flags)
+                .build()
+                .user_data(1)
+        }
+
+        /// Submits up to two entries, tagged with their index as user data, and waits for all of
+        /// them to complete. Returns their results in order.
+        fn submit(&mut self, entries: &[squeue::Entry]) -> io::Result<[i32; 2]> {
+            // SAFETY: the buffers referenced by the entries are borrowed by the caller for the
+            // duration of this call, which only returns once every entry has completed.
+            #[allow(unsafe_code)]
+            unsafe {
+                self.ring
+                    .submission()
+                    .push_multiple(entries)
+                    .map_err(|_| io::Error::other("io_uring submission queue is full"))?;
+            }
+            let mut results = [0; 2];
+            let mut completed = 0;
+            while completed < entries.len() {
+                match self.ring.submit_and_wait(entries.len() - completed) {
+                    Ok(_) => {}
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+                for cqe in self.ring.completion() {
+                    if let Some(result) = results.get_mut(cqe.user_data() as usize) {
+                        *result = cqe.result();
+                    }
+                    completed += 1;
+                }
+            }
+            Ok(results)
+        }
+    }
+
+    /// Converts a completion result, a negated errno on failure.
+    fn completion(result: i32) -> io::Result<usize> {
+        if result < 0 {
+            Err(io::Error::from_raw_os_error(-result))
+        } else {
+            Ok(result as usize)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+
+    fn append_records(backend: IoBackend) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("records");
+        let open = || {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .unwrap()
+        };
+
+        let mut file = BackedFile::new(open(), backend);
+        file.write_all_synced(b"hello ", false).unwrap();
+        file.write_all_synced(b"world", true).unwrap();
+        file.sync_all().unwrap();
+        drop(file);
+
+        let mut file = BackedFile::new(open(), backend);
+        file.write_all(b"!").unwrap();
+        file.sync_data().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world!");
+    }
+
+    #[test]
+    fn test_std_backend() {
+        append_records(IoBackend::Std);
+    }
+
+    #[test]
+    fn test_io_uring_backend() {
+        // falls back to std IO when io_uring is unavailable, the content is the same
+        append_records(IoBackend::IoUring);
+    }
+
+    #[test]
+    fn test_io_backend_config() {
+        let backend: IoBackend = serde_json::from_value(serde_json::json!("io_uring")).unwrap();
+        assert_eq!(backend, IoBackend::IoUring);
+        assert!(serde_json::from_value::<IoBackend>(serde_json::json!("epoll")).is_err());
+    }
+}
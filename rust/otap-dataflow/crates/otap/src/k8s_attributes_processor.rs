@@ -0,0 +1,607 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Kubernetes attributes processor for OTAP pipelines.
+//!
+//! This processor stamps pod, namespace, deployment and node attributes onto the resources of
+//! all signals, so that telemetry can be routed and queried by Kubernetes workload. The pod
+//! metadata comes from one of two sources:
+//!
+//! - `downward_api`: the files of a downward API volume describing the pod the pipeline runs in
+//!   (sidecar deployments). Every resource is associated with that pod.
+//! - `pod_list`: a file holding a pod list in the JSON format of the Kubernetes API, e.g. kept
+//!   up to date by an informer sidecar, and reloaded every `refresh_interval` (node agent
+//!   deployments). Resources are associated with a pod by their `k8s.pod.uid` attribute, or
+//!   else by their `k8s.pod.ip` attribute.
+//!
+//! The attributes set are `k8s.pod.name`, `k8s.pod.uid`, `k8s.namespace.name`,
+//! `k8s.node.name`, `k8s.deployment.name` (for pods of a deployment) and
+//! `k8s.pod.label.<label>` for each of the configured `labels`. Attributes a resource already has
+//! are kept, unless `override` is set.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! source:
+//!   type: pod_list
+//!   path: /var/run/otap/pods.json
+//!   refresh_interval: 30s
+//! labels: [app, tenant]
+//! override: false
+//! ```
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::attributes_processor::value_actions::{
+    append_str_attributes, parent_ids, unpack_dictionaries,
+};
+use crate::pdata::attributes::{AttributeIter, AttributeValue};
+use crate::pdata::{OtapPdata, pdata_error};
+use arrow::array::{AsArray, BooleanArray, RecordBatch};
+use arrow::compute::{cast, filter_record_batch};
+use arrow::datatypes::{DataType, UInt32Type};
+use arrow::error::ArrowError;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::NodeControlMsg;
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::schema::consts;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod metrics;
+mod pods;
+use self::metrics::K8sAttributesProcessorMetrics;
+use self::pods::{Pod, PodTable, read_downward_api};
+
+/// URN for the K8sAttributesProcessor
+pub const K8S_ATTRIBUTES_PROCESSOR_URN: &str = "urn:otap:processor:k8s_attributes";
+
+/// Default interval between two reloads of the pod list.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where the pod metadata comes from.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PodSource {
+    /// Downward API volume describing the pod the pipeline runs in.
+    DownwardApi {
+        /// Directory the volume is mounted on.
+        path: PathBuf,
+    },
+    /// File holding a pod list in the JSON format of the Kubernetes API.
+    PodList {
+        /// Path of the pod list file.
+        path: PathBuf,
+        /// Interval between two reloads of the file.
+        #[serde(with = "humantime_serde", default = "default_refresh_interval")]
+        #[schemars(with = "String")]
+        refresh_interval: Duration,
+    },
+}
+
+/// Configuration for the K8sAttributesProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Source of the pod metadata.
+    pub source: PodSource,
+
+    /// Pod labels stamped as `k8s.pod.label.<label>` attributes.
+    #[serde(default)]
+    pub labels: Vec<String>,
+
+    /// Overwrite the resource attributes that are already set with the pod metadata.
+    #[serde(default, rename = "override")]
+    pub override_existing: bool,
+}
+
+const fn default_refresh_interval() -> Duration {
+    DEFAULT_REFRESH_INTERVAL
+}
+
+/// Pod metadata loaded from the configured source.
+enum Pods {
+    /// The pod the pipeline runs in.
+    Own(Pod),
+    /// All the pods of a pod list.
+    Table(PodTable),
+}
+
+impl Pods {
+    fn load(source: &PodSource) -> Result<Self, String> {
+        match source {
+            PodSource::DownwardApi { path } => read_downward_api(path).map(Pods::Own),
+            PodSource::PodList { path, .. } => {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+                PodTable::from_json(&content).map(Pods::Table)
+            }
+        }
+    }
+
+    fn lookup(&self, uid: Option<&str>, ip: Option<&str>) -> Option<&Pod> {
+        match self {
+            Pods::Own(pod) => Some(pod),
+            Pods::Table(table) => table.lookup(uid, ip),
+        }
+    }
+}
+
+/// Number of resources and attributes changed by the enrichment of a batch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct EnrichStats {
+    associated: u64,
+    unassociated: u64,
+    attributes_set: u64,
+}
+
+/// Processor stamping Kubernetes pod metadata onto resources.
+pub struct K8sAttributesProcessor {
+    config: Config,
+    // Pod metadata, loaded on first data
+    pods: Option<Pods>,
+    timer_started: bool,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<K8sAttributesProcessorMetrics>>,
+}
+
+impl K8sAttributesProcessor {
+    /// Creates a new K8sAttributesProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse K8sAttributesProcessor configuration: {e}"),
+            })?;
+        if let PodSource::PodList {
+            refresh_interval, ..
+        } = &config.source
+        {
+            if refresh_interval.is_zero() {
+                return Err(ConfigError::InvalidUserConfig {
+                    error: "refresh_interval must be greater than 0".to_string(),
+                });
+            }
+        }
+        Ok(Self {
+            config,
+            pods: None,
+            timer_started: false,
+            metrics_set: None,
+        })
+    }
+
+    /// Load the pod metadata, keeping the previous metadata when it can't be read.
+    fn reload(&mut self) {
+        match Pods::load(&self.config.source) {
+            Ok(pods) => self.pods = Some(pods),
+            Err(e) => {
+                log::warn!("k8s attributes: {e}");
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.pod_metadata_failed.inc();
+                }
+            }
+        }
+    }
+
+    /// Set the attributes of the pod associated with each resource of `records`.
+    fn enrich(&self, records: &mut OtapArrowRecords) -> Result<EnrichStats, ArrowError> {
+        let mut stats = EnrichStats::default();
+        let Some(pods) = self.pods.as_ref() else {
+            return Ok(stats);
+        };
+
+        // attributes are matched to their resource by id
+        records
+            .decode_transport_optimized_ids()
+            .map_err(|e| ArrowError::InvalidArgumentError(e.to_string()))?;
+        let resources = parent_ids(records, ArrowPayloadType::ResourceAttrs)?;
+
+        // keys of the attributes of each resource, and the values identifying its pod
+        let mut existing: HashMap<u32, HashSet<String>> = HashMap::new();
+        let mut uids: HashMap<u32, String> = HashMap::new();
+        let mut ips: HashMap<u32, String> = HashMap::new();
+        if let Some(attrs) = records.get(ArrowPayloadType::ResourceAttrs) {
+            let attrs = AttributeIter::try_new(attrs)
+                .map_err(|e| ArrowError::InvalidArgumentError(e.to_string()))?;
+            for attr in attrs {
+                let _ = existing
+                    .entry(attr.parent_id)
+                    .or_default()
+                    .insert(attr.key.to_string());
+                if let AttributeValue::Str(value) = attr.value {
+                    match attr.key {
+                        "k8s.pod.uid" => {
+                            let _ = uids.insert(attr.parent_id, value.to_string());
+                        }
+                        "k8s.pod.ip" => {
+                            let _ = ips.insert(attr.parent_id, value.to_string());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        let mut new_rows = Vec::new();
+        let mut replaced: HashSet<(u32, String)> = HashSet::new();
+        for id in resources {
+            let uid = uids.get(&id).map(String::as_str);
+            let ip = ips.get(&id).map(String::as_str);
+            let Some(pod) = pods.lookup(uid, ip) else {
+                stats.unassociated += 1;
+                continue;
+            };
+            stats.associated += 1;
+            let keys = existing.get(&id);
+            for (key, value) in pod.attributes(&self.config.labels) {
+                if keys.is_some_and(|keys| keys.contains(&key)) {
+                    if !self.config.override_existing {
+                        continue;
+                    }
+                    let _ = replaced.insert((id, key.clone()));
+                }
+                new_rows.push((id, key, value));
+            }
+        }
+
+        if !replaced.is_empty() {
+            if let Some(attrs) = records.get(ArrowPayloadType::ResourceAttrs) {
+                let attrs = unpack_dictionaries(attrs)?;
+                let keep = replaced_rows_mask(&attrs, &replaced)?;
+                records.set(
+                    ArrowPayloadType::ResourceAttrs,
+                    filter_record_batch(&attrs, &keep)?,
+                );
+            }
+        }
+        stats.attributes_set = new_rows.len() as u64;
+        append_str_attributes(records, ArrowPayloadType::ResourceAttrs, &new_rows)?;
+        Ok(stats)
+    }
+}
+
+/// Rows of an unpacked attribute batch whose `(parent id, key)` is not in `replaced`.
+fn replaced_rows_mask(
+    attrs: &RecordBatch,
+    replaced: &HashSet<(u32, String)>,
+) -> Result<BooleanArray, ArrowError> {
+    let missing = |name: &str| ArrowError::SchemaError(format!("missing {name} column"));
+    let parent_ids = attrs
+        .column_by_name(consts::PARENT_ID)
+        .ok_or_else(|| missing(consts::PARENT_ID))?;
+    let parent_ids = cast(parent_ids, &DataType::UInt32)?;
+    let parent_ids = parent_ids.as_primitive::<UInt32Type>();
+    let keys = attrs
+        .column_by_name(consts::ATTRIBUTE_KEY)
+        .ok_or_else(|| missing(consts::ATTRIBUTE_KEY))?
+        .as_string::<i32>();
+    Ok((0..attrs.num_rows())
+        .map(|row| {
+            let replaced = parent_ids.is_valid(row)
+                && keys.is_valid(row)
+                && replaced.contains(&(parent_ids.value(row), keys.value(row).to_string()));
+            Some(!replaced)
+        })
+        .collect())
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for K8sAttributesProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                NodeControlMsg::TimerTick { .. } => {
+                    self.reload();
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                if !self.timer_started {
+                    // Processors have no start hook, so the metadata is loaded on first data
+                    self.reload();
+                    if let PodSource::PodList {
+                        refresh_interval, ..
+                    } = self.config.source
+                    {
+                        let _timer_cancel = effect_handler
+                            .start_periodic_timer(refresh_interval)
+                            .await?;
+                    }
+                    self.timer_started = true;
+                }
+
+                let (context, payload) = pdata.into_parts();
+                let mut records: OtapArrowRecords = payload.try_into()?;
+                match self.enrich(&mut records) {
+                    Ok(stats) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.resources_associated.add(stats.associated);
+                            m.resources_unassociated.add(stats.unassociated);
+                            m.attributes_set.add(stats.attributes_set);
+                        }
+                    }
+                    Err(e) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.enrich_failed.inc();
+                        }
+                        return Err(pdata_error(&format!("k8s attributes failed: {e}")));
+                    }
+                }
+
+                effect_handler
+                    .send_message(OtapPdata::new(context, records.into()))
+                    .await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_forwarded.inc();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Factory function to create a K8sAttributesProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_k8s_attributes_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = K8sAttributesProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<K8sAttributesProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register K8sAttributesProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static K8S_ATTRIBUTES_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: K8S_ATTRIBUTES_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_k8s_attributes_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the k8s attributes processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static K8S_ATTRIBUTES_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(K8S_ATTRIBUTES_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::{node::test_node, processor::TestRuntime};
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::logs::v1::ExportLogsServiceRequest,
+        common::v1::{AnyValue, InstrumentationScope, KeyValue, any_value},
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+        resource::v1::Resource,
+    };
+    use prost::Message as _;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn resource_logs(attributes: Vec<KeyValue>) -> ResourceLogs {
+        ResourceLogs {
+            resource: Some(Resource {
+                attributes,
+                ..Default::default()
+            }),
+            scope_logs: vec![ScopeLogs {
+                scope: Some(InstrumentationScope::default()),
+                log_records: vec![LogRecord {
+                    time_unix_nano: 1,
+                    body: Some(AnyValue::new_string("hello")),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn logs_request(resource_logs: Vec<ResourceLogs>) -> OtapPdata {
+        let req = ExportLogsServiceRequest { resource_logs };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(bytes).into())
+    }
+
+    /// Attributes of each resource of the forwarded logs.
+    fn resource_attributes(pdata: OtapPdata) -> Vec<BTreeMap<String, String>> {
+        let OtlpProtoBytes::ExportLogsRequest(bytes) =
+            pdata.payload().try_into().expect("convert to otlp")
+        else {
+            panic!("unexpected otlp variant");
+        };
+        ExportLogsServiceRequest::decode(bytes.as_slice())
+            .expect("decode")
+            .resource_logs
+            .into_iter()
+            .map(|rl| {
+                rl.resource
+                    .unwrap_or_default()
+                    .attributes
+                    .into_iter()
+                    .map(|kv| match kv.value.and_then(|v| v.value) {
+                        Some(any_value::Value::StringValue(s)) => (kv.key, s),
+                        other => panic!("unexpected value {other:?}"),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn run(config: Value, input: OtapPdata, check: impl FnOnce(OtapPdata) + 'static) {
+        let pipeline_ctx = ControllerContext::new(MetricsRegistryHandle::new())
+            .pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let mut node_config = NodeUserConfig::new_processor_config(K8S_ATTRIBUTES_PROCESSOR_URN);
+        node_config.config = config;
+        let proc = create_k8s_attributes_processor(
+            pipeline_ctx,
+            test_node("k8s-attributes-processor-test"),
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                ctx.process(Message::PData(input)).await.expect("process");
+                let mut out = ctx.drain_pdata().await;
+                assert_eq!(out.len(), 1);
+                check(out.remove(0));
+            })
+            .validate(|_| async move {});
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("otap-k8s-attributes-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir
+    }
+
+    #[test]
+    fn test_pod_list_association_by_uid_and_ip() {
+        let dir = temp_dir("pod-list");
+        let pods = json!({
+            "items": [
+                {
+                    "metadata": {
+                        "name": "checkout-7d9f8b6c5-x2x4p",
+                        "uid": "uid-1",
+                        "namespace": "shop",
+                        "labels": {"app": "checkout", "pod-template-hash": "7d9f8b6c5"}
+                    },
+                    "spec": {"nodeName": "node-a"},
+                    "status": {"podIP": "10.1.0.5"}
+                },
+                {
+                    "metadata": {"name": "db-0", "uid": "uid-2", "namespace": "data"},
+                    "spec": {"nodeName": "node-b"},
+                    "status": {"podIP": "10.1.0.6"}
+                }
+            ]
+        });
+        let path = dir.join("pods.json");
+        std::fs::write(&path, pods.to_string()).expect("write pod list");
+
+        let input = logs_request(vec![
+            resource_logs(vec![KeyValue::new(
+                "k8s.pod.ip",
+                AnyValue::new_string("10.1.0.5"),
+            )]),
+            resource_logs(vec![
+                KeyValue::new("k8s.pod.uid", AnyValue::new_string("uid-2")),
+                KeyValue::new("k8s.namespace.name", AnyValue::new_string("custom")),
+            ]),
+            resource_logs(vec![KeyValue::new(
+                "k8s.pod.ip",
+                AnyValue::new_string("10.9.9.9"),
+            )]),
+        ]);
+        let config = json!({
+            "source": {"type": "pod_list", "path": path},
+            "labels": ["app"],
+        });
+        run(config, input, |out| {
+            let resources = resource_attributes(out);
+            assert_eq!(resources.len(), 3);
+            assert_eq!(resources[0]["k8s.pod.name"], "checkout-7d9f8b6c5-x2x4p");
+            assert_eq!(resources[0]["k8s.deployment.name"], "checkout");
+            assert_eq!(resources[0]["k8s.pod.label.app"], "checkout");
+            assert_eq!(resources[0]["k8s.node.name"], "node-a");
+            assert_eq!(resources[1]["k8s.pod.name"], "db-0");
+            assert_eq!(resources[1]["k8s.namespace.name"], "custom");
+            assert_eq!(resources[2].len(), 1, "unknown pods are left alone");
+        });
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_downward_api_with_override() {
+        let dir = temp_dir("downward-api");
+        std::fs::write(dir.join("pod_name"), "web-0\n").expect("write");
+        std::fs::write(dir.join("namespace"), "shop\n").expect("write");
+
+        let input = logs_request(vec![resource_logs(vec![KeyValue::new(
+            "k8s.namespace.name",
+            AnyValue::new_string("custom"),
+        )])]);
+        let config = json!({
+            "source": {"type": "downward_api", "path": dir},
+            "override": true,
+        });
+        run(config, input, |out| {
+            let resources = resource_attributes(out);
+            assert_eq!(resources[0]["k8s.pod.name"], "web-0");
+            assert_eq!(resources[0]["k8s.namespace.name"], "shop");
+            assert_eq!(resources[0].len(), 2);
+        });
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({}),
+            json!({ "source": {"type": "api_server"} }),
+            json!({ "source": {"type": "pod_list", "path": "p", "refresh_interval": "0s"} }),
+        ] {
+            let err = K8sAttributesProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
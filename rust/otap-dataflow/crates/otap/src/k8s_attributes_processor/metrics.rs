@@ -0,0 +1,40 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the K8sAttributesProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the K8sAttributesProcessor node.
+#[metric_set(name = "k8s_attributes.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct K8sAttributesProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// Resources associated with a pod.
+    #[metric(unit = "{resource}")]
+    pub resources_associated: Counter<u64>,
+
+    /// Resources no pod could be associated with.
+    #[metric(unit = "{resource}")]
+    pub resources_unassociated: Counter<u64>,
+
+    /// Resource attributes set from pod metadata.
+    #[metric(unit = "{attr}")]
+    pub attributes_set: Counter<u64>,
+
+    /// Failed reads of the pod metadata.
+    #[metric(unit = "{op}")]
+    pub pod_metadata_failed: Counter<u64>,
+
+    /// Number of messages whose resources could not be enriched.
+    #[metric(unit = "{msg}")]
+    pub enrich_failed: Counter<u64>,
+}
@@ -0,0 +1,235 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pod metadata of the Kubernetes attributes processor, read from downward API files or from a
+//! pod list file.
+
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Label set by the deployment controller on the pods of a ReplicaSet.
+const POD_TEMPLATE_HASH: &str = "pod-template-hash";
+
+/// Metadata of a pod.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct Pod {
+    pub(super) name: String,
+    pub(super) uid: String,
+    pub(super) namespace: String,
+    pub(super) node_name: String,
+    pub(super) ip: String,
+    pub(super) labels: BTreeMap<String, String>,
+}
+
+impl Pod {
+    /// Resource attributes describing the pod. `labels` selects the pod labels stamped as
+    /// `k8s.pod.label.<label>`.
+    pub(super) fn attributes(&self, labels: &[String]) -> Vec<(String, String)> {
+        let mut attributes: Vec<(String, String)> = [
+            ("k8s.pod.name", &self.name),
+            ("k8s.pod.uid", &self.uid),
+            ("k8s.namespace.name", &self.namespace),
+            ("k8s.node.name", &self.node_name),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| (key.to_string(), value.clone()))
+        .collect();
+        if let Some(deployment) = self.deployment_name() {
+            attributes.push(("k8s.deployment.name".to_string(), deployment.to_string()));
+        }
+        for label in labels {
+            if let Some(value) = self.labels.get(label) {
+                attributes.push((format!("k8s.pod.label.{label}"), value.clone()));
+            }
+        }
+        attributes
+    }
+
+    /// Name of the deployment owning the pod, from the `<deployment>-<pod-template-hash>-<id>`
+    /// name the deployment controller gives to its pods.
+    fn deployment_name(&self) -> Option<&str> {
+        let hash = self.labels.get(POD_TEMPLATE_HASH)?;
+        let (deployment, _) = self.name.rsplit_once(&format!("-{hash}-"))?;
+        (!deployment.is_empty()).then_some(deployment)
+    }
+}
+
+/// Read the pod the pipeline runs in from a downward API volume.
+///
+/// The volume is expected to hold `pod_name`, `pod_uid`, `namespace`, `node_name`, `pod_ip` and
+/// `labels` files (any of them may be missing), as projected with `fieldRef`s on
+/// `metadata.name`, `metadata.uid`, `metadata.namespace`, `spec.nodeName`, `status.podIP` and
+/// `metadata.labels`.
+pub(super) fn read_downward_api(dir: &Path) -> Result<Pod, String> {
+    let read = |name: &str| {
+        std::fs::read_to_string(dir.join(name))
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default()
+    };
+    let pod = Pod {
+        name: read("pod_name"),
+        uid: read("pod_uid"),
+        namespace: read("namespace"),
+        node_name: read("node_name"),
+        ip: read("pod_ip"),
+        labels: parse_downward_api_labels(&read("labels")),
+    };
+    if pod == Pod::default() {
+        return Err(format!("no pod metadata found in {}", dir.display()));
+    }
+    Ok(pod)
+}
+
+/// Parse the `key="value"` lines of a downward API labels file.
+pub(super) fn parse_downward_api_labels(content: &str) -> BTreeMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            // values are quoted and escaped like JSON strings
+            let value = serde_json::from_str::<String>(value.trim()).ok()?;
+            Some((key.trim().to_string(), value))
+        })
+        .collect()
+}
+
+/// Pods of a pod list, indexed by uid and by IP.
+#[derive(Debug, Default)]
+pub(super) struct PodTable {
+    pods: Vec<Pod>,
+    by_uid: HashMap<String, usize>,
+    by_ip: HashMap<String, usize>,
+}
+
+impl PodTable {
+    /// Parse a pod list in the JSON format of the Kubernetes API (`kubectl get pods -o json`).
+    pub(super) fn from_json(content: &str) -> Result<Self, String> {
+        let list: Value =
+            serde_json::from_str(content).map_err(|e| format!("invalid pod list: {e}"))?;
+        let items = list
+            .get("items")
+            .and_then(Value::as_array)
+            .ok_or_else(|| "invalid pod list: missing `items` array".to_string())?;
+
+        let mut table = PodTable::default();
+        for item in items {
+            let str_at = |pointer: &str| {
+                item.pointer(pointer)
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let labels = item
+                .pointer("/metadata/labels")
+                .and_then(Value::as_object)
+                .map(|labels| {
+                    labels
+                        .iter()
+                        .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let pod = Pod {
+                name: str_at("/metadata/name"),
+                uid: str_at("/metadata/uid"),
+                namespace: str_at("/metadata/namespace"),
+                node_name: str_at("/spec/nodeName"),
+                ip: str_at("/status/podIP"),
+                labels,
+            };
+
+            let index = table.pods.len();
+            if !pod.uid.is_empty() {
+                let _ = table.by_uid.insert(pod.uid.clone(), index);
+            }
+            // host network pods share the node IP, which doesn't identify them
+            let host_network = item
+                .pointer("/spec/hostNetwork")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            if !pod.ip.is_empty() && !host_network {
+                let _ = table.by_ip.insert(pod.ip.clone(), index);
+            }
+            table.pods.push(pod);
+        }
+        Ok(table)
+    }
+
+    /// Pod with the given uid, or else with the given IP.
+    pub(super) fn lookup(&self, uid: Option<&str>, ip: Option<&str>) -> Option<&Pod> {
+        uid.and_then(|uid| self.by_uid.get(uid))
+            .or_else(|| ip.and_then(|ip| self.by_ip.get(ip)))
+            .map(|index| &self.pods[*index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pod_attributes() {
+        let pod = Pod {
+            name: "checkout-7d9f8b6c5-x2x4p".into(),
+            uid: "uid-1".into(),
+            namespace: "shop".into(),
+            node_name: "node-a".into(),
+            ip: "10.1.0.5".into(),
+            labels: parse_downward_api_labels(
+                "app=\"checkout\"\npod-template-hash=\"7d9f8b6c5\"\nteam=\"a \\\"b\\\"\"\n",
+            ),
+        };
+        assert_eq!(pod.labels["team"], "a \"b\"");
+        assert_eq!(
+            pod.attributes(&["app".into(), "missing".into()]),
+            vec![
+                (
+                    "k8s.pod.name".to_string(),
+                    "checkout-7d9f8b6c5-x2x4p".to_string()
+                ),
+                ("k8s.pod.uid".to_string(), "uid-1".to_string()),
+                ("k8s.namespace.name".to_string(), "shop".to_string()),
+                ("k8s.node.name".to_string(), "node-a".to_string()),
+                ("k8s.deployment.name".to_string(), "checkout".to_string()),
+                ("k8s.pod.label.app".to_string(), "checkout".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pod_table_lookup() {
+        let list = json!({
+            "kind": "PodList",
+            "items": [
+                {
+                    "metadata": {"name": "web-0", "uid": "uid-1", "namespace": "shop"},
+                    "spec": {"nodeName": "node-a"},
+                    "status": {"podIP": "10.1.0.5"}
+                },
+                {
+                    "metadata": {"name": "agent-abc", "uid": "uid-2", "namespace": "kube-system"},
+                    "spec": {"nodeName": "node-a", "hostNetwork": true},
+                    "status": {"podIP": "192.168.0.10"}
+                }
+            ]
+        });
+        let table = PodTable::from_json(&list.to_string()).expect("valid pod list");
+        assert_eq!(
+            table
+                .lookup(None, Some("10.1.0.5"))
+                .map(|p| p.name.as_str()),
+            Some("web-0")
+        );
+        assert_eq!(
+            table
+                .lookup(Some("uid-2"), Some("10.1.0.5"))
+                .map(|p| p.name.as_str()),
+            Some("agent-abc")
+        );
+        assert!(table.lookup(None, Some("192.168.0.10")).is_none());
+        assert!(PodTable::from_json("{}").is_err());
+    }
+}
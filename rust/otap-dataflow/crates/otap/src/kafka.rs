@@ -0,0 +1,145 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Connection settings shared by the Kafka receiver and exporter.
+
+use rdkafka::config::ClientConfig;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// SASL mechanism used to authenticate with the brokers.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SaslMechanism {
+    /// `PLAIN`
+    Plain,
+    /// `SCRAM-SHA-256`
+    ScramSha256,
+    /// `SCRAM-SHA-512`
+    ScramSha512,
+}
+
+impl SaslMechanism {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Plain => "PLAIN",
+            Self::ScramSha256 => "SCRAM-SHA-256",
+            Self::ScramSha512 => "SCRAM-SHA-512",
+        }
+    }
+}
+
+/// SASL authentication settings.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SaslSettings {
+    /// The SASL mechanism.
+    pub mechanism: SaslMechanism,
+    /// The SASL username.
+    pub username: String,
+    /// The SASL password.
+    pub password: String,
+}
+
+/// TLS settings. Paths are PEM files; omitted paths fall back to the librdkafka defaults.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TlsSettings {
+    /// CA certificate used to verify the brokers.
+    pub ca_file: Option<String>,
+    /// Client certificate, for mutual TLS.
+    pub cert_file: Option<String>,
+    /// Client private key, for mutual TLS.
+    pub key_file: Option<String>,
+}
+
+/// Settings used to connect to a Kafka cluster.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectionSettings {
+    /// Comma separated list of bootstrap brokers (`host:port`).
+    pub brokers: String,
+
+    /// Optional SASL authentication.
+    #[serde(default)]
+    pub sasl: Option<SaslSettings>,
+
+    /// Optional TLS encryption.
+    #[serde(default)]
+    pub tls: Option<TlsSettings>,
+
+    /// Additional librdkafka properties, applied last so they can override any setting above.
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+}
+
+impl ConnectionSettings {
+    /// Builds the librdkafka client configuration for these settings.
+    #[must_use]
+    pub fn client_config(&self) -> ClientConfig {
+        let mut config = ClientConfig::new();
+        let _ = config.set("bootstrap.servers", &self.brokers);
+
+        let protocol = match (&self.sasl, &self.tls) {
+            (None, None) => "plaintext",
+            (None, Some(_)) => "ssl",
+            (Some(_), None) => "sasl_plaintext",
+            (Some(_), Some(_)) => "sasl_ssl",
+        };
+        let _ = config.set("security.protocol", protocol);
+
+        if let Some(sasl) = &self.sasl {
+            let _ = config
+                .set("sasl.mechanism", sasl.mechanism.as_str())
+                .set("sasl.username", &sasl.username)
+                .set("sasl.password", &sasl.password);
+        }
+        if let Some(tls) = &self.tls {
+            for (key, value) in [
+                ("ssl.ca.location", &tls.ca_file),
+                ("ssl.certificate.location", &tls.cert_file),
+                ("ssl.key.location", &tls.key_file),
+            ] {
+                if let Some(value) = value {
+                    let _ = config.set(key, value);
+                }
+            }
+        }
+        for (key, value) in &self.properties {
+            let _ = config.set(key, value);
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_client_config() {
+        let settings: ConnectionSettings = serde_json::from_value(json!({
+            "brokers": "localhost:9092",
+            "sasl": { "mechanism": "scram_sha512", "username": "user", "password": "pass" },
+            "tls": { "ca_file": "/etc/ca.pem" },
+            "properties": { "client.id": "df" }
+        }))
+        .unwrap();
+        let config = settings.client_config();
+        assert_eq!(config.get("bootstrap.servers"), Some("localhost:9092"));
+        assert_eq!(config.get("security.protocol"), Some("sasl_ssl"));
+        assert_eq!(config.get("sasl.mechanism"), Some("SCRAM-SHA-512"));
+        assert_eq!(config.get("ssl.ca.location"), Some("/etc/ca.pem"));
+        assert_eq!(config.get("ssl.key.location"), None);
+        assert_eq!(config.get("client.id"), Some("df"));
+
+        let settings: ConnectionSettings =
+            serde_json::from_value(json!({ "brokers": "localhost:9092" })).unwrap();
+        assert_eq!(
+            settings.client_config().get("security.protocol"),
+            Some("plaintext")
+        );
+    }
+}
@@ -0,0 +1,589 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exporter producing OTLP or OTAP payloads to Kafka topics.
+//!
+//! Each pdata becomes one Kafka message on the topic configured for its signal, serialized as an
+//! OTLP `Export*ServiceRequest` or as a self-contained OTAP `BatchArrowRecords` (the format read
+//! by the Kafka receiver). Batching and compression of messages are left to the producer
+//! (`linger`, `compression`). The pdata is Acked once the brokers confirmed the delivery and
+//! Nacked if the delivery failed; payloads that cannot be serialized are Nacked as permanent
+//! failures.
+
+use crate::OTAP_EXPORTER_FACTORIES;
+use crate::kafka::ConnectionSettings;
+use crate::metrics::ExporterPDataMetrics;
+use crate::pdata::attributes::{AttributeIter, AttributeValue};
+use crate::pdata::{Context, OtapPayload, OtapPdata, OtlpProtoBytes};
+use crate::proto::opentelemetry::collector::logs::v1::ExportLogsServiceRequest;
+use crate::proto::opentelemetry::collector::metrics::v1::ExportMetricsServiceRequest;
+use crate::proto::opentelemetry::collector::trace::v1::ExportTraceServiceRequest;
+use crate::proto::opentelemetry::common::v1::{KeyValue, any_value};
+use crate::proto::opentelemetry::resource::v1::Resource;
+use async_trait::async_trait;
+use futures::StreamExt;
+use futures::future::{FutureExt, LocalBoxFuture};
+use futures::stream::FuturesUnordered;
+use linkme::distributed_slice;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ExporterConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, NackClass, NackMsg, NodeControlMsg};
+use otap_df_engine::error::{Error, ExporterErrorKind, format_error_sources};
+use otap_df_engine::exporter::ExporterWrapper;
+use otap_df_engine::local::exporter::{EffectHandler, Exporter};
+use otap_df_engine::message::{Message, MessageChannel};
+use otap_df_engine::node::NodeId;
+use otap_df_engine::terminal_state::TerminalState;
+use otap_df_engine::{ConsumerEffectHandlerExtension, ExporterFactory};
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::Producer;
+use otel_arrow_rust::encode::producer::ProducerOptions;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use prost::Message as _;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The URN for the Kafka exporter
+pub const KAFKA_EXPORTER_URN: &str = "urn:otel:kafka:exporter";
+
+/// Serialization of the produced Kafka messages.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// OTLP `Export*ServiceRequest` protobuf.
+    #[default]
+    Otlp,
+    /// OTAP `BatchArrowRecords` protobuf, each message carrying complete Arrow IPC streams.
+    Otap,
+}
+
+/// Compression of the Arrow IPC buffers in OTAP messages.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArrowCompression {
+    /// No compression.
+    None,
+    /// Zstd compression.
+    #[default]
+    Zstd,
+}
+
+/// Topic to produce to for each signal.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub struct Topics {
+    /// Topic for logs (default: `otlp_logs`)
+    pub logs: String,
+    /// Topic for metrics (default: `otlp_metrics`)
+    pub metrics: String,
+    /// Topic for traces (default: `otlp_spans`)
+    pub traces: String,
+}
+
+impl Default for Topics {
+    fn default() -> Self {
+        Self {
+            logs: "otlp_logs".into(),
+            metrics: "otlp_metrics".into(),
+            traces: "otlp_spans".into(),
+        }
+    }
+}
+
+impl Topics {
+    fn get(&self, signal: SignalType) -> &str {
+        match signal {
+            SignalType::Logs => &self.logs,
+            SignalType::Metrics => &self.metrics,
+            SignalType::Traces => &self.traces,
+        }
+    }
+}
+
+/// Configuration for the Kafka Exporter
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// How to connect to the Kafka cluster.
+    pub connection: ConnectionSettings,
+
+    /// Topic to produce to for each signal.
+    #[serde(default)]
+    pub topics: Topics,
+
+    /// Serialization of the messages.
+    #[serde(default)]
+    pub format: ExportFormat,
+
+    /// Compression of the Arrow IPC buffers, for the `otap` format.
+    #[serde(default)]
+    pub arrow_compression: ArrowCompression,
+
+    /// Resource attribute whose value is used as the message key, so that the data of a
+    /// resource always lands in the same partition. Messages are unkeyed when unset or when no
+    /// resource carries the attribute.
+    #[serde(default)]
+    pub key_attribute: Option<String>,
+
+    /// Kafka message compression codec: `none`, `gzip`, `snappy`, `lz4` or `zstd`
+    /// (default: `none`)
+    #[serde(default = "default_compression")]
+    pub compression: String,
+
+    /// How long the producer waits to batch messages together (default: 5ms)
+    #[serde(default = "default_linger", with = "humantime_serde")]
+    #[schemars(with = "String")]
+    pub linger: Duration,
+
+    /// Maximum number of messages awaiting a delivery report (default: 1000)
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+}
+
+fn default_compression() -> String {
+    "none".into()
+}
+
+const fn default_linger() -> Duration {
+    Duration::from_millis(5)
+}
+
+const fn default_max_in_flight() -> usize {
+    1000
+}
+
+/// Exporter that produces OTLP/OTAP data to Kafka
+pub struct KafkaExporter {
+    config: Config,
+    pdata_metrics: MetricSet<ExporterPDataMetrics>,
+}
+
+/// Declare the Kafka Exporter as a local exporter factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_EXPORTER_FACTORIES)]
+pub static KAFKA_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
+    name: KAFKA_EXPORTER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             exporter_config: &ExporterConfig| {
+        Ok(ExporterWrapper::local(
+            KafkaExporter::from_config(pipeline, &node_config.config)?,
+            node,
+            node_config,
+            exporter_config,
+        ))
+    },
+};
+
+/// Schema of the configuration of the Kafka exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static KAFKA_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(KAFKA_EXPORTER_URN);
+
+/// Outcome of a message delivery, carrying what is needed to notify the subscribers.
+struct Delivery {
+    context: Context,
+    payload: OtapPayload,
+    signal_type: SignalType,
+    result: Result<(), String>,
+}
+
+impl KafkaExporter {
+    /// create a new instance of the `[KafkaExporter]` from json config value
+    pub fn from_config(
+        pipeline_ctx: PipelineContext,
+        config: &serde_json::Value,
+    ) -> Result<Self, otap_df_config::error::Error> {
+        let pdata_metrics = pipeline_ctx.register_metrics::<ExporterPDataMetrics>();
+
+        let config: Config = parse_user_config(config).map_err(|e| {
+            otap_df_config::error::Error::InvalidUserConfig {
+                error: e.to_string(),
+            }
+        })?;
+
+        Ok(Self {
+            config,
+            pdata_metrics,
+        })
+    }
+
+    fn ipc_compression(&self) -> Option<arrow_ipc::CompressionType> {
+        match self.config.arrow_compression {
+            ArrowCompression::None => None,
+            ArrowCompression::Zstd => Some(arrow_ipc::CompressionType::ZSTD),
+        }
+    }
+
+    /// Serializes the pdata and hands it to the producer. Returns the future resolving once the
+    /// brokers acknowledged the message, or the payload back if it could not be serialized.
+    fn produce(
+        &self,
+        producer: &FutureProducer,
+        pdata: OtapPdata,
+    ) -> Result<LocalBoxFuture<'static, Delivery>, (Context, OtapPayload, String)> {
+        let signal_type = pdata.signal_type();
+        let (context, mut payload) = pdata.into_parts();
+        let key = self
+            .config
+            .key_attribute
+            .as_deref()
+            .and_then(|attr| resource_key(&payload, attr));
+
+        let data = context.take_payload_for_export(&mut payload);
+        let value = match encode_payload(data, self.config.format, self.ipc_compression()) {
+            Ok(value) => value,
+            Err(e) => return Err((context, payload, e)),
+        };
+
+        let mut record = FutureRecord::<String, Vec<u8>>::to(self.config.topics.get(signal_type))
+            .payload(&value);
+        if let Some(key) = &key {
+            record = record.key(key);
+        }
+        let delivery = producer.send_result(record).map_err(|(e, _)| e.to_string());
+
+        Ok(async move {
+            let result = match delivery {
+                Ok(delivery) => match delivery.await {
+                    Ok(Ok(_)) => Ok(()),
+                    Ok(Err((e, _))) => Err(e.to_string()),
+                    Err(_) => Err("delivery canceled".to_string()),
+                },
+                Err(e) => Err(e),
+            };
+            Delivery {
+                context,
+                payload,
+                signal_type,
+                result,
+            }
+        }
+        .boxed_local())
+    }
+
+    /// Notifies the subscribers of a completed delivery.
+    async fn complete(
+        &mut self,
+        delivery: Delivery,
+        effect_handler: &EffectHandler<OtapPdata>,
+    ) -> Result<(), Error> {
+        let pdata = OtapPdata::new(delivery.context, delivery.payload);
+        match delivery.result {
+            Ok(()) => {
+                self.pdata_metrics.inc_exported(delivery.signal_type);
+                effect_handler.notify_ack(AckMsg::new(pdata)).await
+            }
+            Err(e) => {
+                self.pdata_metrics.inc_failed(delivery.signal_type);
+                effect_handler
+                    .notify_nack(
+                        NackMsg::new(&format!("kafka delivery failed: {e}"), pdata)
+                            .with_class(NackClass::Unavailable),
+                    )
+                    .await
+            }
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Exporter<OtapPdata> for KafkaExporter {
+    async fn start(
+        mut self: Box<Self>,
+        mut msg_chan: MessageChannel<OtapPdata>,
+        effect_handler: EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        let producer: FutureProducer = self
+            .config
+            .connection
+            .client_config()
+            .set("compression.codec", &self.config.compression)
+            .set("linger.ms", self.config.linger.as_millis().to_string())
+            .create()
+            .map_err(|e| {
+                let source_detail = format_error_sources(&e);
+                Error::ExporterError {
+                    exporter: effect_handler.exporter_id(),
+                    kind: ExporterErrorKind::Configuration,
+                    error: format!("kafka producer error: {e}"),
+                    source_detail,
+                }
+            })?;
+
+        let timer_cancel_handle = effect_handler
+            .start_periodic_telemetry(Duration::from_secs(1))
+            .await?;
+
+        let mut in_flight = FuturesUnordered::new();
+
+        loop {
+            tokio::select! {
+                biased;
+
+                Some(delivery) = in_flight.next(), if !in_flight.is_empty() => {
+                    self.complete(delivery, &effect_handler).await?;
+                }
+
+                // stop consuming while too many messages await their delivery report
+                msg = msg_chan.recv(), if in_flight.len() < self.config.max_in_flight => match msg? {
+                    Message::Control(NodeControlMsg::Shutdown { deadline, .. }) => {
+                        // give the pending deliveries until the deadline to complete
+                        let drain = async {
+                            while let Some(delivery) = in_flight.next().await {
+                                self.complete(delivery, &effect_handler).await?;
+                            }
+                            Ok::<_, Error>(())
+                        };
+                        if let Ok(result) =
+                            tokio::time::timeout_at(deadline.into(), drain).await
+                        {
+                            result?;
+                        }
+                        _ = producer.flush(Timeout::Never);
+                        _ = timer_cancel_handle.cancel().await;
+                        return Ok(TerminalState::new(deadline, [self.pdata_metrics]));
+                    }
+                    Message::Control(NodeControlMsg::CollectTelemetry {
+                        mut metrics_reporter,
+                    }) => {
+                        _ = metrics_reporter.report(&mut self.pdata_metrics);
+                    }
+                    Message::PData(pdata) => {
+                        self.pdata_metrics.inc_consumed(pdata.signal_type());
+                        match self.produce(&producer, pdata) {
+                            Ok(delivery) => in_flight.push(delivery),
+                            Err((context, payload, e)) => {
+                                self.pdata_metrics.inc_failed(payload.signal_type());
+                                let nack = NackMsg::new_permanent(
+                                    &format!("cannot serialize payload for kafka: {e}"),
+                                    OtapPdata::new(context, payload),
+                                )
+                                .with_class(NackClass::InvalidData);
+                                effect_handler.notify_nack(nack).await?;
+                            }
+                        }
+                    }
+                    _ => {
+                        // ignore unhandled messages
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Serializes a payload into a Kafka message value.
+pub(crate) fn encode_payload(
+    payload: OtapPayload,
+    format: ExportFormat,
+    ipc_compression: Option<arrow_ipc::CompressionType>,
+) -> Result<Vec<u8>, String> {
+    match format {
+        ExportFormat::Otlp => {
+            let bytes: OtlpProtoBytes = payload.try_into().map_err(|e| format!("{e}"))?;
+            Ok(match bytes {
+                OtlpProtoBytes::ExportLogsRequest(bytes)
+                | OtlpProtoBytes::ExportMetricsRequest(bytes)
+                | OtlpProtoBytes::ExportTracesRequest(bytes) => bytes,
+            })
+        }
+        ExportFormat::Otap => {
+            let mut records: OtapArrowRecords = payload.try_into().map_err(|e| format!("{e}"))?;
+            // a new producer per message makes every message an independent Arrow IPC stream
+            let batch = Producer::new_with_options(ProducerOptions { ipc_compression })
+                .produce_bar(&mut records)
+                .map_err(|e| format!("{e}"))?;
+            Ok(batch.encode_to_vec())
+        }
+    }
+}
+
+/// Returns the value of the first resource attribute named `attr`.
+fn resource_key(payload: &OtapPayload, attr: &str) -> Option<String> {
+    match payload {
+        OtapPayload::OtapArrowRecords(records) => {
+            let batch = records.get(ArrowPayloadType::ResourceAttrs)?;
+            AttributeIter::try_new(batch)
+                .ok()?
+                .find(|a| a.key == attr)
+                .and_then(|a| match a.value {
+                    AttributeValue::Str(s) => Some(s.to_string()),
+                    AttributeValue::Int(i) => Some(i.to_string()),
+                    _ => None,
+                })
+        }
+        OtapPayload::OtlpBytes(bytes) => {
+            let resources: Vec<Option<Resource>> = match bytes {
+                OtlpProtoBytes::ExportLogsRequest(b) => ExportLogsServiceRequest::decode(&b[..])
+                    .ok()?
+                    .resource_logs
+                    .into_iter()
+                    .map(|r| r.resource)
+                    .collect(),
+                OtlpProtoBytes::ExportMetricsRequest(b) => {
+                    ExportMetricsServiceRequest::decode(&b[..])
+                        .ok()?
+                        .resource_metrics
+                        .into_iter()
+                        .map(|r| r.resource)
+                        .collect()
+                }
+                OtlpProtoBytes::ExportTracesRequest(b) => ExportTraceServiceRequest::decode(&b[..])
+                    .ok()?
+                    .resource_spans
+                    .into_iter()
+                    .map(|r| r.resource)
+                    .collect(),
+            };
+            resources
+                .iter()
+                .flatten()
+                .flat_map(|r| &r.attributes)
+                .find(|kv: &&KeyValue| kv.key == attr)
+                .and_then(|kv| match kv.value.as_ref()?.value.as_ref()? {
+                    any_value::Value::StringValue(s) => Some(s.clone()),
+                    any_value::Value::IntValue(i) => Some(i.to_string()),
+                    _ => None,
+                })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::opentelemetry::common::v1::AnyValue;
+    use crate::proto::opentelemetry::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::proto::opentelemetry::arrow::v1::BatchArrowRecords;
+    use serde_json::json;
+
+    fn logs_payload() -> OtlpProtoBytes {
+        let request = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue {
+                        key: "service.name".into(),
+                        value: Some(AnyValue {
+                            value: Some(any_value::Value::StringValue("checkout".into())),
+                        }),
+                    }],
+                    ..Default::default()
+                }),
+                scope_logs: vec![ScopeLogs {
+                    log_records: vec![LogRecord {
+                        time_unix_nano: 1,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        OtlpProtoBytes::ExportLogsRequest(request.encode_to_vec())
+    }
+
+    #[test]
+    fn test_config() {
+        let controller_ctx = ControllerContext::new(MetricsRegistryHandle::new());
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+
+        let exporter = KafkaExporter::from_config(
+            pipeline_ctx.clone(),
+            &json!({ "connection": { "brokers": "localhost:9092" } }),
+        )
+        .unwrap();
+        assert_eq!(exporter.config.format, ExportFormat::Otlp);
+        assert_eq!(exporter.config.topics.get(SignalType::Traces), "otlp_spans");
+        assert_eq!(exporter.config.linger, Duration::from_millis(5));
+
+        let exporter = KafkaExporter::from_config(
+            pipeline_ctx,
+            &json!({
+                "connection": { "brokers": "localhost:9092" },
+                "topics": { "logs": "logs" },
+                "format": "otap",
+                "arrow_compression": "none",
+                "key_attribute": "service.name",
+                "linger": "20ms"
+            }),
+        )
+        .unwrap();
+        assert_eq!(exporter.config.topics.get(SignalType::Logs), "logs");
+        assert_eq!(
+            exporter.config.topics.get(SignalType::Metrics),
+            "otlp_metrics"
+        );
+        assert_eq!(exporter.ipc_compression(), None);
+    }
+
+    #[test]
+    fn test_encode_payload() {
+        let otlp = logs_payload();
+        let value = encode_payload(otlp.clone().into(), ExportFormat::Otlp, None).unwrap();
+        assert_eq!(value, otlp.as_bytes());
+
+        let value = encode_payload(
+            otlp.into(),
+            ExportFormat::Otap,
+            Some(arrow_ipc::CompressionType::ZSTD),
+        )
+        .unwrap();
+        let batch = BatchArrowRecords::decode(value.as_slice()).unwrap();
+        assert!(
+            batch
+                .arrow_payloads
+                .iter()
+                .any(|p| p.r#type == ArrowPayloadType::Logs as i32)
+        );
+    }
+
+    #[test]
+    fn test_resource_key() {
+        let otlp = logs_payload();
+        let payload: OtapPayload = otlp.clone().into();
+        assert_eq!(
+            resource_key(&payload, "service.name").as_deref(),
+            Some("checkout")
+        );
+        assert_eq!(resource_key(&payload, "host.name"), None);
+
+        let records: OtapArrowRecords = otlp.try_into().unwrap();
+        let payload: OtapPayload = records.into();
+        assert_eq!(
+            resource_key(&payload, "service.name").as_deref(),
+            Some("checkout")
+        );
+    }
+
+    #[test]
+    fn test_kafka_exporter_conformance() {
+        // nothing listens on the broker port and the deliveries time out quickly, so every
+        // export is expected to be Nack'ed before the shutdown deadline
+        let port = portpicker::pick_unused_port().expect("No free ports");
+        crate::testing::conformance::run_exporter_conformance(
+            &KAFKA_EXPORTER,
+            json!({
+                "connection": {
+                    "brokers": format!("127.0.0.1:{port}"),
+                    "properties": { "message.timeout.ms": "100" }
+                }
+            }),
+            Some(otap_df_engine::Interests::NACKS),
+        );
+    }
+}
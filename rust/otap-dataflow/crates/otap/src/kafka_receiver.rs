@@ -0,0 +1,1015 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Receiver consuming OTLP or OTAP payloads from Kafka topics.
+//!
+//! Each Kafka message holds one serialized request: an OTLP `Export*ServiceRequest` for the
+//! signal configured with `format`, or a self-contained OTAP `BatchArrowRecords`. The receiver
+//! subscribes to the Ack/Nack of every message it sends down the pipeline and only commits a
+//! partition offset once all the messages before it have been acknowledged, so that messages in
+//! flight when the receiver stops (or whose export failed) are consumed again on restart.
+//!
+//! The receiver never waits on a full pipeline channel: when the downstream channel is full, the
+//! assigned partitions are paused and the pending messages are retried periodically, the
+//! partitions being resumed once they all went through. Control messages (Acks, Nacks, Shutdown)
+//! are therefore still handled while the pipeline applies backpressure.
+//!
+//! A message Nacked with a retryable failure is sent down the pipeline again after a backoff
+//! doubling with each attempt, up to `max_redeliveries` times, its offset staying in flight
+//! meanwhile. A message refused for good, i.e. whose Nack is not retryable or which exhausted its
+//! redeliveries, is produced to the `dead_letter_topic` when one is configured, with the reason,
+//! class and refusing node of the Nack in its headers, and skipped otherwise. Dead lettering does
+//! not hold up the control loop: the offset of the message is committed once the delivery to the
+//! dead letter topic completes, and a message that could not be dead lettered is counted in
+//! `dead_letter_failures` and skipped all the same.
+//!
+//! The offsets of the partitions revoked by a rebalance are forgotten: the Acks and pending
+//! redeliveries of their messages are ignored, the new owner of a partition consuming it again
+//! from its last committed offset.
+
+use crate::OTAP_RECEIVER_FACTORIES;
+use crate::kafka::ConnectionSettings;
+use crate::kafka_exporter::{ExportFormat, encode_payload};
+use crate::pdata::{OtapPayload, OtapPdata, OtlpProtoBytes};
+use async_trait::async_trait;
+use futures::StreamExt;
+use futures::future::{BoxFuture, FutureExt};
+use futures::stream::FuturesUnordered;
+use linkme::distributed_slice;
+use otap_df_channel::error::SendError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ReceiverConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{CallData, Context8u8, NackMsg, NodeControlMsg};
+use otap_df_engine::error::TypedError;
+use otap_df_engine::error::{Error, ReceiverErrorKind, format_error_sources};
+use otap_df_engine::node::NodeId;
+use otap_df_engine::receiver::ReceiverWrapper;
+use otap_df_engine::shared::receiver as shared;
+use otap_df_engine::terminal_state::TerminalState;
+use otap_df_engine::{Interests, ProducerEffectHandlerExtension, ReceiverFactory};
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry::metrics::MetricSet;
+use otap_df_telemetry_macros::metric_set;
+use otel_arrow_rust::otap::{OtapArrowRecords, from_record_messages};
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::{ArrowPayloadType, BatchArrowRecords};
+use prost::Message as _;
+use rdkafka::client::ClientContext;
+use rdkafka::consumer::{
+    BaseConsumer, CommitMode, Consumer as _, ConsumerContext, Rebalance, StreamConsumer,
+};
+use rdkafka::error::KafkaError;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{DeliveryFuture, FutureProducer, FutureRecord};
+use rdkafka::{Message as _, Offset, TopicPartitionList};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use smallvec::smallvec;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// URN for the Kafka Receiver
+pub const KAFKA_RECEIVER_URN: &str = "urn:otel:kafka:receiver";
+
+/// Delay between two attempts to send the messages held back while the pipeline is full.
+const BACKPRESSURE_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Maximum time waited for the brokers to acknowledge a message produced to the dead letter
+/// topic.
+const DEAD_LETTER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Delay before the first redelivery of a message Nacked with a retryable failure, doubled with
+/// each attempt.
+const REDELIVERY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Encoding of the consumed Kafka messages.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageFormat {
+    /// Serialized OTLP `ExportLogsServiceRequest`.
+    OtlpLogs,
+    /// Serialized OTLP `ExportMetricsServiceRequest`.
+    OtlpMetrics,
+    /// Serialized OTLP `ExportTraceServiceRequest`.
+    OtlpTraces,
+    /// Serialized OTAP `BatchArrowRecords` carrying complete Arrow IPC streams.
+    Otap,
+}
+
+/// Configuration for Kafka Receiver
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// How to connect to the Kafka cluster.
+    connection: ConnectionSettings,
+
+    /// Topics to subscribe to.
+    topics: Vec<String>,
+
+    /// Consumer group id.
+    group_id: String,
+
+    /// Encoding of the messages in the topics.
+    format: MessageFormat,
+
+    /// Topic receiving the messages refused downstream for good, in the same encoding. Without
+    /// it, these messages are skipped.
+    #[serde(default)]
+    dead_letter_topic: Option<String>,
+
+    /// Number of times a message Nacked with a retryable failure is sent down the pipeline again
+    /// before it is refused for good.
+    #[serde(default = "default_max_redeliveries")]
+    max_redeliveries: u32,
+}
+
+const fn default_max_redeliveries() -> u32 {
+    5
+}
+
+/// Receiver implementation that consumes OTLP/OTAP messages from Kafka.
+pub struct KafkaReceiver {
+    config: Config,
+    metrics: MetricSet<KafkaReceiverMetrics>,
+}
+
+/// Kafka receiver metrics.
+#[metric_set(name = "kafka.receiver.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct KafkaReceiverMetrics {
+    /// Number of messages consumed and sent down the pipeline.
+    #[metric(unit = "{msg}")]
+    pub messages_received: Counter<u64>,
+
+    /// Number of messages skipped because they could not be decoded.
+    #[metric(unit = "{msg}")]
+    pub messages_invalid: Counter<u64>,
+
+    /// Number of acks received.
+    #[metric(unit = "{acks}")]
+    pub acks_received: Counter<u64>,
+
+    /// Number of nacks received.
+    #[metric(unit = "{nacks}")]
+    pub nacks_received: Counter<u64>,
+
+    /// Number of offset commits that failed.
+    #[metric(unit = "{commit}")]
+    pub commits_failed: Counter<u64>,
+
+    /// Number of times consumption was paused because the pipeline was full.
+    #[metric(unit = "{pause}")]
+    pub backpressure_pauses: Counter<u64>,
+
+    /// Number of refused messages produced to the dead letter topic.
+    #[metric(unit = "{msg}")]
+    pub messages_dead_lettered: Counter<u64>,
+
+    /// Number of refused messages that could not be produced to the dead letter topic.
+    #[metric(unit = "{msg}")]
+    pub dead_letter_failures: Counter<u64>,
+
+    /// Number of messages sent down the pipeline again after a retryable Nack.
+    #[metric(unit = "{msg}")]
+    pub messages_redelivered: Counter<u64>,
+
+    /// Number of messages refused for good after exhausting their redeliveries.
+    #[metric(unit = "{msg}")]
+    pub redeliveries_exhausted: Counter<u64>,
+}
+
+/// Consumer context recording the partitions revoked by a rebalance, for the receiver to forget
+/// their offsets.
+#[derive(Default)]
+struct RebalanceContext {
+    revoked: Mutex<Vec<(String, i32)>>,
+}
+
+impl ClientContext for RebalanceContext {}
+
+impl ConsumerContext for RebalanceContext {
+    fn pre_rebalance(&self, _consumer: &BaseConsumer<Self>, rebalance: &Rebalance<'_>) {
+        if let Rebalance::Revoke(partitions) = rebalance {
+            if let Ok(mut revoked) = self.revoked.lock() {
+                revoked.extend(
+                    partitions
+                        .elements()
+                        .iter()
+                        .map(|elem| (elem.topic().to_owned(), elem.partition())),
+                );
+            }
+        }
+    }
+}
+
+type KafkaConsumer = StreamConsumer<RebalanceContext>;
+
+/// Declares the Kafka receiver as a shared receiver factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_RECEIVER_FACTORIES)]
+pub static KAFKA_RECEIVER: ReceiverFactory<OtapPdata> = ReceiverFactory {
+    name: KAFKA_RECEIVER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             receiver_config: &ReceiverConfig| {
+        Ok(ReceiverWrapper::shared(
+            KafkaReceiver::from_config(pipeline, &node_config.config)?,
+            node,
+            node_config,
+            receiver_config,
+        ))
+    },
+};
+
+/// Schema of the configuration of the Kafka receiver
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static KAFKA_RECEIVER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(KAFKA_RECEIVER_URN);
+
+impl KafkaReceiver {
+    /// Creates a new KafkaReceiver from a configuration object
+    pub fn from_config(
+        pipeline_ctx: PipelineContext,
+        config: &Value,
+    ) -> Result<Self, otap_df_config::error::Error> {
+        let config: Config = parse_user_config(config).map_err(|e| {
+            otap_df_config::error::Error::InvalidUserConfig {
+                error: e.to_string(),
+            }
+        })?;
+        if config.topics.is_empty() {
+            return Err(otap_df_config::error::Error::InvalidUserConfig {
+                error: "at least one topic is required".into(),
+            });
+        }
+        let metrics = pipeline_ctx.register_metrics::<KafkaReceiverMetrics>();
+        Ok(Self { config, metrics })
+    }
+
+    fn create_consumer(&self) -> Result<KafkaConsumer, KafkaError> {
+        let consumer: KafkaConsumer = self
+            .config
+            .connection
+            .client_config()
+            .set("group.id", &self.config.group_id)
+            // offsets are committed explicitly once the pipeline acknowledged the data
+            .set("enable.auto.commit", "false")
+            .create_with_context(RebalanceContext::default())?;
+        let topics: Vec<&str> = self.config.topics.iter().map(String::as_str).collect();
+        consumer.subscribe(&topics)?;
+        Ok(consumer)
+    }
+
+    fn create_dead_letter(&self) -> Result<Option<DeadLetter>, KafkaError> {
+        let Some(topic) = &self.config.dead_letter_topic else {
+            return Ok(None);
+        };
+        Ok(Some(DeadLetter {
+            producer: self
+                .config
+                .connection
+                .client_config()
+                .set(
+                    "message.timeout.ms",
+                    DEAD_LETTER_TIMEOUT.as_millis().to_string(),
+                )
+                .create()?,
+            topic: topic.clone(),
+            format: match self.config.format {
+                MessageFormat::Otap => ExportFormat::Otap,
+                _ => ExportFormat::Otlp,
+            },
+        }))
+    }
+
+    /// Commits `offset` for the partition; failures are only counted since a later commit
+    /// supersedes them.
+    fn commit(&mut self, consumer: &KafkaConsumer, topic: &str, partition: i32, offset: i64) {
+        let mut tpl = TopicPartitionList::new();
+        let result = tpl
+            .add_partition_offset(topic, partition, Offset::Offset(offset))
+            .and_then(|_| consumer.commit(&tpl, CommitMode::Async));
+        if let Err(e) = result {
+            log::warn!("Failed to commit offset {offset} of {topic}/{partition}: {e}");
+            self.metrics.commits_failed.inc();
+        }
+    }
+
+    /// Handles the Ack or Nack of a message, committing the partition offset if it advanced.
+    fn handle_done(
+        &mut self,
+        consumer: &KafkaConsumer,
+        tracker: &mut OffsetTracker,
+        calldata: CallData,
+    ) {
+        let Some((topic, partition, offset)) = decode_calldata(&calldata) else {
+            return;
+        };
+        if let Some(commit) = tracker.done(topic, partition, offset) {
+            if let Some(topic) = self.config.topics.get(topic).cloned() {
+                self.commit(consumer, &topic, partition, commit);
+            }
+        }
+    }
+
+    /// Forgets the offsets of the partitions revoked since the last call.
+    fn forget_revoked(&self, consumer: &KafkaConsumer, tracker: &mut OffsetTracker) {
+        let Ok(mut revoked) = consumer.context().revoked.lock() else {
+            return;
+        };
+        for (topic, partition) in revoked.drain(..) {
+            if let Some(topic) = self.config.topics.iter().position(|t| *t == topic) {
+                tracker.revoke(topic, partition);
+            }
+        }
+    }
+
+    /// Sends a message down the pipeline, holding it back and pausing the consumption if the
+    /// pipeline is full.
+    fn send_or_block(
+        &mut self,
+        consumer: &KafkaConsumer,
+        effect_handler: &shared::EffectHandler<OtapPdata>,
+        blocked: &mut VecDeque<OtapPdata>,
+        pdata: OtapPdata,
+    ) -> Result<(), Error> {
+        if !blocked.is_empty() {
+            // Messages fetched before the pause took effect keep their order
+            blocked.push_back(pdata);
+        } else if let Some(pdata) = try_send(effect_handler, pdata)? {
+            blocked.push_back(pdata);
+            self.metrics.backpressure_pauses.inc();
+            if let Err(e) = consumer
+                .assignment()
+                .and_then(|assignment| consumer.pause(&assignment))
+            {
+                log::warn!("Failed to pause Kafka consumption: {e}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What to do with a message Nacked downstream.
+#[derive(Debug, PartialEq)]
+enum NackAction {
+    /// Keep the offset in flight and send the message down the pipeline again, up to
+    /// `max_redeliveries` times.
+    Redeliver,
+    /// Move past the message, producing it to the dead letter topic if one is configured.
+    DeadLetter,
+}
+
+fn nack_action(nack: &NackMsg<OtapPdata>) -> NackAction {
+    if nack.is_retryable() {
+        NackAction::Redeliver
+    } else {
+        NackAction::DeadLetter
+    }
+}
+
+/// A message waiting for its redelivery.
+type Redelivery = BoxFuture<'static, OtapPdata>;
+
+/// Returns the message to send down the pipeline again once the backoff of the redelivery
+/// `attempt` (starting at 1) elapsed.
+fn redeliver_after(attempt: u32, pdata: OtapPdata) -> Redelivery {
+    let backoff = REDELIVERY_BACKOFF.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    async move {
+        tokio::time::sleep(backoff).await;
+        pdata
+    }
+    .boxed()
+}
+
+/// Producer of the refused messages to the dead letter topic.
+struct DeadLetter {
+    producer: FutureProducer,
+    topic: String,
+    format: ExportFormat,
+}
+
+/// Delivery of a refused message to the dead letter topic, resolving to the calldata of the
+/// message and the outcome of the delivery.
+type DeadLetterDelivery = BoxFuture<'static, (CallData, Result<(), String>)>;
+
+impl DeadLetter {
+    /// Starts producing the message refused by `nack`, consumed from `source` (topic, partition,
+    /// offset), to the dead letter topic, without waiting for the brokers.
+    fn produce(
+        &self,
+        nack: &NackMsg<OtapPdata>,
+        source: Option<(&str, i32, i64)>,
+    ) -> DeadLetterDelivery {
+        let calldata = nack.calldata.clone();
+        let delivery = self.send(nack, source);
+        async move {
+            let result = match delivery {
+                Ok(delivery) => match delivery.await {
+                    Ok(Ok(_)) => Ok(()),
+                    Ok(Err((e, _))) => Err(e.to_string()),
+                    Err(_) => Err("delivery canceled".to_string()),
+                },
+                Err(e) => Err(e),
+            };
+            (calldata, result)
+        }
+        .boxed()
+    }
+
+    fn send(
+        &self,
+        nack: &NackMsg<OtapPdata>,
+        source: Option<(&str, i32, i64)>,
+    ) -> Result<DeliveryFuture, String> {
+        if nack.refused.is_empty() {
+            return Err("the Nack does not return the message".into());
+        }
+        let (_, payload) = nack.refused.as_ref().clone().into_parts();
+        let value = encode_payload(payload, self.format, None)?;
+        let record = FutureRecord::<String, Vec<u8>>::to(&self.topic)
+            .payload(&value)
+            .headers(dead_letter_headers(nack, source));
+        self.producer
+            .send_result(record)
+            .map_err(|(e, _)| e.to_string())
+    }
+}
+
+/// Headers describing the failure and the origin of a dead letter message.
+fn dead_letter_headers(
+    nack: &NackMsg<OtapPdata>,
+    source: Option<(&str, i32, i64)>,
+) -> OwnedHeaders {
+    let node = nack
+        .node
+        .as_ref()
+        .map(|node| node.name.to_string())
+        .unwrap_or_default();
+    let mut headers = OwnedHeaders::new()
+        .insert(Header {
+            key: "nack.reason",
+            value: Some(nack.reason.as_str()),
+        })
+        .insert(Header {
+            key: "nack.class",
+            value: Some(nack.class.as_str()),
+        })
+        .insert(Header {
+            key: "nack.node",
+            value: Some(node.as_str()),
+        });
+    if let Some((topic, partition, offset)) = source {
+        headers = headers
+            .insert(Header {
+                key: "source.topic",
+                value: Some(topic),
+            })
+            .insert(Header {
+                key: "source.partition",
+                value: Some(&partition.to_string()),
+            })
+            .insert(Header {
+                key: "source.offset",
+                value: Some(&offset.to_string()),
+            });
+    }
+    headers
+}
+
+/// Tracks the offsets in flight per partition to compute the offset that is safe to commit.
+#[derive(Debug, Default)]
+struct OffsetTracker {
+    partitions: HashMap<(usize, i32), PartitionOffsets>,
+}
+
+#[derive(Debug, Default)]
+struct PartitionOffsets {
+    /// Offsets sent down the pipeline and not yet acknowledged.
+    in_flight: BTreeSet<i64>,
+    /// Offset following the last message consumed.
+    next: i64,
+    /// Last offset committed.
+    committed: Option<i64>,
+    /// Number of redeliveries of the offsets in flight that were Nacked.
+    redeliveries: HashMap<i64, u32>,
+}
+
+impl OffsetTracker {
+    /// Records a message consumed from the partition.
+    fn consumed(&mut self, topic: usize, partition: i32, offset: i64) {
+        let offsets = self.partitions.entry((topic, partition)).or_default();
+        let _ = offsets.in_flight.insert(offset);
+        offsets.next = offsets.next.max(offset + 1);
+    }
+
+    /// Records a message as done and returns the new offset to commit, if it advanced.
+    ///
+    /// The committed offset is the next offset to consume, i.e. the oldest message still in
+    /// flight, or the one after the last consumed message when nothing is in flight.
+    fn done(&mut self, topic: usize, partition: i32, offset: i64) -> Option<i64> {
+        let offsets = self.partitions.get_mut(&(topic, partition))?;
+        let _ = offsets.in_flight.remove(&offset);
+        let _ = offsets.redeliveries.remove(&offset);
+        let commit = offsets.in_flight.first().copied().unwrap_or(offsets.next);
+        if offsets
+            .committed
+            .is_some_and(|committed| committed >= commit)
+        {
+            return None;
+        }
+        offsets.committed = Some(commit);
+        Some(commit)
+    }
+
+    /// Records a redelivery of a message in flight and returns its attempt number, starting at
+    /// 1, or `None` if the message was already redelivered `max` times or is not in flight.
+    fn redeliver(&mut self, topic: usize, partition: i32, offset: i64, max: u32) -> Option<u32> {
+        let offsets = self.partitions.get_mut(&(topic, partition))?;
+        if !offsets.in_flight.contains(&offset) {
+            return None;
+        }
+        let attempts = offsets.redeliveries.entry(offset).or_default();
+        if *attempts >= max {
+            return None;
+        }
+        *attempts += 1;
+        Some(*attempts)
+    }
+
+    /// Returns true if the message is in flight.
+    fn is_in_flight(&self, topic: usize, partition: i32, offset: i64) -> bool {
+        self.partitions
+            .get(&(topic, partition))
+            .is_some_and(|offsets| offsets.in_flight.contains(&offset))
+    }
+
+    /// Forgets the offsets of a partition revoked by a rebalance.
+    fn revoke(&mut self, topic: usize, partition: i32) {
+        let _ = self.partitions.remove(&(topic, partition));
+    }
+}
+
+/// The calldata of a message holds its topic index, partition and offset.
+fn encode_calldata(topic: usize, partition: i32, offset: i64) -> CallData {
+    smallvec![
+        Context8u8::from(topic as u64),
+        Context8u8::from(i64::from(partition)),
+        Context8u8::from(offset)
+    ]
+}
+
+fn decode_calldata(calldata: &CallData) -> Option<(usize, i32, i64)> {
+    let [topic, partition, offset] = calldata.as_slice() else {
+        return None;
+    };
+    Some((
+        usize::try_from(u64::from(*topic)).ok()?,
+        i32::try_from(u64::from(*partition) as i64).ok()?,
+        u64::from(*offset) as i64,
+    ))
+}
+
+/// Decodes a Kafka message payload according to the configured format.
+fn decode_payload(format: MessageFormat, payload: &[u8]) -> Result<OtapPayload, String> {
+    let bytes = match format {
+        MessageFormat::OtlpLogs => OtlpProtoBytes::ExportLogsRequest(payload.to_vec()),
+        MessageFormat::OtlpMetrics => OtlpProtoBytes::ExportMetricsRequest(payload.to_vec()),
+        MessageFormat::OtlpTraces => OtlpProtoBytes::ExportTracesRequest(payload.to_vec()),
+        MessageFormat::Otap => {
+            let mut batch = BatchArrowRecords::decode(payload).map_err(|e| e.to_string())?;
+            let signal = batch
+                .arrow_payloads
+                .iter()
+                .find_map(|p| match ArrowPayloadType::try_from(p.r#type) {
+                    Ok(ArrowPayloadType::Logs) => Some(SignalType::Logs),
+                    Ok(ArrowPayloadType::Spans) => Some(SignalType::Traces),
+                    Ok(
+                        ArrowPayloadType::UnivariateMetrics | ArrowPayloadType::MultivariateMetrics,
+                    ) => Some(SignalType::Metrics),
+                    _ => None,
+                })
+                .ok_or("OTAP batch has no root payload")?;
+            // every message is an independent Arrow IPC stream
+            let records = otel_arrow_rust::Consumer::default()
+                .consume_bar(&mut batch)
+                .map_err(|e| e.to_string())?;
+            let records = match signal {
+                SignalType::Logs => OtapArrowRecords::Logs(from_record_messages(records)),
+                SignalType::Metrics => OtapArrowRecords::Metrics(from_record_messages(records)),
+                SignalType::Traces => OtapArrowRecords::Traces(from_record_messages(records)),
+            };
+            return Ok(records.into());
+        }
+    };
+    Ok(bytes.into())
+}
+
+#[async_trait]
+impl shared::Receiver<OtapPdata> for KafkaReceiver {
+    async fn start(
+        mut self: Box<Self>,
+        mut ctrl_msg_recv: shared::ControlChannel<OtapPdata>,
+        effect_handler: shared::EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        let consumer = self.create_consumer().map_err(|error| {
+            let source_detail = format_error_sources(&error);
+            Error::ReceiverError {
+                receiver: effect_handler.receiver_id(),
+                kind: ReceiverErrorKind::Connect,
+                error: error.to_string(),
+                source_detail,
+            }
+        })?;
+        let dead_letter = self.create_dead_letter().map_err(|error| {
+            let source_detail = format_error_sources(&error);
+            Error::ReceiverError {
+                receiver: effect_handler.receiver_id(),
+                kind: ReceiverErrorKind::Connect,
+                error: error.to_string(),
+                source_detail,
+            }
+        })?;
+        // the refused messages are returned to be redelivered or dead lettered
+        let interests = Interests::ACKS | Interests::NACKS | Interests::RETURN_DATA;
+        let mut tracker = OffsetTracker::default();
+        // Messages consumed while the pipeline was full, sent in order once it drains.
+        let mut blocked: VecDeque<OtapPdata> = VecDeque::new();
+        // Refused messages being produced to the dead letter topic.
+        let mut dead_lettering: FuturesUnordered<DeadLetterDelivery> = FuturesUnordered::new();
+        // Messages Nacked with a retryable failure, waiting for their redelivery.
+        let mut redeliveries: FuturesUnordered<Redelivery> = FuturesUnordered::new();
+
+        let telemetry_cancel_handle = effect_handler
+            .start_periodic_telemetry(Duration::from_secs(1))
+            .await?;
+
+        loop {
+            self.forget_revoked(&consumer, &mut tracker);
+            tokio::select! {
+                biased;
+
+                ctrl_msg = ctrl_msg_recv.recv() => match ctrl_msg {
+                    Ok(NodeControlMsg::Shutdown { deadline, .. }) => {
+                        let snapshot = self.metrics.snapshot();
+                        _ = telemetry_cancel_handle.cancel().await;
+                        return Ok(TerminalState::new(deadline, [snapshot]));
+                    }
+                    Ok(NodeControlMsg::CollectTelemetry { mut metrics_reporter }) => {
+                        _ = metrics_reporter.report(&mut self.metrics);
+                    }
+                    Ok(NodeControlMsg::Ack(ack)) => {
+                        self.metrics.acks_received.inc();
+                        self.handle_done(&consumer, &mut tracker, ack.calldata);
+                    }
+                    Ok(NodeControlMsg::Nack(nack)) => {
+                        self.metrics.nacks_received.inc();
+                        let Some((topic, partition, offset)) = decode_calldata(&nack.calldata)
+                            .filter(|&(topic, partition, offset)| {
+                                tracker.is_in_flight(topic, partition, offset)
+                            })
+                        else {
+                            // the partition was revoked, its new owner consumes the message again
+                            continue;
+                        };
+                        // A message refused for good will not succeed on redelivery, so move past
+                        // it. Otherwise its offset stays in flight and the message is sent again,
+                        // until it exhausted its redeliveries.
+                        if nack_action(&nack) == NackAction::Redeliver {
+                            let attempt = if nack.refused.is_empty() {
+                                None
+                            } else {
+                                let max = self.config.max_redeliveries;
+                                tracker.redeliver(topic, partition, offset, max)
+                            };
+                            if let Some(attempt) = attempt {
+                                log::warn!(
+                                    "Kafka message refused downstream, redelivery {attempt}: {}",
+                                    nack.reason
+                                );
+                                self.metrics.messages_redelivered.inc();
+                                let (_, payload) = nack.refused.into_parts();
+                                let mut pdata = OtapPdata::new_todo_context(payload);
+                                effect_handler.subscribe_to(interests, nack.calldata, &mut pdata);
+                                redeliveries.push(redeliver_after(attempt, pdata));
+                                continue;
+                            }
+                            self.metrics.redeliveries_exhausted.inc();
+                        }
+                        if let Some(dead_letter) = &dead_letter {
+                            let source = self
+                                .config
+                                .topics
+                                .get(topic)
+                                .map(|topic| (topic.as_str(), partition, offset));
+                            // the offset is committed once the delivery completes
+                            dead_lettering.push(dead_letter.produce(&nack, source));
+                            continue;
+                        }
+                        self.handle_done(&consumer, &mut tracker, nack.calldata);
+                    }
+                    Err(e) => return Err(Error::ChannelRecvError(e)),
+                    _ => {
+                        // unknown control message do nothing
+                    }
+                },
+
+                Some((calldata, result)) = dead_lettering.next(), if !dead_lettering.is_empty() => {
+                    match result {
+                        Ok(()) => self.metrics.messages_dead_lettered.inc(),
+                        Err(e) => {
+                            // Redelivering the message would only get it refused again
+                            log::warn!("Failed to dead letter refused Kafka message: {e}");
+                            self.metrics.dead_letter_failures.inc();
+                        }
+                    }
+                    self.handle_done(&consumer, &mut tracker, calldata);
+                }
+
+                Some(pdata) = redeliveries.next(), if !redeliveries.is_empty() => {
+                    // the partition may have been revoked during the backoff
+                    let in_flight = pdata
+                        .current_calldata()
+                        .and_then(|calldata| decode_calldata(&calldata))
+                        .is_some_and(|(topic, partition, offset)| {
+                            tracker.is_in_flight(topic, partition, offset)
+                        });
+                    if in_flight {
+                        self.send_or_block(&consumer, &effect_handler, &mut blocked, pdata)?;
+                    }
+                }
+
+                _ = tokio::time::sleep(BACKPRESSURE_RETRY_INTERVAL), if !blocked.is_empty() => {
+                    while let Some(pdata) = blocked.pop_front() {
+                        if let Some(pdata) = try_send(&effect_handler, pdata)? {
+                            blocked.push_front(pdata);
+                            break;
+                        }
+                    }
+                    if blocked.is_empty() {
+                        if let Err(e) = consumer
+                            .assignment()
+                            .and_then(|assignment| consumer.resume(&assignment))
+                        {
+                            log::warn!("Failed to resume Kafka consumption: {e}");
+                        }
+                    }
+                }
+
+                message = consumer.recv() => {
+                    // Extract everything needed from the borrowed message, which must not be
+                    // held across an await point.
+                    let received = match message {
+                        Ok(message) => self
+                            .config
+                            .topics
+                            .iter()
+                            .position(|t| t == message.topic())
+                            .map(|topic| {
+                                let payload = message.payload().unwrap_or_default();
+                                (
+                                    topic,
+                                    message.partition(),
+                                    message.offset(),
+                                    decode_payload(self.config.format, payload),
+                                )
+                            }),
+                        Err(error) => {
+                            let source_detail = format_error_sources(&error);
+                            return Err(Error::ReceiverError {
+                                receiver: effect_handler.receiver_id(),
+                                kind: ReceiverErrorKind::Transport,
+                                error: error.to_string(),
+                                source_detail,
+                            });
+                        }
+                    };
+                    let Some((topic, partition, offset, decoded)) = received else {
+                        continue;
+                    };
+                    tracker.consumed(topic, partition, offset);
+
+                    match decoded {
+                        Ok(payload) => {
+                            let mut pdata = OtapPdata::new_todo_context(payload);
+                            effect_handler.subscribe_to(
+                                interests,
+                                encode_calldata(topic, partition, offset),
+                                &mut pdata,
+                            );
+                            self.metrics.messages_received.inc();
+                            self.send_or_block(&consumer, &effect_handler, &mut blocked, pdata)?;
+                        }
+                        Err(e) => {
+                            log::warn!(
+                                "Skipping invalid Kafka message at {}/{partition}/{offset}: {e}",
+                                self.config.topics[topic]
+                            );
+                            self.metrics.messages_invalid.inc();
+                            self.handle_done(&consumer, &mut tracker, encode_calldata(topic, partition, offset));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Send a message down the pipeline without waiting, handing it back if the channel is full.
+fn try_send(
+    effect_handler: &shared::EffectHandler<OtapPdata>,
+    pdata: OtapPdata,
+) -> Result<Option<OtapPdata>, Error> {
+    match effect_handler.try_send_message(pdata) {
+        Ok(()) => Ok(None),
+        Err(TypedError::ChannelSendError(SendError::Full(pdata))) => Ok(Some(pdata)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{SimpleDataGenOptions, create_simple_logs_arrow_record_batches};
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::control::NackClass;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use serde_json::json;
+
+    #[test]
+    fn test_config() {
+        let controller_ctx = ControllerContext::new(MetricsRegistryHandle::new());
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+
+        let receiver = KafkaReceiver::from_config(
+            pipeline_ctx.clone(),
+            &json!({
+                "connection": { "brokers": "localhost:9092" },
+                "topics": ["otlp_logs"],
+                "group_id": "df",
+                "format": "otlp_logs",
+                "dead_letter_topic": "otlp_logs_dlq"
+            }),
+        )
+        .unwrap();
+        assert_eq!(receiver.config.format, MessageFormat::OtlpLogs);
+        assert_eq!(
+            receiver.config.dead_letter_topic.as_deref(),
+            Some("otlp_logs_dlq")
+        );
+        assert_eq!(receiver.config.max_redeliveries, 5);
+
+        assert!(
+            KafkaReceiver::from_config(
+                pipeline_ctx,
+                &json!({
+                    "connection": { "brokers": "localhost:9092" },
+                    "topics": [],
+                    "group_id": "df",
+                    "format": "otap"
+                }),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_offset_tracker() {
+        let mut tracker = OffsetTracker::default();
+        for offset in 10..13 {
+            tracker.consumed(0, 1, offset);
+        }
+        // out of order acks do not commit past the oldest message in flight
+        assert_eq!(tracker.done(0, 1, 11), Some(10));
+        assert_eq!(tracker.done(0, 1, 12), None);
+        assert_eq!(tracker.done(0, 1, 10), Some(13));
+        // unknown partitions are ignored
+        assert_eq!(tracker.done(0, 2, 10), None);
+    }
+
+    #[test]
+    fn test_offset_tracker_redelivery() {
+        let mut tracker = OffsetTracker::default();
+        for offset in 0..3 {
+            tracker.consumed(0, 1, offset);
+        }
+        // the message Nacked with a retryable failure stays in flight while it is redelivered
+        assert_eq!(tracker.redeliver(0, 1, 0, 2), Some(1));
+        assert_eq!(tracker.done(0, 1, 1), Some(0));
+        assert_eq!(tracker.done(0, 1, 2), None);
+        assert_eq!(tracker.redeliver(0, 1, 0, 2), Some(2));
+        // then it is released once its redeliveries are exhausted, and the commit advances
+        assert_eq!(tracker.redeliver(0, 1, 0, 2), None);
+        assert_eq!(tracker.done(0, 1, 0), Some(3));
+        assert!(!tracker.is_in_flight(0, 1, 0));
+        assert_eq!(tracker.redeliver(0, 1, 0, 2), None);
+
+        // the offsets of a revoked partition are forgotten
+        tracker.consumed(0, 1, 3);
+        assert!(tracker.is_in_flight(0, 1, 3));
+        tracker.revoke(0, 1);
+        assert!(!tracker.is_in_flight(0, 1, 3));
+        assert_eq!(tracker.done(0, 1, 3), None);
+    }
+
+    #[test]
+    fn test_calldata_round_trip() {
+        let calldata = encode_calldata(3, 7, 1 << 40);
+        assert_eq!(decode_calldata(&calldata), Some((3, 7, 1 << 40)));
+    }
+
+    #[test]
+    fn test_nack_action() {
+        let pdata = || OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(vec![]).into());
+        assert_eq!(
+            nack_action(&NackMsg::new("unavailable", pdata()).with_class(NackClass::Unavailable)),
+            NackAction::Redeliver
+        );
+        assert_eq!(
+            nack_action(&NackMsg::new_permanent("rejected", pdata())),
+            NackAction::DeadLetter
+        );
+        assert_eq!(
+            nack_action(&NackMsg::new("malformed", pdata()).with_class(NackClass::InvalidData)),
+            NackAction::Redeliver
+        );
+        assert_eq!(
+            nack_action(
+                &NackMsg::new_permanent("malformed", pdata()).with_class(NackClass::InvalidData)
+            ),
+            NackAction::DeadLetter
+        );
+    }
+
+    #[test]
+    fn test_dead_letter_headers() {
+        use rdkafka::message::Headers;
+
+        let mut nack = NackMsg::new_permanent(
+            "schema rejected",
+            OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(vec![]).into()),
+        )
+        .with_class(NackClass::InvalidData);
+        nack.node = Some(otap_df_engine::testing::test_node("otlp_exporter"));
+
+        let headers = dead_letter_headers(&nack, Some(("otlp_logs", 2, 42)));
+        let headers: Vec<(&str, &[u8])> = headers
+            .iter()
+            .map(|h| (h.key, h.value.unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            headers,
+            vec![
+                ("nack.reason", b"schema rejected".as_slice()),
+                ("nack.class", b"invalid_data".as_slice()),
+                ("nack.node", b"otlp_exporter".as_slice()),
+                ("source.topic", b"otlp_logs".as_slice()),
+                ("source.partition", b"2".as_slice()),
+                ("source.offset", b"42".as_slice()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_failure() {
+        let dead_letter = DeadLetter {
+            producer: rdkafka::ClientConfig::new()
+                .set("bootstrap.servers", "localhost:9092")
+                .create()
+                .unwrap(),
+            topic: "otlp_logs_dlq".into(),
+            format: ExportFormat::Otlp,
+        };
+        let mut nack = NackMsg::new_permanent(
+            "rejected",
+            OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(vec![]).into()),
+        );
+        nack.calldata = encode_calldata(0, 1, 42);
+
+        // a Nack without the refused message cannot be dead lettered, and completes at once
+        // with the calldata of the message so that its offset is committed all the same
+        let (calldata, result) = dead_letter.produce(&nack, None).await;
+        assert_eq!(decode_calldata(&calldata), Some((0, 1, 42)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_decode_payload() {
+        let payload = decode_payload(MessageFormat::OtlpTraces, b"").unwrap();
+        assert_eq!(payload.signal_type(), SignalType::Traces);
+
+        let batch = create_simple_logs_arrow_record_batches(SimpleDataGenOptions::default());
+        let payload = decode_payload(MessageFormat::Otap, &batch.encode_to_vec()).unwrap();
+        assert_eq!(payload.signal_type(), SignalType::Logs);
+
+        assert!(decode_payload(MessageFormat::Otap, b"\xff").is_err());
+    }
+}
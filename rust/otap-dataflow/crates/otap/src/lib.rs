@@ -20,6 +20,24 @@ pub mod otap_receiver;
 /// produce for the pipeline OTAP PData
 pub mod otlp_receiver;
 
+/// Implementation of an OTLP/HTTP receiver accepting protobuf and JSON encoded requests
+pub mod otlp_http_receiver;
+
+/// Conversion between OTLP protobuf requests and OTLP/JSON
+pub mod otlp_json;
+
+/// Connection settings shared by the Kafka nodes
+#[cfg(feature = "kafka")]
+pub mod kafka;
+
+/// Implementation of a Kafka receiver consuming OTLP or OTAP messages
+#[cfg(feature = "kafka")]
+pub mod kafka_receiver;
+
+/// Implementation of a Kafka exporter producing OTLP or OTAP messages
+#[cfg(feature = "kafka")]
+pub mod kafka_exporter;
+
 /// Implementation of OTLP exporter that implements the exporter trait
 pub mod otlp_exporter;
 
@@ -46,15 +64,45 @@ pub mod perf_exporter;
 
 pub mod fake_data_generator;
 
+/// Implementation of a receiver replaying OTAP batches captured to files
+pub mod replay_receiver;
+
+/// Implementation of a receiver exporting the internal metrics of the engine as OTLP metrics
+pub mod internal_telemetry_receiver;
+
 /// Implementation of debug processor that outputs received signals in a string format for user view
 pub mod debug_processor;
 
+/// Implementation of a debug exporter printing samples of the OTAP record batches
+pub mod debug_exporter;
+
+/// Implementation of a file exporter writing OTLP JSON-lines or length-prefixed protobuf
+pub mod file_exporter;
+
 /// Implementation of a noop exporter that acts as a exporter placeholder
 pub mod noop_exporter;
 
 /// An error-exporter returns a static error.
 pub mod error_exporter;
 
+/// In-process channels connecting the pipelines of a pipeline group
+pub mod pipeline_bridge;
+
+/// Implementation of an exporter sending pdata to another pipeline of the same pipeline group
+pub mod pipeline_bridge_exporter;
+
+/// Implementation of a receiver consuming the pdata sent to its pipeline by other pipelines
+pub mod pipeline_bridge_receiver;
+
+/// Implementation of an exporter distributing pdata across several backends with consistent hashing
+pub mod load_balancing_exporter;
+
+/// Implementation of an exporter switching to a secondary exporter while the primary is unhealthy
+pub mod failover_exporter;
+
+/// Exporters run as a part of another exporter
+mod nested_exporter;
+
 /// testing utilities
 #[cfg(test)]
 mod otap_mock;
@@ -72,11 +120,42 @@ pub mod signal_type_router;
 
 /// Attributes processor (OTAP-based)
 pub mod attributes_processor;
+
 /// compression formats
 pub mod compression;
+/// Fan-out processor duplicating pdata to several out ports through independent queues
+pub mod fanout_processor;
+/// Filter processor evaluating expressions over OTAP columns
+pub mod filter_processor;
+/// File IO backends, with an optional io_uring backend on Linux
+pub mod io_backend;
+/// Kubernetes attributes processor stamping pod metadata onto resources
+pub mod k8s_attributes_processor;
+/// Log deduplication processor collapsing repeated log records within a time window
+pub mod log_dedup_processor;
+/// Logs-to-metrics processor counting log records into metrics sent on a dedicated port
+pub mod logs_to_metrics_processor;
 mod metrics;
 /// gRPC service implementation
 pub mod otlp_grpc;
+/// Persistent queue processor buffering pdata on disk until it is delivered downstream
+pub mod persistent_queue_processor;
+/// Probabilistic sampler processor keeping a consistent percentage of traces and logs
+pub mod probabilistic_sampler_processor;
+/// Rate limiting processor throttling the record and byte rates of pipelines or tenants
+pub mod rate_limit_processor;
+/// Redaction processor removing or masking sensitive attributes of all signals
+pub mod redaction_processor;
+/// Resource detection processor enriching resources with host, OS, cloud and container attributes
+pub mod resource_detection_processor;
+/// Routing processor sending pdata to named out ports selected by signal and filter expressions
+pub mod routing_processor;
+/// Semantic convention processor renaming attributes between convention versions
+pub mod semconv_processor;
+/// Span metrics processor aggregating spans into request, error and duration metrics
+pub mod span_metrics_processor;
+/// Tail sampling processor deciding on whole traces after a decision window
+pub mod tail_sampling_processor;
 
 /// Factory for OTAP-based pipeline
 #[pipeline_factory(OTAP, OtapPdata)]
@@ -0,0 +1,648 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exporter distributing pdata across several backends with consistent hashing.
+//!
+//! The exporter wraps one OTLP or OTAP exporter per endpoint and sends each log record, span or
+//! metric to the backend selected by hashing its trace ID or the value of one of its resource
+//! attributes, so that all the spans of a trace reach the same backend, e.g. a tier of tail
+//! sampling collectors.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! endpoints:
+//!   - http://sampler-0:4317
+//!   - http://sampler-1:4317
+//! protocol: otap
+//! routing_key: trace_id          # or { resource_attribute: service.name }
+//! exporter:
+//!   compression_method: zstd
+//! ```
+//!
+//! `exporter` is the configuration of the backend exporters, without their `grpc_endpoint`.
+//! Endpoints are placed on a hash ring with many virtual nodes each, so adding or removing an
+//! endpoint only moves the keys of about one backend. Rows without a key (e.g. metrics when
+//! routing by trace ID) are sent together to a backend chosen in turn for every message.
+//!
+//! A message whose rows all go to the same backend is forwarded as is, and Acked or Nacked by
+//! the backend exporter. Otherwise it is split with Arrow's filter kernel into one message per
+//! backend, and acknowledged once the parts are queued.
+
+use crate::OTAP_EXPORTER_FACTORIES;
+use crate::filter_processor::eval::{field_values, filter_rows, root_payload_type};
+use crate::filter_processor::expr::{Domain, Field};
+use crate::nested_exporter::NestedExporter;
+use crate::otap_exporter::OTAP_EXPORTER;
+use crate::otlp_exporter::OTLP_EXPORTER;
+use crate::pdata::{OtapPayload, OtapPdata, decode_ids};
+use arrow::array::{AsArray, BooleanArray};
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ExporterConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, NackClass, NackMsg, NodeControlMsg};
+use otap_df_engine::error::Error;
+use otap_df_engine::exporter::ExporterWrapper;
+use otap_df_engine::local::exporter::{EffectHandler, Exporter};
+use otap_df_engine::message::{Message, MessageChannel};
+use otap_df_engine::node::NodeId;
+use otap_df_engine::terminal_state::TerminalState;
+use otap_df_engine::{ConsumerEffectHandlerExtension, ExporterFactory};
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry::metrics::MetricSet;
+use otap_df_telemetry_macros::metric_set;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::schema::consts;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The URN for the load balancing exporter
+pub const LOAD_BALANCING_EXPORTER_URN: &str = "urn:otap:exporter:load_balancing";
+
+/// Number of points of every endpoint on the hash ring.
+const VIRTUAL_NODES: usize = 128;
+
+/// Protocol of the backend exporters.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    /// OTLP over gRPC.
+    #[default]
+    Otlp,
+    /// OTAP streams over gRPC.
+    Otap,
+}
+
+/// Key the rows are distributed by.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingKey {
+    /// The trace ID of the span or log record.
+    #[default]
+    TraceId,
+    /// The value of the given resource attribute.
+    ResourceAttribute(String),
+}
+
+/// Configuration for the load balancing exporter
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// gRPC endpoints of the backends.
+    pub endpoints: Vec<String>,
+
+    /// Protocol of the backend exporters.
+    #[serde(default)]
+    pub protocol: Protocol,
+
+    /// Key the rows are distributed by.
+    #[serde(default)]
+    pub routing_key: RoutingKey,
+
+    /// Configuration of the backend exporters, without their `grpc_endpoint`.
+    #[serde(default)]
+    pub exporter: Map<String, Value>,
+}
+
+/// Load balancing exporter metrics.
+#[metric_set(name = "load_balancing.exporter.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct LoadBalancingExporterMetrics {
+    /// Number of messages sent as is to a single backend.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// Number of messages split across several backends.
+    #[metric(unit = "{msg}")]
+    pub msgs_split: Counter<u64>,
+
+    /// Number of messages Nacked because they could not be split or their backend stopped.
+    #[metric(unit = "{msg}")]
+    pub msgs_refused: Counter<u64>,
+}
+
+/// Consistent hash ring mapping keys to backend indexes.
+struct HashRing {
+    /// Points of the ring and their backend, sorted by point.
+    points: Vec<(u64, usize)>,
+}
+
+impl HashRing {
+    fn new(endpoints: &[String]) -> Self {
+        let mut points: Vec<(u64, usize)> = endpoints
+            .iter()
+            .enumerate()
+            .flat_map(|(index, endpoint)| {
+                (0..VIRTUAL_NODES)
+                    .map(move |node| (hash(format!("{endpoint}#{node}").as_bytes()), index))
+            })
+            .collect();
+        points.sort_unstable();
+        Self { points }
+    }
+
+    /// Backend of `key`: the one owning the first point at or after the hash of the key.
+    fn backend(&self, key: &[u8]) -> usize {
+        let hash = hash(key);
+        let index = self.points.partition_point(|(point, _)| *point < hash);
+        self.points[index % self.points.len()].1
+    }
+}
+
+/// FNV-1a hash of `bytes`, followed by the finalizer of MurmurHash3 to spread nearby keys.
+fn hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    let mut hash = bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    });
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+/// Exporter distributing pdata across backend exporters
+pub struct LoadBalancingExporter {
+    routing_key: RoutingKey,
+    ring: HashRing,
+    /// The backend exporters, named after their endpoint.
+    backends: Vec<NestedExporter>,
+    /// Backend of the next message with keyless rows.
+    next_keyless: usize,
+    metrics: MetricSet<LoadBalancingExporterMetrics>,
+}
+
+/// Declare the load balancing exporter as a local exporter factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_EXPORTER_FACTORIES)]
+pub static LOAD_BALANCING_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
+    name: LOAD_BALANCING_EXPORTER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             exporter_config: &ExporterConfig| {
+        Ok(ExporterWrapper::local(
+            LoadBalancingExporter::from_config(
+                pipeline,
+                node.clone(),
+                &node_config.config,
+                exporter_config,
+            )?,
+            node,
+            node_config,
+            exporter_config,
+        ))
+    },
+};
+
+/// Schema of the configuration of the load balancing exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static LOAD_BALANCING_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(LOAD_BALANCING_EXPORTER_URN);
+
+impl LoadBalancingExporter {
+    /// create a new instance of the `[LoadBalancingExporter]` and of its backend exporters from
+    /// json config value
+    pub fn from_config(
+        pipeline_ctx: PipelineContext,
+        node: NodeId,
+        config: &Value,
+        exporter_config: &ExporterConfig,
+    ) -> Result<Self, otap_df_config::error::Error> {
+        let config: Config = parse_user_config(config).map_err(|e| {
+            otap_df_config::error::Error::InvalidUserConfig {
+                error: e.to_string(),
+            }
+        })?;
+        validate(&config)?;
+
+        let factory = match config.protocol {
+            Protocol::Otlp => &OTLP_EXPORTER,
+            Protocol::Otap => &OTAP_EXPORTER,
+        };
+        let backends = config
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                let mut backend_config = config.exporter.clone();
+                let _ = backend_config
+                    .insert("grpc_endpoint".to_string(), Value::String(endpoint.clone()));
+                NestedExporter::new(
+                    endpoint.clone(),
+                    factory,
+                    Value::Object(backend_config),
+                    &pipeline_ctx,
+                    &node,
+                    exporter_config,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            routing_key: config.routing_key,
+            ring: HashRing::new(&config.endpoints),
+            backends,
+            next_keyless: 0,
+            metrics: pipeline_ctx.register_metrics::<LoadBalancingExporterMetrics>(),
+        })
+    }
+
+    /// Backend of each root row of `records`, the keyless rows going to `keyless`.
+    ///
+    /// The ids of `records` must have been decoded with `decode_transport_optimized_ids`.
+    fn assign(&self, records: &OtapArrowRecords, keyless: usize) -> Result<Vec<usize>, ArrowError> {
+        let Some(root) = records.get(root_payload_type(records)) else {
+            return Ok(Vec::new());
+        };
+        let backend = |key: Option<&[u8]>| key.map_or(keyless, |key| self.ring.backend(key));
+        match &self.routing_key {
+            RoutingKey::TraceId => {
+                let Some(trace_ids) = root.column_by_name(consts::TRACE_ID) else {
+                    return Ok(vec![keyless; root.num_rows()]);
+                };
+                let trace_ids = cast(trace_ids, &DataType::FixedSizeBinary(16))?;
+                Ok(trace_ids
+                    .as_fixed_size_binary()
+                    .iter()
+                    .map(|trace_id| backend(trace_id.filter(|id| id.iter().any(|b| *b != 0))))
+                    .collect())
+            }
+            RoutingKey::ResourceAttribute(key) => {
+                let field = Field::Attribute {
+                    domain: Domain::Resource,
+                    key: key.clone(),
+                };
+                Ok(field_values(&field, records)?
+                    .iter()
+                    .map(|value| backend(value.as_deref().map(str::as_bytes)))
+                    .collect())
+            }
+        }
+    }
+
+    /// Sends `pdata` to one or several backends.
+    async fn dispatch(
+        &mut self,
+        pdata: OtapPdata,
+        effect_handler: &EffectHandler<OtapPdata>,
+    ) -> Result<(), Error> {
+        let keyless = self.next_keyless;
+        self.next_keyless = (self.next_keyless + 1) % self.backends.len();
+        if self.backends.len() == 1 {
+            return self.forward(0, pdata, effect_handler).await;
+        }
+
+        let (context, payload) = pdata.into_parts();
+        let split = |payload: OtapPayload| -> Result<_, String> {
+            let mut records: OtapArrowRecords = payload.try_into().map_err(|e| format!("{e}"))?;
+            decode_ids(&mut records).map_err(|e| format!("failed to decode ids: {e}"))?;
+            let targets = self
+                .assign(&records, keyless)
+                .map_err(|e| format!("failed to hash rows: {e}"))?;
+            Ok((records, targets))
+        };
+        let (records, targets) = match split(payload.clone()) {
+            Ok(split) => split,
+            Err(e) => {
+                self.metrics.msgs_refused.inc();
+                let nack = NackMsg::new_permanent(
+                    &format!("cannot balance message: {e}"),
+                    OtapPdata::new(context, payload),
+                )
+                .with_class(NackClass::InvalidData);
+                return effect_handler.notify_nack(nack).await;
+            }
+        };
+
+        // distinct backends, in order of appearance
+        let mut distinct: Vec<usize> = Vec::new();
+        for target in &targets {
+            if !distinct.contains(target) {
+                distinct.push(*target);
+            }
+        }
+        if distinct.len() <= 1 {
+            let target = distinct.first().copied().unwrap_or(keyless);
+            return self
+                .forward(
+                    target,
+                    OtapPdata::new(context, records.into()),
+                    effect_handler,
+                )
+                .await;
+        }
+
+        self.metrics.msgs_split.inc();
+        let root_type = root_payload_type(&records);
+        for target in distinct {
+            let mask: BooleanArray = targets.iter().map(|t| Some(*t == target)).collect();
+            let mut part = records.clone();
+            if let Err(e) = filter_rows(&mut part, root_type, &mask) {
+                self.metrics.msgs_refused.inc();
+                let nack = NackMsg::new_permanent(
+                    &format!("failed to split message: {e}"),
+                    OtapPdata::new(context, payload),
+                )
+                .with_class(NackClass::Internal);
+                return effect_handler.notify_nack(nack).await;
+            }
+            let backend = &self.backends[target];
+            if let Err(e) = backend.send(OtapPdata::new_todo_context(part.into())).await {
+                self.metrics.msgs_refused.inc();
+                let nack = NackMsg::new(
+                    &format!("backend {} stopped: {e}", backend.name),
+                    OtapPdata::new(context, payload),
+                )
+                .with_class(NackClass::Unavailable);
+                return effect_handler.notify_nack(nack).await;
+            }
+        }
+
+        // The parts are on their way: the original message is fully processed
+        let mut payload = payload;
+        if !context.may_return_payload() {
+            let _ = payload.take_payload();
+        }
+        effect_handler
+            .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+            .await
+    }
+
+    /// Sends `pdata` as is to the backend `target`, which Acks or Nacks it.
+    async fn forward(
+        &mut self,
+        target: usize,
+        pdata: OtapPdata,
+        effect_handler: &EffectHandler<OtapPdata>,
+    ) -> Result<(), Error> {
+        let backend = &self.backends[target];
+        match backend.send(pdata).await {
+            Ok(()) => {
+                self.metrics.msgs_forwarded.inc();
+                Ok(())
+            }
+            Err(e) => {
+                let error = format!("backend {} stopped", backend.name);
+                self.metrics.msgs_refused.inc();
+                effect_handler
+                    .notify_nack(NackMsg::new(&error, e.inner()).with_class(NackClass::Unavailable))
+                    .await
+            }
+        }
+    }
+}
+
+/// Checks the parts of the configuration the backend exporters don't check.
+fn validate(config: &Config) -> Result<(), otap_df_config::error::Error> {
+    let invalid = |error: String| Err(otap_df_config::error::Error::InvalidUserConfig { error });
+    if config.endpoints.is_empty() {
+        return invalid("endpoints must not be empty".to_string());
+    }
+    for (index, endpoint) in config.endpoints.iter().enumerate() {
+        if config.endpoints[..index].contains(endpoint) {
+            return invalid(format!("duplicate endpoint {endpoint}"));
+        }
+    }
+    if config.exporter.contains_key("grpc_endpoint") {
+        return invalid("the exporter configuration must not set grpc_endpoint".to_string());
+    }
+    if config.routing_key == RoutingKey::ResourceAttribute(String::new()) {
+        return invalid("resource_attribute must not be empty".to_string());
+    }
+    Ok(())
+}
+
+#[async_trait(?Send)]
+impl Exporter<OtapPdata> for LoadBalancingExporter {
+    async fn start(
+        mut self: Box<Self>,
+        mut msg_chan: MessageChannel<OtapPdata>,
+        effect_handler: EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        for backend in &mut self.backends {
+            backend.start(&effect_handler);
+        }
+        let timer_cancel_handle = effect_handler
+            .start_periodic_telemetry(Duration::from_secs(1))
+            .await?;
+
+        loop {
+            match msg_chan.recv().await? {
+                Message::Control(NodeControlMsg::Shutdown { deadline, reason }) => {
+                    _ = timer_cancel_handle.cancel().await;
+                    for backend in &self.backends {
+                        backend
+                            .send_control(NodeControlMsg::Shutdown {
+                                deadline,
+                                reason: reason.clone(),
+                            })
+                            .await;
+                    }
+                    for backend in &mut self.backends {
+                        backend.join().await;
+                    }
+                    return Ok(TerminalState::new(deadline, [self.metrics]));
+                }
+                Message::Control(NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                }) => {
+                    _ = metrics_reporter.report(&mut self.metrics);
+                    // the backend exporters share the telemetry timer of this exporter
+                    for backend in &self.backends {
+                        backend
+                            .send_control(NodeControlMsg::CollectTelemetry {
+                                metrics_reporter: metrics_reporter.clone(),
+                            })
+                            .await;
+                    }
+                }
+                Message::PData(pdata) => self.dispatch(pdata, &effect_handler).await?,
+                _ => {
+                    // ignore unhandled messages
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::test_node;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::proto::opentelemetry::collector::trace::v1::ExportTraceServiceRequest;
+    use otel_arrow_rust::proto::opentelemetry::common::v1::{AnyValue, KeyValue};
+    use otel_arrow_rust::proto::opentelemetry::resource::v1::Resource;
+    use otel_arrow_rust::proto::opentelemetry::trace::v1::{ResourceSpans, ScopeSpans, Span};
+    use prost::Message as _;
+    use serde_json::json;
+
+    fn exporter(config: Value) -> Result<LoadBalancingExporter, otap_df_config::error::Error> {
+        let controller_ctx = ControllerContext::new(MetricsRegistryHandle::new());
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        LoadBalancingExporter::from_config(
+            pipeline_ctx,
+            test_node("load_balancing"),
+            &config,
+            &ExporterConfig::new("load_balancing"),
+        )
+    }
+
+    fn endpoints(count: usize) -> Vec<String> {
+        (0..count)
+            .map(|i| format!("http://backend-{i}:4317"))
+            .collect()
+    }
+
+    /// One resource per service, with one span per trace id.
+    fn traces_records(services: &[(&str, Vec<u8>)]) -> OtapArrowRecords {
+        let req = ExportTraceServiceRequest {
+            resource_spans: services
+                .iter()
+                .map(|(service, trace_id)| ResourceSpans {
+                    resource: Some(Resource {
+                        attributes: vec![KeyValue::new(
+                            "service.name",
+                            AnyValue::new_string(*service),
+                        )],
+                        ..Default::default()
+                    }),
+                    scope_spans: vec![ScopeSpans {
+                        spans: vec![Span {
+                            trace_id: trace_id.clone(),
+                            span_id: vec![1; 8],
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })
+                .collect(),
+        };
+        let payload: OtapPayload = OtlpProtoBytes::ExportTracesRequest(req.encode_to_vec()).into();
+        let mut records: OtapArrowRecords = payload.try_into().expect("convert to arrow");
+        records
+            .decode_transport_optimized_ids()
+            .expect("decode ids");
+        records
+    }
+
+    #[test]
+    fn test_config() {
+        let lb = exporter(json!({
+            "endpoints": endpoints(2),
+            "protocol": "otap",
+            "routing_key": { "resource_attribute": "service.name" },
+            "exporter": { "compression_method": "gzip" },
+        }))
+        .expect("valid config");
+        assert_eq!(lb.backends.len(), 2);
+        assert_eq!(
+            lb.routing_key,
+            RoutingKey::ResourceAttribute("service.name".into())
+        );
+
+        assert!(exporter(json!({ "endpoints": [] })).is_err());
+        assert!(exporter(json!({ "endpoints": ["http://a:4317", "http://a:4317"] })).is_err());
+        assert!(
+            exporter(json!({
+                "endpoints": endpoints(2),
+                "exporter": { "grpc_endpoint": "http://a:4317" },
+            }))
+            .is_err()
+        );
+        // the backend exporters validate their own configuration
+        assert!(
+            exporter(json!({ "endpoints": endpoints(2), "exporter": { "unknown": 1 } })).is_err()
+        );
+    }
+
+    #[test]
+    fn test_hash_ring() {
+        let ring = HashRing::new(&endpoints(4));
+        let keys: Vec<[u8; 16]> = (0..4000u32)
+            .map(|i| {
+                let mut key = [0; 16];
+                key[..4].copy_from_slice(&i.to_be_bytes());
+                key
+            })
+            .collect();
+
+        // keys are spread across all the backends
+        let mut counts = [0; 4];
+        for key in &keys {
+            counts[ring.backend(key)] += 1;
+        }
+        assert!(counts.iter().all(|count| *count > 500), "{counts:?}");
+
+        // adding a backend only moves keys to the new backend
+        let bigger = HashRing::new(&endpoints(5));
+        for key in &keys {
+            let before = ring.backend(key);
+            let after = bigger.backend(key);
+            assert!(after == before || after == 4);
+        }
+    }
+
+    #[test]
+    fn test_assign_by_trace_id() {
+        let lb = exporter(json!({ "endpoints": endpoints(3) })).expect("valid config");
+        let trace_a = vec![1; 16];
+        let trace_b = vec![2; 16];
+        let records = traces_records(&[
+            ("checkout", trace_a.clone()),
+            ("payment", trace_b.clone()),
+            ("cart", trace_a.clone()),
+            ("cart", vec![0; 16]),
+        ]);
+        let targets = lb.assign(&records, 7).expect("assign");
+        assert_eq!(targets.len(), 4);
+        assert_eq!(targets[0], lb.ring.backend(&trace_a));
+        assert_eq!(targets[1], lb.ring.backend(&trace_b));
+        // spans of the same trace go to the same backend
+        assert_eq!(targets[0], targets[2]);
+        // spans without trace id go to the keyless backend
+        assert_eq!(targets[3], 7);
+    }
+
+    #[test]
+    fn test_assign_by_resource_attribute() {
+        let lb = exporter(json!({
+            "endpoints": endpoints(3),
+            "routing_key": { "resource_attribute": "service.name" },
+        }))
+        .expect("valid config");
+        let records = traces_records(&[("checkout", vec![1; 16]), ("payment", vec![2; 16])]);
+        let targets = lb.assign(&records, 7).expect("assign");
+        assert_eq!(
+            targets,
+            vec![lb.ring.backend(b"checkout"), lb.ring.backend(b"payment")]
+        );
+    }
+
+    #[test]
+    fn test_load_balancing_exporter_conformance() {
+        // the single backend is unreachable, so it Nacks every message forwarded to it
+        let grpc_port = portpicker::pick_unused_port().expect("No free ports");
+        crate::testing::conformance::run_exporter_conformance(
+            &LOAD_BALANCING_EXPORTER,
+            json!({ "endpoints": [format!("http://127.0.0.1:{grpc_port}")] }),
+            Some(otap_df_engine::Interests::NACKS),
+        );
+    }
+}
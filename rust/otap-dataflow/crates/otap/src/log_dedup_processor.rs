@@ -0,0 +1,597 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Log deduplication processor for OTAP pipelines.
+//!
+//! This processor buffers the log records received during a time window, then collapses the
+//! records repeating an earlier record of the window into that first record. Two records are
+//! repeats when their bodies are equal and they have the same values for the configured
+//! attributes (a missing attribute only equals a missing attribute). The remaining records get
+//! an integer attribute counting the records they stand for. Metrics and traces pass through.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! interval: 10s
+//! include_attributes: [http.route, error.type]
+//! count_attribute: dedup.count
+//! ```
+//!
+//! The buffered batches of a window are merged into as few batches as possible before the
+//! repeats are removed with Arrow's filter kernel. Input messages are acknowledged once the
+//! window they belong to is forwarded. Records only collapse into records of the same window:
+//! a flood spanning several windows yields one record per window.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::attributes_processor::value_actions::set_int_attribute;
+use crate::filter_processor::eval::filter_rows;
+use crate::pdata::attributes::{AttributeIter, AttributeValue};
+use crate::pdata::{Context, OtapPayload, OtapPdata, decode_ids, pdata_error};
+use arrow::array::{AsArray, BooleanArray, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, UInt32Type};
+use arrow::error::ArrowError;
+use arrow::row::{RowConverter, SortField};
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::ConsumerEffectHandlerExtension;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, NodeControlMsg};
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::otap::batching::make_output_batches;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::schema::consts;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod metrics;
+use self::metrics::LogDedupProcessorMetrics;
+
+/// URN for the LogDedupProcessor
+pub const LOG_DEDUP_PROCESSOR_URN: &str = "urn:otap:processor:log_dedup";
+
+/// Default deduplication window.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+/// Default name of the attribute counting the collapsed records.
+const DEFAULT_COUNT_ATTRIBUTE: &str = "dedup.count";
+
+/// Configuration for the LogDedupProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Length of the deduplication window.
+    #[serde(with = "humantime_serde", default = "default_interval")]
+    #[schemars(with = "String")]
+    pub interval: Duration,
+
+    /// Log attributes that must also be equal for two records to be repeats.
+    #[serde(default)]
+    pub include_attributes: Vec<String>,
+
+    /// Attribute set to the number of records a forwarded record stands for.
+    #[serde(default = "default_count_attribute")]
+    pub count_attribute: String,
+}
+
+const fn default_interval() -> Duration {
+    DEFAULT_INTERVAL
+}
+
+fn default_count_attribute() -> String {
+    DEFAULT_COUNT_ATTRIBUTE.to_string()
+}
+
+/// Processor that collapses repeated log records within a time window.
+pub struct LogDedupProcessor {
+    config: Config,
+    // Log batches of the current window, with the context of the message they came in
+    buffered: Vec<(Context, OtapArrowRecords)>,
+    timer_started: bool,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<LogDedupProcessorMetrics>>,
+}
+
+impl LogDedupProcessor {
+    /// Creates a new LogDedupProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse LogDedupProcessor configuration: {e}"),
+            })?;
+        if config.interval.is_zero() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "interval must be greater than 0".to_string(),
+            });
+        }
+        if config.count_attribute.is_empty() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "count_attribute must not be empty".to_string(),
+            });
+        }
+        Ok(Self {
+            config,
+            buffered: Vec::new(),
+            timer_started: false,
+            metrics_set: None,
+        })
+    }
+
+    /// Merge, deduplicate and forward the batches of the current window, then acknowledge the
+    /// messages they came in.
+    async fn flush(
+        &mut self,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        if self.buffered.is_empty() {
+            return Ok(());
+        }
+        let mut buffered = std::mem::take(&mut self.buffered);
+        if let Some(m) = self.metrics_set.as_mut() {
+            m.flushes.inc();
+        }
+
+        let result = self.deduplicate_window(&buffered);
+        let mut outputs = match result {
+            Ok(outputs) => outputs,
+            Err(e) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.dedup_failed.inc();
+                }
+                return Err(pdata_error(&format!("log deduplication failed: {e}")));
+            }
+        };
+
+        if buffered.len() == 1 && outputs.len() == 1 {
+            if let (Some((context, _)), Some(records)) = (buffered.pop(), outputs.pop()) {
+                // a single message keeps its context
+                effect_handler
+                    .send_message(OtapPdata::new(context, records.into()))
+                    .await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_forwarded.inc();
+                }
+                return Ok(());
+            }
+        }
+
+        for records in outputs {
+            effect_handler
+                .send_message(OtapPdata::new_todo_context(records.into()))
+                .await?;
+            if let Some(m) = self.metrics_set.as_mut() {
+                m.msgs_forwarded.inc();
+            }
+        }
+        for (context, records) in buffered {
+            let mut payload: OtapPayload = records.into();
+            if !context.may_return_payload() {
+                let _ = payload.take_payload();
+            }
+            effect_handler
+                .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Merge the batches of a window and collapse their repeated records.
+    fn deduplicate_window(
+        &mut self,
+        buffered: &[(Context, OtapArrowRecords)],
+    ) -> Result<Vec<OtapArrowRecords>, ArrowError> {
+        let inputs: Vec<OtapArrowRecords> = buffered
+            .iter()
+            .map(|(_, records)| records.clone())
+            .collect();
+        let merged = if inputs.len() > 1 {
+            make_output_batches(None, inputs)
+                .map_err(|e| ArrowError::ComputeError(e.to_string()))?
+        } else {
+            inputs
+        };
+
+        let mut outputs = Vec::with_capacity(merged.len());
+        for mut records in merged {
+            let (rows, kept) = deduplicate(&mut records, &self.config)?;
+            if let Some(m) = self.metrics_set.as_mut() {
+                m.logs_deduplicated.add((rows - kept) as u64);
+            }
+            outputs.push(records);
+        }
+        Ok(outputs)
+    }
+}
+
+/// Collapse the repeated log records of `records` and count the records each remaining record
+/// stands for.
+///
+/// Returns the number of log records before and after deduplication.
+fn deduplicate(
+    records: &mut OtapArrowRecords,
+    config: &Config,
+) -> Result<(usize, usize), ArrowError> {
+    let num_rows = records
+        .get(ArrowPayloadType::Logs)
+        .map_or(0, RecordBatch::num_rows);
+    if num_rows == 0 {
+        return Ok((0, 0));
+    }
+
+    decode_ids(records)?;
+    let keys = record_keys(records, &config.include_attributes)?;
+
+    // first row of each distinct key, and the number of rows having that key
+    let mut groups: HashMap<Vec<u8>, (usize, i64)> = HashMap::new();
+    for (row, key) in keys.into_iter().enumerate() {
+        groups.entry(key).or_insert((row, 0)).1 += 1;
+    }
+    let mut counts = vec![0; num_rows];
+    for (row, count) in groups.into_values() {
+        counts[row] = count;
+    }
+    let mask: BooleanArray = counts.iter().map(|count| Some(*count > 0)).collect();
+    let kept = mask.true_count();
+    if kept < num_rows {
+        filter_rows(records, ArrowPayloadType::Logs, &mask)?;
+    }
+
+    counts.retain(|count| *count > 0);
+    set_int_attribute(
+        records,
+        ArrowPayloadType::LogAttrs,
+        &config.count_attribute,
+        &counts,
+    )?;
+    Ok((num_rows, kept))
+}
+
+/// Deduplication key of each log record: its body, followed by the values of the included
+/// attributes.
+fn record_keys(
+    records: &OtapArrowRecords,
+    include_attributes: &[String],
+) -> Result<Vec<Vec<u8>>, ArrowError> {
+    let Some(logs) = records.get(ArrowPayloadType::Logs) else {
+        return Ok(Vec::new());
+    };
+    let mut keys = vec![Vec::new(); logs.num_rows()];
+
+    if let Some(body) = logs.column_by_name(consts::BODY) {
+        // the row format gives equal bodies equal bytes, whatever their type and encoding
+        let converter = RowConverter::new(vec![SortField::new(body.data_type().clone())])?;
+        let rows = converter.convert_columns(&[body.clone()])?;
+        for (key, row) in keys.iter_mut().zip(rows.iter()) {
+            key.extend_from_slice(row.as_ref());
+        }
+    }
+
+    if include_attributes.is_empty() {
+        return Ok(keys);
+    }
+    // encoded value of each included attribute, by parent id
+    let mut values: HashMap<(u32, usize), Vec<u8>> = HashMap::new();
+    if let Some(attrs) = records.get(ArrowPayloadType::LogAttrs) {
+        let attrs = AttributeIter::try_new(attrs)
+            .map_err(|e| ArrowError::InvalidArgumentError(e.to_string()))?;
+        for attr in attrs {
+            if let Some(index) = include_attributes.iter().position(|key| key == attr.key) {
+                let _ = values.insert((attr.parent_id, index), encode_value(&attr.value));
+            }
+        }
+    }
+    let Some(ids) = logs.column_by_name(consts::ID) else {
+        return Ok(keys);
+    };
+    let ids = cast(ids, &DataType::UInt32)?;
+    for (key, id) in keys.iter_mut().zip(ids.as_primitive::<UInt32Type>().iter()) {
+        for index in 0..include_attributes.len() {
+            match id.and_then(|id| values.get(&(id, index))) {
+                Some(value) => {
+                    key.push(1);
+                    key.extend_from_slice(&(value.len() as u32).to_be_bytes());
+                    key.extend_from_slice(value);
+                }
+                None => key.push(0),
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Unambiguous byte encoding of an attribute value.
+fn encode_value(value: &AttributeValue<'_>) -> Vec<u8> {
+    let (tag, bytes): (u8, Vec<u8>) = match value {
+        AttributeValue::Empty => (0, Vec::new()),
+        AttributeValue::Str(v) => (1, v.as_bytes().to_vec()),
+        AttributeValue::Int(v) => (2, v.to_be_bytes().to_vec()),
+        AttributeValue::Double(v) => (3, v.to_be_bytes().to_vec()),
+        AttributeValue::Bool(v) => (4, vec![u8::from(*v)]),
+        AttributeValue::Bytes(v) => (5, v.to_vec()),
+        AttributeValue::Serialized(v) => (6, v.to_vec()),
+    };
+    let mut encoded = Vec::with_capacity(bytes.len() + 1);
+    encoded.push(tag);
+    encoded.extend_from_slice(&bytes);
+    encoded
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for LogDedupProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                NodeControlMsg::TimerTick { .. } | NodeControlMsg::Shutdown { .. } => {
+                    self.flush(effect_handler).await
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                if pdata.signal_type() != SignalType::Logs {
+                    effect_handler.send_message(pdata).await?;
+                    if let Some(m) = self.metrics_set.as_mut() {
+                        m.msgs_forwarded.inc();
+                    }
+                    return Ok(());
+                }
+
+                if !self.timer_started {
+                    // Processors have no start hook, so the window timer starts on first data
+                    let _timer_cancel = effect_handler
+                        .start_periodic_timer(self.config.interval)
+                        .await?;
+                    self.timer_started = true;
+                }
+
+                let (context, payload) = pdata.into_parts();
+                let records: OtapArrowRecords = payload.try_into()?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.logs_received.add(records.batch_length() as u64);
+                }
+                self.buffered.push((context, records));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Factory function to create a LogDedupProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_log_dedup_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = LogDedupProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<LogDedupProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register LogDedupProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static LOG_DEDUP_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: LOG_DEDUP_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_log_dedup_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the log dedup processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static LOG_DEDUP_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(LOG_DEDUP_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::{node::test_node, processor::TestRuntime};
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::logs::v1::ExportLogsServiceRequest,
+        common::v1::{AnyValue, InstrumentationScope, KeyValue, any_value},
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+        resource::v1::Resource,
+    };
+    use prost::Message as _;
+    use serde_json::json;
+
+    fn log(body: &str, route: Option<&str>) -> LogRecord {
+        LogRecord {
+            time_unix_nano: 1,
+            body: Some(AnyValue::new_string(body)),
+            attributes: route
+                .map(|route| vec![KeyValue::new("http.route", AnyValue::new_string(route))])
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    fn logs_request(log_records: Vec<LogRecord>) -> OtapPdata {
+        let req = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource::default()),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(InstrumentationScope::default()),
+                    log_records,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(bytes).into())
+    }
+
+    /// Sorted (body, route, count) of the forwarded log records.
+    fn summarize(out: Vec<OtapPdata>) -> Vec<(String, Option<String>, i64)> {
+        let mut summary: Vec<_> = out
+            .into_iter()
+            .flat_map(|pdata| {
+                let OtlpProtoBytes::ExportLogsRequest(bytes) =
+                    pdata.payload().try_into().expect("convert to otlp")
+                else {
+                    panic!("unexpected otlp variant");
+                };
+                ExportLogsServiceRequest::decode(bytes.as_slice())
+                    .expect("decode")
+                    .resource_logs
+                    .into_iter()
+                    .flat_map(|rl| rl.scope_logs)
+                    .flat_map(|sl| sl.log_records)
+                    .collect::<Vec<_>>()
+            })
+            .map(|log| {
+                let body = match log.body.and_then(|b| b.value) {
+                    Some(any_value::Value::StringValue(s)) => s,
+                    other => panic!("unexpected body {other:?}"),
+                };
+                let attribute = |key: &str| {
+                    log.attributes
+                        .iter()
+                        .find(|kv| kv.key == key)
+                        .and_then(|kv| kv.value.clone())
+                        .and_then(|v| v.value)
+                };
+                let route = match attribute("http.route") {
+                    Some(any_value::Value::StringValue(s)) => Some(s),
+                    _ => None,
+                };
+                let count = match attribute("dedup.count") {
+                    Some(any_value::Value::IntValue(n)) => n,
+                    other => panic!("unexpected count {other:?}"),
+                };
+                (body, route, count)
+            })
+            .collect();
+        summary.sort();
+        summary
+    }
+
+    fn run(config: Value, inputs: Vec<OtapPdata>, check: impl FnOnce(Vec<OtapPdata>) + 'static) {
+        let pipeline_ctx = ControllerContext::new(MetricsRegistryHandle::new())
+            .pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let mut node_config = NodeUserConfig::new_processor_config(LOG_DEDUP_PROCESSOR_URN);
+        node_config.config = config;
+        let proc = create_log_dedup_processor(
+            pipeline_ctx,
+            test_node("log-dedup-processor-test"),
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                for input in inputs {
+                    ctx.process(Message::PData(input)).await.expect("process");
+                }
+                assert!(ctx.drain_pdata().await.is_empty(), "logs are held");
+                ctx.process(Message::Control(NodeControlMsg::TimerTick {}))
+                    .await
+                    .expect("timer tick");
+                check(ctx.drain_pdata().await);
+            })
+            .validate(|_| async move {});
+    }
+
+    #[test]
+    fn test_repeats_collapsed_within_window() {
+        let inputs = vec![
+            logs_request(vec![
+                log("connection refused", Some("/a")),
+                log("connection refused", Some("/a")),
+                log("connection refused", Some("/b")),
+                log("ok", None),
+            ]),
+            logs_request(vec![log("connection refused", Some("/a")), log("ok", None)]),
+        ];
+        run(
+            json!({ "include_attributes": ["http.route"] }),
+            inputs,
+            |out| {
+                assert_eq!(
+                    summarize(out),
+                    vec![
+                        ("connection refused".to_string(), Some("/a".to_string()), 3),
+                        ("connection refused".to_string(), Some("/b".to_string()), 1),
+                        ("ok".to_string(), None, 2),
+                    ]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_attributes_ignored_unless_included() {
+        let inputs = vec![logs_request(vec![
+            log("connection refused", Some("/a")),
+            log("connection refused", Some("/b")),
+        ])];
+        run(json!({}), inputs, |out| {
+            assert_eq!(out.len(), 1);
+            let summary = summarize(out);
+            assert_eq!(summary.len(), 1);
+            assert_eq!(summary[0].2, 2);
+        });
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({ "interval": "0s" }),
+            json!({ "count_attribute": "" }),
+            json!({ "include_attributes": "http.route" }),
+        ] {
+            let err = LogDedupProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
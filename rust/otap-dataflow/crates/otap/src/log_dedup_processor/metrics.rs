@@ -0,0 +1,36 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the LogDedupProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the LogDedupProcessor node.
+#[metric_set(name = "log_dedup.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct LogDedupProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// Log records received.
+    #[metric(unit = "{log}")]
+    pub logs_received: Counter<u64>,
+
+    /// Log records removed because they repeated an earlier record of the same window.
+    #[metric(unit = "{log}")]
+    pub logs_deduplicated: Counter<u64>,
+
+    /// Number of windows flushed.
+    #[metric(unit = "{flush}")]
+    pub flushes: Counter<u64>,
+
+    /// Number of failed window flushes.
+    #[metric(unit = "{op}")]
+    pub dedup_failed: Counter<u64>,
+}
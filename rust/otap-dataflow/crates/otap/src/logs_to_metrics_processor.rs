@@ -0,0 +1,719 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Logs-to-metrics processor for OTAP pipelines.
+//!
+//! This processor counts the log records flowing through it and periodically emits the counts
+//! as OTAP metric batches on a dedicated out port, so that counters (e.g. records by severity,
+//! errors by route) can be exported without instrumenting the services a second time.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! interval: 60s
+//! metrics_port: metrics
+//! forward_port: logs
+//! metrics:
+//!   - name: log.records
+//!     description: Log records by severity
+//!     group_by: [severity_text]
+//!   - name: log.errors
+//!     condition: 'severity_number >= 17'
+//!     group_by: ['resource.attributes["service.name"]', 'attributes["http.route"]']
+//! ```
+//!
+//! Each configured metric is a cumulative monotonic sum counting the log records matching its
+//! `condition` (all records when not set), with one data point per distinct combination of the
+//! `group_by` fields. Conditions and fields use the expression language of the filter processor.
+//! Data point attributes are named after the fields (attribute key, or column path), and omitted
+//! for records without a value. Error rates are derived downstream from the error and total
+//! counters.
+//!
+//! Beyond `max_series` data points per metric, the records are counted in a single data point
+//! with the `otel.metric.overflow` attribute set to `true`.
+//!
+//! Input telemetry (logs and any other signal) is forwarded to `forward_port` when set, and
+//! consumed otherwise. When no `metrics` are configured, log records are counted by severity
+//! text as `log.records`.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::filter_processor::eval::{field_values, matching_rows, root_payload_type};
+use crate::filter_processor::expr::{self, Expr, Field};
+use crate::pdata::{OtapPayload, OtapPdata, OtlpProtoBytes, decode_ids, pdata_error};
+use arrow::array::RecordBatch;
+use arrow::error::ArrowError;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::ConsumerEffectHandlerExtension;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, NodeControlMsg};
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::collector::metrics::v1::ExportMetricsServiceRequest;
+use otel_arrow_rust::proto::opentelemetry::common::v1::{AnyValue, InstrumentationScope, KeyValue};
+use otel_arrow_rust::proto::opentelemetry::metrics::v1::{
+    AggregationTemporality, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics, Sum, metric,
+    number_data_point,
+};
+use otel_arrow_rust::proto::opentelemetry::resource::v1::Resource;
+use prost::Message as _;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+mod metrics;
+use self::metrics::LogsToMetricsProcessorMetrics;
+
+/// URN for the LogsToMetricsProcessor
+pub const LOGS_TO_METRICS_PROCESSOR_URN: &str = "urn:otap:processor:logs_to_metrics";
+
+/// Default emission interval.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+/// Default out port of the generated metrics.
+const DEFAULT_METRICS_PORT: &str = "metrics";
+/// Default maximum number of data points per metric.
+const DEFAULT_MAX_SERIES: usize = 1000;
+/// Attribute marking the data point counting the records beyond `max_series`.
+const OVERFLOW_ATTRIBUTE: &str = "otel.metric.overflow";
+/// Instrumentation scope of the generated metrics.
+const SCOPE_NAME: &str = "otap.logs_to_metrics";
+
+/// Configuration for the LogsToMetricsProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// How often the counters are emitted.
+    #[serde(with = "humantime_serde", default = "default_interval")]
+    #[schemars(with = "String")]
+    pub interval: Duration,
+
+    /// Out port the generated metrics are sent to.
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: String,
+
+    /// Out port the input telemetry is forwarded to. The input is consumed when not set.
+    #[serde(default)]
+    pub forward_port: Option<String>,
+
+    /// The metrics to derive from the log records.
+    #[serde(default = "default_metrics")]
+    pub metrics: Vec<MetricConfig>,
+
+    /// Maximum number of data points per metric.
+    #[serde(default = "default_max_series")]
+    pub max_series: usize,
+}
+
+/// A counter derived from the log records.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MetricConfig {
+    /// Name of the metric.
+    pub name: String,
+
+    /// Description of the metric.
+    #[serde(default)]
+    pub description: String,
+
+    /// Filter expression selecting the counted records (all records when not set).
+    #[serde(default)]
+    pub condition: Option<String>,
+
+    /// Fields whose values split the count into data points, e.g. `severity_text` or
+    /// `attributes["http.route"]`.
+    #[serde(default)]
+    pub group_by: Vec<String>,
+}
+
+const fn default_interval() -> Duration {
+    DEFAULT_INTERVAL
+}
+
+fn default_metrics_port() -> String {
+    DEFAULT_METRICS_PORT.to_string()
+}
+
+fn default_metrics() -> Vec<MetricConfig> {
+    vec![MetricConfig {
+        name: "log.records".to_string(),
+        description: "Log records by severity".to_string(),
+        condition: None,
+        group_by: vec!["severity_text".to_string()],
+    }]
+}
+
+const fn default_max_series() -> usize {
+    DEFAULT_MAX_SERIES
+}
+
+/// A configured metric, with its parsed expressions and its current counts.
+struct Counter {
+    name: String,
+    description: String,
+    condition: Option<Expr>,
+    // Data point attribute key, and the field it is read from
+    group_by: Vec<(String, Field)>,
+    // Count of each combination of values, `None` for the overflow data point
+    series: BTreeMap<Option<Vec<Option<String>>>, u64>,
+}
+
+/// Counts of all the configured metrics.
+struct Counters {
+    counters: Vec<Counter>,
+    max_series: usize,
+}
+
+impl Counters {
+    fn new(config: &Config) -> Result<Self, ConfigError> {
+        let invalid = |name: &str, e: expr::ParseError| ConfigError::InvalidUserConfig {
+            error: format!("invalid expression in metric `{name}`: {e}"),
+        };
+        let counters = config
+            .metrics
+            .iter()
+            .map(|metric| {
+                if metric.name.is_empty() {
+                    return Err(ConfigError::InvalidUserConfig {
+                        error: "metric name must not be empty".to_string(),
+                    });
+                }
+                let condition = metric
+                    .condition
+                    .as_deref()
+                    .map(expr::parse)
+                    .transpose()
+                    .map_err(|e| invalid(&metric.name, e))?;
+                let group_by = metric
+                    .group_by
+                    .iter()
+                    .map(|field| -> Result<(String, Field), expr::ParseError> {
+                        let field = expr::parse_field(field)?;
+                        let key = match &field {
+                            Field::Attribute { key, .. } => key.clone(),
+                            Field::Column(path) => path.join("."),
+                        };
+                        Ok((key, field))
+                    })
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| invalid(&metric.name, e))?;
+                Ok(Counter {
+                    name: metric.name.clone(),
+                    description: metric.description.clone(),
+                    condition,
+                    group_by,
+                    series: BTreeMap::new(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            counters,
+            max_series: config.max_series,
+        })
+    }
+
+    /// Counts the log records of `records`, returning the number of records.
+    ///
+    /// The ids of `records` must have been decoded with `decode_transport_optimized_ids`.
+    fn count(&mut self, records: &OtapArrowRecords) -> Result<usize, ArrowError> {
+        let num_rows = records
+            .get(root_payload_type(records))
+            .map_or(0, RecordBatch::num_rows);
+        if num_rows == 0 {
+            return Ok(0);
+        }
+        for counter in &mut self.counters {
+            let mask = counter
+                .condition
+                .as_ref()
+                .map(|condition| matching_rows(condition, records))
+                .transpose()?;
+            let values = counter
+                .group_by
+                .iter()
+                .map(|(_, field)| field_values(field, records))
+                .collect::<Result<Vec<_>, _>>()?;
+            for row in 0..num_rows {
+                if mask.as_ref().is_some_and(|mask| !mask.value(row)) {
+                    continue;
+                }
+                let key: Vec<Option<String>> =
+                    values.iter().map(|values| values[row].clone()).collect();
+                let key = if counter.series.contains_key(&Some(key.clone()))
+                    || counter.series.len() < self.max_series
+                {
+                    Some(key)
+                } else {
+                    None
+                };
+                *counter.series.entry(key).or_default() += 1;
+            }
+        }
+        Ok(num_rows)
+    }
+
+    /// Builds the request reporting the current counts, returning it with its number of data
+    /// points. Returns `None` if nothing has been counted yet.
+    fn build_request(
+        &self,
+        start_time_unix_nano: u64,
+        time_unix_nano: u64,
+    ) -> Option<(ExportMetricsServiceRequest, u64)> {
+        let mut data_points = 0;
+        let metrics: Vec<Metric> = self
+            .counters
+            .iter()
+            .filter(|counter| !counter.series.is_empty())
+            .map(|counter| {
+                let points: Vec<NumberDataPoint> = counter
+                    .series
+                    .iter()
+                    .map(|(key, count)| {
+                        let attributes = match key {
+                            Some(values) => counter
+                                .group_by
+                                .iter()
+                                .zip(values)
+                                .filter_map(|((name, _), value)| {
+                                    value.as_ref().map(|value| {
+                                        KeyValue::new(name.clone(), AnyValue::new_string(value))
+                                    })
+                                })
+                                .collect(),
+                            None => {
+                                vec![KeyValue::new(OVERFLOW_ATTRIBUTE, AnyValue::new_bool(true))]
+                            }
+                        };
+                        NumberDataPoint {
+                            attributes,
+                            start_time_unix_nano,
+                            time_unix_nano,
+                            value: Some(number_data_point::Value::AsInt(
+                                i64::try_from(*count).unwrap_or(i64::MAX),
+                            )),
+                            ..Default::default()
+                        }
+                    })
+                    .collect();
+                data_points += points.len() as u64;
+                Metric {
+                    name: counter.name.clone(),
+                    description: counter.description.clone(),
+                    unit: "{log}".to_owned(),
+                    data: Some(metric::Data::Sum(Sum {
+                        data_points: points,
+                        aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                        is_monotonic: true,
+                    })),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        if metrics.is_empty() {
+            return None;
+        }
+
+        let request = ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                resource: Some(Resource::default()),
+                scope_metrics: vec![ScopeMetrics {
+                    scope: Some(InstrumentationScope {
+                        name: SCOPE_NAME.to_owned(),
+                        version: env!("CARGO_PKG_VERSION").to_owned(),
+                        ..Default::default()
+                    }),
+                    metrics,
+                    schema_url: String::new(),
+                }],
+                schema_url: String::new(),
+            }],
+        };
+        Some((request, data_points))
+    }
+}
+
+/// Processor deriving counters from log records.
+pub struct LogsToMetricsProcessor {
+    config: Config,
+    counters: Counters,
+    start_time: SystemTime,
+    timer_started: bool,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<LogsToMetricsProcessorMetrics>>,
+}
+
+impl LogsToMetricsProcessor {
+    /// Creates a new LogsToMetricsProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse LogsToMetricsProcessor configuration: {e}"),
+            })?;
+        if config.interval.is_zero() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "interval must be greater than 0".to_string(),
+            });
+        }
+        if config.max_series == 0 {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "max_series must be greater than 0".to_string(),
+            });
+        }
+        if config.forward_port.as_ref() == Some(&config.metrics_port) {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "forward_port and metrics_port must be different".to_string(),
+            });
+        }
+        let counters = Counters::new(&config)?;
+        Ok(Self {
+            config,
+            counters,
+            start_time: SystemTime::now(),
+            timer_started: false,
+            metrics_set: None,
+        })
+    }
+
+    /// Sends the current counts to the metrics port.
+    async fn emit(
+        &mut self,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        let Some((request, data_points)) = self
+            .counters
+            .build_request(unix_nanos(self.start_time), unix_nanos(SystemTime::now()))
+        else {
+            return Ok(());
+        };
+        let mut bytes = Vec::new();
+        request
+            .encode(&mut bytes)
+            .map_err(|e| pdata_error(&format!("failed to encode metrics: {e}")))?;
+        let payload: OtapPayload = OtlpProtoBytes::ExportMetricsRequest(bytes).into();
+        let records: OtapArrowRecords = payload.try_into()?;
+        effect_handler
+            .send_message_to(
+                self.config.metrics_port.clone(),
+                OtapPdata::new_todo_context(records.into()),
+            )
+            .await?;
+        if let Some(m) = self.metrics_set.as_mut() {
+            m.data_points_emitted.add(data_points);
+        }
+        Ok(())
+    }
+
+    /// Forwards `pdata` to the forward port, or acknowledges it when there is none.
+    async fn forward(
+        &mut self,
+        pdata: OtapPdata,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match self.config.forward_port.clone() {
+            Some(port) => {
+                effect_handler.send_message_to(port, pdata).await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_forwarded.inc();
+                }
+                Ok(())
+            }
+            None => {
+                let (context, mut payload) = pdata.into_parts();
+                if !context.may_return_payload() {
+                    let _ = payload.take_payload();
+                }
+                effect_handler
+                    .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+                    .await
+            }
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for LogsToMetricsProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                NodeControlMsg::TimerTick { .. } | NodeControlMsg::Shutdown { .. } => {
+                    self.emit(effect_handler).await
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                if pdata.signal_type() != SignalType::Logs {
+                    return self.forward(pdata, effect_handler).await;
+                }
+
+                if !self.timer_started {
+                    // Processors have no start hook, so the emission timer starts on first data
+                    let _timer_cancel = effect_handler
+                        .start_periodic_timer(self.config.interval)
+                        .await?;
+                    self.timer_started = true;
+                }
+
+                let (context, payload) = pdata.into_parts();
+                let mut records: OtapArrowRecords = payload.try_into()?;
+                let counted = decode_ids(&mut records).and_then(|_| self.counters.count(&records));
+                if let Some(m) = self.metrics_set.as_mut() {
+                    match counted {
+                        Ok(logs) => m.logs_counted.add(logs as u64),
+                        Err(_) => m.count_failed.inc(),
+                    }
+                }
+                self.forward(OtapPdata::new(context, records.into()), effect_handler)
+                    .await
+            }
+        }
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+}
+
+/// Factory function to create a LogsToMetricsProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_logs_to_metrics_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = LogsToMetricsProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<LogsToMetricsProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register LogsToMetricsProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static LOGS_TO_METRICS_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: LOGS_TO_METRICS_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_logs_to_metrics_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the logs to metrics processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static LOGS_TO_METRICS_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(LOGS_TO_METRICS_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::logs::v1::ExportLogsServiceRequest,
+        common::v1::any_value,
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+    };
+    use serde_json::json;
+
+    fn log(severity_number: i32, severity_text: &str, route: &str) -> LogRecord {
+        LogRecord {
+            time_unix_nano: 1,
+            severity_number,
+            severity_text: severity_text.to_string(),
+            body: Some(AnyValue::new_string("hello")),
+            attributes: vec![KeyValue::new("http.route", AnyValue::new_string(route))],
+            ..Default::default()
+        }
+    }
+
+    fn logs_records(log_records: Vec<LogRecord>) -> OtapArrowRecords {
+        let req = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource::default()),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(InstrumentationScope::default()),
+                    log_records,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        let payload: OtapPayload = OtlpProtoBytes::ExportLogsRequest(bytes).into();
+        let mut records: OtapArrowRecords = payload.try_into().expect("convert to arrow");
+        records
+            .decode_transport_optimized_ids()
+            .expect("decode ids");
+        records
+    }
+
+    /// (metric name, sorted attributes, count) of each data point.
+    fn summarize(request: &ExportMetricsServiceRequest) -> Vec<(String, Vec<String>, i64)> {
+        let mut summary = Vec::new();
+        for metric in &request.resource_metrics[0].scope_metrics[0].metrics {
+            let Some(metric::Data::Sum(sum)) = &metric.data else {
+                panic!("expected a sum");
+            };
+            assert!(sum.is_monotonic);
+            for point in &sum.data_points {
+                let mut attributes: Vec<String> = point
+                    .attributes
+                    .iter()
+                    .map(
+                        |kv| match kv.value.as_ref().and_then(|v| v.value.as_ref()) {
+                            Some(any_value::Value::StringValue(s)) => format!("{}={s}", kv.key),
+                            Some(any_value::Value::BoolValue(b)) => format!("{}={b}", kv.key),
+                            other => panic!("unexpected value {other:?}"),
+                        },
+                    )
+                    .collect();
+                attributes.sort();
+                let Some(number_data_point::Value::AsInt(count)) = point.value else {
+                    panic!("expected an int value");
+                };
+                summary.push((metric.name.clone(), attributes, count));
+            }
+        }
+        summary
+    }
+
+    #[test]
+    fn test_count_by_severity_and_attribute() {
+        let config = LogsToMetricsProcessor::from_config(&json!({
+            "metrics": [
+                { "name": "log.records", "group_by": ["severity_text"] },
+                {
+                    "name": "log.errors",
+                    "condition": "severity_number >= 17",
+                    "group_by": ["attributes[\"http.route\"]"],
+                },
+            ],
+        }))
+        .expect("valid config");
+        let mut counters = config.counters;
+        assert!(counters.build_request(1, 2).is_none());
+
+        let records = logs_records(vec![
+            log(9, "INFO", "/a"),
+            log(17, "ERROR", "/a"),
+            log(17, "ERROR", "/b"),
+        ]);
+        assert_eq!(counters.count(&records).expect("count"), 3);
+        let records = logs_records(vec![log(17, "ERROR", "/a")]);
+        assert_eq!(counters.count(&records).expect("count"), 1);
+
+        let (request, data_points) = counters.build_request(1, 2).expect("request");
+        assert_eq!(data_points, 4);
+        assert_eq!(
+            summarize(&request),
+            vec![
+                (
+                    "log.records".to_string(),
+                    vec!["severity_text=ERROR".to_string()],
+                    3
+                ),
+                (
+                    "log.records".to_string(),
+                    vec!["severity_text=INFO".to_string()],
+                    1
+                ),
+                (
+                    "log.errors".to_string(),
+                    vec!["http.route=/a".to_string()],
+                    2
+                ),
+                (
+                    "log.errors".to_string(),
+                    vec!["http.route=/b".to_string()],
+                    1
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overflow_series() {
+        let config = LogsToMetricsProcessor::from_config(&json!({
+            "metrics": [{ "name": "log.records", "group_by": ["attributes[\"http.route\"]"] }],
+            "max_series": 1,
+        }))
+        .expect("valid config");
+        let mut counters = config.counters;
+        let records = logs_records(vec![
+            log(9, "INFO", "/a"),
+            log(9, "INFO", "/b"),
+            log(9, "INFO", "/c"),
+            log(9, "INFO", "/a"),
+        ]);
+        let _ = counters.count(&records).expect("count");
+        let (request, _) = counters.build_request(1, 2).expect("request");
+        assert_eq!(
+            summarize(&request),
+            vec![
+                (
+                    "log.records".to_string(),
+                    vec!["otel.metric.overflow=true".to_string()],
+                    2
+                ),
+                (
+                    "log.records".to_string(),
+                    vec!["http.route=/a".to_string()],
+                    2
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({ "interval": "0s" }),
+            json!({ "max_series": 0 }),
+            json!({ "forward_port": "metrics" }),
+            json!({ "metrics": [{ "name": "" }] }),
+            json!({ "metrics": [{ "name": "m", "condition": "severity_number >=" }] }),
+            json!({ "metrics": [{ "name": "m", "group_by": ["a == 1"] }] }),
+        ] {
+            let err = LogsToMetricsProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
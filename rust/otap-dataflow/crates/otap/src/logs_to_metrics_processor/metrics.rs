@@ -0,0 +1,32 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the LogsToMetricsProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the LogsToMetricsProcessor node.
+#[metric_set(name = "logs_to_metrics.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct LogsToMetricsProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// Log records counted.
+    #[metric(unit = "{log}")]
+    pub logs_counted: Counter<u64>,
+
+    /// Metric data points emitted.
+    #[metric(unit = "{data_point}")]
+    pub data_points_emitted: Counter<u64>,
+
+    /// Number of log batches that could not be counted.
+    #[metric(unit = "{op}")]
+    pub count_failed: Counter<u64>,
+}
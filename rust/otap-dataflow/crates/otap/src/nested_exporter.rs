@@ -0,0 +1,110 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exporters run as a part of another exporter, e.g. the backends of the load balancing exporter.
+//!
+//! A nested exporter is built by the factory of its plugin and started with
+//! `start_nested_exporter`, so the Acks and Nacks it sends reach the subscribers of the pdata
+//! directly. It shares the node id of the wrapping exporter, which feeds its pdata channel and
+//! forwards its control messages.
+
+use crate::pdata::OtapPdata;
+use otap_df_channel::error::SendError;
+use otap_df_channel::mpsc;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_engine::ExporterFactory;
+use otap_df_engine::config::ExporterConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{Controllable, NodeControlMsg};
+use otap_df_engine::error::Error;
+use otap_df_engine::exporter::ExporterWrapper;
+use otap_df_engine::local::exporter::EffectHandler;
+use otap_df_engine::local::message::{LocalReceiver, LocalSender};
+use otap_df_engine::message::{Receiver, Sender};
+use otap_df_engine::node::{NodeId, NodeWithPDataReceiver};
+use otap_df_engine::terminal_state::TerminalState;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// An exporter run by another exporter, and the channels feeding it.
+pub(crate) struct NestedExporter {
+    /// Name of the exporter in errors and logs, e.g. its endpoint.
+    pub(crate) name: String,
+    pdata_tx: LocalSender<OtapPdata>,
+    control_tx: Sender<NodeControlMsg<OtapPdata>>,
+    /// The exporter, until it is started.
+    exporter: Option<ExporterWrapper<OtapPdata>>,
+    /// The task running the exporter, once it is started.
+    task: Option<JoinHandle<Result<TerminalState, Error>>>,
+}
+
+impl NestedExporter {
+    /// Builds an exporter with the given factory and plugin configuration.
+    pub(crate) fn new(
+        name: String,
+        factory: &ExporterFactory<OtapPdata>,
+        config: serde_json::Value,
+        pipeline_ctx: &PipelineContext,
+        node: &NodeId,
+        exporter_config: &ExporterConfig,
+    ) -> Result<Self, ConfigError> {
+        let mut node_config = NodeUserConfig::new_exporter_config(factory.name);
+        node_config.config = config;
+        let mut exporter = (factory.create)(
+            pipeline_ctx.clone(),
+            node.clone(),
+            Arc::new(node_config),
+            exporter_config,
+        )?;
+
+        let (pdata_tx, pdata_rx) = mpsc::Channel::new(exporter_config.input_pdata_channel.capacity);
+        exporter
+            .set_pdata_receiver(
+                node.clone(),
+                Receiver::Local(LocalReceiver::MpscReceiver(pdata_rx)),
+            )
+            .map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("cannot create the exporter {name}: {e}"),
+            })?;
+        Ok(Self {
+            name,
+            pdata_tx: LocalSender::MpscSender(pdata_tx),
+            control_tx: exporter.control_sender(),
+            exporter: Some(exporter),
+            task: None,
+        })
+    }
+
+    /// Starts the exporter on the current thread.
+    pub(crate) fn start(&mut self, effect_handler: &EffectHandler<OtapPdata>) {
+        if let Some(exporter) = self.exporter.take() {
+            let effect_handler = effect_handler.clone();
+            self.task = Some(tokio::task::spawn_local(async move {
+                effect_handler.start_nested_exporter(exporter).await
+            }));
+        }
+    }
+
+    /// Sends pdata to the exporter, waiting while its channel is full.
+    pub(crate) async fn send(&self, pdata: OtapPdata) -> Result<(), SendError<OtapPdata>> {
+        self.pdata_tx.send(pdata).await
+    }
+
+    /// Forwards a control message to the exporter, ignoring a stopped exporter.
+    pub(crate) async fn send_control(&self, msg: NodeControlMsg<OtapPdata>) {
+        _ = self.control_tx.send(msg).await;
+    }
+
+    /// Waits for the exporter to stop after a shutdown.
+    pub(crate) async fn join(&mut self) {
+        let Some(task) = self.task.take() else {
+            return;
+        };
+        match task.await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => log::warn!("Nested exporter {} failed: {e}", self.name),
+            Err(e) => log::warn!("Nested exporter {} panicked: {e}", self.name),
+        }
+    }
+}
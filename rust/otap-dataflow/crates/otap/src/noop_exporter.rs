@@ -98,4 +98,13 @@ mod tests {
             Interests::empty(),
         );
     }
+
+    #[test]
+    fn test_noop_exporter_conformance() {
+        crate::testing::conformance::run_exporter_conformance(
+            &NOOP_EXPORTER,
+            json!({}),
+            Some(Interests::ACKS),
+        );
+    }
 }
@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! OTAP batch processor.
-//! Batches OtapPdata by count or timer; uses upstream OTAP batching for merge/split.
+//! Batches OtapPdata by row count, byte size or age; uses upstream OTAP batching for
+//! merge/split (which also re-normalizes dictionaries across the merged record batches).
 
 use crate::OTAP_PROCESSOR_FACTORIES;
 use crate::pdata::OtapPdata;
@@ -11,17 +12,20 @@ use linkme::distributed_slice;
 use otap_df_config::error::Error as ConfigError;
 use otap_df_config::experimental::SignalType;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::config::ProcessorConfig;
 use otap_df_engine::error::Error as EngineError;
 use otap_df_engine::local::processor as local;
 use otap_df_engine::message::Message;
 use otap_df_engine::node::NodeId;
 use otap_df_engine::processor::ProcessorWrapper;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json::Value;
 use std::num::NonZeroU64;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 // Telemetry metrics
 pub mod metrics;
 use crate::otap_batch_processor::metrics::OtapBatchProcessorMetrics;
@@ -66,7 +70,7 @@ const SIG_METRICS: &str = "metrics";
 const SIG_TRACES: &str = "traces";
 
 /// Configuration for the OTAP batch processor (parity with Go batchprocessor)
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct Config {
     /// Flush current batch when this count is reached.
     /// When set to 0, the batch size is ignored and data will be sent immediately
@@ -77,8 +81,14 @@ pub struct Config {
     /// Go behavior: 0 means no upper limit; missing defaults to 0 (unlimited).
     #[serde(default = "default_send_batch_max_size")]
     pub send_batch_max_size: usize,
-    /// Flush non-empty batches on this interval.
+    /// Flush the current batch of a signal once its buffered Arrow memory reaches this many
+    /// bytes. 0 (the default) disables the byte-size trigger.
+    #[serde(default)]
+    pub send_batch_max_bytes: usize,
+    /// Flush non-empty batches on this interval. A periodic timer is started on the first
+    /// message and buffered data is flushed once the oldest buffered message reaches this age.
     #[serde(with = "humantime_serde", default = "default_timeout_duration_opt")]
+    #[schemars(with = "Option<String>")]
     pub timeout: Option<Duration>,
     /// Optional metadata partitioning keys (resource/scope/attribute names). Not yet supported.
     /// ToDo: Support metadata-aware batching.
@@ -111,6 +121,7 @@ impl Default for Config {
         Self {
             send_batch_size: default_send_batch_size_opt(),
             send_batch_max_size: default_send_batch_max_size(),
+            send_batch_max_bytes: 0,
             timeout: default_timeout_duration_opt(),
             metadata_keys: Vec::new(),
             metadata_cardinality_limit: None,
@@ -134,6 +145,16 @@ pub struct OtapBatchProcessor {
     dirty_logs: bool,
     dirty_metrics: bool,
     dirty_traces: bool,
+    // Running buffered Arrow memory per signal for the byte-size trigger
+    bytes_logs: usize,
+    bytes_metrics: usize,
+    bytes_traces: usize,
+    // Arrival time of the oldest buffered record per signal for the timeout trigger
+    oldest_logs: Option<Instant>,
+    oldest_metrics: Option<Instant>,
+    oldest_traces: Option<Instant>,
+    // Whether the periodic flush timer has been started
+    timer_started: bool,
     // Internal telemetry
     metrics: MetricSet<OtapBatchProcessorMetrics>,
 }
@@ -158,6 +179,16 @@ fn requested_split_max(input: &[OtapArrowRecords], max_val: usize) -> Option<Non
     }
 }
 
+/// Approximate in-memory size of all the record batches of an OTAP record.
+fn record_bytes(records: &OtapArrowRecords) -> usize {
+    records
+        .allowed_payload_types()
+        .iter()
+        .filter_map(|payload_type| records.get(*payload_type))
+        .map(|batch| batch.get_array_memory_size())
+        .sum()
+}
+
 async fn log_batching_failed(
     effect: &mut local::EffectHandler<OtapPdata>,
     signal: &str,
@@ -179,7 +210,7 @@ impl OtapBatchProcessor {
         metrics: MetricSet<OtapBatchProcessorMetrics>,
     ) -> Result<Self, ConfigError> {
         let mut config: Config =
-            serde_json::from_value(cfg.clone()).map_err(|e| ConfigError::InvalidUserConfig {
+            parse_user_config(cfg).map_err(|e| ConfigError::InvalidUserConfig {
                 error: format!("invalid OTAP batch processor config: {e}"),
             })?;
 
@@ -214,6 +245,13 @@ impl OtapBatchProcessor {
             dirty_logs: false,
             dirty_metrics: false,
             dirty_traces: false,
+            bytes_logs: 0,
+            bytes_metrics: 0,
+            bytes_traces: 0,
+            oldest_logs: None,
+            oldest_metrics: None,
+            oldest_traces: None,
+            timer_started: false,
             metrics,
         })
     }
@@ -245,6 +283,24 @@ impl OtapBatchProcessor {
         self.flush_traces(effect, reason).await
     }
 
+    /// Returns true if a buffer of `bytes` would go beyond the configured byte limit.
+    fn exceeds_max_bytes(&self, bytes: usize) -> bool {
+        self.config.send_batch_max_bytes > 0 && bytes > self.config.send_batch_max_bytes
+    }
+
+    /// Returns true if a buffer of `bytes` has reached the configured byte limit.
+    fn reaches_max_bytes(&self, bytes: usize) -> bool {
+        self.config.send_batch_max_bytes > 0 && bytes >= self.config.send_batch_max_bytes
+    }
+
+    /// Returns true if the oldest buffered record has waited for at least the configured timeout.
+    fn timed_out(&self, oldest: Option<Instant>) -> bool {
+        match (self.config.timeout, oldest) {
+            (Some(timeout), Some(oldest)) => oldest.elapsed() >= timeout,
+            _ => false,
+        }
+    }
+
     fn inc_flushes(&mut self, reason: FlushReason) {
         match reason {
             FlushReason::Size => self.metrics.flushes_size.inc(),
@@ -355,6 +411,11 @@ impl OtapBatchProcessor {
                 }
             }
         }
+        // Whatever was re-buffered keeps its original age
+        self.bytes_logs = self.current_logs.iter().map(record_bytes).sum();
+        if self.current_logs.is_empty() {
+            self.oldest_logs = None;
+        }
         Ok(())
     }
 
@@ -450,6 +511,11 @@ impl OtapBatchProcessor {
                 }
             }
         }
+        // Whatever was re-buffered keeps its original age
+        self.bytes_metrics = self.current_metrics.iter().map(record_bytes).sum();
+        if self.current_metrics.is_empty() {
+            self.oldest_metrics = None;
+        }
         Ok(())
     }
 
@@ -545,6 +611,11 @@ impl OtapBatchProcessor {
                 }
             }
         }
+        // Whatever was re-buffered keeps its original age
+        self.bytes_traces = self.current_traces.iter().map(record_bytes).sum();
+        if self.current_traces.is_empty() {
+            self.oldest_traces = None;
+        }
         Ok(())
     }
 }
@@ -579,8 +650,11 @@ impl local::Processor<OtapPdata> for OtapBatchProcessor {
             Message::Control(ctrl) => {
                 match ctrl {
                     NodeControlMsg::TimerTick { .. } => {
-                        // Flush on timer only when thresholds were crossed and buffers are non-empty (unchanged behavior)
-                        if self.dirty_logs && !self.current_logs.is_empty() {
+                        // Flush on timer when thresholds were crossed or the oldest buffered record
+                        // has waited for the configured timeout, and buffers are non-empty
+                        if (self.dirty_logs || self.timed_out(self.oldest_logs))
+                            && !self.current_logs.is_empty()
+                        {
                             self.metrics.timer_flush_performed_logs.inc();
                             self.flush_logs(effect, FlushReason::Timer).await?;
                             self.dirty_logs = false;
@@ -588,7 +662,9 @@ impl local::Processor<OtapPdata> for OtapBatchProcessor {
                         } else {
                             self.metrics.timer_flush_skipped_logs.inc();
                         }
-                        if self.dirty_metrics && !self.current_metrics.is_empty() {
+                        if (self.dirty_metrics || self.timed_out(self.oldest_metrics))
+                            && !self.current_metrics.is_empty()
+                        {
                             self.metrics.timer_flush_performed_metrics.inc();
                             self.flush_metrics(effect, FlushReason::Timer).await?;
                             self.dirty_metrics = false;
@@ -596,7 +672,9 @@ impl local::Processor<OtapPdata> for OtapBatchProcessor {
                         } else {
                             self.metrics.timer_flush_skipped_metrics.inc();
                         }
-                        if self.dirty_traces && !self.current_traces.is_empty() {
+                        if (self.dirty_traces || self.timed_out(self.oldest_traces))
+                            && !self.current_traces.is_empty()
+                        {
                             self.metrics.timer_flush_performed_traces.inc();
                             self.flush_traces(effect, FlushReason::Timer).await?;
                             self.dirty_traces = false;
@@ -627,6 +705,13 @@ impl local::Processor<OtapPdata> for OtapBatchProcessor {
                 }
             }
             Message::PData(request) => {
+                if !self.timer_started {
+                    // Processors have no start hook, so the timeout timer starts on first data
+                    if let Some(timeout) = self.config.timeout {
+                        let _timer_cancel = effect.start_periodic_timer(timeout).await?;
+                    }
+                    self.timer_started = true;
+                }
                 let max = self.config.send_batch_max_size;
                 let signal_type = request.signal_type();
 
@@ -645,9 +730,13 @@ impl local::Processor<OtapPdata> for OtapBatchProcessor {
                                     Ok(())
                                 } else {
                                     self.metrics.consumed_items_logs.add(rows as u64);
-                                    // Pre-append: flush if the incoming record would exceed max.
-                                    if max > FOLLOW_SEND_BATCH_SIZE_SENTINEL
-                                        && self.rows_logs + rows > max
+                                    let bytes = record_bytes(&rec);
+                                    // Pre-append: flush if the incoming record would exceed max
+                                    // rows or max bytes.
+                                    if (max > FOLLOW_SEND_BATCH_SIZE_SENTINEL
+                                        && self.rows_logs + rows > max)
+                                        || (!self.current_logs.is_empty()
+                                            && self.exceeds_max_bytes(self.bytes_logs + bytes))
                                     {
                                         // Would exceed max: mark as threshold-crossed and flush current
                                         self.metrics.dirty_set_logs.inc();
@@ -655,11 +744,14 @@ impl local::Processor<OtapPdata> for OtapBatchProcessor {
                                         self.flush_logs(effect, FlushReason::Size).await?;
                                     }
                                     self.rows_logs += rows;
+                                    self.bytes_logs += bytes;
+                                    let _ = self.oldest_logs.get_or_insert_with(Instant::now);
                                     self.current_logs.push(rec);
                                     // Post-append: flush when we hit the boundary exactly (>= max),
                                     // and also honor send_batch_size as a trigger (0 => immediate).
                                     if (max > FOLLOW_SEND_BATCH_SIZE_SENTINEL
                                         && self.rows_logs >= max)
+                                        || self.reaches_max_bytes(self.bytes_logs)
                                         || matches!(self.config.send_batch_size, Some(s) if self.rows_logs >= s)
                                         || matches!(self.config.send_batch_size, Some(0))
                                     {
@@ -679,20 +771,27 @@ impl local::Processor<OtapPdata> for OtapBatchProcessor {
                                     Ok(())
                                 } else {
                                     self.metrics.consumed_items_metrics.add(rows as u64);
-                                    // Pre-append: flush if the incoming record would exceed max.
-                                    if max > FOLLOW_SEND_BATCH_SIZE_SENTINEL
-                                        && self.rows_metrics + rows > max
+                                    let bytes = record_bytes(&rec);
+                                    // Pre-append: flush if the incoming record would exceed max
+                                    // rows or max bytes.
+                                    if (max > FOLLOW_SEND_BATCH_SIZE_SENTINEL
+                                        && self.rows_metrics + rows > max)
+                                        || (!self.current_metrics.is_empty()
+                                            && self.exceeds_max_bytes(self.bytes_metrics + bytes))
                                     {
                                         self.metrics.dirty_set_metrics.inc();
                                         self.dirty_metrics = true;
                                         self.flush_metrics(effect, FlushReason::Size).await?;
                                     }
                                     self.rows_metrics += rows;
+                                    self.bytes_metrics += bytes;
+                                    let _ = self.oldest_metrics.get_or_insert_with(Instant::now);
                                     self.current_metrics.push(rec);
                                     // Post-append: flush on boundary equality (>= max) and
                                     // honor send_batch_size as a trigger (0 => immediate).
                                     if (max > FOLLOW_SEND_BATCH_SIZE_SENTINEL
                                         && self.rows_metrics >= max)
+                                        || self.reaches_max_bytes(self.bytes_metrics)
                                         || matches!(self.config.send_batch_size, Some(s) if self.rows_metrics >= s)
                                         || matches!(self.config.send_batch_size, Some(0))
                                     {
@@ -711,20 +810,27 @@ impl local::Processor<OtapPdata> for OtapBatchProcessor {
                                     Ok(())
                                 } else {
                                     self.metrics.consumed_items_traces.add(rows as u64);
-                                    // Pre-append: flush if the incoming record would exceed max.
-                                    if max > FOLLOW_SEND_BATCH_SIZE_SENTINEL
-                                        && self.rows_traces + rows > max
+                                    let bytes = record_bytes(&rec);
+                                    // Pre-append: flush if the incoming record would exceed max
+                                    // rows or max bytes.
+                                    if (max > FOLLOW_SEND_BATCH_SIZE_SENTINEL
+                                        && self.rows_traces + rows > max)
+                                        || (!self.current_traces.is_empty()
+                                            && self.exceeds_max_bytes(self.bytes_traces + bytes))
                                     {
                                         self.metrics.dirty_set_traces.inc();
                                         self.dirty_traces = true;
                                         self.flush_traces(effect, FlushReason::Size).await?;
                                     }
                                     self.rows_traces += rows;
+                                    self.bytes_traces += bytes;
+                                    let _ = self.oldest_traces.get_or_insert_with(Instant::now);
                                     self.current_traces.push(rec);
                                     // Post-append: flush on boundary equality (>= max) and
                                     // honor send_batch_size as a trigger (0 => immediate).
                                     if (max > FOLLOW_SEND_BATCH_SIZE_SENTINEL
                                         && self.rows_traces >= max)
+                                        || self.reaches_max_bytes(self.bytes_traces)
                                         || matches!(self.config.send_batch_size, Some(s) if self.rows_traces >= s)
                                         || matches!(self.config.send_batch_size, Some(0))
                                     {
@@ -776,6 +882,12 @@ pub static OTAP_BATCH_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPd
         },
     };
 
+/// Schema of the configuration of the OTAP batch processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static OTAP_BATCH_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(OTAP_BATCH_PROCESSOR_URN);
+
 #[cfg(test)]
 mod test_helpers {
     use super::*;
@@ -1534,6 +1646,87 @@ mod tests {
         validation.validate(|_vctx| async move {});
     }
 
+    #[test]
+    fn test_timer_flushes_once_timeout_elapsed() {
+        let cfg = json!({
+            "send_batch_size": 1000,
+            "send_batch_max_size": 1000,
+            "timeout": "5ms"
+        });
+        let processor_config = ProcessorConfig::new("otap_batch_test_timeout_age");
+        let test_rt = TestRuntime::new();
+        let node = test_node(processor_config.name.clone());
+        let proc = from_config(node, &cfg, &processor_config).expect("proc from config");
+
+        let phase = test_rt.set_processor(proc);
+
+        let validation = phase.run_test(|mut ctx| async move {
+            let pdata = OtapPdata::new_default(one_trace_record().into());
+            ctx.process(Message::PData(pdata)).await.expect("process 1");
+            assert!(ctx.drain_pdata().await.is_empty());
+
+            // The buffered record is below all thresholds but older than the timeout
+            ctx.sleep(Duration::from_millis(20)).await;
+            ctx.process(Message::Control(NodeControlMsg::TimerTick {}))
+                .await
+                .expect("timer tick");
+            let emitted = ctx.drain_pdata().await;
+            assert_eq!(emitted.len(), 1, "aged batch should flush on timer");
+
+            // Nothing left to flush
+            ctx.process(Message::Control(NodeControlMsg::TimerTick {}))
+                .await
+                .expect("timer tick");
+            assert!(ctx.drain_pdata().await.is_empty());
+        });
+
+        validation.validate(|_vctx| async move {});
+    }
+
+    #[test]
+    fn test_flush_on_max_bytes() {
+        let record_size = record_bytes(&one_trace_record());
+        let cfg = json!({
+            "send_batch_size": 1000,
+            "send_batch_max_bytes": record_size + 1,
+            "timeout": "200ms"
+        });
+        let processor_config = ProcessorConfig::new("otap_batch_test_max_bytes");
+        let test_rt = TestRuntime::new();
+        let node = test_node(processor_config.name.clone());
+        let proc = from_config(node, &cfg, &processor_config).expect("proc from config");
+
+        let phase = test_rt.set_processor(proc);
+
+        let validation = phase.run_test(|mut ctx| async move {
+            // First record fits within the byte limit
+            let pdata = OtapPdata::new_default(one_trace_record().into());
+            ctx.process(Message::PData(pdata)).await.expect("process 1");
+            assert!(ctx.drain_pdata().await.is_empty());
+
+            // Second record would exceed the byte limit: the first one is flushed before append
+            let pdata = OtapPdata::new_default(one_trace_record().into());
+            ctx.process(Message::PData(pdata)).await.expect("process 2");
+            let emitted = ctx.drain_pdata().await;
+            assert_eq!(
+                emitted.len(),
+                1,
+                "byte limit should flush the buffered record"
+            );
+
+            ctx.process(Message::Control(NodeControlMsg::Shutdown {
+                deadline: Instant::now().add(Duration::from_millis(TEST_SHUTDOWN_DEADLINE_MS)),
+                reason: TEST_SHUTDOWN_REASON.into(),
+            }))
+            .await
+            .expect("shutdown");
+            let emitted = ctx.drain_pdata().await;
+            assert_eq!(emitted.len(), 1, "remaining record flushed on shutdown");
+        });
+
+        validation.validate(|_vctx| async move {});
+    }
+
     #[test]
     fn test_batch_with_out_port() {
         let id = PipelineId::from("batch-with-out-port".to_string());
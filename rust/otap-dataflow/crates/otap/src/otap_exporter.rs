@@ -15,7 +15,9 @@ use async_trait::async_trait;
 use linkme::distributed_slice;
 use otap_df_config::experimental::SignalType;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
 use otap_df_engine::ExporterFactory;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::config::ExporterConfig;
 use otap_df_engine::context::PipelineContext;
 use otap_df_engine::control::NodeControlMsg;
@@ -75,6 +77,12 @@ pub static OTAP_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
     },
 };
 
+/// Schema of the configuration of the OTAP exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static OTAP_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(OTAP_EXPORTER_URN);
+
 impl OTAPExporter {
     /// Creates a new OTAPExporter
     #[must_use]
@@ -91,7 +99,7 @@ impl OTAPExporter {
         pipeline_ctx: PipelineContext,
         config: &Value,
     ) -> Result<Self, otap_df_config::error::Error> {
-        let config: Config = serde_json::from_value(config.clone()).map_err(|e| {
+        let config: Config = parse_user_config(config).map_err(|e| {
             otap_df_config::error::Error::InvalidUserConfig {
                 error: e.to_string(),
             }
@@ -930,4 +938,17 @@ mod tests {
             .block_on(server_handle)
             .expect("server shutdown success");
     }
+
+    #[test]
+    fn test_otap_exporter_conformance() {
+        use crate::testing::conformance::*;
+
+        // The OTAP exporter neither notifies subscribers nor drops data while its endpoint is
+        // unreachable, so the Ack/Nack and backpressure checks are not run.
+        let grpc_port = portpicker::pick_unused_port().expect("No free ports");
+        let config = json!({ "grpc_endpoint": format!("http://127.0.0.1:{grpc_port}") });
+        check_shutdown(&OTAP_EXPORTER, config.clone());
+        check_control_messages(&OTAP_EXPORTER, config.clone());
+        check_collect_telemetry(&OTAP_EXPORTER, config);
+    }
 }
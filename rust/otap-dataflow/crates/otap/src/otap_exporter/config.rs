@@ -4,10 +4,11 @@
 //! Configuration for the OTAP Exporter
 
 use crate::compression::CompressionMethod;
+use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer};
 
 /// Configuration for the OTAP Exporter
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     /// The grpc endpoint to which OTAP service requests will be sent
@@ -19,6 +20,7 @@ pub struct Config {
         default = "default_compression_method",
         deserialize_with = "deserialize_compression_method"
     )]
+    #[schemars(with = "Option<String>")]
     pub compression_method: Option<CompressionMethod>,
 
     /// Configuration for the arrow payloads
@@ -27,7 +29,7 @@ pub struct Config {
 }
 
 /// Configuration for the arrow payloads produced by the [`OtapExporter`]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ArrowConfig {
     /// Compression to use for IPC serialized payloads within the BatchArrowMessages
@@ -44,11 +46,12 @@ pub struct ArrowConfig {
         default = "default_arrow_payload_compression",
         deserialize_with = "deserialize_payload_compression"
     )]
+    #[schemars(with = "Option<String>")]
     pub payload_compression: Option<ArrowPayloadCompression>,
 }
 
 /// Compression options for arrow payloads
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ArrowPayloadCompression {
     /// Zstd compression
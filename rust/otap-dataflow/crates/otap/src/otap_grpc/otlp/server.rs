@@ -5,14 +5,15 @@
 //!
 //! The Pdata it produces contain the serialized protobuf messages. This means that we can
 //! use these servers to receive telemetry data and deserialize it lazily only if some pipeline
-//! requires it
+//! requires it. Alternatively, the servers can convert logs and traces to OTAP Arrow records as
+//! they are received (see [`Settings::convert_to_otap`]).
 
 use std::convert::Infallible;
 use std::sync::{Arc, Mutex};
 use std::task::Poll;
 
 use crate::accessory::slots::{Key as SlotKey, State as SlotsState};
-use crate::pdata::{Context, OtapPdata, OtlpProtoBytes};
+use crate::pdata::{Context, OtapPayload, OtapPdata, OtlpProtoBytes};
 use crate::proto::opentelemetry::collector::logs::v1::ExportLogsServiceResponse;
 use crate::proto::opentelemetry::collector::metrics::v1::ExportMetricsServiceResponse;
 use crate::proto::opentelemetry::collector::trace::v1::ExportTraceServiceResponse;
@@ -22,6 +23,7 @@ use otap_df_config::experimental::SignalType;
 use otap_df_engine::control::{CallData, NackMsg};
 use otap_df_engine::shared::receiver::EffectHandler;
 use otap_df_engine::{Interests, ProducerEffectHandlerExtension};
+use otel_arrow_rust::otap::OtapArrowRecords;
 use prost::Message;
 use prost::bytes::Buf;
 use tokio::sync::oneshot;
@@ -96,16 +98,23 @@ pub struct Settings {
     pub accept_compression_encodings: EnabledCompressionEncodings,
     /// Response compression used
     pub send_compression_encodings: EnabledCompressionEncodings,
+    /// Whether logs and traces requests are converted to OTAP Arrow records on receipt.
+    /// Metrics requests are forwarded as OTLP bytes until their conversion is supported.
+    pub convert_to_otap: bool,
 }
 
 /// Tonic `Codec` implementation that returns the bytes of the serialized message
 struct OtlpBytesCodec {
     signal: SignalType,
+    convert_to_otap: bool,
 }
 
 impl OtlpBytesCodec {
-    fn new(signal: SignalType) -> Self {
-        Self { signal }
+    fn new(signal: SignalType, convert_to_otap: bool) -> Self {
+        Self {
+            signal,
+            convert_to_otap,
+        }
     }
 }
 
@@ -121,7 +130,7 @@ impl Codec for OtlpBytesCodec {
     }
 
     fn decoder(&mut self) -> Self::Decoder {
-        OtlpBytesDecoder::new(self.signal)
+        OtlpBytesDecoder::new(self.signal, self.convert_to_otap)
     }
 }
 
@@ -168,11 +177,15 @@ impl Encoder for OtlpResponseEncoder {
 /// Tonic codec `Decoder` implementation that decodes OtapBatch from protobuf request bytes
 struct OtlpBytesDecoder {
     signal: SignalType,
+    convert_to_otap: bool,
 }
 
 impl OtlpBytesDecoder {
-    fn new(signal: SignalType) -> Self {
-        Self { signal }
+    fn new(signal: SignalType, convert_to_otap: bool) -> Self {
+        Self {
+            signal,
+            convert_to_otap,
+        }
     }
 }
 
@@ -189,7 +202,18 @@ impl Decoder for OtlpBytesDecoder {
             SignalType::Traces => OtlpProtoBytes::ExportTracesRequest(buf.to_vec()),
         };
         src.advance(buf.len());
-        Ok(Some(OtapPdata::new(Context::default(), result.into())))
+
+        let payload: OtapPayload = match self.signal {
+            SignalType::Logs | SignalType::Traces if self.convert_to_otap => {
+                OtapArrowRecords::try_from(result)
+                    .map_err(|e| {
+                        Status::invalid_argument(format!("cannot convert request to OTAP: {e}"))
+                    })?
+                    .into()
+            }
+            _ => result.into(),
+        };
+        Ok(Some(OtapPdata::new(Context::default(), payload)))
     }
 }
 
@@ -198,7 +222,7 @@ impl Decoder for OtlpBytesDecoder {
 /// each request instead of a Clone + Sync + Send trait binding that
 /// would require Arc<Mutex<_>>.
 fn new_grpc(signal: SignalType, settings: Settings) -> Grpc<OtlpBytesCodec> {
-    let codec = OtlpBytesCodec::new(signal);
+    let codec = OtlpBytesCodec::new(signal, settings.convert_to_otap);
     Grpc::new(codec).apply_compression_config(
         settings.accept_compression_encodings,
         settings.send_compression_encodings,
@@ -17,6 +17,8 @@ use crate::pdata::OtapPdata;
 use async_trait::async_trait;
 use linkme::distributed_slice;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::ReceiverFactory;
 use otap_df_engine::config::ReceiverConfig;
 use otap_df_engine::context::PipelineContext;
@@ -31,6 +33,7 @@ use otel_arrow_rust::proto::opentelemetry::arrow::v1::{
     arrow_metrics_service_server::ArrowMetricsServiceServer,
     arrow_traces_service_server::ArrowTracesServiceServer,
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json::Value;
 use std::net::SocketAddr;
@@ -42,7 +45,7 @@ use tonic_middleware::MiddlewareLayer;
 const OTAP_RECEIVER_URN: &str = "urn:otel:otap:receiver";
 
 /// Configuration for the OTAP Receiver
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     listening_addr: SocketAddr,
@@ -76,6 +79,12 @@ pub static OTAP_RECEIVER: ReceiverFactory<OtapPdata> = ReceiverFactory {
     },
 };
 
+/// Schema of the configuration of the OTAP receiver
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static OTAP_RECEIVER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(OTAP_RECEIVER_URN);
+
 impl OTAPReceiver {
     /// creates a new OTAP Receiver
     #[must_use]
@@ -98,7 +107,7 @@ impl OTAPReceiver {
         _pipeline: PipelineContext,
         config: &Value,
     ) -> Result<Self, otap_df_config::error::Error> {
-        let config: Config = serde_json::from_value(config.clone()).map_err(|e| {
+        let config: Config = parse_user_config(config).map_err(|e| {
             otap_df_config::error::Error::InvalidUserConfig {
                 error: e.to_string(),
             }
@@ -10,11 +10,13 @@ use async_trait::async_trait;
 use linkme::distributed_slice;
 use otap_df_config::experimental::SignalType;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
 use otap_df_engine::ConsumerEffectHandlerExtension;
 use otap_df_engine::ExporterFactory;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::config::ExporterConfig;
 use otap_df_engine::context::PipelineContext;
-use otap_df_engine::control::{AckMsg, NackMsg, NodeControlMsg};
+use otap_df_engine::control::{AckMsg, NackClass, NackMsg, NodeControlMsg};
 use otap_df_engine::error::{Error, ExporterErrorKind, format_error_sources};
 use otap_df_engine::exporter::ExporterWrapper;
 use otap_df_engine::local::exporter::{EffectHandler, Exporter};
@@ -26,6 +28,7 @@ use otel_arrow_rust::otlp::logs::LogsProtoBytesEncoder;
 use otel_arrow_rust::otlp::metrics::MetricsProtoBytesEncoder;
 use otel_arrow_rust::otlp::traces::TracesProtoBytesEncoder;
 use otel_arrow_rust::otlp::{ProtoBuffer, ProtoBytesEncoder};
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::sync::Arc;
 use std::time::Duration;
@@ -35,13 +38,75 @@ use tonic::transport::Channel;
 pub const OTLP_EXPORTER_URN: &str = "urn:otel:otlp:exporter";
 
 /// Configuration for the OTLP Exporter
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     /// The gRPC endpoint to connect to
     pub grpc_endpoint: String,
     /// The compression method to use for the gRPC connection
     pub compression_method: Option<CompressionMethod>,
+    /// How each class of export failure is handled
+    #[serde(default)]
+    pub failure_policy: FailurePolicy,
+}
+
+/// Action taken when an export fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureAction {
+    /// Nack the data so that an upstream retry processor may retry it.
+    #[default]
+    Retry,
+    /// Nack the data as a permanent failure, which is not retried.
+    Reject,
+    /// Nack the data as a permanent failure and stop the exporter, which shuts down the
+    /// pipeline.
+    HaltPipeline,
+}
+
+/// Action taken for each class of export failure, derived from the gRPC status code returned by
+/// the server. Every class defaults to [`FailureAction::Retry`].
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FailurePolicy {
+    /// The server is throttling (`RESOURCE_EXHAUSTED`).
+    #[serde(default)]
+    pub on_throttle: FailureAction,
+    /// The server refused the data as malformed (`INVALID_ARGUMENT`).
+    #[serde(default)]
+    pub on_schema_reject: FailureAction,
+    /// The credentials were missing or refused (`UNAUTHENTICATED`, `PERMISSION_DENIED`).
+    #[serde(default)]
+    pub on_auth_failure: FailureAction,
+    /// Any other failure, including an unreachable endpoint.
+    #[serde(default)]
+    pub on_other: FailureAction,
+}
+
+impl FailurePolicy {
+    /// Returns the action configured for a failed export with the given status code.
+    #[must_use]
+    pub fn action(&self, code: tonic::Code) -> FailureAction {
+        match code {
+            tonic::Code::ResourceExhausted => self.on_throttle,
+            tonic::Code::InvalidArgument => self.on_schema_reject,
+            tonic::Code::Unauthenticated | tonic::Code::PermissionDenied => self.on_auth_failure,
+            _ => self.on_other,
+        }
+    }
+}
+
+/// Returns the class of the Nack of a failed export with the given status code.
+const fn nack_class(code: tonic::Code) -> NackClass {
+    match code {
+        tonic::Code::ResourceExhausted => NackClass::Throttled,
+        tonic::Code::InvalidArgument => NackClass::InvalidData,
+        tonic::Code::Unauthenticated | tonic::Code::PermissionDenied => NackClass::Unauthorized,
+        tonic::Code::Unavailable => NackClass::Unavailable,
+        tonic::Code::DeadlineExceeded => NackClass::DeadlineExceeded,
+        tonic::Code::Internal => NackClass::Internal,
+        _ => NackClass::Unknown,
+    }
 }
 
 /// Exporter that sends OTLP data via gRPC
@@ -68,6 +133,12 @@ pub static OTLP_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
     },
 };
 
+/// Schema of the configuration of the OTLP exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static OTLP_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(OTLP_EXPORTER_URN);
+
 impl OTLPExporter {
     /// create a new instance of the `[OTLPExporter]` from json config value
     pub fn from_config(
@@ -76,7 +147,7 @@ impl OTLPExporter {
     ) -> Result<Self, otap_df_config::error::Error> {
         let pdata_metrics = pipeline_ctx.register_metrics::<ExporterPDataMetrics>();
 
-        let config: Config = serde_json::from_value(config.clone()).map_err(|e| {
+        let config: Config = parse_user_config(config).map_err(|e| {
             otap_df_config::error::Error::InvalidUserConfig {
                 error: e.to_string(),
             }
@@ -164,98 +235,90 @@ impl Exporter<OtapPdata> for OTLPExporter {
                     let (context, payload) = pdata.into_parts();
                     self.pdata_metrics.inc_consumed(signal_type);
 
-                    match (signal_type, payload) {
+                    let policy = &self.config.failure_policy;
+                    let result = match (signal_type, payload) {
                         // use optimized direct encoding OTAP -> OTLP bytes directly
                         (SignalType::Logs, OtapPayload::OtapArrowRecords(otap_batch)) => {
-                            match handle_otap_export(
+                            handle_otap_export(
                                 otap_batch,
                                 context,
                                 &mut proto_buffer,
                                 &mut logs_encoder,
                                 &mut logs_client,
                                 &effect_handler,
+                                policy,
                             )
                             .await
-                            {
-                                Ok(()) => self.pdata_metrics.logs_exported.inc(),
-                                _ => self.pdata_metrics.logs_failed.inc(),
-                            }
                         }
                         (SignalType::Metrics, OtapPayload::OtapArrowRecords(otap_batch)) => {
-                            match handle_otap_export(
+                            handle_otap_export(
                                 otap_batch,
                                 context,
                                 &mut proto_buffer,
                                 &mut metrics_encoder,
                                 &mut metrics_client,
                                 &effect_handler,
+                                policy,
                             )
                             .await
-                            {
-                                Ok(()) => self.pdata_metrics.metrics_exported.inc(),
-                                _ => self.pdata_metrics.metrics_failed.inc(),
-                            }
                         }
                         (SignalType::Traces, OtapPayload::OtapArrowRecords(otap_batch)) => {
-                            match handle_otap_export(
+                            handle_otap_export(
                                 otap_batch,
                                 context,
                                 &mut proto_buffer,
                                 &mut traces_encoder,
                                 &mut trace_client,
                                 &effect_handler,
+                                policy,
                             )
                             .await
-                            {
-                                Ok(()) => self.pdata_metrics.traces_exported.inc(),
-                                _ => self.pdata_metrics.traces_failed.inc(),
-                            }
                         }
-                        (_, OtapPayload::OtlpBytes(service_req)) => {
-                            _ = match service_req {
-                                OtlpProtoBytes::ExportLogsRequest(bytes) => {
-                                    match handle_otlp_export(
-                                        bytes,
-                                        context,
-                                        &mut logs_client,
-                                        &effect_handler,
-                                        |b| OtlpProtoBytes::ExportLogsRequest(b.to_vec()).into(),
-                                    )
-                                    .await
-                                    {
-                                        Ok(()) => self.pdata_metrics.logs_exported.inc(),
-                                        _ => self.pdata_metrics.logs_failed.inc(),
-                                    }
-                                }
-                                OtlpProtoBytes::ExportMetricsRequest(bytes) => {
-                                    match handle_otlp_export(
-                                        bytes,
-                                        context,
-                                        &mut metrics_client,
-                                        &effect_handler,
-                                        |b| OtlpProtoBytes::ExportMetricsRequest(b.to_vec()).into(),
-                                    )
-                                    .await
-                                    {
-                                        Ok(()) => self.pdata_metrics.metrics_exported.inc(),
-                                        _ => self.pdata_metrics.metrics_failed.inc(),
-                                    }
-                                }
-                                OtlpProtoBytes::ExportTracesRequest(bytes) => {
-                                    match handle_otlp_export(
-                                        bytes,
-                                        context,
-                                        &mut trace_client,
-                                        &effect_handler,
-                                        |b| OtlpProtoBytes::ExportTracesRequest(b.to_vec()).into(),
-                                    )
-                                    .await
-                                    {
-                                        Ok(()) => self.pdata_metrics.traces_exported.inc(),
-                                        _ => self.pdata_metrics.traces_failed.inc(),
-                                    }
-                                }
-                            };
+                        (_, OtapPayload::OtlpBytes(service_req)) => match service_req {
+                            OtlpProtoBytes::ExportLogsRequest(bytes) => {
+                                handle_otlp_export(
+                                    bytes,
+                                    context,
+                                    &mut logs_client,
+                                    &effect_handler,
+                                    policy,
+                                    |b| OtlpProtoBytes::ExportLogsRequest(b.to_vec()).into(),
+                                )
+                                .await
+                            }
+                            OtlpProtoBytes::ExportMetricsRequest(bytes) => {
+                                handle_otlp_export(
+                                    bytes,
+                                    context,
+                                    &mut metrics_client,
+                                    &effect_handler,
+                                    policy,
+                                    |b| OtlpProtoBytes::ExportMetricsRequest(b.to_vec()).into(),
+                                )
+                                .await
+                            }
+                            OtlpProtoBytes::ExportTracesRequest(bytes) => {
+                                handle_otlp_export(
+                                    bytes,
+                                    context,
+                                    &mut trace_client,
+                                    &effect_handler,
+                                    policy,
+                                    |b| OtlpProtoBytes::ExportTracesRequest(b.to_vec()).into(),
+                                )
+                                .await
+                            }
+                        },
+                    };
+
+                    match result {
+                        Ok(()) => self.pdata_metrics.inc_exported(signal_type),
+                        Err(failure) => {
+                            self.pdata_metrics.inc_failed(signal_type);
+                            if failure.halt {
+                                _ = timer_cancel_handle.cancel().await;
+                                return Err(failure.error);
+                            }
                         }
                     }
                 }
@@ -267,13 +330,27 @@ impl Exporter<OtapPdata> for OTLPExporter {
     }
 }
 
+/// A failed export. The Nack has already been delivered.
+struct ExportFailure {
+    error: Error,
+    /// The failure policy requires the exporter to stop.
+    halt: bool,
+}
+
+impl From<Error> for ExportFailure {
+    fn from(error: Error) -> Self {
+        Self { error, halt: false }
+    }
+}
+
 /// Helper function to handle export result and send Ack/Nack accordingly.
 async fn handle_export_result<T>(
     result: Result<T, tonic::Status>,
     context: Context,
     saved_payload: OtapPayload,
     effect_handler: &EffectHandler<OtapPdata>,
-) -> Result<(), Error> {
+    policy: &FailurePolicy,
+) -> Result<(), ExportFailure> {
     match result {
         Ok(_) => {
             effect_handler
@@ -282,19 +359,26 @@ async fn handle_export_result<T>(
             Ok(())
         }
         Err(e) => {
+            let action = policy.action(e.code());
             let error_msg = e.to_string();
-            effect_handler
-                .notify_nack(NackMsg::new(
-                    &error_msg,
-                    OtapPdata::new(context, saved_payload),
-                ))
-                .await?;
+            let refused = OtapPdata::new(context, saved_payload);
+            let nack = match action {
+                FailureAction::Retry => NackMsg::new(&error_msg, refused),
+                FailureAction::Reject | FailureAction::HaltPipeline => {
+                    NackMsg::new_permanent(&error_msg, refused)
+                }
+            }
+            .with_class(nack_class(e.code()));
+            effect_handler.notify_nack(nack).await?;
             let source_detail = format_error_sources(&e);
-            Err(Error::ExporterError {
-                exporter: effect_handler.exporter_id(),
-                kind: ExporterErrorKind::Transport,
-                error: error_msg,
-                source_detail,
+            Err(ExportFailure {
+                error: Error::ExporterError {
+                    exporter: effect_handler.exporter_id(),
+                    kind: ExporterErrorKind::Transport,
+                    error: error_msg,
+                    source_detail,
+                },
+                halt: action == FailureAction::HaltPipeline,
             })
         }
     }
@@ -309,7 +393,8 @@ async fn handle_otap_export<Enc: ProtoBytesEncoder, T2, Resp, S>(
     encoder: &mut Enc,
     client: &mut crate::otap_grpc::otlp::client::OtlpServiceClient<T2, Resp, S>,
     effect_handler: &EffectHandler<OtapPdata>,
-) -> Result<(), Error>
+    policy: &FailurePolicy,
+) -> Result<(), ExportFailure>
 where
     T2: tonic::client::GrpcService<tonic::body::Body>,
     T2::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
@@ -339,7 +424,7 @@ where
 
     // Export and handle result with Ack/Nack
     let result = client.export(bytes).await;
-    handle_export_result(result, context, saved_payload, effect_handler).await
+    handle_export_result(result, context, saved_payload, effect_handler, policy).await
 }
 
 /// Generic function for exporting OTLP bytes via gRPC and handling Ack/Nack delivery.
@@ -348,8 +433,9 @@ async fn handle_otlp_export<T2, Resp, S>(
     context: Context,
     client: &mut crate::otap_grpc::otlp::client::OtlpServiceClient<T2, Resp, S>,
     effect_handler: &EffectHandler<OtapPdata>,
+    policy: &FailurePolicy,
     save_payload_fn: impl FnOnce(&[u8]) -> OtapPayload,
-) -> Result<(), Error>
+) -> Result<(), ExportFailure>
 where
     T2: tonic::client::GrpcService<tonic::body::Body>,
     T2::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
@@ -366,7 +452,7 @@ where
     };
 
     let result = client.export(bytes).await;
-    handle_export_result(result, context, saved_payload, effect_handler).await
+    handle_export_result(result, context, saved_payload, effect_handler, policy).await
 }
 
 #[cfg(test)]
@@ -399,6 +485,7 @@ mod tests {
     use otap_df_telemetry::registry::MetricsRegistryHandle;
     use otap_df_telemetry::reporter::MetricsReporter;
     use prost::Message;
+    use serde_json::json;
     use std::net::SocketAddr;
     use std::time::Instant;
     use tokio::net::TcpListener;
@@ -911,4 +998,93 @@ mod tests {
             .block_on(server_handle)
             .expect("server shutdown success");
     }
+
+    #[test]
+    fn test_failure_policy_config() {
+        let config: Config =
+            serde_json::from_value(json!({ "grpc_endpoint": "http://localhost:4317" })).unwrap();
+        assert_eq!(
+            config.failure_policy.action(tonic::Code::ResourceExhausted),
+            FailureAction::Retry
+        );
+        assert_eq!(
+            config.failure_policy.action(tonic::Code::Unavailable),
+            FailureAction::Retry
+        );
+
+        let config: Config = serde_json::from_value(json!({
+            "grpc_endpoint": "http://localhost:4317",
+            "failure_policy": {
+                "on_schema_reject": "reject",
+                "on_auth_failure": "halt_pipeline",
+            },
+        }))
+        .unwrap();
+        let policy = &config.failure_policy;
+        assert_eq!(
+            policy.action(tonic::Code::ResourceExhausted),
+            FailureAction::Retry
+        );
+        assert_eq!(
+            policy.action(tonic::Code::InvalidArgument),
+            FailureAction::Reject
+        );
+        assert_eq!(
+            policy.action(tonic::Code::Unauthenticated),
+            FailureAction::HaltPipeline
+        );
+        assert_eq!(
+            policy.action(tonic::Code::PermissionDenied),
+            FailureAction::HaltPipeline
+        );
+        assert_eq!(
+            policy.action(tonic::Code::Unavailable),
+            FailureAction::Retry
+        );
+
+        let err = serde_json::from_value::<Config>(json!({
+            "grpc_endpoint": "http://localhost:4317",
+            "failure_policy": { "on_throttle": "dead_letter" },
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown variant"));
+
+        let err = (OTLP_EXPORTER_CONFIG_SCHEMA.validate)(&json!({
+            "grpc_endpoint": "http://localhost:4317",
+            "failure_policy": { "on_throttle": "dead_letter" },
+        }))
+        .unwrap_err();
+        assert_eq!(err.path, "failure_policy.on_throttle");
+        let schema = (OTLP_EXPORTER_CONFIG_SCHEMA.schema)();
+        assert_eq!(schema["required"], json!(["grpc_endpoint"]));
+    }
+
+    #[test]
+    fn test_nack_class() {
+        assert_eq!(
+            nack_class(tonic::Code::ResourceExhausted),
+            NackClass::Throttled
+        );
+        assert_eq!(
+            nack_class(tonic::Code::InvalidArgument),
+            NackClass::InvalidData
+        );
+        assert_eq!(
+            nack_class(tonic::Code::PermissionDenied),
+            NackClass::Unauthorized
+        );
+        assert_eq!(nack_class(tonic::Code::Unavailable), NackClass::Unavailable);
+        assert_eq!(nack_class(tonic::Code::Cancelled), NackClass::Unknown);
+    }
+
+    #[test]
+    fn test_otlp_exporter_conformance() {
+        // nothing listens on the endpoint, so every export is expected to be Nack'ed
+        let grpc_port = portpicker::pick_unused_port().expect("No free ports");
+        crate::testing::conformance::run_exporter_conformance(
+            &OTLP_EXPORTER,
+            json!({ "grpc_endpoint": format!("http://127.0.0.1:{grpc_port}") }),
+            Some(Interests::NACKS),
+        );
+    }
 }
@@ -0,0 +1,546 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Receiver accepting OTLP export requests over HTTP.
+//!
+//! Requests are posted to `/v1/logs`, `/v1/traces` or `/v1/metrics`, encoded either as
+//! protobuf (`application/x-protobuf`) or as OTLP/JSON (`application/json`), and optionally
+//! gzip compressed (`Content-Encoding: gzip`). JSON payloads are re-encoded as protobuf so that
+//! downstream nodes always receive OTLP bytes.
+
+use crate::OTAP_RECEIVER_FACTORIES;
+use crate::otlp_json;
+use crate::pdata::{Context, OtapPdata, OtlpProtoBytes};
+use crate::proto::opentelemetry::collector::logs::v1::ExportLogsServiceResponse;
+use crate::proto::opentelemetry::collector::metrics::v1::ExportMetricsServiceResponse;
+use crate::proto::opentelemetry::collector::trace::v1::ExportTraceServiceResponse;
+use async_trait::async_trait;
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use flate2::read::GzDecoder;
+use linkme::distributed_slice;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::ReceiverFactory;
+use otap_df_engine::config::ReceiverConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::NodeControlMsg;
+use otap_df_engine::error::{Error, ReceiverErrorKind, format_error_sources};
+use otap_df_engine::node::NodeId;
+use otap_df_engine::receiver::ReceiverWrapper;
+use otap_df_engine::shared::receiver as shared;
+use otap_df_engine::terminal_state::TerminalState;
+use prost::Message;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// URN for the OTLP/HTTP Receiver
+pub const OTLP_HTTP_RECEIVER_URN: &str = "urn:otel:otlp_http:receiver";
+
+const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Configuration for OTLP/HTTP Receiver
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The address the HTTP server listens on.
+    listening_addr: SocketAddr,
+
+    /// Maximum size in bytes of a request body, after decompression (default: 20 MiB)
+    ///
+    /// Larger requests are refused with `413 Payload Too Large`.
+    #[serde(default = "default_max_request_body_size")]
+    max_request_body_size: usize,
+}
+
+const fn default_max_request_body_size() -> usize {
+    20 * 1024 * 1024
+}
+
+/// Receiver implementation that receives OTLP/HTTP requests and forwards them as OTLP bytes.
+pub struct OtlpHttpReceiver {
+    config: Config,
+}
+
+/// Declares the OTLP/HTTP receiver as a shared receiver factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_RECEIVER_FACTORIES)]
+pub static OTLP_HTTP_RECEIVER: ReceiverFactory<OtapPdata> = ReceiverFactory {
+    name: OTLP_HTTP_RECEIVER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             receiver_config: &ReceiverConfig| {
+        Ok(ReceiverWrapper::shared(
+            OtlpHttpReceiver::from_config(pipeline, &node_config.config)?,
+            node,
+            node_config,
+            receiver_config,
+        ))
+    },
+};
+
+/// Schema of the configuration of the OTLP/HTTP receiver
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static OTLP_HTTP_RECEIVER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(OTLP_HTTP_RECEIVER_URN);
+
+impl OtlpHttpReceiver {
+    /// Creates a new OtlpHttpReceiver from a configuration object
+    pub fn from_config(
+        _pipeline_ctx: PipelineContext,
+        config: &Value,
+    ) -> Result<Self, otap_df_config::error::Error> {
+        let config: Config = parse_user_config(config).map_err(|e| {
+            otap_df_config::error::Error::InvalidUserConfig {
+                error: e.to_string(),
+            }
+        })?;
+        Ok(Self { config })
+    }
+}
+
+/// State shared by the HTTP request handlers.
+#[derive(Clone)]
+struct HttpState {
+    effect_handler: shared::EffectHandler<OtapPdata>,
+    max_request_body_size: usize,
+}
+
+/// Payload encodings accepted by the receiver.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Encoding {
+    Protobuf,
+    Json,
+}
+
+impl Encoding {
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+        // ignore parameters such as `; charset=utf-8`
+        let mime = content_type.split(';').next()?.trim();
+        if mime.eq_ignore_ascii_case(PROTOBUF_CONTENT_TYPE) {
+            Some(Self::Protobuf)
+        } else if mime.eq_ignore_ascii_case(JSON_CONTENT_TYPE) {
+            Some(Self::Json)
+        } else {
+            None
+        }
+    }
+
+    const fn content_type(self) -> &'static str {
+        match self {
+            Self::Protobuf => PROTOBUF_CONTENT_TYPE,
+            Self::Json => JSON_CONTENT_TYPE,
+        }
+    }
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, message.into()).into_response()
+}
+
+/// Returns the request body, decompressed according to its `Content-Encoding`.
+fn decode_body(headers: &HeaderMap, body: Bytes, limit: usize) -> Result<Vec<u8>, Response> {
+    let content_encoding = headers.get(header::CONTENT_ENCODING).map(|value| {
+        value
+            .to_str()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+    });
+    match content_encoding.as_deref() {
+        None | Some("") | Some("identity") => Ok(body.into()),
+        Some("gzip") => {
+            let mut decompressed = Vec::new();
+            // read one byte past the limit to detect oversized payloads
+            let _ = GzDecoder::new(&body[..])
+                .take(limit as u64 + 1)
+                .read_to_end(&mut decompressed)
+                .map_err(|e| {
+                    error_response(
+                        StatusCode::BAD_REQUEST,
+                        format!("invalid gzip payload: {e}"),
+                    )
+                })?;
+            if decompressed.len() > limit {
+                return Err(error_response(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    "decompressed request body exceeds the maximum size",
+                ));
+            }
+            Ok(decompressed)
+        }
+        Some(other) => Err(error_response(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("unsupported content encoding: {other}"),
+        )),
+    }
+}
+
+/// Converts a request body into OTLP protobuf bytes for the given signal.
+fn to_otlp_bytes(
+    signal: SignalType,
+    encoding: Encoding,
+    body: Vec<u8>,
+) -> Result<OtlpProtoBytes, otlp_json::Error> {
+    let bytes = match encoding {
+        Encoding::Protobuf => body,
+        Encoding::Json => match signal {
+            SignalType::Logs => otlp_json::logs_request_to_proto(&body)?,
+            SignalType::Traces => otlp_json::traces_request_to_proto(&body)?,
+            SignalType::Metrics => otlp_json::metrics_request_to_proto(&body)?,
+        },
+    };
+    Ok(match signal {
+        SignalType::Logs => OtlpProtoBytes::ExportLogsRequest(bytes),
+        SignalType::Traces => OtlpProtoBytes::ExportTracesRequest(bytes),
+        SignalType::Metrics => OtlpProtoBytes::ExportMetricsRequest(bytes),
+    })
+}
+
+/// Builds the (empty) export response for the given signal in the request encoding.
+fn success_response(signal: SignalType, encoding: Encoding) -> Response {
+    let body = match encoding {
+        Encoding::Protobuf => match signal {
+            SignalType::Logs => ExportLogsServiceResponse::default().encode_to_vec(),
+            SignalType::Traces => ExportTraceServiceResponse::default().encode_to_vec(),
+            SignalType::Metrics => ExportMetricsServiceResponse::default().encode_to_vec(),
+        },
+        Encoding::Json => b"{}".to_vec(),
+    };
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, encoding.content_type())],
+        body,
+    )
+        .into_response()
+}
+
+async fn export(signal: SignalType, state: HttpState, headers: HeaderMap, body: Bytes) -> Response {
+    let Some(encoding) = Encoding::from_headers(&headers) else {
+        return error_response(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("content type must be {PROTOBUF_CONTENT_TYPE} or {JSON_CONTENT_TYPE}"),
+        );
+    };
+    let body = match decode_body(&headers, body, state.max_request_body_size) {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    let payload = match to_otlp_bytes(signal, encoding, body) {
+        Ok(payload) => payload,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+
+    match state
+        .effect_handler
+        .send_message(OtapPdata::new(Context::default(), payload.into()))
+        .await
+    {
+        Ok(()) => success_response(signal, encoding),
+        Err(e) => error_response(StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+    }
+}
+
+fn router(state: HttpState) -> Router {
+    let limit = state.max_request_body_size;
+    Router::new()
+        .route(
+            "/v1/logs",
+            post(
+                |State(state): State<HttpState>, headers: HeaderMap, body: Bytes| {
+                    export(SignalType::Logs, state, headers, body)
+                },
+            ),
+        )
+        .route(
+            "/v1/traces",
+            post(
+                |State(state): State<HttpState>, headers: HeaderMap, body: Bytes| {
+                    export(SignalType::Traces, state, headers, body)
+                },
+            ),
+        )
+        .route(
+            "/v1/metrics",
+            post(
+                |State(state): State<HttpState>, headers: HeaderMap, body: Bytes| {
+                    export(SignalType::Metrics, state, headers, body)
+                },
+            ),
+        )
+        .layer(DefaultBodyLimit::max(limit))
+        .with_state(state)
+}
+
+#[async_trait]
+impl shared::Receiver<OtapPdata> for OtlpHttpReceiver {
+    async fn start(
+        self: Box<Self>,
+        mut ctrl_msg_recv: shared::ControlChannel<OtapPdata>,
+        effect_handler: shared::EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        let listener = effect_handler.tcp_listener(self.config.listening_addr)?;
+        let app = router(HttpState {
+            effect_handler: effect_handler.clone(),
+            max_request_body_size: self.config.max_request_body_size,
+        });
+
+        tokio::select! {
+            biased;
+
+            // Process internal events
+            ctrl_msg_result = async {
+                loop {
+                    match ctrl_msg_recv.recv().await {
+                        Ok(NodeControlMsg::Shutdown { .. }) => {
+                            return Ok(TerminalState::default());
+                        },
+                        Err(e) => {
+                            return Err(Error::ChannelRecvError(e));
+                        }
+                        _ => {
+                            // unknown control message do nothing
+                        }
+                    }
+                }
+            } => {
+                ctrl_msg_result
+            },
+
+            // Run server
+            result = axum::serve(listener, app) => {
+                match result {
+                    Ok(()) => Ok(TerminalState::default()),
+                    Err(error) => {
+                        let source_detail = format_error_sources(&error);
+                        Err(Error::ReceiverError {
+                            receiver: effect_handler.receiver_id(),
+                            kind: ReceiverErrorKind::Transport,
+                            error: error.to_string(),
+                            source_detail,
+                        })
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::proto::opentelemetry::collector::logs::v1::ExportLogsServiceRequest;
+    use crate::proto::opentelemetry::common::v1::{AnyValue, any_value};
+    use crate::proto::opentelemetry::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::{
+        receiver::{NotSendValidateContext, TestContext, TestRuntime},
+        test_node,
+    };
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use serde_json::json;
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio::time::timeout;
+
+    fn create_logs_service_request() -> ExportLogsServiceRequest {
+        ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                scope_logs: vec![ScopeLogs {
+                    log_records: vec![LogRecord {
+                        time_unix_nano: 1,
+                        body: Some(AnyValue {
+                            value: Some(any_value::Value::StringValue("hello".into())),
+                        }),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    /// Sends a minimal HTTP/1.1 request and returns the response status code.
+    async fn post(addr: SocketAddr, path: &str, headers: &[(&str, &str)], body: &[u8]) -> u16 {
+        let mut stream = TcpStream::connect(addr).await.expect("can connect");
+        let mut request = format!(
+            "POST {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\nContent-Length: {}\r\n",
+            body.len()
+        );
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        stream.write_all(body).await.unwrap();
+
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response).await.unwrap();
+        let status_line = String::from_utf8_lossy(&response);
+        status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .expect("valid status line")
+    }
+
+    fn create_receiver(
+        test_runtime: &TestRuntime<OtapPdata>,
+        addr: SocketAddr,
+    ) -> ReceiverWrapper<OtapPdata> {
+        let node_config = Arc::new(NodeUserConfig::new_receiver_config(OTLP_HTTP_RECEIVER_URN));
+        let controller_ctx = ControllerContext::new(MetricsRegistryHandle::new());
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        ReceiverWrapper::shared(
+            OtlpHttpReceiver::from_config(
+                pipeline_ctx,
+                &json!({ "listening_addr": addr, "max_request_body_size": 1024 }),
+            )
+            .unwrap(),
+            test_node(test_runtime.config().name.clone()),
+            node_config,
+            test_runtime.config(),
+        )
+    }
+
+    #[test]
+    fn test_config() {
+        let controller_ctx = ControllerContext::new(MetricsRegistryHandle::new());
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+
+        let receiver = OtlpHttpReceiver::from_config(
+            pipeline_ctx.clone(),
+            &json!({ "listening_addr": "127.0.0.1:4318" }),
+        )
+        .unwrap();
+        assert_eq!(
+            receiver.config.max_request_body_size,
+            default_max_request_body_size()
+        );
+
+        assert!(
+            OtlpHttpReceiver::from_config(
+                pipeline_ctx,
+                &json!({ "listening_addr": "127.0.0.1:4318", "unknown": 1 }),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_encoding_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(Encoding::from_headers(&headers), None);
+        let _ = headers.insert(
+            header::CONTENT_TYPE,
+            "application/json; charset=utf-8".parse().unwrap(),
+        );
+        assert_eq!(Encoding::from_headers(&headers), Some(Encoding::Json));
+        let _ = headers.insert(
+            header::CONTENT_TYPE,
+            "application/x-protobuf".parse().unwrap(),
+        );
+        assert_eq!(Encoding::from_headers(&headers), Some(Encoding::Protobuf));
+        let _ = headers.insert(header::CONTENT_TYPE, "text/plain".parse().unwrap());
+        assert_eq!(Encoding::from_headers(&headers), None);
+    }
+
+    #[test]
+    fn test_otlp_http_receiver() {
+        let test_runtime = TestRuntime::new();
+        let port = portpicker::pick_unused_port().expect("No free ports");
+        let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+        let receiver = create_receiver(&test_runtime, addr);
+
+        let scenario = move |ctx: TestContext<OtapPdata>| {
+            Box::pin(async move {
+                let protobuf = create_logs_service_request().encode_to_vec();
+                let protobuf_headers = [("Content-Type", PROTOBUF_CONTENT_TYPE)];
+                assert_eq!(
+                    post(addr, "/v1/logs", &protobuf_headers, &protobuf).await,
+                    200
+                );
+
+                let json = json!({
+                    "resourceLogs": [{ "scopeLogs": [{ "logRecords": [{
+                        "timeUnixNano": "1",
+                        "body": { "stringValue": "hello" }
+                    }] }] }]
+                })
+                .to_string();
+                let json_headers = [("Content-Type", JSON_CONTENT_TYPE)];
+                assert_eq!(
+                    post(addr, "/v1/logs", &json_headers, json.as_bytes()).await,
+                    200
+                );
+
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&protobuf).unwrap();
+                let gzipped = encoder.finish().unwrap();
+                let gzip_headers = [
+                    ("Content-Type", PROTOBUF_CONTENT_TYPE),
+                    ("Content-Encoding", "gzip"),
+                ];
+                assert_eq!(post(addr, "/v1/logs", &gzip_headers, &gzipped).await, 200);
+
+                // refused requests
+                let text_headers = [("Content-Type", "text/plain")];
+                assert_eq!(post(addr, "/v1/logs", &text_headers, b"hi").await, 415);
+                assert_eq!(post(addr, "/v1/logs", &json_headers, b"{").await, 400);
+                assert_eq!(
+                    post(addr, "/v1/logs", &protobuf_headers, &[0u8; 2048]).await,
+                    413
+                );
+
+                ctx.send_shutdown(Instant::now(), "Test")
+                    .await
+                    .expect("Failed to send Shutdown");
+            }) as std::pin::Pin<Box<dyn Future<Output = ()>>>
+        };
+
+        let validation = |mut ctx: NotSendValidateContext<OtapPdata>| {
+            Box::pin(async move {
+                let expected = create_logs_service_request().encode_to_vec();
+                for _ in 0..3 {
+                    let pdata = timeout(Duration::from_secs(3), ctx.recv())
+                        .await
+                        .expect("Timed out waiting for logs message")
+                        .expect("No logs message received");
+                    let proto: OtlpProtoBytes = pdata
+                        .payload()
+                        .try_into()
+                        .expect("can convert to OtlpProtoBytes");
+                    assert!(matches!(proto, OtlpProtoBytes::ExportLogsRequest(_)));
+                    assert_eq!(proto.as_bytes(), expected.as_slice());
+                }
+            }) as std::pin::Pin<Box<dyn Future<Output = ()>>>
+        };
+
+        test_runtime
+            .set_receiver(receiver)
+            .run_test(scenario)
+            .run_validation_concurrent(validation);
+    }
+}
@@ -0,0 +1,29 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversion between OTLP protobuf export requests and their [OTLP/JSON] encoding.
+//!
+//! In OTLP/JSON, field names are lowerCamelCase, trace and span ids are hex encoded rather than
+//! base64 encoded, and 64-bit integers are encoded as decimal strings.
+//!
+//! [OTLP/JSON]: https://opentelemetry.io/docs/specs/otlp/#json-protobuf-encoding
+
+mod decode;
+mod encode;
+
+pub use decode::{logs_request_to_proto, metrics_request_to_proto, traces_request_to_proto};
+pub use encode::{logs_request_to_json, metrics_request_to_json, traces_request_to_json};
+
+/// Errors converting between OTLP/JSON and OTLP protobuf.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The OTLP/JSON request is malformed.
+    #[error("invalid OTLP/JSON request: {0}")]
+    InvalidJson(String),
+
+    /// The OTLP protobuf request cannot be decoded.
+    #[error("invalid OTLP protobuf request: {0}")]
+    InvalidProto(#[from] prost::DecodeError),
+}
+
+type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,766 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decoding of OTLP/JSON export requests into their protobuf encoding.
+//!
+//! Enums may be given by number or by name, 64-bit integers may be given as numbers or decimal
+//! strings, and unknown fields are ignored.
+
+use crate::proto::opentelemetry::collector::logs::v1::ExportLogsServiceRequest;
+use crate::proto::opentelemetry::collector::metrics::v1::ExportMetricsServiceRequest;
+use crate::proto::opentelemetry::collector::trace::v1::ExportTraceServiceRequest;
+use crate::proto::opentelemetry::common::v1::{
+    AnyValue, ArrayValue, InstrumentationScope, KeyValue, KeyValueList, any_value,
+};
+use crate::proto::opentelemetry::logs::v1::{LogRecord, ResourceLogs, ScopeLogs, SeverityNumber};
+use crate::proto::opentelemetry::metrics::v1::{
+    AggregationTemporality, Exemplar, ExponentialHistogram, ExponentialHistogramDataPoint, Gauge,
+    Histogram, HistogramDataPoint, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics, Sum,
+    Summary, SummaryDataPoint, exemplar, exponential_histogram_data_point, metric,
+    number_data_point, summary_data_point,
+};
+use crate::proto::opentelemetry::resource::v1::Resource;
+use crate::proto::opentelemetry::trace::v1::{
+    ResourceSpans, ScopeSpans, Span, Status, span, status,
+};
+use data_encoding::{BASE64, HEXLOWER_PERMISSIVE};
+use prost::Message;
+use serde_json::{Map, Value};
+
+use super::{Error, Result};
+
+type Object = Map<String, Value>;
+
+/// Decodes an OTLP/JSON logs export request and returns its protobuf encoding.
+pub fn logs_request_to_proto(json: &[u8]) -> Result<Vec<u8>> {
+    let root = parse(json)?;
+    let request = ExportLogsServiceRequest {
+        resource_logs: objects(&root, "resourceLogs")?
+            .map(|rl| {
+                Ok(ResourceLogs {
+                    resource: resource(rl)?,
+                    scope_logs: objects(rl, "scopeLogs")?
+                        .map(|sl| {
+                            Ok(ScopeLogs {
+                                scope: scope(sl)?,
+                                log_records: objects(sl, "logRecords")?
+                                    .map(log_record)
+                                    .collect::<Result<_>>()?,
+                                schema_url: string(sl, "schemaUrl")?,
+                            })
+                        })
+                        .collect::<Result<_>>()?,
+                    schema_url: string(rl, "schemaUrl")?,
+                })
+            })
+            .collect::<Result<_>>()?,
+    };
+    Ok(request.encode_to_vec())
+}
+
+/// Decodes an OTLP/JSON traces export request and returns its protobuf encoding.
+pub fn traces_request_to_proto(json: &[u8]) -> Result<Vec<u8>> {
+    let root = parse(json)?;
+    let request = ExportTraceServiceRequest {
+        resource_spans: objects(&root, "resourceSpans")?
+            .map(|rs| {
+                Ok(ResourceSpans {
+                    resource: resource(rs)?,
+                    scope_spans: objects(rs, "scopeSpans")?
+                        .map(|ss| {
+                            Ok(ScopeSpans {
+                                scope: scope(ss)?,
+                                spans: objects(ss, "spans")?.map(span).collect::<Result<_>>()?,
+                                schema_url: string(ss, "schemaUrl")?,
+                            })
+                        })
+                        .collect::<Result<_>>()?,
+                    schema_url: string(rs, "schemaUrl")?,
+                })
+            })
+            .collect::<Result<_>>()?,
+    };
+    Ok(request.encode_to_vec())
+}
+
+/// Decodes an OTLP/JSON metrics export request and returns its protobuf encoding.
+pub fn metrics_request_to_proto(json: &[u8]) -> Result<Vec<u8>> {
+    let root = parse(json)?;
+    let request = ExportMetricsServiceRequest {
+        resource_metrics: objects(&root, "resourceMetrics")?
+            .map(|rm| {
+                Ok(ResourceMetrics {
+                    resource: resource(rm)?,
+                    scope_metrics: objects(rm, "scopeMetrics")?
+                        .map(|sm| {
+                            Ok(ScopeMetrics {
+                                scope: scope(sm)?,
+                                metrics: objects(sm, "metrics")?
+                                    .map(metric)
+                                    .collect::<Result<_>>()?,
+                                schema_url: string(sm, "schemaUrl")?,
+                            })
+                        })
+                        .collect::<Result<_>>()?,
+                    schema_url: string(rm, "schemaUrl")?,
+                })
+            })
+            .collect::<Result<_>>()?,
+    };
+    Ok(request.encode_to_vec())
+}
+
+fn parse(json: &[u8]) -> Result<Object> {
+    match serde_json::from_slice(json) {
+        Ok(Value::Object(root)) => Ok(root),
+        Ok(_) => Err(Error::InvalidJson("request must be a JSON object".into())),
+        Err(e) => Err(Error::InvalidJson(e.to_string())),
+    }
+}
+
+/* -------- messages -------- */
+
+fn resource(parent: &Object) -> Result<Option<Resource>> {
+    object(parent, "resource")?
+        .map(|r| {
+            Ok(Resource {
+                attributes: attributes(r, "attributes")?,
+                dropped_attributes_count: uint32(r, "droppedAttributesCount")?,
+                ..Default::default()
+            })
+        })
+        .transpose()
+}
+
+fn scope(parent: &Object) -> Result<Option<InstrumentationScope>> {
+    object(parent, "scope")?
+        .map(|s| {
+            Ok(InstrumentationScope {
+                name: string(s, "name")?,
+                version: string(s, "version")?,
+                attributes: attributes(s, "attributes")?,
+                dropped_attributes_count: uint32(s, "droppedAttributesCount")?,
+            })
+        })
+        .transpose()
+}
+
+fn log_record(lr: &Object) -> Result<LogRecord> {
+    Ok(LogRecord {
+        time_unix_nano: uint64(lr, "timeUnixNano")?,
+        observed_time_unix_nano: uint64(lr, "observedTimeUnixNano")?,
+        severity_number: enumeration(lr, "severityNumber", |name| {
+            SeverityNumber::from_str_name(name).map(|v| v as i32)
+        })?,
+        severity_text: string(lr, "severityText")?,
+        body: lr.get("body").map(any_value).transpose()?,
+        attributes: attributes(lr, "attributes")?,
+        dropped_attributes_count: uint32(lr, "droppedAttributesCount")?,
+        flags: uint32(lr, "flags")?,
+        trace_id: hex(lr, "traceId")?,
+        span_id: hex(lr, "spanId")?,
+        event_name: string(lr, "eventName")?,
+    })
+}
+
+fn span(s: &Object) -> Result<Span> {
+    Ok(Span {
+        trace_id: hex(s, "traceId")?,
+        span_id: hex(s, "spanId")?,
+        trace_state: string(s, "traceState")?,
+        parent_span_id: hex(s, "parentSpanId")?,
+        flags: uint32(s, "flags")?,
+        name: string(s, "name")?,
+        kind: enumeration(s, "kind", |name| {
+            span::SpanKind::from_str_name(name).map(|v| v as i32)
+        })?,
+        start_time_unix_nano: uint64(s, "startTimeUnixNano")?,
+        end_time_unix_nano: uint64(s, "endTimeUnixNano")?,
+        attributes: attributes(s, "attributes")?,
+        dropped_attributes_count: uint32(s, "droppedAttributesCount")?,
+        events: objects(s, "events")?
+            .map(|e| {
+                Ok(span::Event {
+                    time_unix_nano: uint64(e, "timeUnixNano")?,
+                    name: string(e, "name")?,
+                    attributes: attributes(e, "attributes")?,
+                    dropped_attributes_count: uint32(e, "droppedAttributesCount")?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        dropped_events_count: uint32(s, "droppedEventsCount")?,
+        links: objects(s, "links")?
+            .map(|l| {
+                Ok(span::Link {
+                    trace_id: hex(l, "traceId")?,
+                    span_id: hex(l, "spanId")?,
+                    trace_state: string(l, "traceState")?,
+                    attributes: attributes(l, "attributes")?,
+                    dropped_attributes_count: uint32(l, "droppedAttributesCount")?,
+                    flags: uint32(l, "flags")?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        dropped_links_count: uint32(s, "droppedLinksCount")?,
+        status: object(s, "status")?
+            .map(|st| {
+                Ok(Status {
+                    message: string(st, "message")?,
+                    code: enumeration(st, "code", |name| {
+                        status::StatusCode::from_str_name(name).map(|v| v as i32)
+                    })?,
+                })
+            })
+            .transpose()?,
+    })
+}
+
+fn metric(m: &Object) -> Result<Metric> {
+    let data = if let Some(gauge) = object(m, "gauge")? {
+        Some(metric::Data::Gauge(Gauge {
+            data_points: number_data_points(gauge)?,
+        }))
+    } else if let Some(sum) = object(m, "sum")? {
+        Some(metric::Data::Sum(Sum {
+            data_points: number_data_points(sum)?,
+            aggregation_temporality: temporality(sum)?,
+            is_monotonic: boolean(sum, "isMonotonic")?,
+        }))
+    } else if let Some(histogram) = object(m, "histogram")? {
+        Some(metric::Data::Histogram(Histogram {
+            data_points: objects(histogram, "dataPoints")?
+                .map(|dp| {
+                    Ok(HistogramDataPoint {
+                        attributes: attributes(dp, "attributes")?,
+                        start_time_unix_nano: uint64(dp, "startTimeUnixNano")?,
+                        time_unix_nano: uint64(dp, "timeUnixNano")?,
+                        count: uint64(dp, "count")?,
+                        sum: optional_double(dp, "sum")?,
+                        bucket_counts: uint64_list(dp, "bucketCounts")?,
+                        explicit_bounds: double_list(dp, "explicitBounds")?,
+                        exemplars: exemplars(dp)?,
+                        flags: uint32(dp, "flags")?,
+                        min: optional_double(dp, "min")?,
+                        max: optional_double(dp, "max")?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            aggregation_temporality: temporality(histogram)?,
+        }))
+    } else if let Some(histogram) = object(m, "exponentialHistogram")? {
+        Some(metric::Data::ExponentialHistogram(ExponentialHistogram {
+            data_points: objects(histogram, "dataPoints")?
+                .map(|dp| {
+                    Ok(ExponentialHistogramDataPoint {
+                        attributes: attributes(dp, "attributes")?,
+                        start_time_unix_nano: uint64(dp, "startTimeUnixNano")?,
+                        time_unix_nano: uint64(dp, "timeUnixNano")?,
+                        count: uint64(dp, "count")?,
+                        sum: optional_double(dp, "sum")?,
+                        scale: int32(dp, "scale")?,
+                        zero_count: uint64(dp, "zeroCount")?,
+                        positive: buckets(dp, "positive")?,
+                        negative: buckets(dp, "negative")?,
+                        flags: uint32(dp, "flags")?,
+                        exemplars: exemplars(dp)?,
+                        min: optional_double(dp, "min")?,
+                        max: optional_double(dp, "max")?,
+                        zero_threshold: double(dp, "zeroThreshold")?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            aggregation_temporality: temporality(histogram)?,
+        }))
+    } else if let Some(summary) = object(m, "summary")? {
+        Some(metric::Data::Summary(Summary {
+            data_points: objects(summary, "dataPoints")?
+                .map(|dp| {
+                    Ok(SummaryDataPoint {
+                        attributes: attributes(dp, "attributes")?,
+                        start_time_unix_nano: uint64(dp, "startTimeUnixNano")?,
+                        time_unix_nano: uint64(dp, "timeUnixNano")?,
+                        count: uint64(dp, "count")?,
+                        sum: double(dp, "sum")?,
+                        quantile_values: objects(dp, "quantileValues")?
+                            .map(|q| {
+                                Ok(summary_data_point::ValueAtQuantile {
+                                    quantile: double(q, "quantile")?,
+                                    value: double(q, "value")?,
+                                })
+                            })
+                            .collect::<Result<_>>()?,
+                        flags: uint32(dp, "flags")?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        }))
+    } else {
+        None
+    };
+
+    Ok(Metric {
+        name: string(m, "name")?,
+        description: string(m, "description")?,
+        unit: string(m, "unit")?,
+        metadata: attributes(m, "metadata")?,
+        data,
+    })
+}
+
+fn number_data_points(parent: &Object) -> Result<Vec<NumberDataPoint>> {
+    objects(parent, "dataPoints")?
+        .map(|dp| {
+            let value = if dp.contains_key("asInt") {
+                Some(number_data_point::Value::AsInt(int64(dp, "asInt")?))
+            } else if dp.contains_key("asDouble") {
+                Some(number_data_point::Value::AsDouble(double(dp, "asDouble")?))
+            } else {
+                None
+            };
+            Ok(NumberDataPoint {
+                attributes: attributes(dp, "attributes")?,
+                start_time_unix_nano: uint64(dp, "startTimeUnixNano")?,
+                time_unix_nano: uint64(dp, "timeUnixNano")?,
+                exemplars: exemplars(dp)?,
+                flags: uint32(dp, "flags")?,
+                value,
+            })
+        })
+        .collect()
+}
+
+fn exemplars(parent: &Object) -> Result<Vec<Exemplar>> {
+    objects(parent, "exemplars")?
+        .map(|e| {
+            let value = if e.contains_key("asInt") {
+                Some(exemplar::Value::AsInt(int64(e, "asInt")?))
+            } else if e.contains_key("asDouble") {
+                Some(exemplar::Value::AsDouble(double(e, "asDouble")?))
+            } else {
+                None
+            };
+            Ok(Exemplar {
+                filtered_attributes: attributes(e, "filteredAttributes")?,
+                time_unix_nano: uint64(e, "timeUnixNano")?,
+                span_id: hex(e, "spanId")?,
+                trace_id: hex(e, "traceId")?,
+                value,
+            })
+        })
+        .collect()
+}
+
+fn buckets(
+    parent: &Object,
+    name: &str,
+) -> Result<Option<exponential_histogram_data_point::Buckets>> {
+    object(parent, name)?
+        .map(|b| {
+            Ok(exponential_histogram_data_point::Buckets {
+                offset: int32(b, "offset")?,
+                bucket_counts: uint64_list(b, "bucketCounts")?,
+            })
+        })
+        .transpose()
+}
+
+fn temporality(parent: &Object) -> Result<i32> {
+    enumeration(parent, "aggregationTemporality", |name| {
+        AggregationTemporality::from_str_name(name).map(|v| v as i32)
+    })
+}
+
+fn attributes(parent: &Object, name: &str) -> Result<Vec<KeyValue>> {
+    objects(parent, name)?.map(key_value).collect()
+}
+
+fn key_value(kv: &Object) -> Result<KeyValue> {
+    Ok(KeyValue {
+        key: string(kv, "key")?,
+        value: kv.get("value").map(any_value).transpose()?,
+    })
+}
+
+fn any_value(value: &Value) -> Result<AnyValue> {
+    let Value::Object(obj) = value else {
+        return Err(Error::InvalidJson("AnyValue must be an object".into()));
+    };
+    let value = if let Some(v) = obj.get("stringValue") {
+        Some(any_value::Value::StringValue(as_string(v, "stringValue")?))
+    } else if obj.contains_key("boolValue") {
+        Some(any_value::Value::BoolValue(boolean(obj, "boolValue")?))
+    } else if obj.contains_key("intValue") {
+        Some(any_value::Value::IntValue(int64(obj, "intValue")?))
+    } else if obj.contains_key("doubleValue") {
+        Some(any_value::Value::DoubleValue(double(obj, "doubleValue")?))
+    } else if let Some(array) = object(obj, "arrayValue")? {
+        Some(any_value::Value::ArrayValue(ArrayValue {
+            values: array
+                .get("values")
+                .map(|v| as_array(v, "values"))
+                .transpose()?
+                .unwrap_or_default()
+                .iter()
+                .map(any_value)
+                .collect::<Result<_>>()?,
+        }))
+    } else if let Some(kvlist) = object(obj, "kvlistValue")? {
+        Some(any_value::Value::KvlistValue(KeyValueList {
+            values: attributes(kvlist, "values")?,
+        }))
+    } else if let Some(v) = obj.get("bytesValue") {
+        let encoded = as_string(v, "bytesValue")?;
+        Some(any_value::Value::BytesValue(
+            BASE64
+                .decode(encoded.as_bytes())
+                .map_err(|e| Error::InvalidJson(format!("bytesValue: {e}")))?,
+        ))
+    } else {
+        None
+    };
+    Ok(AnyValue { value })
+}
+
+/* -------- fields -------- */
+
+fn object<'a>(parent: &'a Object, name: &str) -> Result<Option<&'a Object>> {
+    match parent.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Object(obj)) => Ok(Some(obj)),
+        Some(_) => Err(Error::InvalidJson(format!("{name}: expected an object"))),
+    }
+}
+
+fn objects<'a>(parent: &'a Object, name: &str) -> Result<impl Iterator<Item = &'a Object>> {
+    let values = match parent.get(name) {
+        None | Some(Value::Null) => &[][..],
+        Some(v) => as_array(v, name)?,
+    };
+    if values.iter().any(|v| !v.is_object()) {
+        return Err(Error::InvalidJson(format!(
+            "{name}: expected an array of objects"
+        )));
+    }
+    Ok(values.iter().filter_map(Value::as_object))
+}
+
+fn as_array<'a>(value: &'a Value, name: &str) -> Result<&'a [Value]> {
+    value
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| Error::InvalidJson(format!("{name}: expected an array")))
+}
+
+fn as_string(value: &Value, name: &str) -> Result<String> {
+    value
+        .as_str()
+        .map(str::to_owned)
+        .ok_or_else(|| Error::InvalidJson(format!("{name}: expected a string")))
+}
+
+fn string(parent: &Object, name: &str) -> Result<String> {
+    match parent.get(name) {
+        None | Some(Value::Null) => Ok(String::new()),
+        Some(v) => as_string(v, name),
+    }
+}
+
+fn boolean(parent: &Object, name: &str) -> Result<bool> {
+    match parent.get(name) {
+        None | Some(Value::Null) => Ok(false),
+        Some(Value::Bool(b)) => Ok(*b),
+        Some(_) => Err(Error::InvalidJson(format!("{name}: expected a boolean"))),
+    }
+}
+
+/// Integers may be encoded either as JSON numbers or as decimal strings.
+fn integer<T: TryFrom<i128>>(value: &Value, name: &str) -> Result<T> {
+    let parsed: Option<i128> = match value {
+        Value::Number(n) => n
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from)),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    };
+    parsed
+        .and_then(|v| T::try_from(v).ok())
+        .ok_or_else(|| Error::InvalidJson(format!("{name}: expected an integer in range")))
+}
+
+fn uint64(parent: &Object, name: &str) -> Result<u64> {
+    parent
+        .get(name)
+        .filter(|v| !v.is_null())
+        .map_or(Ok(0), |v| integer(v, name))
+}
+
+fn int64(parent: &Object, name: &str) -> Result<i64> {
+    parent
+        .get(name)
+        .filter(|v| !v.is_null())
+        .map_or(Ok(0), |v| integer(v, name))
+}
+
+fn uint32(parent: &Object, name: &str) -> Result<u32> {
+    parent
+        .get(name)
+        .filter(|v| !v.is_null())
+        .map_or(Ok(0), |v| integer(v, name))
+}
+
+fn int32(parent: &Object, name: &str) -> Result<i32> {
+    parent
+        .get(name)
+        .filter(|v| !v.is_null())
+        .map_or(Ok(0), |v| integer(v, name))
+}
+
+fn uint64_list(parent: &Object, name: &str) -> Result<Vec<u64>> {
+    match parent.get(name) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(v) => as_array(v, name)?
+            .iter()
+            .map(|item| integer(item, name))
+            .collect(),
+    }
+}
+
+/// Doubles may be encoded as JSON numbers or as strings, including "NaN" and "Infinity".
+fn as_double(value: &Value, name: &str) -> Result<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => match s.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            other => other.parse().ok(),
+        },
+        _ => None,
+    }
+    .ok_or_else(|| Error::InvalidJson(format!("{name}: expected a number")))
+}
+
+fn double(parent: &Object, name: &str) -> Result<f64> {
+    optional_double(parent, name).map(Option::unwrap_or_default)
+}
+
+fn optional_double(parent: &Object, name: &str) -> Result<Option<f64>> {
+    parent
+        .get(name)
+        .filter(|v| !v.is_null())
+        .map(|v| as_double(v, name))
+        .transpose()
+}
+
+fn double_list(parent: &Object, name: &str) -> Result<Vec<f64>> {
+    match parent.get(name) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(v) => as_array(v, name)?
+            .iter()
+            .map(|item| as_double(item, name))
+            .collect(),
+    }
+}
+
+/// Enums may be encoded either by number or by their protobuf name.
+fn enumeration(
+    parent: &Object,
+    name: &str,
+    from_str_name: impl Fn(&str) -> Option<i32>,
+) -> Result<i32> {
+    match parent.get(name) {
+        None | Some(Value::Null) => Ok(0),
+        Some(Value::String(s)) => {
+            from_str_name(s).ok_or_else(|| Error::InvalidJson(format!("{name}: unknown value {s}")))
+        }
+        Some(v) => integer(v, name),
+    }
+}
+
+/// Trace and span ids are hex encoded rather than base64 encoded.
+fn hex(parent: &Object, name: &str) -> Result<Vec<u8>> {
+    let encoded = string(parent, name)?;
+    HEXLOWER_PERMISSIVE
+        .decode(encoded.as_bytes())
+        .map_err(|e| Error::InvalidJson(format!("{name}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_logs_request() {
+        let json = json!({
+            "resourceLogs": [{
+                "resource": {
+                    "attributes": [{ "key": "service.name", "value": { "stringValue": "svc" } }]
+                },
+                "scopeLogs": [{
+                    "scope": { "name": "lib", "version": "1.0" },
+                    "logRecords": [{
+                        "timeUnixNano": "1700000000000000000",
+                        "severityNumber": 9,
+                        "severityText": "INFO",
+                        "body": { "stringValue": "hello" },
+                        "attributes": [
+                            { "key": "i", "value": { "intValue": "42" } },
+                            { "key": "d", "value": { "doubleValue": 1.5 } },
+                            { "key": "b", "value": { "bytesValue": "AQI=" } },
+                            { "key": "a", "value": { "arrayValue": { "values": [
+                                { "boolValue": true }
+                            ] } } }
+                        ],
+                        "traceId": "5B8EFFF798038103D269B633813FC60C",
+                        "spanId": "eee19b7ec3c1b174",
+                        "unknownField": "ignored"
+                    }]
+                }]
+            }]
+        });
+        let bytes = logs_request_to_proto(json.to_string().as_bytes()).unwrap();
+        let request = ExportLogsServiceRequest::decode(bytes.as_slice()).unwrap();
+
+        let resource_logs = &request.resource_logs[0];
+        assert_eq!(
+            resource_logs.resource.as_ref().unwrap().attributes[0].key,
+            "service.name"
+        );
+        let scope_logs = &resource_logs.scope_logs[0];
+        assert_eq!(scope_logs.scope.as_ref().unwrap().version, "1.0");
+
+        let log = &scope_logs.log_records[0];
+        assert_eq!(log.time_unix_nano, 1_700_000_000_000_000_000);
+        assert_eq!(log.severity_number, SeverityNumber::Info as i32);
+        assert_eq!(
+            log.body.as_ref().unwrap().value,
+            Some(any_value::Value::StringValue("hello".into()))
+        );
+        assert_eq!(
+            log.attributes[0].value.as_ref().unwrap().value,
+            Some(any_value::Value::IntValue(42))
+        );
+        assert_eq!(
+            log.attributes[1].value.as_ref().unwrap().value,
+            Some(any_value::Value::DoubleValue(1.5))
+        );
+        assert_eq!(
+            log.attributes[2].value.as_ref().unwrap().value,
+            Some(any_value::Value::BytesValue(vec![1, 2]))
+        );
+        assert!(matches!(
+            log.attributes[3].value.as_ref().unwrap().value,
+            Some(any_value::Value::ArrayValue(_))
+        ));
+        assert_eq!(log.trace_id.len(), 16);
+        assert_eq!(log.span_id.len(), 8);
+    }
+
+    #[test]
+    fn test_traces_request_enum_names() {
+        let json = json!({
+            "resourceSpans": [{
+                "scopeSpans": [{
+                    "spans": [{
+                        "traceId": "5b8efff798038103d269b633813fc60c",
+                        "spanId": "eee19b7ec3c1b174",
+                        "name": "op",
+                        "kind": "SPAN_KIND_SERVER",
+                        "startTimeUnixNano": 1,
+                        "endTimeUnixNano": 2,
+                        "status": { "code": 2, "message": "boom" },
+                        "events": [{ "name": "event", "timeUnixNano": "3" }]
+                    }]
+                }]
+            }]
+        });
+        let bytes = traces_request_to_proto(json.to_string().as_bytes()).unwrap();
+        let request = ExportTraceServiceRequest::decode(bytes.as_slice()).unwrap();
+        let span = &request.resource_spans[0].scope_spans[0].spans[0];
+        assert_eq!(span.kind, span::SpanKind::Server as i32);
+        assert_eq!(
+            span.status.as_ref().unwrap().code,
+            status::StatusCode::Error as i32
+        );
+        assert_eq!(span.events[0].time_unix_nano, 3);
+    }
+
+    #[test]
+    fn test_metrics_request() {
+        let json = json!({
+            "resourceMetrics": [{
+                "scopeMetrics": [{
+                    "metrics": [
+                        {
+                            "name": "requests",
+                            "sum": {
+                                "aggregationTemporality": 2,
+                                "isMonotonic": true,
+                                "dataPoints": [{ "asInt": "10", "timeUnixNano": "5" }]
+                            }
+                        },
+                        {
+                            "name": "latency",
+                            "histogram": {
+                                "aggregationTemporality": "AGGREGATION_TEMPORALITY_DELTA",
+                                "dataPoints": [{
+                                    "count": "3",
+                                    "sum": 4.5,
+                                    "bucketCounts": ["1", 2],
+                                    "explicitBounds": [1.0]
+                                }]
+                            }
+                        }
+                    ]
+                }]
+            }]
+        });
+        let bytes = metrics_request_to_proto(json.to_string().as_bytes()).unwrap();
+        let request = ExportMetricsServiceRequest::decode(bytes.as_slice()).unwrap();
+        let metrics = &request.resource_metrics[0].scope_metrics[0].metrics;
+
+        let Some(metric::Data::Sum(sum)) = &metrics[0].data else {
+            panic!("expected a sum");
+        };
+        assert!(sum.is_monotonic);
+        assert_eq!(
+            sum.data_points[0].value,
+            Some(number_data_point::Value::AsInt(10))
+        );
+
+        let Some(metric::Data::Histogram(histogram)) = &metrics[1].data else {
+            panic!("expected a histogram");
+        };
+        assert_eq!(
+            histogram.aggregation_temporality,
+            AggregationTemporality::Delta as i32
+        );
+        assert_eq!(histogram.data_points[0].bucket_counts, vec![1, 2]);
+        assert_eq!(histogram.data_points[0].sum, Some(4.5));
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        for (json, expect) in [
+            (r#"[]"#, "JSON object"),
+            (r#"{"resourceLogs": {}}"#, "resourceLogs"),
+            (
+                r#"{"resourceLogs": [{"scopeLogs": [{"logRecords": [{"timeUnixNano": "abc"}]}]}]}"#,
+                "timeUnixNano",
+            ),
+            (
+                r#"{"resourceLogs": [{"scopeLogs": [{"logRecords": [{"traceId": "xyz"}]}]}]}"#,
+                "traceId",
+            ),
+            (r#"{"resourceLogs": [{"#, "EOF"),
+        ] {
+            let err = logs_request_to_proto(json.as_bytes()).unwrap_err();
+            assert!(
+                err.to_string().contains(expect),
+                "{err} should contain {expect}"
+            );
+        }
+    }
+}
@@ -0,0 +1,634 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encoding of OTLP protobuf export requests as OTLP/JSON.
+//!
+//! Enums are encoded by number and fields holding their default value are omitted, except for
+//! the members of a `oneof` whose presence is meaningful.
+
+use super::Result;
+use crate::proto::opentelemetry::collector::logs::v1::ExportLogsServiceRequest;
+use crate::proto::opentelemetry::collector::metrics::v1::ExportMetricsServiceRequest;
+use crate::proto::opentelemetry::collector::trace::v1::ExportTraceServiceRequest;
+use crate::proto::opentelemetry::common::v1::{
+    AnyValue, InstrumentationScope, KeyValue, any_value,
+};
+use crate::proto::opentelemetry::logs::v1::LogRecord;
+use crate::proto::opentelemetry::metrics::v1::{
+    Exemplar, Metric, NumberDataPoint, exemplar, exponential_histogram_data_point, metric,
+    number_data_point,
+};
+use crate::proto::opentelemetry::resource::v1::Resource;
+use crate::proto::opentelemetry::trace::v1::Span;
+use data_encoding::{BASE64, HEXLOWER};
+use prost::Message;
+use serde_json::{Map, Value};
+
+/// Encodes a protobuf logs export request as OTLP/JSON.
+pub fn logs_request_to_json(proto: &[u8]) -> Result<String> {
+    let request = ExportLogsServiceRequest::decode(proto)?;
+    let resource_logs = request.resource_logs.iter().map(|rl| -> Value {
+        Obj::new()
+            .opt("resource", rl.resource.as_ref().map(resource))
+            .list(
+                "scopeLogs",
+                rl.scope_logs.iter().map(|sl| {
+                    Obj::new()
+                        .opt("scope", sl.scope.as_ref().map(scope))
+                        .list("logRecords", sl.log_records.iter().map(log_record))
+                        .str("schemaUrl", &sl.schema_url)
+                        .into()
+                }),
+            )
+            .str("schemaUrl", &rl.schema_url)
+            .into()
+    });
+    Ok(Value::from(Obj::new().list("resourceLogs", resource_logs)).to_string())
+}
+
+/// Encodes a protobuf traces export request as OTLP/JSON.
+pub fn traces_request_to_json(proto: &[u8]) -> Result<String> {
+    let request = ExportTraceServiceRequest::decode(proto)?;
+    let resource_spans = request.resource_spans.iter().map(|rs| -> Value {
+        Obj::new()
+            .opt("resource", rs.resource.as_ref().map(resource))
+            .list(
+                "scopeSpans",
+                rs.scope_spans.iter().map(|ss| {
+                    Obj::new()
+                        .opt("scope", ss.scope.as_ref().map(scope))
+                        .list("spans", ss.spans.iter().map(span))
+                        .str("schemaUrl", &ss.schema_url)
+                        .into()
+                }),
+            )
+            .str("schemaUrl", &rs.schema_url)
+            .into()
+    });
+    Ok(Value::from(Obj::new().list("resourceSpans", resource_spans)).to_string())
+}
+
+/// Encodes a protobuf metrics export request as OTLP/JSON.
+pub fn metrics_request_to_json(proto: &[u8]) -> Result<String> {
+    let request = ExportMetricsServiceRequest::decode(proto)?;
+    let resource_metrics = request.resource_metrics.iter().map(|rm| -> Value {
+        Obj::new()
+            .opt("resource", rm.resource.as_ref().map(resource))
+            .list(
+                "scopeMetrics",
+                rm.scope_metrics.iter().map(|sm| {
+                    Obj::new()
+                        .opt("scope", sm.scope.as_ref().map(scope))
+                        .list("metrics", sm.metrics.iter().map(metric))
+                        .str("schemaUrl", &sm.schema_url)
+                        .into()
+                }),
+            )
+            .str("schemaUrl", &rm.schema_url)
+            .into()
+    });
+    Ok(Value::from(Obj::new().list("resourceMetrics", resource_metrics)).to_string())
+}
+
+/* -------- messages -------- */
+
+fn resource(r: &Resource) -> Value {
+    Obj::new()
+        .list("attributes", r.attributes.iter().map(key_value))
+        .u32("droppedAttributesCount", r.dropped_attributes_count)
+        .into()
+}
+
+fn scope(s: &InstrumentationScope) -> Value {
+    Obj::new()
+        .str("name", &s.name)
+        .str("version", &s.version)
+        .list("attributes", s.attributes.iter().map(key_value))
+        .u32("droppedAttributesCount", s.dropped_attributes_count)
+        .into()
+}
+
+fn log_record(lr: &LogRecord) -> Value {
+    Obj::new()
+        .u64("timeUnixNano", lr.time_unix_nano)
+        .u64("observedTimeUnixNano", lr.observed_time_unix_nano)
+        .i32("severityNumber", lr.severity_number)
+        .str("severityText", &lr.severity_text)
+        .opt("body", lr.body.as_ref().map(any_value))
+        .list("attributes", lr.attributes.iter().map(key_value))
+        .u32("droppedAttributesCount", lr.dropped_attributes_count)
+        .u32("flags", lr.flags)
+        .hex("traceId", &lr.trace_id)
+        .hex("spanId", &lr.span_id)
+        .str("eventName", &lr.event_name)
+        .into()
+}
+
+fn span(s: &Span) -> Value {
+    Obj::new()
+        .hex("traceId", &s.trace_id)
+        .hex("spanId", &s.span_id)
+        .str("traceState", &s.trace_state)
+        .hex("parentSpanId", &s.parent_span_id)
+        .u32("flags", s.flags)
+        .str("name", &s.name)
+        .i32("kind", s.kind)
+        .u64("startTimeUnixNano", s.start_time_unix_nano)
+        .u64("endTimeUnixNano", s.end_time_unix_nano)
+        .list("attributes", s.attributes.iter().map(key_value))
+        .u32("droppedAttributesCount", s.dropped_attributes_count)
+        .list(
+            "events",
+            s.events.iter().map(|e| {
+                Obj::new()
+                    .u64("timeUnixNano", e.time_unix_nano)
+                    .str("name", &e.name)
+                    .list("attributes", e.attributes.iter().map(key_value))
+                    .u32("droppedAttributesCount", e.dropped_attributes_count)
+                    .into()
+            }),
+        )
+        .u32("droppedEventsCount", s.dropped_events_count)
+        .list(
+            "links",
+            s.links.iter().map(|l| {
+                Obj::new()
+                    .hex("traceId", &l.trace_id)
+                    .hex("spanId", &l.span_id)
+                    .str("traceState", &l.trace_state)
+                    .list("attributes", l.attributes.iter().map(key_value))
+                    .u32("droppedAttributesCount", l.dropped_attributes_count)
+                    .u32("flags", l.flags)
+                    .into()
+            }),
+        )
+        .u32("droppedLinksCount", s.dropped_links_count)
+        .opt(
+            "status",
+            s.status.as_ref().map(|st| {
+                Obj::new()
+                    .str("message", &st.message)
+                    .i32("code", st.code)
+                    .into()
+            }),
+        )
+        .into()
+}
+
+fn metric(m: &Metric) -> Value {
+    let obj = Obj::new()
+        .str("name", &m.name)
+        .str("description", &m.description)
+        .str("unit", &m.unit)
+        .list("metadata", m.metadata.iter().map(key_value));
+    match &m.data {
+        None => obj,
+        Some(metric::Data::Gauge(gauge)) => obj.raw(
+            "gauge",
+            Obj::new()
+                .list(
+                    "dataPoints",
+                    gauge.data_points.iter().map(number_data_point),
+                )
+                .into(),
+        ),
+        Some(metric::Data::Sum(sum)) => obj.raw(
+            "sum",
+            Obj::new()
+                .list("dataPoints", sum.data_points.iter().map(number_data_point))
+                .i32("aggregationTemporality", sum.aggregation_temporality)
+                .bool("isMonotonic", sum.is_monotonic)
+                .into(),
+        ),
+        Some(metric::Data::Histogram(histogram)) => obj.raw(
+            "histogram",
+            Obj::new()
+                .list(
+                    "dataPoints",
+                    histogram.data_points.iter().map(|dp| {
+                        Obj::new()
+                            .list("attributes", dp.attributes.iter().map(key_value))
+                            .u64("startTimeUnixNano", dp.start_time_unix_nano)
+                            .u64("timeUnixNano", dp.time_unix_nano)
+                            .u64("count", dp.count)
+                            .opt("sum", dp.sum.map(double))
+                            .list("bucketCounts", dp.bucket_counts.iter().map(|c| uint64(*c)))
+                            .list(
+                                "explicitBounds",
+                                dp.explicit_bounds.iter().map(|b| double(*b)),
+                            )
+                            .list("exemplars", dp.exemplars.iter().map(exemplar))
+                            .u32("flags", dp.flags)
+                            .opt("min", dp.min.map(double))
+                            .opt("max", dp.max.map(double))
+                            .into()
+                    }),
+                )
+                .i32("aggregationTemporality", histogram.aggregation_temporality)
+                .into(),
+        ),
+        Some(metric::Data::ExponentialHistogram(histogram)) => obj.raw(
+            "exponentialHistogram",
+            Obj::new()
+                .list(
+                    "dataPoints",
+                    histogram.data_points.iter().map(|dp| {
+                        Obj::new()
+                            .list("attributes", dp.attributes.iter().map(key_value))
+                            .u64("startTimeUnixNano", dp.start_time_unix_nano)
+                            .u64("timeUnixNano", dp.time_unix_nano)
+                            .u64("count", dp.count)
+                            .opt("sum", dp.sum.map(double))
+                            .i32("scale", dp.scale)
+                            .u64("zeroCount", dp.zero_count)
+                            .opt("positive", dp.positive.as_ref().map(buckets))
+                            .opt("negative", dp.negative.as_ref().map(buckets))
+                            .u32("flags", dp.flags)
+                            .list("exemplars", dp.exemplars.iter().map(exemplar))
+                            .opt("min", dp.min.map(double))
+                            .opt("max", dp.max.map(double))
+                            .f64("zeroThreshold", dp.zero_threshold)
+                            .into()
+                    }),
+                )
+                .i32("aggregationTemporality", histogram.aggregation_temporality)
+                .into(),
+        ),
+        Some(metric::Data::Summary(summary)) => obj.raw(
+            "summary",
+            Obj::new()
+                .list(
+                    "dataPoints",
+                    summary.data_points.iter().map(|dp| {
+                        Obj::new()
+                            .list("attributes", dp.attributes.iter().map(key_value))
+                            .u64("startTimeUnixNano", dp.start_time_unix_nano)
+                            .u64("timeUnixNano", dp.time_unix_nano)
+                            .u64("count", dp.count)
+                            .f64("sum", dp.sum)
+                            .list(
+                                "quantileValues",
+                                dp.quantile_values.iter().map(|q| {
+                                    Obj::new()
+                                        .f64("quantile", q.quantile)
+                                        .f64("value", q.value)
+                                        .into()
+                                }),
+                            )
+                            .u32("flags", dp.flags)
+                            .into()
+                    }),
+                )
+                .into(),
+        ),
+    }
+    .into()
+}
+
+fn number_data_point(dp: &NumberDataPoint) -> Value {
+    let obj = Obj::new()
+        .list("attributes", dp.attributes.iter().map(key_value))
+        .u64("startTimeUnixNano", dp.start_time_unix_nano)
+        .u64("timeUnixNano", dp.time_unix_nano)
+        .list("exemplars", dp.exemplars.iter().map(exemplar))
+        .u32("flags", dp.flags);
+    match dp.value {
+        Some(number_data_point::Value::AsInt(v)) => obj.raw("asInt", int64(v)),
+        Some(number_data_point::Value::AsDouble(v)) => obj.raw("asDouble", double(v)),
+        None => obj,
+    }
+    .into()
+}
+
+fn exemplar(e: &Exemplar) -> Value {
+    let obj = Obj::new()
+        .list(
+            "filteredAttributes",
+            e.filtered_attributes.iter().map(key_value),
+        )
+        .u64("timeUnixNano", e.time_unix_nano)
+        .hex("spanId", &e.span_id)
+        .hex("traceId", &e.trace_id);
+    match e.value {
+        Some(exemplar::Value::AsInt(v)) => obj.raw("asInt", int64(v)),
+        Some(exemplar::Value::AsDouble(v)) => obj.raw("asDouble", double(v)),
+        None => obj,
+    }
+    .into()
+}
+
+fn buckets(b: &exponential_histogram_data_point::Buckets) -> Value {
+    Obj::new()
+        .i32("offset", b.offset)
+        .list("bucketCounts", b.bucket_counts.iter().map(|c| uint64(*c)))
+        .into()
+}
+
+fn key_value(kv: &KeyValue) -> Value {
+    Obj::new()
+        .raw("key", Value::String(kv.key.clone()))
+        .opt("value", kv.value.as_ref().map(any_value))
+        .into()
+}
+
+fn any_value(value: &AnyValue) -> Value {
+    let obj = Obj::new();
+    match &value.value {
+        None => obj,
+        Some(any_value::Value::StringValue(s)) => obj.raw("stringValue", Value::String(s.clone())),
+        Some(any_value::Value::BoolValue(b)) => obj.raw("boolValue", Value::Bool(*b)),
+        Some(any_value::Value::IntValue(i)) => obj.raw("intValue", int64(*i)),
+        Some(any_value::Value::DoubleValue(d)) => obj.raw("doubleValue", double(*d)),
+        Some(any_value::Value::ArrayValue(array)) => obj.raw(
+            "arrayValue",
+            Obj::new()
+                .list("values", array.values.iter().map(any_value))
+                .into(),
+        ),
+        Some(any_value::Value::KvlistValue(kvlist)) => obj.raw(
+            "kvlistValue",
+            Obj::new()
+                .list("values", kvlist.values.iter().map(key_value))
+                .into(),
+        ),
+        Some(any_value::Value::BytesValue(bytes)) => {
+            obj.raw("bytesValue", Value::String(BASE64.encode(bytes)))
+        }
+    }
+    .into()
+}
+
+/* -------- fields -------- */
+
+/// 64-bit integers are encoded as decimal strings.
+fn uint64(v: u64) -> Value {
+    Value::String(v.to_string())
+}
+
+fn int64(v: i64) -> Value {
+    Value::String(v.to_string())
+}
+
+/// Non-finite doubles, which JSON numbers cannot represent, are encoded as strings.
+fn double(v: f64) -> Value {
+    if v.is_nan() {
+        Value::String("NaN".into())
+    } else if v == f64::INFINITY {
+        Value::String("Infinity".into())
+    } else if v == f64::NEG_INFINITY {
+        Value::String("-Infinity".into())
+    } else {
+        Value::from(v)
+    }
+}
+
+/// Builder of a JSON object omitting fields that hold their default value.
+struct Obj(Map<String, Value>);
+
+impl Obj {
+    fn new() -> Self {
+        Self(Map::new())
+    }
+
+    fn raw(mut self, name: &str, value: Value) -> Self {
+        let _ = self.0.insert(name.to_owned(), value);
+        self
+    }
+
+    fn opt(self, name: &str, value: Option<Value>) -> Self {
+        match value {
+            Some(value) => self.raw(name, value),
+            None => self,
+        }
+    }
+
+    fn list(self, name: &str, values: impl Iterator<Item = Value>) -> Self {
+        let values: Vec<Value> = values.collect();
+        if values.is_empty() {
+            self
+        } else {
+            self.raw(name, Value::Array(values))
+        }
+    }
+
+    fn str(self, name: &str, value: &str) -> Self {
+        if value.is_empty() {
+            self
+        } else {
+            self.raw(name, Value::String(value.to_owned()))
+        }
+    }
+
+    fn bool(self, name: &str, value: bool) -> Self {
+        if value {
+            self.raw(name, Value::Bool(true))
+        } else {
+            self
+        }
+    }
+
+    fn u64(self, name: &str, value: u64) -> Self {
+        if value == 0 {
+            self
+        } else {
+            self.raw(name, uint64(value))
+        }
+    }
+
+    fn u32(self, name: &str, value: u32) -> Self {
+        if value == 0 {
+            self
+        } else {
+            self.raw(name, Value::from(value))
+        }
+    }
+
+    fn i32(self, name: &str, value: i32) -> Self {
+        if value == 0 {
+            self
+        } else {
+            self.raw(name, Value::from(value))
+        }
+    }
+
+    fn f64(self, name: &str, value: f64) -> Self {
+        if value == 0.0 {
+            self
+        } else {
+            self.raw(name, double(value))
+        }
+    }
+
+    fn hex(self, name: &str, value: &[u8]) -> Self {
+        if value.is_empty() {
+            self
+        } else {
+            self.raw(name, Value::String(HEXLOWER.encode(value)))
+        }
+    }
+}
+
+impl From<Obj> for Value {
+    fn from(obj: Obj) -> Self {
+        Value::Object(obj.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::otlp_json::{
+        logs_request_to_proto, metrics_request_to_proto, traces_request_to_proto,
+    };
+    use crate::proto::opentelemetry::common::v1::ArrayValue;
+    use crate::proto::opentelemetry::logs::v1::{ResourceLogs, ScopeLogs};
+    use crate::proto::opentelemetry::metrics::v1::{
+        Gauge, Histogram, HistogramDataPoint, ResourceMetrics, ScopeMetrics,
+    };
+    use crate::proto::opentelemetry::trace::v1::{ResourceSpans, ScopeSpans, Status, span, status};
+
+    fn string_value(s: &str) -> Option<AnyValue> {
+        Some(AnyValue {
+            value: Some(any_value::Value::StringValue(s.into())),
+        })
+    }
+
+    #[test]
+    fn test_logs_round_trip() {
+        let request = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue {
+                        key: "service.name".into(),
+                        value: string_value("svc"),
+                    }],
+                    ..Default::default()
+                }),
+                scope_logs: vec![ScopeLogs {
+                    log_records: vec![LogRecord {
+                        time_unix_nano: u64::MAX,
+                        severity_number: 9,
+                        body: string_value("hello"),
+                        attributes: vec![
+                            KeyValue {
+                                key: "bytes".into(),
+                                value: Some(AnyValue {
+                                    value: Some(any_value::Value::BytesValue(vec![1, 2, 3])),
+                                }),
+                            },
+                            KeyValue {
+                                key: "array".into(),
+                                value: Some(AnyValue {
+                                    value: Some(any_value::Value::ArrayValue(ArrayValue {
+                                        values: vec![AnyValue {
+                                            value: Some(any_value::Value::IntValue(-1)),
+                                        }],
+                                    })),
+                                }),
+                            },
+                        ],
+                        trace_id: vec![0xab; 16],
+                        span_id: vec![0xcd; 8],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let proto = request.encode_to_vec();
+
+        let json = logs_request_to_json(&proto).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let log = &value["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(log["timeUnixNano"], "18446744073709551615");
+        assert_eq!(log["traceId"], "abababababababababababababababab");
+        assert_eq!(log["attributes"][0]["value"]["bytesValue"], "AQID");
+        assert!(log.get("observedTimeUnixNano").is_none());
+
+        assert_eq!(logs_request_to_proto(json.as_bytes()).unwrap(), proto);
+    }
+
+    #[test]
+    fn test_traces_round_trip() {
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![Span {
+                        trace_id: vec![1; 16],
+                        span_id: vec![2; 8],
+                        name: "op".into(),
+                        kind: span::SpanKind::Client as i32,
+                        events: vec![span::Event {
+                            name: "event".into(),
+                            time_unix_nano: 5,
+                            ..Default::default()
+                        }],
+                        status: Some(Status {
+                            code: status::StatusCode::Ok as i32,
+                            message: String::new(),
+                        }),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let proto = request.encode_to_vec();
+        let json = traces_request_to_json(&proto).unwrap();
+        assert_eq!(traces_request_to_proto(json.as_bytes()).unwrap(), proto);
+    }
+
+    #[test]
+    fn test_metrics_round_trip() {
+        let request = ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                scope_metrics: vec![ScopeMetrics {
+                    metrics: vec![
+                        Metric {
+                            name: "gauge".into(),
+                            data: Some(metric::Data::Gauge(Gauge {
+                                data_points: vec![NumberDataPoint {
+                                    // oneof members are kept even when zero
+                                    value: Some(number_data_point::Value::AsInt(0)),
+                                    ..Default::default()
+                                }],
+                            })),
+                            ..Default::default()
+                        },
+                        Metric {
+                            name: "histogram".into(),
+                            data: Some(metric::Data::Histogram(Histogram {
+                                data_points: vec![HistogramDataPoint {
+                                    count: 2,
+                                    sum: Some(f64::INFINITY),
+                                    bucket_counts: vec![1, 1],
+                                    explicit_bounds: vec![0.5],
+                                    ..Default::default()
+                                }],
+                                aggregation_temporality: 1,
+                            })),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let proto = request.encode_to_vec();
+        let json = metrics_request_to_json(&proto).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let metrics = &value["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        assert_eq!(metrics[0]["gauge"]["dataPoints"][0]["asInt"], "0");
+        assert_eq!(metrics[1]["histogram"]["dataPoints"][0]["sum"], "Infinity");
+
+        assert_eq!(metrics_request_to_proto(json.as_bytes()).unwrap(), proto);
+    }
+
+    #[test]
+    fn test_invalid_proto() {
+        assert!(logs_request_to_json(b"\xff\xff").is_err());
+    }
+}
@@ -13,6 +13,8 @@ use async_trait::async_trait;
 use linkme::distributed_slice;
 use otap_df_config::experimental::SignalType;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::ReceiverFactory;
 use otap_df_engine::config::ReceiverConfig;
 use otap_df_engine::context::PipelineContext;
@@ -25,6 +27,7 @@ use otap_df_engine::terminal_state::TerminalState;
 use otap_df_telemetry::instrument::Counter;
 use otap_df_telemetry::metrics::MetricSet;
 use otap_df_telemetry_macros::metric_set;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json::Value;
 use std::net::SocketAddr;
@@ -39,7 +42,7 @@ use tonic::transport::Server;
 pub const OTLP_RECEIVER_URN: &str = "urn:otel:otlp:receiver";
 
 /// Configuration for OTLP Receiver
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     /// The endpoint details: protocol, name, port.
@@ -66,6 +69,14 @@ pub struct Config {
     /// see a failure, errors are effectively suppressed.
     #[serde(default = "default_wait_for_result")]
     wait_for_result: bool,
+
+    /// Whether to convert received logs and traces to OTAP Arrow records (default: false)
+    ///
+    /// By default the requests are forwarded as serialized OTLP bytes and only converted when a
+    /// downstream component requires it. Enabling this performs the conversion in the receiver,
+    /// so a request that cannot be converted is refused with `INVALID_ARGUMENT`.
+    #[serde(default)]
+    convert_to_otap: bool,
 }
 
 const fn default_max_concurrent_requests() -> usize {
@@ -110,13 +121,19 @@ pub static OTLP_RECEIVER: ReceiverFactory<OtapPdata> = ReceiverFactory {
     },
 };
 
+/// Schema of the configuration of the OTLP receiver
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static OTLP_RECEIVER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(OTLP_RECEIVER_URN);
+
 impl OTLPReceiver {
     /// Creates a new OTLPReceiver from a configuration object
     pub fn from_config(
         pipeline_ctx: PipelineContext,
         config: &Value,
     ) -> Result<Self, otap_df_config::error::Error> {
-        let config: Config = serde_json::from_value(config.clone()).map_err(|e| {
+        let config: Config = parse_user_config(config).map_err(|e| {
             otap_df_config::error::Error::InvalidUserConfig {
                 error: e.to_string(),
             }
@@ -233,6 +250,7 @@ impl shared::Receiver<OtapPdata> for OTLPReceiver {
             wait_for_result: self.config.wait_for_result,
             accept_compression_encodings: compression,
             send_compression_encodings: compression,
+            convert_to_otap: self.config.convert_to_otap,
         };
 
         let logs_server = LogsServiceServer::new(effect_handler.clone(), &settings);
@@ -314,7 +332,7 @@ impl shared::Receiver<OtapPdata> for OTLPReceiver {
 mod tests {
     use super::*;
 
-    use crate::pdata::OtlpProtoBytes;
+    use crate::pdata::{OtapPayload, OtlpProtoBytes};
     use crate::proto::opentelemetry::collector::logs::v1::logs_service_client::LogsServiceClient;
     use crate::proto::opentelemetry::collector::logs::v1::{
         ExportLogsServiceRequest, ExportLogsServiceResponse,
@@ -342,6 +360,7 @@ mod tests {
         test_node,
     };
     use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::otap::OtapArrowRecords;
     use prost::Message;
     use std::pin::Pin;
     use std::time::{Duration, Instant};
@@ -632,6 +651,7 @@ mod tests {
                     listening_addr: addr,
                     compression_method: None,
                     max_concurrent_requests: 1000,
+                    convert_to_otap: false,
                 },
                 metrics: pipeline_ctx.register_metrics::<OtlpReceiverMetrics>(),
             },
@@ -669,6 +689,7 @@ mod tests {
                     listening_addr: addr,
                     compression_method: None,
                     max_concurrent_requests: 1000,
+                    convert_to_otap: false,
                 },
                 metrics: pipeline_ctx.register_metrics::<OtlpReceiverMetrics>(),
             },
@@ -721,4 +742,87 @@ mod tests {
             .run_test(nack_scenario)
             .run_validation_concurrent(nack_validation);
     }
+
+    #[test]
+    fn test_otlp_receiver_convert_to_otap() {
+        let test_runtime = TestRuntime::new();
+
+        let grpc_addr = "127.0.0.1";
+        let grpc_port = portpicker::pick_unused_port().expect("No free ports");
+        let grpc_endpoint = format!("http://{grpc_addr}:{grpc_port}");
+
+        let node_config = Arc::new(NodeUserConfig::new_receiver_config(OTLP_RECEIVER_URN));
+
+        let metrics_registry_handle = MetricsRegistryHandle::new();
+        let controller_ctx = ControllerContext::new(metrics_registry_handle);
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+
+        let receiver = ReceiverWrapper::shared(
+            OTLPReceiver::from_config(
+                pipeline_ctx,
+                &serde_json::json!({
+                    "listening_addr": format!("{grpc_addr}:{grpc_port}"),
+                    "convert_to_otap": true,
+                }),
+            )
+            .unwrap(),
+            test_node(test_runtime.config().name.clone()),
+            node_config,
+            test_runtime.config(),
+        );
+
+        let convert_scenario = move |ctx: TestContext<OtapPdata>| {
+            Box::pin(async move {
+                let mut logs_client = LogsServiceClient::connect(grpc_endpoint.clone())
+                    .await
+                    .expect("Failed to connect to server from Logs Service Client");
+                _ = logs_client
+                    .export(create_logs_service_request())
+                    .await
+                    .expect("Can send log request");
+
+                let mut metrics_client = MetricsServiceClient::connect(grpc_endpoint.clone())
+                    .await
+                    .expect("Failed to connect to server from Metrics Service Client");
+                _ = metrics_client
+                    .export(create_metrics_service_request())
+                    .await
+                    .expect("can send metrics request");
+
+                ctx.send_shutdown(Instant::now(), "Test complete")
+                    .await
+                    .expect("Failed to send shutdown");
+            }) as Pin<Box<dyn Future<Output = ()>>>
+        };
+
+        let convert_validation = |mut ctx: NotSendValidateContext<OtapPdata>| {
+            Box::pin(async move {
+                let logs_pdata = timeout(Duration::from_secs(3), ctx.recv())
+                    .await
+                    .expect("Timed out waiting for logs message")
+                    .expect("No logs message received");
+                assert_eq!(logs_pdata.num_items(), 2);
+                assert!(matches!(
+                    logs_pdata.payload(),
+                    OtapPayload::OtapArrowRecords(OtapArrowRecords::Logs(_))
+                ));
+
+                // metrics are not converted
+                let metrics_pdata = timeout(Duration::from_secs(3), ctx.recv())
+                    .await
+                    .expect("Timed out waiting for metrics message")
+                    .expect("No metrics message received");
+                assert!(matches!(
+                    metrics_pdata.payload(),
+                    OtapPayload::OtlpBytes(OtlpProtoBytes::ExportMetricsRequest(_))
+                ));
+            }) as Pin<Box<dyn Future<Output = ()>>>
+        };
+
+        test_runtime
+            .set_receiver(receiver)
+            .run_test(convert_scenario)
+            .run_validation_concurrent(convert_validation);
+    }
 }
@@ -36,7 +36,9 @@ use futures::{FutureExt, pin_mut};
 use futures_timer::Delay;
 use linkme::distributed_slice;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
 use otap_df_engine::ExporterFactory;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::config::ExporterConfig;
 use otap_df_engine::context::PipelineContext;
 use otap_df_engine::control::NodeControlMsg;
@@ -89,6 +91,12 @@ pub static PARQUET_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
     },
 };
 
+/// Schema of the configuration of the parquet exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static PARQUET_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<config::Config>(PARQUET_EXPORTER_URN);
+
 impl ParquetExporter {
     /// construct a new instance of the `ParquetExporter`
     #[must_use]
@@ -107,7 +115,7 @@ impl ParquetExporter {
         pipeline_ctx: PipelineContext,
         config: &serde_json::Value,
     ) -> Result<Self, otap_df_config::error::Error> {
-        let config: config::Config = serde_json::from_value(config.clone()).map_err(|e| {
+        let config: config::Config = parse_user_config(config).map_err(|e| {
             otap_df_config::error::Error::InvalidUserConfig {
                 error: e.to_string(),
             }
@@ -1015,6 +1023,7 @@ mod test {
             writer_options: Some(WriterOptions {
                 target_rows_per_file: None,
                 flush_when_older_than: Some(Duration::from_millis(200)),
+                ..Default::default()
             }),
         });
 
@@ -1574,4 +1583,18 @@ mod test {
                 })
             });
     }
+
+    #[test]
+    fn test_parquet_exporter_conformance() {
+        use serde_json::json;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base_dir: String = temp_dir.path().to_str().unwrap().into();
+        // the parquet exporter does not notify subscribers
+        crate::testing::conformance::run_exporter_conformance(
+            &PARQUET_EXPORTER,
+            json!({ "base_uri": base_dir }),
+            None,
+        );
+    }
 }
@@ -3,10 +3,13 @@
 
 use std::time::Duration;
 
+use parquet::basic::{BrotliLevel, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 /// Configuration of parquet exporter
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, JsonSchema, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     /// The base URI for where the parquet files should be written
@@ -19,7 +22,7 @@ pub struct Config {
     pub writer_options: Option<WriterOptions>,
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
 pub struct WriterOptions {
     /// Target number of rows in one parquet file. The writer will flush automatically any files
     /// that attain greater than this number of rows. If this is `None`, the writer won't flush
@@ -42,7 +45,21 @@ pub struct WriterOptions {
     /// details see [`Self::flush_age_check_interval`]
     #[serde(default)]
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
     pub flush_when_older_than: Option<Duration>,
+
+    /// Compression codec used for the column chunks of the parquet files.
+    ///
+    /// Default = uncompressed.
+    #[serde(default)]
+    pub compression: Option<Compression>,
+
+    /// Maximum number of rows in one row group of the parquet files. Smaller row groups reduce
+    /// the memory used while writing at the expense of less efficient compression and reads.
+    ///
+    /// Default = the parquet writer default (1024 * 1024 rows).
+    #[serde(default)]
+    pub max_row_group_size: Option<usize>,
 }
 
 impl Default for WriterOptions {
@@ -50,16 +67,81 @@ impl Default for WriterOptions {
         Self {
             flush_when_older_than: None,
             target_rows_per_file: Some(100_000_000),
+            compression: None,
+            max_row_group_size: None,
+        }
+    }
+}
+
+impl WriterOptions {
+    /// The properties of the parquet writers created with these options
+    pub fn writer_properties(&self) -> WriterProperties {
+        let mut builder = WriterProperties::builder();
+        if let Some(compression) = self.compression {
+            builder = builder.set_compression(compression.into());
+        }
+        if let Some(max_row_group_size) = self.max_row_group_size {
+            builder = builder.set_max_row_group_size(max_row_group_size);
+        }
+        builder.build()
+    }
+}
+
+/// Compression codecs for the parquet files. Codecs with levels use their default level.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    /// No compression
+    Uncompressed,
+    /// Snappy compression
+    Snappy,
+    /// Gzip compression
+    Gzip,
+    /// LZ4 raw compression
+    Lz4,
+    /// Zstandard compression
+    Zstd,
+    /// Brotli compression
+    Brotli,
+}
+
+impl From<Compression> for parquet::basic::Compression {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::Uncompressed => Self::UNCOMPRESSED,
+            Compression::Snappy => Self::SNAPPY,
+            Compression::Gzip => Self::GZIP(GzipLevel::default()),
+            Compression::Lz4 => Self::LZ4_RAW,
+            Compression::Zstd => Self::ZSTD(ZstdLevel::default()),
+            Compression::Brotli => Self::BROTLI(BrotliLevel::default()),
         }
     }
 }
 
 /// Configuration options for how the parquet files should be partitioned
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, JsonSchema, PartialEq)]
 pub enum PartitioningStrategy {
     /// compute partition values from schema metadata keys
     #[serde(alias = "schema_metadata")]
     SchemaMetadata(Vec<String>),
+
+    /// partition by the signal of the data (`signal=logs|metrics|traces`). Without it, the
+    /// payload types shared between signals (e.g. resource attributes) of all the signals are
+    /// written to the same files.
+    #[serde(alias = "signal")]
+    Signal,
+
+    /// partition by the time window, of the given duration, in which the data was received
+    /// (`window_start=<UTC start of the window>`). Windows are aligned on the unix epoch.
+    ///
+    /// The files of a past window are closed by the flush policies of the writer, so
+    /// [`WriterOptions::flush_when_older_than`] should be set to about the window duration.
+    #[serde(alias = "time_window")]
+    TimeWindow(
+        #[serde(with = "humantime_serde")]
+        #[schemars(with = "String")]
+        Duration,
+    ),
 }
 
 #[cfg(test)]
@@ -73,23 +155,33 @@ mod test {
             \"partitioning_strategies\": [
                 {
                     \"schema_metadata\": [ \"_part_id\" ]
+                },
+                \"signal\",
+                {
+                    \"time_window\": \"1h\"
                 }
             ],
             \"writer_options\": {
                 \"target_rows_per_file\": 1000000000,
-                \"flush_when_older_than\": \"5m\"
+                \"flush_when_older_than\": \"5m\",
+                \"compression\": \"zstd\",
+                \"max_row_group_size\": 65536
             }
         }";
 
         let config: Config = serde_json::from_str(json_cfg).unwrap();
         let expected = Config {
             base_uri: "s3://albert-bucket/parquet-files".to_string(),
-            partitioning_strategies: Some(vec![PartitioningStrategy::SchemaMetadata(vec![
-                "_part_id".to_string(),
-            ])]),
+            partitioning_strategies: Some(vec![
+                PartitioningStrategy::SchemaMetadata(vec!["_part_id".to_string()]),
+                PartitioningStrategy::Signal,
+                PartitioningStrategy::TimeWindow(Duration::from_secs(3600)),
+            ]),
             writer_options: Some(WriterOptions {
                 flush_when_older_than: Some(Duration::from_secs(300)),
                 target_rows_per_file: Some(1000000000),
+                compression: Some(Compression::Zstd),
+                max_row_group_size: Some(65536),
             }),
         };
         assert_eq!(config, expected)
@@ -110,4 +202,25 @@ mod test {
         }";
         assert!(serde_json::from_str::<Config>(json_cfg).is_err())
     }
+
+    #[test]
+    fn test_writer_properties() {
+        let props = WriterOptions::default().writer_properties();
+        assert_eq!(
+            props.compression(&"col".into()),
+            parquet::basic::Compression::UNCOMPRESSED
+        );
+
+        let props = WriterOptions {
+            compression: Some(Compression::Zstd),
+            max_row_group_size: Some(1000),
+            ..Default::default()
+        }
+        .writer_properties();
+        assert_eq!(
+            props.compression(&"col".into()),
+            parquet::basic::Compression::ZSTD(ZstdLevel::default())
+        );
+        assert_eq!(props.max_row_group_size(), 1000);
+    }
 }
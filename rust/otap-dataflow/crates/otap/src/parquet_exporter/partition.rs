@@ -1,6 +1,9 @@
 // Copyright The OpenTelemetry Authors
 // SPDX-License-Identifier: Apache-2.0
 
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::DateTime;
 use otel_arrow_rust::{
     otap::OtapArrowRecords, proto::opentelemetry::arrow::v1::ArrowPayloadType,
     schema::get_schema_metadata,
@@ -43,6 +46,10 @@ pub fn partition(
             PartitioningStrategy::SchemaMetadata(metadata_keys) => attributes.append(
                 &mut static_partitions_from_schema_metadata(otap_batch, metadata_keys),
             ),
+            PartitioningStrategy::Signal => attributes.push(signal_partition(otap_batch)),
+            PartitioningStrategy::TimeWindow(window) => {
+                attributes.push(time_window_partition(*window, SystemTime::now()))
+            }
         }
     }
 
@@ -52,6 +59,31 @@ pub fn partition(
     }]
 }
 
+fn signal_partition(otap_batch: &OtapArrowRecords) -> PartitionAttribute {
+    let signal = match otap_batch {
+        OtapArrowRecords::Logs(_) => "logs",
+        OtapArrowRecords::Metrics(_) => "metrics",
+        OtapArrowRecords::Traces(_) => "traces",
+    };
+    PartitionAttribute {
+        key: "signal".to_string(),
+        value: PartitionAttributeValue::String(signal.to_string()),
+    }
+}
+
+/// Computes the partition of the time window containing `now`. The value is the start of the
+/// window formatted as a basic ISO 8601 UTC timestamp, which is safe to use in object paths.
+fn time_window_partition(window: Duration, now: SystemTime) -> PartitionAttribute {
+    let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let window_secs = window.as_secs().max(1);
+    let start = since_epoch - since_epoch % window_secs;
+    let start = DateTime::from_timestamp(start as i64, 0).unwrap_or_default();
+    PartitionAttribute {
+        key: "window_start".to_string(),
+        value: PartitionAttributeValue::String(start.format("%Y%m%dT%H%M%SZ").to_string()),
+    }
+}
+
 fn static_partitions_from_schema_metadata(
     otap_batch: &OtapArrowRecords,
     metadata_keys: &[String],
@@ -140,6 +172,34 @@ pub mod test {
         assert!(attrs.is_empty());
     }
 
+    #[test]
+    fn test_partition_with_signal_and_time_window_strategies() {
+        let otap_batch = make_otap_batch_with_metadata("present", "yes");
+        let strategies = vec![
+            PartitioningStrategy::Signal,
+            PartitioningStrategy::TimeWindow(Duration::from_secs(3600)),
+        ];
+        let partitions = partition(&otap_batch, &strategies);
+        assert_eq!(partitions.len(), 1);
+        let attrs = partitions[0].attributes.as_ref().unwrap();
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].key, "signal");
+        assert_eq!(attrs[0].value.to_string(), "logs");
+        assert_eq!(attrs[1].key, "window_start");
+    }
+
+    #[test]
+    fn test_time_window_partition() {
+        // 2025-01-02T03:04:05Z
+        let now = UNIX_EPOCH + Duration::from_secs(1_735_787_045);
+        let attr = time_window_partition(Duration::from_secs(3600), now);
+        assert_eq!(attr.value.to_string(), "20250102T030000Z");
+        let attr = time_window_partition(Duration::from_secs(15 * 60), now);
+        assert_eq!(attr.value.to_string(), "20250102T030000Z");
+        let attr = time_window_partition(Duration::from_secs(60), now);
+        assert_eq!(attr.value.to_string(), "20250102T030400Z");
+    }
+
     #[test]
     fn test_partition_display_trait() {
         let attr_val = PartitionAttributeValue::String("hello".to_string());
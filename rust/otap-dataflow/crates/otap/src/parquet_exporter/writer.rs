@@ -74,6 +74,7 @@ impl<'a> WriteBatch<'a> {
 pub struct WriterManager {
     object_store: Arc<dyn ObjectStore>,
     options: WriterOptions,
+    writer_properties: WriterProperties,
 
     // the current filename used for each path prefix. Prefixes in this case are based
     // on the payload type, and the partition attributes. There can be multiple files
@@ -96,6 +97,7 @@ impl WriterManager {
     pub fn new(object_store: Arc<dyn ObjectStore>, options: WriterOptions) -> Self {
         Self {
             object_store,
+            writer_properties: options.writer_properties(),
             options,
             curr_writer_for_prefix: HashMap::new(),
             unflushed_batches_state: UnflushedBatchState::new(),
@@ -185,6 +187,7 @@ impl WriterManager {
                         self.object_store.clone(),
                         record_batch.schema(),
                         full_path,
+                        self.writer_properties.clone(),
                     ),
                 ))
             }
@@ -243,13 +246,16 @@ impl WriterManager {
     }
 
     fn should_flush(&self, file_writer: &FileWriter) -> bool {
-        if let Some(target_rows_per_file) = self.options.target_rows_per_file {
-            file_writer.rows_written >= target_rows_per_file
-        } else if let Some(flush_when_older_than) = self.options.flush_when_older_than {
-            file_writer.created_at.elapsed() > flush_when_older_than
-        } else {
-            false // If no target rows per file is set, don't flush automatically
-        }
+        // If neither threshold is set, don't flush automatically
+        self.options
+            .target_rows_per_file
+            .is_some_and(|target_rows_per_file| file_writer.rows_written >= target_rows_per_file)
+            || self
+                .options
+                .flush_when_older_than
+                .is_some_and(|flush_when_older_than| {
+                    file_writer.created_at.elapsed() > flush_when_older_than
+                })
     }
 
     /// This method attempts to flush all the scheduled writers. It tries to flush child
@@ -361,9 +367,10 @@ fn new_parquet_arrow_writer(
     object_store: Arc<dyn ObjectStore>,
     schema: SchemaRef,
     full_path: String,
+    writer_properties: WriterProperties,
 ) -> AsyncArrowWriter<ParquetObjectWriter> {
     let object_writer = ParquetObjectWriter::new(object_store.clone(), full_path.clone().into());
-    AsyncArrowWriter::try_new(object_writer, schema, Some(WriterProperties::default()))
+    AsyncArrowWriter::try_new(object_writer, schema, Some(writer_properties))
         .expect("Failed to create AsyncArrowWriter")
 }
 
@@ -78,9 +78,14 @@
 // directly from OTAP -> OTLP bytes. The utility functions we use might change as part of
 // this diagram may need to be updated (https://github.com/open-telemetry/otel-arrow/issues/1095)
 
+use arrow::array::RecordBatch;
+use arrow::error::ArrowError;
 use async_trait::async_trait;
 use otap_df_config::experimental::SignalType;
 use otap_df_engine::error::Error;
+use otap_df_engine::in_flight::InFlightData;
+use otap_df_engine::latency::LatencyTrackedData;
+use otap_df_engine::memory::{MemoryAccountedData, MemoryReservation};
 use otap_df_engine::{
     ConsumerEffectHandlerExtension, Interests, ProducerEffectHandlerExtension,
     control::{AckMsg, CallData, NackMsg},
@@ -92,13 +97,25 @@ use otel_arrow_rust::otlp::logs::LogsProtoBytesEncoder;
 use otel_arrow_rust::otlp::metrics::MetricsProtoBytesEncoder;
 use otel_arrow_rust::otlp::traces::TracesProtoBytesEncoder;
 use otel_arrow_rust::otlp::{ProtoBuffer, ProtoBytesEncoder};
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::schema::consts;
+use std::time::Instant;
 
 use crate::encoder::{encode_logs_otap_batch, encode_spans_otap_batch};
+use attributes::AttributeValue;
+
+pub mod attributes;
 
 /// Context for OTAP requests
 #[derive(Clone, Debug, Default)]
 pub struct Context {
     stack: Vec<Frame>,
+    /// Id of the pdata in flight in an exporter tracking its Ack deadline.
+    in_flight: Option<u64>,
+    /// Memory of the pdata charged against the budget of the pipeline, shared by its clones.
+    memory: Option<MemoryReservation>,
+    /// Instant the pdata was enqueued in the input channel of its current node.
+    enqueued_at: Option<Instant>,
 }
 
 impl Context {
@@ -170,6 +187,18 @@ impl Context {
             .unwrap_or(false)
     }
 
+    /// Returns the payload to hand to an exporter's destination. The payload is cloned if the
+    /// context requests it returned with the Ack/Nack, otherwise it is taken, leaving `payload`
+    /// empty.
+    #[must_use]
+    pub fn take_payload_for_export(&self, payload: &mut OtapPayload) -> OtapPayload {
+        if self.may_return_payload() {
+            payload.clone()
+        } else {
+            payload.take_payload()
+        }
+    }
+
     /// Return the current calldata.
     #[must_use]
     pub fn current_calldata(&self) -> Option<CallData> {
@@ -199,6 +228,13 @@ pub mod error {
             /// The error that occurred
             error: String,
         },
+
+        /// An attribute record batch does not have the expected schema
+        #[error("Invalid attribute record batch: {error}")]
+        InvalidAttributes {
+            /// The error that occurred
+            error: String,
+        },
     }
 
     impl From<Error> for otap_df_engine::error::Error {
@@ -210,6 +246,13 @@ pub mod error {
     }
 }
 
+/// Returns the error of a processor failing to transform or evaluate the pdata.
+pub(crate) fn pdata_error(msg: &str) -> Error {
+    Error::PdataConversionError {
+        error: msg.to_string(),
+    }
+}
+
 /// Pipeline data represented as protobuf serialized OTLP request messages
 #[derive(Clone, Debug)]
 pub enum OtlpProtoBytes {
@@ -322,6 +365,12 @@ impl OtapPdata {
         self.payload.num_items()
     }
 
+    /// Returns the approximate size of the payload in bytes.
+    #[must_use]
+    pub fn num_bytes(&self) -> usize {
+        self.payload.num_bytes()
+    }
+
     /// Enable testing Ack/Nack without an effect handler. Consumes,
     /// modifies and returns self.
     #[cfg(test)]
@@ -341,6 +390,18 @@ impl OtapPdata {
     pub fn current_calldata(&self) -> Option<CallData> {
         self.context.current_calldata()
     }
+
+    /// Returns a typed view over the logs record batches, or `None` if the payload is not
+    /// OTAP Arrow logs. See [`OtapPayload::logs_view`].
+    pub fn logs_view(&mut self) -> Result<Option<OtapLogsView<'_>>, ArrowError> {
+        self.payload.logs_view()
+    }
+
+    /// Returns a typed view over the traces record batches, or `None` if the payload is not
+    /// OTAP Arrow traces. See [`OtapPayload::traces_view`].
+    pub fn traces_view(&mut self) -> Result<Option<OtapTracesView<'_>>, ArrowError> {
+        self.payload.traces_view()
+    }
 }
 
 impl OtapPayload {
@@ -381,6 +442,202 @@ impl OtapPayload {
             Self::OtapArrowRecords(value) => value.num_items(),
         }
     }
+
+    /// Returns the approximate size of the payload in bytes: the encoded size of OTLP bytes, or
+    /// the in-memory size of the Arrow record batches.
+    #[must_use]
+    pub fn num_bytes(&self) -> usize {
+        match self {
+            Self::OtlpBytes(value) => value.as_bytes().len(),
+            Self::OtapArrowRecords(records) => records
+                .allowed_payload_types()
+                .iter()
+                .filter_map(|payload_type| records.get(*payload_type))
+                .map(|batch| batch.get_array_memory_size())
+                .sum(),
+        }
+    }
+
+    /// Returns a typed view over the logs record batches.
+    ///
+    /// Returns `None` if the payload is not represented as OTAP Arrow logs or if it does not
+    /// contain a `Logs` record batch. OTLP bytes payloads must first be converted into
+    /// [`OtapArrowRecords`]. The transport-optimized ids are decoded first (see [`decode_ids`]),
+    /// so that the attributes of the view are joined to the right rows.
+    pub fn logs_view(&mut self) -> Result<Option<OtapLogsView<'_>>, ArrowError> {
+        match self {
+            Self::OtapArrowRecords(records @ OtapArrowRecords::Logs(_)) => {
+                decode_ids(records)?;
+                Ok(OtapLogsView::new(records))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns a typed view over the traces record batches.
+    ///
+    /// Returns `None` if the payload is not represented as OTAP Arrow traces or if it does not
+    /// contain a `Spans` record batch. OTLP bytes payloads must first be converted into
+    /// [`OtapArrowRecords`]. The transport-optimized ids are decoded first (see [`decode_ids`]),
+    /// so that the attributes, events, and links of the view are joined to the right rows.
+    pub fn traces_view(&mut self) -> Result<Option<OtapTracesView<'_>>, ArrowError> {
+        match self {
+            Self::OtapArrowRecords(records @ OtapArrowRecords::Traces(_)) => {
+                decode_ids(records)?;
+                Ok(OtapTracesView::new(records))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/* -------- Typed views -------- */
+
+/// Borrowed view over the record batches of an OTAP logs payload.
+///
+/// The attribute batches are related to the main `logs` batch through their `parent_id`
+/// column: `log_attrs` to the `id` column of `logs`, `resource_attrs` to `resource.id`, and
+/// `scope_attrs` to `scope.id`.
+#[derive(Clone, Copy, Debug)]
+pub struct OtapLogsView<'a> {
+    /// Log records, one row per log record.
+    pub logs: &'a RecordBatch,
+    /// Attributes of the log records.
+    pub log_attrs: Option<&'a RecordBatch>,
+    /// Attributes of the resources.
+    pub resource_attrs: Option<&'a RecordBatch>,
+    /// Attributes of the instrumentation scopes.
+    pub scope_attrs: Option<&'a RecordBatch>,
+}
+
+impl<'a> OtapLogsView<'a> {
+    fn new(records: &'a OtapArrowRecords) -> Option<Self> {
+        Some(Self {
+            logs: records.get(ArrowPayloadType::Logs)?,
+            log_attrs: records.get(ArrowPayloadType::LogAttrs),
+            resource_attrs: records.get(ArrowPayloadType::ResourceAttrs),
+            scope_attrs: records.get(ArrowPayloadType::ScopeAttrs),
+        })
+    }
+
+    /// Number of log records in the view.
+    #[must_use]
+    pub fn num_logs(&self) -> usize {
+        self.logs.num_rows()
+    }
+
+    /// Value of the attribute `key` of each log record. See [`attributes::row_attribute_values`].
+    pub fn log_attribute(
+        &self,
+        key: &str,
+    ) -> Result<Vec<Option<AttributeValue<'a>>>, error::Error> {
+        attributes::row_attribute_values(self.logs, None, self.log_attrs, key)
+    }
+
+    /// Value of the resource attribute `key` of each log record.
+    pub fn resource_attribute(
+        &self,
+        key: &str,
+    ) -> Result<Vec<Option<AttributeValue<'a>>>, error::Error> {
+        attributes::row_attribute_values(
+            self.logs,
+            Some(consts::RESOURCE),
+            self.resource_attrs,
+            key,
+        )
+    }
+
+    /// Value of the scope attribute `key` of each log record.
+    pub fn scope_attribute(
+        &self,
+        key: &str,
+    ) -> Result<Vec<Option<AttributeValue<'a>>>, error::Error> {
+        attributes::row_attribute_values(self.logs, Some(consts::SCOPE), self.scope_attrs, key)
+    }
+}
+
+/// Borrowed view over the record batches of an OTAP traces payload.
+///
+/// The attribute, event, and link batches are related to the main `spans` batch through their
+/// `parent_id` column (`span_event_attrs` and `span_link_attrs` point at `span_events` and
+/// `span_links` respectively).
+#[derive(Clone, Copy, Debug)]
+pub struct OtapTracesView<'a> {
+    /// Spans, one row per span.
+    pub spans: &'a RecordBatch,
+    /// Attributes of the spans.
+    pub span_attrs: Option<&'a RecordBatch>,
+    /// Events recorded on the spans.
+    pub span_events: Option<&'a RecordBatch>,
+    /// Links recorded on the spans.
+    pub span_links: Option<&'a RecordBatch>,
+    /// Attributes of the span events.
+    pub span_event_attrs: Option<&'a RecordBatch>,
+    /// Attributes of the span links.
+    pub span_link_attrs: Option<&'a RecordBatch>,
+    /// Attributes of the resources.
+    pub resource_attrs: Option<&'a RecordBatch>,
+    /// Attributes of the instrumentation scopes.
+    pub scope_attrs: Option<&'a RecordBatch>,
+}
+
+impl<'a> OtapTracesView<'a> {
+    fn new(records: &'a OtapArrowRecords) -> Option<Self> {
+        Some(Self {
+            spans: records.get(ArrowPayloadType::Spans)?,
+            span_attrs: records.get(ArrowPayloadType::SpanAttrs),
+            span_events: records.get(ArrowPayloadType::SpanEvents),
+            span_links: records.get(ArrowPayloadType::SpanLinks),
+            span_event_attrs: records.get(ArrowPayloadType::SpanEventAttrs),
+            span_link_attrs: records.get(ArrowPayloadType::SpanLinkAttrs),
+            resource_attrs: records.get(ArrowPayloadType::ResourceAttrs),
+            scope_attrs: records.get(ArrowPayloadType::ScopeAttrs),
+        })
+    }
+
+    /// Number of spans in the view.
+    #[must_use]
+    pub fn num_spans(&self) -> usize {
+        self.spans.num_rows()
+    }
+
+    /// Value of the attribute `key` of each span. See [`attributes::row_attribute_values`].
+    pub fn span_attribute(
+        &self,
+        key: &str,
+    ) -> Result<Vec<Option<AttributeValue<'a>>>, error::Error> {
+        attributes::row_attribute_values(self.spans, None, self.span_attrs, key)
+    }
+
+    /// Value of the resource attribute `key` of each span.
+    pub fn resource_attribute(
+        &self,
+        key: &str,
+    ) -> Result<Vec<Option<AttributeValue<'a>>>, error::Error> {
+        attributes::row_attribute_values(
+            self.spans,
+            Some(consts::RESOURCE),
+            self.resource_attrs,
+            key,
+        )
+    }
+
+    /// Value of the scope attribute `key` of each span.
+    pub fn scope_attribute(
+        &self,
+        key: &str,
+    ) -> Result<Vec<Option<AttributeValue<'a>>>, error::Error> {
+        attributes::row_attribute_values(self.spans, Some(consts::SCOPE), self.scope_attrs, key)
+    }
+}
+
+/// Decodes the transport-optimized (delta and quasi-delta encoded) ids of `records`, so that the
+/// attributes and the child rows can be looked up by the ids of their parent rows. Processors
+/// and exporters evaluating attributes or filtering rows call it first.
+pub fn decode_ids(records: &mut OtapArrowRecords) -> Result<(), ArrowError> {
+    records
+        .decode_transport_optimized_ids()
+        .map_err(|e| ArrowError::InvalidArgumentError(e.to_string()))
 }
 
 /* -------- Trait implementations -------- */
@@ -419,18 +676,15 @@ impl OtapPayloadHelpers for OtapArrowRecords {
 
     fn is_empty(&self) -> bool {
         match self {
-            Self::Logs(_) => {
-                self.get(otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType::Logs)
-                    .is_none_or(|batch| batch.num_rows() == 0)
-            }
-            Self::Traces(_) => {
-                self.get(otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType::Spans)
-                    .is_none_or(|batch| batch.num_rows() == 0)
-            }
-            Self::Metrics(_) => {
-                self.get(otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType::UnivariateMetrics)
-                    .is_none_or(|batch| batch.num_rows() == 0)
-            }
+            Self::Logs(_) => self
+                .get(ArrowPayloadType::Logs)
+                .is_none_or(|batch| batch.num_rows() == 0),
+            Self::Traces(_) => self
+                .get(ArrowPayloadType::Spans)
+                .is_none_or(|batch| batch.num_rows() == 0),
+            Self::Metrics(_) => self
+                .get(ArrowPayloadType::UnivariateMetrics)
+                .is_none_or(|batch| batch.num_rows() == 0),
         }
     }
 
@@ -630,6 +884,16 @@ impl ProducerEffectHandlerExtension<OtapPdata>
     }
 }
 
+#[async_trait(?Send)]
+impl ProducerEffectHandlerExtension<OtapPdata>
+    for otap_df_engine::local::exporter::EffectHandler<OtapPdata>
+{
+    fn subscribe_to(&self, int: Interests, ctx: CallData, data: &mut OtapPdata) {
+        data.context
+            .subscribe_to(int, ctx, self.exporter_id().index)
+    }
+}
+
 #[async_trait(?Send)]
 impl ProducerEffectHandlerExtension<OtapPdata>
     for otap_df_engine::shared::processor::EffectHandler<OtapPdata>
@@ -704,10 +968,86 @@ impl ConsumerEffectHandlerExtension<OtapPdata>
     }
 }
 
+/* -------- Ack deadline tracking -------- */
+
+impl InFlightData for OtapPdata {
+    fn set_in_flight_id(&mut self, id: u64) {
+        self.context.in_flight = Some(id);
+    }
+
+    fn in_flight_id(&self) -> Option<u64> {
+        self.context.in_flight
+    }
+
+    fn deadline_nack_data(&self) -> Option<Self> {
+        // the Nack is routed to the most recent subscriber to Nacks
+        let subscriber = self
+            .context
+            .stack
+            .iter()
+            .rev()
+            .find(|frame| frame.interests.contains(Interests::NACKS))?;
+        let payload = if subscriber.interests.contains(Interests::RETURN_DATA) {
+            // A copy of the payload, for the subscriber to retry it, e.g. a retry processor.
+            self.payload.clone()
+        } else {
+            // The payload stays with the exporter, the subscriber only gets the signal type.
+            match self.signal_type() {
+                SignalType::Logs => OtlpProtoBytes::ExportLogsRequest(Vec::new()),
+                SignalType::Metrics => OtlpProtoBytes::ExportMetricsRequest(Vec::new()),
+                SignalType::Traces => OtlpProtoBytes::ExportTracesRequest(Vec::new()),
+            }
+            .into()
+        };
+        let mut context = self.context.clone();
+        context.memory = None;
+        Some(OtapPdata::new(context, payload))
+    }
+
+    fn next_nack(nack: NackMsg<Self>) -> Option<(usize, NackMsg<Self>)> {
+        Context::next_nack(nack)
+    }
+}
+
+impl MemoryAccountedData for OtapPdata {
+    fn memory_size(&self) -> usize {
+        match &self.payload {
+            // the allocated buffer, which may be larger than the encoded request
+            OtapPayload::OtlpBytes(
+                OtlpProtoBytes::ExportLogsRequest(bytes)
+                | OtlpProtoBytes::ExportMetricsRequest(bytes)
+                | OtlpProtoBytes::ExportTracesRequest(bytes),
+            ) => bytes.capacity(),
+            OtapPayload::OtapArrowRecords(_) => self.num_bytes(),
+        }
+    }
+
+    fn memory_reservation(&self) -> Option<&MemoryReservation> {
+        self.context.memory.as_ref()
+    }
+
+    fn set_memory_reservation(&mut self, reservation: MemoryReservation) {
+        self.context.memory = Some(reservation);
+    }
+}
+
+impl LatencyTrackedData for OtapPdata {
+    fn enqueued_at(&self) -> Option<Instant> {
+        self.context.enqueued_at
+    }
+
+    fn set_enqueued_at(&mut self, at: Option<Instant>) {
+        self.context.enqueued_at = at;
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::testing::{TestCallData, create_test_logs, create_test_pdata};
+    use arrow::array::AsArray;
+    use arrow::datatypes::TimestampNanosecondType;
+    use otap_df_engine::control::NackClass;
     use otel_arrow_rust::{
         otap::OtapArrowRecords,
         proto::opentelemetry::{
@@ -1086,6 +1426,79 @@ mod test {
         assert_eq!(pdata_metrics.signal_type(), SignalType::Metrics);
     }
 
+    #[test]
+    fn test_typed_views() {
+        let mut pdata = create_test_pdata();
+        // views are only available on OTAP Arrow records
+        assert!(pdata.logs_view().unwrap().is_none());
+        assert!(pdata.traces_view().unwrap().is_none());
+
+        let (context, payload) = pdata.into_parts();
+        let records: OtapArrowRecords = payload.try_into().unwrap();
+        let mut pdata = OtapPdata::new(context, records.into());
+
+        let view = pdata.logs_view().unwrap().unwrap();
+        assert_eq!(view.num_logs(), 1);
+        assert_eq!(view.log_attrs.map(|b| b.num_rows()), Some(1));
+        assert_eq!(
+            view.log_attribute("key").unwrap(),
+            vec![Some(AttributeValue::Str("val"))]
+        );
+        assert_eq!(view.resource_attribute("key").unwrap(), vec![None]);
+        assert!(pdata.traces_view().unwrap().is_none());
+
+        // a logs payload without the main logs batch has no view
+        let mut empty = OtapPdata::new_default(OtapArrowRecords::Logs(Default::default()).into());
+        assert!(empty.logs_view().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_typed_views_transport_optimized() {
+        // each log record carries its timestamp as attribute, plus an attribute shared by all
+        // of them so that the parent ids get delta encoded
+        let log_records: Vec<LogRecord> = (1..=4u64)
+            .map(|i| {
+                LogRecord::build(i, SeverityNumber::Info, "event")
+                    .attributes(vec![
+                        KeyValue::new("time", AnyValue::new_int(i as i64)),
+                        KeyValue::new("shared", AnyValue::new_string("val")),
+                    ])
+                    .finish()
+            })
+            .collect();
+        let request = ExportLogsServiceRequest::new(vec![
+            ResourceLogs::build(Resource::default())
+                .scope_logs(vec![
+                    ScopeLogs::build(InstrumentationScope::default())
+                        .log_records(log_records)
+                        .finish(),
+                ])
+                .finish(),
+        ]);
+        let mut otlp_bytes = vec![];
+        request.encode(&mut otlp_bytes).unwrap();
+        let payload: OtapPayload = OtlpProtoBytes::ExportLogsRequest(otlp_bytes).into();
+        let mut records: OtapArrowRecords = payload.try_into().unwrap();
+        records.encode_transport_optimized().unwrap();
+        let mut payload: OtapPayload = records.into();
+
+        let view = payload.logs_view().unwrap().unwrap();
+        let times = view
+            .logs
+            .column_by_name(consts::TIME_UNIX_NANO)
+            .unwrap()
+            .as_primitive::<TimestampNanosecondType>();
+        let expected: Vec<_> = times
+            .iter()
+            .map(|time| Some(AttributeValue::Int(time.unwrap())))
+            .collect();
+        assert_eq!(view.log_attribute("time").unwrap(), expected);
+        assert_eq!(
+            view.log_attribute("shared").unwrap(),
+            vec![Some(AttributeValue::Str("val")); 4]
+        );
+    }
+
     #[test]
     fn test_context_next_ack_drops_payload_without_return_data() {
         let (test_data, pdata) = create_test();
@@ -1246,6 +1659,104 @@ mod test {
         assert_eq!(recv_data, test_data);
     }
 
+    #[test]
+    fn test_context_next_nack_preserves_failure() {
+        let (_, pdata) = create_test();
+        let pdata = pdata
+            .test_subscribe_to(Interests::NACKS, CallData::default(), 1)
+            .test_subscribe_to(Interests::NACKS, CallData::default(), 2);
+
+        let mut nack = NackMsg::new_permanent("rejected", pdata).with_class(NackClass::InvalidData);
+        nack.node = Some(otap_df_engine::testing::test_node("exporter"));
+
+        // the failure is delivered unchanged to every subscriber
+        let (node_id, nack) = Context::next_nack(nack).expect("first subscriber");
+        assert_eq!(node_id, 2);
+        let (node_id, nack) = Context::next_nack(nack).expect("second subscriber");
+        assert_eq!(node_id, 1);
+        assert!(!nack.is_retryable());
+        assert_eq!(nack.class, NackClass::InvalidData);
+        assert_eq!(nack.node.expect("refusing node").name, "exporter");
+    }
+
+    #[test]
+    fn test_in_flight_data() {
+        let (_, mut pdata) = create_test();
+        assert!(pdata.in_flight_id().is_none());
+        pdata.set_in_flight_id(7);
+        assert_eq!(pdata.in_flight_id(), Some(7));
+        assert!(pdata.deadline_nack_data().is_none());
+
+        let pdata = pdata.test_subscribe_to(Interests::NACKS, CallData::default(), 1);
+        let data = pdata.deadline_nack_data().expect("subscribed");
+        assert!(data.is_empty());
+        assert_eq!(data.signal_type(), pdata.signal_type());
+        let (node_id, _) =
+            OtapPdata::next_nack(NackMsg::new("deadline", data)).expect("subscriber");
+        assert_eq!(node_id, 1);
+
+        // a subscriber asking for the data returned gets a copy of the payload to retry it
+        let pdata = pdata.test_subscribe_to(
+            Interests::NACKS | Interests::RETURN_DATA,
+            CallData::default(),
+            2,
+        );
+        let data = pdata.deadline_nack_data().expect("subscribed");
+        assert_eq!(data.num_items(), pdata.num_items());
+        let (node_id, nack) =
+            OtapPdata::next_nack(NackMsg::new("deadline", data)).expect("subscriber");
+        assert_eq!(node_id, 2);
+        assert_eq!(nack.refused.num_items(), pdata.num_items());
+    }
+
+    #[test]
+    fn test_memory_accounted_data() {
+        use otap_df_config::pipeline::{MemoryBudgetConfig, MemoryBudgetPolicy};
+        use otap_df_engine::memory::{MemoryBudget, MemoryBudgetMetrics};
+        use otap_df_telemetry::registry::MetricsRegistryHandle;
+        use otap_df_telemetry::testing::EmptyAttributes;
+
+        let budget = MemoryBudget::new(
+            &MemoryBudgetConfig {
+                limit_bytes: 1 << 20,
+                on_exceeded: MemoryBudgetPolicy::Reject,
+            },
+            MetricsRegistryHandle::new().register::<MemoryBudgetMetrics>(EmptyAttributes()),
+        );
+        let mut bytes = Vec::with_capacity(64);
+        bytes.extend_from_slice(b"request");
+        let otlp = OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(bytes).into());
+        assert_eq!((otlp.num_bytes(), otlp.memory_size()), (7, 64));
+
+        let (_, mut pdata) = create_test();
+        assert!(pdata.memory_size() > 0);
+        let reservation = budget
+            .try_reserve(pdata.memory_size())
+            .expect("within budget");
+        pdata.set_memory_reservation(reservation);
+        assert_eq!(budget.used(), pdata.memory_size());
+
+        // the deadline Nack does not hold the memory of the payload
+        let pdata = pdata.test_subscribe_to(Interests::NACKS, CallData::default(), 1);
+        let data = pdata.deadline_nack_data().expect("subscribed");
+        assert!(data.memory_reservation().is_none());
+        drop(pdata);
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[test]
+    fn test_latency_tracked_data() {
+        let (_, mut pdata) = create_test();
+        assert!(pdata.enqueued_at().is_none());
+        let now = Instant::now();
+        pdata.set_enqueued_at(Some(now));
+
+        // the stamp follows the pdata through the Ack routing
+        let pdata = pdata.test_subscribe_to(Interests::ACKS, CallData::default(), 1);
+        let (_, ack) = Context::next_ack(AckMsg::new(pdata)).expect("subscriber");
+        assert_eq!(ack.accepted.enqueued_at(), Some(now));
+    }
+
     #[test]
     fn test_context_no_ack() {
         let (_, pdata) = create_test();
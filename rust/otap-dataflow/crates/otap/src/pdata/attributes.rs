@@ -0,0 +1,448 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Zero-allocation iteration over OTAP attribute record batches (e.g. `LogAttrs`,
+//! `ResourceAttrs`, `SpanAttrs`).
+//!
+//! [`AttributeIter`] yields one [`Attribute`] per row with the key and value borrowed from the
+//! underlying Arrow arrays, so walking a batch does not allocate per row regardless of whether
+//! the columns are plain or dictionary encoded.
+//!
+//! [`row_attribute_values`] joins an attribute batch to the rows it belongs to, returning the
+//! value of one attribute per row.
+//!
+//! Note: the `parent_id` values are returned as stored in the batch. Batches received in
+//! transport-optimized form may have delta-encoded parent ids, in which case they should first
+//! be materialized (see `otel_arrow_rust::otap::transform::materialize_parent_id_for_attributes`).
+
+use crate::pdata::error::Error;
+use arrow::array::{
+    Array, ArrayRef, AsArray, BinaryArray, BooleanArray, DictionaryArray, Float64Array, Int64Array,
+    RecordBatch, StringArray, UInt8Array, UInt16Array, UInt32Array,
+};
+use arrow::datatypes::{DataType, UInt8Type, UInt16Type, UInt32Type};
+use otel_arrow_rust::otlp::attributes::AttributeValueType;
+use otel_arrow_rust::schema::consts;
+use std::collections::HashMap;
+
+/// Value of an attribute borrowed from an attribute record batch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttributeValue<'a> {
+    /// The attribute has no value.
+    Empty,
+    /// String value.
+    Str(&'a str),
+    /// Integer value.
+    Int(i64),
+    /// Double value.
+    Double(f64),
+    /// Boolean value.
+    Bool(bool),
+    /// Bytes value.
+    Bytes(&'a [u8]),
+    /// Map or slice value, serialized as CBOR.
+    Serialized(&'a [u8]),
+}
+
+/// A single attribute row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attribute<'a> {
+    /// Id of the parent record (log record, resource, scope, span, ...) this attribute belongs to.
+    pub parent_id: u32,
+    /// Attribute key.
+    pub key: &'a str,
+    /// Attribute value.
+    pub value: AttributeValue<'a>,
+}
+
+/// Iterator over the rows of an attribute record batch.
+///
+/// Rows with a null key or an unknown value type are skipped.
+pub struct AttributeIter<'a> {
+    parent_id: ParentIdColumn<'a>,
+    key: Column<'a, StringArray>,
+    value_type: &'a UInt8Array,
+    str: Option<Column<'a, StringArray>>,
+    int: Option<Column<'a, Int64Array>>,
+    double: Option<&'a Float64Array>,
+    bool: Option<&'a BooleanArray>,
+    bytes: Option<Column<'a, BinaryArray>>,
+    ser: Option<Column<'a, BinaryArray>>,
+    row: usize,
+    num_rows: usize,
+}
+
+impl<'a> AttributeIter<'a> {
+    /// Creates an iterator over the given attribute record batch.
+    ///
+    /// Returns an error if a required column (`parent_id`, `key`, `type`) is missing or if a
+    /// column has an unexpected data type.
+    pub fn try_new(batch: &'a RecordBatch) -> Result<Self, Error> {
+        let value_type = required(batch, consts::ATTRIBUTE_TYPE)?;
+        Ok(Self {
+            parent_id: ParentIdColumn::try_new(required(batch, consts::PARENT_ID)?)?,
+            key: Column::try_new(
+                consts::ATTRIBUTE_KEY,
+                required(batch, consts::ATTRIBUTE_KEY)?,
+            )?,
+            value_type: value_type
+                .as_any()
+                .downcast_ref()
+                .ok_or_else(|| invalid_type(consts::ATTRIBUTE_TYPE, value_type))?,
+            str: optional(batch, consts::ATTRIBUTE_STR)?,
+            int: optional(batch, consts::ATTRIBUTE_INT)?,
+            double: optional_native(batch, consts::ATTRIBUTE_DOUBLE)?,
+            bool: optional_native(batch, consts::ATTRIBUTE_BOOL)?,
+            bytes: optional(batch, consts::ATTRIBUTE_BYTES)?,
+            ser: optional(batch, consts::ATTRIBUTE_SER)?,
+            row: 0,
+            num_rows: batch.num_rows(),
+        })
+    }
+
+    fn value_at(&self, row: usize) -> Option<AttributeValue<'a>> {
+        if self.value_type.is_null(row) {
+            return None;
+        }
+        let value_type = AttributeValueType::try_from(self.value_type.value(row)).ok()?;
+        let value = match value_type {
+            AttributeValueType::Empty => Some(AttributeValue::Empty),
+            AttributeValueType::Str => self
+                .str
+                .as_ref()
+                .and_then(|c| c.get(row))
+                .map(|(a, i)| AttributeValue::Str(a.value(i))),
+            AttributeValueType::Int => self
+                .int
+                .as_ref()
+                .and_then(|c| c.get(row))
+                .map(|(a, i)| AttributeValue::Int(a.value(i))),
+            AttributeValueType::Double => self
+                .double
+                .filter(|a| a.is_valid(row))
+                .map(|a| AttributeValue::Double(a.value(row))),
+            AttributeValueType::Bool => self
+                .bool
+                .filter(|a| a.is_valid(row))
+                .map(|a| AttributeValue::Bool(a.value(row))),
+            AttributeValueType::Bytes => self
+                .bytes
+                .as_ref()
+                .and_then(|c| c.get(row))
+                .map(|(a, i)| AttributeValue::Bytes(a.value(i))),
+            AttributeValueType::Map | AttributeValueType::Slice => self
+                .ser
+                .as_ref()
+                .and_then(|c| c.get(row))
+                .map(|(a, i)| AttributeValue::Serialized(a.value(i))),
+        };
+        // a missing value column for a known type is treated as an empty value
+        Some(value.unwrap_or(AttributeValue::Empty))
+    }
+}
+
+impl<'a> Iterator for AttributeIter<'a> {
+    type Item = Attribute<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.row < self.num_rows {
+            let row = self.row;
+            self.row += 1;
+
+            let Some((keys, key_idx)) = self.key.get(row) else {
+                continue;
+            };
+            let Some(value) = self.value_at(row) else {
+                continue;
+            };
+            return Some(Attribute {
+                parent_id: self.parent_id.get(row),
+                key: keys.value(key_idx),
+                value,
+            });
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.num_rows - self.row))
+    }
+}
+
+/// Returns, for each row of `rows`, the value of its attribute `key` in `attrs`, joining the
+/// `parent_id` column of `attrs` to the ids of the rows: the `id` column of `rows`, or the `id`
+/// field of its `struct_column` (e.g. `resource` for the resource attributes).
+///
+/// A row has no value when it has no id or no such attribute. If a row has several attributes
+/// with this key, the last one wins. The ids must not be transport optimized.
+pub fn row_attribute_values<'a>(
+    rows: &RecordBatch,
+    struct_column: Option<&str>,
+    attrs: Option<&'a RecordBatch>,
+    key: &str,
+) -> Result<Vec<Option<AttributeValue<'a>>>, Error> {
+    let ids = match struct_column {
+        Some(name) => rows
+            .column_by_name(name)
+            .and_then(|column| column.as_struct_opt())
+            .and_then(|column| column.column_by_name(consts::ID)),
+        None => rows.column_by_name(consts::ID),
+    };
+    let (Some(ids), Some(attrs)) = (ids, attrs) else {
+        return Ok(vec![None; rows.num_rows()]);
+    };
+
+    let mut values = HashMap::new();
+    for attr in AttributeIter::try_new(attrs)? {
+        if attr.key == key {
+            let _ = values.insert(attr.parent_id, attr.value);
+        }
+    }
+    let value = |id: Option<u32>| id.and_then(|id| values.get(&id).copied());
+    match ids.data_type() {
+        DataType::UInt16 => Ok(ids
+            .as_primitive::<UInt16Type>()
+            .iter()
+            .map(|id| value(id.map(u32::from)))
+            .collect()),
+        DataType::UInt32 => Ok(ids.as_primitive::<UInt32Type>().iter().map(value).collect()),
+        _ => Err(invalid_type(consts::ID, ids)),
+    }
+}
+
+/// A column that is either plain or dictionary encoded (with `u8` or `u16` keys).
+enum Column<'a, A> {
+    Native(&'a A),
+    Dict8(&'a DictionaryArray<UInt8Type>, &'a A),
+    Dict16(&'a DictionaryArray<UInt16Type>, &'a A),
+}
+
+impl<'a, A: Array + 'static> Column<'a, A> {
+    fn try_new(name: &str, array: &'a ArrayRef) -> Result<Self, Error> {
+        let column = match array.data_type() {
+            DataType::Dictionary(key, _) => match key.as_ref() {
+                DataType::UInt8 => {
+                    let dict = array.as_dictionary::<UInt8Type>();
+                    dict.values()
+                        .as_any()
+                        .downcast_ref()
+                        .map(|values| Self::Dict8(dict, values))
+                }
+                DataType::UInt16 => {
+                    let dict = array.as_dictionary::<UInt16Type>();
+                    dict.values()
+                        .as_any()
+                        .downcast_ref()
+                        .map(|values| Self::Dict16(dict, values))
+                }
+                _ => None,
+            },
+            _ => array.as_any().downcast_ref().map(Self::Native),
+        };
+        column.ok_or_else(|| invalid_type(name, array))
+    }
+
+    /// Returns the values array and the index within it for the given row, or `None` if the
+    /// row is null.
+    #[inline]
+    fn get(&self, row: usize) -> Option<(&'a A, usize)> {
+        match self {
+            Self::Native(values) => values.is_valid(row).then_some((*values, row)),
+            Self::Dict8(dict, values) => dict
+                .is_valid(row)
+                .then(|| (*values, dict.keys().value(row) as usize)),
+            Self::Dict16(dict, values) => dict
+                .is_valid(row)
+                .then(|| (*values, dict.keys().value(row) as usize)),
+        }
+    }
+}
+
+/// The `parent_id` column is `u16` for most attribute batches and `u32` (optionally dictionary
+/// encoded) for span event/link and exemplar attributes.
+enum ParentIdColumn<'a> {
+    U16(&'a UInt16Array),
+    U32(Column<'a, UInt32Array>),
+}
+
+impl<'a> ParentIdColumn<'a> {
+    fn try_new(array: &'a ArrayRef) -> Result<Self, Error> {
+        match array.as_any().downcast_ref::<UInt16Array>() {
+            Some(ids) => Ok(Self::U16(ids)),
+            None => Column::try_new(consts::PARENT_ID, array).map(Self::U32),
+        }
+    }
+
+    #[inline]
+    fn get(&self, row: usize) -> u32 {
+        match self {
+            Self::U16(ids) => u32::from(ids.value(row)),
+            Self::U32(ids) => ids.get(row).map_or(0, |(a, i)| a.value(i)),
+        }
+    }
+}
+
+fn required<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a ArrayRef, Error> {
+    batch
+        .column_by_name(name)
+        .ok_or_else(|| Error::InvalidAttributes {
+            error: format!("missing column `{name}`"),
+        })
+}
+
+fn optional<'a, A: Array + 'static>(
+    batch: &'a RecordBatch,
+    name: &str,
+) -> Result<Option<Column<'a, A>>, Error> {
+    batch
+        .column_by_name(name)
+        .map(|array| Column::try_new(name, array))
+        .transpose()
+}
+
+fn optional_native<'a, A: Array + 'static>(
+    batch: &'a RecordBatch,
+    name: &str,
+) -> Result<Option<&'a A>, Error> {
+    batch
+        .column_by_name(name)
+        .map(|array| {
+            array
+                .as_any()
+                .downcast_ref()
+                .ok_or_else(|| invalid_type(name, array))
+        })
+        .transpose()
+}
+
+fn invalid_type(name: &str, array: &ArrayRef) -> Error {
+    Error::InvalidAttributes {
+        error: format!(
+            "unexpected data type {} for column `{name}`",
+            array.data_type()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use crate::testing::create_test_logs;
+    use arrow::datatypes::{Field, Schema};
+    use otel_arrow_rust::otap::OtapArrowRecords;
+    use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+    use prost::Message;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_iterate_log_attrs() {
+        let mut bytes = vec![];
+        create_test_logs().encode(&mut bytes).unwrap();
+        let records: OtapArrowRecords =
+            OtlpProtoBytes::ExportLogsRequest(bytes).try_into().unwrap();
+        let batch = records.get(ArrowPayloadType::LogAttrs).unwrap();
+
+        let attrs = AttributeIter::try_new(batch).unwrap().collect::<Vec<_>>();
+        assert_eq!(
+            attrs,
+            vec![Attribute {
+                parent_id: 0,
+                key: "key",
+                value: AttributeValue::Str("val"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_iterate_native_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(consts::PARENT_ID, DataType::UInt16, false),
+            Field::new(consts::ATTRIBUTE_KEY, DataType::Utf8, true),
+            Field::new(consts::ATTRIBUTE_TYPE, DataType::UInt8, false),
+            Field::new(consts::ATTRIBUTE_INT, DataType::Int64, true),
+            Field::new(consts::ATTRIBUTE_BOOL, DataType::Boolean, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(UInt16Array::from(vec![1, 1, 2, 3])),
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("b"),
+                    None,
+                    Some("d"),
+                ])),
+                Arc::new(UInt8Array::from(vec![
+                    AttributeValueType::Int as u8,
+                    AttributeValueType::Bool as u8,
+                    AttributeValueType::Int as u8,
+                    AttributeValueType::Str as u8,
+                ])),
+                Arc::new(Int64Array::from(vec![Some(7), None, Some(9), None])),
+                Arc::new(BooleanArray::from(vec![None, Some(true), None, None])),
+            ],
+        )
+        .unwrap();
+
+        let attrs = AttributeIter::try_new(&batch).unwrap().collect::<Vec<_>>();
+        assert_eq!(
+            attrs,
+            vec![
+                Attribute {
+                    parent_id: 1,
+                    key: "a",
+                    value: AttributeValue::Int(7),
+                },
+                Attribute {
+                    parent_id: 1,
+                    key: "b",
+                    value: AttributeValue::Bool(true),
+                },
+                // row with a null key is skipped, the missing str column yields an empty value
+                Attribute {
+                    parent_id: 3,
+                    key: "d",
+                    value: AttributeValue::Empty,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_row_attribute_values() {
+        let mut bytes = vec![];
+        create_test_logs().encode(&mut bytes).unwrap();
+        let mut records: OtapArrowRecords =
+            OtlpProtoBytes::ExportLogsRequest(bytes).try_into().unwrap();
+        records.decode_transport_optimized_ids().unwrap();
+        let logs = records.get(ArrowPayloadType::Logs).unwrap();
+        let log_attrs = records.get(ArrowPayloadType::LogAttrs);
+
+        assert_eq!(
+            row_attribute_values(logs, None, log_attrs, "key").unwrap(),
+            vec![Some(AttributeValue::Str("val"))]
+        );
+        assert_eq!(
+            row_attribute_values(logs, None, log_attrs, "missing").unwrap(),
+            vec![None]
+        );
+        // rows without the id column have no value
+        assert_eq!(
+            row_attribute_values(logs, Some("unknown"), log_attrs, "key").unwrap(),
+            vec![None]
+        );
+    }
+
+    #[test]
+    fn test_missing_required_column() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            consts::PARENT_ID,
+            DataType::UInt16,
+            false,
+        )]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(UInt16Array::from(vec![1]))]).unwrap();
+        assert!(AttributeIter::try_new(&batch).is_err());
+    }
+}
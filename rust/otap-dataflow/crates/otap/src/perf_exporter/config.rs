@@ -4,10 +4,11 @@
 //! Implementation of the configuration of the perf exporter
 //!
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 /// Defines the settings of the perf exporter such as what to track
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     /// Time duration after which a perf trace is displayed (default = 1000ms).
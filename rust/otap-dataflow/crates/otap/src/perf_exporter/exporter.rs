@@ -26,7 +26,9 @@ use crate::perf_exporter::metrics::PerfExporterPdataMetrics;
 use async_trait::async_trait;
 use otap_df_config::experimental::SignalType;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
 use otap_df_engine::ConsumerEffectHandlerExtension;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::config::ExporterConfig;
 use otap_df_engine::context::PipelineContext;
 use otap_df_engine::control::{AckMsg, NodeControlMsg};
@@ -75,6 +77,12 @@ pub static PERF_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
     },
 };
 
+/// Schema of the configuration of the perf exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static OTAP_PERF_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(OTAP_PERF_EXPORTER_URN);
+
 impl PerfExporter {
     /// creates a perf exporter with the provided config
     #[must_use]
@@ -96,7 +104,7 @@ impl PerfExporter {
     ) -> Result<Self, otap_df_config::error::Error> {
         Ok(PerfExporter::new(
             pipeline_ctx,
-            serde_json::from_value(config.clone()).map_err(|e| {
+            parse_user_config(config).map_err(|e| {
                 otap_df_config::error::Error::InvalidUserConfig {
                     error: e.to_string(),
                 }
@@ -0,0 +1,639 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Persistent queue processor for OTAP pipelines.
+//!
+//! This processor writes each message to a disk-backed queue, acknowledges it upstream once
+//! written, and delivers the queue downstream in order, so that data survives restarts and
+//! backend outages. It can be placed in front of any exporter.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! path: /var/lib/otap/queue
+//! segment_size: 16777216
+//! max_size: 1073741824
+//! max_in_flight: 16
+//! retry_interval: 1s
+//! sync: false
+//! io_backend: std
+//! ```
+//!
+//! - `path`: directory of the queue files, one directory per processor instance;
+//! - `segment_size`: size in bytes of the segment files the queue is split into;
+//! - `max_size`: size cap in bytes of the queue, messages are NACKed upstream when reached;
+//! - `max_in_flight`: number of messages sent downstream and waiting for their ACK;
+//! - `retry_interval`: delay before resending the messages NACKed downstream;
+//! - `sync`: whether each write is flushed to the disk before the message is ACKed;
+//! - `io_backend`: backend of the file writes, `std` (default) or `io_uring`, see
+//!   [`IoBackend`].
+//!
+//! Messages are stored as OTLP protobuf, with a CRC-32 per record. A message is removed from
+//! the queue when ACKed downstream (or permanently NACKed), and fully delivered segments are
+//! deleted. After a restart, the messages not yet ACKed are delivered again.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::io_backend::IoBackend;
+use crate::pdata::{OtapPayload, OtapPdata, OtlpProtoBytes};
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, CallData, NackClass, NackMsg, NodeControlMsg};
+use otap_df_engine::error::{Error as EngineError, ProcessorErrorKind};
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_engine::{ConsumerEffectHandlerExtension, Interests, ProducerEffectHandlerExtension};
+use otap_df_telemetry::metrics::MetricSet;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod metrics;
+mod storage;
+use self::metrics::PersistentQueueProcessorMetrics;
+use self::storage::{Record, Storage};
+
+/// URN for the PersistentQueueProcessor
+pub const PERSISTENT_QUEUE_PROCESSOR_URN: &str = "urn:otap:processor:persistent_queue";
+
+const DEFAULT_SEGMENT_SIZE: u64 = 16 * 1024 * 1024;
+const DEFAULT_MAX_SIZE: u64 = 1024 * 1024 * 1024;
+const DEFAULT_MAX_IN_FLIGHT: usize = 16;
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configuration for the PersistentQueueProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Directory of the queue files.
+    pub path: PathBuf,
+
+    /// Size in bytes of the segment files.
+    #[serde(default = "default_segment_size")]
+    pub segment_size: u64,
+
+    /// Size cap in bytes of the queue.
+    #[serde(default = "default_max_size")]
+    pub max_size: u64,
+
+    /// Number of messages sent downstream and not yet acknowledged.
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+
+    /// Delay before resending the messages refused downstream.
+    #[serde(with = "humantime_serde", default = "default_retry_interval")]
+    #[schemars(with = "String")]
+    pub retry_interval: Duration,
+
+    /// Flush each write to the disk before acknowledging the message.
+    #[serde(default)]
+    pub sync: bool,
+
+    /// Backend of the file writes.
+    #[serde(default)]
+    pub io_backend: IoBackend,
+}
+
+const fn default_segment_size() -> u64 {
+    DEFAULT_SEGMENT_SIZE
+}
+
+const fn default_max_size() -> u64 {
+    DEFAULT_MAX_SIZE
+}
+
+const fn default_max_in_flight() -> usize {
+    DEFAULT_MAX_IN_FLIGHT
+}
+
+const fn default_retry_interval() -> Duration {
+    DEFAULT_RETRY_INTERVAL
+}
+
+/// Processor queueing messages on disk before sending them downstream.
+pub struct PersistentQueueProcessor {
+    config: Config,
+    storage: Storage,
+    /// Records sent downstream and waiting for their ACK, by sequence number.
+    in_flight: BTreeMap<u64, Record>,
+    /// Records refused downstream, waiting for the retry timer.
+    resend: VecDeque<u64>,
+    timer_started: bool,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<PersistentQueueProcessorMetrics>>,
+}
+
+impl PersistentQueueProcessor {
+    /// Creates a new PersistentQueueProcessor from configuration, opening its queue.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse PersistentQueueProcessor configuration: {e}"),
+            })?;
+        Self::new(config)
+    }
+
+    fn new(config: Config) -> Result<Self, ConfigError> {
+        if config.segment_size == 0 || config.max_size == 0 || config.max_in_flight == 0 {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "segment_size, max_size and max_in_flight must be positive".to_string(),
+            });
+        }
+        if config.retry_interval.is_zero() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "retry_interval must be greater than zero".to_string(),
+            });
+        }
+        let storage = Storage::open(
+            &config.path,
+            config.segment_size,
+            config.max_size,
+            config.io_backend,
+        )
+        .map_err(|e| ConfigError::InvalidUserConfig {
+            error: format!(
+                "failed to open persistent queue in {}: {e}",
+                config.path.display()
+            ),
+        })?;
+        Ok(Self {
+            config,
+            storage,
+            in_flight: BTreeMap::new(),
+            resend: VecDeque::new(),
+            timer_started: false,
+            metrics_set: None,
+        })
+    }
+
+    fn storage_error(
+        &mut self,
+        effect_handler: &local::EffectHandler<OtapPdata>,
+        action: &str,
+        e: io::Error,
+    ) -> EngineError {
+        if let Some(m) = self.metrics_set.as_mut() {
+            m.storage_errors.inc();
+        }
+        let error = format!(
+            "failed to {action} persistent queue in {}: {e}",
+            self.config.path.display()
+        );
+        if e.kind() == ErrorKind::InvalidData {
+            // the queue files are corrupted
+            return EngineError::ProcessorError {
+                processor: effect_handler.processor_id(),
+                kind: ProcessorErrorKind::Other,
+                error,
+                source_detail: String::new(),
+            };
+        }
+        EngineError::IoError {
+            node: effect_handler.processor_id(),
+            error: io::Error::new(e.kind(), error),
+        }
+    }
+
+    /// Write a message to the queue, then acknowledge it upstream.
+    async fn persist(
+        &mut self,
+        pdata: OtapPdata,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        let (context, payload) = pdata.into_parts();
+        let signal = payload.signal_type();
+        let bytes: OtlpProtoBytes = payload.try_into()?;
+
+        let written = self
+            .storage
+            .append(signal_code(signal), bytes.as_bytes(), self.config.sync);
+        let (reason, class) = match written {
+            Ok(Some(_)) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_persisted.inc();
+                }
+                let mut payload: OtapPayload = bytes.into();
+                if !context.may_return_payload() {
+                    let _ = payload.take_payload();
+                }
+                return effect_handler
+                    .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+                    .await;
+            }
+            Ok(None) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_refused_full.inc();
+                }
+                (
+                    format!("persistent queue is full ({} bytes)", self.storage.size()),
+                    NackClass::Throttled,
+                )
+            }
+            Err(e) => (
+                self.storage_error(effect_handler, "write", e).to_string(),
+                NackClass::Internal,
+            ),
+        };
+        effect_handler
+            .notify_nack(
+                NackMsg::new(reason, OtapPdata::new(context, bytes.into())).with_class(class),
+            )
+            .await
+    }
+
+    /// Send queued records downstream, up to `max_in_flight` unacknowledged records.
+    async fn deliver(
+        &mut self,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        while self.in_flight.len() < self.config.max_in_flight {
+            let record = match self.storage.read_next() {
+                Ok(Some(record)) => record,
+                Ok(None) => break,
+                Err(e) => return Err(self.storage_error(effect_handler, "read", e)),
+            };
+            let seq = record.seq;
+            let pdata =
+                to_pdata(&record).map_err(|e| self.storage_error(effect_handler, "read", e))?;
+            let _ = self.in_flight.insert(seq, record);
+            self.send(seq, pdata, effect_handler).await?;
+        }
+        Ok(())
+    }
+
+    /// Resend the records refused downstream.
+    async fn resend(
+        &mut self,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        while let Some(seq) = self.resend.pop_front() {
+            if let Some(record) = self.in_flight.get(&seq) {
+                let pdata =
+                    to_pdata(record).map_err(|e| self.storage_error(effect_handler, "read", e))?;
+                self.send(seq, pdata, effect_handler).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(
+        &mut self,
+        seq: u64,
+        mut pdata: OtapPdata,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        let calldata: CallData = smallvec::smallvec![seq.into()];
+        effect_handler.subscribe_to(Interests::ACKS | Interests::NACKS, calldata, &mut pdata);
+        effect_handler.send_message(pdata).await?;
+        if let Some(m) = self.metrics_set.as_mut() {
+            m.msgs_sent.inc();
+        }
+        Ok(())
+    }
+
+    /// Remove a record delivered downstream from the queue.
+    fn complete(
+        &mut self,
+        seq: u64,
+        effect_handler: &local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        if self.in_flight.remove(&seq).is_none() {
+            return Ok(());
+        }
+        // Everything below the oldest record in flight is delivered
+        let delivered = self
+            .in_flight
+            .keys()
+            .next()
+            .copied()
+            .unwrap_or_else(|| self.storage.read_seq());
+        self.storage
+            .commit(delivered)
+            .map_err(|e| self.storage_error(effect_handler, "update", e))
+    }
+
+    async fn ensure_timer(
+        &mut self,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        if !self.timer_started {
+            // Processors have no start hook: the timer, which also replays the queue left by a
+            // previous run, starts on the first message of any kind
+            let _timer_cancel = effect_handler
+                .start_periodic_timer(self.config.retry_interval)
+                .await?;
+            self.timer_started = true;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for PersistentQueueProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    self.ensure_timer(effect_handler).await
+                }
+                NodeControlMsg::TimerTick { .. } => {
+                    self.resend(effect_handler).await?;
+                    self.deliver(effect_handler).await
+                }
+                NodeControlMsg::Ack(ack) => {
+                    if let Some(seq) = sequence_number(&ack.calldata) {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.msgs_acked.inc();
+                        }
+                        self.complete(seq, effect_handler)?;
+                    }
+                    self.deliver(effect_handler).await
+                }
+                NodeControlMsg::Nack(nack) => {
+                    if let Some(seq) = sequence_number(&nack.calldata) {
+                        if !nack.is_retryable() {
+                            if let Some(m) = self.metrics_set.as_mut() {
+                                m.msgs_dropped.inc();
+                            }
+                            self.complete(seq, effect_handler)?;
+                            return self.deliver(effect_handler).await;
+                        }
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.msgs_nacked.inc();
+                        }
+                        self.resend.push_back(seq);
+                    }
+                    Ok(())
+                }
+                // The queue is on disk: undelivered records are sent after the restart
+                NodeControlMsg::Shutdown { .. } => self
+                    .storage
+                    .sync()
+                    .map_err(|e| self.storage_error(effect_handler, "flush", e)),
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                self.ensure_timer(effect_handler).await?;
+                self.persist(pdata, effect_handler).await?;
+                self.deliver(effect_handler).await
+            }
+        }
+    }
+}
+
+fn sequence_number(calldata: &CallData) -> Option<u64> {
+    calldata.first().copied().map(u64::from)
+}
+
+fn signal_code(signal: SignalType) -> u8 {
+    match signal {
+        SignalType::Logs => 0,
+        SignalType::Metrics => 1,
+        SignalType::Traces => 2,
+    }
+}
+
+fn to_pdata(record: &Record) -> io::Result<OtapPdata> {
+    let payload = record.payload.clone();
+    let bytes = match record.signal {
+        0 => OtlpProtoBytes::ExportLogsRequest(payload),
+        1 => OtlpProtoBytes::ExportMetricsRequest(payload),
+        2 => OtlpProtoBytes::ExportTracesRequest(payload),
+        other => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown signal {other} of queued record {}", record.seq),
+            ));
+        }
+    };
+    Ok(OtapPdata::new_todo_context(bytes.into()))
+}
+
+/// Factory function to create a PersistentQueueProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_persistent_queue_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = PersistentQueueProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<PersistentQueueProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register PersistentQueueProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static PERSISTENT_QUEUE_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: PERSISTENT_QUEUE_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_persistent_queue_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the persistent queue processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static PERSISTENT_QUEUE_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(PERSISTENT_QUEUE_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::Context;
+    use crate::testing::{TestCallData, create_test_pdata};
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::control::{PipelineControlMsg, pipeline_ctrl_msg_channel};
+    use otap_df_engine::testing::processor::TestRuntime;
+    use otap_df_engine::testing::test_node;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn processor(rt: &TestRuntime<OtapPdata>, config: Value) -> ProcessorWrapper<OtapPdata> {
+        let pipeline_ctx = ControllerContext::new(MetricsRegistryHandle::new())
+            .pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        let mut node_config = NodeUserConfig::new_processor_config(PERSISTENT_QUEUE_PROCESSOR_URN);
+        node_config.config = config;
+        create_persistent_queue_processor(
+            pipeline_ctx,
+            test_node("persistent-queue"),
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor")
+    }
+
+    fn ack(pdata: OtapPdata) -> Message<OtapPdata> {
+        let (_, ack) = Context::next_ack(AckMsg::new(pdata)).expect("subscribed");
+        Message::ack_ctrl_msg(ack)
+    }
+
+    /// Queue `count` messages, acknowledge the first `acked` ones downstream, and return the
+    /// number of messages sent downstream.
+    fn run(dir: &Path, count: usize, acked: usize) -> usize {
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let proc = processor(&rt, json!({ "path": dir }));
+        let sent = std::rc::Rc::new(std::cell::Cell::new(0));
+        let sent_in_test = sent.clone();
+        rt.set_processor(proc)
+            .run_test(move |mut ctx| async move {
+                ctx.process(Message::Control(NodeControlMsg::TimerTick {}))
+                    .await
+                    .expect("tick");
+                for _ in 0..count {
+                    ctx.process(Message::PData(create_test_pdata()))
+                        .await
+                        .expect("process");
+                }
+                let output = ctx.drain_pdata().await;
+                sent_in_test.set(output.len());
+                for pdata in output.into_iter().take(acked) {
+                    assert_eq!(pdata.num_items(), create_test_pdata().num_items());
+                    ctx.process(ack(pdata)).await.expect("ack");
+                }
+            })
+            .validate(|_| async move {});
+        sent.get()
+    }
+
+    #[test]
+    fn test_replay_after_restart() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        assert_eq!(run(dir.path(), 3, 1), 3);
+        // the two messages not acknowledged are delivered again
+        assert_eq!(run(dir.path(), 0, 2), 2);
+        assert_eq!(run(dir.path(), 1, 1), 1);
+    }
+
+    #[test]
+    fn test_nacked_message_resent() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let proc = processor(&rt, json!({ "path": dir.path() }));
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                ctx.process(Message::PData(create_test_pdata()))
+                    .await
+                    .expect("process");
+                let mut output = ctx.drain_pdata().await;
+                assert_eq!(output.len(), 1);
+
+                let nack = NackMsg::new("backend unavailable", output.remove(0));
+                let (_, nack) = Context::next_nack(nack).expect("subscribed");
+                ctx.process(Message::nack_ctrl_msg(nack))
+                    .await
+                    .expect("nack");
+                assert!(ctx.drain_pdata().await.is_empty());
+
+                ctx.process(Message::Control(NodeControlMsg::TimerTick {}))
+                    .await
+                    .expect("tick");
+                let output = ctx.drain_pdata().await;
+                assert_eq!(output.len(), 1);
+                ctx.process(ack(output.into_iter().next().expect("resent")))
+                    .await
+                    .expect("ack");
+            })
+            .validate(|_| async move {});
+    }
+
+    #[test]
+    fn test_full_queue_nacks_upstream() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let proc = processor(&rt, json!({ "path": dir.path(), "max_size": 16 }));
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                let (pipeline_tx, mut pipeline_rx) = pipeline_ctrl_msg_channel(10);
+                ctx.set_pipeline_ctrl_sender(pipeline_tx);
+
+                let pdata = create_test_pdata().test_subscribe_to(
+                    Interests::ACKS | Interests::NACKS,
+                    TestCallData::default().into(),
+                    4444,
+                );
+                ctx.process(Message::PData(pdata)).await.expect("process");
+                assert!(ctx.drain_pdata().await.is_empty());
+
+                loop {
+                    match pipeline_rx.recv().await {
+                        Ok(PipelineControlMsg::DeliverNack { node_id, nack }) => {
+                            assert_eq!(node_id, 4444);
+                            assert!(nack.reason.contains("full"));
+                            break;
+                        }
+                        Ok(PipelineControlMsg::StartTimer { .. }) => {}
+                        other => panic!("expected DeliverNack but got: {other:?}"),
+                    }
+                }
+            })
+            .validate(|_| async move {});
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        for config in [
+            json!({}),
+            json!({ "path": dir.path(), "max_in_flight": 0 }),
+            json!({ "path": dir.path(), "retry_interval": "0s" }),
+            json!({ "path": dir.path(), "unknown": true }),
+        ] {
+            let err = PersistentQueueProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+
+    #[test]
+    fn test_unknown_signal_is_invalid_data() {
+        let record = Record {
+            seq: 3,
+            signal: 9,
+            payload: Vec::new(),
+        };
+        let err = to_pdata(&record).err().expect("unknown signal");
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(
+            err.to_string()
+                .contains("unknown signal 9 of queued record 3")
+        );
+    }
+}
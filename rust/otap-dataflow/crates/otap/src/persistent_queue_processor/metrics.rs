@@ -0,0 +1,44 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the PersistentQueueProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the PersistentQueueProcessor node.
+#[metric_set(name = "persistent_queue.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct PersistentQueueProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages written to the queue.
+    #[metric(unit = "{msg}")]
+    pub msgs_persisted: Counter<u64>,
+
+    /// PData messages refused because the queue reached its size cap.
+    #[metric(unit = "{msg}")]
+    pub msgs_refused_full: Counter<u64>,
+
+    /// Queued messages sent downstream, including resends.
+    #[metric(unit = "{msg}")]
+    pub msgs_sent: Counter<u64>,
+
+    /// Queued messages acknowledged downstream.
+    #[metric(unit = "{msg}")]
+    pub msgs_acked: Counter<u64>,
+
+    /// Queued messages refused downstream and scheduled for a resend.
+    #[metric(unit = "{msg}")]
+    pub msgs_nacked: Counter<u64>,
+
+    /// Queued messages permanently refused downstream, and removed from the queue.
+    #[metric(unit = "{msg}")]
+    pub msgs_dropped: Counter<u64>,
+
+    /// Number of failed reads or writes of the queue files.
+    #[metric(unit = "{error}")]
+    pub storage_errors: Counter<u64>,
+}
@@ -0,0 +1,452 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Segment files of the persistent queue processor.
+//!
+//! Records are appended to segment files named after the sequence number of their first record,
+//! `<first_seq>.seg`. A record is laid out as:
+//!
+//! ```text
+//! | len: u32 LE | crc32: u32 LE | seq: u64 LE | signal: u8 | payload: len bytes |
+//! ```
+//!
+//! where the CRC-32 (IEEE) covers `seq`, `signal` and `payload`. The sequence number below which
+//! all records have been delivered is kept in a `cursor` file. On open, segments are scanned, a
+//! torn or corrupted tail is truncated, and reading resumes at the cursor.
+
+use crate::io_backend::{BackedFile, IoBackend};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const SEGMENT_EXTENSION: &str = "seg";
+const CURSOR_FILE: &str = "cursor";
+const HEADER_LEN: u64 = 17;
+
+/// A record read back from the queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Record {
+    pub(super) seq: u64,
+    pub(super) signal: u8,
+    pub(super) payload: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Segment {
+    /// Sequence number of the last record, `None` while the segment is empty.
+    last_seq: Option<u64>,
+    path: PathBuf,
+    size: u64,
+}
+
+/// Append-only record log split across segment files.
+#[derive(Debug)]
+pub(super) struct Storage {
+    dir: PathBuf,
+    segment_size: u64,
+    max_size: u64,
+    io_backend: IoBackend,
+    segments: VecDeque<Segment>,
+    /// Total size of the segment files.
+    size: u64,
+    /// Open handle on the last segment.
+    writer: Option<BackedFile>,
+    next_seq: u64,
+    /// All records below this sequence number have been delivered.
+    committed: u64,
+    read_index: usize,
+    read_offset: u64,
+    /// Sequence number following the last record read.
+    read_seq: u64,
+}
+
+impl Storage {
+    /// Open the queue stored in `dir`, creating it if needed.
+    pub(super) fn open(
+        dir: &Path,
+        segment_size: u64,
+        max_size: u64,
+        io_backend: IoBackend,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let committed = match fs::read(dir.join(CURSOR_FILE)) {
+            Ok(bytes) => bytes
+                .try_into()
+                .map(u64::from_le_bytes)
+                .map_err(|_| io::Error::new(ErrorKind::InvalidData, "invalid cursor file"))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+
+        let mut first_seqs = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION) {
+                if let Some(first_seq) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse::<u64>().ok())
+                {
+                    first_seqs.push(first_seq);
+                }
+            }
+        }
+        first_seqs.sort_unstable();
+
+        let mut storage = Storage {
+            dir: dir.to_path_buf(),
+            segment_size,
+            max_size,
+            io_backend,
+            segments: VecDeque::new(),
+            size: 0,
+            writer: None,
+            next_seq: committed,
+            committed,
+            read_index: 0,
+            read_offset: 0,
+            read_seq: committed,
+        };
+        for first_seq in first_seqs {
+            let segment = scan_segment(storage.segment_path(first_seq))?;
+            if let Some(last_seq) = segment.last_seq {
+                storage.next_seq = storage.next_seq.max(last_seq + 1);
+            }
+            storage.size += segment.size;
+            storage.segments.push_back(segment);
+        }
+        storage.remove_delivered_segments()?;
+        Ok(storage)
+    }
+
+    /// Append a record, returning its sequence number, or `None` if the queue is full.
+    ///
+    /// With `sync`, the record is flushed to the disk before returning.
+    pub(super) fn append(
+        &mut self,
+        signal: u8,
+        payload: &[u8],
+        sync: bool,
+    ) -> io::Result<Option<u64>> {
+        let record_len = HEADER_LEN + payload.len() as u64;
+        if self.size + record_len > self.max_size {
+            return Ok(None);
+        }
+        let roll = self
+            .segments
+            .back()
+            .is_none_or(|last| last.size > 0 && last.size + record_len > self.segment_size);
+        if roll {
+            let path = self.segment_path(self.next_seq);
+            self.writer = Some(BackedFile::new(File::create(&path)?, self.io_backend));
+            self.segments.push_back(Segment {
+                last_seq: None,
+                path,
+                size: 0,
+            });
+        }
+        if self.writer.is_none() {
+            // the last segment found on open is appended to in place
+            if let Some(last) = self.segments.back() {
+                let file = OpenOptions::new().append(true).open(&last.path)?;
+                self.writer = Some(BackedFile::new(file, self.io_backend));
+            }
+        }
+        let Some(writer) = self.writer.as_mut() else {
+            return Err(io::Error::other("no segment to append to"));
+        };
+
+        let seq = self.next_seq;
+        writer.write_all_synced(&encode_record(seq, signal, payload), sync)?;
+        if let Some(last) = self.segments.back_mut() {
+            last.last_seq = Some(seq);
+            last.size += record_len;
+        }
+        self.size += record_len;
+        self.next_seq += 1;
+        Ok(Some(seq))
+    }
+
+    /// Flush the appended records to the disk.
+    pub(super) fn sync(&mut self) -> io::Result<()> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.sync_data(),
+            None => Ok(()),
+        }
+    }
+
+    /// Read the next undelivered record, in sequence order.
+    pub(super) fn read_next(&mut self) -> io::Result<Option<Record>> {
+        while let Some(segment) = self.segments.get(self.read_index) {
+            if self.read_offset >= segment.size {
+                if self.read_index + 1 == self.segments.len() {
+                    return Ok(None);
+                }
+                self.read_index += 1;
+                self.read_offset = 0;
+                continue;
+            }
+            // Records are read one at a time, as the segment may be appended to in between
+            let mut file = File::open(&segment.path)?;
+            let _ = file.seek(SeekFrom::Start(self.read_offset))?;
+            match read_record(&mut file)? {
+                Some(record) => {
+                    self.read_offset += HEADER_LEN + record.payload.len() as u64;
+                    if record.seq < self.committed {
+                        continue;
+                    }
+                    self.read_seq = record.seq + 1;
+                    return Ok(Some(record));
+                }
+                None => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("corrupted record in {}", segment.path.display()),
+                    ));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Sequence number following the last record read.
+    pub(super) fn read_seq(&self) -> u64 {
+        self.read_seq
+    }
+
+    /// Record that all records below `seq` have been delivered, and delete the segments holding
+    /// only such records.
+    pub(super) fn commit(&mut self, seq: u64) -> io::Result<()> {
+        if seq <= self.committed {
+            return Ok(());
+        }
+        let tmp = self.dir.join(format!("{CURSOR_FILE}.tmp"));
+        fs::write(&tmp, seq.to_le_bytes())?;
+        fs::rename(&tmp, self.dir.join(CURSOR_FILE))?;
+        self.committed = seq;
+        self.remove_delivered_segments()
+    }
+
+    /// Total size of the segment files.
+    pub(super) fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn remove_delivered_segments(&mut self) -> io::Result<()> {
+        // the last segment is kept to be appended to
+        while self.segments.len() > 1 {
+            let delivered = self.segments.front().is_some_and(|segment| {
+                segment
+                    .last_seq
+                    .is_none_or(|last_seq| last_seq < self.committed)
+            });
+            if !delivered {
+                break;
+            }
+            if let Some(segment) = self.segments.pop_front() {
+                fs::remove_file(&segment.path)?;
+                self.size -= segment.size;
+                if self.read_index > 0 {
+                    self.read_index -= 1;
+                } else {
+                    self.read_offset = 0;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn segment_path(&self, first_seq: u64) -> PathBuf {
+        self.dir
+            .join(format!("{first_seq:020}.{SEGMENT_EXTENSION}"))
+    }
+}
+
+/// Scan a segment, truncating it after its last valid record.
+fn scan_segment(path: PathBuf) -> io::Result<Segment> {
+    let file = OpenOptions::new().read(true).write(true).open(&path)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let mut size = 0;
+    let mut last_seq = None;
+    while let Some(record) = read_record(&mut reader)? {
+        size += HEADER_LEN + record.payload.len() as u64;
+        last_seq = Some(record.seq);
+    }
+    if size < file_len {
+        // torn write or corruption: drop the tail
+        reader.into_inner().set_len(size)?;
+    }
+    Ok(Segment {
+        last_seq,
+        path,
+        size,
+    })
+}
+
+fn encode_record(seq: u64, signal: u8, payload: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(HEADER_LEN as usize + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&[0; 4]);
+    record.extend_from_slice(&seq.to_le_bytes());
+    record.push(signal);
+    record.extend_from_slice(payload);
+    let crc = crc32(&record[8..]);
+    record[4..8].copy_from_slice(&crc.to_le_bytes());
+    record
+}
+
+/// Read a record, `None` at the end of the segment or on a torn or corrupted record.
+fn read_record(reader: &mut impl Read) -> io::Result<Option<Record>> {
+    let mut header = [0u8; HEADER_LEN as usize];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let [l0, l1, l2, l3, c0, c1, c2, c3, rest @ ..] = header;
+    let len = u32::from_le_bytes([l0, l1, l2, l3]) as usize;
+    let crc = u32::from_le_bytes([c0, c1, c2, c3]);
+    let mut payload = vec![0; len];
+    match reader.read_exact(&mut payload) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let mut checked = rest.to_vec();
+    checked.extend_from_slice(&payload);
+    if crc32(&checked) != crc {
+        return Ok(None);
+    }
+    let [s0, s1, s2, s3, s4, s5, s6, s7, signal] = rest;
+    Ok(Some(Record {
+        seq: u64::from_le_bytes([s0, s1, s2, s3, s4, s5, s6, s7]),
+        signal,
+        payload,
+    }))
+}
+
+/// CRC-32 (IEEE 802.3) of `data`.
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+    !data.iter().fold(!0u32, |crc, byte| {
+        TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_replay_after_reopen() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut storage = Storage::open(dir.path(), 64, 1024, IoBackend::Std).expect("open");
+        for i in 0..5u8 {
+            let seq = storage.append(0, &[i; 20], false).expect("append");
+            assert_eq!(seq, Some(u64::from(i)));
+        }
+        // 37 bytes per record, one record per segment of 64 bytes
+        assert_eq!(storage.segments.len(), 5);
+
+        let first = storage.read_next().expect("read").expect("record");
+        assert_eq!(first.payload, vec![0; 20]);
+        let _ = storage.read_next().expect("read").expect("record");
+        storage.commit(1).expect("commit");
+        assert_eq!(storage.segments.len(), 4);
+        drop(storage);
+
+        // The undelivered records are replayed after a restart, in order
+        let mut storage = Storage::open(dir.path(), 64, 1024, IoBackend::Std).expect("reopen");
+        let replayed: Vec<u64> = std::iter::from_fn(|| storage.read_next().expect("read"))
+            .map(|record| record.seq)
+            .collect();
+        assert_eq!(replayed, vec![1, 2, 3, 4]);
+        assert_eq!(storage.append(0, b"next", false).expect("append"), Some(5));
+    }
+
+    #[test]
+    fn test_torn_tail_truncated() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut storage = Storage::open(dir.path(), 1024, 1024, IoBackend::Std).expect("open");
+        let _ = storage.append(2, b"first", false).expect("append");
+        let _ = storage.append(2, b"second", false).expect("append");
+        let path = storage.segments[0].path.clone();
+        drop(storage);
+
+        // Simulate a write interrupted in the middle of the second record
+        let len = fs::metadata(&path).expect("metadata").len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .expect("open segment")
+            .set_len(len - 3)
+            .expect("truncate");
+
+        let mut storage = Storage::open(dir.path(), 1024, 1024, IoBackend::Std).expect("reopen");
+        let record = storage.read_next().expect("read").expect("record");
+        assert_eq!((record.seq, record.signal), (0, 2));
+        assert_eq!(record.payload, b"first");
+        assert!(storage.read_next().expect("read").is_none());
+        assert_eq!(storage.append(2, b"again", true).expect("append"), Some(1));
+    }
+
+    #[test]
+    fn test_size_cap() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut storage = Storage::open(dir.path(), 64, 100, IoBackend::Std).expect("open");
+        assert!(
+            storage
+                .append(0, &[0; 30], false)
+                .expect("append")
+                .is_some()
+        );
+        assert!(
+            storage
+                .append(0, &[0; 30], false)
+                .expect("append")
+                .is_some()
+        );
+        assert!(
+            storage
+                .append(0, &[0; 30], false)
+                .expect("append")
+                .is_none()
+        );
+
+        // Delivered segments free room
+        let _ = storage.read_next().expect("read");
+        storage.commit(1).expect("commit");
+        assert_eq!(storage.size(), 47);
+        assert!(
+            storage
+                .append(0, &[0; 30], false)
+                .expect("append")
+                .is_some()
+        );
+    }
+}
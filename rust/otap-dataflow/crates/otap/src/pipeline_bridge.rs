@@ -0,0 +1,79 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! In-process channels connecting the pipelines of a pipeline group.
+//!
+//! A bridge lets one receiver, e.g. a single OTLP gRPC listener, feed several pipelines that have
+//! their own processors and exporters. The pipeline owning the receiver selects the target
+//! pipelines with a routing processor whose out ports lead to bridge exporters, and every target
+//! pipeline starts with a bridge receiver:
+//!
+//! ```text
+//! ingest:  otlp receiver -> routing processor -+-> bridge exporter (pipeline: team-a)
+//!                                              +-> bridge exporter (pipeline: team-b)
+//! team-a:  bridge receiver -> ... -> exporter
+//! team-b:  bridge receiver -> ... -> exporter
+//! ```
+//!
+//! Each target pipeline has one bounded channel, shared by the instances of the pipeline running
+//! on the different cores, so the source and target pipelines do not need the same core
+//! allocation. Only the payload crosses the bridge: the context of a pdata references nodes of
+//! the source pipeline, so the bridge exporter Acks the pdata once it is queued and the bridge
+//! receiver sends it with a fresh context.
+
+use crate::pdata::OtapPayload;
+use otap_df_config::{PipelineGroupId, PipelineId};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+/// Number of payloads queued on a bridge before the bridge exporters wait.
+pub const BRIDGE_CHANNEL_CAPACITY: usize = 128;
+
+/// Both ends of the channel of a target pipeline.
+#[derive(Clone)]
+pub(crate) struct Bridge {
+    pub(crate) sender: flume::Sender<OtapPayload>,
+    pub(crate) receiver: flume::Receiver<OtapPayload>,
+}
+
+/// The bridges of the process, keyed by target pipeline.
+///
+/// The registry keeps both ends of every channel, so that exporters and receivers can be built in
+/// any order and survive the rebuild of the pipelines on a configuration reload.
+static BRIDGES: LazyLock<Mutex<HashMap<(PipelineGroupId, PipelineId), Bridge>>> =
+    LazyLock::new(Default::default);
+
+/// Returns the bridge feeding the given pipeline, creating it on first use.
+pub(crate) fn bridge(pipeline_group_id: &PipelineGroupId, pipeline_id: &PipelineId) -> Bridge {
+    let mut bridges = BRIDGES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    bridges
+        .entry((pipeline_group_id.clone(), pipeline_id.clone()))
+        .or_insert_with(|| {
+            let (sender, receiver) = flume::bounded(BRIDGE_CHANNEL_CAPACITY);
+            Bridge { sender, receiver }
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+
+    #[test]
+    fn test_bridge_is_shared() {
+        let group: PipelineGroupId = "bridge-test-group".into();
+        let first = bridge(&group, &"team-a".into());
+        let second = bridge(&group, &"team-a".into());
+        let other = bridge(&group, &"team-b".into());
+
+        first
+            .sender
+            .send(OtlpProtoBytes::ExportLogsRequest(vec![1]).into())
+            .expect("bridge is open");
+        assert!(other.receiver.is_empty());
+        assert!(second.receiver.try_recv().is_ok());
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exporter sending pdata to another pipeline of the same pipeline group.
+//!
+//! The payloads are queued on the bridge of the target pipeline (see
+//! [`crate::pipeline_bridge`]) and Acked once queued. The exporter waits while the bridge is
+//! full, applying backpressure to its own pipeline.
+
+use crate::OTAP_EXPORTER_FACTORIES;
+use crate::metrics::ExporterPDataMetrics;
+use crate::pdata::OtapPdata;
+use crate::pipeline_bridge::{Bridge, bridge};
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::PipelineId;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ExporterConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, NackClass, NackMsg, NodeControlMsg};
+use otap_df_engine::error::Error;
+use otap_df_engine::exporter::ExporterWrapper;
+use otap_df_engine::local::exporter::{EffectHandler, Exporter};
+use otap_df_engine::message::{Message, MessageChannel};
+use otap_df_engine::node::NodeId;
+use otap_df_engine::terminal_state::TerminalState;
+use otap_df_engine::{ConsumerEffectHandlerExtension, ExporterFactory};
+use otap_df_telemetry::metrics::MetricSet;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The URN for the pipeline bridge exporter
+pub const PIPELINE_BRIDGE_EXPORTER_URN: &str = "urn:otap:exporter:pipeline_bridge";
+
+/// Configuration for the pipeline bridge exporter
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Pipeline of the same pipeline group receiving the pdata.
+    pub pipeline: PipelineId,
+}
+
+/// Exporter queuing pdata on the bridge of another pipeline
+pub struct PipelineBridgeExporter {
+    bridge: Bridge,
+    pdata_metrics: MetricSet<ExporterPDataMetrics>,
+}
+
+/// Declare the pipeline bridge exporter as a local exporter factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_EXPORTER_FACTORIES)]
+pub static PIPELINE_BRIDGE_EXPORTER: ExporterFactory<OtapPdata> = ExporterFactory {
+    name: PIPELINE_BRIDGE_EXPORTER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             exporter_config: &ExporterConfig| {
+        Ok(ExporterWrapper::local(
+            PipelineBridgeExporter::from_config(pipeline, &node_config.config)?,
+            node,
+            node_config,
+            exporter_config,
+        ))
+    },
+};
+
+/// Schema of the configuration of the pipeline bridge exporter
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static PIPELINE_BRIDGE_EXPORTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(PIPELINE_BRIDGE_EXPORTER_URN);
+
+impl PipelineBridgeExporter {
+    /// create a new instance of the `[PipelineBridgeExporter]` from json config value
+    pub fn from_config(
+        pipeline_ctx: PipelineContext,
+        config: &serde_json::Value,
+    ) -> Result<Self, otap_df_config::error::Error> {
+        let config: Config = parse_user_config(config).map_err(|e| {
+            otap_df_config::error::Error::InvalidUserConfig {
+                error: e.to_string(),
+            }
+        })?;
+        if config.pipeline == pipeline_ctx.pipeline_id() {
+            return Err(otap_df_config::error::Error::InvalidUserConfig {
+                error: format!("pipeline `{}` cannot bridge to itself", config.pipeline),
+            });
+        }
+
+        Ok(Self {
+            bridge: bridge(&pipeline_ctx.pipeline_group_id(), &config.pipeline),
+            pdata_metrics: pipeline_ctx.register_metrics::<ExporterPDataMetrics>(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Exporter<OtapPdata> for PipelineBridgeExporter {
+    async fn start(
+        mut self: Box<Self>,
+        mut msg_chan: MessageChannel<OtapPdata>,
+        effect_handler: EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        let timer_cancel_handle = effect_handler
+            .start_periodic_telemetry(Duration::from_secs(1))
+            .await?;
+
+        loop {
+            match msg_chan.recv().await? {
+                Message::Control(NodeControlMsg::Shutdown { deadline, .. }) => {
+                    _ = timer_cancel_handle.cancel().await;
+                    return Ok(TerminalState::new(deadline, [self.pdata_metrics]));
+                }
+                Message::Control(NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                }) => {
+                    _ = metrics_reporter.report(&mut self.pdata_metrics);
+                }
+                Message::PData(pdata) => {
+                    let signal_type = pdata.signal_type();
+                    self.pdata_metrics.inc_consumed(signal_type);
+
+                    let (context, mut payload) = pdata.into_parts();
+                    let data = context.take_payload_for_export(&mut payload);
+
+                    match self.bridge.sender.send_async(data).await {
+                        Ok(()) => {
+                            self.pdata_metrics.inc_exported(signal_type);
+                            effect_handler
+                                .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+                                .await?;
+                        }
+                        Err(_) => {
+                            self.pdata_metrics.inc_failed(signal_type);
+                            effect_handler
+                                .notify_nack(
+                                    NackMsg::new(
+                                        "pipeline bridge is closed",
+                                        OtapPdata::new(context, payload),
+                                    )
+                                    .with_class(NackClass::Unavailable),
+                                )
+                                .await?;
+                        }
+                    }
+                }
+                _ => {
+                    // ignore unhandled messages
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{test_exporter_no_subscription, test_exporter_with_subscription};
+    use otap_df_engine::Interests;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use serde_json::json;
+
+    #[test]
+    fn test_config() {
+        let controller_ctx = ControllerContext::new(MetricsRegistryHandle::new());
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "ingest".into(), 0, 0);
+        assert!(
+            PipelineBridgeExporter::from_config(pipeline_ctx.clone(), &json!({ "pipeline": "a" }))
+                .is_ok()
+        );
+        assert!(
+            PipelineBridgeExporter::from_config(
+                pipeline_ctx.clone(),
+                &json!({ "pipeline": "ingest" })
+            )
+            .is_err()
+        );
+        assert!(PipelineBridgeExporter::from_config(pipeline_ctx, &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_pipeline_bridge_exporter() {
+        let config = json!({ "pipeline": "bridge-exporter-test" });
+        test_exporter_no_subscription(&PIPELINE_BRIDGE_EXPORTER, config.clone());
+        test_exporter_with_subscription(
+            &PIPELINE_BRIDGE_EXPORTER,
+            config.clone(),
+            Interests::ACKS,
+            Interests::ACKS,
+        );
+        test_exporter_with_subscription(
+            &PIPELINE_BRIDGE_EXPORTER,
+            config,
+            Interests::ACKS | Interests::RETURN_DATA,
+            Interests::ACKS,
+        );
+    }
+}
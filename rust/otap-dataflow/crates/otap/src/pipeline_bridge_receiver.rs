@@ -0,0 +1,176 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Receiver consuming the pdata sent to its pipeline by the pipeline bridge exporters of the
+//! other pipelines of the same pipeline group.
+//!
+//! The instances of the receiver running on the different cores share the bridge of their
+//! pipeline (see [`crate::pipeline_bridge`]), each payload being received by one of them.
+//! Payloads still queued when the pipeline shuts down for a configuration reload are received by
+//! the rebuilt pipeline.
+
+use crate::OTAP_RECEIVER_FACTORIES;
+use crate::pdata::{Context, OtapPdata};
+use crate::pipeline_bridge::{Bridge, bridge};
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_engine::ReceiverFactory;
+use otap_df_engine::config::ReceiverConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::NodeControlMsg;
+use otap_df_engine::error::Error;
+use otap_df_engine::local::receiver as local;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::receiver::ReceiverWrapper;
+use otap_df_engine::terminal_state::TerminalState;
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry::metrics::MetricSet;
+use otap_df_telemetry_macros::metric_set;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The URN for the pipeline bridge receiver
+pub const PIPELINE_BRIDGE_RECEIVER_URN: &str = "urn:otap:receiver:pipeline_bridge";
+
+/// Pipeline bridge receiver metrics.
+#[metric_set(name = "pipeline_bridge.receiver.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct PipelineBridgeReceiverMetrics {
+    /// Number of batches received from the other pipelines.
+    #[metric(unit = "{batch}")]
+    pub batches_received: Counter<u64>,
+}
+
+/// A Receiver consuming the bridge of its pipeline.
+pub struct PipelineBridgeReceiver {
+    bridge: Bridge,
+    metrics: MetricSet<PipelineBridgeReceiverMetrics>,
+}
+
+/// Declares the pipeline bridge receiver as a local receiver factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_RECEIVER_FACTORIES)]
+pub static PIPELINE_BRIDGE_RECEIVER: ReceiverFactory<OtapPdata> = ReceiverFactory {
+    name: PIPELINE_BRIDGE_RECEIVER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             receiver_config: &ReceiverConfig| {
+        Ok(ReceiverWrapper::local(
+            PipelineBridgeReceiver::new(pipeline),
+            node,
+            node_config,
+            receiver_config,
+        ))
+    },
+};
+
+impl PipelineBridgeReceiver {
+    /// Creates a new receiver consuming the bridge of the given pipeline
+    #[must_use]
+    pub fn new(pipeline_ctx: PipelineContext) -> Self {
+        Self {
+            bridge: bridge(
+                &pipeline_ctx.pipeline_group_id(),
+                &pipeline_ctx.pipeline_id(),
+            ),
+            metrics: pipeline_ctx.register_metrics::<PipelineBridgeReceiverMetrics>(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Receiver<OtapPdata> for PipelineBridgeReceiver {
+    async fn start(
+        mut self: Box<Self>,
+        mut ctrl_msg_recv: local::ControlChannel<OtapPdata>,
+        effect_handler: local::EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        let telemetry_cancel_handle = effect_handler
+            .start_periodic_telemetry(Duration::from_secs(1))
+            .await?;
+
+        loop {
+            tokio::select! {
+                biased;
+
+                ctrl_msg = ctrl_msg_recv.recv() => match ctrl_msg {
+                    Ok(NodeControlMsg::Shutdown { deadline, .. }) => {
+                        let snapshot = self.metrics.snapshot();
+                        _ = telemetry_cancel_handle.cancel().await;
+                        return Ok(TerminalState::new(deadline, [snapshot]));
+                    }
+                    Ok(NodeControlMsg::CollectTelemetry { mut metrics_reporter }) => {
+                        _ = metrics_reporter.report(&mut self.metrics);
+                    }
+                    Err(e) => return Err(Error::ChannelRecvError(e)),
+                    _ => {
+                        // unknown control message do nothing
+                    }
+                },
+
+                // the registry keeps a sender of every bridge, so it is never disconnected
+                Ok(payload) = self.bridge.receiver.recv_async() => {
+                    // the context of the source pipeline does not apply to this pipeline
+                    effect_handler
+                        .send_message(OtapPdata::new(Context::default(), payload))
+                        .await?;
+                    self.metrics.batches_received.inc();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::receiver::TestRuntime;
+    use otap_df_engine::testing::test_node;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+
+    #[test]
+    fn test_pipeline_bridge_receiver() {
+        let test_runtime = TestRuntime::new();
+        let node_config = Arc::new(NodeUserConfig::new_receiver_config(
+            PIPELINE_BRIDGE_RECEIVER_URN,
+        ));
+        let controller_ctx = ControllerContext::new(MetricsRegistryHandle::new());
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "bridge-receiver".into(), 0, 0);
+        let receiver = ReceiverWrapper::local(
+            PipelineBridgeReceiver::new(pipeline_ctx),
+            test_node(test_runtime.config().name.clone()),
+            node_config,
+            test_runtime.config(),
+        );
+
+        // payloads queued by the exporters of another pipeline
+        let sender = bridge(&"grp".into(), &"bridge-receiver".into()).sender;
+        for _ in 0..2 {
+            sender
+                .send(OtlpProtoBytes::ExportLogsRequest(vec![]).into())
+                .expect("bridge is open");
+        }
+
+        test_runtime
+            .set_receiver(receiver)
+            .run_test(|ctx| async move {
+                ctx.sleep(Duration::from_millis(100)).await;
+                ctx.send_shutdown(std::time::Instant::now(), "test")
+                    .await
+                    .expect("shutdown");
+            })
+            .run_validation(|mut ctx| async move {
+                let mut batches = 0;
+                while let Ok(pdata) = ctx.recv().await {
+                    assert!(pdata.current_calldata().is_none());
+                    batches += 1;
+                }
+                assert_eq!(batches, 2);
+            });
+    }
+}
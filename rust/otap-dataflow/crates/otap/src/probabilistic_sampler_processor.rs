@@ -0,0 +1,522 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Probabilistic (head) sampler processor for OTAP pipelines.
+//!
+//! This processor keeps a percentage of the spans and log records it receives. The decision for
+//! a row only depends on a hash of its trace id (or of a log attribute) and of the configured
+//! seed, so all the spans of a trace are kept or dropped together, and every pipeline instance
+//! configured alike makes the same decisions. Sampling is computed as a mask over the root
+//! payload and applied with Arrow's filter kernel, dropping the attributes, events and links of
+//! the dropped rows as well. Metrics pass through.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! sampling_percentage: 15
+//! hash_seed: 22
+//! # hash this log attribute instead of the log trace id
+//! from_attribute: request.id
+//! # drop rows without a trace id (or attribute); true by default
+//! fail_closed: true
+//! ```
+//!
+//! `from_attribute` only applies to logs. Batches in which no row is sampled are acknowledged
+//! and not forwarded.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::filter_processor::eval::filter_rows;
+use crate::pdata::attributes::{AttributeIter, AttributeValue};
+use crate::pdata::{OtapPayload, OtapPdata, decode_ids, pdata_error};
+use arrow::array::{AsArray, BooleanArray, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, UInt32Type};
+use arrow::error::ArrowError;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::ConsumerEffectHandlerExtension;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::AckMsg;
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::schema::consts;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod metrics;
+use self::metrics::ProbabilisticSamplerProcessorMetrics;
+
+/// URN for the ProbabilisticSamplerProcessor
+pub const PROBABILISTIC_SAMPLER_PROCESSOR_URN: &str = "urn:otap:processor:probabilistic_sampler";
+
+/// Configuration for the ProbabilisticSamplerProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Percentage of the traces (or logs) to keep, between 0 and 100.
+    pub sampling_percentage: f64,
+
+    /// Seed of the hash. Instances sampling the same data consistently must use the same seed,
+    /// and layered samplers should use different seeds.
+    #[serde(default)]
+    pub hash_seed: u32,
+
+    /// Log attribute whose value is hashed instead of the log trace id.
+    #[serde(default)]
+    pub from_attribute: Option<String>,
+
+    /// Whether rows without a trace id (or attribute) are dropped rather than kept.
+    #[serde(default = "default_fail_closed")]
+    pub fail_closed: bool,
+}
+
+const fn default_fail_closed() -> bool {
+    true
+}
+
+/// Whether a row whose hash source is `source` is sampled at `sampling_percentage` (0 to 100)
+/// with the given seed.
+///
+/// This is shared with the probabilistic policy of the tail sampling processor so both make the
+/// same decision for a trace.
+pub(crate) fn is_sampled(seed: u32, source: &[u8], sampling_percentage: f64) -> bool {
+    // Compare the hash with the percentage in units of 1/10000 of a percent
+    let bucket = hash(seed, source) % 1_000_000;
+    (bucket as f64) < sampling_percentage * 10_000.0
+}
+
+/// FNV-1a hash of the seed followed by `bytes`.
+fn hash(seed: u32, bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    seed.to_be_bytes()
+        .iter()
+        .chain(bytes)
+        .fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+        })
+}
+
+/// Processor that keeps a consistent percentage of the spans and log records.
+pub struct ProbabilisticSamplerProcessor {
+    config: Config,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<ProbabilisticSamplerProcessorMetrics>>,
+}
+
+/// Outcome of sampling a batch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct SampleCounts {
+    rows: usize,
+    kept: usize,
+    missing_source: usize,
+}
+
+impl ProbabilisticSamplerProcessor {
+    /// Creates a new ProbabilisticSamplerProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse ProbabilisticSamplerProcessor configuration: {e}"),
+            })?;
+        if !(0.0..=100.0).contains(&config.sampling_percentage) {
+            return Err(ConfigError::InvalidUserConfig {
+                error: format!(
+                    "sampling_percentage must be between 0 and 100, got {}",
+                    config.sampling_percentage
+                ),
+            });
+        }
+        Ok(Self {
+            config,
+            metrics_set: None,
+        })
+    }
+
+    /// Drop the rows of `records` that are not sampled.
+    fn sample(&self, records: &mut OtapArrowRecords) -> Result<SampleCounts, ArrowError> {
+        let root_type = match records {
+            OtapArrowRecords::Logs(_) => ArrowPayloadType::Logs,
+            OtapArrowRecords::Traces(_) => ArrowPayloadType::Spans,
+            OtapArrowRecords::Metrics(_) => return Ok(SampleCounts::default()),
+        };
+        let num_rows = records.get(root_type).map_or(0, RecordBatch::num_rows);
+        if num_rows == 0 {
+            return Ok(SampleCounts::default());
+        }
+
+        decode_ids(records)?;
+        let Some(root) = records.get(root_type) else {
+            return Ok(SampleCounts::default());
+        };
+
+        let decisions = match (&self.config.from_attribute, root_type) {
+            (Some(key), ArrowPayloadType::Logs) => self.attribute_decisions(records, root, key)?,
+            _ => self.trace_id_decisions(root)?,
+        };
+        let missing_source = decisions.iter().filter(|d| d.is_none()).count();
+        let mask: BooleanArray = decisions
+            .into_iter()
+            .map(|decision| Some(decision.unwrap_or(!self.config.fail_closed)))
+            .collect();
+        let kept = mask.true_count();
+        if kept > 0 && kept < num_rows {
+            filter_rows(records, root_type, &mask)?;
+        }
+        Ok(SampleCounts {
+            rows: num_rows,
+            kept,
+            missing_source,
+        })
+    }
+
+    /// Decision for each row of `root` based on its trace id, None if it has no trace id.
+    fn trace_id_decisions(&self, root: &RecordBatch) -> Result<Vec<Option<bool>>, ArrowError> {
+        let Some(trace_ids) = root.column_by_name(consts::TRACE_ID) else {
+            return Ok(vec![None; root.num_rows()]);
+        };
+        let trace_ids = cast(trace_ids, &DataType::FixedSizeBinary(16))?;
+        Ok(trace_ids
+            .as_fixed_size_binary()
+            .iter()
+            .map(|trace_id| {
+                trace_id
+                    .filter(|id| id.iter().any(|b| *b != 0))
+                    .map(|id| self.decide(id))
+            })
+            .collect())
+    }
+
+    /// Decision for each row of `root` based on the value of its `key` attribute, None if it does
+    /// not have one.
+    fn attribute_decisions(
+        &self,
+        records: &OtapArrowRecords,
+        root: &RecordBatch,
+        key: &str,
+    ) -> Result<Vec<Option<bool>>, ArrowError> {
+        let mut by_parent = HashMap::new();
+        if let Some(attrs) = records.get(ArrowPayloadType::LogAttrs) {
+            let attrs = AttributeIter::try_new(attrs)
+                .map_err(|e| ArrowError::InvalidArgumentError(e.to_string()))?;
+            for attr in attrs.filter(|attr| attr.key == key) {
+                let decision = match attr.value {
+                    AttributeValue::Str(v) => self.decide(v.as_bytes()),
+                    AttributeValue::Int(v) => self.decide(&v.to_be_bytes()),
+                    AttributeValue::Double(v) => self.decide(&v.to_be_bytes()),
+                    AttributeValue::Bool(v) => self.decide(&[u8::from(v)]),
+                    AttributeValue::Bytes(v) | AttributeValue::Serialized(v) => self.decide(v),
+                    AttributeValue::Empty => continue,
+                };
+                let _ = by_parent.insert(attr.parent_id, decision);
+            }
+        }
+
+        let Some(ids) = root.column_by_name(consts::ID) else {
+            return Ok(vec![None; root.num_rows()]);
+        };
+        let ids = cast(ids, &DataType::UInt32)?;
+        Ok(ids
+            .as_primitive::<UInt32Type>()
+            .iter()
+            .map(|id| id.and_then(|id| by_parent.get(&id).copied()))
+            .collect())
+    }
+
+    fn decide(&self, source: &[u8]) -> bool {
+        is_sampled(
+            self.config.hash_seed,
+            source,
+            self.config.sampling_percentage,
+        )
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for ProbabilisticSamplerProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                otap_df_engine::control::NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                if pdata.signal_type() == SignalType::Metrics {
+                    effect_handler.send_message(pdata).await?;
+                    if let Some(m) = self.metrics_set.as_mut() {
+                        m.msgs_forwarded.inc();
+                    }
+                    return Ok(());
+                }
+
+                let (context, payload) = pdata.into_parts();
+                let mut records: OtapArrowRecords = payload.try_into()?;
+                let counts = self
+                    .sample(&mut records)
+                    .map_err(|e| pdata_error(&format!("sampling failed: {e}")))?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.rows_sampled.add(counts.kept as u64);
+                    m.rows_dropped.add((counts.rows - counts.kept) as u64);
+                    m.rows_missing_source.add(counts.missing_source as u64);
+                }
+
+                if counts.rows > 0 && counts.kept == 0 {
+                    // Nothing left to forward: the batch is fully processed
+                    if let Some(m) = self.metrics_set.as_mut() {
+                        m.msgs_dropped.inc();
+                    }
+                    let mut payload: OtapPayload = records.into();
+                    if !context.may_return_payload() {
+                        let _ = payload.take_payload();
+                    }
+                    return effect_handler
+                        .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+                        .await;
+                }
+
+                effect_handler
+                    .send_message(OtapPdata::new(context, records.into()))
+                    .await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_forwarded.inc();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Factory function to create a ProbabilisticSamplerProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_probabilistic_sampler_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = ProbabilisticSamplerProcessor::from_config(&node_config.config)?;
+    proc.metrics_set =
+        Some(pipeline_ctx.register_metrics::<ProbabilisticSamplerProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register ProbabilisticSamplerProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static PROBABILISTIC_SAMPLER_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: PROBABILISTIC_SAMPLER_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_probabilistic_sampler_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the probabilistic sampler processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static PROBABILISTIC_SAMPLER_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(PROBABILISTIC_SAMPLER_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::{logs::v1::ExportLogsServiceRequest, trace::v1::ExportTraceServiceRequest},
+        common::v1::{AnyValue, InstrumentationScope, KeyValue},
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+        resource::v1::Resource,
+        trace::v1::{ResourceSpans, ScopeSpans, Span},
+    };
+    use prost::Message as _;
+    use serde_json::json;
+
+    fn trace_id(i: u32) -> Vec<u8> {
+        let mut trace_id = vec![7; 16];
+        trace_id[..4].copy_from_slice(&i.to_be_bytes());
+        trace_id
+    }
+
+    /// 100 traces of 2 spans each.
+    fn traces() -> OtapArrowRecords {
+        let spans = (0..200u32)
+            .map(|i| Span {
+                trace_id: trace_id(i / 2),
+                span_id: vec![1; 8],
+                name: format!("span-{i}"),
+                ..Default::default()
+            })
+            .collect();
+        let req = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource::default()),
+                scope_spans: vec![ScopeSpans {
+                    scope: Some(InstrumentationScope::default()),
+                    spans,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtapPayload::from(OtlpProtoBytes::ExportTracesRequest(bytes))
+            .try_into()
+            .expect("convert to otap")
+    }
+
+    /// 100 log records, with a `request.id` attribute on the even ones.
+    fn logs() -> OtapArrowRecords {
+        let log_records = (0..100u32)
+            .map(|i| LogRecord {
+                time_unix_nano: 1,
+                body: Some(AnyValue::new_string(format!("log-{i}"))),
+                attributes: if i % 2 == 0 {
+                    vec![KeyValue::new("request.id", AnyValue::new_int(i as i64))]
+                } else {
+                    Vec::new()
+                },
+                ..Default::default()
+            })
+            .collect();
+        let req = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource::default()),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(InstrumentationScope::default()),
+                    log_records,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtapPayload::from(OtlpProtoBytes::ExportLogsRequest(bytes))
+            .try_into()
+            .expect("convert to otap")
+    }
+
+    fn sampler(config: Value) -> ProbabilisticSamplerProcessor {
+        ProbabilisticSamplerProcessor::from_config(&config).expect("valid config")
+    }
+
+    /// Names of the spans of `records`.
+    fn span_names(records: OtapArrowRecords) -> Vec<String> {
+        let OtlpProtoBytes::ExportTracesRequest(bytes) = OtapPayload::from(records)
+            .try_into()
+            .expect("convert to otlp")
+        else {
+            panic!("unexpected otlp variant");
+        };
+        ExportTraceServiceRequest::decode(bytes.as_slice())
+            .expect("decode")
+            .resource_spans
+            .into_iter()
+            .flat_map(|rs| rs.scope_spans)
+            .flat_map(|ss| ss.spans)
+            .map(|span| span.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_traces_sampled_consistently_by_trace_id() {
+        let config = json!({ "sampling_percentage": 50, "hash_seed": 3 });
+        let mut records = traces();
+        let counts = sampler(config.clone())
+            .sample(&mut records)
+            .expect("sample");
+        assert_eq!(counts.rows, 200);
+        assert!((60..140).contains(&counts.kept), "kept {}", counts.kept);
+        assert_eq!(counts.kept % 2, 0, "spans of a trace are kept together");
+        assert_eq!(records.batch_length(), counts.kept);
+
+        // the same decisions are made by another instance
+        let mut again = traces();
+        let _ = sampler(config).sample(&mut again).expect("sample");
+        assert_eq!(span_names(records), span_names(again));
+
+        for (percentage, expected) in [(0, 0), (100, 200)] {
+            let mut records = traces();
+            let counts = sampler(json!({ "sampling_percentage": percentage }))
+                .sample(&mut records)
+                .expect("sample");
+            assert_eq!(counts.kept, expected);
+        }
+    }
+
+    #[test]
+    fn test_logs_sampled_by_attribute() {
+        let mut records = logs();
+        let counts = sampler(json!({
+            "sampling_percentage": 100,
+            "from_attribute": "request.id",
+        }))
+        .sample(&mut records)
+        .expect("sample");
+        // logs without the attribute (and without trace id) are dropped
+        assert_eq!(counts.missing_source, 50);
+        assert_eq!(counts.kept, 50);
+
+        let mut records = logs();
+        let counts = sampler(json!({
+            "sampling_percentage": 100,
+            "from_attribute": "request.id",
+            "fail_closed": false,
+        }))
+        .sample(&mut records)
+        .expect("sample");
+        assert_eq!(counts.kept, 100);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({}),
+            json!({ "sampling_percentage": 150 }),
+            json!({ "sampling_percentage": 10, "attribute": "a" }),
+        ] {
+            let err = ProbabilisticSamplerProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
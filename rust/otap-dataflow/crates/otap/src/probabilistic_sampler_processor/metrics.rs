@@ -0,0 +1,36 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the ProbabilisticSamplerProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the ProbabilisticSamplerProcessor node.
+#[metric_set(name = "probabilistic_sampler.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct ProbabilisticSamplerProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// PData messages dropped because none of their rows were sampled.
+    #[metric(unit = "{msg}")]
+    pub msgs_dropped: Counter<u64>,
+
+    /// Rows (spans or log records) sampled.
+    #[metric(unit = "{row}")]
+    pub rows_sampled: Counter<u64>,
+
+    /// Rows (spans or log records) not sampled and dropped.
+    #[metric(unit = "{row}")]
+    pub rows_dropped: Counter<u64>,
+
+    /// Rows without a trace id or sampling attribute, whose fate follows `fail_closed`.
+    #[metric(unit = "{row}")]
+    pub rows_missing_source: Counter<u64>,
+}
@@ -0,0 +1,650 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rate limiting processor for OTAP pipelines.
+//!
+//! This processor enforces a maximum rate of records (log records, spans or metric data points)
+//! and/or bytes per second, with a burst allowance, using token buckets. The limits apply to the
+//! whole pipeline instance, or to each tenant when `tenant_attribute` names a resource attribute
+//! identifying the tenants.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! records_per_second: 50000
+//! burst_records: 100000
+//! bytes_per_second: 10000000
+//! tenant_attribute: service.namespace
+//! on_limit: backpressure
+//! ```
+//!
+//! When a message exceeds the limits, the processor either:
+//! - `backpressure` (default): holds the message until the limits allow it. The processor stops
+//!   reading its input meanwhile, so the upstream nodes are slowed down once the channel is full.
+//! - `drop`: nacks the message, letting the upstream nodes decide whether to retry.
+//!
+//! A message larger than the burst allowance is let through once the buckets are full (and, in
+//! `backpressure` mode, it delays the following messages accordingly).
+//!
+//! The size of a message is the encoded size of OTLP payloads, or the in-memory size of Arrow
+//! payloads. Messages are attributed to tenants by their number of root rows (e.g. log records).
+//! Messages without the tenant attribute, and tenants beyond `max_tenants`, share a default
+//! bucket. Note that tenant attribution requires converting OTLP payloads to Arrow.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::filter_processor::eval::field_values;
+use crate::filter_processor::expr::{Domain, Field};
+use crate::pdata::OtapPdata;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::ConsumerEffectHandlerExtension;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{NackClass, NackMsg, NodeControlMsg};
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod metrics;
+use self::metrics::RateLimitProcessorMetrics;
+
+/// URN for the RateLimitProcessor
+pub const RATE_LIMIT_PROCESSOR_URN: &str = "urn:otap:processor:rate_limit";
+
+/// Default maximum number of tenants tracked with their own limits.
+const DEFAULT_MAX_TENANTS: usize = 10_000;
+
+/// Tenant key of the messages not attributed to a tenant.
+const DEFAULT_TENANT: &str = "";
+
+/// What to do with a message exceeding the limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitAction {
+    /// Hold the message until the limits allow it, slowing down the upstream nodes.
+    #[default]
+    Backpressure,
+    /// Nack the message.
+    Drop,
+}
+
+/// Configuration for the RateLimitProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Maximum sustained number of records per second.
+    #[serde(default)]
+    pub records_per_second: Option<f64>,
+
+    /// Maximum number of records allowed in a burst (defaults to one second of records).
+    #[serde(default)]
+    pub burst_records: Option<f64>,
+
+    /// Maximum sustained number of bytes per second.
+    #[serde(default)]
+    pub bytes_per_second: Option<f64>,
+
+    /// Maximum number of bytes allowed in a burst (defaults to one second of bytes).
+    #[serde(default)]
+    pub burst_bytes: Option<f64>,
+
+    /// Resource attribute identifying the tenants. The limits apply to each tenant when set, and
+    /// to the whole pipeline instance otherwise.
+    #[serde(default)]
+    pub tenant_attribute: Option<String>,
+
+    /// Maximum number of tenants tracked with their own limits.
+    #[serde(default = "default_max_tenants")]
+    pub max_tenants: usize,
+
+    /// What to do with a message exceeding the limits.
+    #[serde(default)]
+    pub on_limit: LimitAction,
+}
+
+const fn default_max_tenants() -> usize {
+    DEFAULT_MAX_TENANTS
+}
+
+/// Token bucket refilled at a constant rate, up to its burst capacity.
+///
+/// The balance may go negative: a message is always charged in full, and the debt delays the
+/// following messages.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, burst: f64, now: Instant) -> Self {
+        Self {
+            rate,
+            burst,
+            tokens: burst,
+            last: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last = now;
+    }
+
+    /// Whether `amount` can be taken without going into debt. Amounts larger than the burst
+    /// only need a full bucket.
+    fn can_take(&mut self, amount: f64, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= amount.min(self.burst)
+    }
+
+    /// Takes `amount` and returns how long to wait for the balance to be paid back.
+    fn take(&mut self, amount: f64, now: Instant) -> Duration {
+        self.refill(now);
+        self.tokens -= amount;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// Record and byte buckets of a tenant.
+#[derive(Debug, Clone)]
+struct Limits {
+    records: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+impl Limits {
+    fn can_take(&mut self, usage: Usage, now: Instant) -> bool {
+        let records = self
+            .records
+            .as_mut()
+            .is_none_or(|bucket| bucket.can_take(usage.records, now));
+        let bytes = self
+            .bytes
+            .as_mut()
+            .is_none_or(|bucket| bucket.can_take(usage.bytes, now));
+        records && bytes
+    }
+
+    fn take(&mut self, usage: Usage, now: Instant) -> Duration {
+        let records = self
+            .records
+            .as_mut()
+            .map_or(Duration::ZERO, |bucket| bucket.take(usage.records, now));
+        let bytes = self
+            .bytes
+            .as_mut()
+            .map_or(Duration::ZERO, |bucket| bucket.take(usage.bytes, now));
+        records.max(bytes)
+    }
+}
+
+/// Records and bytes charged to a tenant.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Usage {
+    records: f64,
+    bytes: f64,
+}
+
+/// Processor throttling the record and byte rates of a pipeline or of its tenants.
+pub struct RateLimitProcessor {
+    config: Config,
+    // Resource attribute identifying the tenants
+    tenant_field: Option<Field>,
+    // Buckets of each tenant, created on first use
+    tenants: HashMap<String, Limits>,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<RateLimitProcessorMetrics>>,
+}
+
+impl RateLimitProcessor {
+    /// Creates a new RateLimitProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse RateLimitProcessor configuration: {e}"),
+            })?;
+        if config.records_per_second.is_none() && config.bytes_per_second.is_none() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "at least one of records_per_second and bytes_per_second is required"
+                    .to_string(),
+            });
+        }
+        for (name, value) in [
+            ("records_per_second", config.records_per_second),
+            ("burst_records", config.burst_records),
+            ("bytes_per_second", config.bytes_per_second),
+            ("burst_bytes", config.burst_bytes),
+        ] {
+            if value.is_some_and(|v| !v.is_finite() || v <= 0.0) {
+                return Err(ConfigError::InvalidUserConfig {
+                    error: format!("{name} must be a positive number"),
+                });
+            }
+        }
+        if config.tenant_attribute.as_deref() == Some("") {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "tenant_attribute must not be empty".to_string(),
+            });
+        }
+        let tenant_field = config.tenant_attribute.clone().map(|key| Field::Attribute {
+            domain: Domain::Resource,
+            key,
+        });
+        Ok(Self {
+            config,
+            tenant_field,
+            tenants: HashMap::new(),
+            metrics_set: None,
+        })
+    }
+
+    /// Fresh buckets for a new tenant.
+    fn new_limits(&self, now: Instant) -> Limits {
+        let bucket = |rate: Option<f64>, burst: Option<f64>| {
+            rate.map(|rate| TokenBucket::new(rate, burst.unwrap_or(rate), now))
+        };
+        Limits {
+            records: bucket(self.config.records_per_second, self.config.burst_records),
+            bytes: bucket(self.config.bytes_per_second, self.config.burst_bytes),
+        }
+    }
+
+    /// Buckets of `tenant`, falling back to the default tenant beyond `max_tenants`.
+    fn limits(&mut self, tenant: &str, now: Instant) -> &mut Limits {
+        let key =
+            if self.tenants.contains_key(tenant) || self.tenants.len() < self.config.max_tenants {
+                tenant
+            } else {
+                DEFAULT_TENANT
+            };
+        let limits = self.new_limits(now);
+        self.tenants.entry(key.to_string()).or_insert(limits)
+    }
+
+    /// Splits the records and bytes of `pdata` between its tenants, returning the (possibly
+    /// converted) pdata.
+    fn usage(
+        &mut self,
+        pdata: OtapPdata,
+    ) -> Result<(OtapPdata, Vec<(String, Usage)>), EngineError> {
+        let total = Usage {
+            records: pdata.num_items() as f64,
+            bytes: pdata.num_bytes() as f64,
+        };
+        let Some(tenant_field) = self.tenant_field.as_ref() else {
+            return Ok((pdata, vec![(DEFAULT_TENANT.to_string(), total)]));
+        };
+
+        let (context, payload) = pdata.into_parts();
+        let mut records: OtapArrowRecords = payload.try_into()?;
+        let tenants = records
+            .decode_transport_optimized_ids()
+            .map_err(|e| e.to_string())
+            .and_then(|_| field_values(tenant_field, &records).map_err(|e| e.to_string()));
+        let pdata = OtapPdata::new(context, records.into());
+        let tenants = match tenants {
+            Ok(tenants) => tenants,
+            Err(_) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.tenant_lookup_failed.inc();
+                }
+                return Ok((pdata, vec![(DEFAULT_TENANT.to_string(), total)]));
+            }
+        };
+        Ok((pdata, split_usage(total, tenants)))
+    }
+}
+
+/// Splits `total` between the tenants in proportion to their number of rows.
+fn split_usage(total: Usage, tenants: Vec<Option<String>>) -> Vec<(String, Usage)> {
+    if tenants.is_empty() {
+        return vec![(DEFAULT_TENANT.to_string(), total)];
+    }
+    let num_rows = tenants.len() as f64;
+    let mut rows: HashMap<String, usize> = HashMap::new();
+    for tenant in tenants {
+        *rows
+            .entry(tenant.unwrap_or_else(|| DEFAULT_TENANT.to_string()))
+            .or_default() += 1;
+    }
+    let mut usage: Vec<(String, Usage)> = rows
+        .into_iter()
+        .map(|(tenant, rows)| {
+            let share = rows as f64 / num_rows;
+            let usage = Usage {
+                records: total.records * share,
+                bytes: total.bytes * share,
+            };
+            (tenant, usage)
+        })
+        .collect();
+    usage.sort_by(|a, b| a.0.cmp(&b.0));
+    usage
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for RateLimitProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                let (pdata, usage) = self.usage(pdata)?;
+                let now = Instant::now();
+
+                match self.config.on_limit {
+                    LimitAction::Drop => {
+                        let allowed = usage
+                            .iter()
+                            .all(|(tenant, usage)| self.limits(tenant, now).can_take(*usage, now));
+                        if !allowed {
+                            if let Some(m) = self.metrics_set.as_mut() {
+                                m.msgs_dropped.inc();
+                            }
+                            return effect_handler
+                                .notify_nack(
+                                    NackMsg::new("rate limit exceeded", pdata)
+                                        .with_class(NackClass::Throttled),
+                                )
+                                .await;
+                        }
+                        for (tenant, usage) in &usage {
+                            let _ = self.limits(tenant, now).take(*usage, now);
+                        }
+                    }
+                    LimitAction::Backpressure => {
+                        let wait = usage
+                            .iter()
+                            .map(|(tenant, usage)| self.limits(tenant, now).take(*usage, now))
+                            .max()
+                            .unwrap_or_default();
+                        if !wait.is_zero() {
+                            if let Some(m) = self.metrics_set.as_mut() {
+                                m.msgs_throttled.inc();
+                                m.throttled_time.add(wait.as_millis() as u64);
+                            }
+                            // not reading the input meanwhile is what slows down the upstream
+                            tokio::time::sleep(wait).await;
+                        }
+                    }
+                }
+
+                effect_handler.send_message(pdata).await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_forwarded.inc();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Factory function to create a RateLimitProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_rate_limit_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = RateLimitProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<RateLimitProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register RateLimitProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static RATE_LIMIT_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: RATE_LIMIT_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_rate_limit_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the rate limit processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static RATE_LIMIT_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(RATE_LIMIT_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::{node::test_node, processor::TestRuntime};
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::logs::v1::ExportLogsServiceRequest,
+        common::v1::{AnyValue, InstrumentationScope, KeyValue},
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+        resource::v1::Resource,
+    };
+    use prost::Message as _;
+    use serde_json::json;
+
+    /// A logs request with `count` log records for each of the given tenants.
+    fn logs_request(tenants: &[(&str, usize)]) -> OtapPdata {
+        let req = ExportLogsServiceRequest {
+            resource_logs: tenants
+                .iter()
+                .map(|(tenant, count)| ResourceLogs {
+                    resource: Some(Resource {
+                        attributes: vec![KeyValue::new("tenant", AnyValue::new_string(*tenant))],
+                        ..Default::default()
+                    }),
+                    scope_logs: vec![ScopeLogs {
+                        scope: Some(InstrumentationScope::default()),
+                        log_records: vec![
+                            LogRecord {
+                                time_unix_nano: 1,
+                                body: Some(AnyValue::new_string("hello")),
+                                ..Default::default()
+                            };
+                            *count
+                        ],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })
+                .collect(),
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(bytes).into())
+    }
+
+    fn run(config: Value, inputs: Vec<OtapPdata>, check: impl FnOnce(Vec<OtapPdata>) + 'static) {
+        let pipeline_ctx = ControllerContext::new(MetricsRegistryHandle::new())
+            .pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let mut node_config = NodeUserConfig::new_processor_config(RATE_LIMIT_PROCESSOR_URN);
+        node_config.config = config;
+        let proc = create_rate_limit_processor(
+            pipeline_ctx,
+            test_node("rate-limit-processor-test"),
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                for input in inputs {
+                    ctx.process(Message::PData(input)).await.expect("process");
+                }
+                check(ctx.drain_pdata().await);
+            })
+            .validate(|_| async move {});
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10.0, 20.0, start);
+        assert!(bucket.can_take(20.0, start));
+        assert_eq!(bucket.take(15.0, start), Duration::ZERO);
+        assert!(!bucket.can_take(10.0, start));
+        // going into debt delays until the debt is paid back
+        assert_eq!(bucket.take(10.0, start), Duration::from_millis(500));
+
+        // refilled at the configured rate, up to the burst
+        let later = start + Duration::from_secs(1);
+        assert!(bucket.can_take(5.0, later));
+        assert!(!bucket.can_take(6.0, later));
+        let much_later = start + Duration::from_secs(60);
+        assert!(bucket.can_take(20.0, much_later));
+        // larger than the burst, only needs a full bucket
+        assert!(bucket.can_take(100.0, much_later));
+    }
+
+    #[test]
+    fn test_split_usage() {
+        let total = Usage {
+            records: 4.0,
+            bytes: 400.0,
+        };
+        let usage = split_usage(
+            total,
+            vec![Some("a".into()), Some("b".into()), Some("a".into()), None],
+        );
+        assert_eq!(
+            usage,
+            vec![
+                (
+                    "".to_string(),
+                    Usage {
+                        records: 1.0,
+                        bytes: 100.0
+                    }
+                ),
+                (
+                    "a".to_string(),
+                    Usage {
+                        records: 2.0,
+                        bytes: 200.0
+                    }
+                ),
+                (
+                    "b".to_string(),
+                    Usage {
+                        records: 1.0,
+                        bytes: 100.0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drop_over_limit() {
+        let inputs = vec![
+            logs_request(&[("a", 3)]),
+            logs_request(&[("a", 3)]),
+            logs_request(&[("b", 3)]),
+        ];
+        run(
+            json!({
+                "records_per_second": 1.0,
+                "burst_records": 5.0,
+                "on_limit": "drop",
+            }),
+            inputs,
+            |out| assert_eq!(out.len(), 1),
+        );
+    }
+
+    #[test]
+    fn test_drop_over_tenant_limit() {
+        let inputs = vec![
+            logs_request(&[("a", 3)]),
+            logs_request(&[("a", 3)]),
+            logs_request(&[("b", 3)]),
+        ];
+        run(
+            json!({
+                "records_per_second": 1.0,
+                "burst_records": 5.0,
+                "tenant_attribute": "tenant",
+                "on_limit": "drop",
+            }),
+            inputs,
+            |out| assert_eq!(out.len(), 2),
+        );
+    }
+
+    #[test]
+    fn test_backpressure_forwards_everything() {
+        let inputs = vec![logs_request(&[("a", 3)]), logs_request(&[("a", 3)])];
+        run(
+            json!({ "records_per_second": 100.0, "burst_records": 5.0 }),
+            inputs,
+            |out| assert_eq!(out.len(), 2),
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({}),
+            json!({ "records_per_second": 0 }),
+            json!({ "bytes_per_second": -1 }),
+            json!({ "records_per_second": 10, "burst_records": 0 }),
+            json!({ "records_per_second": 10, "tenant_attribute": "" }),
+            json!({ "records_per_second": 10, "on_limit": "block" }),
+        ] {
+            let err = RateLimitProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
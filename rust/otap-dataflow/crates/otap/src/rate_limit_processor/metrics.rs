@@ -0,0 +1,36 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the RateLimitProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the RateLimitProcessor node.
+#[metric_set(name = "rate_limit.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct RateLimitProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// PData messages held back until the rate limits allowed them.
+    #[metric(unit = "{msg}")]
+    pub msgs_throttled: Counter<u64>,
+
+    /// PData messages refused (nacked) because they exceeded the rate limits.
+    #[metric(unit = "{msg}")]
+    pub msgs_dropped: Counter<u64>,
+
+    /// Total time messages were held back.
+    #[metric(unit = "ms")]
+    pub throttled_time: Counter<u64>,
+
+    /// Number of failures to attribute a message to its tenants.
+    #[metric(unit = "{op}")]
+    pub tenant_lookup_failed: Counter<u64>,
+}
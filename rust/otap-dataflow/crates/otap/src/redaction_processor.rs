@@ -0,0 +1,552 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Redaction processor for OTAP pipelines.
+//!
+//! This processor scrubs sensitive data from the attributes of all signals, so that it happens
+//! once in the pipeline whatever the exporters. It applies to the resource, scope and signal
+//! attributes (span events and links, metric data points and exemplars included):
+//!
+//! - attributes whose key is not in `allowed_keys` are removed, unless `allow_all_keys` is set;
+//! - in the string values of the remaining attributes, the substrings matching one of the
+//!   `blocked_values` regular expressions are replaced with `****`;
+//! - attributes whose key is in `ignored_keys` are left untouched.
+//!
+//! With `hash_values`, the attributes whose key is not allowed are kept with the SHA-256 hash of
+//! their value, and blocked substrings are replaced with their hash instead of `****`. Hashing
+//! keeps values correlatable without exposing them.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! allowed_keys: [http.method, http.route, http.status_code]
+//! ignored_keys: [service.name]
+//! blocked_values:
+//!   - "4[0-9]{12}(?:[0-9]{3})?"          # Visa card numbers
+//!   - "[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+"  # email addresses
+//! hash_values: false
+//! ```
+//!
+//! Note that an empty configuration removes all the attributes.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::attributes_processor::value_actions::{hash, hex, map_strings, unpack_dictionaries};
+use crate::pdata::{OtapPdata, pdata_error};
+use arrow::array::{AsArray, BooleanArray, RecordBatch};
+use arrow::compute::{filter_record_batch, not};
+use arrow::datatypes::UInt8Type;
+use arrow::error::ArrowError;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::NodeControlMsg;
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::otlp::attributes::AttributeValueType;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::schema::consts;
+use regex::{Regex, RegexSet};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+mod metrics;
+use self::metrics::RedactionProcessorMetrics;
+
+/// URN for the RedactionProcessor
+pub const REDACTION_PROCESSOR_URN: &str = "urn:otap:processor:redaction";
+
+/// Replacement of the blocked substrings when values are not hashed.
+const MASK: &str = "****";
+
+/// Configuration for the RedactionProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Keep all attribute keys, only masking blocked values.
+    #[serde(default)]
+    pub allow_all_keys: bool,
+
+    /// Keys of the attributes to keep. The others are removed, or hashed with `hash_values`.
+    #[serde(default)]
+    pub allowed_keys: Vec<String>,
+
+    /// Keys of the attributes left untouched, whatever their value.
+    #[serde(default)]
+    pub ignored_keys: Vec<String>,
+
+    /// Regular expressions matching the substrings to mask in the string values.
+    #[serde(default)]
+    pub blocked_values: Vec<String>,
+
+    /// Hash the values of the attributes that are not allowed and the blocked substrings,
+    /// instead of removing and masking them.
+    #[serde(default)]
+    pub hash_values: bool,
+}
+
+/// Attribute payloads redacted for each signal.
+const LOGS_ATTRS: &[ArrowPayloadType] = &[
+    ArrowPayloadType::ResourceAttrs,
+    ArrowPayloadType::ScopeAttrs,
+    ArrowPayloadType::LogAttrs,
+];
+const METRICS_ATTRS: &[ArrowPayloadType] = &[
+    ArrowPayloadType::ResourceAttrs,
+    ArrowPayloadType::ScopeAttrs,
+    ArrowPayloadType::MetricAttrs,
+    ArrowPayloadType::NumberDpAttrs,
+    ArrowPayloadType::HistogramDpAttrs,
+    ArrowPayloadType::SummaryDpAttrs,
+    ArrowPayloadType::NumberDpExemplarAttrs,
+    ArrowPayloadType::HistogramDpExemplarAttrs,
+];
+const TRACES_ATTRS: &[ArrowPayloadType] = &[
+    ArrowPayloadType::ResourceAttrs,
+    ArrowPayloadType::ScopeAttrs,
+    ArrowPayloadType::SpanAttrs,
+    ArrowPayloadType::SpanEventAttrs,
+    ArrowPayloadType::SpanLinkAttrs,
+];
+
+/// Number of attributes changed by the redaction of a batch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct RedactionStats {
+    dropped: u64,
+    hashed: u64,
+    redacted: u64,
+}
+
+/// Processor removing or masking sensitive attributes.
+pub struct RedactionProcessor {
+    allow_all_keys: bool,
+    allowed_keys: HashSet<String>,
+    ignored_keys: HashSet<String>,
+    // Detects the values to redact, `blocked_patterns` then replaces the matches
+    blocked_set: RegexSet,
+    blocked_patterns: Vec<Regex>,
+    hash_values: bool,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<RedactionProcessorMetrics>>,
+}
+
+impl RedactionProcessor {
+    /// Creates a new RedactionProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse RedactionProcessor configuration: {e}"),
+            })?;
+        Self::new(config)
+    }
+
+    fn new(config: Config) -> Result<Self, ConfigError> {
+        let invalid_pattern = |e: regex::Error| ConfigError::InvalidUserConfig {
+            error: format!("invalid blocked_values pattern: {e}"),
+        };
+        let blocked_set = RegexSet::new(&config.blocked_values).map_err(invalid_pattern)?;
+        let blocked_patterns = config
+            .blocked_values
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid_pattern)?;
+        Ok(Self {
+            allow_all_keys: config.allow_all_keys,
+            allowed_keys: config.allowed_keys.into_iter().collect(),
+            ignored_keys: config.ignored_keys.into_iter().collect(),
+            blocked_set,
+            blocked_patterns,
+            hash_values: config.hash_values,
+            metrics_set: None,
+        })
+    }
+
+    /// Redact all the attribute payloads of `records`.
+    fn redact(
+        &self,
+        records: &mut OtapArrowRecords,
+        signal: SignalType,
+    ) -> Result<RedactionStats, ArrowError> {
+        let payloads = match signal {
+            SignalType::Logs => LOGS_ATTRS,
+            SignalType::Metrics => METRICS_ATTRS,
+            SignalType::Traces => TRACES_ATTRS,
+        };
+        let mut stats = RedactionStats::default();
+        for &payload_type in payloads {
+            if let Some(batch) = records.get(payload_type) {
+                let (batch, batch_stats) = self.redact_batch(batch)?;
+                stats.dropped += batch_stats.dropped;
+                stats.hashed += batch_stats.hashed;
+                stats.redacted += batch_stats.redacted;
+                records.set(payload_type, batch);
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Redact an attribute record batch.
+    fn redact_batch(
+        &self,
+        batch: &RecordBatch,
+    ) -> Result<(RecordBatch, RedactionStats), ArrowError> {
+        let batch = unpack_dictionaries(batch)?;
+        let keys = batch
+            .column_by_name(consts::ATTRIBUTE_KEY)
+            .ok_or_else(|| ArrowError::SchemaError("missing attribute key column".into()))?
+            .as_string::<i32>();
+        let types = batch
+            .column_by_name(consts::ATTRIBUTE_TYPE)
+            .ok_or_else(|| ArrowError::SchemaError("missing attribute type column".into()))?
+            .as_primitive::<UInt8Type>();
+        let strings = batch
+            .column_by_name(consts::ATTRIBUTE_STR)
+            .map(|column| column.as_string::<i32>());
+
+        // classify the rows before any change, so that row indices stay valid
+        let mut not_allowed = Vec::with_capacity(batch.num_rows());
+        let mut blocked = Vec::with_capacity(batch.num_rows());
+        for row in 0..batch.num_rows() {
+            let key = keys.is_valid(row).then(|| keys.value(row));
+            if key.is_some_and(|key| self.ignored_keys.contains(key)) {
+                not_allowed.push(false);
+                blocked.push(false);
+                continue;
+            }
+            let allowed =
+                self.allow_all_keys || key.is_some_and(|key| self.allowed_keys.contains(key));
+            not_allowed.push(!allowed);
+            let is_str = types.is_valid(row) && types.value(row) == AttributeValueType::Str as u8;
+            let value = strings
+                .filter(|strings| is_str && strings.is_valid(row))
+                .map(|strings| strings.value(row));
+            blocked.push(allowed && value.is_some_and(|value| self.blocked_set.is_match(value)));
+        }
+        let not_allowed = BooleanArray::from(not_allowed);
+        let blocked = BooleanArray::from(blocked);
+
+        let mut stats = RedactionStats::default();
+        let (batch, redacted) = map_strings(&batch, &blocked, |value| self.redact_value(value))?;
+        stats.redacted = redacted;
+        let batch = if self.hash_values {
+            let (batch, hashed) = hash(&batch, &not_allowed)?;
+            stats.hashed = hashed;
+            batch
+        } else {
+            stats.dropped = not_allowed.true_count() as u64;
+            if stats.dropped > 0 {
+                filter_record_batch(&batch, &not(&not_allowed)?)?
+            } else {
+                batch
+            }
+        };
+        Ok((batch, stats))
+    }
+
+    /// Replace the blocked substrings of `value`.
+    fn redact_value(&self, value: &str) -> String {
+        let mut value = value.to_string();
+        for pattern in &self.blocked_patterns {
+            value = if self.hash_values {
+                pattern
+                    .replace_all(&value, |caps: &regex::Captures<'_>| {
+                        hex(&Sha256::digest(caps[0].as_bytes()))
+                    })
+                    .into_owned()
+            } else {
+                pattern.replace_all(&value, MASK).into_owned()
+            };
+        }
+        value
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for RedactionProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+
+                let signal = pdata.signal_type();
+                let (context, payload) = pdata.into_parts();
+                let mut records: OtapArrowRecords = payload.try_into()?;
+
+                match self.redact(&mut records, signal) {
+                    Ok(stats) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.attributes_dropped.add(stats.dropped);
+                            m.attributes_hashed.add(stats.hashed);
+                            m.values_redacted.add(stats.redacted);
+                        }
+                    }
+                    Err(e) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.redaction_failed.inc();
+                        }
+                        return Err(pdata_error(&format!("redaction failed: {e}")));
+                    }
+                }
+
+                effect_handler
+                    .send_message(OtapPdata::new(context, records.into()))
+                    .await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_forwarded.inc();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Factory function to create a RedactionProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_redaction_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = RedactionProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<RedactionProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register RedactionProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static REDACTION_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: REDACTION_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_redaction_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the redaction processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static REDACTION_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(REDACTION_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::{node::test_node, processor::TestRuntime};
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::logs::v1::ExportLogsServiceRequest,
+        common::v1::{AnyValue, InstrumentationScope, KeyValue, any_value},
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+        resource::v1::Resource,
+    };
+    use prost::Message as _;
+    use serde_json::json;
+
+    fn logs_request() -> OtapPdata {
+        let req = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource {
+                    attributes: vec![
+                        KeyValue::new("service.name", AnyValue::new_string("checkout")),
+                        KeyValue::new("host.ip", AnyValue::new_string("10.0.0.7")),
+                    ],
+                    ..Default::default()
+                }),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(InstrumentationScope::default()),
+                    log_records: vec![LogRecord {
+                        time_unix_nano: 1,
+                        body: Some(AnyValue::new_string("payment")),
+                        attributes: vec![
+                            KeyValue::new(
+                                "message",
+                                AnyValue::new_string(
+                                    "charged 4111111111111111 for bob@example.com",
+                                ),
+                            ),
+                            KeyValue::new("http.status_code", AnyValue::new_int(200)),
+                            KeyValue::new("user.id", AnyValue::new_string("u-42")),
+                        ],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(bytes).into())
+    }
+
+    /// Sorted `key=value` of the resource and log attributes of the forwarded logs.
+    fn attributes(out: Vec<OtapPdata>) -> (Vec<String>, Vec<String>) {
+        let format = |attrs: Vec<KeyValue>| {
+            let mut attrs: Vec<String> = attrs
+                .into_iter()
+                .map(|kv| match kv.value.and_then(|v| v.value) {
+                    Some(any_value::Value::StringValue(s)) => format!("{}={s}", kv.key),
+                    Some(any_value::Value::IntValue(i)) => format!("{}={i}", kv.key),
+                    other => panic!("unexpected value {other:?}"),
+                })
+                .collect();
+            attrs.sort();
+            attrs
+        };
+        assert_eq!(out.len(), 1);
+        let pdata = out.into_iter().next().expect("one message");
+        let OtlpProtoBytes::ExportLogsRequest(bytes) =
+            pdata.payload().try_into().expect("convert to otlp")
+        else {
+            panic!("unexpected otlp variant");
+        };
+        let mut req = ExportLogsServiceRequest::decode(bytes.as_slice()).expect("decode");
+        let mut resource_logs = req.resource_logs.remove(0);
+        let resource_attrs = resource_logs.resource.take().unwrap_or_default().attributes;
+        let log = resource_logs.scope_logs.remove(0).log_records.remove(0);
+        (format(resource_attrs), format(log.attributes))
+    }
+
+    fn run(config: Value, check: impl FnOnce((Vec<String>, Vec<String>)) + 'static) {
+        let pipeline_ctx = ControllerContext::new(MetricsRegistryHandle::new())
+            .pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let mut node_config = NodeUserConfig::new_processor_config(REDACTION_PROCESSOR_URN);
+        node_config.config = config;
+        let proc = create_redaction_processor(
+            pipeline_ctx,
+            test_node("redaction-processor-test"),
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                ctx.process(Message::PData(logs_request()))
+                    .await
+                    .expect("process");
+                check(attributes(ctx.drain_pdata().await));
+            })
+            .validate(|_| async move {});
+    }
+
+    #[test]
+    fn test_drops_keys_not_allowed_and_masks_blocked_values() {
+        run(
+            json!({
+                "allowed_keys": ["message", "http.status_code"],
+                "ignored_keys": ["service.name"],
+                "blocked_values": ["4[0-9]{15}", "[a-z]+@[a-z.]+"],
+            }),
+            |(resource, log)| {
+                assert_eq!(resource, vec!["service.name=checkout"]);
+                assert_eq!(
+                    log,
+                    vec!["http.status_code=200", "message=charged **** for ****"]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_hash_instead_of_drop() {
+        run(
+            json!({
+                "allow_all_keys": false,
+                "allowed_keys": ["message", "http.status_code", "service.name"],
+                "blocked_values": ["4[0-9]{15}"],
+                "hash_values": true,
+            }),
+            |(resource, log)| {
+                assert_eq!(
+                    resource,
+                    vec![
+                        format!("host.ip={}", hex(&Sha256::digest("10.0.0.7"))),
+                        "service.name=checkout".to_string(),
+                    ]
+                );
+                assert_eq!(
+                    log,
+                    vec![
+                        "http.status_code=200".to_string(),
+                        format!(
+                            "message=charged {} for bob@example.com",
+                            hex(&Sha256::digest("4111111111111111"))
+                        ),
+                        format!("user.id={}", hex(&Sha256::digest("u-42"))),
+                    ]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_allow_all_keys_only_masks() {
+        run(
+            json!({ "allow_all_keys": true, "blocked_values": ["10\\.[0-9.]+"] }),
+            |(resource, log)| {
+                assert_eq!(resource, vec!["host.ip=****", "service.name=checkout"]);
+                assert_eq!(log.len(), 3);
+            },
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({ "blocked_values": ["(unclosed"] }),
+            json!({ "allowed_keys": "message" }),
+            json!({ "drop_keys": ["message"] }),
+        ] {
+            let err = RedactionProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
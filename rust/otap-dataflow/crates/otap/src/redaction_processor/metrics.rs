@@ -0,0 +1,36 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the RedactionProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the RedactionProcessor node.
+#[metric_set(name = "redaction.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct RedactionProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// Number of messages that could not be redacted.
+    #[metric(unit = "{msg}")]
+    pub redaction_failed: Counter<u64>,
+
+    /// Attributes removed because their key is not allowed.
+    #[metric(unit = "{attr}")]
+    pub attributes_dropped: Counter<u64>,
+
+    /// Attributes whose value was hashed because their key is not allowed.
+    #[metric(unit = "{attr}")]
+    pub attributes_hashed: Counter<u64>,
+
+    /// String values in which a blocked pattern was masked or hashed.
+    #[metric(unit = "{attr}")]
+    pub values_redacted: Counter<u64>,
+}
@@ -0,0 +1,603 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Receiver replaying OTAP data previously captured to files.
+//!
+//! The receiver reads files of length-prefixed OTAP `BatchArrowRecords`, each holding
+//! self-contained Arrow IPC streams, as written by the file exporter with the `otap` format.
+//! `path` is either one file or a directory whose files are replayed in name order.
+//!
+//! Batches are sent as fast as the pipeline accepts them, or paced by their original timestamps:
+//! every batch is sent after the interval separating its timestamp (the earliest log time, span
+//! start time or data point time) from the one of the first batch, divided by `speed`.
+//!
+//! Parquet files written by the parquet exporter cannot be replayed: their tables are
+//! denormalized and their IDs rewritten per partition, so the original batches cannot be rebuilt
+//! from them.
+
+use crate::OTAP_RECEIVER_FACTORIES;
+use crate::pdata::OtapPdata;
+use arrow::array::{AsArray, RecordBatch};
+use arrow::datatypes::TimestampNanosecondType;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::ReceiverFactory;
+use otap_df_engine::config::ReceiverConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::NodeControlMsg;
+use otap_df_engine::error::{Error, ReceiverErrorKind, format_error_sources};
+use otap_df_engine::local::receiver as local;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::receiver::ReceiverWrapper;
+use otap_df_engine::terminal_state::TerminalState;
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry::metrics::MetricSet;
+use otap_df_telemetry_macros::metric_set;
+use otel_arrow_rust::otap::{OtapArrowRecords, from_record_messages};
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::{ArrowPayloadType, BatchArrowRecords};
+use otel_arrow_rust::schema::consts;
+use prost::Message as _;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, BufReader};
+use tokio::time::{Duration, Instant, sleep_until};
+
+/// The URN for the replay receiver
+pub const REPLAY_RECEIVER_URN: &str = "urn:otel:otap:replay:receiver";
+
+/// How the replayed batches are paced.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Pacing {
+    /// Send every batch as soon as the pipeline accepts it.
+    #[default]
+    AsFastAsPossible,
+    /// Reproduce the intervals between the timestamps of the batches.
+    OriginalTimestamps,
+}
+
+/// Configuration for the replay receiver
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// File, or directory of files, to replay.
+    pub path: PathBuf,
+
+    /// How the batches are paced.
+    #[serde(default)]
+    pub pacing: Pacing,
+
+    /// Speed-up factor of the `original_timestamps` pacing, e.g. `2.0` replays twice as fast.
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+
+    /// Replay the files again once all of them have been replayed.
+    #[serde(default)]
+    pub repeat: bool,
+
+    /// Largest record accepted, in bytes. A file announcing a larger record is rejected.
+    #[serde(default = "default_max_record_bytes")]
+    pub max_record_bytes: u32,
+}
+
+const fn default_speed() -> f64 {
+    1.0
+}
+
+const fn default_max_record_bytes() -> u32 {
+    64 * 1024 * 1024
+}
+
+/// Replay receiver metrics.
+#[metric_set(name = "replay.receiver.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct ReplayReceiverMetrics {
+    /// Number of batches sent down the pipeline.
+    #[metric(unit = "{batch}")]
+    pub batches_replayed: Counter<u64>,
+
+    /// Number of records skipped because they could not be decoded.
+    #[metric(unit = "{batch}")]
+    pub batches_invalid: Counter<u64>,
+}
+
+/// A Receiver that replays OTAP batches read from files.
+pub struct ReplayReceiver {
+    config: Config,
+    metrics: MetricSet<ReplayReceiverMetrics>,
+}
+
+/// Declares the replay receiver as a local receiver factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_RECEIVER_FACTORIES)]
+pub static REPLAY_RECEIVER: ReceiverFactory<OtapPdata> = ReceiverFactory {
+    name: REPLAY_RECEIVER_URN,
+    create: |pipeline: PipelineContext,
+             node: NodeId,
+             node_config: Arc<NodeUserConfig>,
+             receiver_config: &ReceiverConfig| {
+        Ok(ReceiverWrapper::local(
+            ReplayReceiver::from_config(pipeline, &node_config.config)?,
+            node,
+            node_config,
+            receiver_config,
+        ))
+    },
+};
+
+/// Schema of the configuration of the replay receiver
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static REPLAY_RECEIVER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(REPLAY_RECEIVER_URN);
+
+impl ReplayReceiver {
+    /// Creates a new replay receiver from a configuration object
+    pub fn from_config(
+        pipeline_ctx: PipelineContext,
+        config: &Value,
+    ) -> Result<Self, otap_df_config::error::Error> {
+        let config: Config = parse_user_config(config).map_err(|e| {
+            otap_df_config::error::Error::InvalidUserConfig {
+                error: e.to_string(),
+            }
+        })?;
+        if !(config.speed > 0.0 && config.speed.is_finite()) {
+            return Err(otap_df_config::error::Error::InvalidUserConfig {
+                error: format!("speed must be a positive number, got {}", config.speed),
+            });
+        }
+        let metrics = pipeline_ctx.register_metrics::<ReplayReceiverMetrics>();
+        Ok(Self { config, metrics })
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Receiver<OtapPdata> for ReplayReceiver {
+    async fn start(
+        mut self: Box<Self>,
+        mut ctrl_msg_recv: local::ControlChannel<OtapPdata>,
+        effect_handler: local::EffectHandler<OtapPdata>,
+    ) -> Result<TerminalState, Error> {
+        let path = self.config.path.clone();
+        let receiver_error = |kind: ReceiverErrorKind, e: io::Error| {
+            let source_detail = format_error_sources(&e);
+            Error::ReceiverError {
+                receiver: effect_handler.receiver_id(),
+                kind,
+                error: format!("replay of {} failed: {e}", path.display()),
+                source_detail,
+            }
+        };
+
+        let mut reader = RecordReader::open(&self.config.path, self.config.max_record_bytes)
+            .await
+            .map_err(|e| receiver_error(ReceiverErrorKind::Configuration, e))?;
+        let mut pacer = Pacer::new(self.config.pacing, self.config.speed);
+
+        let telemetry_cancel_handle = effect_handler
+            .start_periodic_telemetry(Duration::from_secs(1))
+            .await?;
+
+        // the next batch to send and when to send it
+        let mut pending: Option<(OtapArrowRecords, Instant)> = None;
+        let mut replayed_in_pass = false;
+        let mut done = false;
+
+        loop {
+            if pending.is_none() && !done {
+                match reader
+                    .next_record()
+                    .await
+                    .map_err(|e| receiver_error(ReceiverErrorKind::Transport, e))?
+                {
+                    Some(record) => match decode_batch(&record) {
+                        Ok(records) => {
+                            let deadline = pacer.deadline(batch_timestamp(&records));
+                            pending = Some((records, deadline));
+                            replayed_in_pass = true;
+                        }
+                        Err(e) => {
+                            log::warn!("Skipping invalid replay record: {e}");
+                            self.metrics.batches_invalid.inc();
+                            continue;
+                        }
+                    },
+                    None if self.config.repeat && replayed_in_pass => {
+                        reader.rewind();
+                        pacer.reset();
+                        replayed_in_pass = false;
+                        continue;
+                    }
+                    None => done = true,
+                }
+            }
+
+            let deadline = pending.as_ref().map(|(_, deadline)| *deadline);
+            tokio::select! {
+                biased;
+
+                ctrl_msg = ctrl_msg_recv.recv() => match ctrl_msg {
+                    Ok(NodeControlMsg::Shutdown { deadline, .. }) => {
+                        let snapshot = self.metrics.snapshot();
+                        _ = telemetry_cancel_handle.cancel().await;
+                        return Ok(TerminalState::new(deadline, [snapshot]));
+                    }
+                    Ok(NodeControlMsg::CollectTelemetry { mut metrics_reporter }) => {
+                        _ = metrics_reporter.report(&mut self.metrics);
+                    }
+                    Err(e) => return Err(Error::ChannelRecvError(e)),
+                    _ => {
+                        // unknown control message do nothing
+                    }
+                },
+
+                _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    if let Some((records, _)) = pending.take() {
+                        effect_handler
+                            .send_message(OtapPdata::new_todo_context(records.into()))
+                            .await?;
+                        self.metrics.batches_replayed.inc();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reads the length-prefixed records of the replayed files in order.
+struct RecordReader {
+    files: Vec<PathBuf>,
+    next_file: usize,
+    max_record_bytes: u32,
+    /// The file being read and the number of bytes left in it.
+    current: Option<(BufReader<File>, u64)>,
+}
+
+impl RecordReader {
+    async fn open(path: &Path, max_record_bytes: u32) -> io::Result<Self> {
+        let files = if tokio::fs::metadata(path).await?.is_dir() {
+            let mut files = Vec::new();
+            let mut entries = tokio::fs::read_dir(path).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_file() {
+                    files.push(entry.path());
+                }
+            }
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+        Ok(Self {
+            files,
+            next_file: 0,
+            max_record_bytes,
+            current: None,
+        })
+    }
+
+    /// Starts reading the files from the first one again.
+    fn rewind(&mut self) {
+        self.next_file = 0;
+        self.current = None;
+    }
+
+    /// Returns the next record, or `None` once all the files have been read.
+    async fn next_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if let Some((file, remaining)) = self.current.as_mut() {
+                match read_record(file, remaining, self.max_record_bytes).await {
+                    Ok(Some(record)) => return Ok(Some(record)),
+                    Ok(None) => self.current = None,
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                        // e.g. the file was still being written when it was copied
+                        log::warn!("Skipping truncated record at the end of a replayed file");
+                        self.current = None;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let Some(path) = self.files.get(self.next_file) else {
+                return Ok(None);
+            };
+            self.next_file += 1;
+            let file = File::open(path).await?;
+            let len = file.metadata().await?.len();
+            self.current = Some((BufReader::new(file), len));
+        }
+    }
+}
+
+/// Reads one record, returning `None` at the end of the file. `remaining` is the number of bytes
+/// left in the file, updated as they are read.
+///
+/// The length prefix is checked before the record is allocated: a record larger than
+/// `max_record_bytes` is an `InvalidData` error, and one larger than the rest of the file an
+/// `UnexpectedEof` error.
+async fn read_record<R: AsyncRead + Unpin>(
+    file: &mut R,
+    remaining: &mut u64,
+    max_record_bytes: u32,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    let read = file.read(&mut len).await?;
+    if read == 0 {
+        return Ok(None);
+    }
+    _ = file.read_exact(&mut len[read..]).await?;
+    *remaining = remaining.saturating_sub(len.len() as u64);
+
+    let len = u32::from_be_bytes(len);
+    if len > max_record_bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("record of {len} bytes exceeds max_record_bytes ({max_record_bytes})"),
+        ));
+    }
+    if u64::from(len) > *remaining {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("record of {len} bytes but only {remaining} bytes left in the file"),
+        ));
+    }
+    let mut record = vec![0u8; len as usize];
+    _ = file.read_exact(&mut record).await?;
+    *remaining -= u64::from(len);
+    Ok(Some(record))
+}
+
+/// Decodes a record holding a self-contained OTAP batch.
+fn decode_batch(record: &[u8]) -> Result<OtapArrowRecords, String> {
+    let mut batch = BatchArrowRecords::decode(record).map_err(|e| e.to_string())?;
+    let root = batch
+        .arrow_payloads
+        .iter()
+        .find_map(|p| match ArrowPayloadType::try_from(p.r#type) {
+            Ok(
+                t @ (ArrowPayloadType::Logs
+                | ArrowPayloadType::Spans
+                | ArrowPayloadType::UnivariateMetrics
+                | ArrowPayloadType::MultivariateMetrics),
+            ) => Some(t),
+            _ => None,
+        })
+        .ok_or("OTAP batch has no root payload")?;
+    let records = otel_arrow_rust::Consumer::default()
+        .consume_bar(&mut batch)
+        .map_err(|e| e.to_string())?;
+    Ok(match root {
+        ArrowPayloadType::Logs => OtapArrowRecords::Logs(from_record_messages(records)),
+        ArrowPayloadType::Spans => OtapArrowRecords::Traces(from_record_messages(records)),
+        _ => OtapArrowRecords::Metrics(from_record_messages(records)),
+    })
+}
+
+/// Returns the earliest timestamp of a batch, in nanoseconds since the unix epoch.
+fn batch_timestamp(records: &OtapArrowRecords) -> Option<i64> {
+    let (payload_types, column): (&[ArrowPayloadType], &str) = match records {
+        OtapArrowRecords::Logs(_) => (&[ArrowPayloadType::Logs], consts::TIME_UNIX_NANO),
+        OtapArrowRecords::Traces(_) => (&[ArrowPayloadType::Spans], consts::START_TIME_UNIX_NANO),
+        OtapArrowRecords::Metrics(_) => (
+            &[
+                ArrowPayloadType::NumberDataPoints,
+                ArrowPayloadType::HistogramDataPoints,
+                ArrowPayloadType::ExpHistogramDataPoints,
+                ArrowPayloadType::SummaryDataPoints,
+            ],
+            consts::TIME_UNIX_NANO,
+        ),
+    };
+    payload_types
+        .iter()
+        .filter_map(|payload_type| min_timestamp(records.get(*payload_type)?, column))
+        .min()
+}
+
+fn min_timestamp(batch: &RecordBatch, column: &str) -> Option<i64> {
+    let timestamps = batch
+        .column_by_name(column)?
+        .as_primitive_opt::<TimestampNanosecondType>()?;
+    arrow::compute::min(timestamps)
+}
+
+/// Computes when the replayed batches are sent.
+struct Pacer {
+    pacing: Pacing,
+    speed: f64,
+    /// The timestamp of the first paced batch and when it was sent.
+    origin: Option<(i64, Instant)>,
+}
+
+impl Pacer {
+    fn new(pacing: Pacing, speed: f64) -> Self {
+        Self {
+            pacing,
+            speed,
+            origin: None,
+        }
+    }
+
+    /// Restarts the pacing, e.g. when the files are replayed again.
+    fn reset(&mut self) {
+        self.origin = None;
+    }
+
+    /// Returns when to send a batch with the given timestamp. Batches without timestamp, or
+    /// older than the first one, are sent immediately.
+    fn deadline(&mut self, timestamp: Option<i64>) -> Instant {
+        let now = Instant::now();
+        let (Pacing::OriginalTimestamps, Some(timestamp)) = (self.pacing, timestamp) else {
+            return now;
+        };
+        let (first, start) = *self.origin.get_or_insert((timestamp, now));
+        let offset = timestamp.saturating_sub(first).max(0) as f64 / self.speed;
+        start + Duration::from_nanos(offset as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{SimpleDataGenOptions, create_simple_logs_arrow_record_batches};
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::receiver::TestRuntime;
+    use otap_df_engine::testing::test_node;
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use serde_json::json;
+    use std::io::Write;
+
+    fn write_records(path: &Path, count: usize) {
+        let mut file = std::fs::File::create(path).unwrap();
+        for _ in 0..count {
+            let batch = create_simple_logs_arrow_record_batches(SimpleDataGenOptions {
+                num_rows: 3,
+                ..Default::default()
+            })
+            .encode_to_vec();
+            file.write_all(&(batch.len() as u32).to_be_bytes()).unwrap();
+            file.write_all(&batch).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_config() {
+        let controller_ctx = ControllerContext::new(MetricsRegistryHandle::new());
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+
+        let receiver = ReplayReceiver::from_config(
+            pipeline_ctx.clone(),
+            &json!({ "path": "/tmp/capture", "pacing": "original_timestamps", "speed": 10 }),
+        )
+        .unwrap();
+        assert_eq!(receiver.config.pacing, Pacing::OriginalTimestamps);
+        assert_eq!(receiver.config.speed, 10.0);
+        assert_eq!(receiver.config.max_record_bytes, default_max_record_bytes());
+
+        assert!(
+            ReplayReceiver::from_config(pipeline_ctx, &json!({ "path": "/tmp", "speed": 0 }))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_pacer() {
+        let mut pacer = Pacer::new(Pacing::OriginalTimestamps, 2.0);
+        let start = pacer.deadline(Some(1_000_000_000));
+        let later = pacer.deadline(Some(3_000_000_000));
+        assert_eq!(later - start, Duration::from_secs(1));
+        // older batches are not delayed
+        assert!(pacer.deadline(Some(0)) <= Instant::now());
+
+        let mut pacer = Pacer::new(Pacing::AsFastAsPossible, 1.0);
+        _ = pacer.deadline(Some(0));
+        assert!(pacer.deadline(Some(i64::MAX)) <= Instant::now());
+    }
+
+    #[test]
+    fn test_decode_batch() {
+        let batch = create_simple_logs_arrow_record_batches(SimpleDataGenOptions {
+            num_rows: 3,
+            ..Default::default()
+        });
+        let records = decode_batch(&batch.encode_to_vec()).unwrap();
+        assert!(matches!(records, OtapArrowRecords::Logs(_)));
+        assert!(batch_timestamp(&records).is_some());
+
+        assert!(decode_batch(b"\xff").is_err());
+        assert!(decode_batch(&BatchArrowRecords::default().encode_to_vec()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_record_length_prefix() {
+        let mut data = 3u32.to_be_bytes().to_vec();
+        data.extend_from_slice(b"abc");
+        let mut remaining = data.len() as u64;
+        let mut file = &data[..];
+        assert_eq!(
+            read_record(&mut file, &mut remaining, 16).await.unwrap(),
+            Some(b"abc".to_vec())
+        );
+        assert_eq!(remaining, 0);
+        assert_eq!(
+            read_record(&mut file, &mut remaining, 16).await.unwrap(),
+            None
+        );
+
+        // a length prefix larger than the configured maximum
+        let data = u32::MAX.to_be_bytes();
+        let mut remaining = u64::MAX;
+        let err = read_record(&mut &data[..], &mut remaining, 16)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // a length prefix larger than the rest of the file
+        let mut data = 10u32.to_be_bytes().to_vec();
+        data.extend_from_slice(b"abc");
+        let mut remaining = data.len() as u64;
+        let err = read_record(&mut &data[..], &mut remaining, 16)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // a truncated length prefix
+        let mut remaining = 2;
+        let err = read_record(&mut &[0u8, 1][..], &mut remaining, 16)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_replay_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        write_records(&dir.path().join("a.otap"), 2);
+        write_records(&dir.path().join("b.otap"), 1);
+        // truncated record at the end of the last file
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("b.otap"))
+            .unwrap();
+        file.write_all(&100u32.to_be_bytes()).unwrap();
+
+        let test_runtime = TestRuntime::new();
+        let node_config = Arc::new(NodeUserConfig::new_receiver_config(REPLAY_RECEIVER_URN));
+        let controller_ctx = ControllerContext::new(MetricsRegistryHandle::new());
+        let pipeline_ctx =
+            controller_ctx.pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        let receiver = ReceiverWrapper::local(
+            ReplayReceiver::from_config(pipeline_ctx, &json!({ "path": dir.path() })).unwrap(),
+            test_node(test_runtime.config().name.clone()),
+            node_config,
+            test_runtime.config(),
+        );
+
+        test_runtime
+            .set_receiver(receiver)
+            .run_test(|ctx| async move {
+                ctx.sleep(Duration::from_millis(200)).await;
+                ctx.send_shutdown(std::time::Instant::now(), "test")
+                    .await
+                    .unwrap();
+            })
+            .run_validation(|mut ctx| async move {
+                let mut batches = 0;
+                while let Ok(pdata) = ctx.recv().await {
+                    assert_eq!(pdata.num_items(), 3);
+                    batches += 1;
+                }
+                assert_eq!(batches, 3);
+            });
+    }
+}
@@ -0,0 +1,394 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resource detection processor for OTAP pipelines.
+//!
+//! This processor enriches the resources of all signals with attributes describing the local
+//! environment, so that telemetry from lean SDK configurations still carries the host, OS, cloud
+//! and container information exporters rely on (e.g. `host.name` for role instance columns).
+//!
+//! The detectors run once, when the first message is received, and their results are reused for
+//! all messages. When several detectors find the same attribute, the first detector of the list
+//! wins. Detected attributes are only added to the resources that don't have them, unless
+//! `override` is set. A detector that fails (e.g. the Azure detector outside of Azure) is
+//! skipped and counted in the `detectors_failed` metric.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! detectors: [env, azure, system, container]
+//! override: false
+//! timeout: 2s
+//! ```
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::attributes_processor::value_actions::{Literal, ValueAction, ValueSource};
+use crate::pdata::{OtapPdata, pdata_error};
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::NodeControlMsg;
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod detectors;
+mod metrics;
+pub use self::detectors::Detector;
+use self::metrics::ResourceDetectionProcessorMetrics;
+
+/// URN for the ResourceDetectionProcessor
+pub const RESOURCE_DETECTION_PROCESSOR_URN: &str = "urn:otap:processor:resource_detection";
+
+/// Default time allowed to each metadata service query.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Configuration for the ResourceDetectionProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Detectors to run, by decreasing precedence.
+    #[serde(default = "default_detectors")]
+    pub detectors: Vec<Detector>,
+
+    /// Overwrite the resource attributes that are already set with the detected values.
+    #[serde(default, rename = "override")]
+    pub override_existing: bool,
+
+    /// Time allowed to each metadata service query.
+    #[serde(with = "humantime_serde", default = "default_timeout")]
+    #[schemars(with = "String")]
+    pub timeout: Duration,
+}
+
+fn default_detectors() -> Vec<Detector> {
+    vec![Detector::Env, Detector::System]
+}
+
+const fn default_timeout() -> Duration {
+    DEFAULT_TIMEOUT
+}
+
+/// Processor adding detected attributes to the resources of all signals.
+pub struct ResourceDetectionProcessor {
+    config: Config,
+    // Actions setting the detected attributes, once the detectors have run
+    actions: Option<Vec<ValueAction>>,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<ResourceDetectionProcessorMetrics>>,
+}
+
+impl ResourceDetectionProcessor {
+    /// Creates a new ResourceDetectionProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse ResourceDetectionProcessor configuration: {e}"),
+            })?;
+        if config.detectors.is_empty() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "detectors must not be empty".to_string(),
+            });
+        }
+        if config.timeout.is_zero() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "timeout must be greater than 0".to_string(),
+            });
+        }
+        Ok(Self {
+            config,
+            actions: None,
+            metrics_set: None,
+        })
+    }
+
+    /// Run the detectors and build the actions setting the attributes they found.
+    async fn detect(&mut self) -> Vec<ValueAction> {
+        let mut seen = HashSet::new();
+        let mut actions = Vec::new();
+        for detector in &self.config.detectors {
+            match detector.detect(self.config.timeout).await {
+                Ok(attributes) => {
+                    for (key, value) in attributes {
+                        if !seen.insert(key.clone()) {
+                            continue;
+                        }
+                        let source = ValueSource::Literal(Literal::Str(value));
+                        actions.push(if self.config.override_existing {
+                            ValueAction::Upsert { key, source }
+                        } else {
+                            ValueAction::Insert { key, source }
+                        });
+                    }
+                }
+                Err(e) => {
+                    log::warn!("resource detection: {e}");
+                    if let Some(m) = self.metrics_set.as_mut() {
+                        m.detectors_failed.inc();
+                    }
+                }
+            }
+        }
+        actions
+    }
+}
+
+/// Apply the actions to the resource attributes of `records`, returning the number of
+/// attributes set.
+fn enrich(records: &mut OtapArrowRecords, actions: &[ValueAction]) -> Result<u64, String> {
+    if actions.is_empty() {
+        return Ok(0);
+    }
+    // actions match attributes to their resource by id
+    records
+        .decode_transport_optimized_ids()
+        .map_err(|e| format!("failed to decode ids: {e}"))?;
+    let mut count = 0;
+    for action in actions {
+        let stats = action
+            .apply(records, ArrowPayloadType::ResourceAttrs)
+            .map_err(|e| e.to_string())?;
+        count += stats.inserted + stats.updated;
+    }
+    Ok(count)
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for ResourceDetectionProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                if self.actions.is_none() {
+                    // Processors have no start hook, so the detectors run on first data
+                    self.actions = Some(self.detect().await);
+                }
+                let actions = self.actions.as_deref().unwrap_or_default();
+
+                let (context, payload) = pdata.into_parts();
+                let mut records: OtapArrowRecords = payload.try_into()?;
+                match enrich(&mut records, actions) {
+                    Ok(count) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.attributes_set.add(count);
+                        }
+                    }
+                    Err(e) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.enrich_failed.inc();
+                        }
+                        return Err(pdata_error(&format!("resource detection failed: {e}")));
+                    }
+                }
+
+                effect_handler
+                    .send_message(OtapPdata::new(context, records.into()))
+                    .await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_forwarded.inc();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Factory function to create a ResourceDetectionProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_resource_detection_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = ResourceDetectionProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<ResourceDetectionProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register ResourceDetectionProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static RESOURCE_DETECTION_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: RESOURCE_DETECTION_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_resource_detection_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the resource detection processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static RESOURCE_DETECTION_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(RESOURCE_DETECTION_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::{node::test_node, processor::TestRuntime};
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::logs::v1::ExportLogsServiceRequest,
+        common::v1::{AnyValue, InstrumentationScope, KeyValue, any_value},
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+        resource::v1::Resource,
+    };
+    use prost::Message as _;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn logs_request(resource_attrs: Vec<KeyValue>) -> OtapPdata {
+        let req = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource {
+                    attributes: resource_attrs,
+                    ..Default::default()
+                }),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(InstrumentationScope::default()),
+                    log_records: vec![LogRecord {
+                        time_unix_nano: 1,
+                        body: Some(AnyValue::new_string("hello")),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(bytes).into())
+    }
+
+    fn resource_attributes(pdata: OtapPdata) -> BTreeMap<String, String> {
+        let OtlpProtoBytes::ExportLogsRequest(bytes) =
+            pdata.payload().try_into().expect("convert to otlp")
+        else {
+            panic!("unexpected otlp variant");
+        };
+        ExportLogsServiceRequest::decode(bytes.as_slice())
+            .expect("decode")
+            .resource_logs
+            .into_iter()
+            .flat_map(|rl| rl.resource.unwrap_or_default().attributes)
+            .map(|kv| match kv.value.and_then(|v| v.value) {
+                Some(any_value::Value::StringValue(s)) => (kv.key, s),
+                other => panic!("unexpected value {other:?}"),
+            })
+            .collect()
+    }
+
+    fn run(config: Value, input: OtapPdata, check: impl FnOnce(OtapPdata) + 'static) {
+        let pipeline_ctx = ControllerContext::new(MetricsRegistryHandle::new())
+            .pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let mut node_config =
+            NodeUserConfig::new_processor_config(RESOURCE_DETECTION_PROCESSOR_URN);
+        node_config.config = config;
+        let proc = create_resource_detection_processor(
+            pipeline_ctx,
+            test_node("resource-detection-processor-test"),
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                ctx.process(Message::PData(input)).await.expect("process");
+                let mut out = ctx.drain_pdata().await;
+                assert_eq!(out.len(), 1);
+                check(out.remove(0));
+            })
+            .validate(|_| async move {});
+    }
+
+    #[test]
+    fn test_system_attributes_fill_missing_only() {
+        let input = logs_request(vec![
+            KeyValue::new("service.name", AnyValue::new_string("checkout")),
+            KeyValue::new("os.type", AnyValue::new_string("custom")),
+        ]);
+        run(json!({ "detectors": ["system"] }), input, |out| {
+            let attrs = resource_attributes(out);
+            assert_eq!(attrs["service.name"], "checkout");
+            assert_eq!(attrs["os.type"], "custom");
+            assert!(attrs.contains_key("host.arch"));
+        });
+    }
+
+    #[test]
+    fn test_override_existing() {
+        let input = logs_request(vec![KeyValue::new(
+            "os.type",
+            AnyValue::new_string("custom"),
+        )]);
+        run(
+            json!({ "detectors": ["system"], "override": true }),
+            input,
+            |out| {
+                let attrs = resource_attributes(out);
+                assert_ne!(attrs["os.type"], "custom");
+            },
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({ "detectors": [] }),
+            json!({ "detectors": ["gcp"] }),
+            json!({ "timeout": "0s" }),
+        ] {
+            let err = ResourceDetectionProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
@@ -0,0 +1,335 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resource detectors of the resource detection processor.
+//!
+//! Each detector returns the resource attributes it could find about the local environment, as
+//! semantic convention keys and string values. A detector that finds nothing returns an error
+//! explaining why, so that the processor can report it.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Address of the Azure Instance Metadata Service.
+const AZURE_IMDS_ADDR: &str = "169.254.169.254:80";
+/// Path of the compute metadata of the Azure Instance Metadata Service.
+const AZURE_IMDS_PATH: &str = "/metadata/instance/compute?api-version=2021-12-13&format=json";
+
+/// Source of resource attributes, named in the `detectors` list of the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Detector {
+    /// `OTEL_RESOURCE_ATTRIBUTES` and `OTEL_SERVICE_NAME` environment variables.
+    Env,
+    /// Host name, operating system and architecture.
+    System,
+    /// Id of the container the pipeline runs in, read from `/proc/self`.
+    Container,
+    /// Azure virtual machine metadata, read from the Instance Metadata Service.
+    Azure,
+}
+
+/// Detected resource attributes, in detection order.
+pub(super) type Attributes = Vec<(String, String)>;
+
+impl Detector {
+    /// Run the detector. `timeout` bounds the time spent querying metadata services.
+    pub(super) async fn detect(self, timeout: Duration) -> Result<Attributes, String> {
+        let attributes = match self {
+            Detector::Env => env(),
+            Detector::System => system(),
+            Detector::Container => container(),
+            Detector::Azure => {
+                let addr: SocketAddr = AZURE_IMDS_ADDR.parse().map_err(|e| format!("{e}"))?;
+                azure(addr, timeout).await?
+            }
+        };
+        if attributes.is_empty() {
+            return Err(format!("{self:?} detector found no resource attribute"));
+        }
+        Ok(attributes)
+    }
+}
+
+fn env() -> Attributes {
+    let mut attributes = std::env::var("OTEL_RESOURCE_ATTRIBUTES")
+        .map(|value| parse_resource_attributes(&value))
+        .unwrap_or_default();
+    if let Ok(service_name) = std::env::var("OTEL_SERVICE_NAME") {
+        if !service_name.is_empty() {
+            // OTEL_SERVICE_NAME takes precedence over OTEL_RESOURCE_ATTRIBUTES
+            attributes.retain(|(key, _)| key != "service.name");
+            attributes.insert(0, ("service.name".to_string(), service_name));
+        }
+    }
+    attributes
+}
+
+/// Parse the `key1=value1,key2=value2` list of `OTEL_RESOURCE_ATTRIBUTES`, whose keys and values
+/// are percent-encoded. Malformed entries are skipped.
+pub(super) fn parse_resource_attributes(value: &str) -> Attributes {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (key, value) = entry.split_once('=')?;
+            let key = percent_decode(key.trim())?;
+            let value = percent_decode(value.trim())?;
+            (!key.is_empty()).then_some((key, value))
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn system() -> Attributes {
+    let mut attributes = Vec::new();
+    if let Some(host_name) = host_name() {
+        attributes.push(("host.name".to_string(), host_name));
+    }
+    let os_type = match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    };
+    attributes.push(("os.type".to_string(), os_type.to_string()));
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "x86" => "x86",
+        "aarch64" => "arm64",
+        "arm" => "arm32",
+        "powerpc64" => "ppc64",
+        "s390x" => "s390x",
+        arch => arch,
+    };
+    attributes.push(("host.arch".to_string(), arch.to_string()));
+    attributes
+}
+
+fn host_name() -> Option<String> {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .chain(
+            ["/proc/sys/kernel/hostname", "/etc/hostname"]
+                .into_iter()
+                .filter_map(|path| std::fs::read_to_string(path).ok()),
+        )
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+}
+
+fn container() -> Attributes {
+    let id = std::fs::read_to_string("/proc/self/cgroup")
+        .ok()
+        .and_then(|cgroup| container_id_from_cgroup(&cgroup))
+        .or_else(|| {
+            std::fs::read_to_string("/proc/self/mountinfo")
+                .ok()
+                .and_then(|mountinfo| container_id_from_mountinfo(&mountinfo))
+        });
+    id.map(|id| vec![("container.id".to_string(), id)])
+        .unwrap_or_default()
+}
+
+/// Container id found in the cgroup paths of `/proc/self/cgroup` (cgroup v1).
+pub(super) fn container_id_from_cgroup(cgroup: &str) -> Option<String> {
+    cgroup.lines().find_map(|line| {
+        let path = line.rsplit(':').next()?;
+        let last = path.rsplit('/').next()?;
+        // docker-<id>.scope, cri-containerd-<id>.scope, crio-<id>.scope or a bare id
+        let last = last.strip_suffix(".scope").unwrap_or(last);
+        let id = last.rsplit('-').next()?;
+        is_container_id(id).then(|| id.to_string())
+    })
+}
+
+/// Container id found in the mount points of `/proc/self/mountinfo` (cgroup v2), from the
+/// `/containers/<id>/` directory the runtime mounts `/etc/hostname` from.
+pub(super) fn container_id_from_mountinfo(mountinfo: &str) -> Option<String> {
+    mountinfo.lines().find_map(|line| {
+        let root = line.split_whitespace().nth(3)?;
+        let mut parts = root.split('/');
+        let _ = parts.by_ref().find(|part| *part == "containers")?;
+        let id = parts.next()?;
+        is_container_id(id).then(|| id.to_string())
+    })
+}
+
+fn is_container_id(id: &str) -> bool {
+    id.len() == 64 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+async fn azure(addr: SocketAddr, timeout: Duration) -> Result<Attributes, String> {
+    let body = tokio::time::timeout(timeout, http_get(addr, AZURE_IMDS_PATH))
+        .await
+        .map_err(|_| format!("Azure IMDS did not answer within {timeout:?}"))?
+        .map_err(|e| format!("Azure IMDS request failed: {e}"))?;
+    let compute: Value =
+        serde_json::from_str(&body).map_err(|e| format!("invalid Azure IMDS response: {e}"))?;
+    Ok(azure_attributes(&compute))
+}
+
+/// Resource attributes of the compute metadata returned by the Azure Instance Metadata Service.
+pub(super) fn azure_attributes(compute: &Value) -> Attributes {
+    let mut attributes = vec![
+        ("cloud.provider".to_string(), "azure".to_string()),
+        ("cloud.platform".to_string(), "azure_vm".to_string()),
+    ];
+    let fields = [
+        ("location", "cloud.region"),
+        ("subscriptionId", "cloud.account.id"),
+        ("vmId", "host.id"),
+        ("name", "host.name"),
+        ("vmSize", "host.type"),
+        ("resourceGroupName", "azure.resourcegroup.name"),
+        ("vmScaleSetName", "azure.vm.scaleset.name"),
+    ];
+    for (field, key) in fields {
+        if let Some(value) = compute.get(field).and_then(Value::as_str) {
+            if !value.is_empty() {
+                attributes.push((key.to_string(), value.to_string()));
+            }
+        }
+    }
+    if let Some(os_type) = compute.get("osType").and_then(Value::as_str) {
+        attributes.push(("os.type".to_string(), os_type.to_lowercase()));
+    }
+    attributes
+}
+
+/// Minimal HTTP/1.0 GET returning the body of a successful response. HTTP/1.0 keeps the
+/// response free of chunked encoding and the connection closed at its end.
+async fn http_get(addr: SocketAddr, path: &str) -> std::io::Result<String> {
+    let mut stream = TcpStream::connect(addr).await?;
+    let request = format!(
+        "GET {path} HTTP/1.0\r\nHost: {}\r\nMetadata: true\r\n\r\n",
+        addr.ip()
+    );
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response).await?;
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| std::io::Error::other("malformed HTTP response"))?;
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(std::io::Error::other(format!(
+            "unexpected status `{status}`"
+        )));
+    }
+    Ok(body.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    fn attrs(pairs: &[(&str, &str)]) -> Attributes {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_resource_attributes() {
+        assert_eq!(
+            parse_resource_attributes(
+                "service.name=checkout, deployment.environment=prod%2Ceu,bad"
+            ),
+            attrs(&[
+                ("service.name", "checkout"),
+                ("deployment.environment", "prod,eu"),
+            ])
+        );
+        assert_eq!(parse_resource_attributes("k=%zz,=v"), Attributes::new());
+    }
+
+    #[test]
+    fn test_container_id() {
+        let id = "a".repeat(64);
+        let cgroup = format!("12:pids:/kubepods/besteffort/pod1/cri-containerd-{id}.scope\n0::/\n");
+        assert_eq!(container_id_from_cgroup(&cgroup), Some(id.clone()));
+        assert_eq!(container_id_from_cgroup("0::/\n"), None);
+
+        let hostname_mount = format!(
+            "3 4 8:1 /var/lib/docker/containers/{id}/hostname /etc/hostname rw - ext4 /dev/sda1 rw"
+        );
+        let mountinfo = format!("1 2 0:1 / / rw - overlay overlay rw\n{hostname_mount}\n");
+        assert_eq!(container_id_from_mountinfo(&mountinfo), Some(id));
+    }
+
+    #[test]
+    fn test_azure_attributes() {
+        let compute = json!({
+            "location": "westeurope",
+            "subscriptionId": "sub-1",
+            "vmId": "vm-1",
+            "name": "web-0",
+            "vmSize": "Standard_D2s_v3",
+            "resourceGroupName": "rg",
+            "vmScaleSetName": "",
+            "osType": "Linux",
+        });
+        assert_eq!(
+            azure_attributes(&compute),
+            attrs(&[
+                ("cloud.provider", "azure"),
+                ("cloud.platform", "azure_vm"),
+                ("cloud.region", "westeurope"),
+                ("cloud.account.id", "sub-1"),
+                ("host.id", "vm-1"),
+                ("host.name", "web-0"),
+                ("host.type", "Standard_D2s_v3"),
+                ("azure.resourcegroup.name", "rg"),
+                ("os.type", "linux"),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_azure_imds_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("local addr");
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.expect("accept");
+            let mut request = vec![0; 1024];
+            let n = stream.read(&mut request).await.expect("read request");
+            let request = String::from_utf8_lossy(&request[..n]).to_string();
+            let body = r#"{"location":"eastus","vmId":"vm-2"}"#;
+            let response =
+                format!("HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{body}");
+            stream.write_all(response.as_bytes()).await.expect("write");
+            request
+        });
+
+        let attributes = azure(addr, Duration::from_secs(5)).await.expect("detect");
+        assert!(attributes.contains(&("cloud.region".to_string(), "eastus".to_string())));
+        assert!(attributes.contains(&("host.id".to_string(), "vm-2".to_string())));
+        let request = server.await.expect("server");
+        assert!(request.starts_with(&format!("GET {AZURE_IMDS_PATH} HTTP/1.0")));
+        assert!(request.contains("Metadata: true"));
+    }
+}
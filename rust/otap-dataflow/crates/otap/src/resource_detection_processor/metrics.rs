@@ -0,0 +1,32 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the ResourceDetectionProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the ResourceDetectionProcessor node.
+#[metric_set(name = "resource_detection.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct ResourceDetectionProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// Detectors that failed or found nothing.
+    #[metric(unit = "{detector}")]
+    pub detectors_failed: Counter<u64>,
+
+    /// Resource attributes added or overwritten with detected values.
+    #[metric(unit = "{attr}")]
+    pub attributes_set: Counter<u64>,
+
+    /// Number of messages whose resources could not be enriched.
+    #[metric(unit = "{msg}")]
+    pub enrich_failed: Counter<u64>,
+}
@@ -5,12 +5,32 @@
 //! ACK/NACK handling.  Retry state is stored in the Context. Retries are
 //! issued using exponential backoff.
 //!
-//! The processor is configured via [`retry_processor::RetryConfig`] with
-//! parameters for:
+//! The processor is not tied to an exporter: placed in front of any
+//! exporter that NACKs the data it fails to deliver (returning the
+//! payload), it retries that data, so exporters do not need their own
+//! retry logic.
+//!
+//! ```yaml
+//! retry:
+//!   kind: processor
+//!   plugin_urn: "urn:otel:retry:processor"
+//!   out_ports:
+//!     out_port:
+//!       destinations: [exporter]
+//!       dispatch_strategy: round_robin
+//!   config:
+//!     initial_interval: 1
+//!     max_interval: 30
+//!     max_elapsed_time: 300
+//!     multiplier: 1.5
+//!     max_pending_retries: 1000
+//! ```
+//!
+//! The processor is configured via [`RetryConfig`] with parameters for:
 //! - Initial and maximum retry delays
 //! - Maximum elapsed time
 //! - Backoff multiplier
-//! ```
+//! - Maximum number of requests waiting for a retry
 
 // ToDo: Consider adding a jitter mechanism.
 
@@ -19,12 +39,14 @@ use crate::pdata::OtapPdata;
 use async_trait::async_trait;
 use linkme::distributed_slice;
 use otap_df_config::experimental::SignalType;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
 use otap_df_config::{error::Error as ConfigError, node::NodeUserConfig};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::context::PipelineContext;
 use otap_df_engine::{
     ConsumerEffectHandlerExtension, Interests, ProcessorFactory, ProducerEffectHandlerExtension,
     config::ProcessorConfig,
-    control::{AckMsg, CallData, NackMsg, NodeControlMsg},
+    control::{AckMsg, CallData, NackClass, NackMsg, NodeControlMsg},
     error::{Error, TypedError},
     local::processor::{EffectHandler, Processor},
     message::Message,
@@ -34,6 +56,7 @@ use otap_df_engine::{
 use otap_df_telemetry::instrument::Counter;
 use otap_df_telemetry::metrics::MetricSet;
 use otap_df_telemetry_macros::metric_set;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{DurationSecondsWithFrac, formats::Flexible, serde_as};
 use std::sync::Arc;
@@ -52,13 +75,14 @@ pub const RETRY_PROCESSOR_URN: &str = "urn:otel:retry:processor";
 /// Retries will be attempted until max_elapsed_time has passed
 /// from the initial attempt.
 #[serde_as]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct RetryConfig {
     /// Initial retry interval in seconds. This is how long the
     /// first delay will be following the first NACK response.
     /// This interval is multiplied by the multiplier on subsequent
     /// retries, until it exceeds max_interval.
     #[serde_as(as = "DurationSecondsWithFrac<f64, Flexible>")]
+    #[schemars(with = "f64")]
     #[serde(default = "default_initial_interval")]
     pub initial_interval: Duration,
 
@@ -68,6 +92,7 @@ pub struct RetryConfig {
     /// interval times the exponentiated multiplier reaches this
     /// value.
     #[serde_as(as = "DurationSecondsWithFrac<f64, Flexible>")]
+    #[schemars(with = "f64")]
     #[serde(default = "default_max_interval")]
     pub max_interval: Duration,
 
@@ -76,12 +101,19 @@ pub struct RetryConfig {
     /// processor first sees it. Retries will not be scheduled if they
     /// would begin after this many seconds from the start.
     #[serde_as(as = "DurationSecondsWithFrac<f64, Flexible>")]
+    #[schemars(with = "f64")]
     #[serde(default = "default_max_elapsed_time")]
     pub max_elapsed_time: Duration,
 
     /// Multiplier for the retry interval.
     #[serde(default = "default_multiplier")]
     pub multiplier: f64,
+
+    /// Maximum number of requests waiting for a retry. Requests
+    /// NACKed while this many retries are pending are NACKed upstream
+    /// instead of being retried. Unlimited when not set.
+    #[serde(default)]
+    pub max_pending_retries: Option<usize>,
 }
 
 // These defaults are copied from the Collector (exporterhelper) retry sender.
@@ -116,6 +148,7 @@ impl Default for RetryConfig {
             initial_interval: default_initial_interval(),
             max_elapsed_time: default_max_elapsed_time(),
             multiplier: default_multiplier(),
+            max_pending_retries: None,
         }
     }
 }
@@ -195,6 +228,11 @@ impl RetryConfig {
                 error: "max_elapsed_time cannot be zero".into(),
             });
         }
+        if self.max_pending_retries == Some(0) {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "max_pending_retries cannot be zero".into(),
+            });
+        }
         let (retry_limit, delays) = Self::compute_retry_delays(self)?;
 
         Ok((retry_limit, delays))
@@ -266,6 +304,11 @@ pub struct RetryProcessorMetrics {
     /// Number of retry attempts scheduled as a result of NACKs, metrics.
     #[metric(unit = "{event}")]
     pub retry_attempts_metrics: Counter<u64>,
+
+    /// Number of NACKs not retried because max_pending_retries
+    /// retries were already pending.
+    #[metric(unit = "{event}")]
+    pub retries_rejected_pending_limit: Counter<u64>,
 }
 
 impl RetryProcessorMetrics {
@@ -329,6 +372,12 @@ pub static RETRY_PROCESSOR_FACTORY: ProcessorFactory<OtapPdata> = ProcessorFacto
     create: create_retry_processor,
 };
 
+/// Schema of the configuration of the retry processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static RETRY_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<RetryConfig>(RETRY_PROCESSOR_URN);
+
 /// A processor that handles message retries with exponential backoff
 ///
 /// This component only maintains state in the request context.
@@ -344,6 +393,9 @@ pub struct RetryProcessor {
 
     config: RetryConfig,
     metrics: MetricSet<RetryProcessorMetrics>,
+
+    /// Number of requests delayed for a retry and not yet resent.
+    pending_retries: usize,
 }
 
 /// Factory function to create a SignalTypeRouter processor
@@ -353,11 +405,10 @@ pub fn create_retry_processor(
     node_config: Arc<NodeUserConfig>,
     processor_config: &ProcessorConfig,
 ) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
-    let config: RetryConfig = serde_json::from_value(node_config.config.clone()).map_err(|e| {
-        ConfigError::InvalidUserConfig {
+    let config: RetryConfig =
+        parse_user_config(&node_config.config).map_err(|e| ConfigError::InvalidUserConfig {
             error: format!("Failed to parse retry configuration: {e}"),
-        }
-    })?;
+        })?;
 
     let retry = RetryProcessor::with_pipeline_ctx(pipeline_ctx, config)?;
 
@@ -450,9 +501,20 @@ impl RetryProcessor {
             delays,
             config,
             metrics,
+            pending_retries: 0,
         })
     }
 
+    /// Replaces the configuration, keeping the current one when the
+    /// new one is invalid.
+    fn reconfigure(&mut self, config: RetryConfig) {
+        if let Ok((retry_limit, delays)) = config.validate_retries() {
+            self.retry_limit = retry_limit;
+            self.delays = delays;
+            self.config = config;
+        }
+    }
+
     async fn handle_ack(
         &mut self,
         ack: AckMsg<OtapPdata>,
@@ -497,6 +559,14 @@ impl RetryProcessor {
         // refusal.
         self.metrics.add_produced_refused(signal, rstate.num_items);
 
+        // The downstream indicated that retrying cannot succeed.
+        if !nack.is_retryable() {
+            nack.reason = format!("permanent failure: {}", nack.reason);
+            effect_handler.notify_nack(nack).await?;
+            self.metrics.add_consumed_refused(signal, rstate.num_items);
+            return Ok(());
+        }
+
         // Check for missing payload, we won't retry an empty request.
         if nack.refused.is_empty() {
             // The downstream refused the request and did not give us
@@ -525,6 +595,19 @@ impl RetryProcessor {
             return Ok(());
         }
 
+        // Bound the data held for retries.
+        if self
+            .config
+            .max_pending_retries
+            .is_some_and(|max| self.pending_retries >= max)
+        {
+            nack.reason = format!("too many pending retries: {}", nack.reason);
+            effect_handler.notify_nack(nack).await?;
+            self.metrics.retries_rejected_pending_limit.inc();
+            self.metrics.add_consumed_refused(signal, rstate.num_items);
+            return Ok(());
+        }
+
         let now_i = Instant::now();
         let next_retry_time_i = now_i + *delay;
 
@@ -545,10 +628,15 @@ impl RetryProcessor {
 
         // Delay the data, we'll continue in the DelayedData branch next.
         match effect_handler.delay_data(next_retry_time_i, rereq).await {
-            Ok(_) => Ok(()),
+            Ok(_) => {
+                self.pending_retries += 1;
+                Ok(())
+            }
             Err(refused) => {
                 effect_handler
-                    .notify_nack(NackMsg::new("cannot delay", refused))
+                    .notify_nack(
+                        NackMsg::new("cannot delay", refused).with_class(NackClass::Internal),
+                    )
                     .await?;
                 // This component failed.
                 self.metrics.add_consumed_failure(signal, num_items);
@@ -564,6 +652,7 @@ impl RetryProcessor {
         effect_handler: &mut EffectHandler<OtapPdata>,
         num_items: u64,
     ) -> Result<(), Error> {
+        self.pending_retries = self.pending_retries.saturating_sub(1);
         self.send_or_nack(*data, effect_handler, num_items).await
     }
 
@@ -583,7 +672,7 @@ impl RetryProcessor {
                 let reason = sent.to_string();
                 let data = sent.inner();
                 effect_handler
-                    .notify_nack(NackMsg::new(reason, data))
+                    .notify_nack(NackMsg::new(reason, data).with_class(NackClass::Unavailable))
                     .await?;
                 // This component failed.
                 self.metrics.add_consumed_failure(signal, num_items);
@@ -641,7 +730,7 @@ impl Processor<OtapPdata> for RetryProcessor {
                     }),
                 NodeControlMsg::Config { config } => {
                     if let Ok(new_config) = serde_json::from_value::<RetryConfig>(config) {
-                        self.config = new_config;
+                        self.reconfigure(new_config);
                     }
                     Ok(())
                 }
@@ -669,6 +758,7 @@ impl RetryProcessor {
             delays,
             config,
             metrics,
+            pending_retries: 0,
         }
     }
 }
@@ -681,8 +771,9 @@ mod test {
     use otap_df_config::node::NodeUserConfig;
     use otap_df_engine::context::{ControllerContext, PipelineContext};
     use otap_df_engine::control::{
-        AckMsg, NackMsg, NodeControlMsg, PipelineControlMsg, pipeline_ctrl_msg_channel,
+        AckMsg, NackClass, NackMsg, NodeControlMsg, PipelineControlMsg, pipeline_ctrl_msg_channel,
     };
+    use otap_df_engine::in_flight::InFlightData;
     use otap_df_engine::testing::node::test_node;
     use otap_df_engine::testing::processor::TestRuntime;
     use otap_df_engine::{Interests, message::Message};
@@ -713,6 +804,7 @@ mod test {
                 max_interval: Duration::new(1, 750000000),
                 max_elapsed_time: Duration::new(9, 900000000),
                 multiplier: 1.999,
+                max_pending_retries: None,
             }
         );
     }
@@ -744,6 +836,12 @@ mod test {
                 }),
                 "multiplier",
             ),
+            (
+                json!({
+                    "max_pending_retries": 0,
+                }),
+                "pending",
+            ),
             (
                 json!({
                     "initial_interval": 1.0,
@@ -820,6 +918,235 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_retry_processor_permanent_nack() {
+        let pipeline_ctx = create_test_pipeline_context();
+        let node = test_node("retry-processor-permanent-test");
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+
+        let mut node_config = NodeUserConfig::new_processor_config(RETRY_PROCESSOR_URN);
+        node_config.config = create_test_config();
+
+        let proc = crate::retry_processor::create_retry_processor(
+            pipeline_ctx,
+            node,
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                let (pipeline_tx, mut pipeline_rx) = pipeline_ctrl_msg_channel(10);
+                ctx.set_pipeline_ctrl_sender(pipeline_tx);
+
+                let pdata_in = create_test_pdata().test_subscribe_to(
+                    Interests::ACKS | Interests::NACKS | Interests::RETURN_DATA,
+                    TestCallData::default().into(),
+                    4444,
+                );
+                ctx.process(Message::PData(pdata_in))
+                    .await
+                    .expect("process initial message");
+
+                let mut output = ctx.drain_pdata().await;
+                assert_eq!(output.len(), 1);
+
+                let nack = NackMsg::new_permanent("rejected downstream", output.remove(0));
+                let (_, nack_ctx) = Context::next_nack(nack).unwrap();
+                ctx.process(Message::nack_ctrl_msg(nack_ctx)).await.unwrap();
+
+                // No retry is scheduled, the Nack goes straight upstream.
+                match pipeline_rx.recv().await {
+                    Ok(PipelineControlMsg::DeliverNack { node_id, nack }) => {
+                        assert_eq!(node_id, 4444);
+                        assert!(nack.permanent);
+                        assert!(
+                            nack.reason
+                                .contains("permanent failure: rejected downstream")
+                        );
+                        assert_eq!(create_test_pdata().num_items(), nack.refused.num_items());
+                    }
+                    other => panic!("expected DeliverNack but got: {:?}", other),
+                }
+            })
+            .validate(|ctx| async move {
+                ctx.counters().assert(0, 0, 0, 0);
+            });
+    }
+
+    #[test]
+    fn test_retry_processor_classified_nack() {
+        // An exporter configured to retry the data refused as invalid, e.g. the OTLP exporter with
+        // `on_schema_reject: retry`, sends a non-permanent Nack of class InvalidData.
+        let pipeline_ctx = create_test_pipeline_context();
+        let node = test_node("retry-processor-classified-test");
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+
+        let mut node_config = NodeUserConfig::new_processor_config(RETRY_PROCESSOR_URN);
+        node_config.config = create_test_config();
+
+        let proc = crate::retry_processor::create_retry_processor(
+            pipeline_ctx,
+            node,
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                let (pipeline_tx, mut pipeline_rx) = pipeline_ctrl_msg_channel(10);
+                ctx.set_pipeline_ctrl_sender(pipeline_tx);
+
+                let pdata_in = create_test_pdata().test_subscribe_to(
+                    Interests::ACKS | Interests::NACKS | Interests::RETURN_DATA,
+                    TestCallData::default().into(),
+                    4444,
+                );
+                ctx.process(Message::PData(pdata_in))
+                    .await
+                    .expect("process initial message");
+
+                let mut output = ctx.drain_pdata().await;
+                assert_eq!(output.len(), 1);
+
+                let nack = NackMsg::new("schema rejected", output.remove(0))
+                    .with_class(NackClass::InvalidData);
+                let (_, nack_ctx) = Context::next_nack(nack).unwrap();
+                ctx.process(Message::nack_ctrl_msg(nack_ctx)).await.unwrap();
+
+                // The class does not make the Nack permanent, a retry is scheduled.
+                match pipeline_rx.recv().await {
+                    Ok(PipelineControlMsg::DelayData { .. }) => {}
+                    other => panic!("expected DelayData but got: {:?}", other),
+                }
+            })
+            .validate(|ctx| async move {
+                ctx.counters().assert(0, 0, 0, 0);
+            });
+    }
+
+    #[test]
+    fn test_retry_processor_deadline_nack() {
+        // An exporter Nacks at its ack deadline the pdata it did not acknowledge in time.
+        let pipeline_ctx = create_test_pipeline_context();
+        let node = test_node("retry-processor-deadline-test");
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+
+        let mut node_config = NodeUserConfig::new_processor_config(RETRY_PROCESSOR_URN);
+        node_config.config = create_test_config();
+
+        let proc = crate::retry_processor::create_retry_processor(
+            pipeline_ctx,
+            node,
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                let (pipeline_tx, mut pipeline_rx) = pipeline_ctrl_msg_channel(10);
+                ctx.set_pipeline_ctrl_sender(pipeline_tx);
+
+                let pdata_in = create_test_pdata().test_subscribe_to(
+                    Interests::ACKS | Interests::NACKS | Interests::RETURN_DATA,
+                    TestCallData::default().into(),
+                    4444,
+                );
+                ctx.process(Message::PData(pdata_in))
+                    .await
+                    .expect("process initial message");
+
+                let mut output = ctx.drain_pdata().await;
+                assert_eq!(output.len(), 1);
+
+                let data = output
+                    .remove(0)
+                    .deadline_nack_data()
+                    .expect("the retry processor subscribed to Nacks");
+                let nack =
+                    NackMsg::new("no Ack or Nack", data).with_class(NackClass::DeadlineExceeded);
+                let (_, nack_ctx) = Context::next_nack(nack).unwrap();
+                ctx.process(Message::nack_ctrl_msg(nack_ctx)).await.unwrap();
+
+                // The deadline Nack returns the payload, which is retried.
+                match pipeline_rx.recv().await {
+                    Ok(PipelineControlMsg::DelayData { data, .. }) => {
+                        assert_eq!(create_test_pdata().num_items(), data.num_items());
+                    }
+                    other => panic!("expected DelayData but got: {:?}", other),
+                }
+            })
+            .validate(|ctx| async move {
+                ctx.counters().assert(0, 0, 0, 0);
+            });
+    }
+
+    #[test]
+    fn test_retry_processor_pending_limit() {
+        let pipeline_ctx = create_test_pipeline_context();
+        let node = test_node("retry-processor-pending-test");
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+
+        let mut node_config = NodeUserConfig::new_processor_config(RETRY_PROCESSOR_URN);
+        node_config.config = json!({
+            "initial_interval": 0.05,
+            "max_pending_retries": 1,
+        });
+
+        let proc = crate::retry_processor::create_retry_processor(
+            pipeline_ctx,
+            node,
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                let (pipeline_tx, mut pipeline_rx) = pipeline_ctrl_msg_channel(10);
+                ctx.set_pipeline_ctrl_sender(pipeline_tx);
+
+                for _ in 0..2 {
+                    let pdata_in = create_test_pdata().test_subscribe_to(
+                        Interests::ACKS | Interests::NACKS | Interests::RETURN_DATA,
+                        TestCallData::default().into(),
+                        4444,
+                    );
+                    ctx.process(Message::PData(pdata_in))
+                        .await
+                        .expect("process message");
+                }
+                let output = ctx.drain_pdata().await;
+                assert_eq!(output.len(), 2);
+
+                for data in output {
+                    let nack = NackMsg::new("simulated downstream failure", data);
+                    let (_, nack_ctx) = Context::next_nack(nack).unwrap();
+                    ctx.process(Message::nack_ctrl_msg(nack_ctx)).await.unwrap();
+                }
+
+                // The first request waits for its retry, the second one is
+                // refused upstream.
+                match pipeline_rx.recv().await {
+                    Ok(PipelineControlMsg::DelayData { .. }) => {}
+                    other => panic!("expected DelayData but got: {:?}", other),
+                }
+                match pipeline_rx.recv().await {
+                    Ok(PipelineControlMsg::DeliverNack { node_id, nack }) => {
+                        assert_eq!(node_id, 4444);
+                        assert!(nack.reason.contains("too many pending retries"));
+                    }
+                    other => panic!("expected DeliverNack but got: {:?}", other),
+                }
+            })
+            .validate(|ctx| async move {
+                ctx.counters().assert(0, 0, 0, 0);
+            });
+    }
+
     fn test_retry_processor(
         config: serde_json::Value,
         number_of_nacks: usize,
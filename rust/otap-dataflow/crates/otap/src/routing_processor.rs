@@ -0,0 +1,572 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Routing processor for OTAP pipelines.
+//!
+//! This processor directs telemetry to named out ports, based on its signal type and on a
+//! condition over its rows, so that one receiver can feed different exporters, e.g. production
+//! namespaces to one exporter and everything else to a debug exporter.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! routes:
+//!   - port: production
+//!     signals: [logs, traces]
+//!     condition: 'resource.attributes["k8s.namespace.name"] matches "^prod-"'
+//!   - port: audit
+//!     condition: 'scope.name == "audit"'
+//! default_port: debug
+//! ```
+//!
+//! Routes are tried in order and each log record, span or metric goes to the port of the first
+//! route that accepts its signal (all signals when `signals` is not set) and whose condition it
+//! matches (all rows when `condition` is not set). Conditions use the expression language of the
+//! filter processor, e.g. `resource.attributes["key"]`, `scope.name` or `attributes["key"]`.
+//! Rows matching no route go to `default_port`, or are dropped when no default port is set.
+//!
+//! A message whose rows all go to the same port is forwarded as is. Otherwise it is split with
+//! Arrow's filter kernel into one message per port, and acknowledged once the parts are sent.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::filter_processor::eval::{filter_rows, matching_rows, root_payload_type};
+use crate::filter_processor::expr::{self, Expr};
+use crate::pdata::{OtapPayload, OtapPdata, decode_ids, pdata_error};
+use arrow::array::{BooleanArray, RecordBatch};
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::ConsumerEffectHandlerExtension;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, NodeControlMsg};
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+mod metrics;
+use self::metrics::RoutingProcessorMetrics;
+
+/// URN for the RoutingProcessor
+pub const ROUTING_PROCESSOR_URN: &str = "urn:otap:processor:routing";
+
+/// Signal accepted by a route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteSignal {
+    /// Log records.
+    Logs,
+    /// Metrics.
+    Metrics,
+    /// Spans.
+    Traces,
+}
+
+impl RouteSignal {
+    fn accepts(self, signal: SignalType) -> bool {
+        matches!(
+            (self, signal),
+            (RouteSignal::Logs, SignalType::Logs)
+                | (RouteSignal::Metrics, SignalType::Metrics)
+                | (RouteSignal::Traces, SignalType::Traces)
+        )
+    }
+}
+
+/// A route of the configuration.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RouteConfig {
+    /// Out port the matching rows are sent to.
+    pub port: String,
+
+    /// Signals the route applies to, all signals when not set.
+    #[serde(default)]
+    pub signals: Option<Vec<RouteSignal>>,
+
+    /// Filter expression selecting the rows of the route, all rows when not set.
+    #[serde(default)]
+    pub condition: Option<String>,
+}
+
+/// Configuration for the RoutingProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Routes, tried in order.
+    pub routes: Vec<RouteConfig>,
+
+    /// Out port of the rows matching no route. They are dropped when not set.
+    #[serde(default)]
+    pub default_port: Option<String>,
+}
+
+/// A parsed route.
+struct Route {
+    port: String,
+    signals: Option<Vec<RouteSignal>>,
+    condition: Option<Expr>,
+}
+
+impl Route {
+    fn accepts(&self, signal: SignalType) -> bool {
+        self.signals
+            .as_ref()
+            .is_none_or(|signals| signals.iter().any(|s| s.accepts(signal)))
+    }
+}
+
+/// Where a row goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    /// Port of the route with the given index.
+    Route(usize),
+    /// Default port, or nowhere.
+    Default,
+}
+
+/// Processor sending rows to the out port of the first route they match.
+pub struct RoutingProcessor {
+    routes: Vec<Route>,
+    default_port: Option<String>,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<RoutingProcessorMetrics>>,
+}
+
+impl RoutingProcessor {
+    /// Creates a new RoutingProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse RoutingProcessor configuration: {e}"),
+            })?;
+        Self::new(config)
+    }
+
+    fn new(config: Config) -> Result<Self, ConfigError> {
+        if config.routes.is_empty() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "routes must not be empty".to_string(),
+            });
+        }
+        let routes = config
+            .routes
+            .into_iter()
+            .map(|route| {
+                if route.port.is_empty() {
+                    return Err(ConfigError::InvalidUserConfig {
+                        error: "route port must not be empty".to_string(),
+                    });
+                }
+                let condition = route
+                    .condition
+                    .map(|condition| {
+                        expr::parse(&condition).map_err(|e| ConfigError::InvalidUserConfig {
+                            error: format!(
+                                "invalid condition `{condition}` of route `{}`: {e}",
+                                route.port
+                            ),
+                        })
+                    })
+                    .transpose()?;
+                Ok(Route {
+                    port: route.port,
+                    signals: route.signals,
+                    condition,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            routes,
+            default_port: config.default_port,
+            metrics_set: None,
+        })
+    }
+
+    /// Target of each root row of `records`.
+    fn assign(
+        &self,
+        records: &mut OtapArrowRecords,
+        signal: SignalType,
+    ) -> Result<Vec<Target>, EngineError> {
+        let num_rows = records
+            .get(root_payload_type(records))
+            .map_or(0, RecordBatch::num_rows);
+        let mut targets = vec![Target::Default; num_rows];
+        let mut unassigned = num_rows;
+        let mut ids_decoded = false;
+
+        for (index, route) in self.routes.iter().enumerate() {
+            if unassigned == 0 {
+                break;
+            }
+            if !route.accepts(signal) {
+                continue;
+            }
+            let Some(condition) = route.condition.as_ref() else {
+                // the route takes all the rows left
+                for target in targets.iter_mut().filter(|t| **t == Target::Default) {
+                    *target = Target::Route(index);
+                }
+                break;
+            };
+            if !ids_decoded {
+                decode_ids(records)
+                    .map_err(|e| pdata_error(&format!("failed to decode ids: {e}")))?;
+                ids_decoded = true;
+            }
+            let mask = matching_rows(condition, records)
+                .map_err(|e| pdata_error(&format!("route condition evaluation failed: {e}")))?;
+            for (row, target) in targets.iter_mut().enumerate() {
+                if *target == Target::Default && mask.value(row) {
+                    *target = Target::Route(index);
+                    unassigned -= 1;
+                }
+            }
+        }
+        Ok(targets)
+    }
+
+    /// Port of a target, `None` when its rows are dropped.
+    fn port(&self, target: Target) -> Option<&str> {
+        match target {
+            Target::Route(index) => Some(self.routes[index].port.as_str()),
+            Target::Default => self.default_port.as_deref(),
+        }
+    }
+
+    fn count_rows(&mut self, target: Target, rows: usize) {
+        if let Some(m) = self.metrics_set.as_mut() {
+            match (target, self.default_port.is_some()) {
+                (Target::Route(_), _) => m.rows_routed.add(rows as u64),
+                (Target::Default, true) => m.rows_defaulted.add(rows as u64),
+                (Target::Default, false) => m.rows_dropped.add(rows as u64),
+            }
+        }
+    }
+
+    /// Send a message to the port of `target`, or acknowledge it if its rows are dropped.
+    async fn send(
+        &mut self,
+        target: Target,
+        pdata: OtapPdata,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match self.port(target).map(str::to_string) {
+            Some(port) => {
+                effect_handler.send_message_to(port, pdata).await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_routed.inc();
+                }
+                Ok(())
+            }
+            None => {
+                let (context, mut payload) = pdata.into_parts();
+                if !context.may_return_payload() {
+                    let _ = payload.take_payload();
+                }
+                effect_handler
+                    .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+                    .await
+            }
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for RoutingProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                let signal = pdata.signal_type();
+
+                // Fast path: the first route accepting the signal takes all its rows
+                let first = self.routes.iter().position(|route| route.accepts(signal));
+                let whole = match first {
+                    None => Some(Target::Default),
+                    Some(index) if self.routes[index].condition.is_none() => {
+                        Some(Target::Route(index))
+                    }
+                    Some(_) => None,
+                };
+                if let Some(target) = whole {
+                    self.count_rows(target, pdata.num_items());
+                    return self.send(target, pdata, effect_handler).await;
+                }
+
+                let (context, payload) = pdata.into_parts();
+                let mut records: OtapArrowRecords = payload.try_into()?;
+                let targets = match self.assign(&mut records, signal) {
+                    Ok(targets) => targets,
+                    Err(e) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.routing_failed.inc();
+                        }
+                        return Err(e);
+                    }
+                };
+
+                // distinct targets, in route order
+                let mut distinct: Vec<Target> = Vec::new();
+                for target in &targets {
+                    if !distinct.contains(target) {
+                        distinct.push(*target);
+                    }
+                }
+                if distinct.len() <= 1 {
+                    let target = distinct.first().copied().unwrap_or(Target::Default);
+                    self.count_rows(target, targets.len());
+                    let pdata = OtapPdata::new(context, records.into());
+                    return self.send(target, pdata, effect_handler).await;
+                }
+
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_split.inc();
+                }
+                let root_type = root_payload_type(&records);
+                for target in distinct {
+                    let mask: BooleanArray = targets.iter().map(|t| Some(*t == target)).collect();
+                    self.count_rows(target, mask.true_count());
+                    if self.port(target).is_none() {
+                        continue;
+                    }
+                    let mut part = records.clone();
+                    if let Err(e) = filter_rows(&mut part, root_type, &mask) {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.routing_failed.inc();
+                        }
+                        return Err(pdata_error(&format!("failed to split message: {e}")));
+                    }
+                    let pdata = OtapPdata::new_todo_context(part.into());
+                    self.send(target, pdata, effect_handler).await?;
+                }
+
+                // The parts are on their way: the original message is fully processed
+                let mut payload: OtapPayload = records.into();
+                if !context.may_return_payload() {
+                    let _ = payload.take_payload();
+                }
+                effect_handler
+                    .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+                    .await
+            }
+        }
+    }
+}
+
+/// Factory function to create a RoutingProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_routing_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = RoutingProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<RoutingProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register RoutingProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static ROUTING_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: ROUTING_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_routing_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the routing processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static ROUTING_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(ROUTING_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_channel::mpsc;
+    use otap_df_engine::local::message::LocalSender;
+    use otap_df_engine::local::processor::{EffectHandler as LocalEffectHandler, Processor as _};
+    use otap_df_engine::testing::{setup_test_runtime, test_node};
+    use otap_df_telemetry::reporter::MetricsReporter;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::logs::v1::ExportLogsServiceRequest,
+        common::v1::{AnyValue, InstrumentationScope, KeyValue},
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+        resource::v1::Resource,
+    };
+    use prost::Message as _;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn resource_logs(namespace: &str, bodies: &[&str]) -> ResourceLogs {
+        ResourceLogs {
+            resource: Some(Resource {
+                attributes: vec![KeyValue::new(
+                    "k8s.namespace.name",
+                    AnyValue::new_string(namespace),
+                )],
+                ..Default::default()
+            }),
+            scope_logs: vec![ScopeLogs {
+                scope: Some(InstrumentationScope::default()),
+                log_records: bodies
+                    .iter()
+                    .map(|body| LogRecord {
+                        time_unix_nano: 1,
+                        body: Some(AnyValue::new_string(*body)),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn logs_request(resource_logs: Vec<ResourceLogs>) -> OtapPdata {
+        let req = ExportLogsServiceRequest { resource_logs };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(bytes).into())
+    }
+
+    /// Route `input` with `config` through a processor connected to `ports`, and return the
+    /// number of log records received on each port.
+    fn route(config: Value, ports: &[&'static str], input: OtapPdata) -> HashMap<String, usize> {
+        let (rt, local) = setup_test_runtime();
+        rt.block_on(local.run_until(async move {
+            let mut proc = RoutingProcessor::from_config(&config).expect("valid config");
+            let mut senders = HashMap::new();
+            let mut receivers = Vec::new();
+            for port in ports {
+                let (tx, rx) = mpsc::Channel::new(8);
+                let _ = senders.insert((*port).into(), LocalSender::MpscSender(tx));
+                receivers.push((port.to_string(), rx));
+            }
+            let (_, reporter) = MetricsReporter::create_new_and_receiver(1);
+            let mut eh = LocalEffectHandler::new(test_node("routing"), senders, None, reporter);
+
+            proc.process(Message::PData(input), &mut eh)
+                .await
+                .expect("process");
+
+            let mut received = HashMap::new();
+            for (port, rx) in receivers {
+                while let Ok(pdata) = rx.try_recv() {
+                    *received.entry(port.clone()).or_default() += pdata.num_items();
+                }
+            }
+            received
+        }))
+    }
+
+    #[test]
+    fn test_rows_split_by_resource_attribute() {
+        let config = json!({
+            "routes": [{
+                "port": "production",
+                "signals": ["logs"],
+                "condition": "resource.attributes[\"k8s.namespace.name\"] matches \"^prod-\"",
+            }],
+            "default_port": "debug",
+        });
+        let input = logs_request(vec![
+            resource_logs("prod-eu", &["a", "b"]),
+            resource_logs("staging", &["c"]),
+            resource_logs("prod-us", &["d"]),
+        ]);
+        let received = route(config, &["production", "debug"], input);
+        assert_eq!(received.get("production"), Some(&3));
+        assert_eq!(received.get("debug"), Some(&1));
+    }
+
+    #[test]
+    fn test_whole_message_routing() {
+        let config = json!({
+            "routes": [
+                {"port": "traces_only", "signals": ["traces"]},
+                {"port": "production", "condition": "scope.name == \"never\""},
+                {"port": "logs_all", "signals": ["logs"]},
+            ],
+        });
+        let input = logs_request(vec![resource_logs("prod-eu", &["a", "b"])]);
+        let received = route(config, &["traces_only", "production", "logs_all"], input);
+        assert_eq!(received.len(), 1);
+        assert_eq!(received.get("logs_all"), Some(&2));
+    }
+
+    #[test]
+    fn test_unmatched_rows_dropped_without_default_port() {
+        let config = json!({
+            "routes": [{
+                "port": "production",
+                "condition": "resource.attributes[\"k8s.namespace.name\"] == \"prod-eu\"",
+            }],
+        });
+        let input = logs_request(vec![
+            resource_logs("prod-eu", &["a"]),
+            resource_logs("staging", &["b", "c"]),
+        ]);
+        let received = route(config, &["production"], input);
+        assert_eq!(received.get("production"), Some(&1));
+        assert_eq!(received.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({ "routes": [] }),
+            json!({ "routes": [{"port": ""}] }),
+            json!({ "routes": [{"port": "p", "condition": "name =="}] }),
+            json!({ "routes": [{"port": "p", "signals": ["profiles"]}] }),
+        ] {
+            let err = RoutingProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the RoutingProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the RoutingProcessor node.
+#[metric_set(name = "routing.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct RoutingProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages sent to a route port or to the default port.
+    #[metric(unit = "{msg}")]
+    pub msgs_routed: Counter<u64>,
+
+    /// PData messages split across several ports.
+    #[metric(unit = "{msg}")]
+    pub msgs_split: Counter<u64>,
+
+    /// Rows (log records, spans or metrics) sent to a route port.
+    #[metric(unit = "{row}")]
+    pub rows_routed: Counter<u64>,
+
+    /// Rows matching no route, sent to the default port.
+    #[metric(unit = "{row}")]
+    pub rows_defaulted: Counter<u64>,
+
+    /// Rows matching no route and dropped, as no default port is configured.
+    #[metric(unit = "{row}")]
+    pub rows_dropped: Counter<u64>,
+
+    /// Number of messages that could not be routed.
+    #[metric(unit = "{msg}")]
+    pub routing_failed: Counter<u64>,
+}
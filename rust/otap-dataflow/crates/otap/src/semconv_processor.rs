@@ -0,0 +1,565 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Semantic convention normalization processor for OTAP pipelines.
+//!
+//! This processor renames the attributes (resource, scope and signal attributes of all signals)
+//! between the names of older and newer versions of the OpenTelemetry semantic conventions, e.g.
+//! `http.method` to `http.request.method`, so that the attribute names seen by the backends stay
+//! stable while the SDKs of the instrumented services are upgraded.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! direction: upgrade          # `upgrade` (old to new names, default) or `downgrade`
+//! overrides:                  # old name: new name, added to or replacing the built-in table
+//!   app.tenant: tenant.id
+//! exclude: [net.host.name]    # old names of the built-in table to leave untouched
+//! ```
+//!
+//! The built-in table covers the attributes renamed by the stabilization of the HTTP, network,
+//! database, RPC and code conventions (see [`BUILTIN_RENAMES`]). Attribute values are not
+//! converted.
+//!
+//! When a record already has the attribute an attribute is renamed to (e.g. an SDK emitting both
+//! the old and the new names during a migration), the renamed attribute is removed and the one
+//! already present is kept.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::pdata::{OtapPdata, pdata_error};
+use arrow::array::{AsArray, BooleanArray, RecordBatch};
+use arrow::compute::{cast, filter_record_batch};
+use arrow::datatypes::{DataType, UInt32Type};
+use arrow::error::ArrowError;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::NodeControlMsg;
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::otap::transform::{
+    AttributesTransform, RenameTransform, transform_attributes_with_stats,
+};
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::schema::consts;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+mod metrics;
+use self::metrics::SemconvProcessorMetrics;
+
+/// URN for the SemconvProcessor
+pub const SEMCONV_PROCESSOR_URN: &str = "urn:otap:processor:semconv";
+
+/// Attributes renamed between semantic convention versions, as (old name, new name).
+pub const BUILTIN_RENAMES: &[(&str, &str)] = &[
+    // HTTP (stable since v1.23.0)
+    ("http.method", "http.request.method"),
+    ("http.status_code", "http.response.status_code"),
+    ("http.url", "url.full"),
+    ("http.scheme", "url.scheme"),
+    ("http.request_content_length", "http.request.body.size"),
+    ("http.response_content_length", "http.response.body.size"),
+    ("http.user_agent", "user_agent.original"),
+    ("http.client_ip", "client.address"),
+    // Network (v1.21.0)
+    ("net.host.name", "server.address"),
+    ("net.host.port", "server.port"),
+    ("net.sock.peer.addr", "network.peer.address"),
+    ("net.sock.peer.port", "network.peer.port"),
+    ("net.protocol.name", "network.protocol.name"),
+    ("net.protocol.version", "network.protocol.version"),
+    ("net.transport", "network.transport"),
+    // Database (v1.25.0 to v1.28.0)
+    ("db.name", "db.namespace"),
+    ("db.statement", "db.query.text"),
+    ("db.operation", "db.operation.name"),
+    ("db.sql.table", "db.collection.name"),
+    // RPC (v1.26.0)
+    ("message.type", "rpc.message.type"),
+    ("message.id", "rpc.message.id"),
+    ("message.compressed_size", "rpc.message.compressed_size"),
+    ("message.uncompressed_size", "rpc.message.uncompressed_size"),
+    // Code (v1.30.0)
+    ("code.function", "code.function.name"),
+    ("code.filepath", "code.file.path"),
+    ("code.lineno", "code.line.number"),
+    ("code.column", "code.column.number"),
+    // Resources
+    ("deployment.environment", "deployment.environment.name"),
+    ("telemetry.auto.version", "telemetry.distro.version"),
+    ("faas.execution", "faas.invocation_id"),
+];
+
+/// Direction of the renames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Rename the old names to the new names.
+    #[default]
+    Upgrade,
+    /// Rename the new names to the old names.
+    Downgrade,
+}
+
+/// Configuration for the SemconvProcessor.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Direction of the renames.
+    #[serde(default)]
+    pub direction: Direction,
+
+    /// Renames added to the built-in table, as old name: new name. An override replaces the
+    /// built-in rename of the same old name.
+    #[serde(default)]
+    pub overrides: BTreeMap<String, String>,
+
+    /// Old names of the built-in table that must not be renamed.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// Processor renaming attributes between semantic convention versions.
+pub struct SemconvProcessor {
+    // Source name -> target name
+    renames: BTreeMap<String, String>,
+    transform: AttributesTransform,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<SemconvProcessorMetrics>>,
+}
+
+impl SemconvProcessor {
+    /// Creates a new SemconvProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse SemconvProcessor configuration: {e}"),
+            })?;
+        Self::new(config)
+    }
+
+    fn new(config: Config) -> Result<Self, ConfigError> {
+        let mut renames: BTreeMap<String, String> = BUILTIN_RENAMES
+            .iter()
+            .filter(|(old, _)| !config.exclude.iter().any(|excluded| excluded == old))
+            .map(|(old, new)| (old.to_string(), new.to_string()))
+            .collect();
+        for (old, new) in config.overrides {
+            if old.is_empty() || new.is_empty() || old == new {
+                return Err(ConfigError::InvalidUserConfig {
+                    error: format!("invalid override `{old}: {new}`"),
+                });
+            }
+            let _ = renames.insert(old, new);
+        }
+        // a name can't be both renamed and a rename target, nor the target of several names,
+        // which also makes the table invertible
+        AttributesTransform {
+            rename: Some(RenameTransform::new(renames.clone())),
+            delete: None,
+        }
+        .validate()
+        .map_err(|e| ConfigError::InvalidUserConfig {
+            error: format!("invalid renames: {e}"),
+        })?;
+        if config.direction == Direction::Downgrade {
+            renames = renames.into_iter().map(|(old, new)| (new, old)).collect();
+        }
+
+        let transform = AttributesTransform {
+            rename: Some(RenameTransform::new(renames.clone())),
+            delete: None,
+        };
+        Ok(Self {
+            renames,
+            transform,
+            metrics_set: None,
+        })
+    }
+
+    /// Renames the attributes of `records`, returning the number of renamed and removed
+    /// attributes.
+    fn rename(
+        &self,
+        records: &mut OtapArrowRecords,
+        signal: SignalType,
+    ) -> Result<(u64, u64), ArrowError> {
+        // duplicates are detected by parent id
+        records
+            .decode_transport_optimized_ids()
+            .map_err(|e| ArrowError::InvalidArgumentError(e.to_string()))?;
+        let mut renamed = 0;
+        let mut removed = 0;
+        for &payload_type in attrs_payload_types(signal) {
+            let Some(attrs) = records.get(payload_type) else {
+                continue;
+            };
+            let (attrs, duplicates) = remove_duplicates(attrs, &self.renames)?;
+            let (attrs, stats) = transform_attributes_with_stats(&attrs, &self.transform)
+                .map_err(|e| ArrowError::ComputeError(e.to_string()))?;
+            renamed += stats.renamed_entries;
+            removed += duplicates;
+            records.set(payload_type, attrs);
+        }
+        Ok((renamed, removed))
+    }
+}
+
+/// Attribute payload types of a signal.
+const fn attrs_payload_types(signal: SignalType) -> &'static [ArrowPayloadType] {
+    use ArrowPayloadType as A;
+    match signal {
+        SignalType::Logs => &[A::ResourceAttrs, A::ScopeAttrs, A::LogAttrs],
+        SignalType::Traces => &[
+            A::ResourceAttrs,
+            A::ScopeAttrs,
+            A::SpanAttrs,
+            A::SpanEventAttrs,
+            A::SpanLinkAttrs,
+        ],
+        SignalType::Metrics => &[
+            A::ResourceAttrs,
+            A::ScopeAttrs,
+            A::MetricAttrs,
+            A::NumberDpAttrs,
+            A::SummaryDpAttrs,
+            A::HistogramDpAttrs,
+            A::ExpHistogramDpAttrs,
+        ],
+    }
+}
+
+/// Removes the attributes that would duplicate an attribute of the same parent once renamed.
+///
+/// The parent ids of `attrs` must be decoded.
+fn remove_duplicates(
+    attrs: &RecordBatch,
+    renames: &BTreeMap<String, String>,
+) -> Result<(RecordBatch, u64), ArrowError> {
+    let (Some(keys), Some(parents)) = (
+        attrs.column_by_name(consts::ATTRIBUTE_KEY),
+        attrs.column_by_name(consts::PARENT_ID),
+    ) else {
+        return Ok((attrs.clone(), 0));
+    };
+    let keys = cast(keys, &DataType::Utf8)?;
+    let keys = keys.as_string::<i32>();
+    let parents = cast(parents, &DataType::UInt32)?;
+    let parents = parents.as_primitive::<UInt32Type>();
+
+    // rename targets already present, by parent
+    let targets: HashSet<&str> = renames.values().map(String::as_str).collect();
+    let present: HashSet<(u32, &str)> = keys
+        .iter()
+        .zip(parents.iter())
+        .filter_map(|(key, parent)| Some((parent?, key?)))
+        .filter(|(_, key)| targets.contains(key))
+        .collect();
+    if present.is_empty() {
+        return Ok((attrs.clone(), 0));
+    }
+
+    let mask: BooleanArray = keys
+        .iter()
+        .zip(parents.iter())
+        .map(|(key, parent)| {
+            let duplicate = match (key, parent) {
+                (Some(key), Some(parent)) => renames
+                    .get(key)
+                    .is_some_and(|target| present.contains(&(parent, target.as_str()))),
+                _ => false,
+            };
+            Some(!duplicate)
+        })
+        .collect();
+    let removed = mask.false_count() as u64;
+    if removed == 0 {
+        return Ok((attrs.clone(), 0));
+    }
+    Ok((filter_record_batch(attrs, &mask)?, removed))
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for SemconvProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                let signal = pdata.signal_type();
+                let (context, payload) = pdata.into_parts();
+                let mut records: OtapArrowRecords = payload.try_into()?;
+
+                match self.rename(&mut records, signal) {
+                    Ok((renamed, removed)) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.renamed_entries.add(renamed);
+                            m.duplicates_removed.add(removed);
+                        }
+                    }
+                    Err(e) => {
+                        if let Some(m) = self.metrics_set.as_mut() {
+                            m.transform_failed.inc();
+                        }
+                        return Err(pdata_error(&format!("attribute renames failed: {e}")));
+                    }
+                }
+
+                effect_handler
+                    .send_message(OtapPdata::new(context, records.into()))
+                    .await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_forwarded.inc();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Factory function to create a SemconvProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_semconv_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = SemconvProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<SemconvProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register SemconvProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static SEMCONV_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: SEMCONV_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_semconv_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the semconv processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static SEMCONV_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(SEMCONV_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::{node::test_node, processor::TestRuntime};
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::logs::v1::ExportLogsServiceRequest,
+        common::v1::{AnyValue, InstrumentationScope, KeyValue},
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+        resource::v1::Resource,
+    };
+    use prost::Message as _;
+    use serde_json::json;
+
+    fn attr(key: &str, value: &str) -> KeyValue {
+        KeyValue::new(key, AnyValue::new_string(value))
+    }
+
+    fn log(attributes: Vec<KeyValue>) -> LogRecord {
+        LogRecord {
+            time_unix_nano: 1,
+            attributes,
+            ..Default::default()
+        }
+    }
+
+    /// Sorted `key=value` attributes of the resource and of each log record.
+    fn attributes(req: &ExportLogsServiceRequest) -> Vec<Vec<String>> {
+        let format = |attrs: &[KeyValue]| {
+            let mut attrs: Vec<String> = attrs
+                .iter()
+                .map(|kv| {
+                    let value = kv
+                        .value
+                        .as_ref()
+                        .and_then(|v| v.value.as_ref())
+                        .map(|v| format!("{v:?}"))
+                        .unwrap_or_default();
+                    format!("{}={value}", kv.key)
+                })
+                .collect();
+            attrs.sort();
+            attrs
+        };
+        let resource_logs = &req.resource_logs[0];
+        let mut out = vec![format(
+            &resource_logs
+                .resource
+                .as_ref()
+                .expect("resource")
+                .attributes,
+        )];
+        out.extend(
+            resource_logs.scope_logs[0]
+                .log_records
+                .iter()
+                .map(|log| format(&log.attributes)),
+        );
+        out
+    }
+
+    fn run(config: Value, input: ExportLogsServiceRequest) -> ExportLogsServiceRequest {
+        let pipeline_ctx = ControllerContext::new(MetricsRegistryHandle::new())
+            .pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let mut node_config = NodeUserConfig::new_processor_config(SEMCONV_PROCESSOR_URN);
+        node_config.config = config;
+        let proc = create_semconv_processor(
+            pipeline_ctx,
+            test_node("semconv-processor-test"),
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        let output = Arc::new(std::sync::Mutex::new(None));
+        let result = output.clone();
+        rt.set_processor(proc)
+            .run_test(|mut ctx| async move {
+                let mut bytes = Vec::new();
+                input.encode(&mut bytes).expect("encode");
+                let pdata = OtapPdata::new_default(OtlpProtoBytes::ExportLogsRequest(bytes).into());
+                ctx.process(Message::PData(pdata)).await.expect("process");
+                let out = ctx.drain_pdata().await;
+                let payload = out.into_iter().next().expect("one output").payload();
+                let OtlpProtoBytes::ExportLogsRequest(bytes) =
+                    payload.try_into().expect("convert to otlp")
+                else {
+                    panic!("unexpected otlp variant");
+                };
+                let decoded = ExportLogsServiceRequest::decode(bytes.as_slice()).expect("decode");
+                *result.lock().expect("lock") = Some(decoded);
+            })
+            .validate(|_| async move {});
+        let mut output = output.lock().expect("lock");
+        output.take().expect("output")
+    }
+
+    fn request() -> ExportLogsServiceRequest {
+        ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource {
+                    attributes: vec![attr("deployment.environment", "prod")],
+                    ..Default::default()
+                }),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(InstrumentationScope::default()),
+                    log_records: vec![
+                        log(vec![attr("http.method", "GET"), attr("app", "a")]),
+                        log(vec![
+                            attr("http.method", "GET"),
+                            attr("http.request.method", "POST"),
+                        ]),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let output = run(json!({ "overrides": { "app": "app.name" } }), request());
+        assert_eq!(
+            attributes(&output),
+            vec![
+                vec![r#"deployment.environment.name=StringValue("prod")"#.to_string()],
+                vec![
+                    r#"app.name=StringValue("a")"#.to_string(),
+                    r#"http.request.method=StringValue("GET")"#.to_string(),
+                ],
+                // the attribute already present is kept
+                vec![r#"http.request.method=StringValue("POST")"#.to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_downgrade_with_exclusions() {
+        let output = run(
+            json!({ "direction": "downgrade", "exclude": ["deployment.environment"] }),
+            request(),
+        );
+        assert_eq!(
+            attributes(&output),
+            vec![
+                vec![r#"deployment.environment=StringValue("prod")"#.to_string()],
+                vec![
+                    r#"app=StringValue("a")"#.to_string(),
+                    r#"http.method=StringValue("GET")"#.to_string(),
+                ],
+                vec![r#"http.method=StringValue("GET")"#.to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({ "direction": "sideways" }),
+            json!({ "overrides": { "a": "" } }),
+            json!({ "overrides": { "a": "a" } }),
+            // two names renamed to the same name
+            json!({ "overrides": { "app.method": "http.request.method" } }),
+            // chained renames
+            json!({ "overrides": { "http.request.method": "method" } }),
+        ] {
+            let err = SemconvProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the SemconvProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the SemconvProcessor node.
+#[metric_set(name = "semconv.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct SemconvProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// Attributes renamed to the target convention.
+    #[metric(unit = "{attr}")]
+    pub renamed_entries: Counter<u64>,
+
+    /// Attributes removed because their record already had the renamed attribute.
+    #[metric(unit = "{attr}")]
+    pub duplicates_removed: Counter<u64>,
+
+    /// Number of failed renames.
+    #[metric(unit = "{op}")]
+    pub transform_failed: Counter<u64>,
+}
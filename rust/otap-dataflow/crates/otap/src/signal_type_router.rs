@@ -13,6 +13,8 @@ use async_trait::async_trait;
 use linkme::distributed_slice;
 use otap_df_config::error::Error as ConfigError;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::ProcessorFactory;
 use otap_df_engine::config::ProcessorConfig;
 use otap_df_engine::context::PipelineContext;
@@ -25,6 +27,7 @@ use otap_df_engine::processor::ProcessorWrapper;
 use otap_df_telemetry::instrument::Counter;
 use otap_df_telemetry::metrics::MetricSet;
 use otap_df_telemetry_macros::metric_set;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -128,7 +131,7 @@ impl SignalTypeRouterMetrics {
 }
 
 /// Minimal configuration for the SignalTypeRouter processor
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct SignalTypeRouterConfig {}
 
 /// The SignalTypeRouter processor (local, !Send)
@@ -245,8 +248,8 @@ pub fn create_signal_type_router(
     processor_config: &ProcessorConfig,
 ) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
     // Deserialize the (currently empty) router configuration
-    let router_config: SignalTypeRouterConfig = serde_json::from_value(node_config.config.clone())
-        .map_err(|e| ConfigError::InvalidUserConfig {
+    let router_config: SignalTypeRouterConfig =
+        parse_user_config(&node_config.config).map_err(|e| ConfigError::InvalidUserConfig {
             error: format!("Failed to parse SignalTypeRouter configuration: {e}"),
         })?;
 
@@ -274,11 +277,9 @@ pub static SIGNAL_TYPE_ROUTER_FACTORY: ProcessorFactory<OtapPdata> = ProcessorFa
              node_config: Arc<NodeUserConfig>,
              proc_cfg: &ProcessorConfig| {
         // Deserialize the (currently empty) router configuration
-        let router_config: SignalTypeRouterConfig =
-            serde_json::from_value(node_config.config.clone()).map_err(|e| {
-                ConfigError::InvalidUserConfig {
-                    error: format!("Failed to parse SignalTypeRouter configuration: {e}"),
-                }
+        let router_config: SignalTypeRouterConfig = parse_user_config(&node_config.config)
+            .map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse SignalTypeRouter configuration: {e}"),
             })?;
 
         // Create the router with metrics registered via PipelineContext
@@ -291,6 +292,12 @@ pub static SIGNAL_TYPE_ROUTER_FACTORY: ProcessorFactory<OtapPdata> = ProcessorFa
     },
 };
 
+/// Schema of the configuration of the signal type router
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static SIGNAL_TYPE_ROUTER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<SignalTypeRouterConfig>(SIGNAL_TYPE_ROUTER_URN);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,767 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Span metrics processor for OTAP pipelines.
+//!
+//! This processor aggregates the spans flowing through it into RED (rate, errors, duration)
+//! metrics, and periodically emits them as OTAP metric batches on a dedicated out port:
+//! - `traces.span.metrics.calls`: cumulative monotonic sum counting the spans,
+//! - `traces.span.metrics.duration`: cumulative explicit bucket histogram of the span durations,
+//!   in milliseconds.
+//!
+//! Both metrics have one data point per distinct combination of `service.name` (resource
+//! attribute), `span.name`, `span.kind`, `status.code` and of the configured extra `dimensions`.
+//! Error rates are the calls with `status.code` equal to `STATUS_CODE_ERROR` over all the calls.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! interval: 60s
+//! metrics_port: metrics
+//! forward_port: traces
+//! dimensions: ['attributes["http.route"]', 'resource.attributes["deployment.environment"]']
+//! duration_buckets_ms: [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000]
+//! ```
+//!
+//! Dimensions use the field syntax of the filter processor expressions, and are named after the
+//! attribute key (or column path). Beyond `max_series` combinations, the spans are aggregated in
+//! a single data point with the `otel.metric.overflow` attribute set to `true`.
+//!
+//! Input telemetry (traces and any other signal) is forwarded to `forward_port` when set, and
+//! consumed otherwise.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::filter_processor::eval::{field_values, int_column_values, root_payload_type};
+use crate::filter_processor::expr::{self, Domain, Field};
+use crate::pdata::{OtapPayload, OtapPdata, OtlpProtoBytes, decode_ids, pdata_error};
+use arrow::array::RecordBatch;
+use arrow::error::ArrowError;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::ConsumerEffectHandlerExtension;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, NodeControlMsg};
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::collector::metrics::v1::ExportMetricsServiceRequest;
+use otel_arrow_rust::proto::opentelemetry::common::v1::{AnyValue, InstrumentationScope, KeyValue};
+use otel_arrow_rust::proto::opentelemetry::metrics::v1::{
+    AggregationTemporality, Histogram, HistogramDataPoint, Metric, NumberDataPoint,
+    ResourceMetrics, ScopeMetrics, Sum, metric, number_data_point,
+};
+use otel_arrow_rust::proto::opentelemetry::resource::v1::Resource;
+use otel_arrow_rust::proto::opentelemetry::trace::v1::{span, status};
+use otel_arrow_rust::schema::consts;
+use prost::Message as _;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+mod metrics;
+use self::metrics::SpanMetricsProcessorMetrics;
+
+/// URN for the SpanMetricsProcessor
+pub const SPAN_METRICS_PROCESSOR_URN: &str = "urn:otap:processor:span_metrics";
+
+/// Default emission interval.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+/// Default out port of the generated metrics.
+const DEFAULT_METRICS_PORT: &str = "metrics";
+/// Default maximum number of data points per metric.
+const DEFAULT_MAX_SERIES: usize = 1000;
+/// Default upper bounds of the duration histogram buckets, in milliseconds.
+const DEFAULT_DURATION_BUCKETS_MS: [f64; 16] = [
+    2.0, 4.0, 6.0, 8.0, 10.0, 50.0, 100.0, 200.0, 400.0, 800.0, 1000.0, 1400.0, 2000.0, 5000.0,
+    10000.0, 15000.0,
+];
+/// Attribute marking the data point aggregating the spans beyond `max_series`.
+const OVERFLOW_ATTRIBUTE: &str = "otel.metric.overflow";
+/// Instrumentation scope of the generated metrics.
+const SCOPE_NAME: &str = "otap.span_metrics";
+/// Name of the calls metric.
+const CALLS_METRIC: &str = "traces.span.metrics.calls";
+/// Name of the duration metric.
+const DURATION_METRIC: &str = "traces.span.metrics.duration";
+
+/// Configuration for the SpanMetricsProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// How often the metrics are emitted.
+    #[serde(with = "humantime_serde", default = "default_interval")]
+    #[schemars(with = "String")]
+    pub interval: Duration,
+
+    /// Out port the generated metrics are sent to.
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: String,
+
+    /// Out port the input telemetry is forwarded to. The input is consumed when not set.
+    #[serde(default)]
+    pub forward_port: Option<String>,
+
+    /// Fields added to the default dimensions, e.g. `attributes["http.route"]`.
+    #[serde(default)]
+    pub dimensions: Vec<String>,
+
+    /// Upper bounds of the duration histogram buckets, in milliseconds.
+    #[serde(default = "default_duration_buckets_ms")]
+    pub duration_buckets_ms: Vec<f64>,
+
+    /// Maximum number of data points per metric.
+    #[serde(default = "default_max_series")]
+    pub max_series: usize,
+}
+
+const fn default_interval() -> Duration {
+    DEFAULT_INTERVAL
+}
+
+fn default_metrics_port() -> String {
+    DEFAULT_METRICS_PORT.to_string()
+}
+
+fn default_duration_buckets_ms() -> Vec<f64> {
+    DEFAULT_DURATION_BUCKETS_MS.to_vec()
+}
+
+const fn default_max_series() -> usize {
+    DEFAULT_MAX_SERIES
+}
+
+/// Aggregated calls and durations of the spans of a series.
+#[derive(Debug, Clone)]
+struct SpanStats {
+    calls: u64,
+    bucket_counts: Vec<u64>,
+    sum_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+}
+
+impl SpanStats {
+    fn new(num_buckets: usize) -> Self {
+        Self {
+            calls: 0,
+            bucket_counts: vec![0; num_buckets],
+            sum_ms: 0.0,
+            min_ms: f64::INFINITY,
+            max_ms: f64::NEG_INFINITY,
+        }
+    }
+
+    fn record(&mut self, bounds: &[f64], duration_ms: f64) {
+        self.calls += 1;
+        // buckets are upper-bound inclusive, the last one is unbounded
+        let bucket = bounds.partition_point(|bound| *bound < duration_ms);
+        self.bucket_counts[bucket] += 1;
+        self.sum_ms += duration_ms;
+        self.min_ms = self.min_ms.min(duration_ms);
+        self.max_ms = self.max_ms.max(duration_ms);
+    }
+}
+
+/// Aggregation of the spans into series.
+struct Aggregator {
+    // Data point attribute key, and the field it is read from, for the extra dimensions
+    dimensions: Vec<(String, Field)>,
+    bounds: Vec<f64>,
+    max_series: usize,
+    // Stats of each combination of values, `None` for the overflow data point
+    series: BTreeMap<Option<Vec<Option<String>>>, SpanStats>,
+}
+
+impl Aggregator {
+    fn new(config: &Config) -> Result<Self, ConfigError> {
+        let dimensions = config
+            .dimensions
+            .iter()
+            .map(|field| -> Result<(String, Field), expr::ParseError> {
+                let field = expr::parse_field(field)?;
+                let key = match &field {
+                    Field::Attribute { key, .. } => key.clone(),
+                    Field::Column(path) => path.join("."),
+                };
+                Ok((key, field))
+            })
+            .collect::<Result<_, _>>()
+            .map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("invalid dimension: {e}"),
+            })?;
+        let bounds = config.duration_buckets_ms.clone();
+        if bounds.iter().any(|bound| !bound.is_finite())
+            || bounds.windows(2).any(|pair| pair[0] >= pair[1])
+        {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "duration_buckets_ms must be finite and strictly increasing".to_string(),
+            });
+        }
+        Ok(Self {
+            dimensions,
+            bounds,
+            max_series: config.max_series,
+            series: BTreeMap::new(),
+        })
+    }
+
+    /// Keys of the data point attributes, in the order of the series values.
+    fn attribute_keys(&self) -> impl Iterator<Item = &str> {
+        ["service.name", "span.name", "span.kind", "status.code"]
+            .into_iter()
+            .chain(self.dimensions.iter().map(|(key, _)| key.as_str()))
+    }
+
+    /// Aggregates the spans of `records`, returning the number of spans.
+    ///
+    /// The ids of `records` must have been decoded with `decode_transport_optimized_ids`.
+    fn aggregate(&mut self, records: &OtapArrowRecords) -> Result<usize, ArrowError> {
+        let num_rows = records
+            .get(root_payload_type(records))
+            .map_or(0, RecordBatch::num_rows);
+        if num_rows == 0 {
+            return Ok(0);
+        }
+        let path = |path: &[&str]| path.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let service_names = field_values(
+            &Field::Attribute {
+                domain: Domain::Resource,
+                key: "service.name".to_string(),
+            },
+            records,
+        )?;
+        let span_names = field_values(&Field::Column(path(&[consts::NAME])), records)?;
+        let kinds = int_column_values(records, &path(&[consts::KIND]))?;
+        let status_codes =
+            int_column_values(records, &path(&[consts::STATUS, consts::STATUS_CODE]))?;
+        let durations = int_column_values(records, &path(&[consts::DURATION_TIME_UNIX_NANO]))?;
+        let dimensions = self
+            .dimensions
+            .iter()
+            .map(|(_, field)| field_values(field, records))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for row in 0..num_rows {
+            let kind = kinds[row]
+                .and_then(|kind| i32::try_from(kind).ok())
+                .and_then(|kind| span::SpanKind::try_from(kind).ok())
+                .unwrap_or(span::SpanKind::Unspecified);
+            let status_code = status_codes[row]
+                .and_then(|code| i32::try_from(code).ok())
+                .and_then(|code| status::StatusCode::try_from(code).ok())
+                .unwrap_or(status::StatusCode::Unset);
+            let mut key = vec![
+                service_names[row].clone(),
+                span_names[row].clone(),
+                Some(kind.as_str_name().to_string()),
+                Some(status_code.as_str_name().to_string()),
+            ];
+            key.extend(dimensions.iter().map(|values| values[row].clone()));
+
+            let key = Some(key);
+            let key = if self.series.contains_key(&key) || self.series.len() < self.max_series {
+                key
+            } else {
+                None
+            };
+            let duration_ms = durations[row].unwrap_or(0).max(0) as f64 / 1_000_000.0;
+            let num_buckets = self.bounds.len() + 1;
+            self.series
+                .entry(key)
+                .or_insert_with(|| SpanStats::new(num_buckets))
+                .record(&self.bounds, duration_ms);
+        }
+        Ok(num_rows)
+    }
+
+    /// Builds the request reporting the current aggregates, returning it with its number of data
+    /// points. Returns `None` if no span has been aggregated yet.
+    fn build_request(
+        &self,
+        start_time_unix_nano: u64,
+        time_unix_nano: u64,
+    ) -> Option<(ExportMetricsServiceRequest, u64)> {
+        if self.series.is_empty() {
+            return None;
+        }
+        let mut calls = Vec::with_capacity(self.series.len());
+        let mut durations = Vec::with_capacity(self.series.len());
+        for (key, stats) in &self.series {
+            let attributes: Vec<KeyValue> = match key {
+                Some(values) => self
+                    .attribute_keys()
+                    .zip(values)
+                    .filter_map(|(name, value)| {
+                        value
+                            .as_ref()
+                            .map(|value| KeyValue::new(name, AnyValue::new_string(value)))
+                    })
+                    .collect(),
+                None => vec![KeyValue::new(OVERFLOW_ATTRIBUTE, AnyValue::new_bool(true))],
+            };
+            calls.push(NumberDataPoint {
+                attributes: attributes.clone(),
+                start_time_unix_nano,
+                time_unix_nano,
+                value: Some(number_data_point::Value::AsInt(
+                    i64::try_from(stats.calls).unwrap_or(i64::MAX),
+                )),
+                ..Default::default()
+            });
+            durations.push(HistogramDataPoint {
+                attributes,
+                start_time_unix_nano,
+                time_unix_nano,
+                count: stats.calls,
+                sum: Some(stats.sum_ms),
+                bucket_counts: stats.bucket_counts.clone(),
+                explicit_bounds: self.bounds.clone(),
+                min: Some(stats.min_ms),
+                max: Some(stats.max_ms),
+                ..Default::default()
+            });
+        }
+        let data_points = (calls.len() + durations.len()) as u64;
+
+        let metrics = vec![
+            Metric {
+                name: CALLS_METRIC.to_owned(),
+                description: "Number of spans".to_owned(),
+                unit: "{span}".to_owned(),
+                data: Some(metric::Data::Sum(Sum {
+                    data_points: calls,
+                    aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                    is_monotonic: true,
+                })),
+                ..Default::default()
+            },
+            Metric {
+                name: DURATION_METRIC.to_owned(),
+                description: "Duration of the spans".to_owned(),
+                unit: "ms".to_owned(),
+                data: Some(metric::Data::Histogram(Histogram {
+                    data_points: durations,
+                    aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                })),
+                ..Default::default()
+            },
+        ];
+        let request = ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                resource: Some(Resource::default()),
+                scope_metrics: vec![ScopeMetrics {
+                    scope: Some(InstrumentationScope {
+                        name: SCOPE_NAME.to_owned(),
+                        version: env!("CARGO_PKG_VERSION").to_owned(),
+                        ..Default::default()
+                    }),
+                    metrics,
+                    schema_url: String::new(),
+                }],
+                schema_url: String::new(),
+            }],
+        };
+        Some((request, data_points))
+    }
+}
+
+/// Processor aggregating spans into RED metrics.
+pub struct SpanMetricsProcessor {
+    config: Config,
+    aggregator: Aggregator,
+    start_time: SystemTime,
+    timer_started: bool,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<SpanMetricsProcessorMetrics>>,
+}
+
+impl SpanMetricsProcessor {
+    /// Creates a new SpanMetricsProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let config: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse SpanMetricsProcessor configuration: {e}"),
+            })?;
+        if config.interval.is_zero() {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "interval must be greater than 0".to_string(),
+            });
+        }
+        if config.max_series == 0 {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "max_series must be greater than 0".to_string(),
+            });
+        }
+        if config.forward_port.as_ref() == Some(&config.metrics_port) {
+            return Err(ConfigError::InvalidUserConfig {
+                error: "forward_port and metrics_port must be different".to_string(),
+            });
+        }
+        let aggregator = Aggregator::new(&config)?;
+        Ok(Self {
+            config,
+            aggregator,
+            start_time: SystemTime::now(),
+            timer_started: false,
+            metrics_set: None,
+        })
+    }
+
+    /// Sends the current aggregates to the metrics port.
+    async fn emit(
+        &mut self,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        let Some((request, data_points)) = self
+            .aggregator
+            .build_request(unix_nanos(self.start_time), unix_nanos(SystemTime::now()))
+        else {
+            return Ok(());
+        };
+        let mut bytes = Vec::new();
+        request
+            .encode(&mut bytes)
+            .map_err(|e| pdata_error(&format!("failed to encode metrics: {e}")))?;
+        let payload: OtapPayload = OtlpProtoBytes::ExportMetricsRequest(bytes).into();
+        let records: OtapArrowRecords = payload.try_into()?;
+        effect_handler
+            .send_message_to(
+                self.config.metrics_port.clone(),
+                OtapPdata::new_todo_context(records.into()),
+            )
+            .await?;
+        if let Some(m) = self.metrics_set.as_mut() {
+            m.data_points_emitted.add(data_points);
+        }
+        Ok(())
+    }
+
+    /// Forwards `pdata` to the forward port, or acknowledges it when there is none.
+    async fn forward(
+        &mut self,
+        pdata: OtapPdata,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match self.config.forward_port.clone() {
+            Some(port) => {
+                effect_handler.send_message_to(port, pdata).await?;
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_forwarded.inc();
+                }
+                Ok(())
+            }
+            None => {
+                let (context, mut payload) = pdata.into_parts();
+                if !context.may_return_payload() {
+                    let _ = payload.take_payload();
+                }
+                effect_handler
+                    .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+                    .await
+            }
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for SpanMetricsProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                NodeControlMsg::TimerTick { .. } | NodeControlMsg::Shutdown { .. } => {
+                    self.emit(effect_handler).await
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                if pdata.signal_type() != SignalType::Traces {
+                    return self.forward(pdata, effect_handler).await;
+                }
+
+                if !self.timer_started {
+                    // Processors have no start hook, so the emission timer starts on first data
+                    let _timer_cancel = effect_handler
+                        .start_periodic_timer(self.config.interval)
+                        .await?;
+                    self.timer_started = true;
+                }
+
+                let (context, payload) = pdata.into_parts();
+                let mut records: OtapArrowRecords = payload.try_into()?;
+                let aggregated =
+                    decode_ids(&mut records).and_then(|_| self.aggregator.aggregate(&records));
+                if let Some(m) = self.metrics_set.as_mut() {
+                    match aggregated {
+                        Ok(spans) => m.spans_aggregated.add(spans as u64),
+                        Err(_) => m.aggregation_failed.inc(),
+                    }
+                }
+                self.forward(OtapPdata::new(context, records.into()), effect_handler)
+                    .await
+            }
+        }
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+}
+
+/// Factory function to create a SpanMetricsProcessor.
+///
+/// See the module documentation for the configuration.
+pub fn create_span_metrics_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = SpanMetricsProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<SpanMetricsProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register SpanMetricsProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static SPAN_METRICS_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: SPAN_METRICS_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_span_metrics_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the span metrics processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static SPAN_METRICS_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(SPAN_METRICS_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::trace::v1::ExportTraceServiceRequest,
+        common::v1::any_value,
+        trace::v1::{ResourceSpans, ScopeSpans, Span, Status},
+    };
+    use serde_json::json;
+
+    fn span(name: &str, duration_ms: u64, error: bool, route: &str) -> Span {
+        Span {
+            trace_id: vec![1; 16],
+            span_id: vec![2; 8],
+            name: name.to_string(),
+            kind: span::SpanKind::Server as i32,
+            start_time_unix_nano: 1_000_000_000,
+            end_time_unix_nano: 1_000_000_000 + duration_ms * 1_000_000,
+            attributes: vec![KeyValue::new("http.route", AnyValue::new_string(route))],
+            status: Some(Status {
+                code: (if error {
+                    status::StatusCode::Error
+                } else {
+                    status::StatusCode::Ok
+                }) as i32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn traces_records(spans: Vec<Span>) -> OtapArrowRecords {
+        let req = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue::new(
+                        "service.name",
+                        AnyValue::new_string("checkout"),
+                    )],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    scope: Some(InstrumentationScope::default()),
+                    spans,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        let payload: OtapPayload = OtlpProtoBytes::ExportTracesRequest(bytes).into();
+        let mut records: OtapArrowRecords = payload.try_into().expect("convert to arrow");
+        records
+            .decode_transport_optimized_ids()
+            .expect("decode ids");
+        records
+    }
+
+    fn attributes(attributes: &[KeyValue]) -> Vec<String> {
+        let mut attributes: Vec<String> = attributes
+            .iter()
+            .map(
+                |kv| match kv.value.as_ref().and_then(|v| v.value.as_ref()) {
+                    Some(any_value::Value::StringValue(s)) => format!("{}={s}", kv.key),
+                    Some(any_value::Value::BoolValue(b)) => format!("{}={b}", kv.key),
+                    other => panic!("unexpected value {other:?}"),
+                },
+            )
+            .collect();
+        attributes.sort();
+        attributes
+    }
+
+    #[test]
+    fn test_red_metrics() {
+        let proc = SpanMetricsProcessor::from_config(&json!({
+            "dimensions": ["attributes[\"http.route\"]"],
+            "duration_buckets_ms": [10, 100],
+        }))
+        .expect("valid config");
+        let mut aggregator = proc.aggregator;
+        assert!(aggregator.build_request(1, 2).is_none());
+
+        let records = traces_records(vec![
+            span("GET", 5, false, "/a"),
+            span("GET", 50, false, "/a"),
+            span("GET", 500, true, "/a"),
+        ]);
+        assert_eq!(aggregator.aggregate(&records).expect("aggregate"), 3);
+        let records = traces_records(vec![span("GET", 10, false, "/a")]);
+        assert_eq!(aggregator.aggregate(&records).expect("aggregate"), 1);
+
+        let (request, data_points) = aggregator.build_request(1, 2).expect("request");
+        assert_eq!(data_points, 4);
+        let metrics = &request.resource_metrics[0].scope_metrics[0].metrics;
+        assert_eq!(metrics[0].name, CALLS_METRIC);
+        assert_eq!(metrics[1].name, DURATION_METRIC);
+
+        let Some(metric::Data::Sum(calls)) = &metrics[0].data else {
+            panic!("expected a sum");
+        };
+        let calls: Vec<_> = calls
+            .data_points
+            .iter()
+            .map(|point| (attributes(&point.attributes), point.value))
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                (
+                    vec![
+                        "http.route=/a".to_string(),
+                        "service.name=checkout".to_string(),
+                        "span.kind=SPAN_KIND_SERVER".to_string(),
+                        "span.name=GET".to_string(),
+                        "status.code=STATUS_CODE_ERROR".to_string(),
+                    ],
+                    Some(number_data_point::Value::AsInt(1)),
+                ),
+                (
+                    vec![
+                        "http.route=/a".to_string(),
+                        "service.name=checkout".to_string(),
+                        "span.kind=SPAN_KIND_SERVER".to_string(),
+                        "span.name=GET".to_string(),
+                        "status.code=STATUS_CODE_OK".to_string(),
+                    ],
+                    Some(number_data_point::Value::AsInt(3)),
+                ),
+            ]
+        );
+
+        let Some(metric::Data::Histogram(durations)) = &metrics[1].data else {
+            panic!("expected a histogram");
+        };
+        let ok = &durations.data_points[1];
+        assert_eq!(ok.count, 3);
+        assert_eq!(ok.explicit_bounds, vec![10.0, 100.0]);
+        assert_eq!(ok.bucket_counts, vec![2, 1, 0]);
+        assert_eq!(ok.sum, Some(65.0));
+        assert_eq!(ok.min, Some(5.0));
+        assert_eq!(ok.max, Some(50.0));
+        assert_eq!(durations.data_points[0].bucket_counts, vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn test_overflow_series() {
+        let proc =
+            SpanMetricsProcessor::from_config(&json!({ "max_series": 1 })).expect("valid config");
+        let mut aggregator = proc.aggregator;
+        let records = traces_records(vec![
+            span("GET", 5, false, "/a"),
+            span("POST", 5, false, "/a"),
+            span("PUT", 5, false, "/a"),
+        ]);
+        let _ = aggregator.aggregate(&records).expect("aggregate");
+        let (request, _) = aggregator.build_request(1, 2).expect("request");
+        let Some(metric::Data::Sum(calls)) =
+            &request.resource_metrics[0].scope_metrics[0].metrics[0].data
+        else {
+            panic!("expected a sum");
+        };
+        assert_eq!(calls.data_points.len(), 2);
+        assert_eq!(
+            attributes(&calls.data_points[0].attributes),
+            vec!["otel.metric.overflow=true".to_string()]
+        );
+        assert_eq!(
+            calls.data_points[0].value,
+            Some(number_data_point::Value::AsInt(2))
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            json!({ "interval": "0s" }),
+            json!({ "max_series": 0 }),
+            json!({ "forward_port": "metrics" }),
+            json!({ "dimensions": ["a == 1"] }),
+            json!({ "duration_buckets_ms": [10, 5] }),
+        ] {
+            let err = SpanMetricsProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the SpanMetricsProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the SpanMetricsProcessor node.
+#[metric_set(name = "span_metrics.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct SpanMetricsProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// Spans aggregated.
+    #[metric(unit = "{span}")]
+    pub spans_aggregated: Counter<u64>,
+
+    /// Metric data points emitted.
+    #[metric(unit = "{data_point}")]
+    pub data_points_emitted: Counter<u64>,
+
+    /// Number of span batches that could not be aggregated.
+    #[metric(unit = "{op}")]
+    pub aggregation_failed: Counter<u64>,
+}
@@ -7,6 +7,8 @@ use crate::pdata::OtapPdata;
 use async_trait::async_trait;
 use linkme::distributed_slice;
 use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
 use otap_df_engine::ReceiverFactory;
 use otap_df_engine::config::ReceiverConfig;
 use otap_df_engine::context::PipelineContext;
@@ -21,6 +23,7 @@ use otap_df_engine::{
 use otap_df_telemetry::instrument::{Counter, UpDownCounter};
 use otap_df_telemetry::metrics::MetricSet;
 use otap_df_telemetry_macros::metric_set;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json::Value;
 use std::cell::RefCell;
@@ -41,7 +44,7 @@ const BATCH_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(100)
 const MAX_BATCH_SIZE: u16 = 100; // Maximum number of messages to build an Arrow batch
 
 /// Protocol type for the receiver
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 #[allow(dead_code)]
 enum Protocol {
@@ -52,7 +55,7 @@ enum Protocol {
 }
 
 /// config for a syslog cef receiver
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 struct Config {
     listening_addr: SocketAddr,
@@ -94,7 +97,7 @@ impl SyslogCefReceiver {
         pipeline: PipelineContext,
         config: &Value,
     ) -> Result<Self, otap_df_config::error::Error> {
-        let cfg: Config = serde_json::from_value(config.clone()).map_err(|e| {
+        let cfg: Config = parse_user_config(config).map_err(|e| {
             otap_df_config::error::Error::InvalidUserConfig {
                 error: e.to_string(),
             }
@@ -121,6 +124,12 @@ pub static SYSLOG_CEF_RECEIVER: ReceiverFactory<OtapPdata> = ReceiverFactory {
     },
 };
 
+/// Schema of the configuration of the syslog CEF receiver
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static SYSLOG_CEF_RECEIVER_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(SYSLOG_CEF_RECEIVER_URN);
+
 #[async_trait(?Send)]
 impl local::Receiver<OtapPdata> for SyslogCefReceiver {
     async fn start(
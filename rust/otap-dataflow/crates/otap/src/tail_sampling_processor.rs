@@ -0,0 +1,653 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tail sampling processor for OTAP pipelines.
+//!
+//! This processor holds span batches for a decision window after the first span of each of
+//! their traces is received, then decides for each whole trace whether it is sampled. A trace is
+//! sampled when at least one policy samples it; the spans of the other traces are removed from
+//! the batches with Arrow's filter kernel (along with their attributes, events and links) before
+//! the batches are released. Logs and metrics pass through.
+//!
+//! Example configuration (YAML):
+//! ```yaml
+//! decision_wait: 10s
+//! num_traces: 50000
+//! policies:
+//!   - name: errors
+//!     type: status_code
+//!     status_codes: [ERROR]
+//!   - name: slow
+//!     type: latency
+//!     threshold: 500ms
+//!   - name: vip
+//!     type: attribute
+//!     key: customer.tier
+//!     values: [gold, platinum]
+//!   - name: baseline
+//!     type: probabilistic
+//!     sampling_percentage: 5
+//! ```
+//!
+//! Policies (see [`Policy`]):
+//! - `status_code`: a span of the trace has one of the status codes (`UNSET`, `OK`, `ERROR`).
+//! - `latency`: the trace lasts at least `threshold`, from its earliest span start to its latest
+//!   span end.
+//! - `attribute`: a span of the trace, or its resource, has a `key` attribute equal to one of
+//!   `values`.
+//! - `probabilistic`: a hash of the trace id falls within `sampling_percentage`.
+//!
+//! When more than `num_traces` traces are waiting for a decision, the oldest ones are decided
+//! early. Decisions are remembered for the last `num_traces` decided traces, so late spans of a
+//! decided trace are released (or dropped) without waiting. Batches are held until all their
+//! traces are decided, and batches left without spans are acknowledged and not forwarded. All
+//! pending traces are decided on shutdown.
+
+use crate::OTAP_PROCESSOR_FACTORIES;
+use crate::filter_processor::eval::filter_rows;
+use crate::pdata::{Context, OtapPayload, OtapPdata, decode_ids, pdata_error};
+use arrow::array::BooleanArray;
+use async_trait::async_trait;
+use linkme::distributed_slice;
+use otap_df_config::error::Error as ConfigError;
+use otap_df_config::experimental::SignalType;
+use otap_df_config::node::NodeUserConfig;
+use otap_df_config::schema::{NodeConfigSchema, parse_user_config};
+use otap_df_engine::ConsumerEffectHandlerExtension;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::config::ProcessorConfig;
+use otap_df_engine::context::PipelineContext;
+use otap_df_engine::control::{AckMsg, NodeControlMsg};
+use otap_df_engine::error::Error as EngineError;
+use otap_df_engine::local::processor as local;
+use otap_df_engine::message::Message;
+use otap_df_engine::node::NodeId;
+use otap_df_engine::processor::ProcessorWrapper;
+use otap_df_telemetry::metrics::MetricSet;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod metrics;
+mod policy;
+mod spans;
+use self::metrics::TailSamplingProcessorMetrics;
+use self::policy::{MAX_POLICIES, TraceId, TraceSummary};
+pub use self::policy::{Policy, PolicyConfig, StatusCode};
+
+/// URN for the TailSamplingProcessor
+pub const TAIL_SAMPLING_PROCESSOR_URN: &str = "urn:otap:processor:tail_sampling";
+
+/// Default time to wait after the first span of a trace before deciding.
+const DEFAULT_DECISION_WAIT: Duration = Duration::from_secs(30);
+/// Default maximum number of traces waiting for a decision.
+const DEFAULT_NUM_TRACES: usize = 50_000;
+/// Upper bound of the interval at which pending traces are checked.
+const MAX_TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configuration for the TailSamplingProcessor.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Time to wait after the first span of a trace before deciding whether it is sampled.
+    #[serde(with = "humantime_serde", default = "default_decision_wait")]
+    #[schemars(with = "String")]
+    pub decision_wait: Duration,
+
+    /// Maximum number of traces waiting for a decision, and of remembered decisions.
+    #[serde(default = "default_num_traces")]
+    pub num_traces: usize,
+
+    /// Sampling policies. A trace is sampled when any of them samples it.
+    pub policies: Vec<PolicyConfig>,
+}
+
+const fn default_decision_wait() -> Duration {
+    DEFAULT_DECISION_WAIT
+}
+
+const fn default_num_traces() -> usize {
+    DEFAULT_NUM_TRACES
+}
+
+/// A span batch held until all its traces are decided.
+struct PendingBatch {
+    context: Context,
+    records: OtapArrowRecords,
+    // Trace id of each span row
+    row_traces: Vec<Option<TraceId>>,
+    // Decision for each trace of the batch, None while undecided
+    decisions: HashMap<TraceId, Option<bool>>,
+}
+
+impl PendingBatch {
+    fn is_ready(&self) -> bool {
+        self.decisions.values().all(Option::is_some)
+    }
+}
+
+/// Processor that samples whole traces after a decision window.
+pub struct TailSamplingProcessor {
+    decision_wait: Duration,
+    num_traces: usize,
+    policies: Vec<PolicyConfig>,
+    // Batches waiting for decisions, in arrival order
+    pending: Vec<PendingBatch>,
+    // Undecided traces, and their first arrival time in arrival order
+    traces: HashMap<TraceId, TraceSummary>,
+    arrivals: VecDeque<(Instant, TraceId)>,
+    // Recent decisions, for spans arriving after their trace was decided
+    decided: HashMap<TraceId, bool>,
+    decided_order: VecDeque<TraceId>,
+    timer_started: bool,
+    // Metrics handle (set at runtime in factory; None when parsed-only)
+    metrics_set: Option<MetricSet<TailSamplingProcessorMetrics>>,
+}
+
+impl TailSamplingProcessor {
+    /// Creates a new TailSamplingProcessor from configuration.
+    pub fn from_config(config: &Value) -> Result<Self, ConfigError> {
+        let cfg: Config =
+            parse_user_config(config).map_err(|e| ConfigError::InvalidUserConfig {
+                error: format!("Failed to parse TailSamplingProcessor configuration: {e}"),
+            })?;
+        Self::new(cfg)
+    }
+
+    /// Creates a new TailSamplingProcessor with the given parsed configuration.
+    fn new(config: Config) -> Result<Self, ConfigError> {
+        let invalid = |error: String| ConfigError::InvalidUserConfig { error };
+        if config.policies.is_empty() {
+            return Err(invalid("at least one sampling policy is required".into()));
+        }
+        if config.policies.len() > MAX_POLICIES {
+            return Err(invalid(format!(
+                "at most {MAX_POLICIES} sampling policies are supported"
+            )));
+        }
+        if config.num_traces == 0 {
+            return Err(invalid("num_traces must be greater than 0".into()));
+        }
+        for policy in &config.policies {
+            policy
+                .policy
+                .validate()
+                .map_err(|e| invalid(format!("invalid policy `{}`: {e}", policy.name)))?;
+        }
+        Ok(Self {
+            decision_wait: config.decision_wait,
+            num_traces: config.num_traces,
+            policies: config.policies,
+            pending: Vec::new(),
+            traces: HashMap::new(),
+            arrivals: VecDeque::new(),
+            decided: HashMap::new(),
+            decided_order: VecDeque::new(),
+            timer_started: false,
+            metrics_set: None,
+        })
+    }
+
+    /// Record the spans of a batch and hold it until its traces are decided.
+    fn add_batch(
+        &mut self,
+        context: Context,
+        mut records: OtapArrowRecords,
+        now: Instant,
+    ) -> Result<(), EngineError> {
+        decode_ids(&mut records).map_err(|e| pdata_error(&format!("failed to decode ids: {e}")))?;
+        let rows = spans::span_rows(&records, &self.policies)
+            .map_err(|e| pdata_error(&format!("failed to inspect spans: {e}")))?;
+
+        let mut decisions = HashMap::new();
+        for row in &rows {
+            let Some(trace_id) = row.trace_id else {
+                continue;
+            };
+            let decision = decisions
+                .entry(trace_id)
+                .or_insert_with(|| self.decided.get(&trace_id).copied());
+            if decision.is_some() {
+                // late span of a decided trace
+                continue;
+            }
+            self.traces
+                .entry(trace_id)
+                .or_insert_with(|| {
+                    self.arrivals.push_back((now, trace_id));
+                    TraceSummary::default()
+                })
+                .add_span(row.status_code, row.start, row.end, row.attribute_matches);
+        }
+
+        self.pending.push(PendingBatch {
+            context,
+            records,
+            row_traces: rows.iter().map(|row| row.trace_id).collect(),
+            decisions,
+        });
+        Ok(())
+    }
+
+    /// Decide the traces whose decision window ended, the oldest traces beyond `num_traces`, or
+    /// all the pending traces if `force` is set.
+    fn decide(&mut self, now: Instant, force: bool) {
+        while let Some(&(arrival, trace_id)) = self.arrivals.front() {
+            let expired = now.saturating_duration_since(arrival) >= self.decision_wait;
+            let overflow = self.traces.len() > self.num_traces;
+            if !(force || expired || overflow) {
+                break;
+            }
+            let _ = self.arrivals.pop_front();
+            let Some(trace) = self.traces.remove(&trace_id) else {
+                continue;
+            };
+
+            let sampled = self
+                .policies
+                .iter()
+                .enumerate()
+                .any(|(index, policy)| policy.policy.samples(index, &trace_id, &trace));
+            if let Some(m) = self.metrics_set.as_mut() {
+                if sampled {
+                    m.traces_sampled.inc();
+                } else {
+                    m.traces_not_sampled.inc();
+                }
+                if !force && !expired {
+                    m.traces_decided_early.inc();
+                }
+            }
+
+            for batch in &mut self.pending {
+                if let Some(decision) = batch.decisions.get_mut(&trace_id) {
+                    *decision = Some(sampled);
+                }
+            }
+            if self.decided.insert(trace_id, sampled).is_none() {
+                self.decided_order.push_back(trace_id);
+            }
+            while self.decided_order.len() > self.num_traces {
+                if let Some(oldest) = self.decided_order.pop_front() {
+                    let _ = self.decided.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    /// Forward the sampled spans of the batches whose traces are all decided.
+    async fn release_ready(
+        &mut self,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        let (ready, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(PendingBatch::is_ready);
+        self.pending = waiting;
+
+        for batch in ready {
+            let PendingBatch {
+                context,
+                mut records,
+                row_traces,
+                decisions,
+            } = batch;
+            // spans without a trace id are kept
+            let mask: BooleanArray = row_traces
+                .iter()
+                .map(|trace_id| {
+                    Some(
+                        trace_id
+                            .is_none_or(|id| decisions.get(&id).copied().flatten() == Some(true)),
+                    )
+                })
+                .collect();
+            let kept = mask.true_count();
+            let dropped = mask.len() - kept;
+            if let Some(m) = self.metrics_set.as_mut() {
+                m.spans_dropped.add(dropped as u64);
+            }
+
+            if kept == 0 {
+                // Nothing left to forward: the batch is fully processed
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_dropped.inc();
+                }
+                let mut payload: OtapPayload = records.into();
+                if !context.may_return_payload() {
+                    let _ = payload.take_payload();
+                }
+                effect_handler
+                    .notify_ack(AckMsg::new(OtapPdata::new(context, payload)))
+                    .await?;
+                continue;
+            }
+            if dropped > 0 {
+                filter_rows(&mut records, ArrowPayloadType::Spans, &mask)
+                    .map_err(|e| pdata_error(&format!("failed to drop spans: {e}")))?;
+            }
+            effect_handler
+                .send_message(OtapPdata::new(context, records.into()))
+                .await?;
+            if let Some(m) = self.metrics_set.as_mut() {
+                m.msgs_forwarded.inc();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl local::Processor<OtapPdata> for TailSamplingProcessor {
+    async fn process(
+        &mut self,
+        msg: Message<OtapPdata>,
+        effect_handler: &mut local::EffectHandler<OtapPdata>,
+    ) -> Result<(), EngineError> {
+        match msg {
+            Message::Control(control_msg) => match control_msg {
+                NodeControlMsg::CollectTelemetry {
+                    mut metrics_reporter,
+                } => {
+                    if let Some(metrics) = self.metrics_set.as_mut() {
+                        let _ = metrics_reporter.report(metrics);
+                    }
+                    Ok(())
+                }
+                NodeControlMsg::TimerTick { .. } => {
+                    self.decide(Instant::now(), false);
+                    self.release_ready(effect_handler).await
+                }
+                NodeControlMsg::Shutdown { .. } => {
+                    self.decide(Instant::now(), true);
+                    self.release_ready(effect_handler).await
+                }
+                _ => Ok(()),
+            },
+            Message::PData(pdata) => {
+                if let Some(m) = self.metrics_set.as_mut() {
+                    m.msgs_consumed.inc();
+                }
+                if pdata.signal_type() != SignalType::Traces {
+                    effect_handler.send_message(pdata).await?;
+                    if let Some(m) = self.metrics_set.as_mut() {
+                        m.msgs_forwarded.inc();
+                    }
+                    return Ok(());
+                }
+
+                if !self.timer_started {
+                    // Processors have no start hook, so the decision timer starts on first data
+                    let _timer_cancel = effect_handler
+                        .start_periodic_timer(self.decision_wait.min(MAX_TICK_INTERVAL))
+                        .await?;
+                    self.timer_started = true;
+                }
+
+                let (context, payload) = pdata.into_parts();
+                let records: OtapArrowRecords = payload.try_into()?;
+                let now = Instant::now();
+                if let Err(e) = self.add_batch(context, records, now) {
+                    if let Some(m) = self.metrics_set.as_mut() {
+                        m.sampling_failed.inc();
+                    }
+                    return Err(e);
+                }
+                self.decide(now, false);
+                self.release_ready(effect_handler).await
+            }
+        }
+    }
+}
+
+/// Factory function to create a TailSamplingProcessor.
+///
+/// See the module documentation for the configuration and the sampling policies.
+pub fn create_tail_sampling_processor(
+    pipeline_ctx: PipelineContext,
+    node: NodeId,
+    node_config: Arc<NodeUserConfig>,
+    processor_config: &ProcessorConfig,
+) -> Result<ProcessorWrapper<OtapPdata>, ConfigError> {
+    let mut proc = TailSamplingProcessor::from_config(&node_config.config)?;
+    proc.metrics_set = Some(pipeline_ctx.register_metrics::<TailSamplingProcessorMetrics>());
+    Ok(ProcessorWrapper::local(
+        proc,
+        node,
+        node_config,
+        processor_config,
+    ))
+}
+
+/// Register TailSamplingProcessor as an OTAP processor factory
+#[allow(unsafe_code)]
+#[distributed_slice(OTAP_PROCESSOR_FACTORIES)]
+pub static TAIL_SAMPLING_PROCESSOR_FACTORY: otap_df_engine::ProcessorFactory<OtapPdata> =
+    otap_df_engine::ProcessorFactory {
+        name: TAIL_SAMPLING_PROCESSOR_URN,
+        create: |pipeline_ctx: PipelineContext,
+                 node: NodeId,
+                 node_config: Arc<NodeUserConfig>,
+                 proc_cfg: &ProcessorConfig| {
+            create_tail_sampling_processor(pipeline_ctx, node, node_config, proc_cfg)
+        },
+    };
+
+/// Schema of the configuration of the tail sampling processor
+#[allow(unsafe_code)]
+#[distributed_slice(NODE_CONFIG_SCHEMAS)]
+static TAIL_SAMPLING_PROCESSOR_CONFIG_SCHEMA: NodeConfigSchema =
+    NodeConfigSchema::new::<Config>(TAIL_SAMPLING_PROCESSOR_URN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdata::OtlpProtoBytes;
+    use otap_df_engine::context::ControllerContext;
+    use otap_df_engine::testing::processor::TestContext;
+    use otap_df_engine::testing::{node::test_node, processor::TestRuntime};
+    use otap_df_telemetry::registry::MetricsRegistryHandle;
+    use otel_arrow_rust::proto::opentelemetry::{
+        collector::trace::v1::ExportTraceServiceRequest,
+        common::v1::{AnyValue, InstrumentationScope, KeyValue},
+        resource::v1::Resource,
+        trace::v1::{ResourceSpans, ScopeSpans, Span, Status, status::StatusCode},
+    };
+    use prost::Message as _;
+    use serde_json::json;
+    use std::future::Future;
+
+    const MS: u64 = 1_000_000;
+
+    fn span(trace: u8, id: u8, start_ms: u64, end_ms: u64) -> Span {
+        Span {
+            trace_id: vec![trace; 16],
+            span_id: vec![id; 8],
+            name: format!("span-{trace}-{id}"),
+            start_time_unix_nano: start_ms * MS,
+            end_time_unix_nano: end_ms * MS,
+            ..Default::default()
+        }
+    }
+
+    fn traces_request(spans: Vec<Span>) -> OtapPdata {
+        let req = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource::default()),
+                scope_spans: vec![ScopeSpans {
+                    scope: Some(InstrumentationScope::default()),
+                    spans,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut bytes = Vec::new();
+        req.encode(&mut bytes).expect("encode");
+        OtapPdata::new_default(OtlpProtoBytes::ExportTracesRequest(bytes).into())
+    }
+
+    /// Sorted names of the spans of the forwarded batches.
+    fn span_names(out: Vec<OtapPdata>) -> Vec<String> {
+        let mut names: Vec<String> = out
+            .into_iter()
+            .flat_map(|pdata| {
+                let OtlpProtoBytes::ExportTracesRequest(bytes) =
+                    pdata.payload().try_into().expect("convert to otlp")
+                else {
+                    panic!("unexpected otlp variant");
+                };
+                let decoded = ExportTraceServiceRequest::decode(bytes.as_slice()).expect("decode");
+                decoded
+                    .resource_spans
+                    .into_iter()
+                    .flat_map(|rs| rs.scope_spans)
+                    .flat_map(|ss| ss.spans)
+                    .map(|span| span.name)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Runs the processor configured with `config` with the given test scenario.
+    fn run<F, Fut>(config: Value, scenario: F)
+    where
+        F: FnOnce(TestContext<OtapPdata>) -> Fut + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let pipeline_ctx = ControllerContext::new(MetricsRegistryHandle::new())
+            .pipeline_context_with("grp".into(), "pipeline".into(), 0, 0);
+        let rt: TestRuntime<OtapPdata> = TestRuntime::new();
+        let mut node_config = NodeUserConfig::new_processor_config(TAIL_SAMPLING_PROCESSOR_URN);
+        node_config.config = config;
+        let proc = create_tail_sampling_processor(
+            pipeline_ctx,
+            test_node("tail-sampling-processor-test"),
+            Arc::new(node_config),
+            rt.config(),
+        )
+        .expect("create processor");
+
+        rt.set_processor(proc)
+            .run_test(scenario)
+            .validate(|_| async move {});
+    }
+
+    #[test]
+    fn test_samples_whole_traces_after_decision_wait() {
+        let config = json!({
+            "decision_wait": "20ms",
+            "policies": [
+                {"name": "errors", "type": "status_code", "status_codes": ["ERROR"]},
+                {"name": "slow", "type": "latency", "threshold": "1s"},
+                {"name": "vip", "type": "attribute", "key": "tier", "values": ["gold"]},
+            ]
+        });
+        run(config, |mut ctx| async move {
+            let mut error_span = span(1, 2, 0, 10);
+            error_span.status = Some(Status::new("boom", StatusCode::Error));
+            let mut vip_span = span(4, 1, 0, 10);
+            vip_span.attributes = vec![KeyValue::new("tier", AnyValue::new_string("gold"))];
+            ctx.process(Message::PData(traces_request(vec![
+                span(1, 1, 0, 10),
+                span(2, 1, 0, 10),
+                span(3, 1, 0, 10),
+                vip_span,
+            ])))
+            .await
+            .expect("process");
+            // the rest of traces 1 and 3 arrives in a later batch
+            ctx.process(Message::PData(traces_request(vec![
+                error_span,
+                span(3, 2, 500, 1500),
+            ])))
+            .await
+            .expect("process");
+            assert!(ctx.drain_pdata().await.is_empty(), "traces are held");
+
+            ctx.sleep(Duration::from_millis(40)).await;
+            ctx.process(Message::Control(NodeControlMsg::TimerTick {}))
+                .await
+                .expect("timer tick");
+            assert_eq!(
+                span_names(ctx.drain_pdata().await),
+                vec!["span-1-1", "span-1-2", "span-3-1", "span-3-2", "span-4-1"]
+            );
+
+            // a late span of a sampled trace is released without waiting, and a batch
+            // without sampled traces is not forwarded
+            ctx.process(Message::PData(traces_request(vec![span(1, 3, 0, 10)])))
+                .await
+                .expect("process");
+            assert_eq!(span_names(ctx.drain_pdata().await), vec!["span-1-3"]);
+            ctx.process(Message::PData(traces_request(vec![span(2, 2, 0, 10)])))
+                .await
+                .expect("process");
+            assert!(ctx.drain_pdata().await.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_pending_traces_decided_on_shutdown_and_overflow() {
+        let config = json!({
+            "decision_wait": "1h",
+            "num_traces": 2,
+            "policies": [{"name": "all", "type": "probabilistic", "sampling_percentage": 100}]
+        });
+        run(config, |mut ctx| async move {
+            ctx.process(Message::PData(traces_request(vec![
+                span(1, 1, 0, 10),
+                span(2, 1, 0, 10),
+            ])))
+            .await
+            .expect("process");
+            assert!(ctx.drain_pdata().await.is_empty());
+
+            // a third trace exceeds num_traces: the oldest trace is decided early, but the first
+            // batch still waits for trace 2
+            ctx.process(Message::PData(traces_request(vec![span(3, 1, 0, 10)])))
+                .await
+                .expect("process");
+            assert!(ctx.drain_pdata().await.is_empty());
+
+            ctx.process(Message::Control(NodeControlMsg::Shutdown {
+                deadline: Instant::now() + Duration::from_secs(1),
+                reason: "test".into(),
+            }))
+            .await
+            .expect("shutdown");
+            assert_eq!(
+                span_names(ctx.drain_pdata().await),
+                vec!["span-1-1", "span-2-1", "span-3-1"]
+            );
+        });
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let latency = json!({"name": "p", "type": "latency", "threshold": "1s"});
+        let bad_code = json!({"name": "p", "type": "status_code", "status_codes": ["FAILED"]});
+        let bad_pct = json!({"name": "p", "type": "probabilistic", "sampling_percentage": -1});
+        for config in [
+            json!({}),
+            json!({ "policies": [] }),
+            json!({ "num_traces": 0, "policies": [latency] }),
+            json!({ "policies": [{"name": "p", "type": "latency", "threshold": "soon"}] }),
+            json!({ "policies": [bad_code] }),
+            json!({ "policies": [bad_pct] }),
+        ] {
+            let err = TailSamplingProcessor::from_config(&config)
+                .err()
+                .expect("config should be rejected");
+            assert!(matches!(err, ConfigError::InvalidUserConfig { .. }));
+        }
+        assert!(TailSamplingProcessor::from_config(&json!({ "policies": [latency] })).is_ok());
+    }
+}
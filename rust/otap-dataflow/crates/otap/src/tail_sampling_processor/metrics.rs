@@ -0,0 +1,45 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics for the TailSamplingProcessor node.
+
+use otap_df_telemetry::instrument::Counter;
+use otap_df_telemetry_macros::metric_set;
+
+/// Metrics for the TailSamplingProcessor node.
+#[metric_set(name = "tail_sampling.processor.metrics")]
+#[derive(Debug, Default, Clone)]
+pub struct TailSamplingProcessorMetrics {
+    /// PData messages consumed by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_consumed: Counter<u64>,
+
+    /// PData messages forwarded by this processor.
+    #[metric(unit = "{msg}")]
+    pub msgs_forwarded: Counter<u64>,
+
+    /// PData messages dropped because none of their traces were sampled.
+    #[metric(unit = "{msg}")]
+    pub msgs_dropped: Counter<u64>,
+
+    /// Traces sampled by at least one policy.
+    #[metric(unit = "{trace}")]
+    pub traces_sampled: Counter<u64>,
+
+    /// Traces not sampled by any policy.
+    #[metric(unit = "{trace}")]
+    pub traces_not_sampled: Counter<u64>,
+
+    /// Traces decided before the end of their decision window because too many traces were
+    /// pending.
+    #[metric(unit = "{trace}")]
+    pub traces_decided_early: Counter<u64>,
+
+    /// Spans of traces that were not sampled.
+    #[metric(unit = "{span}")]
+    pub spans_dropped: Counter<u64>,
+
+    /// Number of failed span batch inspections.
+    #[metric(unit = "{op}")]
+    pub sampling_failed: Counter<u64>,
+}
@@ -0,0 +1,287 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sampling policies of the tail sampling processor.
+//!
+//! A trace is sampled when at least one of the configured policies samples it. Policies are
+//! evaluated on a [`TraceSummary`] accumulated over all the spans of the trace received during
+//! the decision window.
+
+use crate::pdata::attributes::AttributeValue;
+use crate::probabilistic_sampler_processor::is_sampled;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Identifier of a trace.
+pub(super) type TraceId = [u8; 16];
+
+/// Maximum number of policies, bounded by the width of [`TraceSummary::attribute_matches`].
+pub(super) const MAX_POLICIES: usize = 64;
+
+/// Span status code, as named in the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum StatusCode {
+    /// The status has not been set.
+    Unset = 0,
+    /// The operation completed successfully.
+    Ok = 1,
+    /// The operation contains an error.
+    Error = 2,
+}
+
+/// A named sampling policy.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct PolicyConfig {
+    /// Name of the policy, used in error messages.
+    pub name: String,
+    /// The policy itself.
+    #[serde(flatten)]
+    pub policy: Policy,
+}
+
+/// Sampling policy, selected with the `type` field of its configuration.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Policy {
+    /// Sample the traces containing a span with one of the given status codes.
+    StatusCode {
+        /// Status codes selecting the trace.
+        status_codes: Vec<StatusCode>,
+    },
+    /// Sample the traces lasting at least `threshold`, from the earliest span start to the latest
+    /// span end.
+    Latency {
+        /// Minimum duration of a sampled trace.
+        #[serde(with = "humantime_serde")]
+        #[schemars(with = "String")]
+        threshold: Duration,
+    },
+    /// Sample the traces containing a span, or a span of a resource, with a `key` attribute equal
+    /// to one of `values`.
+    Attribute {
+        /// Attribute key.
+        key: String,
+        /// Accepted string, integer, double or boolean values.
+        values: Vec<Value>,
+    },
+    /// Sample a percentage of the traces. The decision only depends on the trace id and the
+    /// seed, and is the same as the one of the probabilistic sampler processor configured alike.
+    Probabilistic {
+        /// Percentage of the traces to sample, between 0 and 100.
+        sampling_percentage: f64,
+        /// Seed of the trace id hash.
+        #[serde(default)]
+        hash_seed: u32,
+    },
+}
+
+/// What is known about a trace from the spans received so far.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct TraceSummary {
+    /// Bit set of the status codes of the spans (bit `n` for code `n`).
+    pub(super) status_codes: u8,
+    /// Earliest span start, in nanoseconds since the epoch.
+    pub(super) start: i64,
+    /// Latest span end, in nanoseconds since the epoch.
+    pub(super) end: i64,
+    /// Bit set of the attribute policies (by index) matched by a span of the trace.
+    pub(super) attribute_matches: u64,
+}
+
+impl Default for TraceSummary {
+    fn default() -> Self {
+        Self {
+            status_codes: 0,
+            start: i64::MAX,
+            end: i64::MIN,
+            attribute_matches: 0,
+        }
+    }
+}
+
+impl TraceSummary {
+    /// Add the properties of a span to the summary.
+    pub(super) fn add_span(
+        &mut self,
+        status_code: Option<i32>,
+        start: Option<i64>,
+        end: Option<i64>,
+        attribute_matches: u64,
+    ) {
+        if let Some(code) = status_code.filter(|code| (0..8).contains(code)) {
+            self.status_codes |= 1 << code;
+        }
+        if let Some(start) = start {
+            self.start = self.start.min(start);
+        }
+        if let Some(end) = end {
+            self.end = self.end.max(end);
+        }
+        self.attribute_matches |= attribute_matches;
+    }
+
+    /// Duration of the trace, if the start and end of its spans are known.
+    fn duration(&self) -> Option<Duration> {
+        (self.start <= self.end).then(|| Duration::from_nanos((self.end - self.start) as u64))
+    }
+}
+
+impl Policy {
+    /// Check the parameters of the policy.
+    pub(super) fn validate(&self) -> Result<(), String> {
+        match self {
+            Policy::StatusCode { status_codes } if status_codes.is_empty() => {
+                Err("status_codes must not be empty".to_string())
+            }
+            Policy::Attribute { values, .. } => {
+                if values.is_empty() {
+                    return Err("values must not be empty".to_string());
+                }
+                match values.iter().find(|value| !is_scalar(value)) {
+                    Some(value) => Err(format!(
+                        "unsupported attribute value `{value}`, expected a string, number or boolean"
+                    )),
+                    None => Ok(()),
+                }
+            }
+            Policy::Probabilistic {
+                sampling_percentage,
+                ..
+            } if !(0.0..=100.0).contains(sampling_percentage) => Err(format!(
+                "sampling_percentage must be between 0 and 100, got {sampling_percentage}"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the policy samples the trace. `index` is the index of the policy in the
+    /// configuration, identifying its bit in [`TraceSummary::attribute_matches`].
+    pub(super) fn samples(&self, index: usize, trace_id: &TraceId, trace: &TraceSummary) -> bool {
+        match self {
+            Policy::StatusCode { status_codes } => status_codes
+                .iter()
+                .any(|code| trace.status_codes & (1 << *code as u8) != 0),
+            Policy::Latency { threshold } => trace
+                .duration()
+                .is_some_and(|duration| duration >= *threshold),
+            Policy::Attribute { .. } => trace.attribute_matches & (1 << index) != 0,
+            Policy::Probabilistic {
+                sampling_percentage,
+                hash_seed,
+            } => is_sampled(*hash_seed, trace_id, *sampling_percentage),
+        }
+    }
+
+    /// Whether an attribute with the given key and value is selected by this policy.
+    pub(super) fn matches_attribute(&self, key: &str, value: &AttributeValue<'_>) -> bool {
+        let Policy::Attribute {
+            key: expected_key,
+            values,
+        } = self
+        else {
+            return false;
+        };
+        key == expected_key && values.iter().any(|expected| value_eq(value, expected))
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    matches!(value, Value::String(_) | Value::Number(_) | Value::Bool(_))
+}
+
+fn value_eq(value: &AttributeValue<'_>, expected: &Value) -> bool {
+    match (value, expected) {
+        (AttributeValue::Str(v), Value::String(e)) => v == e,
+        (AttributeValue::Int(v), Value::Number(e)) => match e.as_i64() {
+            Some(e) => *v == e,
+            None => e.as_f64() == Some(*v as f64),
+        },
+        (AttributeValue::Double(v), Value::Number(e)) => e.as_f64() == Some(*v),
+        (AttributeValue::Bool(v), Value::Bool(e)) => v == e,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy(config: Value) -> Policy {
+        serde_json::from_value::<PolicyConfig>(config)
+            .expect("valid policy")
+            .policy
+    }
+
+    #[test]
+    fn test_status_code_and_latency() {
+        let errors =
+            policy(json!({"name": "errors", "type": "status_code", "status_codes": ["ERROR"]}));
+        let slow = policy(json!({"name": "slow", "type": "latency", "threshold": "500ms"}));
+
+        let mut trace = TraceSummary::default();
+        trace.add_span(Some(1), Some(1_000), Some(2_000), 0);
+        assert!(!errors.samples(0, &[0; 16], &trace));
+        assert!(!slow.samples(1, &[0; 16], &trace));
+
+        trace.add_span(Some(2), Some(100_000_000), Some(600_001_000), 0);
+        assert!(errors.samples(0, &[0; 16], &trace));
+        assert!(slow.samples(1, &[0; 16], &trace));
+    }
+
+    #[test]
+    fn test_attribute_matching() {
+        let vip = policy(json!({
+            "name": "vip", "type": "attribute", "key": "tier", "values": ["gold", 3, true]
+        }));
+        assert!(vip.matches_attribute("tier", &AttributeValue::Str("gold")));
+        assert!(vip.matches_attribute("tier", &AttributeValue::Int(3)));
+        assert!(vip.matches_attribute("tier", &AttributeValue::Double(3.0)));
+        assert!(vip.matches_attribute("tier", &AttributeValue::Bool(true)));
+        assert!(!vip.matches_attribute("tier", &AttributeValue::Str("silver")));
+        assert!(!vip.matches_attribute("plan", &AttributeValue::Str("gold")));
+
+        let mut trace = TraceSummary::default();
+        assert!(!vip.samples(2, &[0; 16], &trace));
+        trace.add_span(None, None, None, 1 << 2);
+        assert!(vip.samples(2, &[0; 16], &trace));
+    }
+
+    #[test]
+    fn test_probabilistic_is_consistent() {
+        let half = policy(json!({"name": "p", "type": "probabilistic", "sampling_percentage": 50}));
+        let all = policy(json!({"name": "p", "type": "probabilistic", "sampling_percentage": 100}));
+        let none = policy(json!({"name": "p", "type": "probabilistic", "sampling_percentage": 0}));
+        let trace = TraceSummary::default();
+
+        let mut sampled = 0;
+        for i in 0..1000u32 {
+            let mut trace_id = [0; 16];
+            trace_id[..4].copy_from_slice(&i.to_be_bytes());
+            let decision = half.samples(0, &trace_id, &trace);
+            assert_eq!(decision, half.samples(0, &trace_id, &trace));
+            assert!(all.samples(0, &trace_id, &trace));
+            assert!(!none.samples(0, &trace_id, &trace));
+            sampled += usize::from(decision);
+        }
+        assert!((400..600).contains(&sampled), "sampled {sampled} of 1000");
+    }
+
+    #[test]
+    fn test_validate() {
+        for config in [
+            json!({"name": "p", "type": "status_code", "status_codes": []}),
+            json!({"name": "p", "type": "attribute", "key": "k", "values": []}),
+            json!({"name": "p", "type": "attribute", "key": "k", "values": [{"a": 1}]}),
+            json!({"name": "p", "type": "probabilistic", "sampling_percentage": 101}),
+        ] {
+            assert!(policy(config).validate().is_err());
+        }
+        assert!(
+            serde_json::from_value::<PolicyConfig>(json!({"name": "p", "type": "always"})).is_err()
+        );
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Extraction of the per-span properties the sampling policies need from OTAP span batches.
+
+use super::policy::{Policy, PolicyConfig, TraceId};
+use crate::pdata::attributes::AttributeIter;
+use arrow::array::{ArrayRef, AsArray, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Int32Type, Int64Type, UInt32Type};
+use arrow::error::ArrowError;
+use otel_arrow_rust::otap::OtapArrowRecords;
+use otel_arrow_rust::proto::opentelemetry::arrow::v1::ArrowPayloadType;
+use otel_arrow_rust::schema::consts;
+use std::collections::HashSet;
+
+/// Properties of a single span row.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct SpanRow {
+    pub(super) trace_id: Option<TraceId>,
+    pub(super) status_code: Option<i32>,
+    pub(super) start: Option<i64>,
+    pub(super) end: Option<i64>,
+    /// Bit set of the attribute policies (by index) matched by the span or its resource.
+    pub(super) attribute_matches: u64,
+}
+
+/// Inspect the rows of the `Spans` payload of `records`, in order.
+///
+/// The ids of `records` must already be decoded (plain encoded).
+pub(super) fn span_rows(
+    records: &OtapArrowRecords,
+    policies: &[PolicyConfig],
+) -> Result<Vec<SpanRow>, ArrowError> {
+    let Some(spans) = records.get(ArrowPayloadType::Spans) else {
+        return Ok(Vec::new());
+    };
+    let mut rows = vec![SpanRow::default(); spans.num_rows()];
+
+    if let Some(trace_ids) = column(
+        spans,
+        consts::TRACE_ID,
+        None,
+        &DataType::FixedSizeBinary(16),
+    )? {
+        let trace_ids = trace_ids.as_fixed_size_binary();
+        for (row, trace_id) in rows.iter_mut().zip(trace_ids.iter()) {
+            row.trace_id = trace_id.and_then(|id| id.try_into().ok());
+        }
+    }
+    if let Some(codes) = column(
+        spans,
+        consts::STATUS,
+        Some(consts::STATUS_CODE),
+        &DataType::Int32,
+    )? {
+        for (row, code) in rows
+            .iter_mut()
+            .zip(codes.as_primitive::<Int32Type>().iter())
+        {
+            row.status_code = code;
+        }
+    }
+    if let Some(starts) = column(spans, consts::START_TIME_UNIX_NANO, None, &DataType::Int64)? {
+        for (row, start) in rows
+            .iter_mut()
+            .zip(starts.as_primitive::<Int64Type>().iter())
+        {
+            row.start = start;
+        }
+    }
+    if let Some(durations) = column(
+        spans,
+        consts::DURATION_TIME_UNIX_NANO,
+        None,
+        &DataType::Int64,
+    )? {
+        for (row, duration) in rows
+            .iter_mut()
+            .zip(durations.as_primitive::<Int64Type>().iter())
+        {
+            row.end = row.start.zip(duration).map(|(start, d)| start + d);
+        }
+    }
+
+    for (index, policy) in policies.iter().enumerate() {
+        let policy = &policy.policy;
+        if !matches!(policy, Policy::Attribute { .. }) {
+            continue;
+        }
+        let matching_parents =
+            |payload_type: ArrowPayloadType| -> Result<HashSet<u32>, ArrowError> {
+                let mut parents = HashSet::new();
+                if let Some(attrs) = records.get(payload_type) {
+                    let attrs = AttributeIter::try_new(attrs)
+                        .map_err(|e| ArrowError::InvalidArgumentError(e.to_string()))?;
+                    for attr in attrs {
+                        if policy.matches_attribute(attr.key, &attr.value) {
+                            let _ = parents.insert(attr.parent_id);
+                        }
+                    }
+                }
+                Ok(parents)
+            };
+        let span_parents = matching_parents(ArrowPayloadType::SpanAttrs)?;
+        let resource_parents = matching_parents(ArrowPayloadType::ResourceAttrs)?;
+        if span_parents.is_empty() && resource_parents.is_empty() {
+            continue;
+        }
+
+        for (parents, name, field) in [
+            (&span_parents, consts::ID, None),
+            (&resource_parents, consts::RESOURCE, Some(consts::ID)),
+        ] {
+            if parents.is_empty() {
+                continue;
+            }
+            let Some(ids) = column(spans, name, field, &DataType::UInt32)? else {
+                continue;
+            };
+            for (row, id) in rows.iter_mut().zip(ids.as_primitive::<UInt32Type>().iter()) {
+                if id.is_some_and(|id| parents.contains(&id)) {
+                    row.attribute_matches |= 1 << index;
+                }
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Column `name` of `batch`, or its `field` if the column is a struct, cast to `data_type`.
+fn column(
+    batch: &RecordBatch,
+    name: &str,
+    field: Option<&str>,
+    data_type: &DataType,
+) -> Result<Option<ArrayRef>, ArrowError> {
+    let Some(column) = batch.column_by_name(name) else {
+        return Ok(None);
+    };
+    let column = match field {
+        Some(field) => match column
+            .as_struct_opt()
+            .and_then(|column| column.column_by_name(field))
+        {
+            Some(column) => column,
+            None => return Ok(None),
+        },
+        None => column,
+    };
+    cast(column, data_type).map(Some)
+}
@@ -0,0 +1,172 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exporter conformance suite.
+//!
+//! Shared checks exercising the engine-facing behavior every exporter is expected to honor:
+//! prompt shutdown, tolerance of control messages, telemetry collection, draining a backlog of
+//! pdata, and Ack/Nack delivery to subscribers. Exporters run the whole suite with
+//! [`run_exporter_conformance`], or the individual checks when a behavior does not apply.
+
+use crate::pdata::OtapPdata;
+use crate::testing::{TestCallData, create_test_pdata};
+use otap_df_engine::testing::exporter::{TestRuntime, create_exporter_from_factory};
+use otap_df_engine::{ExporterFactory, Interests, control::PipelineControlMsg};
+use serde_json::{Value, json};
+use std::time::{Duration, Instant};
+
+/// Node id used as the subscriber of the pdata sent in the Ack/Nack check.
+const SUBSCRIBER_NODE_ID: usize = 654321;
+
+/// Number of pdata messages sent by the backpressure check. This is several times the capacity
+/// of the test pdata channel so that the test task must wait on the exporter to make progress.
+const BACKPRESSURE_MESSAGES: usize = 128;
+
+fn shutdown_deadline() -> Instant {
+    Instant::now() + Duration::from_secs(1)
+}
+
+/// Runs every conformance check against the exporter built by `factory` with `config`.
+///
+/// `ack_nack` is the interest the exporter is expected to deliver for the test pdata when a
+/// subscriber asks for both Acks and Nacks (e.g. `Interests::NACKS` for an exporter whose
+/// destination is unreachable), or `None` if the exporter does not notify subscribers.
+pub fn run_exporter_conformance(
+    factory: &ExporterFactory<OtapPdata>,
+    config: Value,
+    ack_nack: Option<Interests>,
+) {
+    check_shutdown(factory, config.clone());
+    check_control_messages(factory, config.clone());
+    check_collect_telemetry(factory, config.clone());
+    check_backpressure(factory, config.clone());
+    if let Some(expected) = ack_nack {
+        check_ack_nack(factory, config.clone(), expected, false);
+        check_ack_nack(factory, config, expected, true);
+    }
+}
+
+/// The exporter stops cleanly when it receives a shutdown before any data.
+pub fn check_shutdown(factory: &ExporterFactory<OtapPdata>, config: Value) {
+    let exporter = create_exporter_from_factory(factory, config).unwrap();
+    TestRuntime::new()
+        .set_exporter(exporter)
+        .run_test(|ctx| async move {
+            ctx.send_shutdown(shutdown_deadline(), "conformance shutdown")
+                .await
+                .unwrap();
+        })
+        .run_validation(|_ctx, result| async move {
+            result.expect("exporter should shut down cleanly");
+        });
+}
+
+/// The exporter tolerates timer ticks and config updates without failing.
+pub fn check_control_messages(factory: &ExporterFactory<OtapPdata>, config: Value) {
+    let exporter = create_exporter_from_factory(factory, config).unwrap();
+    TestRuntime::new()
+        .set_exporter(exporter)
+        .run_test(|ctx| async move {
+            ctx.send_timer_tick().await.unwrap();
+            ctx.send_config(json!({})).await.unwrap();
+            ctx.send_pdata(create_test_pdata()).await.unwrap();
+            ctx.send_timer_tick().await.unwrap();
+            ctx.send_shutdown(shutdown_deadline(), "conformance shutdown")
+                .await
+                .unwrap();
+        })
+        .run_validation(|_ctx, result| async move {
+            result.expect("exporter should tolerate control messages");
+        });
+}
+
+/// The exporter handles a telemetry collection request.
+pub fn check_collect_telemetry(factory: &ExporterFactory<OtapPdata>, config: Value) {
+    let exporter = create_exporter_from_factory(factory, config).unwrap();
+    let test_runtime = TestRuntime::new();
+    let metrics_reporter = test_runtime.metrics_reporter();
+    test_runtime
+        .set_exporter(exporter)
+        .run_test(move |ctx| async move {
+            ctx.send_pdata(create_test_pdata()).await.unwrap();
+            ctx.send_collect_telemetry(metrics_reporter).await.unwrap();
+            ctx.send_shutdown(shutdown_deadline(), "conformance shutdown")
+                .await
+                .unwrap();
+        })
+        .run_validation(|_ctx, result| async move {
+            result.expect("exporter should handle telemetry collection");
+        });
+}
+
+/// The exporter keeps consuming when more pdata is queued than its input channel can hold, and
+/// still shuts down cleanly afterwards.
+pub fn check_backpressure(factory: &ExporterFactory<OtapPdata>, config: Value) {
+    let exporter = create_exporter_from_factory(factory, config).unwrap();
+    TestRuntime::new()
+        .set_exporter(exporter)
+        .run_test(|ctx| async move {
+            for _ in 0..BACKPRESSURE_MESSAGES {
+                ctx.send_pdata(create_test_pdata()).await.unwrap();
+            }
+            ctx.send_shutdown(shutdown_deadline(), "conformance shutdown")
+                .await
+                .unwrap();
+        })
+        .run_validation(|_ctx, result| async move {
+            result.expect("exporter should drain its input and shut down cleanly");
+        });
+}
+
+/// The exporter delivers exactly the `expected` Ack or Nack to a subscriber interested in both,
+/// preserving the subscriber calldata, and returns the payload only when requested.
+pub fn check_ack_nack(
+    factory: &ExporterFactory<OtapPdata>,
+    config: Value,
+    expected: Interests,
+    return_data: bool,
+) {
+    let exporter = create_exporter_from_factory(factory, config).unwrap();
+    let mut interests = Interests::ACKS_OR_NACKS;
+    if return_data {
+        interests |= Interests::RETURN_DATA;
+    }
+
+    TestRuntime::new()
+        .set_exporter(exporter)
+        .run_test(move |ctx| async move {
+            let pdata = create_test_pdata().test_subscribe_to(
+                interests,
+                TestCallData::default().into(),
+                SUBSCRIBER_NODE_ID,
+            );
+            ctx.send_pdata(pdata).await.unwrap();
+            ctx.send_shutdown(shutdown_deadline(), "conformance shutdown")
+                .await
+                .unwrap();
+        })
+        .run_validation(move |mut ctx, result| async move {
+            result.expect("exporter should shut down cleanly");
+
+            let mut pipeline_rx = ctx.take_pipeline_ctrl_receiver().unwrap();
+            let (trigger, node_id, calldata, payload) = loop {
+                match pipeline_rx.recv().await {
+                    Ok(PipelineControlMsg::DeliverAck { node_id, ack }) => {
+                        break (Interests::ACKS, node_id, ack.calldata, ack.accepted);
+                    }
+                    Ok(PipelineControlMsg::DeliverNack { node_id, nack }) => {
+                        break (Interests::NACKS, node_id, nack.calldata, nack.refused);
+                    }
+                    // timers registered by the exporter are not part of the contract
+                    Ok(_) => {}
+                    Err(err) => panic!("expected {expected:?} to be delivered, got: {err}"),
+                }
+            };
+
+            assert_eq!(trigger, expected);
+            assert_eq!(node_id, SUBSCRIBER_NODE_ID);
+            let calldata: TestCallData = calldata.try_into().unwrap();
+            assert_eq!(calldata, TestCallData::default());
+            assert_eq!(payload.num_items(), if return_data { 1 } else { 0 });
+        });
+}
@@ -3,6 +3,8 @@
 
 //! Ultra-minimal test utilities for OTAP components
 
+pub mod conformance;
+
 use crate::pdata::{OtapPdata, OtlpProtoBytes};
 use otap_df_engine::testing::exporter::{TestRuntime, create_exporter_from_factory};
 use otap_df_engine::{
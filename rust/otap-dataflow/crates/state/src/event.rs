@@ -50,6 +50,11 @@ impl ObservedEventRingBuffer {
     pub(crate) fn is_empty(&self) -> bool {
         self.buf.is_empty()
     }
+
+    /// Iterates over the events, most recent first.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &ObservedEvent> {
+        self.buf.iter().rev()
+    }
 }
 
 /// An observed event emitted by the engine.
@@ -171,6 +176,18 @@ pub enum ErrorSummary {
 }
 
 impl ObservedEvent {
+    /// Returns `true` for error/failure events.
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        matches!(self.r#type, EventType::Error(_))
+    }
+
+    /// Returns the time at which the event was observed.
+    #[must_use]
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+
     /// Create an `Admitted` engine-level event.
     #[must_use]
     pub fn admitted(key: DeployedPipelineKey, message: Option<String>) -> Self {
@@ -0,0 +1,237 @@
+// Copyright The OpenTelemetry Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Health statuses reported by the nodes of the running pipelines.
+//!
+//! Nodes report their own health through a [`NodeHealthReporter`] (e.g. an exporter is unhealthy
+//! until it is authenticated against its backend, a queue is degraded above a fill threshold).
+//! The [`HealthRegistry`] aggregates these statuses, which are combined with the observed pipeline
+//! phases to answer the process-level liveness and readiness probes (see [`HealthReport`]).
+
+use crate::DeployedPipelineKey;
+use crate::store::ts_to_rfc3339;
+use otap_df_config::NodeId;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+/// Health of a single node, as reported by the node itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum NodeHealth {
+    /// The node is fully functional.
+    Healthy,
+    /// The node still processes data, but in a reduced capacity (e.g. a queue above its
+    /// threshold). Degraded nodes do not make the process unready.
+    Degraded(String),
+    /// The node can't process data (e.g. authentication failed). The process is reported as not
+    /// ready until the node recovers.
+    Unhealthy(String),
+}
+
+/// The last health reported by a node.
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeHealthStatus {
+    /// `group_id:pipeline_id` of the pipeline the node belongs to.
+    pub pipeline: String,
+    /// The core the pipeline instance runs on.
+    pub core_id: usize,
+    /// The node identifier.
+    pub node_id: String,
+    /// The last reported health.
+    pub health: NodeHealth,
+    /// When the node entered its current health.
+    #[serde(serialize_with = "ts_to_rfc3339")]
+    pub since: SystemTime,
+}
+
+type NodeKey = (String, usize, String);
+
+/// Registry of the health statuses reported by the nodes of all the pipelines.
+///
+/// This registry is cloneable and thread-safe, all the clones share the same statuses.
+#[derive(Debug, Clone, Default)]
+pub struct HealthRegistry {
+    nodes: Arc<Mutex<BTreeMap<NodeKey, NodeHealthStatus>>>,
+}
+
+impl HealthRegistry {
+    /// Registers a node as healthy and returns the reporter it uses to update its health.
+    ///
+    /// The node is unregistered when the reporter is dropped.
+    #[must_use]
+    pub fn reporter(&self, key: &DeployedPipelineKey, node_id: NodeId) -> NodeHealthReporter {
+        let node_key = (
+            format!("{}:{}", key.pipeline_group_id, key.pipeline_id),
+            key.core_id,
+            node_id.to_string(),
+        );
+        let reporter = NodeHealthReporter {
+            registry: self.clone(),
+            key: node_key,
+        };
+        reporter.report(NodeHealth::Healthy);
+        reporter
+    }
+
+    /// Returns the statuses of all the registered nodes.
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<NodeHealthStatus> {
+        self.lock().values().cloned().collect()
+    }
+
+    /// Returns `true` if no registered node is unhealthy.
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        !self
+            .lock()
+            .values()
+            .any(|status| matches!(status.health, NodeHealth::Unhealthy(_)))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<NodeKey, NodeHealthStatus>> {
+        self.nodes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Handle used by a node to report its health.
+#[derive(Debug)]
+pub struct NodeHealthReporter {
+    registry: HealthRegistry,
+    key: NodeKey,
+}
+
+impl NodeHealthReporter {
+    /// Reports the current health of the node. Reporting an unchanged health is cheap and keeps
+    /// the time the node entered this health.
+    pub fn report(&self, health: NodeHealth) {
+        let mut nodes = self.registry.lock();
+        match nodes.get_mut(&self.key) {
+            Some(status) if status.health == health => {}
+            Some(status) => {
+                status.health = health;
+                status.since = SystemTime::now();
+            }
+            None => {
+                let (pipeline, core_id, node_id) = self.key.clone();
+                let _ = nodes.insert(
+                    self.key.clone(),
+                    NodeHealthStatus {
+                        pipeline,
+                        core_id,
+                        node_id,
+                        health,
+                        since: SystemTime::now(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl Drop for NodeHealthReporter {
+    fn drop(&mut self) {
+        let _ = self.registry.lock().remove(&self.key);
+    }
+}
+
+/// Liveness and readiness of a deployed pipeline.
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineHealth {
+    /// `group_id:pipeline_id` of the pipeline.
+    pub pipeline: String,
+    /// Whether the pipeline satisfies its liveness policy.
+    pub live: bool,
+    /// Whether the pipeline satisfies its readiness policy.
+    pub ready: bool,
+}
+
+/// Process-level health, aggregated from the pipeline phases and the node health statuses.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// All the deployed pipelines are live.
+    pub live: bool,
+    /// At least one pipeline is deployed, all of them are ready and no node is unhealthy.
+    pub ready: bool,
+    /// Per-pipeline liveness and readiness.
+    pub pipelines: Vec<PipelineHealth>,
+    /// The nodes not reporting themselves as healthy.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<NodeHealthStatus>,
+}
+
+impl HealthReport {
+    pub(crate) fn new(mut pipelines: Vec<PipelineHealth>, registry: &HealthRegistry) -> Self {
+        pipelines.sort_by(|a, b| a.pipeline.cmp(&b.pipeline));
+        let nodes: Vec<NodeHealthStatus> = registry
+            .snapshot()
+            .into_iter()
+            .filter(|status| status.health != NodeHealth::Healthy)
+            .collect();
+        let live = pipelines.iter().all(|p| p.live);
+        let ready = !pipelines.is_empty()
+            && pipelines.iter().all(|p| p.ready)
+            && !nodes
+                .iter()
+                .any(|status| matches!(status.health, NodeHealth::Unhealthy(_)));
+        Self {
+            live,
+            ready,
+            pipelines,
+            nodes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(core_id: usize) -> DeployedPipelineKey {
+        DeployedPipelineKey {
+            pipeline_group_id: "group".into(),
+            pipeline_id: "pipeline".into(),
+            core_id,
+        }
+    }
+
+    fn pipeline(ready: bool) -> PipelineHealth {
+        PipelineHealth {
+            pipeline: "group:pipeline".into(),
+            live: true,
+            ready,
+        }
+    }
+
+    #[test]
+    fn test_node_health_aggregation() {
+        let registry = HealthRegistry::default();
+        assert!(!HealthReport::new(vec![], &registry).ready);
+
+        let exporter = registry.reporter(&key(0), "exporter".into());
+        let queue = registry.reporter(&key(1), "queue".into());
+        assert!(registry.is_ready());
+        assert!(HealthReport::new(vec![pipeline(true)], &registry).ready);
+        assert!(!HealthReport::new(vec![pipeline(false)], &registry).ready);
+
+        queue.report(NodeHealth::Degraded("queue above 80%".into()));
+        let report = HealthReport::new(vec![pipeline(true)], &registry);
+        assert!(report.ready);
+        assert_eq!(report.nodes.len(), 1);
+
+        exporter.report(NodeHealth::Unhealthy("authentication failed".into()));
+        let report = HealthReport::new(vec![pipeline(true)], &registry);
+        assert!(report.live);
+        assert!(!report.ready);
+        assert_eq!(report.nodes.len(), 2);
+        assert_eq!(report.nodes[0].node_id, "exporter");
+
+        // Unregistered when the node goes away.
+        drop(exporter);
+        assert!(registry.is_ready());
+        assert_eq!(registry.snapshot().len(), 1);
+    }
+}
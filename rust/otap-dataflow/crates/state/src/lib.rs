@@ -8,6 +8,7 @@ use serde::{Serialize, Serializer};
 
 pub mod error;
 pub mod event;
+pub mod health;
 pub mod phase;
 mod pipeline_rt_status;
 pub mod pipeline_status;
@@ -60,6 +60,11 @@ impl Default for PipelineRuntimeStatus {
 }
 
 impl PipelineRuntimeStatus {
+    /// Returns the most recent events observed for this core, most recent first.
+    pub fn recent_events(&self) -> impl Iterator<Item = &ObservedEvent> {
+        self.recent_events.iter()
+    }
+
     pub(crate) fn apply_event(&mut self, event: ObservedEvent) -> Result<ApplyOutcome, Error> {
         let outcome = self.apply(event.r#type.clone());
         self.last_beat = event.time;
@@ -6,6 +6,7 @@
 use crate::PipelineKey;
 use crate::error::Error;
 use crate::event::{EventType, ObservedEvent, ObservedEventRingBuffer};
+use crate::health::{HealthRegistry, HealthReport, PipelineHealth};
 use crate::phase::PipelinePhase;
 use crate::pipeline_rt_status::{ApplyOutcome, PipelineRuntimeStatus};
 use crate::pipeline_status::PipelineStatus;
@@ -36,12 +37,18 @@ pub struct ObservedStateStore {
     receiver: flume::Receiver<ObservedEvent>,
 
     pipelines: Arc<Mutex<HashMap<PipelineKey, PipelineStatus>>>,
+
+    #[serde(skip)]
+    health: HealthRegistry,
 }
 
 /// A handle to the observed state, suitable for serialization and external consumption.
 #[derive(Debug, Clone, Serialize)]
 pub struct ObservedStateHandle {
     pipelines: Arc<Mutex<HashMap<PipelineKey, PipelineStatus>>>,
+
+    #[serde(skip)]
+    health: HealthRegistry,
 }
 
 impl ObservedStateHandle {
@@ -80,6 +87,7 @@ impl ObservedStateStore {
             sender,
             receiver,
             pipelines: Arc::new(Mutex::new(HashMap::new())),
+            health: HealthRegistry::default(),
         }
     }
 
@@ -97,9 +105,16 @@ impl ObservedStateStore {
     pub fn handle(&self) -> ObservedStateHandle {
         ObservedStateHandle {
             pipelines: self.pipelines.clone(),
+            health: self.health.clone(),
         }
     }
 
+    /// Returns the registry collecting the health statuses reported by the nodes.
+    #[must_use]
+    pub fn health_registry(&self) -> HealthRegistry {
+        self.health.clone()
+    }
+
     /// Reports a new observed event in the store.
     #[allow(
         clippy::print_stderr,
@@ -190,4 +205,20 @@ impl ObservedStateHandle {
             .ok()
             .is_some_and(|pipelines| pipelines.get(pipeline_key).is_some_and(|ps| ps.readiness()))
     }
+
+    /// Aggregates the liveness and readiness of all the pipelines with the health statuses
+    /// reported by their nodes.
+    #[must_use]
+    pub fn health_report(&self) -> HealthReport {
+        let pipelines = self
+            .snapshot()
+            .iter()
+            .map(|(key, status)| PipelineHealth {
+                pipeline: key.as_string(),
+                live: status.liveness(),
+                ready: status.readiness(),
+            })
+            .collect();
+        HealthReport::new(pipelines, &self.health)
+    }
 }
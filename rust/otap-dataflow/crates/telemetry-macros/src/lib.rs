@@ -75,6 +75,9 @@ pub fn derive_metric_set_handler(input: TokenStream) -> TokenStream {
     let mut metric_field_names = Vec::new();
     let mut metric_field_briefs = Vec::new();
     let mut metric_field_instruments: Vec<proc_macro2::TokenStream> = Vec::new();
+    // Snapshot and flush-check statements, histograms spanning several snapshot values
+    let mut metric_field_snapshots: Vec<proc_macro2::TokenStream> = Vec::new();
+    let mut metric_field_flush_checks: Vec<proc_macro2::TokenStream> = Vec::new();
 
     for field in fields {
         let ident = field
@@ -136,7 +139,7 @@ pub fn derive_metric_set_handler(input: TokenStream) -> TokenStream {
                             _ => false,
                         };
                         if !is_u64 {
-                            return syn::Error::new(seg.ident.span(), "Metric field type must be one of Counter<u64>, UpDownCounter<u64>, Gauge<u64>")
+                            return syn::Error::new(seg.ident.span(), "Metric field type must be one of Counter<u64>, UpDownCounter<u64>, Gauge<u64>, Histogram<u64>")
                                 .to_compile_error().into();
                         }
                         match ident_ty.as_str() {
@@ -145,6 +148,9 @@ pub fn derive_metric_set_handler(input: TokenStream) -> TokenStream {
                                 quote!(otap_df_telemetry::descriptor::Instrument::UpDownCounter)
                             }
                             "Gauge" => quote!(otap_df_telemetry::descriptor::Instrument::Gauge),
+                            "Histogram" => {
+                                quote!(otap_df_telemetry::descriptor::Instrument::Histogram)
+                            }
                             other => {
                                 return syn::Error::new(
                                     seg.ident.span(),
@@ -166,6 +172,13 @@ pub fn derive_metric_set_handler(input: TokenStream) -> TokenStream {
                         .into();
                 }
             };
+            if field_is_histogram(&field.ty) {
+                metric_field_snapshots.push(quote!(self.#ident.snapshot_into(&mut out);));
+                metric_field_flush_checks.push(quote!(self.#ident.count() != 0));
+            } else {
+                metric_field_snapshots.push(quote!(out.push(self.#ident.get());));
+                metric_field_flush_checks.push(quote!(self.#ident.get() != 0));
+            }
             metric_field_idents.push(ident);
             metric_field_units.push(unit);
             metric_field_names.push(final_name);
@@ -194,14 +207,14 @@ pub fn derive_metric_set_handler(input: TokenStream) -> TokenStream {
             }
             fn snapshot_values(&self) -> ::std::vec::Vec<u64> {
                 let mut out = ::std::vec::Vec::with_capacity(self.descriptor().metrics.len());
-                #( out.push(self.#metric_field_idents.get()); )*
+                #( #metric_field_snapshots )*
                 out
             }
             fn clear_values(&mut self) {
                 #( self.#metric_field_idents.reset(); )*
             }
             fn needs_flush(&self) -> bool {
-                #( if self.#metric_field_idents.get() != 0 { return true; } )*
+                #( if #metric_field_flush_checks { return true; } )*
                 false
             }
         }
@@ -210,6 +223,17 @@ pub fn derive_metric_set_handler(input: TokenStream) -> TokenStream {
     generated.into()
 }
 
+/// Returns true if the metric field is a `Histogram<u64>`.
+fn field_is_histogram(ty: &syn::Type) -> bool {
+    let syn::Type::Path(tp) = ty else {
+        return false;
+    };
+    tp.path
+        .segments
+        .last()
+        .is_some_and(|seg| seg.ident == "Histogram")
+}
+
 /// Derive implementation of `otap_df_telemetry::attributes::AttributeSetHandler` for a struct.
 ///
 /// Container attribute:
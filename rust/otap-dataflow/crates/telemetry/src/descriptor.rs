@@ -19,6 +19,17 @@ pub enum Instrument {
     Histogram,
 }
 
+impl Instrument {
+    /// Number of values a metric recorded with this instrument occupies in metric snapshots.
+    #[must_use]
+    pub const fn values_len(self) -> usize {
+        match self {
+            Self::Counter | Self::UpDownCounter | Self::Gauge => 1,
+            Self::Histogram => crate::instrument::HISTOGRAM_VALUES_LEN,
+        }
+    }
+}
+
 /// Metadata describing a single field inside a metrics struct.
 #[derive(Debug, Clone, Copy, Serialize)]
 pub struct MetricsField {
@@ -6,7 +6,6 @@
 //! These instruments are designed to be used in thread-per-core scenarios.
 //!
 //! ToDo Finish the implementation of UpDownCounter and Gauge (clean_values and needs_flush need some massage).
-//! ToDo Add histogram support
 
 use std::fmt::Debug;
 use std::ops::{AddAssign, SubAssign};
@@ -26,6 +25,27 @@ pub struct UpDownCounter<T>(T);
 #[derive(Default, Clone, Copy)]
 pub struct Gauge<T>(T);
 
+/// Upper bounds (inclusive) of the buckets of a [`Histogram`], in the unit of the recorded values.
+/// A last bucket counts the values above the largest bound.
+///
+/// The bounds follow a 1-2-5 progression, e.g. from 1µs to 10s for latencies in microseconds.
+pub const HISTOGRAM_BOUNDS: [u64; 22] = [
+    1, 2, 5, 10, 20, 50, 100, 200, 500, 1_000, 2_000, 5_000, 10_000, 20_000, 50_000, 100_000,
+    200_000, 500_000, 1_000_000, 2_000_000, 5_000_000, 10_000_000,
+];
+
+/// Number of values a [`Histogram`] is flattened into in metric snapshots: its count, its sum and
+/// the count of each of its buckets.
+pub const HISTOGRAM_VALUES_LEN: usize = HISTOGRAM_BOUNDS.len() + 3;
+
+/// A distribution of values over the fixed [`HISTOGRAM_BOUNDS`] buckets (e.g., latencies).
+#[derive(Default, Clone, Copy)]
+pub struct Histogram<T> {
+    count: T,
+    sum: T,
+    buckets: [T; HISTOGRAM_BOUNDS.len() + 1],
+}
+
 // Counter implementation.
 // =======================
 
@@ -267,6 +287,93 @@ impl From<u64> for Gauge<u64> {
     }
 }
 
+// Histogram implementation.
+// =========================
+
+impl Histogram<u64> {
+    /// Records a value.
+    #[inline]
+    pub fn record(&mut self, v: u64) {
+        let bucket = HISTOGRAM_BOUNDS.partition_point(|&bound| bound < v);
+        self.count = self.count.wrapping_add(1);
+        self.sum = self.sum.saturating_add(v);
+        self.buckets[bucket] = self.buckets[bucket].wrapping_add(1);
+    }
+
+    /// Reset the histogram to 0.
+    #[inline]
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Returns the number of recorded values.
+    #[inline]
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the sum of the recorded values.
+    #[inline]
+    pub const fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Returns the number of values recorded in each bucket, see [`HISTOGRAM_BOUNDS`].
+    #[inline]
+    pub fn bucket_counts(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// Appends the [`HISTOGRAM_VALUES_LEN`] values of the histogram to a metric snapshot.
+    #[inline]
+    pub fn snapshot_into(&self, out: &mut Vec<u64>) {
+        out.push(self.count);
+        out.push(self.sum);
+        out.extend_from_slice(&self.buckets);
+    }
+}
+
+impl Debug for Histogram<u64> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Histogram")
+            .field("count", &self.count)
+            .field("sum", &self.sum)
+            .field("buckets", &self.buckets)
+            .finish()
+    }
+}
+
+/// The values of a [`Histogram`] in a metric snapshot, see [`HISTOGRAM_VALUES_LEN`].
+#[derive(Debug, Clone, Copy)]
+pub struct HistogramValues<'a>(&'a [u64]);
+
+impl<'a> HistogramValues<'a> {
+    /// Wraps the flattened values of a histogram, or returns `None` if their number is wrong.
+    #[must_use]
+    pub fn new(values: &'a [u64]) -> Option<Self> {
+        (values.len() == HISTOGRAM_VALUES_LEN).then_some(Self(values))
+    }
+
+    /// Returns the number of recorded values.
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.0[0]
+    }
+
+    /// Returns the sum of the recorded values.
+    #[must_use]
+    pub fn sum(&self) -> u64 {
+        self.0[1]
+    }
+
+    /// Returns the upper bound and the number of values of each bucket, the upper bound of the
+    /// last bucket being `None` (+Inf).
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<u64>, u64)> + 'a {
+        let bounds = HISTOGRAM_BOUNDS.iter().copied().map(Some).chain([None]);
+        bounds.zip(self.0[2..].iter().copied())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -420,4 +527,30 @@ mod tests {
         counter.inc();
         assert_eq!(counter.get(), 1);
     }
+
+    #[test]
+    fn test_histogram_record() {
+        let mut histogram = Histogram::<u64>::default();
+        histogram.record(0);
+        histogram.record(1);
+        histogram.record(3);
+        histogram.record(20_000_000);
+        assert_eq!(histogram.count(), 4);
+        assert_eq!(histogram.sum(), 20_000_004);
+        assert_eq!(histogram.bucket_counts()[0], 2);
+        assert_eq!(histogram.bucket_counts()[2], 1);
+        assert_eq!(histogram.bucket_counts()[HISTOGRAM_BOUNDS.len()], 1);
+
+        let mut values = Vec::new();
+        histogram.snapshot_into(&mut values);
+        let values = HistogramValues::new(&values).unwrap();
+        assert_eq!(values.count(), 4);
+        assert_eq!(values.sum(), 20_000_004);
+        let buckets: Vec<_> = values.buckets().filter(|(_, count)| *count > 0).collect();
+        assert_eq!(buckets, vec![(Some(1), 2), (Some(5), 1), (None, 1)]);
+
+        histogram.reset();
+        assert_eq!(histogram.count(), 0);
+        assert!(histogram.bucket_counts().iter().all(|&c| c == 0));
+    }
 }
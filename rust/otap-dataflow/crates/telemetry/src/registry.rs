@@ -8,7 +8,8 @@
 
 use crate::attributes::AttributeSetHandler;
 use crate::descriptor::MetricsDescriptor;
-use crate::descriptor::MetricsField;
+use crate::descriptor::{Instrument, MetricsField};
+use crate::instrument::HistogramValues;
 use crate::metrics::{MetricSet, MetricSetHandler};
 use crate::semconv::SemConvRegistry;
 use parking_lot::Mutex;
@@ -66,36 +67,60 @@ impl MetricsEntry {
 }
 
 /// Lightweight iterator over metrics (no heap allocs).
+///
+/// Histograms are yielded with their count, see [`MetricsIterator::with_histograms`] for their
+/// buckets.
 pub struct MetricsIterator<'a> {
     fields: &'static [MetricsField],
     values: &'a [u64],
     idx: usize,
     len: usize,
+    /// Offset in `values` of the metric at `idx`, histograms spanning several values.
+    offset: usize,
+}
+
+/// Value of a metric yielded by [`MetricsIterator::with_histograms`].
+#[derive(Debug, Clone, Copy)]
+pub enum MetricValue<'a> {
+    /// Value of a counter, up-down-counter or gauge.
+    Value(u64),
+    /// Values of a histogram.
+    Histogram(HistogramValues<'a>),
+}
+
+impl MetricValue<'_> {
+    /// Returns the value of a counter, up-down-counter or gauge, or the count of a histogram.
+    #[must_use]
+    pub fn scalar(&self) -> u64 {
+        match self {
+            Self::Value(v) => *v,
+            Self::Histogram(h) => h.count(),
+        }
+    }
 }
 
 impl<'a> MetricsIterator<'a> {
     #[inline]
     fn new(fields: &'static [MetricsField], values: &'a [u64]) -> Self {
-        let len = values.len();
         debug_assert_eq!(
-            fields.len(),
-            len,
+            fields
+                .iter()
+                .map(|f| f.instrument.values_len())
+                .sum::<usize>(),
+            values.len(),
             "descriptor.fields and metric values length must match"
         );
         Self {
             fields,
             values,
             idx: 0,
-            len,
+            len: fields.len(),
+            offset: 0,
         }
     }
-}
-
-impl<'a> Iterator for MetricsIterator<'a> {
-    type Item = (&'static MetricsField, u64);
 
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Returns the next metric with its full value, including the buckets of histograms.
+    pub fn next_value(&mut self) -> Option<(&'static MetricsField, MetricValue<'a>)> {
         // Single bound check: emit every metric (including zeros).
         if self.idx >= self.len {
             return None;
@@ -103,32 +128,58 @@ impl<'a> Iterator for MetricsIterator<'a> {
         let i = self.idx;
         self.idx = i + 1;
 
-        // SAFETY: `i < self.len` and `self.len == self.fields.len() == self.values.len()` by construction.
-        let v = {
+        // SAFETY: `i < self.len` and `self.len == self.fields.len()` by construction.
+        let field = {
             #[cfg(feature = "unchecked-index")]
             #[allow(unsafe_code)]
             unsafe {
-                *self.values.get_unchecked(i)
+                self.fields.get_unchecked(i)
             }
             #[cfg(not(feature = "unchecked-index"))]
             {
-                self.values[i]
+                &self.fields[i]
             }
         };
+        let offset = self.offset;
+        self.offset = offset + field.instrument.values_len();
 
-        let field = {
+        if field.instrument == Instrument::Histogram {
+            let values = HistogramValues::new(self.values.get(offset..self.offset)?)?;
+            return Some((field, MetricValue::Histogram(values)));
+        }
+
+        // SAFETY: the values of the fields before `i` end at `offset`, and the values length is
+        // the sum of the value lengths of the fields by construction.
+        let v = {
             #[cfg(feature = "unchecked-index")]
             #[allow(unsafe_code)]
             unsafe {
-                self.fields.get_unchecked(i)
+                *self.values.get_unchecked(offset)
             }
             #[cfg(not(feature = "unchecked-index"))]
             {
-                &self.fields[i]
+                self.values[offset]
             }
         };
 
-        Some((field, v))
+        Some((field, MetricValue::Value(v)))
+    }
+
+    /// Converts into an iterator yielding the full value of each metric.
+    pub fn with_histograms(
+        mut self,
+    ) -> impl Iterator<Item = (&'static MetricsField, MetricValue<'a>)> + 'a {
+        std::iter::from_fn(move || self.next_value())
+    }
+}
+
+impl<'a> Iterator for MetricsIterator<'a> {
+    type Item = (&'static MetricsField, u64);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_value()
+            .map(|(field, value)| (field, value.scalar()))
     }
 
     #[inline]
@@ -637,6 +688,44 @@ mod tests {
         assert!(iter.next().is_none());
     }
 
+    #[test]
+    fn test_metrics_iterator_histogram() {
+        let fields = &[
+            MetricsField {
+                name: "duration",
+                unit: "us",
+                brief: "Test histogram",
+                instrument: Instrument::Histogram,
+            },
+            MetricsField {
+                name: "metric",
+                unit: "1",
+                brief: "Test metric",
+                instrument: Instrument::Counter,
+            },
+        ];
+
+        let mut histogram = crate::instrument::Histogram::<u64>::default();
+        histogram.record(3);
+        histogram.record(7);
+        let mut values = Vec::new();
+        histogram.snapshot_into(&mut values);
+        values.push(42);
+
+        let scalars: Vec<_> = MetricsIterator::new(fields, &values)
+            .map(|(field, value)| (field.name, value))
+            .collect();
+        assert_eq!(scalars, vec![("duration", 2), ("metric", 42)]);
+
+        let mut iter = MetricsIterator::new(fields, &values).with_histograms();
+        let Some((_, MetricValue::Histogram(h))) = iter.next() else {
+            panic!("expected a histogram");
+        };
+        assert_eq!((h.count(), h.sum()), (2, 10));
+        assert!(matches!(iter.next(), Some((_, MetricValue::Value(42)))));
+        assert!(iter.next().is_none());
+    }
+
     #[test]
     fn test_metrics_iterator_size_hint() {
         let fields = &[MetricsField {
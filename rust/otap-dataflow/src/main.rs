@@ -5,12 +5,18 @@
 
 use clap::Parser;
 use mimalloc_rust::*;
+use otap_df_config::node::NodeKind;
 use otap_df_config::pipeline::PipelineConfig;
 use otap_df_config::pipeline_group::{CoreAllocation, Quota};
+use otap_df_config::schema::find_schema;
 use otap_df_config::{PipelineGroupId, PipelineId};
 use otap_df_controller::Controller;
+use otap_df_engine::NODE_CONFIG_SCHEMAS;
+use otap_df_engine::feature_gate;
+use otap_df_engine::topology::materialize_config;
 use otap_df_otap::OTAP_PIPELINE_FACTORY;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[global_allocator]
 static GLOBAL_MIMALLOC: GlobalMiMalloc = GlobalMiMalloc;
@@ -24,21 +30,66 @@ static GLOBAL_MIMALLOC: GlobalMiMalloc = GlobalMiMalloc;
     after_help = system_info()
 )]
 struct Args {
-    /// Path to the pipeline configuration file (.json, .yaml, or .yml)
+    /// Path to the pipeline configuration file (.json, .yaml, or .yml). The file can include the
+    /// files it is layered on in a top-level `include` list, e.g. a base shared by several
+    /// deployments.
     #[arg(short, long)]
     pipeline: PathBuf,
 
+    /// Additional pipeline to run alongside the main one, as `ID=PATH` (e.g.
+    /// "team-a=team-a.yaml"). Pipelines exchange data through the pipeline bridge exporter and
+    /// receiver. Can be repeated.
+    #[arg(long, value_name = "ID=PATH", value_parser = parse_additional_pipeline)]
+    additional_pipeline: Vec<(PipelineId, PathBuf)>,
+
+    /// Cores of an additional pipeline, as `ID=CORES` with CORES a comma-separated list of core
+    /// IDs and ranges (e.g. "workers=1-7"). One instance of the pipeline is pinned to each core,
+    /// instead of sharing the cores of the main pipeline. Can be repeated.
+    #[arg(long, value_name = "ID=CORES", value_parser = parse_pipeline_cores)]
+    pipeline_cores: Vec<(PipelineId, CoreAllocation)>,
+
     /// Number of cores to use (0 for default)
-    #[arg(long, default_value = "0", conflicts_with = "core_id_range")]
+    #[arg(long, default_value = "0", conflicts_with_all = ["core_id_range", "core_set"])]
     num_cores: usize,
 
     /// Inclusive range of CPU core IDs to pin threads to (e.g. "0-3", "0..3", "0..=3").
-    #[arg(long, value_name = "START..END", value_parser = parse_core_id_range, conflicts_with = "num_cores")]
+    #[arg(long, value_name = "START..END", value_parser = parse_core_id_range, conflicts_with_all = ["num_cores", "core_set"])]
     core_id_range: Option<CoreAllocation>,
 
+    /// Comma-separated list of CPU core IDs and ranges to pin threads to (e.g. "0,2,4-7").
+    #[arg(long, value_name = "CORES", value_parser = parse_core_set, conflicts_with_all = ["num_cores", "core_id_range"])]
+    core_set: Option<CoreAllocation>,
+
     /// Address to bind the HTTP admin server to (e.g., "127.0.0.1:8080", "0.0.0.0:8080")
     #[arg(long, default_value = "127.0.0.1:8080")]
     http_admin_bind: String,
+
+    /// Expose the `/debug` pages on the HTTP admin server (live topology, per-node counters and
+    /// recent errors)
+    #[arg(long)]
+    debug_pages: bool,
+
+    /// Address to bind the gRPC control-plane server to (e.g., "127.0.0.1:8081"). The gRPC server
+    /// is disabled when not set.
+    #[arg(long)]
+    grpc_admin_bind: Option<String>,
+
+    /// Validate the pipeline configuration and exit without starting the pipeline
+    #[arg(long)]
+    validate: bool,
+
+    /// Print the effective pipeline configuration (defaults filled in, environment variables
+    /// expanded, secrets masked) as YAML and exit without starting the pipeline
+    #[arg(long)]
+    print_default_config: bool,
+
+    /// Watch the pipeline configuration file and apply its changes without restarting
+    #[arg(long)]
+    watch_config: bool,
+
+    /// Interval in seconds between two checks of the watched pipeline configuration file
+    #[arg(long, default_value = "5", requires = "watch_config")]
+    watch_interval_secs: u64,
 }
 
 fn parse_core_id_range(s: &str) -> Result<CoreAllocation, String> {
@@ -63,6 +114,52 @@ fn parse_core_id_range(s: &str) -> Result<CoreAllocation, String> {
     Ok(CoreAllocation::CoreRange { start, end })
 }
 
+fn parse_core_set(s: &str) -> Result<CoreAllocation, String> {
+    let mut cores = Vec::new();
+    for part in s.split(',') {
+        let part = part.trim();
+        if part.contains('-') || part.contains("..") {
+            match parse_core_id_range(part)? {
+                CoreAllocation::CoreRange { start, end } if start <= end => {
+                    cores.extend(start..=end)
+                }
+                _ => return Err(format!("invalid core id range `{part}`")),
+            }
+        } else {
+            let core = part
+                .parse::<usize>()
+                .map_err(|_| format!("invalid core id `{part}` (expected unsigned integer)"))?;
+            cores.push(core);
+        }
+    }
+    Ok(CoreAllocation::CoreSet { cores })
+}
+
+fn parse_pipeline_cores(s: &str) -> Result<(PipelineId, CoreAllocation), String> {
+    let (id, cores) = s
+        .split_once('=')
+        .ok_or_else(|| "expected ID=CORES".to_string())?;
+    let id = id.trim();
+    if id.is_empty() {
+        return Err("missing pipeline id".to_string());
+    }
+    Ok((id.to_owned().into(), parse_core_set(cores)?))
+}
+
+fn parse_additional_pipeline(s: &str) -> Result<(PipelineId, PathBuf), String> {
+    let (id, path) = s
+        .split_once('=')
+        .ok_or_else(|| "expected ID=PATH".to_string())?;
+    let id = id.trim();
+    if id.is_empty() {
+        return Err("missing pipeline id".to_string());
+    }
+    if path.is_empty() {
+        return Err("missing pipeline configuration path".to_string());
+    }
+    Ok((id.to_owned().into(), PathBuf::from(path)))
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
@@ -78,12 +175,74 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         &args.pipeline,
     )?;
 
+    let mut additional_pipelines: Vec<(PipelineId, PipelineConfig)> =
+        Vec::with_capacity(args.additional_pipeline.len());
+    for (additional_id, path) in &args.additional_pipeline {
+        if *additional_id == pipeline_id
+            || additional_pipelines
+                .iter()
+                .any(|(id, _)| id == additional_id)
+        {
+            return Err(format!("duplicate pipeline id `{additional_id}`").into());
+        }
+        let cfg =
+            PipelineConfig::from_file(pipeline_group_id.clone(), additional_id.clone(), path)?;
+        additional_pipelines.push((additional_id.clone(), cfg));
+    }
+
+    if args.print_default_config {
+        print!(
+            "{}",
+            serde_yaml::to_string(&materialize_config(&pipeline_cfg))?
+        );
+        for (additional_id, cfg) in &additional_pipelines {
+            println!("# pipeline `{additional_id}`");
+            println!("---");
+            print!("{}", serde_yaml::to_string(&materialize_config(cfg))?);
+        }
+        std::process::exit(0);
+    }
+
+    if args.validate {
+        std::process::exit(validate(&pipeline_cfg, &additional_pipelines));
+    }
+
     // Create controller and start pipeline with multi-core support
-    let controller = Controller::new(&OTAP_PIPELINE_FACTORY);
+    let mut controller = Controller::new(&OTAP_PIPELINE_FACTORY);
+    if args.watch_config {
+        controller = controller.with_config_watch(
+            args.pipeline.clone(),
+            Duration::from_secs(args.watch_interval_secs.max(1)),
+        );
+    }
+    for (cores_id, _) in &args.pipeline_cores {
+        if !additional_pipelines.iter().any(|(id, _)| id == cores_id) {
+            return Err(format!("cores given for unknown additional pipeline `{cores_id}`").into());
+        }
+    }
+    for (additional_id, cfg) in additional_pipelines {
+        let cores = args
+            .pipeline_cores
+            .iter()
+            .rev()
+            .find(|(id, _)| *id == additional_id);
+        controller = match cores {
+            Some((_, core_allocation)) => controller.with_sharded_pipeline(
+                additional_id,
+                cfg,
+                Quota {
+                    core_allocation: core_allocation.clone(),
+                },
+            ),
+            None => controller.with_additional_pipeline(additional_id, cfg),
+        };
+    }
 
     // Map CLI arguments to the new enum structure
     let core_allocation = if let Some(range) = args.core_id_range {
         range
+    } else if let Some(set) = args.core_set {
+        set
     } else if args.num_cores == 0 {
         CoreAllocation::AllCores
     } else {
@@ -101,10 +260,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         CoreAllocation::CoreRange { start, end } => {
             println!("Starting pipeline on core ID range [{start}-{end}]");
         }
+        CoreAllocation::CoreSet { cores } => {
+            println!("Starting pipeline on core IDs {cores:?}");
+        }
     }
 
     let admin_settings = otap_df_config::engine::HttpAdminSettings {
         bind_address: args.http_admin_bind,
+        debug_pages: args.debug_pages,
+        grpc_bind_address: args.grpc_admin_bind,
     };
     let result = controller.run_forever(
         pipeline_group_id,
@@ -125,6 +289,82 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
+/// Validates the main pipeline configuration and the additional ones, printing the problems
+/// found. Returns the exit status of `--validate`: 0 if the configurations are valid, 1 otherwise.
+fn validate(
+    pipeline_cfg: &PipelineConfig,
+    additional_pipelines: &[(PipelineId, PipelineConfig)],
+) -> i32 {
+    // The feature gates are applied first, as they decide which plugins can be used.
+    let mut problems: Vec<String> =
+        feature_gate::apply(&pipeline_cfg.pipeline_settings().feature_gates)
+            .err()
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+    problems.extend(validation_problems(pipeline_cfg));
+    for (additional_id, cfg) in additional_pipelines {
+        problems.extend(
+            validation_problems(cfg)
+                .into_iter()
+                .map(|problem| format!("pipeline `{additional_id}`: {problem}")),
+        );
+    }
+    if problems.is_empty() {
+        println!("Pipeline configuration is valid");
+        return 0;
+    }
+    for problem in problems {
+        eprintln!("{problem}");
+    }
+    1
+}
+
+/// Returns a description of every node whose plugin URN is not registered in this binary or
+/// whose configuration does not match the schema of its plugin.
+fn validation_problems(pipeline_cfg: &PipelineConfig) -> Vec<String> {
+    let mut problems: Vec<String> = pipeline_cfg
+        .node_iter()
+        .filter(|(_, node)| {
+            let urn = node.plugin_urn.as_ref();
+            match node.kind {
+                NodeKind::Receiver => !OTAP_PIPELINE_FACTORY
+                    .get_receiver_factory_map()
+                    .contains_key(urn),
+                NodeKind::Processor => !OTAP_PIPELINE_FACTORY
+                    .get_processor_factory_map()
+                    .contains_key(urn),
+                NodeKind::Exporter => !OTAP_PIPELINE_FACTORY
+                    .get_exporter_factory_map()
+                    .contains_key(urn),
+                NodeKind::ProcessorChain => false,
+            }
+        })
+        .map(|(node_id, node)| {
+            format!(
+                "node `{node_id}`: unknown {:?} plugin `{}`",
+                node.kind, node.plugin_urn
+            )
+        })
+        .collect();
+    problems.extend(pipeline_cfg.node_iter().filter_map(|(node_id, node)| {
+        let gate = feature_gate::disabled_gate_for_plugin(&node.plugin_urn)?;
+        Some(format!(
+            "node `{node_id}`: plugin `{}` requires the feature gate `{}`",
+            node.plugin_urn, gate.id
+        ))
+    }));
+    problems.extend(pipeline_cfg.node_iter().filter_map(|(node_id, node)| {
+        let schema = find_schema(&NODE_CONFIG_SCHEMAS, &node.plugin_urn)?;
+        (schema.validate)(&node.config)
+            .err()
+            .map(|e| format!("node `{node_id}`: {e}"))
+    }));
+    problems.sort();
+    problems
+}
+
 fn system_info() -> String {
     // Your custom logic here - this could read files, check system state, etc.
     let available_cores = std::thread::available_parallelism()
@@ -197,3 +437,61 @@ Configuration files can be found in the configs/ directory.{}",
         debug_warning
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipeline(receiver_config: &str, exporter_urn: &str) -> PipelineConfig {
+        let yaml = format!(
+            r#"
+nodes:
+  receiver:
+    kind: receiver
+    plugin_urn: "urn:otel:otap:replay:receiver"
+    out_ports:
+      out_port:
+        destinations: [exporter]
+        dispatch_strategy: round_robin
+    config: {receiver_config}
+  exporter:
+    kind: exporter
+    plugin_urn: "{exporter_urn}"
+    config:
+"#
+        );
+        PipelineConfig::from_yaml("group".into(), "pipeline".into(), &yaml).unwrap()
+    }
+
+    #[test]
+    fn test_validate_valid_config() {
+        let cfg = pipeline("{ path: /tmp/capture }", "urn:otel:noop:exporter");
+        assert!(validation_problems(&cfg).is_empty());
+        assert_eq!(validate(&cfg, &[]), 0);
+    }
+
+    #[test]
+    fn test_validate_unknown_urn() {
+        let cfg = pipeline("{ path: /tmp/capture }", "urn:otel:unknown:exporter");
+        let problems = validation_problems(&cfg);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("unknown Exporter plugin `urn:otel:unknown:exporter`"));
+        assert_eq!(validate(&cfg, &[]), 1);
+    }
+
+    #[test]
+    fn test_validate_schema_violation() {
+        let cfg = pipeline(
+            "{ path: /tmp/capture, speed: fast }",
+            "urn:otel:noop:exporter",
+        );
+        let problems = validation_problems(&cfg);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("node `receiver`:"));
+        assert_eq!(validate(&cfg, &[]), 1);
+
+        // the problems of the additional pipelines are reported with their pipeline id
+        let valid = pipeline("{ path: /tmp/capture }", "urn:otel:noop:exporter");
+        assert_eq!(validate(&valid, &[("other".into(), cfg)]), 1);
+    }
+}
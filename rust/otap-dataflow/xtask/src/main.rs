@@ -118,6 +118,14 @@ fn compile_proto() -> anyhow::Result<()> {
             ],
         )
         .expect("Failed to compile protos.");
+    // Admin control plane
+    tonic_prost_build::configure()
+        .out_dir("crates/admin/src/proto")
+        .compile_protos(
+            &["otap_df/admin/v1/control_service.proto"],
+            &[format!("{base}/../crates/admin/proto").as_str()],
+        )
+        .expect("Failed to compile protos.");
     Ok(())
 }
 
@@ -137,6 +137,33 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Too many IDs in column {} to merge the batches", name))]
+    IdOverflow {
+        name: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Cannot merge batches of different signals"))]
+    MixedSignals {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Slice of {} rows at offset {} is out of bounds of a batch of {} rows",
+        length,
+        offset,
+        num_rows
+    ))]
+    SliceOutOfBounds {
+        offset: usize,
+        length: usize,
+        num_rows: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Unsupported payload type, got: {}", actual))]
     UnsupportedPayloadType {
         actual: i32,
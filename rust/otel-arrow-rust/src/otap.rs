@@ -4,14 +4,23 @@
 //! This module contains various types and methods for interacting with and manipulating
 //! OTAP data / record batches
 
-use arrow::array::RecordBatch;
+use std::num::{NonZeroU64, NonZeroUsize};
 
+use arrow::array::{
+    Array, ArrayRef, ArrowPrimitiveType, AsArray, BooleanArray, PrimitiveArray, RecordBatch,
+    StructArray,
+};
+use arrow::datatypes::{DataType, UInt16Type, UInt32Type};
+use snafu::{ResultExt, ensure};
+
+use groups::RecordsGroup;
 use transform::transport_optimize::{
     RESOURCE_ID_COL_PATH, SCOPE_ID_COL_PATH, apply_transport_optimized_encodings, remap_parent_ids,
     remove_transport_optimized_encodings,
 };
 
 use crate::{
+    arrays::get_required_array,
     decode::record_message::RecordMessage,
     error::{self, Result},
     proto::opentelemetry::arrow::v1::ArrowPayloadType,
@@ -101,6 +110,16 @@ impl OtapArrowRecords {
         }
     }
 
+    /// The payload type of the primary table, the parent of the other tables.
+    #[must_use]
+    const fn primary_payload_type(&self) -> ArrowPayloadType {
+        match self {
+            Self::Logs(_) => ArrowPayloadType::Logs,
+            Self::Metrics(_) => ArrowPayloadType::UnivariateMetrics,
+            Self::Traces(_) => ArrowPayloadType::Spans,
+        }
+    }
+
     #[must_use]
     const fn tag(&self) -> OtapArrowRecordTag {
         match self {
@@ -127,6 +146,298 @@ impl OtapArrowRecords {
             Self::Traces(_) => Traces::encode_transport_optimized(self),
         }
     }
+
+    /// Split into batches of at most `max_rows` items, as counted by [`Self::batch_length`], and
+    /// of about `max_bytes` of Arrow data, e.g. to respect the request size limit of a backend.
+    ///
+    /// The child tables are split along with the primary table, so that every output batch is
+    /// self-consistent: it carries the attributes, events, links or data points of its items, and
+    /// the resource and scope attributes they reference. The byte budget is an estimate, the
+    /// memory size of the batch being assumed to be evenly spread over its items. An item is never
+    /// split, and a batch within the limits is returned as is.
+    ///
+    /// The IDs must not be transport optimized, see [`Self::decode_transport_optimized_ids`].
+    pub fn split(
+        mut self,
+        max_rows: Option<NonZeroU64>,
+        max_bytes: Option<NonZeroUsize>,
+    ) -> Result<Vec<Self>> {
+        let batch_length = self.batch_length() as u64;
+        let mut limit = max_rows.map_or(u64::MAX, NonZeroU64::get);
+        if let Some(max_bytes) = max_bytes {
+            let num_bytes: usize = self
+                .allowed_payload_types()
+                .iter()
+                .filter_map(|payload_type| self.get(*payload_type))
+                .map(RecordBatch::get_array_memory_size)
+                .sum();
+            if num_bytes > max_bytes.get() {
+                let rows = max_bytes.get() as u128 * batch_length as u128 / num_bytes as u128;
+                limit = limit.min((rows as u64).max(1));
+            }
+        }
+
+        let primary_payload = self.primary_payload_type();
+        let primary_rows = self.get(primary_payload).map_or(0, RecordBatch::num_rows);
+        if batch_length <= limit || primary_rows == 0 {
+            return Ok(vec![self]);
+        }
+        let limit = NonZeroU64::new(limit).expect("limit is at least 1");
+
+        // The resource and scope attributes are referenced by the `resource.id` and `scope.id`
+        // columns of the primary table rather than by its `id` column, and may be shared by the
+        // items of several output batches: they are selected for each output batch instead.
+        let resource_attrs = self.take(ArrowPayloadType::ResourceAttrs);
+        let scope_attrs = self.take(ArrowPayloadType::ScopeAttrs);
+
+        let tag = self.tag();
+        let [logs, metrics, traces] = RecordsGroup::split_by_type(vec![self]);
+        let group = match tag {
+            OtapArrowRecordTag::Logs => logs,
+            OtapArrowRecordTag::Metrics => metrics,
+            OtapArrowRecordTag::Traces => traces,
+        };
+        let mut result = group.split(limit)?.into_otap_arrow_records();
+
+        for records in result.iter_mut() {
+            for (payload_type, struct_column, attrs) in [
+                (
+                    ArrowPayloadType::ResourceAttrs,
+                    consts::RESOURCE,
+                    &resource_attrs,
+                ),
+                (ArrowPayloadType::ScopeAttrs, consts::SCOPE, &scope_attrs),
+            ] {
+                let (Some(ids), Some(attrs)) = (
+                    records
+                        .get(primary_payload)
+                        .and_then(|primary| struct_ids(primary, struct_column)),
+                    attrs,
+                ) else {
+                    continue;
+                };
+                if let Some(selected) = select_children(attrs, ids)? {
+                    records.set(payload_type, selected);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Return the rows `offset..offset + length` of the primary table, i.e. of the logs, the spans
+    /// or the metrics, along with the rows of the other tables related to them.
+    ///
+    /// The result is a view over this batch: the tables are sliced rather than copied, sharing the
+    /// Arrow buffers of this batch, as long as the related rows of a child table are contiguous,
+    /// i.e. the child table is sorted by parent ID and the parent IDs in the range are a gap free
+    /// sequence. This is the case of the batches built by the encoder or by [`Self::split`] once
+    /// their IDs are decoded. The related rows of the other child tables are copied. The IDs must
+    /// not be transport optimized, see [`Self::decode_transport_optimized_ids`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `offset + length` is greater than the number of rows of the primary
+    /// table.
+    pub fn slice(&self, offset: usize, length: usize) -> Result<Self> {
+        let primary_payload = self.primary_payload_type();
+        let mut result = match self {
+            Self::Logs(_) => Self::Logs(Logs::default()),
+            Self::Metrics(_) => Self::Metrics(Metrics::default()),
+            Self::Traces(_) => Self::Traces(Traces::default()),
+        };
+        let primary = self.get(primary_payload);
+        let num_rows = primary.map_or(0, RecordBatch::num_rows);
+        ensure!(
+            offset
+                .checked_add(length)
+                .is_some_and(|end| end <= num_rows),
+            error::SliceOutOfBoundsSnafu {
+                offset,
+                length,
+                num_rows
+            }
+        );
+        let Some(primary) = primary else {
+            return Ok(result);
+        };
+
+        let primary = primary.slice(offset, length);
+        self.slice_children(primary_payload, &primary, &mut result)?;
+        for (payload_type, struct_column) in [
+            (ArrowPayloadType::ResourceAttrs, consts::RESOURCE),
+            (ArrowPayloadType::ScopeAttrs, consts::SCOPE),
+        ] {
+            let (Some(ids), Some(attrs)) =
+                (struct_ids(&primary, struct_column), self.get(payload_type))
+            else {
+                continue;
+            };
+            if let Some(selected) = select_children(attrs, ids)? {
+                result.set(payload_type, selected);
+            }
+        }
+        result.set(primary_payload, primary);
+        Ok(result)
+    }
+
+    /// Select the rows of the child tables of `parent`, a slice of the `parent_payload` table,
+    /// and recursively of their own child tables, into `result`.
+    fn slice_children(
+        &self,
+        parent_payload: ArrowPayloadType,
+        parent: &RecordBatch,
+        result: &mut Self,
+    ) -> Result<()> {
+        let Some(ids) = parent.column_by_name(consts::ID) else {
+            return Ok(());
+        };
+        for child_payload in child_payload_types(parent_payload).iter().filter(|child| {
+            !matches!(
+                child,
+                ArrowPayloadType::ResourceAttrs | ArrowPayloadType::ScopeAttrs
+            )
+        }) {
+            let Some(child) = self.get(*child_payload) else {
+                continue;
+            };
+            if let Some(child) = select_children(child, ids)? {
+                self.slice_children(*child_payload, &child, result)?;
+                result.set(*child_payload, child);
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge batches of the same signal into one batch, e.g. to coalesce small batches without a
+    /// round trip through OTLP.
+    ///
+    /// The IDs of each batch are renumbered past the IDs of the batches before it, along with the
+    /// parent IDs referencing them, and the columns are unified across the batches, e.g. the
+    /// dictionary columns with different key types or the optional columns missing from some
+    /// batches. The IDs must not be transport optimized, see
+    /// [`Self::decode_transport_optimized_ids`].
+    pub fn concat(records: &[Self]) -> Result<Self> {
+        let Some(first) = records.first() else {
+            return error::EmptyBatchSnafu.fail();
+        };
+        let tag = first.tag();
+        ensure!(
+            records.iter().all(|records| records.tag() == tag),
+            error::MixedSignalsSnafu
+        );
+
+        let [logs, metrics, traces] = RecordsGroup::split_by_type(records.to_vec());
+        let group = match tag {
+            OtapArrowRecordTag::Logs => logs,
+            OtapArrowRecordTag::Metrics => metrics,
+            OtapArrowRecordTag::Traces => traces,
+        };
+        let merged = group.concatenate(None)?.into_otap_arrow_records().pop();
+
+        // all the batches were empty
+        Ok(merged.unwrap_or_else(|| match tag {
+            OtapArrowRecordTag::Logs => Self::Logs(Logs::default()),
+            OtapArrowRecordTag::Metrics => Self::Metrics(Metrics::default()),
+            OtapArrowRecordTag::Traces => Self::Traces(Traces::default()),
+        }))
+    }
+
+    /// Remove the record batch for the given payload type, if this payload type was included in
+    /// the batch.
+    fn take(&mut self, payload_type: ArrowPayloadType) -> Option<RecordBatch> {
+        if !self.allowed_payload_types().contains(&payload_type) {
+            return None;
+        }
+        let batches = match self {
+            Self::Logs(logs) => logs.batches_mut(),
+            Self::Metrics(metrics) => metrics.batches_mut(),
+            Self::Traces(traces) => traces.batches_mut(),
+        };
+        batches[POSITION_LOOKUP[payload_type as usize]].take()
+    }
+}
+
+/// Return the `id` field of the `resource` or `scope` struct column of a primary table.
+fn struct_ids<'a>(primary: &'a RecordBatch, struct_column: &str) -> Option<&'a ArrayRef> {
+    primary
+        .column_by_name(struct_column)?
+        .as_any()
+        .downcast_ref::<StructArray>()?
+        .column_by_name(consts::ID)
+}
+
+/// Select the rows of the child table `child` whose parent ID is one of the u16 or u32 `ids` of
+/// their parent, or `None` if no row is selected.
+///
+/// The rows are sliced out of `child` rather than copied when they are contiguous, i.e. when
+/// `child` is sorted by parent ID and `ids` are a gap free sequence.
+fn select_children(child: &RecordBatch, ids: &ArrayRef) -> Result<Option<RecordBatch>> {
+    let mut ids: Vec<u32> = match ids.data_type() {
+        DataType::UInt16 => ids
+            .as_primitive::<UInt16Type>()
+            .iter()
+            .flatten()
+            .map(u32::from)
+            .collect(),
+        DataType::UInt32 => ids.as_primitive::<UInt32Type>().iter().flatten().collect(),
+        data_type => {
+            return error::UnsupportedParentIdTypeSnafu {
+                actual: data_type.clone(),
+            }
+            .fail();
+        }
+    };
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.is_empty() {
+        return Ok(None);
+    }
+
+    let parent_ids = get_required_array(child, consts::PARENT_ID)?;
+    match parent_ids.data_type() {
+        DataType::UInt16 => {
+            select_children_generic(child, parent_ids.as_primitive::<UInt16Type>(), &ids)
+        }
+        DataType::UInt32 => {
+            select_children_generic(child, parent_ids.as_primitive::<UInt32Type>(), &ids)
+        }
+        data_type => error::UnsupportedParentIdTypeSnafu {
+            actual: data_type.clone(),
+        }
+        .fail(),
+    }
+}
+
+fn select_children_generic<T>(
+    child: &RecordBatch,
+    parent_ids: &PrimitiveArray<T>,
+    ids: &[u32],
+) -> Result<Option<RecordBatch>>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Into<u32>,
+{
+    let (first_id, last_id) = (ids[0], ids[ids.len() - 1]);
+    let values = parent_ids.values();
+    if (last_id - first_id) as usize + 1 == ids.len()
+        && parent_ids.null_count() == 0
+        && values.is_sorted()
+    {
+        let start = values.partition_point(|parent_id| (*parent_id).into() < first_id);
+        let end = values.partition_point(|parent_id| (*parent_id).into() <= last_id);
+        return Ok((end > start).then(|| child.slice(start, end - start)));
+    }
+
+    let selected = parent_ids
+        .iter()
+        .map(|parent_id| {
+            Some(parent_id.is_some_and(|parent_id| ids.binary_search(&parent_id.into()).is_ok()))
+        })
+        .collect::<BooleanArray>();
+    let selected =
+        arrow::compute::filter_record_batch(child, &selected).context(error::BatchingSnafu)?;
+    Ok((selected.num_rows() > 0).then_some(selected))
 }
 
 /// The ArrowBatchStore helper trait is used to define a common interface for
@@ -805,7 +1116,7 @@ pub const fn child_payload_types(payload_type: ArrowPayloadType) -> &'static [Ar
         ArrowPayloadType::Spans => &[
             ArrowPayloadType::ResourceAttrs,
             ArrowPayloadType::ScopeAttrs,
-            ArrowPayloadType::LogAttrs,
+            ArrowPayloadType::SpanAttrs,
             ArrowPayloadType::SpanEvents,
             ArrowPayloadType::SpanLinks,
         ],
@@ -814,7 +1125,7 @@ pub const fn child_payload_types(payload_type: ArrowPayloadType) -> &'static [Ar
         ArrowPayloadType::UnivariateMetrics | ArrowPayloadType::MultivariateMetrics => &[
             ArrowPayloadType::ResourceAttrs,
             ArrowPayloadType::ScopeAttrs,
-            ArrowPayloadType::LogAttrs,
+            ArrowPayloadType::MetricAttrs,
             ArrowPayloadType::NumberDataPoints,
             ArrowPayloadType::SummaryDataPoints,
             ArrowPayloadType::HistogramDataPoints,
@@ -852,6 +1163,7 @@ mod test {
     use arrow::datatypes::{DataType, Field, Fields, Schema, UInt16Type, UInt32Type};
     use std::sync::Arc;
 
+    use crate::arrays::get_u16_array;
     use crate::otlp::attributes::AttributeValueType;
     use crate::schema::FieldExt;
 
@@ -991,6 +1303,284 @@ mod test {
         assert_eq!(multivariate_types, univariate_types)
     }
 
+    /// Logs with the ids `[null, 0, 1, 2]`, the first two referencing resource 0 and the others
+    /// resource 1, with their log attributes and resource attributes.
+    fn logs_to_split() -> OtapArrowRecords {
+        let resource_fields = Fields::from(vec![Field::new(consts::ID, DataType::UInt16, true)]);
+        let logs_rb = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new(consts::ID, DataType::UInt16, true),
+                Field::new(
+                    consts::RESOURCE,
+                    DataType::Struct(resource_fields.clone()),
+                    true,
+                ),
+            ])),
+            vec![
+                Arc::new(UInt16Array::from(vec![None, Some(0), Some(1), Some(2)])),
+                Arc::new(StructArray::new(
+                    resource_fields,
+                    vec![Arc::new(UInt16Array::from_iter_values(vec![0, 0, 1, 1]))],
+                    None,
+                )),
+            ],
+        )
+        .unwrap();
+
+        let mut otap_batch = OtapArrowRecords::Logs(Logs::default());
+        otap_batch.set(ArrowPayloadType::Logs, logs_rb);
+        otap_batch.set(
+            ArrowPayloadType::LogAttrs,
+            attrs_from_data::<UInt16Type>(
+                vec![(0, "a"), (0, "b"), (1, "c"), (2, "d")],
+                consts::metadata::encodings::PLAIN,
+            ),
+        );
+        otap_batch.set(
+            ArrowPayloadType::ResourceAttrs,
+            attrs_from_data::<UInt16Type>(
+                vec![(0, "r0"), (1, "r1")],
+                consts::metadata::encodings::PLAIN,
+            ),
+        );
+        otap_batch
+    }
+
+    fn parent_ids(otap_batch: &OtapArrowRecords, payload_type: ArrowPayloadType) -> Vec<u16> {
+        otap_batch
+            .get(payload_type)
+            .map(|rb| {
+                get_u16_array(rb, consts::PARENT_ID)
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            })
+            .unwrap_or_default()
+    }
+
+    fn resource_ids(otap_batch: &OtapArrowRecords) -> Vec<u16> {
+        let logs_rb = otap_batch.get(ArrowPayloadType::Logs).unwrap();
+        let resources = logs_rb
+            .column_by_name(consts::RESOURCE)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StructArray>()
+            .unwrap();
+        resources
+            .column_by_name(consts::ID)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt16Array>()
+            .unwrap()
+            .values()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_logs_split_by_rows() {
+        let split = logs_to_split().split(NonZeroU64::new(2), None).unwrap();
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[0].batch_length(), 2);
+        assert_eq!(split[1].batch_length(), 2);
+
+        // every part carries the attributes of its logs and of the resources they reference
+        assert_eq!(
+            parent_ids(&split[0], ArrowPayloadType::LogAttrs),
+            vec![0, 0]
+        );
+        assert_eq!(
+            parent_ids(&split[1], ArrowPayloadType::LogAttrs),
+            vec![1, 2]
+        );
+        assert_eq!(
+            parent_ids(&split[0], ArrowPayloadType::ResourceAttrs),
+            vec![0]
+        );
+        assert_eq!(
+            parent_ids(&split[1], ArrowPayloadType::ResourceAttrs),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_logs_split_by_bytes() {
+        let logs = logs_to_split();
+        let num_bytes: usize = logs
+            .allowed_payload_types()
+            .iter()
+            .filter_map(|payload_type| logs.get(*payload_type))
+            .map(RecordBatch::get_array_memory_size)
+            .sum();
+
+        // within the limits
+        let split = logs
+            .clone()
+            .split(NonZeroU64::new(4), NonZeroUsize::new(num_bytes))
+            .unwrap();
+        assert_eq!(split, vec![logs.clone()]);
+
+        // about a quarter of the bytes per part, and never less than one log
+        let split = logs
+            .clone()
+            .split(None, NonZeroUsize::new(num_bytes / 4))
+            .unwrap();
+        assert_eq!(split.len(), 4);
+        let split = logs.split(None, NonZeroUsize::new(1)).unwrap();
+        assert_eq!(split.len(), 4);
+        assert!(split.iter().all(|part| part.batch_length() == 1));
+        assert_eq!(parent_ids(&split[3], ArrowPayloadType::LogAttrs), vec![2]);
+        assert_eq!(
+            parent_ids(&split[3], ArrowPayloadType::ResourceAttrs),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_logs_slice() {
+        let logs = logs_to_split();
+        let slice = logs.slice(1, 2).unwrap();
+        assert_eq!(slice.batch_length(), 2);
+        assert_eq!(
+            parent_ids(&slice, ArrowPayloadType::LogAttrs),
+            vec![0, 0, 1]
+        );
+        assert_eq!(
+            parent_ids(&slice, ArrowPayloadType::ResourceAttrs),
+            vec![0, 1]
+        );
+
+        // the sorted tables are sliced, sharing the buffers of the batch
+        for payload_type in [ArrowPayloadType::Logs, ArrowPayloadType::LogAttrs] {
+            let buffer_ptr = |records: &OtapArrowRecords| {
+                records
+                    .get(payload_type)
+                    .unwrap()
+                    .column(0)
+                    .to_data()
+                    .buffers()[0]
+                    .data_ptr()
+            };
+            assert_eq!(buffer_ptr(&slice), buffer_ptr(&logs));
+        }
+
+        let empty = logs.slice(4, 0).unwrap();
+        assert_eq!(empty.batch_length(), 0);
+        assert!(empty.get(ArrowPayloadType::LogAttrs).is_none());
+    }
+
+    #[test]
+    fn test_slice_out_of_bounds() {
+        let logs = logs_to_split();
+        assert!(logs.slice(3, 2).is_err());
+        assert!(logs.slice(5, 0).is_err());
+        assert!(logs.slice(usize::MAX, 2).is_err());
+
+        // a batch without primary table only has an empty slice
+        let empty = OtapArrowRecords::Logs(Logs::default());
+        assert!(empty.slice(0, 0).is_ok());
+        assert!(empty.slice(0, 1).is_err());
+    }
+
+    #[test]
+    fn test_logs_slice_unsorted_children() {
+        let mut logs = logs_to_split();
+        logs.set(
+            ArrowPayloadType::LogAttrs,
+            attrs_from_data::<UInt16Type>(
+                vec![(2, "d"), (0, "a"), (1, "c"), (0, "b")],
+                consts::metadata::encodings::PLAIN,
+            ),
+        );
+
+        // the related rows are copied
+        let slice = logs.slice(2, 2).unwrap();
+        assert_eq!(parent_ids(&slice, ArrowPayloadType::LogAttrs), vec![2, 1]);
+        assert_eq!(parent_ids(&slice, ArrowPayloadType::ResourceAttrs), vec![1]);
+    }
+
+    #[test]
+    fn test_logs_concat() {
+        let logs = logs_to_split();
+        let merged = OtapArrowRecords::concat(&[logs.clone(), logs]).unwrap();
+        assert_eq!(merged.batch_length(), 8);
+
+        // the ids of the second batch are renumbered past the ids of the first one, along with the
+        // parent ids referencing them
+        let logs_rb = merged.get(ArrowPayloadType::Logs).unwrap();
+        let ids = get_u16_array(logs_rb, consts::ID).unwrap();
+        assert_eq!(
+            ids.iter().collect::<Vec<_>>(),
+            vec![
+                None,
+                Some(0),
+                Some(1),
+                Some(2),
+                None,
+                Some(3),
+                Some(4),
+                Some(5)
+            ]
+        );
+        assert_eq!(
+            parent_ids(&merged, ArrowPayloadType::LogAttrs),
+            vec![0, 0, 1, 2, 3, 3, 4, 5]
+        );
+
+        // the resource ids are renumbered separately, along with the resource attributes
+        assert_eq!(resource_ids(&merged), vec![0, 0, 1, 1, 2, 2, 3, 3]);
+        assert_eq!(
+            parent_ids(&merged, ArrowPayloadType::ResourceAttrs),
+            vec![0, 1, 2, 3]
+        );
+
+        // splitting the merged batch, sorted by id, gives back self-consistent parts
+        let split = merged.split(NonZeroU64::new(4), None).unwrap();
+        assert_eq!(split.len(), 2);
+        assert_eq!(
+            parent_ids(&split[1], ArrowPayloadType::LogAttrs),
+            vec![2, 3, 3, 4, 5]
+        );
+        assert_eq!(
+            parent_ids(&split[1], ArrowPayloadType::ResourceAttrs),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_logs_concat_many() {
+        // the next ids stay absolute from one batch to the next, so merging many batches doesn't
+        // overflow the u16 ids
+        let merged = OtapArrowRecords::concat(&vec![logs_to_split(); 32]).unwrap();
+        assert_eq!(merged.batch_length(), 128);
+
+        let logs_rb = merged.get(ArrowPayloadType::Logs).unwrap();
+        let ids = get_u16_array(logs_rb, consts::ID).unwrap();
+        assert_eq!(ids.iter().last(), Some(Some(95)));
+        assert_eq!(
+            parent_ids(&merged, ArrowPayloadType::LogAttrs).last(),
+            Some(&95)
+        );
+        assert_eq!(resource_ids(&merged).last(), Some(&63));
+        assert_eq!(
+            parent_ids(&merged, ArrowPayloadType::ResourceAttrs).last(),
+            Some(&63)
+        );
+    }
+
+    #[test]
+    fn test_concat_errors() {
+        assert!(OtapArrowRecords::concat(&[]).is_err());
+        let logs = OtapArrowRecords::Logs(Logs::default());
+        let traces = OtapArrowRecords::Traces(Traces::default());
+        assert!(OtapArrowRecords::concat(&[logs.clone(), traces]).is_err());
+
+        // empty batches merge into an empty batch
+        assert_eq!(
+            OtapArrowRecords::concat(&[logs.clone(), logs.clone()]).unwrap(),
+            logs
+        );
+    }
+
     #[test]
     fn test_log_getset() {
         let mut otap_batch = OtapArrowRecords::Logs(Logs::new());
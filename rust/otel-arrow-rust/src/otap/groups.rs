@@ -25,7 +25,7 @@ use arrow::{
 };
 use itertools::Itertools;
 use smallvec::SmallVec;
-use snafu::{OptionExt, ResultExt};
+use snafu::{OptionExt, ResultExt, ensure};
 
 use crate::{
     otap::{
@@ -207,12 +207,15 @@ fn tag_count(records: &[OtapArrowRecords], tag: OtapArrowRecordTag) -> usize {
 /// Fetch the primary table for a given batch
 #[must_use]
 fn primary_table<const N: usize>(batches: &[Option<RecordBatch>; N]) -> Option<&RecordBatch> {
+    batches[POSITION_LOOKUP[primary_payload_type::<N>() as usize]].as_ref()
+}
+
+/// The payload type of the primary table of a batch
+const fn primary_payload_type<const N: usize>() -> ArrowPayloadType {
     match N {
-        Logs::COUNT => batches[POSITION_LOOKUP[ArrowPayloadType::Logs as usize]].as_ref(),
-        Metrics::COUNT => {
-            batches[POSITION_LOOKUP[ArrowPayloadType::UnivariateMetrics as usize]].as_ref()
-        }
-        Traces::COUNT => batches[POSITION_LOOKUP[ArrowPayloadType::Spans as usize]].as_ref(),
+        Logs::COUNT => ArrowPayloadType::Logs,
+        Metrics::COUNT => ArrowPayloadType::UnivariateMetrics,
+        Traces::COUNT => ArrowPayloadType::Spans,
         _ => {
             unreachable!()
         }
@@ -477,7 +480,7 @@ impl IDSeqs {
 
             let end = start + length;
             let ids = match first_valid_index {
-                Some(first_valid_index) if start >= first_valid_index => {
+                Some(first_valid_index) if end > first_valid_index => {
                     let subslice = &slice[start.max(first_valid_index)..end];
                     Some(subslice[0]..=subslice[subslice.len() - 1])
                 }
@@ -569,7 +572,13 @@ impl IDSeqs {
         }
 
         let mut result = Vec::with_capacity(id_ranges.len());
-        for range in id_ranges.iter().flatten() {
+        for range in id_ranges {
+            let Some(range) = range else {
+                // no ids in this part of the parent table, so no child rows either
+                result.push(None);
+                continue;
+            };
+
             // We're using `partition_point` instead of `.binary_search` because it returns a
             // deterministic result in cases where there are multiple results found.
 
@@ -763,7 +772,7 @@ enum HowToSort {
 }
 
 /// Return a `RecordBatch` lexically sorted by either the `parent_id` column and secondarily by the
-/// `id` column or just by the `id` column.
+/// `id` column or just by the `id` column. A `RecordBatch` without these columns is returned as is.
 fn sort_record_batch(rb: RecordBatch, how: HowToSort) -> Result<RecordBatch> {
     let (schema, columns, _num_rows) = rb.into_parts();
     let id_column_index = schema.column_with_name(consts::ID).map(|pair| pair.0);
@@ -793,14 +802,22 @@ fn sort_record_batch(rb: RecordBatch, how: HowToSort) -> Result<RecordBatch> {
                     },
                 ]
             }
-            (_, None, Some(id)) => {
+            (SortById, _, Some(id)) | (_, None, Some(id)) => {
                 let id_values = columns[id].clone();
                 smallvec::smallvec![SortColumn {
                     values: id_values,
                     options,
                 }]
             }
-            _ => unreachable!(),
+            // attribute tables only have a `parent_id` column
+            (SortByParentIdAndId, Some(parent_id), None) => {
+                let parent_id_values = columns[parent_id].clone();
+                smallvec::smallvec![SortColumn {
+                    values: parent_id_values,
+                    options,
+                }]
+            }
+            _ => return RecordBatch::try_new(schema, columns).context(error::BatchingSnafu),
         };
 
     // safety: [`sort_to_indices`] will only return an error if the passed columns aren't supported
@@ -834,11 +851,15 @@ fn generic_concatenate<const N: usize>(
     let mut current = Vec::new();
     let mut current_batch_length = 0;
     for batches in batches {
-        let emit_new_batch = max_output_batch
-            .map(|max_output_batch| {
-                (current_batch_length + batch_length(&batches)) as u64 >= max_output_batch.get()
-            })
-            .unwrap_or(false);
+        // The batch that would overflow the current output batch starts the next one. An output
+        // batch may hold exactly `max_output_batch` rows, and a single oversized input batch is
+        // emitted on its own.
+        let emit_new_batch = !current.is_empty()
+            && max_output_batch
+                .map(|max_output_batch| {
+                    (current_batch_length + batch_length(&batches)) as u64 > max_output_batch.get()
+                })
+                .unwrap_or(false);
         if emit_new_batch {
             reindex(&mut current, allowed_payloads)?;
             result.push(generic_schemaless_concatenate(&mut current)?);
@@ -847,10 +868,9 @@ fn generic_concatenate<const N: usize>(
                 assert_eq!(batches, &[const { None }; N]);
             }
             current.clear();
-        } else {
-            current_batch_length += batch_length(&batches);
-            current.push(batches);
         }
+        current_batch_length += batch_length(&batches);
+        current.push(batches);
     }
 
     if !current.is_empty() {
@@ -943,19 +963,26 @@ fn reindex<const N: usize>(
 
                     let (parent, next_starting_id) =
                         reindex_record_batch(parent, consts::ID, parent_starting_offset)?;
-                    starting_ids[parent_offset] += next_starting_id;
+                    starting_ids[parent_offset] = next_starting_id;
                     // return parent to batches since we took it!
                     let _ = batches[parent_offset].replace(parent);
 
                     for child in child_payloads {
+                        // The resource and scope attributes reference the resource and scope IDs
+                        // of the primary table, not its IDs. They're handled below.
+                        if matches!(
+                            child,
+                            ArrowPayloadType::ResourceAttrs | ArrowPayloadType::ScopeAttrs
+                        ) {
+                            continue;
+                        }
                         let child_offset = POSITION_LOOKUP[*child as usize];
                         if let Some(child) = batches[child_offset].take() {
-                            let (child, next_starting_id) = reindex_record_batch(
+                            let (child, _) = reindex_record_batch(
                                 child,
                                 consts::PARENT_ID,
                                 parent_starting_offset,
                             )?;
-                            starting_ids[child_offset] += next_starting_id;
                             // return child to batches since we took it!
                             let _ = batches[child_offset].replace(child);
                             // We don't have to reindex child's id column since we'll get to it in a
@@ -967,9 +994,190 @@ fn reindex<const N: usize>(
         }
     }
 
+    let mut next_resource_id = 0;
+    let mut next_scope_id = 0;
+    for batches in batches.iter_mut() {
+        next_resource_id = reindex_struct_ids(
+            batches,
+            consts::RESOURCE,
+            ArrowPayloadType::ResourceAttrs,
+            next_resource_id,
+        )?;
+        next_scope_id = reindex_struct_ids(
+            batches,
+            consts::SCOPE,
+            ArrowPayloadType::ScopeAttrs,
+            next_scope_id,
+        )?;
+    }
+
     Ok(())
 }
 
+/// Shift the IDs of a struct column of the primary table, e.g. `resource.id`, along with the
+/// parent IDs of the attributes referencing them, so that they start at `next_id`. Return the next
+/// free ID.
+///
+/// Unlike [`reindex_record_batch`], the IDs don't need to be sorted, the primary table being sorted
+/// by its own IDs, and the attributes may reference only some of them.
+fn reindex_struct_ids<const N: usize>(
+    batches: &mut [Option<RecordBatch>; N],
+    struct_column: &'static str,
+    attrs_payload: ArrowPayloadType,
+    next_id: u32,
+) -> Result<u32> {
+    let primary_offset = POSITION_LOOKUP[primary_payload_type::<N>() as usize];
+    let attrs_offset = POSITION_LOOKUP[attrs_payload as usize];
+
+    // the smallest and largest IDs, and the largest ID each column can hold
+    let mut bounds: SmallVec<[(i64, i64, i64); 2]> = SmallVec::new();
+    if let Some(ids) = batches[primary_offset]
+        .as_ref()
+        .and_then(|rb| struct_id_column(rb, struct_column))
+    {
+        bounds.extend(id_bounds(ids, consts::ID)?);
+    }
+    if let Some(parent_ids) = batches[attrs_offset]
+        .as_ref()
+        .and_then(|rb| rb.column_by_name(consts::PARENT_ID))
+    {
+        bounds.extend(id_bounds(parent_ids, consts::PARENT_ID)?);
+    }
+    let (Some(min_id), Some(max_id)) = (
+        bounds.iter().map(|(min_id, _, _)| *min_id).min(),
+        bounds.iter().map(|(_, max_id, _)| *max_id).max(),
+    ) else {
+        return Ok(next_id);
+    };
+
+    let offset = i64::from(next_id) - min_id;
+    ensure!(
+        bounds
+            .iter()
+            .all(|(_, max_id, type_max)| max_id + offset <= *type_max),
+        error::IdOverflowSnafu {
+            name: struct_column
+        }
+    );
+    let next_id = u32::try_from(max_id + offset + 1)
+        .ok()
+        .context(error::IdOverflowSnafu {
+            name: struct_column,
+        })?;
+    if offset == 0 {
+        return Ok(next_id);
+    }
+
+    if let Some(ids) = batches[primary_offset]
+        .as_ref()
+        .and_then(|rb| struct_id_column(rb, struct_column))
+    {
+        let ids = shift_ids(ids, consts::ID, offset)?;
+        let rb = batches[primary_offset].take().expect("checked above");
+        batches[primary_offset] = Some(replace_id_column(rb, Some(struct_column), ids)?);
+    }
+    if let Some(parent_ids) = batches[attrs_offset]
+        .as_ref()
+        .and_then(|rb| rb.column_by_name(consts::PARENT_ID))
+    {
+        let parent_ids = shift_ids(parent_ids, consts::PARENT_ID, offset)?;
+        let rb = batches[attrs_offset].take().expect("checked above");
+        batches[attrs_offset] = Some(replace_id_column(rb, None, parent_ids)?);
+    }
+    Ok(next_id)
+}
+
+/// Return the `id` field of the `struct_column` of `rb`, if any.
+fn struct_id_column<'rb>(rb: &'rb RecordBatch, struct_column: &str) -> Option<&'rb ArrayRef> {
+    rb.column_by_name(struct_column)?
+        .as_any()
+        .downcast_ref::<StructArray>()?
+        .column_by_name(consts::ID)
+}
+
+/// Return the smallest and largest non-null IDs of a u16 or u32 column, and the largest ID its type
+/// can hold.
+fn id_bounds(column: &ArrayRef, column_name: &'static str) -> Result<Option<(i64, i64, i64)>> {
+    use arrow::compute::{max, min};
+
+    Ok(match IDColumn::from_array(column_name, column)? {
+        IDColumn::U16(array) => min(array)
+            .zip(max(array))
+            .map(|(min_id, max_id)| (min_id.into(), max_id.into(), u16::MAX.into())),
+        IDColumn::U32(array) => min(array)
+            .zip(max(array))
+            .map(|(min_id, max_id)| (min_id.into(), max_id.into(), u32::MAX.into())),
+    })
+}
+
+/// Add `offset` to the IDs of a u16 or u32 column, which must not overflow.
+fn shift_ids(column: &ArrayRef, column_name: &'static str, offset: i64) -> Result<ArrayRef> {
+    fn shift<T>(
+        array: &PrimitiveArray<T>,
+        column_name: &'static str,
+        offset: i64,
+    ) -> Result<ArrayRef>
+    where
+        T: ArrowPrimitiveType,
+        T::Native: TryFrom<i64>,
+    {
+        let scalar = PrimitiveArray::<T>::new_scalar(
+            T::Native::try_from(offset.abs())
+                .ok()
+                .context(error::IdOverflowSnafu { name: column_name })?,
+        );
+        // the values under the nulls may wrap, the others don't
+        if offset < 0 {
+            arrow::compute::kernels::numeric::sub_wrapping(array, &scalar)
+        } else {
+            arrow::compute::kernels::numeric::add_wrapping(array, &scalar)
+        }
+        .context(error::BatchingSnafu)
+    }
+
+    match IDColumn::from_array(column_name, column)? {
+        IDColumn::U16(array) => shift(array, column_name, offset),
+        IDColumn::U32(array) => shift(array, column_name, offset),
+    }
+}
+
+/// Replace the `id` column of `rb`, or the `id` field of its `struct_column`, with `ids`.
+fn replace_id_column(
+    rb: RecordBatch,
+    struct_column: Option<&str>,
+    ids: ArrayRef,
+) -> Result<RecordBatch> {
+    let (schema, mut columns, _len) = rb.into_parts();
+    match struct_column {
+        None => {
+            let column_index = schema
+                .index_of(consts::PARENT_ID)
+                .context(error::BatchingSnafu)?;
+            columns[column_index] = ids;
+        }
+        Some(struct_column) => {
+            let column_index = schema
+                .index_of(struct_column)
+                .context(error::BatchingSnafu)?;
+            let (fields, mut children, nulls) = columns[column_index]
+                .as_any()
+                .downcast_ref::<StructArray>()
+                .expect("the struct column was extracted before")
+                .clone()
+                .into_parts();
+            let id_index = fields
+                .find(consts::ID)
+                .expect("the id field was extracted before")
+                .0;
+            children[id_index] = ids;
+            columns[column_index] = Arc::new(
+                StructArray::try_new(fields, children, nulls).context(error::BatchingSnafu)?,
+            );
+        }
+    }
+    RecordBatch::try_new(schema, columns).context(error::BatchingSnafu)
+}
+
 fn reindex_record_batch(
     rb: RecordBatch,
     column_name: &'static str,
@@ -3024,6 +3232,28 @@ mod test {
         assert_eq!(merged, original_logs);
     }
 
+    #[test]
+    fn test_concatenate_batch_boundaries() {
+        let output_lengths = |max_output_batch: u64| {
+            let [logs, _, _] =
+                RecordsGroup::split_by_type(vec![make_logs(), make_logs(), make_logs()]);
+            logs.concatenate(NonZeroU64::new(max_output_batch))
+                .unwrap()
+                .into_otap_arrow_records()
+                .iter()
+                .map(OtapArrowRecords::batch_length)
+                .collect::<Vec<_>>()
+        };
+
+        // no input batch is dropped when the limit is reached
+        assert_eq!(output_lengths(4), vec![3, 3, 3]);
+        // the limit is inclusive
+        assert_eq!(output_lengths(6), vec![6, 3]);
+        assert_eq!(output_lengths(9), vec![9]);
+        // an input batch larger than the limit is emitted on its own, without empty batches
+        assert_eq!(output_lengths(2), vec![3, 3, 3]);
+    }
+
     fn make_traces() -> OtapArrowRecords {
         let spans_rb = RecordBatch::try_new(
             Arc::new(Schema::new(vec![